@@ -0,0 +1,330 @@
+//! Azure OpenAI client
+//!
+//! Handles chat, transcription, and speech synthesis through an Azure OpenAI
+//! resource. Requests are routed by deployment name instead of model name,
+//! authenticated with the `api-key` header, and carry an `api-version` query
+//! parameter.
+
+use super::endpoint;
+use super::http;
+use super::openwebui::{ChatCompletionResponse, ChatMessage, GenerationOverrides, LlmReply, ModelInfo, SamplingOptions};
+use super::provider::{LlmProvider, SttProvider, TtsProvider};
+use super::whisper::TranscriptionResponse;
+use crate::config::{AppConfig, AzureOpenAiConfig, OpenWebUiConfig, WhisperConfig};
+use crate::error::{AppResult, AzureError};
+use async_trait::async_trait;
+use reqwest::multipart::{Form, Part};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Azure OpenAI client
+///
+/// Generation settings (temperature, token limits, transcription language) come
+/// from the `openwebui` and `whisper` sections so they apply to every provider.
+pub struct AzureOpenAiClient {
+    client: reqwest::Client,
+    config: AzureOpenAiConfig,
+    llm: OpenWebUiConfig,
+    stt: WhisperConfig,
+    api_key: Option<String>,
+}
+
+/// Chat completion request (the deployment determines the model)
+#[derive(Debug, Serialize)]
+struct AzureChatRequest {
+    messages: Vec<ChatMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<usize>,
+    #[serde(flatten)]
+    sampling: SamplingOptions,
+}
+
+/// Speech synthesis request
+#[derive(Debug, Serialize)]
+struct AzureSpeechRequest<'a> {
+    model: &'a str,
+    input: &'a str,
+    voice: &'a str,
+    response_format: &'static str,
+}
+
+/// Azure OpenAI error response
+#[derive(Debug, Deserialize)]
+struct AzureErrorResponse {
+    error: AzureErrorDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct AzureErrorDetail {
+    message: String,
+}
+
+impl AzureOpenAiClient {
+    /// Create a new Azure OpenAI client
+    pub fn new(config: &AppConfig, api_key: Option<String>) -> AppResult<Self> {
+        let client = http::client(Duration::from_secs(config.azure.timeout_secs))
+            .map_err(|e| AzureError::RequestFailed(e.to_string()))?;
+
+        let mut azure = config.azure.clone();
+        azure.endpoint = endpoint::azure_resource(&azure.endpoint);
+
+        Ok(Self {
+            client,
+            config: azure,
+            llm: config.openwebui.clone(),
+            stt: config.whisper.clone(),
+            api_key,
+        })
+    }
+
+    /// URL of an operation on a deployment, failing if the resource or deployment is not configured
+    fn url(&self, deployment: &str, setting: &'static str, operation: &str) -> AppResult<String> {
+        if self.config.endpoint.is_empty() {
+            return Err(AzureError::NotConfigured("endpoint").into());
+        }
+        if deployment.is_empty() {
+            return Err(AzureError::NotConfigured(setting).into());
+        }
+
+        Ok(deployment_url(&self.config.endpoint, deployment, operation, &self.config.api_version))
+    }
+
+    /// Send a request with the `api-key` header, mapping HTTP errors to `AzureError`
+    async fn send(&self, request: reqwest::RequestBuilder, deployment: &str) -> AppResult<reqwest::Response> {
+        let mut request = request;
+        if let Some(api_key) = &self.api_key {
+            request = request.header("api-key", api_key);
+        }
+
+        let response = http::send("azure", request).await.map_err(|e| {
+            if e.is_timeout() {
+                AzureError::Timeout
+            } else {
+                AzureError::RequestFailed(e.to_string())
+            }
+        })?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let message = response
+            .json::<AzureErrorResponse>()
+            .await
+            .map(|body| body.error.message)
+            .unwrap_or_else(|_| format!("HTTP {}", status));
+
+        Err(match status.as_u16() {
+            401 | 403 => AzureError::AuthenticationFailed,
+            404 => AzureError::DeploymentNotFound(deployment.to_string()),
+            429 => AzureError::RateLimitExceeded,
+            _ => AzureError::RequestFailed(message),
+        }
+        .into())
+    }
+
+    /// Check that the resource is reachable and the API key is accepted
+    async fn check_resource(&self) -> AppResult<bool> {
+        if self.config.endpoint.is_empty() {
+            return Ok(false);
+        }
+
+        let url = format!(
+            "{}/openai/models?api-version={}",
+            self.config.endpoint.trim_end_matches('/'),
+            self.config.api_version
+        );
+
+        let mut request = self.client.get(&url).timeout(Duration::from_secs(5));
+        if let Some(api_key) = &self.api_key {
+            request = request.header("api-key", api_key);
+        }
+
+        match http::send("azure", request).await {
+            Ok(resp) => match resp.status().as_u16() {
+                401 | 403 => Err(AzureError::AuthenticationFailed.into()),
+                status => Ok(status < 500),
+            },
+            Err(e) => {
+                tracing::warn!("Azure OpenAI connectivity check failed: {}", e);
+                Ok(false)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for AzureOpenAiClient {
+    async fn send_message_with(
+        &self,
+        messages: Vec<(String, String)>,
+        overrides: &GenerationOverrides,
+    ) -> AppResult<LlmReply> {
+        // A per-conversation model override names a deployment on Azure
+        let deployment = overrides.model.as_deref().unwrap_or(&self.config.chat_deployment);
+        let url = self.url(deployment, "chat deployment", "chat/completions")?;
+
+        let messages: Vec<ChatMessage> = overrides
+            .system_prompt
+            .iter()
+            .map(|prompt| ChatMessage {
+                role: "system".to_string(),
+                content: prompt.clone(),
+            })
+            .chain(messages.into_iter().map(|(role, content)| ChatMessage { role, content }))
+            .collect();
+
+        let total_chars: usize = messages.iter().map(|m| m.content.len()).sum();
+        if total_chars > self.llm.max_context_length {
+            tracing::warn!("Context length ({}) exceeds maximum ({})", total_chars, self.llm.max_context_length);
+            return Err(AzureError::ContextLimitExceeded.into());
+        }
+
+        tracing::info!("Sending {} messages to Azure OpenAI deployment '{}'", messages.len(), deployment);
+
+        let request_body = AzureChatRequest {
+            messages,
+            temperature: Some(overrides.temperature.unwrap_or(self.llm.temperature)),
+            max_tokens: overrides.max_tokens.or(self.llm.max_tokens),
+            sampling: SamplingOptions::from_config(&self.llm),
+        };
+
+        let response = self
+            .send(self.client.post(&url).json(&request_body), deployment)
+            .await?
+            .json::<ChatCompletionResponse>()
+            .await
+            .map_err(|e| AzureError::InvalidResponse(e.to_string()))?;
+
+        response
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message.into_reply())
+            .ok_or_else(|| AzureError::InvalidResponse("No choices in response".to_string()).into())
+    }
+
+    async fn list_models(&self) -> AppResult<Vec<ModelInfo>> {
+        // Listing deployments needs the management API, so only the configured one is offered
+        if self.config.chat_deployment.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        Ok(vec![ModelInfo {
+            id: self.config.chat_deployment.clone(),
+            ..ModelInfo::default()
+        }])
+    }
+
+    async fn check_connectivity(&self) -> AppResult<bool> {
+        self.check_resource().await
+    }
+}
+
+#[async_trait]
+impl SttProvider for AzureOpenAiClient {
+    async fn transcribe_audio(&self, audio_data: Vec<u8>, filename: &str) -> AppResult<String> {
+        let deployment = &self.config.transcription_deployment;
+        let url = self.url(deployment, "transcription deployment", "audio/transcriptions")?;
+
+        tracing::info!("Transcribing audio with Azure OpenAI: {} ({} bytes)", filename, audio_data.len());
+
+        let audio_part = Part::bytes(audio_data)
+            .file_name(filename.to_string())
+            .mime_str("audio/wav")
+            .map_err(|e| AzureError::RequestFailed(e.to_string()))?;
+
+        let mut form = Form::new().part("file", audio_part).text("response_format", "json");
+        if let Some(language) = &self.stt.language {
+            form = form.text("language", language.clone());
+        }
+        if self.stt.temperature > 0.0 {
+            form = form.text("temperature", self.stt.temperature.to_string());
+        }
+
+        let result = self
+            .send(self.client.post(&url).multipart(form), deployment)
+            .await?
+            .json::<TranscriptionResponse>()
+            .await
+            .map_err(|e| AzureError::InvalidResponse(e.to_string()))?;
+
+        if result.text.trim().is_empty() {
+            return Err(AzureError::InvalidResponse("Empty transcription".to_string()).into());
+        }
+        Ok(result.text)
+    }
+
+    async fn check_connectivity(&self) -> AppResult<bool> {
+        self.check_resource().await
+    }
+}
+
+#[async_trait]
+impl TtsProvider for AzureOpenAiClient {
+    async fn synthesize_speech(&self, text: &str) -> AppResult<Vec<u8>> {
+        let deployment = &self.config.speech_deployment;
+        let url = self.url(deployment, "speech deployment", "audio/speech")?;
+
+        tracing::info!("Synthesizing speech with Azure OpenAI: {} chars", text.len());
+
+        let request_body = AzureSpeechRequest {
+            model: deployment,
+            input: text,
+            voice: &self.config.speech_voice,
+            response_format: "mp3",
+        };
+
+        let audio = self
+            .send(self.client.post(&url).json(&request_body), deployment)
+            .await?
+            .bytes()
+            .await
+            .map_err(|e| AzureError::InvalidResponse(e.to_string()))?;
+
+        Ok(audio.to_vec())
+    }
+
+    async fn check_connectivity(&self) -> AppResult<bool> {
+        self.check_resource().await
+    }
+}
+
+/// `{endpoint}/openai/deployments/{deployment}/{operation}?api-version={version}`
+fn deployment_url(endpoint: &str, deployment: &str, operation: &str, api_version: &str) -> String {
+    format!(
+        "{}/openai/deployments/{}/{}?api-version={}",
+        endpoint.trim_end_matches('/'),
+        deployment,
+        operation,
+        api_version
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deployment_url() {
+        assert_eq!(
+            deployment_url("https://acme.openai.azure.com/", "gpt-4o", "chat/completions", "2024-10-21"),
+            "https://acme.openai.azure.com/openai/deployments/gpt-4o/chat/completions?api-version=2024-10-21"
+        );
+    }
+
+    #[test]
+    fn test_missing_deployment_is_reported() {
+        let mut config = AppConfig::default();
+        config.azure.endpoint = "https://acme.openai.azure.com".to_string();
+        let client = AzureOpenAiClient::new(&config, None).unwrap();
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let result = runtime.block_on(client.synthesize_speech("Hello"));
+
+        assert!(result.unwrap_err().to_string().contains("speech deployment"));
+    }
+}
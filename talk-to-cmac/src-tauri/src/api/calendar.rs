@@ -0,0 +1,261 @@
+//! Calendar client
+//!
+//! Reads events from an iCalendar feed, a CalDAV collection (with a
+//! `calendar-query` REPORT limited to the requested time range), or the
+//! Microsoft Graph calendar view. ICS data is parsed by [`crate::calendar`].
+
+use super::http;
+use crate::calendar::{self, CalendarEvent};
+use crate::config::{CalendarConfig, CalendarSource};
+use crate::error::{AppResult, CalendarError};
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use regex::Regex;
+use serde::Deserialize;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Calendar view used when no Graph URL is configured
+const GRAPH_CALENDAR_VIEW: &str = "https://graph.microsoft.com/v1.0/me/calendarView";
+
+/// Events read from Graph in one request
+const GRAPH_PAGE_SIZE: u32 = 100;
+
+/// Graph calendar view response
+#[derive(Debug, Deserialize)]
+struct GraphEvents {
+    value: Vec<GraphEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphEvent {
+    #[serde(default)]
+    subject: String,
+    start: GraphTime,
+    end: GraphTime,
+    #[serde(default)]
+    is_all_day: bool,
+    location: Option<GraphLocation>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphTime {
+    date_time: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GraphLocation {
+    #[serde(default)]
+    display_name: String,
+}
+
+/// Calendar client
+pub struct CalendarClient {
+    client: reqwest::Client,
+    config: CalendarConfig,
+    secret: Option<String>,
+}
+
+impl CalendarClient {
+    /// Create a client for the configured calendar, authenticating with `secret` if given
+    pub fn new(config: CalendarConfig, secret: Option<String>) -> AppResult<Self> {
+        let client = http::client(Duration::from_secs(config.timeout_secs))
+            .map_err(|e| CalendarError::RequestFailed(e.to_string()))?;
+
+        Ok(Self { client, config, secret })
+    }
+
+    /// Events overlapping `from`..`to`, earliest first
+    pub async fn events(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> AppResult<Vec<CalendarEvent>> {
+        let url = match (self.config.source, self.config.url.trim()) {
+            (CalendarSource::Graph, "") => GRAPH_CALENDAR_VIEW,
+            (_, "") => return Err(CalendarError::NotConfigured("calendar URL").into()),
+            (_, url) => url,
+        };
+
+        let events = match self.config.source {
+            CalendarSource::Ics => calendar::parse_ics(&self.fetch(self.client.get(url)).await?),
+            CalendarSource::CalDav => {
+                let report = reqwest::Method::from_bytes(b"REPORT").expect("REPORT is a valid method");
+                let request = self
+                    .client
+                    .request(report, url)
+                    .header("Depth", "1")
+                    .header("Content-Type", "application/xml; charset=utf-8")
+                    .body(calendar_query(from, to));
+                caldav_calendar_data(&self.fetch(request).await?)
+                    .iter()
+                    .flat_map(|ics| calendar::parse_ics(ics))
+                    .collect()
+            }
+            CalendarSource::Graph => {
+                let request = self
+                    .client
+                    .get(url)
+                    .query(&[
+                        ("startDateTime", from.to_rfc3339()),
+                        ("endDateTime", to.to_rfc3339()),
+                        ("$top", GRAPH_PAGE_SIZE.to_string()),
+                    ])
+                    .header("Prefer", "outlook.timezone=\"UTC\"");
+                let body = self.fetch(request).await?;
+                let events: GraphEvents =
+                    serde_json::from_str(&body).map_err(|e| CalendarError::InvalidResponse(e.to_string()))?;
+                events.value.into_iter().filter_map(graph_event).collect()
+            }
+        };
+
+        Ok(calendar::between(events, from.timestamp(), to.timestamp()))
+    }
+
+    /// Send `request` with the configured credentials, returning the response body
+    async fn fetch(&self, request: reqwest::RequestBuilder) -> AppResult<String> {
+        let request = match (&self.secret, self.config.username.is_empty()) {
+            (None, _) => request,
+            (Some(secret), true) => request.bearer_auth(secret),
+            (Some(secret), false) => request.basic_auth(&self.config.username, Some(secret)),
+        };
+
+        let response = http::send("calendar", request)
+            .await
+            .map_err(|e| CalendarError::RequestFailed(e.to_string()))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(match status.as_u16() {
+                401 | 403 => CalendarError::AuthenticationFailed,
+                _ => CalendarError::RequestFailed(format!("HTTP {}", status)),
+            }
+            .into());
+        }
+
+        response
+            .text()
+            .await
+            .map_err(|e| CalendarError::InvalidResponse(e.to_string()).into())
+    }
+}
+
+/// CalDAV `calendar-query` for events overlapping `from`..`to`
+fn calendar_query(from: DateTime<Utc>, to: DateTime<Utc>) -> String {
+    const FORMAT: &str = "%Y%m%dT%H%M%SZ";
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8" ?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop><c:calendar-data/></d:prop>
+  <c:filter>
+    <c:comp-filter name="VCALENDAR">
+      <c:comp-filter name="VEVENT">
+        <c:time-range start="{}" end="{}"/>
+      </c:comp-filter>
+    </c:comp-filter>
+  </c:filter>
+</c:calendar-query>"#,
+        from.format(FORMAT),
+        to.format(FORMAT)
+    )
+}
+
+/// ICS text of each `calendar-data` element in a CalDAV multistatus response
+fn caldav_calendar_data(xml: &str) -> Vec<String> {
+    static CALENDAR_DATA: OnceLock<Regex> = OnceLock::new();
+    let pattern = CALENDAR_DATA.get_or_init(|| {
+        Regex::new(r"(?s)<(?:[\w-]+:)?calendar-data[^>]*>(.*?)</(?:[\w-]+:)?calendar-data>")
+            .expect("calendar-data pattern is valid")
+    });
+
+    pattern
+        .captures_iter(xml)
+        .map(|caps| {
+            let data = caps[1].trim();
+            match data.strip_prefix("<![CDATA[").and_then(|data| data.strip_suffix("]]>")) {
+                Some(data) => data.to_string(),
+                None => data
+                    .replace("&lt;", "<")
+                    .replace("&gt;", ">")
+                    .replace("&quot;", "\"")
+                    .replace("&apos;", "'")
+                    .replace("&#13;", "\r")
+                    .replace("&amp;", "&"),
+            }
+        })
+        .collect()
+}
+
+/// Event from a Graph calendar view read with UTC times
+fn graph_event(event: GraphEvent) -> Option<CalendarEvent> {
+    let parse = |time: &GraphTime| {
+        NaiveDateTime::parse_from_str(&time.date_time, "%Y-%m-%dT%H:%M:%S%.f")
+            .ok()
+            .map(|time| Utc.from_utc_datetime(&time))
+    };
+    let (start, end) = (parse(&event.start)?, parse(&event.end)?);
+
+    // All-day events run from midnight to midnight of their days, wherever the user is
+    let (start, end) = if event.is_all_day {
+        (calendar::local_midnight(start.date_naive())?, calendar::local_midnight(end.date_naive())?)
+    } else {
+        (start.timestamp(), end.timestamp())
+    };
+
+    Some(CalendarEvent {
+        title: event.subject,
+        start,
+        end,
+        all_day: event.is_all_day,
+        location: event.location.map(|location| location.display_name).filter(|name| !name.is_empty()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_caldav_calendar_data_is_unescaped() {
+        let xml = "<d:multistatus xmlns:d=\"DAV:\"><d:response><d:propstat><d:prop>\
+            <cal:calendar-data>BEGIN:VCALENDAR&#13;\nSUMMARY:R&amp;D sync\nEND:VCALENDAR</cal:calendar-data>\
+            </d:prop></d:propstat></d:response></d:multistatus>";
+
+        assert_eq!(caldav_calendar_data(xml), ["BEGIN:VCALENDAR\r\nSUMMARY:R&D sync\nEND:VCALENDAR"]);
+    }
+
+    #[tokio::test]
+    async fn test_graph_events_are_read() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/me/calendarView"))
+            .and(header("authorization", "Bearer token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "value": [{
+                    "subject": "Site visit",
+                    "start": { "dateTime": "2026-10-17T14:00:00.0000000", "timeZone": "UTC" },
+                    "end": { "dateTime": "2026-10-17T15:30:00.0000000", "timeZone": "UTC" },
+                    "isAllDay": false,
+                    "location": { "displayName": "Lewisville" }
+                }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config = CalendarConfig {
+            enabled: true,
+            source: CalendarSource::Graph,
+            url: format!("{}/me/calendarView", server.uri()),
+            ..CalendarConfig::default()
+        };
+        let client = CalendarClient::new(config, Some("token".to_string())).unwrap();
+        let from = Utc.with_ymd_and_hms(2026, 10, 17, 0, 0, 0).unwrap();
+        let events = client.events(from, from + chrono::Duration::days(1)).await.unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].title, "Site visit");
+        assert_eq!(events[0].start, Utc.with_ymd_and_hms(2026, 10, 17, 14, 0, 0).unwrap().timestamp());
+        assert_eq!(events[0].location.as_deref(), Some("Lewisville"));
+    }
+}
@@ -0,0 +1,286 @@
+//! Fault injection
+//!
+//! Debug-only layer around the providers that makes calls slow or fail on
+//! purpose, so error handling and the UI's error states can be exercised
+//! without waiting for a real outage. Injected failures are the errors the
+//! real clients report: rate limits, server errors, and timeouts. Error bursts
+//! are tracked per pipeline stage, so one failing stage does not affect the others.
+
+use super::openwebui::{GenerationOverrides, LlmReply, ModelInfo};
+use super::whisper::TranscriptionCandidate;
+use super::provider::{ImageAttachment, LlmProvider, SttProvider, TtsProvider};
+use crate::config::{AppConfig, ChaosConfig};
+use crate::error::{AppError, AppResult, ElevenLabsError, OpenWebUiError, WhisperError};
+use async_trait::async_trait;
+use rand::Rng;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+/// How long a dropped call hangs before it times out
+const DROP_HANG: Duration = Duration::from_secs(5);
+
+/// Calls still to fail in each stage's current error burst
+static BURSTS: [AtomicU32; 3] = [AtomicU32::new(0), AtomicU32::new(0), AtomicU32::new(0)];
+
+/// Pipeline stage a provider serves
+#[derive(Debug, Clone, Copy)]
+enum Stage {
+    Stt,
+    Llm,
+    Tts,
+}
+
+/// Failure injected into a call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fault {
+    /// HTTP 429
+    RateLimited,
+
+    /// HTTP 500
+    ServerError,
+
+    /// No response before the timeout
+    Dropped,
+}
+
+impl Stage {
+    /// The error the stage's real client reports for a fault
+    fn error(self, fault: Fault) -> AppError {
+        const SERVER_ERROR: &str = "HTTP 500 Internal Server Error (injected)";
+
+        match (self, fault) {
+            (Stage::Stt, Fault::RateLimited) => WhisperError::RateLimitExceeded.into(),
+            (Stage::Stt, Fault::ServerError) => WhisperError::TranscriptionFailed(SERVER_ERROR.to_string()).into(),
+            (Stage::Stt, Fault::Dropped) => WhisperError::Timeout.into(),
+            (Stage::Llm, Fault::RateLimited) => OpenWebUiError::RateLimitExceeded.into(),
+            (Stage::Llm, Fault::ServerError) => OpenWebUiError::MessageSendFailed(SERVER_ERROR.to_string()).into(),
+            (Stage::Llm, Fault::Dropped) => OpenWebUiError::Timeout.into(),
+            (Stage::Tts, Fault::RateLimited) => ElevenLabsError::RateLimitExceeded.into(),
+            (Stage::Tts, Fault::ServerError) => ElevenLabsError::SynthesisFailed(SERVER_ERROR.to_string()).into(),
+            (Stage::Tts, Fault::Dropped) => ElevenLabsError::Timeout.into(),
+        }
+    }
+}
+
+/// Provider wrapped with fault injection
+struct Chaos<P: ?Sized> {
+    inner: Box<P>,
+    stage: Stage,
+    config: ChaosConfig,
+}
+
+/// Wrap a speech-to-text provider if fault injection is on
+pub fn stt(provider: Box<dyn SttProvider>, config: &AppConfig) -> Box<dyn SttProvider> {
+    if active(config) {
+        Box::new(Chaos::new(provider, Stage::Stt, config))
+    } else {
+        provider
+    }
+}
+
+/// Wrap an LLM provider if fault injection is on
+pub fn llm(provider: Box<dyn LlmProvider>, config: &AppConfig) -> Box<dyn LlmProvider> {
+    if active(config) {
+        Box::new(Chaos::new(provider, Stage::Llm, config))
+    } else {
+        provider
+    }
+}
+
+/// Wrap a text-to-speech provider if fault injection is on
+pub fn tts(provider: Box<dyn TtsProvider>, config: &AppConfig) -> Box<dyn TtsProvider> {
+    if active(config) {
+        Box::new(Chaos::new(provider, Stage::Tts, config))
+    } else {
+        provider
+    }
+}
+
+/// Whether faults are injected; never in release builds
+fn active(config: &AppConfig) -> bool {
+    cfg!(debug_assertions) && config.chaos.enabled
+}
+
+impl<P: ?Sized> Chaos<P> {
+    fn new(inner: Box<P>, stage: Stage, config: &AppConfig) -> Self {
+        Self {
+            inner,
+            stage,
+            config: config.chaos.clone(),
+        }
+    }
+
+    /// Delay the call, then fail it if a fault is drawn
+    async fn disturb(&self) -> AppResult<()> {
+        let (delay, fault) = {
+            let mut rng = rand::thread_rng();
+            let delay = Duration::from_millis(rng.gen_range(0..=self.config.max_latency_ms));
+            (delay, pick_fault(&self.config, &BURSTS[self.stage as usize], &mut rng))
+        };
+        tokio::time::sleep(delay).await;
+
+        let Some(fault) = fault else {
+            return Ok(());
+        };
+        tracing::warn!("Injecting {:?} into {:?} call", fault, self.stage);
+        if fault == Fault::Dropped {
+            tokio::time::sleep(DROP_HANG).await;
+        }
+        Err(self.stage.error(fault))
+    }
+}
+
+/// Draw the fault for one call, continuing or starting an error burst
+fn pick_fault(config: &ChaosConfig, burst: &AtomicU32, rng: &mut impl Rng) -> Option<Fault> {
+    let in_burst = burst.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| left.checked_sub(1)).is_ok();
+
+    if in_burst || rng.gen_bool(probability(config.error_rate)) {
+        if !in_burst {
+            burst.store(config.burst_length.saturating_sub(1), Ordering::SeqCst);
+        }
+        return Some(if rng.gen_bool(0.5) { Fault::RateLimited } else { Fault::ServerError });
+    }
+
+    rng.gen_bool(probability(config.drop_rate)).then_some(Fault::Dropped)
+}
+
+/// Configured rate as a valid probability (out-of-range and NaN rates are clamped)
+fn probability(rate: f32) -> f64 {
+    let rate = f64::from(rate);
+    if rate >= 1.0 {
+        1.0
+    } else if rate > 0.0 {
+        rate
+    } else {
+        0.0
+    }
+}
+
+#[async_trait]
+impl SttProvider for Chaos<dyn SttProvider> {
+    async fn transcribe_audio(&self, audio_data: Vec<u8>, filename: &str) -> AppResult<String> {
+        self.disturb().await?;
+        self.inner.transcribe_audio(audio_data, filename).await
+    }
+
+    async fn transcribe_candidates(&self, audio_data: Vec<u8>, filename: &str) -> AppResult<Vec<TranscriptionCandidate>> {
+        self.disturb().await?;
+        self.inner.transcribe_candidates(audio_data, filename).await
+    }
+
+    async fn check_connectivity(&self) -> AppResult<bool> {
+        self.disturb().await?;
+        self.inner.check_connectivity().await
+    }
+}
+
+#[async_trait]
+impl LlmProvider for Chaos<dyn LlmProvider> {
+    async fn send_message_with(
+        &self,
+        messages: Vec<(String, String)>,
+        overrides: &GenerationOverrides,
+    ) -> AppResult<LlmReply> {
+        self.disturb().await?;
+        self.inner.send_message_with(messages, overrides).await
+    }
+
+    async fn send_message_with_images(
+        &self,
+        messages: Vec<(String, String)>,
+        images: &[ImageAttachment],
+        overrides: &GenerationOverrides,
+    ) -> AppResult<LlmReply> {
+        self.disturb().await?;
+        self.inner.send_message_with_images(messages, images, overrides).await
+    }
+
+    async fn stream_message_with(
+        &self,
+        messages: Vec<(String, String)>,
+        overrides: &GenerationOverrides,
+        on_delta: &mut (dyn for<'d> FnMut(&'d str) + Send),
+    ) -> AppResult<LlmReply> {
+        self.disturb().await?;
+        self.inner.stream_message_with(messages, overrides, on_delta).await
+    }
+
+    async fn list_models(&self) -> AppResult<Vec<ModelInfo>> {
+        self.disturb().await?;
+        self.inner.list_models().await
+    }
+
+    async fn check_connectivity(&self) -> AppResult<bool> {
+        self.disturb().await?;
+        self.inner.check_connectivity().await
+    }
+
+    async fn embed(&self, texts: &[String], model: &str) -> AppResult<Vec<Vec<f32>>> {
+        self.disturb().await?;
+        self.inner.embed(texts, model).await
+    }
+}
+
+#[async_trait]
+impl TtsProvider for Chaos<dyn TtsProvider> {
+    async fn synthesize_speech(&self, text: &str) -> AppResult<Vec<u8>> {
+        self.disturb().await?;
+        self.inner.synthesize_speech(text).await
+    }
+
+    async fn check_connectivity(&self) -> AppResult<bool> {
+        self.disturb().await?;
+        self.inner.check_connectivity().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn config(error_rate: f32, drop_rate: f32) -> ChaosConfig {
+        ChaosConfig {
+            enabled: true,
+            error_rate,
+            drop_rate,
+            ..ChaosConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_bursts_fail_consecutive_calls() {
+        let burst = AtomicU32::new(0);
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let fault = pick_fault(&config(1.0, 0.0), &burst, &mut rng);
+        assert!(matches!(fault, Some(Fault::RateLimited | Fault::ServerError)));
+
+        // The burst continues even though new bursts can no longer start
+        let calm = config(0.0, 0.0);
+        assert!(pick_fault(&calm, &burst, &mut rng).is_some());
+        assert!(pick_fault(&calm, &burst, &mut rng).is_some());
+        assert_eq!(pick_fault(&calm, &burst, &mut rng), None);
+    }
+
+    #[test]
+    fn test_drops_and_rates_are_bounded() {
+        let burst = AtomicU32::new(0);
+        let mut rng = StdRng::seed_from_u64(7);
+
+        assert_eq!(pick_fault(&config(0.0, 1.0), &burst, &mut rng), Some(Fault::Dropped));
+        assert_eq!(pick_fault(&config(-1.0, f32::NAN), &burst, &mut rng), None);
+        assert_eq!(probability(3.0), 1.0);
+    }
+
+    #[test]
+    fn test_faults_match_client_errors() {
+        assert!(matches!(
+            Stage::Tts.error(Fault::RateLimited),
+            AppError::ElevenLabs(ElevenLabsError::RateLimitExceeded)
+        ));
+        assert!(Stage::Llm.error(Fault::ServerError).is_transient());
+        assert!(matches!(Stage::Stt.error(Fault::Dropped), AppError::WhisperApi(WhisperError::Timeout)));
+    }
+}
@@ -0,0 +1,114 @@
+//! Compressed uploads
+//!
+//! When `audio.compress_uploads` is on (it is in low-bandwidth mode), this
+//! layer around the speech-to-text provider re-encodes WAV recordings as FLAC
+//! before they are uploaded. FLAC is lossless, so transcription is unaffected,
+//! and recorded speech shrinks to roughly half its size. Whisper and Azure
+//! OpenAI both accept it. Recordings that are not 16-bit PCM WAV, or that fail
+//! to encode, are uploaded as they are.
+
+use super::provider::SttProvider;
+use super::whisper::TranscriptionCandidate;
+use crate::config::AppConfig;
+use crate::error::{AppResult, AudioError};
+use crate::wav;
+use async_trait::async_trait;
+use flacenc::component::BitRepr;
+use flacenc::error::Verify;
+
+/// Speech-to-text provider that uploads FLAC
+struct Compressed {
+    inner: Box<dyn SttProvider>,
+}
+
+/// Wrap a speech-to-text provider if uploads are compressed
+pub fn stt(provider: Box<dyn SttProvider>, config: &AppConfig) -> Box<dyn SttProvider> {
+    if config.audio.compress_uploads {
+        Box::new(Compressed { inner: provider })
+    } else {
+        provider
+    }
+}
+
+/// `audio` as FLAC with a matching file name, or both unchanged if it cannot be encoded
+///
+/// Encoding a minute of speech takes a few milliseconds, so it runs in place.
+fn compress(audio: Vec<u8>, filename: &str) -> (Vec<u8>, String) {
+    match encode_flac(&audio) {
+        Ok(flac) => {
+            tracing::debug!("Compressed the recording from {} to {} bytes", audio.len(), flac.len());
+            let stem = filename.rsplit_once('.').map_or(filename, |(stem, _)| stem);
+            (flac, format!("{}.flac", stem))
+        }
+        Err(e) => {
+            tracing::debug!("Uploading the recording uncompressed: {}", e);
+            (audio, filename.to_string())
+        }
+    }
+}
+
+/// A 16-bit PCM WAV file as FLAC
+fn encode_flac(wav: &[u8]) -> AppResult<Vec<u8>> {
+    let failed = |reason: String| AudioError::InvalidFormat(format!("FLAC encoding failed: {}", reason));
+    let (channels, sample_rate) = wav::decode_channels(wav)?;
+    let frames = channels[0].len();
+    if frames == 0 {
+        return Err(AudioError::InvalidFormat("no audio data".to_string()).into());
+    }
+
+    // Back to the integer samples the WAV file held, interleaved
+    let samples: Vec<i32> = (0..frames)
+        .flat_map(|i| channels.iter().map(move |channel| (channel[i] * f32::from(i16::MAX)).round() as i32))
+        .collect();
+
+    let config = flacenc::config::Encoder::default()
+        .into_verified()
+        .map_err(|(_, e)| failed(e.to_string()))?;
+    let source = flacenc::source::MemSource::from_samples(&samples, channels.len(), 16, sample_rate as usize);
+    // EncodeError has no Display, only Debug
+    let stream = flacenc::encode_with_fixed_block_size(&config, source, config.block_size)
+        .map_err(|e| failed(format!("{:?}", e)))?;
+
+    let mut sink = flacenc::bitsink::ByteSink::new();
+    stream
+        .write(&mut sink)
+        .map_err(|_| failed("could not write the stream".to_string()))?;
+    Ok(sink.as_slice().to_vec())
+}
+
+#[async_trait]
+impl SttProvider for Compressed {
+    async fn transcribe_audio(&self, audio_data: Vec<u8>, filename: &str) -> AppResult<String> {
+        let (audio_data, filename) = compress(audio_data, filename);
+        self.inner.transcribe_audio(audio_data, &filename).await
+    }
+
+    async fn transcribe_candidates(&self, audio_data: Vec<u8>, filename: &str) -> AppResult<Vec<TranscriptionCandidate>> {
+        let (audio_data, filename) = compress(audio_data, filename);
+        self.inner.transcribe_candidates(audio_data, &filename).await
+    }
+
+    async fn check_connectivity(&self) -> AppResult<bool> {
+        self.inner.check_connectivity().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wav_is_uploaded_as_smaller_flac() {
+        let samples: Vec<f32> = (0..16_000).map(|i| (i as f32 / 20.0).sin() * 0.3).collect();
+        let recording = wav::encode(&samples, 16_000);
+
+        let (flac, filename) = compress(recording.clone(), "recording.wav");
+        assert_eq!(&flac[..4], b"fLaC");
+        assert!(flac.len() < recording.len() / 2);
+        assert_eq!(filename, "recording.flac");
+
+        // Anything else goes up unchanged
+        let (audio, filename) = compress(b"not audio".to_vec(), "clip.webm");
+        assert_eq!((audio.as_slice(), filename.as_str()), (b"not audio".as_slice(), "clip.webm"));
+    }
+}
@@ -0,0 +1,311 @@
+//! Provider cooldowns
+//!
+//! When a provider reports a rate limit or an exhausted quota, retrying right
+//! away only collects more of the same error. The provider is instead paused
+//! for `cooldown.rate_limit_secs` or `cooldown.quota_secs`: calls to it fail at
+//! once without a request, and new pipeline runs use the stage's fallback
+//! provider if one is configured. Cooldowns are kept per provider, so a
+//! fallback that is itself cooling down is not used either.
+
+use super::openwebui::{GenerationOverrides, LlmReply, ModelInfo};
+use super::provider::{llm_provider, stt_provider, tts_provider, ImageAttachment, LlmProvider, SttProvider, TtsProvider};
+use super::whisper::TranscriptionCandidate;
+use crate::config::{ApiKeys, AppConfig};
+use crate::error::{AppError, AppResult};
+use crate::telemetry::Stage;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Why a provider is paused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CooldownReason {
+    /// HTTP 429
+    RateLimited,
+
+    /// HTTP 402: quota or credits used up
+    QuotaExhausted,
+}
+
+/// A paused provider
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderCooldown {
+    /// Provider, as named in the settings
+    pub provider: String,
+
+    pub reason: CooldownReason,
+
+    /// Unix timestamp when calls resume
+    pub until: u64,
+}
+
+/// Cooldowns of all providers, shared by every client created through it
+#[derive(Debug, Clone, Default)]
+pub struct Cooldowns {
+    active: Arc<Mutex<BTreeMap<String, ProviderCooldown>>>,
+}
+
+impl Cooldowns {
+    /// Providers currently paused
+    pub fn active(&self) -> Vec<ProviderCooldown> {
+        let now = now();
+        let mut active = self.active.lock().unwrap();
+        active.retain(|_, cooldown| cooldown.until > now);
+        active.values().cloned().collect()
+    }
+
+    /// End the cooldown of one provider, or of all with `None`; returns how many ended
+    pub fn clear(&self, provider: Option<&str>) -> usize {
+        let mut active = self.active.lock().unwrap();
+        match provider {
+            Some(provider) => usize::from(active.remove(provider).is_some()),
+            None => std::mem::take(&mut *active).len(),
+        }
+    }
+
+    /// Seconds until a provider's cooldown ends, if it is paused
+    fn remaining(&self, provider: &str) -> Option<u64> {
+        let now = now();
+        let active = self.active.lock().unwrap();
+        active.get(provider).map(|cooldown| cooldown.until.saturating_sub(now)).filter(|secs| *secs > 0)
+    }
+
+    /// Pause a provider if `error` is a rate limit or exhausted quota
+    fn observe(&self, provider: &str, error: &AppError, config: &AppConfig) {
+        let (reason, secs) = if error.is_rate_limited() {
+            (CooldownReason::RateLimited, config.cooldown.rate_limit_secs)
+        } else if error.is_quota_exhausted() {
+            (CooldownReason::QuotaExhausted, config.cooldown.quota_secs)
+        } else {
+            return;
+        };
+        if secs == 0 {
+            return;
+        }
+
+        tracing::warn!("Pausing {} for {}s ({:?})", provider, secs, reason);
+        self.active.lock().unwrap().insert(provider.to_string(), ProviderCooldown {
+            provider: provider.to_string(),
+            reason,
+            until: now() + secs,
+        });
+    }
+
+    /// Fail fast if the provider is paused
+    fn check(&self, provider: &str) -> AppResult<()> {
+        match self.remaining(provider) {
+            Some(remaining_secs) => Err(AppError::CoolingDown {
+                provider: provider.to_string(),
+                remaining_secs,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Config for a stage: unchanged, or switched to the fallback while the provider is paused
+    fn route(&self, stage: Stage, config: &AppConfig) -> AppResult<AppConfig> {
+        let routed = self.select(stage, config)?;
+        let (provider, fallback_provider) = (stage.provider(config), stage.provider(&routed));
+        if fallback_provider != provider {
+            tracing::warn!("{} is paused, using {} for {}", provider, fallback_provider, stage.name());
+        }
+        Ok(routed)
+    }
+
+    /// Config with `stage` switched to the provider that handles it now, for reporting which one did
+    ///
+    /// Unchanged if neither the provider nor its fallback is available.
+    pub fn routed(&self, stage: Stage, config: &AppConfig) -> AppConfig {
+        self.select(stage, config).unwrap_or_else(|_| config.clone())
+    }
+
+    fn select(&self, stage: Stage, config: &AppConfig) -> AppResult<AppConfig> {
+        let provider = stage.provider(config);
+        let Err(paused) = self.check(&provider) else {
+            return Ok(config.clone());
+        };
+
+        let mut fallback = config.clone();
+        let has_fallback = match stage {
+            Stage::Stt => config.cooldown.fallback_stt.map(|kind| fallback.providers.stt = kind).is_some(),
+            Stage::Llm => config.cooldown.fallback_llm.map(|kind| fallback.providers.llm = kind).is_some(),
+            Stage::Tts => config.cooldown.fallback_tts.map(|kind| fallback.providers.tts = kind).is_some(),
+        };
+        let fallback_provider = stage.provider(&fallback);
+        if !has_fallback || fallback_provider == provider || self.check(&fallback_provider).is_err() {
+            return Err(paused);
+        }
+        Ok(fallback)
+    }
+
+    /// Speech-to-text provider that respects cooldowns, failing over if configured
+    pub fn stt_provider(&self, config: &AppConfig, api_keys: &ApiKeys) -> AppResult<Box<dyn SttProvider>> {
+        let config = self.route(Stage::Stt, config)?;
+        let provider = stt_provider(&config, api_keys)?;
+        Ok(Box::new(Guarded::new(provider, Stage::Stt, &config, self)))
+    }
+
+    /// LLM provider that respects cooldowns, failing over if configured
+    pub fn llm_provider(&self, config: &AppConfig, api_keys: &ApiKeys) -> AppResult<Box<dyn LlmProvider>> {
+        let config = self.route(Stage::Llm, config)?;
+        let provider = llm_provider(&config, api_keys)?;
+        Ok(Box::new(Guarded::new(provider, Stage::Llm, &config, self)))
+    }
+
+    /// Text-to-speech provider that respects cooldowns, failing over if configured
+    pub fn tts_provider(&self, config: &AppConfig, api_keys: &ApiKeys) -> AppResult<Box<dyn TtsProvider>> {
+        let config = self.route(Stage::Tts, config)?;
+        let provider = tts_provider(&config, api_keys)?;
+        Ok(Box::new(Guarded::new(provider, Stage::Tts, &config, self)))
+    }
+}
+
+/// Provider whose calls are skipped while it is paused and that starts a cooldown on 429/402
+struct Guarded<P: ?Sized> {
+    inner: Box<P>,
+    provider: String,
+    config: AppConfig,
+    cooldowns: Cooldowns,
+}
+
+impl<P: ?Sized> Guarded<P> {
+    fn new(inner: Box<P>, stage: Stage, config: &AppConfig, cooldowns: &Cooldowns) -> Self {
+        Self {
+            inner,
+            provider: stage.provider(config),
+            config: config.clone(),
+            cooldowns: cooldowns.clone(),
+        }
+    }
+
+    fn after<T>(&self, result: AppResult<T>) -> AppResult<T> {
+        if let Err(e) = &result {
+            self.cooldowns.observe(&self.provider, e, &self.config);
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl SttProvider for Guarded<dyn SttProvider> {
+    async fn transcribe_audio(&self, audio_data: Vec<u8>, filename: &str) -> AppResult<String> {
+        self.cooldowns.check(&self.provider)?;
+        self.after(self.inner.transcribe_audio(audio_data, filename).await)
+    }
+
+    async fn transcribe_candidates(&self, audio_data: Vec<u8>, filename: &str) -> AppResult<Vec<TranscriptionCandidate>> {
+        self.cooldowns.check(&self.provider)?;
+        self.after(self.inner.transcribe_candidates(audio_data, filename).await)
+    }
+
+    async fn check_connectivity(&self) -> AppResult<bool> {
+        self.inner.check_connectivity().await
+    }
+}
+
+#[async_trait]
+impl LlmProvider for Guarded<dyn LlmProvider> {
+    async fn send_message_with(
+        &self,
+        messages: Vec<(String, String)>,
+        overrides: &GenerationOverrides,
+    ) -> AppResult<LlmReply> {
+        self.cooldowns.check(&self.provider)?;
+        self.after(self.inner.send_message_with(messages, overrides).await)
+    }
+
+    async fn send_message_with_images(
+        &self,
+        messages: Vec<(String, String)>,
+        images: &[ImageAttachment],
+        overrides: &GenerationOverrides,
+    ) -> AppResult<LlmReply> {
+        self.cooldowns.check(&self.provider)?;
+        self.after(self.inner.send_message_with_images(messages, images, overrides).await)
+    }
+
+    async fn stream_message_with(
+        &self,
+        messages: Vec<(String, String)>,
+        overrides: &GenerationOverrides,
+        on_delta: &mut (dyn for<'d> FnMut(&'d str) + Send),
+    ) -> AppResult<LlmReply> {
+        self.cooldowns.check(&self.provider)?;
+        self.after(self.inner.stream_message_with(messages, overrides, on_delta).await)
+    }
+
+    async fn list_models(&self) -> AppResult<Vec<ModelInfo>> {
+        self.inner.list_models().await
+    }
+
+    async fn check_connectivity(&self) -> AppResult<bool> {
+        self.inner.check_connectivity().await
+    }
+
+    async fn embed(&self, texts: &[String], model: &str) -> AppResult<Vec<Vec<f32>>> {
+        self.cooldowns.check(&self.provider)?;
+        self.after(self.inner.embed(texts, model).await)
+    }
+}
+
+#[async_trait]
+impl TtsProvider for Guarded<dyn TtsProvider> {
+    async fn synthesize_speech(&self, text: &str) -> AppResult<Vec<u8>> {
+        self.cooldowns.check(&self.provider)?;
+        self.after(self.inner.synthesize_speech(text).await)
+    }
+
+    async fn check_connectivity(&self) -> AppResult<bool> {
+        self.inner.check_connectivity().await
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TtsProviderKind;
+    use crate::error::{ElevenLabsError, OpenRouterError, WhisperError};
+
+    #[test]
+    fn test_only_limits_start_a_cooldown() {
+        let cooldowns = Cooldowns::default();
+        let config = AppConfig::default();
+
+        cooldowns.observe("whisper", &WhisperError::Timeout.into(), &config);
+        assert!(cooldowns.active().is_empty());
+
+        cooldowns.observe("whisper", &WhisperError::RateLimitExceeded.into(), &config);
+        cooldowns.observe("openrouter", &OpenRouterError::InsufficientCredits.into(), &config);
+        let active = cooldowns.active();
+        assert_eq!(active.len(), 2);
+        assert_eq!(active[0].reason, CooldownReason::QuotaExhausted);
+        assert!(matches!(cooldowns.check("whisper"), Err(AppError::CoolingDown { remaining_secs: 60, .. })));
+
+        assert_eq!(cooldowns.clear(Some("whisper")), 1);
+        assert!(cooldowns.check("whisper").is_ok());
+        assert_eq!(cooldowns.clear(None), 1);
+    }
+
+    #[tokio::test]
+    async fn test_paused_stage_fails_over_or_fails_fast() {
+        let cooldowns = Cooldowns::default();
+        let mut config = AppConfig::default();
+        config.mock.latency_ms = 0;
+        cooldowns.observe("elevenlabs", &ElevenLabsError::QuotaExceeded.into(), &config);
+
+        // No fallback: the call is refused without a request
+        let error = cooldowns.tts_provider(&config, &ApiKeys::none()).err().unwrap();
+        assert!(matches!(error, AppError::CoolingDown { ref provider, .. } if provider == "elevenlabs"));
+
+        config.cooldown.fallback_tts = Some(TtsProviderKind::Mock);
+        let audio = cooldowns.tts_provider(&config, &ApiKeys::none()).unwrap().synthesize_speech("Hello").await;
+        assert!(audio.is_ok());
+    }
+}
@@ -3,6 +3,9 @@
 //! Handles text-to-speech conversion using ElevenLabs API with voice selection,
 //! voice settings customization, and proper error handling.
 
+use super::endpoint;
+use super::http;
+use super::retry;
 use crate::config::{ElevenLabsConfig, VoiceSettings};
 use crate::error::{AppResult, ElevenLabsError};
 use serde::{Deserialize, Serialize};
@@ -13,6 +16,9 @@ pub struct ElevenLabsClient {
     client: reqwest::Client,
     config: ElevenLabsConfig,
     api_key: Option<String>,
+
+    /// Delay before the first retry of a failed request
+    retry_delay: Duration,
 }
 
 /// Text-to-speech request
@@ -64,16 +70,16 @@ struct VoicesResponse {
 
 impl ElevenLabsClient {
     /// Create a new ElevenLabs client
-    pub fn new(config: ElevenLabsConfig, api_key: Option<String>) -> AppResult<Self> {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(config.timeout_secs))
-            .build()
+    pub fn new(mut config: ElevenLabsConfig, api_key: Option<String>) -> AppResult<Self> {
+        config.endpoint = endpoint::elevenlabs(&config.endpoint);
+        let client = http::client(Duration::from_secs(config.timeout_secs))
             .map_err(|e| ElevenLabsError::SynthesisFailed(e.to_string()))?;
 
         Ok(Self {
             client,
             config,
             api_key,
+            retry_delay: retry::RETRY_BASE_DELAY,
         })
     }
 
@@ -91,30 +97,12 @@ impl ElevenLabsClient {
             return Err(ElevenLabsError::CharacterLimitExceeded.into());
         }
 
-        log::info!("Synthesizing speech for text ({} chars)", text.len());
+        tracing::info!("Synthesizing speech for text ({} chars)", text.len());
 
-        // Attempt synthesis with retry logic
-        let max_retries = 3;
-        let mut last_error = None;
+        let audio_data = retry::with_retries("Synthesis", self.retry_delay, || self.try_synthesize_speech(text)).await?;
 
-        for attempt in 1..=max_retries {
-            match self.try_synthesize_speech(text).await {
-                Ok(audio_data) => {
-                    log::info!("Speech synthesis successful ({} bytes)", audio_data.len());
-                    return Ok(audio_data);
-                }
-                Err(e) => {
-                    last_error = Some(e);
-                    if attempt < max_retries {
-                        let delay = Duration::from_secs(2u64.pow(attempt as u32 - 1));
-                        log::warn!("Synthesis attempt {} failed, retrying in {:?}", attempt, delay);
-                        tokio::time::sleep(delay).await;
-                    }
-                }
-            }
-        }
-
-        Err(last_error.unwrap())
+        tracing::info!("Speech synthesis successful ({} bytes)", audio_data.len());
+        Ok(audio_data)
     }
 
     /// Internal synthesis attempt
@@ -143,15 +131,19 @@ impl ElevenLabsClient {
             self.config.voice_id
         );
 
-        log::debug!("Synthesis request: voice={}, model={}, length={}",
+        tracing::debug!("Synthesis request: voice={}, model={}, length={}",
                    self.config.voice_id, self.config.model_id, text.len());
 
         // Build HTTP request
-        let response = self.client
+        let mut request = self.client
             .post(&endpoint)
             .header("xi-api-key", self.api_key.as_ref().unwrap())
-            .json(&request_body)
-            .send()
+            .json(&request_body);
+        if let Some(format) = &self.config.output_format {
+            request = request.query(&[("output_format", format)]);
+        }
+
+        let response = http::send("elevenlabs", request)
             .await
             .map_err(|e| {
                 if e.is_timeout() {
@@ -207,12 +199,13 @@ impl ElevenLabsClient {
             self.config.endpoint.trim_end_matches('/').replace("/text-to-speech", "")
         );
 
-        log::debug!("Fetching voices from: {}", voices_endpoint);
+        tracing::debug!("Fetching voices from: {}", voices_endpoint);
 
-        let response = self.client
+        let request = self.client
             .get(&voices_endpoint)
-            .header("xi-api-key", self.api_key.as_ref().unwrap())
-            .send()
+            .header("xi-api-key", self.api_key.as_ref().unwrap());
+
+        let response = http::send("elevenlabs", request)
             .await
             .map_err(|e| ElevenLabsError::SynthesisFailed(e.to_string()))?;
 
@@ -242,12 +235,12 @@ impl ElevenLabsClient {
             self.config.endpoint.trim_end_matches('/').replace("/text-to-speech", "")
         );
 
-        let response = self.client
+        let request = self.client
             .get(&voices_endpoint)
             .header("xi-api-key", self.api_key.as_ref().unwrap())
-            .timeout(Duration::from_secs(5))
-            .send()
-            .await;
+            .timeout(Duration::from_secs(5));
+
+        let response = http::send("elevenlabs", request).await;
 
         match response {
             Ok(resp) => {
@@ -256,14 +249,15 @@ impl ElevenLabsClient {
                 Ok(status < 500)
             }
             Err(e) => {
-                log::warn!("ElevenLabs connectivity check failed: {}", e);
+                tracing::warn!("ElevenLabs connectivity check failed: {}", e);
                 Ok(false)
             }
         }
     }
 
     /// Update configuration
-    pub fn update_config(&mut self, config: ElevenLabsConfig) {
+    pub fn update_config(&mut self, mut config: ElevenLabsConfig) {
+        config.endpoint = endpoint::elevenlabs(&config.endpoint);
         self.config = config;
     }
 
@@ -291,6 +285,8 @@ impl ElevenLabsClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::AppError;
+    use crate::test_support::*;
 
     #[test]
     fn test_elevenlabs_client_creation() {
@@ -305,6 +301,8 @@ mod tests {
                 use_speaker_boost: true,
             },
             timeout_secs: 30,
+            presets: Vec::new(),
+            output_format: None,
         };
 
         let client = ElevenLabsClient::new(config, None);
@@ -324,6 +322,8 @@ mod tests {
                 use_speaker_boost: true,
             },
             timeout_secs: 30,
+            presets: Vec::new(),
+            output_format: None,
         };
 
         let client = ElevenLabsClient::new(config, Some("test_key".to_string())).unwrap();
@@ -348,4 +348,93 @@ mod tests {
         assert!(json.contains("stability"));
         assert!(json.contains("0.5"));
     }
+
+    fn mock_client(server: &MockServer) -> ElevenLabsClient {
+        let config = ElevenLabsConfig {
+            endpoint: format!("{}/v1/text-to-speech", server.uri()),
+            voice_id: "voice123".to_string(),
+            model_id: "eleven_monolingual_v1".to_string(),
+            voice_settings: VoiceSettings {
+                stability: 0.5,
+                similarity_boost: 0.75,
+                style: None,
+                use_speaker_boost: true,
+            },
+            timeout_secs: 5,
+            presets: Vec::new(),
+            output_format: None,
+        };
+        let mut client = ElevenLabsClient::new(config, Some("test_key".to_string())).unwrap();
+        client.retry_delay = Duration::ZERO;
+        client
+    }
+
+    #[tokio::test]
+    async fn test_audio_is_returned() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/text-to-speech/voice123"))
+            .and(header("xi-api-key", "test_key"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(vec![0xFF, 0xFB, 0x10], "audio/mpeg"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let audio = mock_client(&server).synthesize_speech("Hello").await.unwrap();
+        assert_eq!(audio, vec![0xFF, 0xFB, 0x10]);
+    }
+
+    #[tokio::test]
+    async fn test_output_format_is_requested() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(wiremock::matchers::query_param("output_format", "mp3_22050_32"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(vec![0xFF, 0xF3], "audio/mpeg"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut client = mock_client(&server);
+        client.config.output_format = Some("mp3_22050_32".to_string());
+        assert_eq!(client.synthesize_speech("Hello").await.unwrap(), vec![0xFF, 0xF3]);
+    }
+
+    #[tokio::test]
+    async fn test_permanent_errors_are_not_retried() {
+        for (status, expected) in [
+            (401, ElevenLabsError::AuthenticationFailed),
+            (402, ElevenLabsError::QuotaExceeded),
+            (429, ElevenLabsError::RateLimitExceeded),
+            (404, ElevenLabsError::VoiceNotFound("voice123".to_string())),
+        ] {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .respond_with(elevenlabs_error(status, "nope"))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            match mock_client(&server).synthesize_speech("Hello").await {
+                Err(AppError::ElevenLabs(error)) => assert_eq!(error.to_string(), expected.to_string()),
+                other => panic!("unexpected result for HTTP {}: {:?}", status, other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_server_error_message_is_kept_after_retries() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(elevenlabs_error(503, "Service temporarily overloaded"))
+            .expect(retry::MAX_ATTEMPTS as u64)
+            .mount(&server)
+            .await;
+
+        match mock_client(&server).synthesize_speech("Hello").await {
+            Err(AppError::ElevenLabs(ElevenLabsError::SynthesisFailed(message))) => {
+                assert_eq!(message, "Service temporarily overloaded")
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
 }
@@ -0,0 +1,155 @@
+//! Endpoint URLs
+//!
+//! Endpoints in the settings, and the `*_BASE_URL` variables their defaults
+//! come from, may be given either as a server's base URL or as the full URL
+//! requests go to. Each client resolves its endpoint here when it is created,
+//! so `http://localhost:3000`, `http://localhost:3000/api`, and
+//! `http://localhost:3000/api/chat/completions` all work, and a full URL is
+//! never given its path a second time.
+//!
+//! A host without a path gets the service's usual API path; a URL that does
+//! not end in an endpoint has the endpoint appended. Query strings are kept.
+//! Text that is not a URL is returned unchanged, so the request fails with the
+//! URL error rather than somewhere else.
+
+/// Whisper transcription endpoint, e.g. `https://api.openai.com/v1/audio/transcriptions`
+pub fn whisper(configured: &str) -> String {
+    resolve(configured, |url| {
+        if url.ends_with("/audio/transcriptions") || url.ends_with("/audio/translations") {
+            url.to_string()
+        } else if url.ends_with("/audio") {
+            format!("{}/transcriptions", url)
+        } else if has_path(url) {
+            format!("{}/audio/transcriptions", url)
+        } else {
+            format!("{}/v1/audio/transcriptions", url)
+        }
+    })
+}
+
+/// Chat endpoint of OpenWebUI or another OpenAI-compatible server
+///
+/// A bare host is taken to be OpenWebUI (`/api/chat`); a URL ending in `/api`
+/// or `/v1` gets the chat path that goes with it.
+pub fn openwebui_chat(configured: &str) -> String {
+    resolve(configured, |url| {
+        if url.ends_with("/chat/completions") || url.ends_with("/api/chat") {
+            url.to_string()
+        } else if url.ends_with("/chat") {
+            format!("{}/completions", url)
+        } else if url.ends_with("/api") {
+            format!("{}/chat", url)
+        } else if has_path(url) {
+            format!("{}/chat/completions", url)
+        } else {
+            format!("{}/api/chat", url)
+        }
+    })
+}
+
+/// ElevenLabs text-to-speech endpoint, without a voice, e.g. `https://api.elevenlabs.io/v1/text-to-speech`
+///
+/// A voice ID pasted after the endpoint is dropped; `elevenlabs.voice_id` picks the voice.
+pub fn elevenlabs(configured: &str) -> String {
+    const ENDPOINT: &str = "/text-to-speech";
+    resolve(configured, |url| {
+        if let Some(index) = url.find(&format!("{}/", ENDPOINT)) {
+            url[..index + ENDPOINT.len()].to_string()
+        } else if url.ends_with(ENDPOINT) {
+            url.to_string()
+        } else if has_path(url) {
+            format!("{}{}", url, ENDPOINT)
+        } else {
+            format!("{}/v1{}", url, ENDPOINT)
+        }
+    })
+}
+
+/// Moderation endpoint, e.g. `https://api.openai.com/v1/moderations`
+pub fn moderation(configured: &str) -> String {
+    resolve(configured, |url| {
+        if url.ends_with("/moderations") {
+            url.to_string()
+        } else if has_path(url) {
+            format!("{}/moderations", url)
+        } else {
+            format!("{}/v1/moderations", url)
+        }
+    })
+}
+
+/// Azure OpenAI resource URL, e.g. `https://acme.openai.azure.com`
+///
+/// Requests are built from the resource, so a pasted deployment URL is cut back to it.
+pub fn azure_resource(configured: &str) -> String {
+    let url = configured.trim().split('?').next().unwrap_or_default();
+    let host = url.find("://").map_or(0, |index| index + 3);
+    match url[host..].find("/openai") {
+        Some(index) => url[..host + index].to_string(),
+        None => url.trim_end_matches('/').to_string(),
+    }
+}
+
+/// `configured` without its trailing slash and query string, passed to `build`, with the query put back
+fn resolve(configured: &str, build: impl FnOnce(&str) -> String) -> String {
+    let configured = configured.trim();
+    let (url, query) = match configured.split_once('?') {
+        Some((url, query)) => (url, Some(query)),
+        None => (configured, None),
+    };
+    let url = url.trim_end_matches('/');
+    if parse(url).is_none() {
+        return configured.to_string();
+    }
+
+    let resolved = build(url);
+    match query {
+        Some(query) => format!("{}?{}", resolved, query),
+        None => resolved,
+    }
+}
+
+/// Whether `url` (a valid URL without a trailing slash) has a path after the host
+fn has_path(url: &str) -> bool {
+    parse(url).is_some_and(|parsed| parsed.path() != "/")
+}
+
+/// `url` parsed, if it is an HTTP or HTTPS URL
+fn parse(url: &str) -> Option<reqwest::Url> {
+    reqwest::Url::parse(url)
+        .ok()
+        .filter(|parsed| matches!(parsed.scheme(), "http" | "https"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_urls_and_full_endpoints_resolve_alike() {
+        for configured in [
+            "https://api.openai.com",
+            "https://api.openai.com/v1/",
+            "https://api.openai.com/v1/audio/transcriptions",
+        ] {
+            assert_eq!(whisper(configured), "https://api.openai.com/v1/audio/transcriptions");
+        }
+
+        assert_eq!(openwebui_chat("http://localhost:3000"), "http://localhost:3000/api/chat");
+        assert_eq!(openwebui_chat("http://localhost:3000/api/chat/completions"), "http://localhost:3000/api/chat/completions");
+        assert_eq!(openwebui_chat("http://localhost:11434/v1"), "http://localhost:11434/v1/chat/completions");
+
+        assert_eq!(elevenlabs("https://api.elevenlabs.io"), "https://api.elevenlabs.io/v1/text-to-speech");
+        assert_eq!(elevenlabs("https://api.elevenlabs.io/v1/text-to-speech/21m00Tcm4TlvDq8ikWAM"), "https://api.elevenlabs.io/v1/text-to-speech");
+
+        assert_eq!(azure_resource("https://acme.openai.azure.com/openai/deployments/gpt-4o/chat/completions?api-version=2024-06-01"), "https://acme.openai.azure.com");
+        assert_eq!(azure_resource("https://openai.example.com/"), "https://openai.example.com");
+    }
+
+    #[test]
+    fn test_query_is_kept_and_non_urls_are_left_alone() {
+        assert_eq!(moderation("https://proxy.example.com/openai/?tenant=7"), "https://proxy.example.com/openai/moderations?tenant=7");
+        assert_eq!(openwebui_chat(""), "");
+        assert_eq!(whisper("localhost:9000"), "localhost:9000");
+    }
+}
@@ -0,0 +1,73 @@
+//! News feed client
+//!
+//! Downloads RSS and Atom feeds for the news briefing. Feeds are parsed by
+//! [`crate::news`].
+
+use super::http;
+use crate::config::NewsFeed;
+use crate::error::{AppResult, NetworkError};
+use crate::news::{self, NewsItem};
+use std::time::Duration;
+
+/// Feed client
+pub struct FeedClient {
+    client: reqwest::Client,
+}
+
+impl FeedClient {
+    /// Create a client giving up on a feed after `timeout`
+    pub fn new(timeout: Duration) -> AppResult<Self> {
+        let client = http::client(timeout).map_err(NetworkError::from)?;
+        Ok(Self { client })
+    }
+
+    /// Items of `feed`, as listed in it
+    pub async fn fetch(&self, feed: &NewsFeed) -> AppResult<Vec<NewsItem>> {
+        let request = self
+            .client
+            .get(&feed.url)
+            .header("Accept", "application/rss+xml, application/atom+xml, application/xml;q=0.9, */*;q=0.8");
+        let response = http::send("news", request).await.map_err(NetworkError::from)?;
+        if !response.status().is_success() {
+            return Err(NetworkError::RequestFailed(format!("HTTP {}", response.status())).into());
+        }
+
+        let xml = response
+            .text()
+            .await
+            .map_err(|e| NetworkError::RequestFailed(e.to_string()))?;
+        Ok(news::parse_feed(&xml, &feed.name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+
+    #[tokio::test]
+    async fn test_feed_items_are_read() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/feed.xml"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                "<rss><channel><title>Trade News</title>\
+                 <item><title>Refrigerant rules change</title><link>https://example.com/1</link></item>\
+                 </channel></rss>",
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let feed = NewsFeed {
+            name: "Trade News".to_string(),
+            url: format!("{}/feed.xml", server.uri()),
+        };
+        let items = FeedClient::new(Duration::from_secs(5)).unwrap().fetch(&feed).await.unwrap();
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].source, "Trade News");
+        assert_eq!(items[0].title, "Refrigerant rules change");
+        assert_eq!(items[0].link, "https://example.com/1");
+    }
+}
@@ -0,0 +1,501 @@
+//! Google Gemini client
+//!
+//! Sends conversations to the Gemini API with `generateContent`, or streams them
+//! with `streamGenerateContent` over server-sent events. Images can be attached
+//! to the latest user message as inline data, and the configured safety
+//! settings are sent with every request.
+
+use super::http;
+use super::openwebui::{GenerationOverrides, LlmReply, ModelInfo};
+use super::provider::{ImageAttachment, LlmProvider};
+use super::sse;
+use crate::config::{AppConfig, GeminiConfig, GeminiSafetySetting, OpenWebUiConfig};
+use crate::error::{AppResult, GeminiError};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Finish reasons that mean the reply was withheld rather than completed
+const BLOCKED_FINISH_REASONS: &[&str] = &["SAFETY", "PROHIBITED_CONTENT", "BLOCKLIST", "SPII", "RECITATION"];
+
+/// Google Gemini client
+///
+/// Temperature, token, and context limits come from the `openwebui` section so
+/// they apply to every provider.
+pub struct GeminiClient {
+    client: reqwest::Client,
+    config: GeminiConfig,
+    llm: OpenWebUiConfig,
+    api_key: Option<String>,
+}
+
+/// `generateContent` request body
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerateContentRequest<'a> {
+    contents: Vec<Content>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system_instruction: Option<Content>,
+    generation_config: GenerationConfig,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    safety_settings: &'a [GeminiSafetySetting],
+}
+
+/// A turn in the conversation
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Content {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    role: Option<String>,
+    #[serde(default)]
+    parts: Vec<Part>,
+}
+
+/// Text or inline image within a turn
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Part {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    inline_data: Option<InlineData>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InlineData {
+    mime_type: String,
+    data: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerationConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_output_tokens: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stop_sequences: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+}
+
+/// `generateContent` response, also sent as each streamed event
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerateContentResponse {
+    #[serde(default)]
+    candidates: Vec<Candidate>,
+    prompt_feedback: Option<PromptFeedback>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Candidate {
+    content: Option<Content>,
+    finish_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PromptFeedback {
+    block_reason: Option<String>,
+}
+
+/// Models list response
+#[derive(Debug, Deserialize)]
+struct GeminiModelsResponse {
+    #[serde(default)]
+    models: Vec<GeminiModel>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiModel {
+    name: String,
+    display_name: Option<String>,
+    input_token_limit: Option<u64>,
+    #[serde(default)]
+    supported_generation_methods: Vec<String>,
+}
+
+/// Gemini error response
+#[derive(Debug, Deserialize)]
+struct GeminiErrorResponse {
+    error: GeminiErrorDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiErrorDetail {
+    message: String,
+}
+
+impl GenerateContentResponse {
+    /// Text of the first candidate, failing if the prompt or the reply was blocked
+    fn into_text(self) -> AppResult<String> {
+        if let Some(reason) = self.prompt_feedback.and_then(|feedback| feedback.block_reason) {
+            return Err(GeminiError::Blocked(reason).into());
+        }
+
+        let Some(candidate) = self.candidates.into_iter().next() else {
+            return Ok(String::new());
+        };
+
+        if let Some(reason) = candidate.finish_reason.filter(|r| BLOCKED_FINISH_REASONS.contains(&r.as_str())) {
+            return Err(GeminiError::Blocked(reason).into());
+        }
+
+        Ok(candidate
+            .content
+            .map(|content| content.parts.into_iter().filter_map(|part| part.text).collect())
+            .unwrap_or_default())
+    }
+}
+
+impl GeminiClient {
+    /// Create a new Gemini client
+    pub fn new(config: &AppConfig, api_key: Option<String>) -> AppResult<Self> {
+        let client = http::client(Duration::from_secs(config.gemini.timeout_secs))
+            .map_err(|e| GeminiError::RequestFailed(e.to_string()))?;
+
+        Ok(Self {
+            client,
+            config: config.gemini.clone(),
+            llm: config.openwebui.clone(),
+            api_key,
+        })
+    }
+
+    /// URL of a model method, e.g. `{endpoint}/models/gemini-2.0-flash:generateContent`
+    fn method_url(&self, model: &str, method: &str) -> String {
+        format!(
+            "{}/models/{}:{}",
+            self.config.endpoint.trim_end_matches('/'),
+            model.trim_start_matches("models/"),
+            method
+        )
+    }
+
+    /// Build the request body, moving system messages into `systemInstruction`
+    fn build_request(
+        &self,
+        messages: Vec<(String, String)>,
+        images: &[ImageAttachment],
+        overrides: &GenerationOverrides,
+    ) -> AppResult<GenerateContentRequest<'_>> {
+        let total_chars: usize = overrides.system_prompt.iter().map(String::len).sum::<usize>()
+            + messages.iter().map(|(_, content)| content.len()).sum::<usize>();
+        if total_chars > self.llm.max_context_length {
+            tracing::warn!("Context length ({}) exceeds maximum ({})", total_chars, self.llm.max_context_length);
+            return Err(GeminiError::ContextLimitExceeded.into());
+        }
+
+        let (contents, system_instruction) = build_contents(messages, images, overrides.system_prompt.clone());
+
+        Ok(GenerateContentRequest {
+            contents,
+            system_instruction,
+            generation_config: GenerationConfig {
+                temperature: Some(overrides.temperature.unwrap_or(self.llm.temperature)),
+                max_output_tokens: overrides.max_tokens.or(self.llm.max_tokens),
+                top_p: self.llm.top_p,
+                presence_penalty: self.llm.presence_penalty,
+                frequency_penalty: self.llm.frequency_penalty,
+                stop_sequences: self.llm.stop.clone(),
+                seed: self.llm.seed,
+            },
+            safety_settings: &self.config.safety_settings,
+        })
+    }
+
+    /// Send a request with the `x-goog-api-key` header, mapping HTTP errors to `GeminiError`
+    async fn send(&self, request: reqwest::RequestBuilder, model: &str) -> AppResult<reqwest::Response> {
+        let mut request = request;
+        if let Some(api_key) = &self.api_key {
+            request = request.header("x-goog-api-key", api_key);
+        }
+
+        let response = http::send("gemini", request).await.map_err(|e| {
+            if e.is_timeout() {
+                GeminiError::Timeout
+            } else {
+                GeminiError::RequestFailed(e.to_string())
+            }
+        })?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let message = response
+            .json::<GeminiErrorResponse>()
+            .await
+            .map(|body| body.error.message)
+            .unwrap_or_else(|_| format!("HTTP {}", status));
+
+        // An invalid key is reported as a bad request rather than 401
+        Err(match status.as_u16() {
+            400 if message.contains("API key") => GeminiError::AuthenticationFailed,
+            401 | 403 => GeminiError::AuthenticationFailed,
+            404 => GeminiError::ModelNotFound(model.to_string()),
+            429 => GeminiError::RateLimitExceeded,
+            _ => GeminiError::RequestFailed(message),
+        }
+        .into())
+    }
+
+    /// Send a request with `generateContent` and return the reply text
+    async fn generate(
+        &self,
+        messages: Vec<(String, String)>,
+        images: &[ImageAttachment],
+        overrides: &GenerationOverrides,
+    ) -> AppResult<LlmReply> {
+        let model = overrides.model.as_deref().unwrap_or(&self.config.model);
+        let request_body = self.build_request(messages, images, overrides)?;
+
+        tracing::info!(
+            "Sending {} messages ({} images) to Gemini model '{}'",
+            request_body.contents.len(),
+            images.len(),
+            model
+        );
+
+        let text = self
+            .send(self.client.post(self.method_url(model, "generateContent")).json(&request_body), model)
+            .await?
+            .json::<GenerateContentResponse>()
+            .await
+            .map_err(|e| GeminiError::InvalidResponse(e.to_string()))?
+            .into_text()?;
+
+        if text.is_empty() {
+            return Err(GeminiError::InvalidResponse("Empty response".to_string()).into());
+        }
+        Ok(LlmReply::parse(&text, None))
+    }
+}
+
+#[async_trait]
+impl LlmProvider for GeminiClient {
+    async fn send_message_with(
+        &self,
+        messages: Vec<(String, String)>,
+        overrides: &GenerationOverrides,
+    ) -> AppResult<LlmReply> {
+        self.generate(messages, &[], overrides).await
+    }
+
+    async fn send_message_with_images(
+        &self,
+        messages: Vec<(String, String)>,
+        images: &[ImageAttachment],
+        overrides: &GenerationOverrides,
+    ) -> AppResult<LlmReply> {
+        self.generate(messages, images, overrides).await
+    }
+
+    async fn stream_message_with(
+        &self,
+        messages: Vec<(String, String)>,
+        overrides: &GenerationOverrides,
+        on_delta: &mut (dyn for<'d> FnMut(&'d str) + Send),
+    ) -> AppResult<LlmReply> {
+        let model = overrides.model.as_deref().unwrap_or(&self.config.model);
+        let request_body = self.build_request(messages, &[], overrides)?;
+        let url = format!("{}?alt=sse", self.method_url(model, "streamGenerateContent"));
+
+        tracing::info!("Streaming {} messages from Gemini model '{}'", request_body.contents.len(), model);
+
+        let response = self.send(self.client.post(url).json(&request_body), model).await?;
+
+        let mut reply = String::new();
+        let map_err = |e: reqwest::Error| {
+            if e.is_timeout() {
+                GeminiError::Timeout.into()
+            } else {
+                GeminiError::RequestFailed(e.to_string()).into()
+            }
+        };
+        sse::read_events(response, map_err, |data| {
+            if let Some(text) = parse_stream_event(data)? {
+                on_delta(&text);
+                reply.push_str(&text);
+            }
+            Ok(true)
+        })
+        .await?;
+
+        if reply.is_empty() {
+            return Err(GeminiError::InvalidResponse("Empty response".to_string()).into());
+        }
+        Ok(LlmReply::parse(&reply, None))
+    }
+
+    async fn list_models(&self) -> AppResult<Vec<ModelInfo>> {
+        let url = format!("{}/models?pageSize=1000", self.config.endpoint.trim_end_matches('/'));
+
+        let response = self
+            .send(self.client.get(url), &self.config.model)
+            .await?
+            .json::<GeminiModelsResponse>()
+            .await
+            .map_err(|e| GeminiError::InvalidResponse(e.to_string()))?;
+
+        // Embedding and other non-chat models cannot be selected
+        Ok(response
+            .models
+            .into_iter()
+            .filter(|model| model.supported_generation_methods.iter().any(|m| m == "generateContent"))
+            .map(|model| ModelInfo {
+                id: model.name.trim_start_matches("models/").to_string(),
+                name: model.display_name,
+                context_length: model.input_token_limit,
+                ..ModelInfo::default()
+            })
+            .collect())
+    }
+
+    async fn check_connectivity(&self) -> AppResult<bool> {
+        let url = format!("{}/models?pageSize=1", self.config.endpoint.trim_end_matches('/'));
+
+        let mut request = self.client.get(url).timeout(Duration::from_secs(5));
+        if let Some(api_key) = &self.api_key {
+            request = request.header("x-goog-api-key", api_key);
+        }
+
+        match http::send("gemini", request).await {
+            Ok(resp) => match resp.status().as_u16() {
+                400 | 401 | 403 => Err(GeminiError::AuthenticationFailed.into()),
+                status => Ok(status < 500),
+            },
+            Err(e) => {
+                tracing::warn!("Gemini connectivity check failed: {}", e);
+                Ok(false)
+            }
+        }
+    }
+}
+
+/// Convert (role, content) messages to Gemini turns
+///
+/// System messages join `system_prompt` as the system instruction, assistant turns
+/// become `model` turns, and images are attached to the latest user turn.
+fn build_contents(
+    messages: Vec<(String, String)>,
+    images: &[ImageAttachment],
+    system_prompt: Option<String>,
+) -> (Vec<Content>, Option<Content>) {
+    let mut system: Vec<String> = system_prompt.into_iter().collect();
+    let mut contents = Vec::new();
+
+    for (role, content) in messages {
+        let role = match role.as_str() {
+            "system" => {
+                system.push(content);
+                continue;
+            }
+            "assistant" => "model",
+            _ => "user",
+        };
+        contents.push(Content {
+            role: Some(role.to_string()),
+            parts: vec![Part {
+                text: Some(content),
+                ..Part::default()
+            }],
+        });
+    }
+
+    if !images.is_empty() {
+        let image_parts = images.iter().map(|image| Part {
+            inline_data: Some(InlineData {
+                mime_type: image.mime_type.clone(),
+                data: image.data.clone(),
+            }),
+            ..Part::default()
+        });
+
+        match contents.iter_mut().rev().find(|c| c.role.as_deref() == Some("user")) {
+            Some(turn) => turn.parts.extend(image_parts),
+            None => contents.push(Content {
+                role: Some("user".to_string()),
+                parts: image_parts.collect(),
+            }),
+        }
+    }
+
+    let system_instruction = (!system.is_empty()).then(|| Content {
+        role: None,
+        parts: vec![Part {
+            text: Some(system.join("\n\n")),
+            ..Part::default()
+        }],
+    });
+
+    (contents, system_instruction)
+}
+
+/// Text carried by one streamed event, if any
+fn parse_stream_event(data: &str) -> AppResult<Option<String>> {
+    let text = serde_json::from_str::<GenerateContentResponse>(data)
+        .map_err(|e| GeminiError::InvalidResponse(e.to_string()))?
+        .into_text()?;
+
+    Ok((!text.is_empty()).then_some(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contents_map_roles_and_attach_images() {
+        let messages = vec![
+            ("system".to_string(), "Be brief.".to_string()),
+            ("user".to_string(), "Hi".to_string()),
+            ("assistant".to_string(), "Hello!".to_string()),
+            ("user".to_string(), "What is this?".to_string()),
+        ];
+        let images = vec![ImageAttachment {
+            mime_type: "image/png".to_string(),
+            data: "iVBORw0KGgo=".to_string(),
+        }];
+
+        let (contents, system) = build_contents(messages, &images, Some("You are CMAC.".to_string()));
+
+        let roles: Vec<_> = contents.iter().map(|c| c.role.as_deref().unwrap()).collect();
+        assert_eq!(roles, vec!["user", "model", "user"]);
+        assert_eq!(contents[2].parts.len(), 2);
+        assert_eq!(contents[2].parts[1].inline_data.as_ref().unwrap().mime_type, "image/png");
+        assert_eq!(
+            system.unwrap().parts[0].text.as_deref(),
+            Some("You are CMAC.\n\nBe brief.")
+        );
+    }
+
+    #[test]
+    fn test_stream_events_yield_text() {
+        let line = r#"{"candidates": [{"content": {"role": "model", "parts": [{"text": "Hel"}, {"text": "lo"}]}}]}"#;
+        assert_eq!(parse_stream_event(line).unwrap().as_deref(), Some("Hello"));
+
+        let done = r#"{"candidates": [{"content": {"parts": []}, "finishReason": "STOP"}]}"#;
+        assert_eq!(parse_stream_event(done).unwrap(), None);
+    }
+
+    #[test]
+    fn test_blocked_responses_are_errors() {
+        let blocked = r#"{"promptFeedback": {"blockReason": "SAFETY"}}"#;
+        assert!(parse_stream_event(blocked).unwrap_err().to_string().contains("SAFETY"));
+
+        let withheld = r#"{"candidates": [{"finishReason": "PROHIBITED_CONTENT"}]}"#;
+        assert!(parse_stream_event(withheld).is_err());
+    }
+}
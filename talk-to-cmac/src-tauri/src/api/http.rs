@@ -0,0 +1,52 @@
+//! Shared HTTP clients
+//!
+//! Every API client draws its `reqwest::Client` from here. Clients are cached by
+//! timeout and share their connection pools, so a connection opened by one
+//! request (or by the startup warm-up) is reused by the next one instead of
+//! paying for DNS, TCP, and TLS setup again.
+//!
+//! Requests are sent with `send`, which records each call in the audit log.
+
+use crate::audit::{self, AuditEntry};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// How long an idle connection is kept open for reuse
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// HTTP client with the given request timeout, shared with every caller that asks for the same timeout
+pub fn client(timeout: Duration) -> reqwest::Result<reqwest::Client> {
+    static CLIENTS: OnceLock<Mutex<HashMap<Duration, reqwest::Client>>> = OnceLock::new();
+
+    let mut clients = CLIENTS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+
+    if let Some(client) = clients.get(&timeout) {
+        return Ok(client.clone());
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(timeout)
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .tcp_keepalive(Duration::from_secs(60))
+        .build()?;
+    clients.insert(timeout, client.clone());
+    Ok(client)
+}
+
+/// Send a request to `service`, recording the call in the audit log
+pub async fn send(service: &str, request: reqwest::RequestBuilder) -> reqwest::Result<reqwest::Response> {
+    let (client, request) = request.build_split();
+    let request = request?;
+
+    let mut entry = AuditEntry::new(service, &request);
+    let started = Instant::now();
+    let result = client.execute(request).await;
+    entry.finish(&result, started.elapsed());
+    audit::record(&entry);
+
+    result
+}
@@ -0,0 +1,454 @@
+//! IMAP client
+//!
+//! Reads the newest unread messages over IMAP with implicit TLS. Access is
+//! read-only by construction: the mailbox is opened with `EXAMINE` and messages
+//! are fetched with `BODY.PEEK`, so no flag (not even `\Seen`) changes on the
+//! server. Only the headers shown and the start of each text are downloaded.
+//!
+//! The username, password, and mailbox are sent as quoted strings, or as
+//! literals when they are not ASCII. Line breaks and NUL are refused in them,
+//! since they could end the command early and start another.
+
+use crate::audit::{self, AuditEntry};
+use crate::config::EmailConfig;
+use crate::email::{self, EmailSummary};
+use crate::error::{AppResult, EmailError};
+use regex::Regex;
+use std::cmp::Reverse;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::{self, pki_types::ServerName, ClientConfig, RootCertStore};
+use tokio_rustls::TlsConnector;
+
+/// Header fields fetched for each message
+const HEADER_FIELDS: &str = "FROM SUBJECT DATE CONTENT-TYPE CONTENT-TRANSFER-ENCODING";
+
+/// Bytes of each message's body fetched for its snippet
+const BODY_BYTES: usize = 4096;
+
+/// Largest literal accepted from the server
+const MAX_LITERAL_BYTES: usize = 1 << 20;
+
+/// IMAP client for the configured mailbox
+pub struct ImapClient {
+    config: EmailConfig,
+    password: String,
+}
+
+impl ImapClient {
+    /// Create a client logging in with `password`
+    pub fn new(config: EmailConfig, password: String) -> Self {
+        Self { config, password }
+    }
+
+    /// The newest unread messages, newest first, at most `max_messages`
+    pub async fn unread(&self) -> AppResult<Vec<EmailSummary>> {
+        let host = self.config.host.trim();
+        if host.is_empty() {
+            return Err(EmailError::NotConfigured("IMAP host").into());
+        }
+        if self.config.username.is_empty() {
+            return Err(EmailError::NotConfigured("IMAP username").into());
+        }
+
+        let started = Instant::now();
+        let session = async {
+            let stream = connect(host, self.config.port).await?;
+            read_unread(stream, &self.config, &self.password).await
+        };
+        let result = tokio::time::timeout(Duration::from_secs(self.config.timeout_secs), session)
+            .await
+            .unwrap_or(Err(EmailError::Timeout));
+
+        let error = match &result {
+            Ok(_) => None,
+            Err(EmailError::Timeout) => Some("timeout"),
+            Err(EmailError::ConnectionFailed(_)) => Some("connect"),
+            Err(_) => Some("request"),
+        };
+        let endpoint = format!("imaps://{}:{}/{}", host, self.config.port, self.config.mailbox);
+        audit::record(&AuditEntry::session("email", "IMAP", endpoint, started.elapsed(), error));
+
+        Ok(result?)
+    }
+}
+
+/// Open a TLS connection to `host`, verified against the bundled web PKI roots
+async fn connect(host: &str, port: u16) -> Result<tokio_rustls::client::TlsStream<TcpStream>, EmailError> {
+    let tcp = TcpStream::connect((host, port))
+        .await
+        .map_err(|e| EmailError::ConnectionFailed(e.to_string()))?;
+
+    let roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let config = ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| EmailError::ConnectionFailed(e.to_string()))?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let name = ServerName::try_from(host.to_string()).map_err(|e| EmailError::ConnectionFailed(e.to_string()))?;
+
+    TlsConnector::from(Arc::new(config))
+        .connect(name, tcp)
+        .await
+        .map_err(|e| EmailError::ConnectionFailed(e.to_string()))
+}
+
+/// Log in, read the newest unread messages of the mailbox, and log out
+async fn read_unread<S>(stream: S, config: &EmailConfig, password: &str) -> Result<Vec<EmailSummary>, EmailError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let username = ImapString::new("username", &config.username)?;
+    let password = ImapString::new("password", password)?;
+    let mailbox = ImapString::new("mailbox", &config.mailbox)?;
+
+    let mut session = Session::new(stream);
+    session.greeting().await?;
+    session
+        .command_with("LOGIN", &[username, password])
+        .await
+        .map_err(|e| match e {
+            EmailError::Rejected(_) => EmailError::AuthenticationFailed,
+            e => e,
+        })?;
+    session.command_with("EXAMINE", &[mailbox]).await?;
+
+    let mut uids: Vec<u32> = session
+        .command("UID SEARCH UNSEEN")
+        .await?
+        .iter()
+        .filter_map(|response| response.text[0].strip_prefix("* SEARCH"))
+        .flat_map(|ids| ids.split_whitespace().filter_map(|id| id.parse().ok()))
+        .collect();
+    uids.sort_unstable();
+    let newest = &uids[uids.len().saturating_sub(config.max_messages)..];
+
+    let mut messages = Vec::new();
+    if !newest.is_empty() {
+        let set = newest.iter().map(u32::to_string).collect::<Vec<_>>().join(",");
+        let fetched = session
+            .command(&format!(
+                "UID FETCH {} (UID BODY.PEEK[HEADER.FIELDS ({})] BODY.PEEK[TEXT]<0.{}>)",
+                set, HEADER_FIELDS, BODY_BYTES
+            ))
+            .await?;
+        messages = fetched
+            .iter()
+            .filter_map(|response| parse_fetch(response, config.snippet_chars))
+            .collect();
+        messages.sort_by_key(|(uid, _)| Reverse(*uid));
+    }
+
+    // The messages are already read, so a failed logout doesn't matter
+    if let Err(e) = session.command("LOGOUT").await {
+        tracing::debug!("IMAP logout failed: {}", e);
+    }
+    Ok(messages.into_iter().map(|(_, message)| message).collect())
+}
+
+/// A server response, with its text split around the literals it carries
+///
+/// `text[i]` is the text before `literals[i]`; the last entry of `text` ends the response.
+#[derive(Debug)]
+struct Response {
+    text: Vec<String>,
+    literals: Vec<Vec<u8>>,
+}
+
+/// An IMAP connection, sending tagged commands one at a time
+struct Session<S> {
+    stream: BufReader<S>,
+    next_tag: u32,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Session<S> {
+    fn new(stream: S) -> Self {
+        Self {
+            stream: BufReader::new(stream),
+            next_tag: 0,
+        }
+    }
+
+    async fn greeting(&mut self) -> Result<(), EmailError> {
+        let greeting = self.read_response().await?;
+        let text = &greeting.text[0];
+        if text.starts_with("* OK") || text.starts_with("* PREAUTH") {
+            Ok(())
+        } else {
+            Err(EmailError::Rejected(text.clone()))
+        }
+    }
+
+    /// Send `command`, returning the untagged responses before its completion
+    async fn command(&mut self, command: &str) -> Result<Vec<Response>, EmailError> {
+        self.command_with(command, &[]).await
+    }
+
+    /// Send `command` followed by `args`, returning the untagged responses before its completion
+    ///
+    /// Before each literal the server is asked to continue; if it refuses, the command ends there.
+    async fn command_with(&mut self, command: &str, args: &[ImapString]) -> Result<Vec<Response>, EmailError> {
+        self.next_tag += 1;
+        let tag = format!("a{}", self.next_tag);
+        let completion = format!("{} ", tag);
+
+        let mut line = format!("{} {}", tag, command).into_bytes();
+        for arg in args {
+            match arg {
+                ImapString::Quoted(quoted) => {
+                    line.push(b' ');
+                    line.extend_from_slice(quoted.as_bytes());
+                }
+                ImapString::Literal(bytes) => {
+                    line.extend_from_slice(format!(" {{{}}}\r\n", bytes.len()).as_bytes());
+                    self.send(&line).await?;
+                    let reply = self.read_response().await?;
+                    if let Some(status) = reply.text[0].strip_prefix(completion.as_str()) {
+                        return Err(EmailError::Rejected(status.to_string()));
+                    }
+                    if !reply.text[0].starts_with('+') {
+                        return Err(EmailError::InvalidResponse(reply.text[0].clone()));
+                    }
+                    line = bytes.clone();
+                }
+            }
+        }
+        line.extend_from_slice(b"\r\n");
+        self.send(&line).await?;
+
+        let mut responses = Vec::new();
+        loop {
+            let response = self.read_response().await?;
+            let Some(status) = response.text[0].strip_prefix(completion.as_str()) else {
+                responses.push(response);
+                continue;
+            };
+            return if status.starts_with("OK") {
+                Ok(responses)
+            } else {
+                Err(EmailError::Rejected(status.to_string()))
+            };
+        }
+    }
+
+    async fn send(&mut self, bytes: &[u8]) -> Result<(), EmailError> {
+        let stream = self.stream.get_mut();
+        stream
+            .write_all(bytes)
+            .await
+            .map_err(|e| EmailError::ConnectionFailed(e.to_string()))?;
+        stream.flush().await.map_err(|e| EmailError::ConnectionFailed(e.to_string()))
+    }
+
+    async fn read_response(&mut self) -> Result<Response, EmailError> {
+        let mut response = Response {
+            text: Vec::new(),
+            literals: Vec::new(),
+        };
+        loop {
+            let mut line = Vec::new();
+            let read = self
+                .stream
+                .read_until(b'\n', &mut line)
+                .await
+                .map_err(|e| EmailError::InvalidResponse(e.to_string()))?;
+            if read == 0 {
+                return Err(EmailError::InvalidResponse("connection closed".to_string()));
+            }
+            let line = String::from_utf8_lossy(&line).trim_end_matches(['\r', '\n']).to_string();
+
+            let Some(size) = literal_size(&line) else {
+                response.text.push(line);
+                return Ok(response);
+            };
+            if size > MAX_LITERAL_BYTES {
+                return Err(EmailError::InvalidResponse(format!("{} byte literal", size)));
+            }
+            let mut literal = vec![0; size];
+            self.stream
+                .read_exact(&mut literal)
+                .await
+                .map_err(|e| EmailError::InvalidResponse(e.to_string()))?;
+            response.text.push(line);
+            response.literals.push(literal);
+        }
+    }
+}
+
+/// Size of the literal announced at the end of `line` (`{123}`)
+fn literal_size(line: &str) -> Option<usize> {
+    let start = line.strip_suffix('}')?.rfind('{')?;
+    line[start + 1..line.len() - 1].parse().ok()
+}
+
+/// A string argument of a command
+#[derive(Debug, PartialEq)]
+enum ImapString {
+    /// Quoted string, quotes included
+    Quoted(String),
+    /// Bytes sent after the server agrees to take them
+    Literal(Vec<u8>),
+}
+
+impl ImapString {
+    /// `text` as a quoted string if it is ASCII, else as a literal; `name` labels the error
+    fn new(name: &'static str, text: &str) -> Result<Self, EmailError> {
+        if text.contains(['\r', '\n', '\0']) {
+            return Err(EmailError::InvalidText(name));
+        }
+        Ok(if text.is_ascii() {
+            Self::Quoted(quote(text))
+        } else {
+            Self::Literal(text.as_bytes().to_vec())
+        })
+    }
+}
+
+/// `text` as an IMAP quoted string
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// UID and summary of a `FETCH` response
+fn parse_fetch(response: &Response, snippet_chars: usize) -> Option<(u32, EmailSummary)> {
+    static UID: OnceLock<Regex> = OnceLock::new();
+    let uid_pattern = UID.get_or_init(|| Regex::new(r"\bUID (\d+)").expect("UID pattern is valid"));
+
+    if !response.text[0].starts_with("* ") || !response.text[0].contains(" FETCH ") {
+        return None;
+    }
+    let uid = response
+        .text
+        .iter()
+        .find_map(|text| uid_pattern.captures(text))
+        .and_then(|caps| caps[1].parse().ok())?;
+
+    let (mut headers, mut body): (&[u8], &[u8]) = (&[], &[]);
+    for (before, literal) in response.text.iter().zip(&response.literals) {
+        let before = before.to_ascii_uppercase();
+        if before.contains("HEADER.FIELDS") {
+            headers = literal;
+        } else if before.contains("BODY[TEXT]") {
+            body = literal;
+        }
+    }
+
+    Some((uid, email::summarize(headers, body, snippet_chars)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::DuplexStream;
+
+    /// Answer a session like a server with two unread messages, returning the commands received
+    async fn serve(stream: DuplexStream) -> Vec<String> {
+        let (read, mut write) = tokio::io::split(stream);
+        let mut lines = BufReader::new(read).lines();
+        write.write_all(b"* OK IMAP4rev1 ready\r\n").await.unwrap();
+
+        let headers = "From: =?UTF-8?Q?Jos=C3=A9_Garc=C3=ADa?= <jose@example.com>\r\n\
+            Subject: Compressor parts\r\n\r\n";
+        let body = "The parts ship Monday.\r\n";
+        let fetch = format!(
+            "* 1 FETCH (UID 7 BODY[HEADER.FIELDS (FROM SUBJECT)] {{{}}}\r\n{} BODY[TEXT]<0> {{{}}}\r\n{})\r\n\
+             * 2 FETCH (UID 9 BODY[HEADER.FIELDS (FROM SUBJECT)] {{18}}\r\nSubject: Invoice\r\n BODY[TEXT]<0> {{0}}\r\n)\r\n",
+            headers.len(),
+            headers,
+            body.len(),
+            body
+        );
+
+        let mut commands = Vec::new();
+        while let Some(line) = lines.next_line().await.unwrap() {
+            let (tag, command) = line.split_once(' ').unwrap();
+            let untagged = match command {
+                c if c.starts_with("UID SEARCH") => "* SEARCH 9 7\r\n".to_string(),
+                c if c.starts_with("UID FETCH") => fetch.clone(),
+                "LOGOUT" => "* BYE\r\n".to_string(),
+                _ => String::new(),
+            };
+            let status = if command.starts_with("LOGIN") && !command.ends_with("\"secret\"") { "NO" } else { "OK" };
+            write.write_all(format!("{}{} {} done\r\n", untagged, tag, status).as_bytes()).await.unwrap();
+            commands.push(command.to_string());
+            if command == "LOGOUT" || status == "NO" {
+                break;
+            }
+        }
+        commands
+    }
+
+    fn config() -> EmailConfig {
+        EmailConfig {
+            username: "tech@example.com".to_string(),
+            ..EmailConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unread_messages_are_read_without_changing_flags() {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let server = tokio::spawn(serve(server));
+
+        let messages = read_unread(client, &config(), "secret").await.unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].subject, "Invoice");
+        assert_eq!(messages[1].from, "José García");
+        assert_eq!(messages[1].subject, "Compressor parts");
+        assert_eq!(messages[1].snippet, "The parts ship Monday.");
+
+        let commands = server.await.unwrap();
+        assert_eq!(commands[1], "EXAMINE \"INBOX\"");
+        assert!(commands[3].starts_with("UID FETCH 7,9 (UID BODY.PEEK[HEADER.FIELDS"));
+        assert!(!commands.iter().any(|c| c.starts_with("SELECT") || c.starts_with("STORE")));
+    }
+
+    #[tokio::test]
+    async fn test_refused_login_is_an_authentication_failure() {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        tokio::spawn(serve(server));
+
+        let result = read_unread(client, &config(), "wrong").await;
+        assert!(matches!(result, Err(EmailError::AuthenticationFailed)));
+    }
+
+    #[test]
+    fn test_literal_size_and_quote() {
+        assert_eq!(literal_size("* 1 FETCH (BODY[TEXT] {42}"), Some(42));
+        assert_eq!(literal_size("* OK {not a literal"), None);
+        assert_eq!(quote(r#"pa"ss\word"#), r#""pa\"ss\\word""#);
+    }
+
+    #[test]
+    fn test_line_breaks_are_refused_and_non_ascii_is_a_literal() {
+        assert!(matches!(ImapString::new("password", "x\r\na2 DELETE INBOX"), Err(EmailError::InvalidText("password"))));
+        assert!(matches!(ImapString::new("mailbox", "INBOX\0"), Err(EmailError::InvalidText("mailbox"))));
+        assert_eq!(ImapString::new("username", "tech").unwrap(), ImapString::Quoted("\"tech\"".to_string()));
+        assert_eq!(ImapString::new("password", "sécret").unwrap(), ImapString::Literal("sécret".as_bytes().to_vec()));
+    }
+
+    #[tokio::test]
+    async fn test_literal_waits_for_continuation() {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let server = tokio::spawn(async move {
+            let (read, mut write) = tokio::io::split(server);
+            let mut lines = BufReader::new(read).lines();
+            write.write_all(b"* OK ready\r\n").await.unwrap();
+            let login = lines.next_line().await.unwrap().unwrap();
+            write.write_all(b"+ go ahead\r\n").await.unwrap();
+            let literal = lines.next_line().await.unwrap().unwrap();
+            write.write_all(b"a1 NO wrong password\r\n").await.unwrap();
+            (login, literal)
+        });
+
+        let result = read_unread(client, &config(), "sécret").await;
+        assert!(matches!(result, Err(EmailError::AuthenticationFailed)));
+        let (login, literal) = server.await.unwrap();
+        assert_eq!(login, "a1 LOGIN \"tech@example.com\" {7}");
+        assert_eq!(literal, "sécret");
+    }
+}
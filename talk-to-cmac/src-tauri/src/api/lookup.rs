@@ -0,0 +1,111 @@
+//! REST lookup client
+//!
+//! Runs a configured search endpoint (a CRM, an asset register, ...) and picks
+//! the results out of its JSON response.
+
+use super::http;
+use crate::config::LookupConfig;
+use crate::error::{AppResult, ConfigError, NetworkError};
+use serde_json::Value;
+use std::time::Duration;
+
+/// Client for one configured lookup
+pub struct LookupClient {
+    client: reqwest::Client,
+    config: LookupConfig,
+    token: Option<String>,
+}
+
+impl LookupClient {
+    /// Create a client for `config`, authenticating with `token` if given
+    pub fn new(config: LookupConfig, token: Option<String>) -> AppResult<Self> {
+        let client = http::client(Duration::from_secs(config.timeout_secs)).map_err(NetworkError::from)?;
+        Ok(Self { client, config, token })
+    }
+
+    /// Search for `query`, returning the results the response holds at `results_pointer`
+    ///
+    /// A list of results is cut to `max_results`.
+    pub async fn search(&self, query: &str) -> AppResult<Value> {
+        if self.config.url.is_empty() {
+            return Err(ConfigError::MissingConfig(format!("URL of lookup '{}'", self.config.name)).into());
+        }
+
+        let url = self.config.url.replace("{query}", &encode_component(query));
+        let mut request = self.client.get(url).header("Accept", "application/json");
+        if let Some(token) = &self.token {
+            request = request.header(&self.config.auth_header, format!("{}{}", self.config.auth_prefix, token));
+        }
+
+        let response = http::send("lookup", request).await.map_err(NetworkError::from)?;
+        if !response.status().is_success() {
+            return Err(NetworkError::RequestFailed(format!("HTTP {}", response.status())).into());
+        }
+
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| NetworkError::RequestFailed(e.to_string()))?;
+
+        let mut results = body
+            .pointer(&self.config.results_pointer)
+            .cloned()
+            .ok_or_else(|| ConfigError::InvalidValue(format!("No results at '{}'", self.config.results_pointer)))?;
+        if let Value::Array(items) = &mut results {
+            items.truncate(self.config.max_results);
+        }
+        Ok(results)
+    }
+}
+
+/// Percent-encode everything but unreserved characters (RFC 3986)
+pub(crate) fn encode_component(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use serde_json::json;
+    use wiremock::matchers::query_param;
+
+    #[tokio::test]
+    async fn test_results_are_picked_and_capped() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/accounts"))
+            .and(query_param("q", "johnson & sons"))
+            .and(header("x-api-key", "secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": { "items": [{ "name": "Johnson" }, { "name": "Johnson & Sons" }, { "name": "Johnsonville" }] }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config = LookupConfig {
+            name: "CRM".to_string(),
+            url: format!("{}/api/accounts?q={{query}}", server.uri()),
+            auth_header: "X-Api-Key".to_string(),
+            auth_prefix: String::new(),
+            results_pointer: "/data/items".to_string(),
+            max_results: 2,
+            ..LookupConfig::default()
+        };
+        let client = LookupClient::new(config, Some("secret".to_string())).unwrap();
+
+        let results = client.search("johnson & sons").await.unwrap();
+        assert_eq!(results, json!([{ "name": "Johnson" }, { "name": "Johnson & Sons" }]));
+    }
+
+    #[test]
+    fn test_encode_component() {
+        assert_eq!(encode_component("Johnson & Sons/ü"), "Johnson%20%26%20Sons%2F%C3%BC");
+    }
+}
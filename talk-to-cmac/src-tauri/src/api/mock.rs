@@ -0,0 +1,222 @@
+//! Mock provider
+//!
+//! Stands in for every pipeline stage without touching the network, so the
+//! frontend can be developed and demoed offline without using API quota.
+//! Transcriptions and replies come from the `mock` settings, and speech is
+//! silent MP3 audio lasting roughly as long as the text would take to say.
+
+use super::elevenlabs::Voice;
+use super::openwebui::{GenerationOverrides, LlmReply, ModelInfo};
+use super::provider::{LlmProvider, SttProvider, TtsProvider};
+use crate::config::MockConfig;
+use crate::error::AppResult;
+use async_trait::async_trait;
+use std::time::Duration;
+
+/// Replies used when none are configured
+const BUILT_IN_RESPONSES: &[&str] = &[
+    "This is the mock assistant. It's sunny and 22 degrees, with a light breeze from the west.",
+    "Sure. The mock provider answers without contacting any service, so nothing here is real.",
+    "Good question! In mock mode every reply is canned, but the rest of the app works as usual.",
+];
+
+/// Model reported by the mock LLM
+const MOCK_MODEL: &str = "mock";
+
+/// One silent MPEG-1 Layer III frame (32 kbps, 44.1 kHz, mono)
+///
+/// With all-zero side information the frame decodes to 1152 samples of silence.
+const SILENT_FRAME_HEADER: [u8; 4] = [0xFF, 0xFB, 0x10, 0xC4];
+
+/// Size of one frame in bytes (144 * bitrate / sample rate)
+const SILENT_FRAME_LEN: usize = 104;
+
+/// Playback time of one frame
+const FRAME_MS: u64 = 1152 * 1000 / 44_100;
+
+/// Approximate speaking time per character of text
+const SPEECH_MS_PER_CHAR: u64 = 60;
+
+/// Longest silence generated for one request
+const MAX_SPEECH_MS: u64 = 30_000;
+
+/// Dimensions of mock embeddings
+const EMBEDDING_DIMENSIONS: usize = 64;
+
+/// Offline provider for every pipeline stage
+pub struct MockClient {
+    config: MockConfig,
+}
+
+impl MockClient {
+    /// Create a new mock client
+    pub fn new(config: MockConfig) -> Self {
+        Self { config }
+    }
+
+    /// Voices offered while text-to-speech is mocked
+    pub fn voices() -> Vec<Voice> {
+        ["Mock Voice", "Mock Voice (Spanish)"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| Voice {
+                voice_id: format!("mock-{}", i + 1),
+                name: name.to_string(),
+                category: Some("mock".to_string()),
+                labels: None,
+            })
+            .collect()
+    }
+
+    /// Wait for the configured latency
+    async fn delay(&self) {
+        if self.config.latency_ms > 0 {
+            tokio::time::sleep(Duration::from_millis(self.config.latency_ms)).await;
+        }
+    }
+
+    /// Reply for a conversation, taking the configured replies in turn
+    fn reply(&self, messages: &[(String, String)]) -> String {
+        let turn = messages.iter().filter(|(role, _)| role == "user").count().saturating_sub(1);
+
+        if self.config.responses.is_empty() {
+            BUILT_IN_RESPONSES[turn % BUILT_IN_RESPONSES.len()].to_string()
+        } else {
+            self.config.responses[turn % self.config.responses.len()].clone()
+        }
+    }
+}
+
+#[async_trait]
+impl SttProvider for MockClient {
+    async fn transcribe_audio(&self, audio_data: Vec<u8>, _filename: &str) -> AppResult<String> {
+        tracing::info!("Mock transcription of {} bytes", audio_data.len());
+        self.delay().await;
+        Ok(self.config.transcription.clone())
+    }
+
+    async fn check_connectivity(&self) -> AppResult<bool> {
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl LlmProvider for MockClient {
+    async fn send_message_with(
+        &self,
+        messages: Vec<(String, String)>,
+        _overrides: &GenerationOverrides,
+    ) -> AppResult<LlmReply> {
+        self.delay().await;
+        Ok(LlmReply::parse(&self.reply(&messages), None))
+    }
+
+    async fn stream_message_with(
+        &self,
+        messages: Vec<(String, String)>,
+        _overrides: &GenerationOverrides,
+        on_delta: &mut (dyn for<'d> FnMut(&'d str) + Send),
+    ) -> AppResult<LlmReply> {
+        self.delay().await;
+        let reply = self.reply(&messages);
+
+        // Deliver a word at a time so streaming paths behave as they do with a real model
+        for word in reply.split_inclusive(' ') {
+            on_delta(word);
+            tokio::time::sleep(Duration::from_millis(30)).await;
+        }
+        Ok(LlmReply::parse(&reply, None))
+    }
+
+    async fn list_models(&self) -> AppResult<Vec<ModelInfo>> {
+        Ok(vec![ModelInfo {
+            id: MOCK_MODEL.to_string(),
+            name: Some("Mock model".to_string()),
+            ..Default::default()
+        }])
+    }
+
+    async fn check_connectivity(&self) -> AppResult<bool> {
+        Ok(true)
+    }
+
+    async fn embed(&self, texts: &[String], _model: &str) -> AppResult<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|text| word_embedding(text)).collect())
+    }
+}
+
+#[async_trait]
+impl TtsProvider for MockClient {
+    async fn synthesize_speech(&self, text: &str) -> AppResult<Vec<u8>> {
+        self.delay().await;
+        Ok(silent_mp3(text.chars().count() as u64 * SPEECH_MS_PER_CHAR))
+    }
+
+    async fn check_connectivity(&self) -> AppResult<bool> {
+        Ok(true)
+    }
+}
+
+/// Bag-of-words embedding, so texts that share words come out similar
+fn word_embedding(text: &str) -> Vec<f32> {
+    let mut embedding = vec![0.0; EMBEDDING_DIMENSIONS];
+    for word in text.split(|c: char| !c.is_alphanumeric()).filter(|word| word.chars().count() > 2) {
+        // FNV-1a, which unlike the standard hasher is the same on every run
+        let hash = word
+            .to_lowercase()
+            .bytes()
+            .fold(0x811c_9dc5u32, |hash, byte| (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193));
+        embedding[hash as usize % EMBEDDING_DIMENSIONS] += 1.0;
+    }
+    embedding
+}
+
+/// Silent MP3 audio lasting about `duration_ms` (at least one frame)
+pub(crate) fn silent_mp3(duration_ms: u64) -> Vec<u8> {
+    let frames = duration_ms.min(MAX_SPEECH_MS).div_ceil(FRAME_MS).max(1) as usize;
+
+    let mut frame = [0u8; SILENT_FRAME_LEN];
+    frame[..SILENT_FRAME_HEADER.len()].copy_from_slice(&SILENT_FRAME_HEADER);
+    frame.repeat(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conversation(user_turns: usize) -> Vec<(String, String)> {
+        (0..user_turns)
+            .flat_map(|i| {
+                [
+                    ("user".to_string(), format!("Question {}", i)),
+                    ("assistant".to_string(), format!("Answer {}", i)),
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_configured_replies_are_used_in_turn() {
+        let client = MockClient::new(MockConfig {
+            responses: vec!["First.".to_string(), "Second.".to_string()],
+            ..MockConfig::default()
+        });
+
+        assert_eq!(client.reply(&conversation(1)), "First.");
+        assert_eq!(client.reply(&conversation(2)), "Second.");
+        assert_eq!(client.reply(&conversation(3)), "First.");
+        assert_eq!(MockClient::new(MockConfig::default()).reply(&[]), BUILT_IN_RESPONSES[0]);
+    }
+
+    #[test]
+    fn test_silence_length_follows_text() {
+        let short = silent_mp3(0);
+        assert_eq!(short.len(), SILENT_FRAME_LEN);
+        assert_eq!(short[..4], SILENT_FRAME_HEADER);
+
+        let long = silent_mp3(1000);
+        assert_eq!(long.len() % SILENT_FRAME_LEN, 0);
+        assert_eq!(long.len() / SILENT_FRAME_LEN, 1000usize.div_ceil(FRAME_MS as usize));
+        assert_eq!(silent_mp3(u64::MAX).len(), silent_mp3(MAX_SPEECH_MS).len());
+    }
+}
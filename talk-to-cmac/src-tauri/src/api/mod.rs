@@ -4,12 +4,57 @@
 //! - Whisper: Speech-to-text transcription
 //! - OpenWebUI: LLM interaction
 //! - ElevenLabs: Text-to-speech synthesis
+//! - Moderation: Input classification before it reaches the LLM
+//! - Azure OpenAI: Hosted alternative for transcription, chat, and speech
+//! - OpenRouter: Chat through many hosted models, with a priced model catalog
+//! - Gemini: Google's models, including image input
+//! - Mock: Canned, offline stand-in for every stage
+//! - Ticketing: Jira and ServiceNow ticket creation
+//! - Lookup: Configured REST search endpoints queried for the LLM
+//! - Calendar: Events from an ICS feed, CalDAV, or Microsoft Graph
+//! - IMAP: Read-only access to unread email
+//! - Feeds: RSS and Atom feeds for the news briefing
+//!
+//! In debug builds, `chaos` can wrap any provider with fault injection.
+//! `compress` uploads recordings as FLAC when compressed uploads are on.
+//! `cooldown` pauses providers that hit a rate limit or run out of quota.
+//! `endpoint` accepts either base URLs or full endpoints in the settings.
+//!
+//! Which client handles each pipeline stage is chosen through the traits in `provider`.
 
 pub mod whisper;
 pub mod openwebui;
 pub mod elevenlabs;
+pub mod moderation;
+pub mod azure;
+pub mod openrouter;
+pub mod gemini;
+pub mod mock;
+pub mod ticketing;
+pub mod lookup;
+pub mod calendar;
+pub mod imap;
+pub mod feeds;
+pub mod chaos;
+pub mod compress;
+pub mod cooldown;
+pub mod endpoint;
+pub mod provider;
+pub mod http;
+pub mod retry;
+pub mod sse;
 
 // Re-export for convenience
-pub use whisper::WhisperClient;
-pub use openwebui::OpenWebUiClient;
+pub use whisper::{TranscriptionCandidate, TranscriptionOverrides, WhisperClient};
+pub use openwebui::{
+    AnswerStream, FileAttachment, GenerationOverrides, KnowledgeCollection, LlmFile, LlmReply, ModelInfo,
+    OpenWebUiClient,
+};
 pub use elevenlabs::ElevenLabsClient;
+pub use moderation::ModerationClient;
+pub use azure::AzureOpenAiClient;
+pub use openrouter::OpenRouterClient;
+pub use gemini::GeminiClient;
+pub use mock::MockClient;
+pub use ticketing::{Ticket, TicketingClient};
+pub use provider::{llm_provider, stt_provider, tts_is_mock, tts_provider, ImageAttachment, TtsProvider};
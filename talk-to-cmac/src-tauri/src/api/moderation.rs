@@ -0,0 +1,106 @@
+//! OpenAI moderation API client
+//!
+//! Classifies user input with OpenAI's moderation endpoint (or a compatible one)
+//! before it is forwarded to the LLM.
+
+use super::endpoint;
+use super::http;
+use crate::config::ModerationConfig;
+use crate::error::{AppResult, ModerationError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Moderation API client
+pub struct ModerationClient {
+    client: reqwest::Client,
+    config: ModerationConfig,
+    api_key: Option<String>,
+}
+
+/// Moderation request
+#[derive(Debug, Serialize)]
+struct ModerationRequest<'a> {
+    model: &'a str,
+    input: &'a str,
+}
+
+/// Moderation response
+#[derive(Debug, Deserialize)]
+struct ModerationResponse {
+    results: Vec<ModerationResult>,
+}
+
+/// Classification of a single input
+#[derive(Debug, Deserialize)]
+struct ModerationResult {
+    flagged: bool,
+    #[serde(default)]
+    categories: BTreeMap<String, bool>,
+}
+
+impl ModerationClient {
+    /// Create a new moderation client
+    pub fn new(mut config: ModerationConfig, api_key: Option<String>) -> AppResult<Self> {
+        config.endpoint = endpoint::moderation(&config.endpoint);
+        let client = http::client(Duration::from_secs(config.timeout_secs))
+            .map_err(|e| ModerationError::RequestFailed(e.to_string()))?;
+
+        Ok(Self {
+            client,
+            config,
+            api_key,
+        })
+    }
+
+    /// Classify text, returning the names of any flagged categories
+    ///
+    /// An empty list means the text was not flagged.
+    pub async fn check(&self, text: &str) -> AppResult<Vec<String>> {
+        let mut request = self.client.post(&self.config.endpoint).json(&ModerationRequest {
+            model: &self.config.model,
+            input: text,
+        });
+
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response = http::send("moderation", request)
+            .await
+            .map_err(|e| ModerationError::RequestFailed(e.to_string()))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(match status.as_u16() {
+                401 | 403 => ModerationError::AuthenticationFailed,
+                _ => ModerationError::RequestFailed(format!("HTTP {}", status)),
+            }
+            .into());
+        }
+
+        let result = response
+            .json::<ModerationResponse>()
+            .await
+            .map_err(|e| ModerationError::InvalidResponse(e.to_string()))?;
+
+        let Some(result) = result.results.into_iter().next() else {
+            return Err(ModerationError::InvalidResponse("No results in response".to_string()).into());
+        };
+
+        if !result.flagged {
+            return Ok(Vec::new());
+        }
+
+        let mut categories: Vec<String> = result
+            .categories
+            .into_iter()
+            .filter(|(_, flagged)| *flagged)
+            .map(|(name, _)| name)
+            .collect();
+        if categories.is_empty() {
+            categories.push("flagged".to_string());
+        }
+        Ok(categories)
+    }
+}
@@ -0,0 +1,225 @@
+//! OpenRouter client
+//!
+//! Sends chat completions through OpenRouter's OpenAI-compatible API and browses
+//! its model catalog, including per-token pricing for the model picker. Every
+//! request carries the `HTTP-Referer` and `X-Title` headers OpenRouter uses to
+//! attribute traffic to the app.
+
+use super::http;
+use super::openwebui::{ChatCompletionResponse, ChatMessage, GenerationOverrides, LlmReply, ModelInfo, ModelsResponse, SamplingOptions};
+use super::provider::LlmProvider;
+use crate::config::{AppConfig, OpenRouterConfig, OpenWebUiConfig};
+use crate::error::{AppResult, OpenRouterError};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// OpenRouter client
+///
+/// Temperature, token, and context limits come from the `openwebui` section so
+/// they apply to every provider.
+pub struct OpenRouterClient {
+    client: reqwest::Client,
+    config: OpenRouterConfig,
+    llm: OpenWebUiConfig,
+    api_key: Option<String>,
+}
+
+/// Chat completion request
+#[derive(Debug, Serialize)]
+struct OpenRouterChatRequest {
+    model: String,
+    messages: Vec<ChatMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<usize>,
+    #[serde(flatten)]
+    sampling: SamplingOptions,
+}
+
+/// OpenRouter error response
+#[derive(Debug, Deserialize)]
+struct OpenRouterErrorResponse {
+    error: OpenRouterErrorDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterErrorDetail {
+    message: String,
+}
+
+impl OpenRouterClient {
+    /// Create a new OpenRouter client
+    pub fn new(config: &AppConfig, api_key: Option<String>) -> AppResult<Self> {
+        let client = http::client(Duration::from_secs(config.openrouter.timeout_secs))
+            .map_err(|e| OpenRouterError::RequestFailed(e.to_string()))?;
+
+        Ok(Self {
+            client,
+            config: config.openrouter.clone(),
+            llm: config.openwebui.clone(),
+            api_key,
+        })
+    }
+
+    /// URL of an API path relative to the configured base
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.config.endpoint.trim_end_matches('/'), path)
+    }
+
+    /// Add authentication and attribution headers
+    fn with_headers(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let mut request = request;
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        if !self.config.referer.is_empty() {
+            request = request.header("HTTP-Referer", &self.config.referer);
+        }
+        if !self.config.title.is_empty() {
+            request = request.header("X-Title", &self.config.title);
+        }
+        request
+    }
+
+    /// Send a request, mapping HTTP errors to `OpenRouterError`
+    async fn send(&self, request: reqwest::RequestBuilder, model: &str) -> AppResult<reqwest::Response> {
+        let response = http::send("openrouter", self.with_headers(request)).await.map_err(|e| {
+            if e.is_timeout() {
+                OpenRouterError::Timeout
+            } else {
+                OpenRouterError::RequestFailed(e.to_string())
+            }
+        })?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let message = response
+            .json::<OpenRouterErrorResponse>()
+            .await
+            .map(|body| body.error.message)
+            .unwrap_or_else(|_| format!("HTTP {}", status));
+
+        Err(match status.as_u16() {
+            401 | 403 => OpenRouterError::AuthenticationFailed,
+            402 => OpenRouterError::InsufficientCredits,
+            404 => OpenRouterError::ModelNotFound(model.to_string()),
+            429 => OpenRouterError::RateLimitExceeded,
+            _ => OpenRouterError::RequestFailed(message),
+        }
+        .into())
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenRouterClient {
+    async fn send_message_with(
+        &self,
+        messages: Vec<(String, String)>,
+        overrides: &GenerationOverrides,
+    ) -> AppResult<LlmReply> {
+        let model = overrides.model.clone().unwrap_or_else(|| self.config.model.clone());
+
+        let messages: Vec<ChatMessage> = overrides
+            .system_prompt
+            .iter()
+            .map(|prompt| ChatMessage {
+                role: "system".to_string(),
+                content: prompt.clone(),
+            })
+            .chain(messages.into_iter().map(|(role, content)| ChatMessage { role, content }))
+            .collect();
+
+        let total_chars: usize = messages.iter().map(|m| m.content.len()).sum();
+        if total_chars > self.llm.max_context_length {
+            tracing::warn!("Context length ({}) exceeds maximum ({})", total_chars, self.llm.max_context_length);
+            return Err(OpenRouterError::ContextLimitExceeded.into());
+        }
+
+        tracing::info!("Sending {} messages to OpenRouter model '{}'", messages.len(), model);
+
+        let request_body = OpenRouterChatRequest {
+            model: model.clone(),
+            messages,
+            temperature: Some(overrides.temperature.unwrap_or(self.llm.temperature)),
+            max_tokens: overrides.max_tokens.or(self.llm.max_tokens),
+            sampling: SamplingOptions::from_config(&self.llm),
+        };
+
+        let response = self
+            .send(self.client.post(self.url("chat/completions")).json(&request_body), &model)
+            .await?
+            .json::<ChatCompletionResponse>()
+            .await
+            .map_err(|e| OpenRouterError::InvalidResponse(e.to_string()))?;
+
+        response
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message.into_reply())
+            .ok_or_else(|| OpenRouterError::InvalidResponse("No choices in response".to_string()).into())
+    }
+
+    async fn list_models(&self) -> AppResult<Vec<ModelInfo>> {
+        let response = self
+            .send(self.client.get(self.url("models")), &self.config.model)
+            .await?
+            .json::<ModelsResponse>()
+            .await
+            .map_err(|e| OpenRouterError::InvalidResponse(e.to_string()))?;
+
+        tracing::debug!("OpenRouter catalog lists {} models", response.data.len());
+        Ok(response.data)
+    }
+
+    async fn check_connectivity(&self) -> AppResult<bool> {
+        // The key endpoint needs authentication, so it also validates the API key
+        let request = self.with_headers(self.client.get(self.url("key")).timeout(Duration::from_secs(5)));
+
+        match http::send("openrouter", request).await {
+            Ok(resp) => match resp.status().as_u16() {
+                401 | 403 => Err(OpenRouterError::AuthenticationFailed.into()),
+                status => Ok(status < 500),
+            },
+            Err(e) => {
+                tracing::warn!("OpenRouter connectivity check failed: {}", e);
+                Ok(false)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_pricing_is_parsed() {
+        let body = r#"{"data": [
+            {"id": "openai/gpt-4o", "name": "OpenAI: GPT-4o", "context_length": 128000,
+             "pricing": {"prompt": "0.0000025", "completion": "0.00001", "request": "0"}},
+            {"id": "local/model"}
+        ]}"#;
+
+        let models: ModelsResponse = serde_json::from_str(body).unwrap();
+        let pricing = models.data[0].pricing.unwrap();
+        assert_eq!(pricing.prompt, 0.0000025);
+        assert_eq!(pricing.completion, 0.00001);
+        assert_eq!(models.data[0].context_length, Some(128000));
+        assert!(models.data[1].pricing.is_none());
+    }
+
+    #[test]
+    fn test_url_joins_endpoint() {
+        let mut config = AppConfig::default();
+        config.openrouter.endpoint = "https://openrouter.ai/api/v1/".to_string();
+        let client = OpenRouterClient::new(&config, None).unwrap();
+
+        assert_eq!(client.url("models"), "https://openrouter.ai/api/v1/models");
+    }
+}
@@ -1,11 +1,20 @@
 //! OpenWebUI API client for LLM interactions
 //!
 //! Handles message sending to OpenWebUI with conversation context management,
-//! streaming support, and proper error handling.
+//! streaming support, and proper error handling. Reasoning emitted by thinking
+//! models is separated from the final answer. Documents uploaded to the
+//! server's file store, and its knowledge collections, can be attached to a
+//! chat so the server answers from them.
 
-use crate::config::OpenWebUiConfig;
+use super::endpoint;
+use super::http;
+use super::retry;
+use super::sse;
+use crate::config::{ApiFlavor, OpenWebUiConfig};
+use crate::state::ConversationSettings;
 use crate::error::{AppResult, OpenWebUiError};
-use serde::{Deserialize, Serialize};
+use reqwest::multipart::{Form, Part};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::time::Duration;
 
 /// OpenWebUI API client
@@ -13,6 +22,9 @@ pub struct OpenWebUiClient {
     client: reqwest::Client,
     config: OpenWebUiConfig,
     api_key: Option<String>,
+
+    /// Delay before the first retry of a failed request
+    retry_delay: Duration,
 }
 
 /// Chat message for API request
@@ -22,6 +34,270 @@ pub struct ChatMessage {
     pub content: String,
 }
 
+/// Tag pairs that wrap reasoning in the content of thinking models
+const REASONING_TAGS: &[(&str, &str)] = &[
+    ("<think>", "</think>"),
+    ("<thinking>", "</thinking>"),
+    ("<reasoning>", "</reasoning>"),
+];
+
+/// LLM reply with any reasoning separated from the final answer
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LlmReply {
+    /// Final answer, the only part shown by default, stored, or spoken
+    pub answer: String,
+
+    /// Reasoning returned in a separate field or in tagged blocks of the content
+    pub reasoning: Option<String>,
+}
+
+impl LlmReply {
+    /// Split tagged reasoning blocks out of `content`, keeping any reasoning returned separately
+    ///
+    /// An unterminated block (a reply cut off, or still streaming, mid-thought) counts as
+    /// reasoning to the end. A closing tag with no opening tag marks everything before it as
+    /// reasoning, since some chat templates put the opening tag in the prompt.
+    pub fn parse(content: &str, reasoning: Option<String>) -> Self {
+        let mut thoughts: Vec<String> = reasoning.into_iter().collect();
+        let mut answer = String::new();
+        let mut rest = content;
+
+        let first_open = REASONING_TAGS.iter().filter_map(|(open, _)| rest.find(open)).min();
+        if let Some((index, close)) = REASONING_TAGS
+            .iter()
+            .filter_map(|(_, close)| rest.find(close).map(|index| (index, *close)))
+            .min()
+        {
+            if first_open.is_none_or(|open| index < open) {
+                thoughts.push(rest[..index].to_string());
+                rest = &rest[index + close.len()..];
+            }
+        }
+
+        while let Some((index, open, close)) = REASONING_TAGS
+            .iter()
+            .filter_map(|(open, close)| rest.find(open).map(|index| (index, *open, *close)))
+            .min()
+        {
+            answer.push_str(&rest[..index]);
+            let inner = &rest[index + open.len()..];
+            match inner.find(close) {
+                Some(end) => {
+                    thoughts.push(inner[..end].to_string());
+                    rest = &inner[end + close.len()..];
+                }
+                None => {
+                    thoughts.push(inner.to_string());
+                    rest = "";
+                }
+            }
+        }
+        answer.push_str(rest);
+
+        let reasoning = thoughts
+            .iter()
+            .map(|thought| thought.trim())
+            .filter(|thought| !thought.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        Self {
+            answer: answer.trim().to_string(),
+            reasoning: (!reasoning.is_empty()).then_some(reasoning),
+        }
+    }
+}
+
+/// Separates the answer from tagged reasoning while a reply streams in
+///
+/// Text is only released once it is known to be answer: a trailing `<...` that
+/// may still become a tag is held back, and so is the start of the reply while
+/// it may still be an opening tag. Once the reply starts with anything else,
+/// text passes straight through, so the first sentence can be spoken at once.
+/// Reasoning whose opening tag was in the prompt cannot be told apart until its
+/// closing tag, by which time it has been released; [`LlmReply::parse`] still
+/// separates it from the finished reply.
+#[derive(Debug, Default)]
+pub struct AnswerStream {
+    pending: String,
+    phase: StreamPhase,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum StreamPhase {
+    /// Start of the reply, which may still be an opening tag
+    #[default]
+    Undecided,
+
+    Answer,
+
+    /// Inside a reasoning block, waiting for its closing tag
+    Reasoning { close: &'static str },
+}
+
+impl AnswerStream {
+    /// Add streamed content, returning the answer text it released
+    pub fn push(&mut self, delta: &str) -> String {
+        self.pending.push_str(delta);
+        let mut answer = String::new();
+
+        loop {
+            match self.phase {
+                StreamPhase::Undecided => {
+                    let start = self.pending.trim_start();
+                    let may_be_tag = REASONING_TAGS
+                        .iter()
+                        .any(|(open, _)| open.len() > start.len() && open.starts_with(start));
+                    if start.is_empty() || may_be_tag {
+                        return answer;
+                    }
+                    self.phase = StreamPhase::Answer;
+                }
+                StreamPhase::Answer => {
+                    match first_tag(&self.pending, REASONING_TAGS.iter().map(|(open, close)| (*open, *close))) {
+                        Some((index, open, close)) => {
+                            answer.push_str(&self.pending[..index]);
+                            self.pending.drain(..index + open.len());
+                            self.phase = StreamPhase::Reasoning { close };
+                        }
+                        None => {
+                            let held = partial_tag(&self.pending, REASONING_TAGS.iter().map(|(open, _)| *open));
+                            answer.extend(self.pending.drain(..held));
+                            return answer;
+                        }
+                    }
+                }
+                StreamPhase::Reasoning { close } => match self.pending.find(close) {
+                    Some(index) => {
+                        self.pending.drain(..index + close.len());
+                        self.phase = StreamPhase::Answer;
+                    }
+                    None => {
+                        let held = partial_tag(&self.pending, [close]);
+                        self.pending.drain(..held);
+                        return answer;
+                    }
+                },
+            }
+        }
+    }
+
+    /// Answer text still held back once the stream has ended
+    pub fn finish(self) -> String {
+        match self.phase {
+            StreamPhase::Reasoning { .. } => String::new(),
+            _ => self.pending,
+        }
+    }
+}
+
+/// Earliest of `tags` in `text`: its index, the tag, and the tag that closes it
+fn first_tag(
+    text: &str,
+    tags: impl Iterator<Item = (&'static str, &'static str)>,
+) -> Option<(usize, &'static str, &'static str)> {
+    tags.filter_map(|(tag, close)| text.find(tag).map(|index| (index, tag, close))).min()
+}
+
+/// Index of a trailing `<...` in `text` that may be the start of one of `tags`, or `text.len()`
+fn partial_tag<'a>(text: &str, tags: impl IntoIterator<Item = &'a str>) -> usize {
+    let Some(index) = text.rfind('<') else {
+        return text.len();
+    };
+    let tail = &text[index..];
+    if tags.into_iter().any(|tag| tag.len() > tail.len() && tag.starts_with(tail)) {
+        index
+    } else {
+        text.len()
+    }
+}
+
+/// Instructions added to spoken queries in voice-optimized mode
+const VOICE_INSTRUCTIONS: &str = "Your reply will be read aloud. Answer in at most three short sentences of plain \
+conversational prose. Do not use markdown, lists, headings, code blocks, or URLs.";
+
+/// Per-request adjustments to the configured generation settings
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GenerationOverrides {
+    /// Model to use instead of the configured one
+    pub model: Option<String>,
+
+    /// Temperature to use instead of the configured one
+    pub temperature: Option<f32>,
+
+    /// System instructions prepended to the conversation
+    pub system_prompt: Option<String>,
+
+    /// Maximum tokens to generate, replacing the configured limit
+    pub max_tokens: Option<usize>,
+
+    /// Files and knowledge collections on the OpenWebUI server to answer from
+    pub files: Vec<FileAttachment>,
+}
+
+impl GenerationOverrides {
+    /// Overrides from a conversation's own settings
+    pub fn for_conversation(settings: &ConversationSettings) -> Self {
+        Self {
+            model: settings.model.clone(),
+            temperature: settings.temperature,
+            system_prompt: settings.system_prompt.clone(),
+            max_tokens: None,
+            files: settings.files.clone(),
+        }
+    }
+
+    /// Add voice brevity instructions for a query that came from voice and will be spoken back
+    ///
+    /// No-op unless `voice_optimized` is enabled; the token cap never exceeds the configured
+    /// `max_tokens`, and the instructions follow any existing system prompt.
+    pub fn with_voice(mut self, config: &OpenWebUiConfig) -> Self {
+        if !config.voice_optimized {
+            return self;
+        }
+
+        let max_tokens = match config.max_tokens {
+            Some(limit) => limit.min(config.voice_max_tokens),
+            None => config.voice_max_tokens,
+        };
+
+        self.system_prompt = Some(match self.system_prompt {
+            Some(prompt) => format!("{}\n\n{}", prompt, VOICE_INSTRUCTIONS),
+            None => VOICE_INSTRUCTIONS.to_string(),
+        });
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+}
+
+/// Optional sampling parameters, omitted from requests when unset
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SamplingOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stop: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+impl SamplingOptions {
+    /// Sampling parameters from the configuration
+    pub fn from_config(config: &OpenWebUiConfig) -> Self {
+        Self {
+            top_p: config.top_p,
+            presence_penalty: config.presence_penalty,
+            frequency_penalty: config.frequency_penalty,
+            stop: config.stop.clone(),
+            seed: config.seed,
+        }
+    }
+}
+
 /// Chat completion request
 #[derive(Debug, Serialize)]
 struct ChatCompletionRequest {
@@ -32,7 +308,34 @@ struct ChatCompletionRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     max_tokens: Option<usize>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    max_completion_tokens: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     stream: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keep_alive: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    files: Vec<FileAttachment>,
+    #[serde(flatten)]
+    sampling: SamplingOptions,
+}
+
+impl ChatCompletionRequest {
+    /// The request with only the fields a server of `flavor` accepts; unchanged if the flavor is unknown
+    fn shaped_for(mut self, flavor: Option<ApiFlavor>) -> Self {
+        let Some(flavor) = flavor else {
+            return self;
+        };
+        if !flavor.accepts_keep_alive() {
+            self.keep_alive = None;
+        }
+        if !flavor.accepts_files() {
+            self.files.clear();
+        }
+        if flavor.uses_max_completion_tokens() {
+            self.max_completion_tokens = self.max_tokens.take();
+        }
+        self
+    }
 }
 
 /// Chat completion response
@@ -48,10 +351,48 @@ pub struct ChatCompletionResponse {
 #[derive(Debug, Deserialize)]
 pub struct ChatChoice {
     pub index: usize,
-    pub message: ChatMessage,
+    pub message: ChatResponseMessage,
     pub finish_reason: Option<String>,
 }
 
+/// Assistant message in a response, with reasoning if the server returns it separately
+#[derive(Debug, Deserialize)]
+pub struct ChatResponseMessage {
+    #[serde(default)]
+    pub content: Option<String>,
+    #[serde(default, alias = "reasoning")]
+    pub reasoning_content: Option<String>,
+}
+
+impl ChatResponseMessage {
+    /// Answer and reasoning of this message
+    pub fn into_reply(self) -> LlmReply {
+        LlmReply::parse(&self.content.unwrap_or_default(), self.reasoning_content)
+    }
+}
+
+/// Streamed chat completion chunk
+#[derive(Debug, Deserialize)]
+struct ChatCompletionChunk {
+    #[serde(default)]
+    choices: Vec<ChunkChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChunkChoice {
+    #[serde(default)]
+    delta: ChunkDelta,
+}
+
+/// Content added by a streamed chunk
+#[derive(Debug, Default, Deserialize)]
+struct ChunkDelta {
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default, alias = "reasoning")]
+    reasoning_content: Option<String>,
+}
+
 /// Token usage information
 #[derive(Debug, Deserialize)]
 pub struct ChatUsage {
@@ -60,6 +401,159 @@ pub struct ChatUsage {
     pub total_tokens: usize,
 }
 
+/// Model available on the server
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelInfo {
+    pub id: String,
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Context window in tokens, if the server reports it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_length: Option<u64>,
+
+    /// Token pricing, if the server reports it (OpenRouter)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pricing: Option<ModelPricing>,
+}
+
+/// Model pricing in USD per token
+///
+/// Prices are sent as decimal strings (e.g., "0.0000025"); a negative price means
+/// the cost varies by the model the request is routed to.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    #[serde(deserialize_with = "deserialize_price")]
+    pub prompt: f64,
+
+    #[serde(deserialize_with = "deserialize_price")]
+    pub completion: f64,
+}
+
+/// Accept a price as either a number or a decimal string
+fn deserialize_price<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Price {
+        Number(f64),
+        Text(String),
+    }
+
+    match Price::deserialize(deserializer)? {
+        Price::Number(price) => Ok(price),
+        Price::Text(text) => text.trim().parse().map_err(serde::de::Error::custom),
+    }
+}
+
+/// File or knowledge collection on the OpenWebUI server, attached to a chat so the
+/// server retrieves from it when answering
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileAttachment {
+    #[serde(rename = "type")]
+    pub kind: FileAttachmentKind,
+    pub id: String,
+}
+
+impl FileAttachment {
+    /// An uploaded file
+    pub fn file(id: &str) -> Self {
+        Self {
+            kind: FileAttachmentKind::File,
+            id: id.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileAttachmentKind {
+    File,
+
+    /// A knowledge collection, searched as a whole
+    Collection,
+}
+
+/// File uploaded to the OpenWebUI server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LlmFile {
+    pub id: String,
+    pub filename: String,
+    pub content_type: Option<String>,
+
+    /// Size in bytes
+    pub size: Option<u64>,
+
+    /// Unix timestamp of the upload
+    pub created_at: Option<i64>,
+}
+
+/// Knowledge collection on the OpenWebUI server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KnowledgeCollection {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// File as OpenWebUI describes it
+#[derive(Debug, Deserialize)]
+struct FileResponse {
+    id: String,
+    filename: String,
+    #[serde(default)]
+    meta: FileMeta,
+    #[serde(default)]
+    created_at: Option<i64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct FileMeta {
+    #[serde(default)]
+    content_type: Option<String>,
+    #[serde(default)]
+    size: Option<u64>,
+}
+
+impl From<FileResponse> for LlmFile {
+    fn from(file: FileResponse) -> Self {
+        Self {
+            id: file.id,
+            filename: file.filename,
+            content_type: file.meta.content_type,
+            size: file.meta.size,
+            created_at: file.created_at,
+        }
+    }
+}
+
+/// Models list response
+#[derive(Debug, Deserialize)]
+pub struct ModelsResponse {
+    pub data: Vec<ModelInfo>,
+}
+
+/// Embeddings request
+#[derive(Debug, Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+/// Embeddings response
+#[derive(Debug, Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<Embedding>,
+}
+
+/// Embedding of one input
+#[derive(Debug, Deserialize)]
+struct Embedding {
+    #[serde(default)]
+    index: usize,
+    embedding: Vec<f32>,
+}
+
 /// OpenWebUI error response
 #[derive(Debug, Deserialize)]
 struct OpenWebUiErrorResponse {
@@ -75,16 +569,16 @@ struct OpenWebUiErrorDetail {
 
 impl OpenWebUiClient {
     /// Create a new OpenWebUI client
-    pub fn new(config: OpenWebUiConfig, api_key: Option<String>) -> AppResult<Self> {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(config.timeout_secs))
-            .build()
+    pub fn new(mut config: OpenWebUiConfig, api_key: Option<String>) -> AppResult<Self> {
+        config.endpoint = endpoint::openwebui_chat(&config.endpoint);
+        let client = http::client(Duration::from_secs(config.timeout_secs))
             .map_err(|e| OpenWebUiError::MessageSendFailed(e.to_string()))?;
 
         Ok(Self {
             client,
             config,
             api_key,
+            retry_delay: retry::RETRY_BASE_DELAY,
         })
     }
 
@@ -96,58 +590,135 @@ impl OpenWebUiClient {
     /// # Returns
     /// The assistant's response text
     pub async fn send_message(&self, messages: Vec<(String, String)>) -> AppResult<String> {
-        log::info!("Sending message to OpenWebUI with {} messages in context", messages.len());
+        self.send_message_with(messages, &GenerationOverrides::default())
+            .await
+            .map(|reply| reply.answer)
+    }
 
-        // Convert to ChatMessage format
-        let chat_messages: Vec<ChatMessage> = messages
-            .into_iter()
-            .map(|(role, content)| ChatMessage { role, content })
-            .collect();
+    /// Send a message to the LLM, applying per-request generation overrides
+    pub async fn send_message_with(
+        &self,
+        messages: Vec<(String, String)>,
+        overrides: &GenerationOverrides,
+    ) -> AppResult<LlmReply> {
+        tracing::info!("Sending message to OpenWebUI with {} messages in context", messages.len());
+        let chat_messages = self.chat_messages(messages, overrides)?;
 
-        // Validate context length
-        let total_chars: usize = chat_messages.iter().map(|m| m.content.len()).sum();
-        if total_chars > self.config.max_context_length {
-            log::warn!("Context length ({}) exceeds maximum ({})", total_chars, self.config.max_context_length);
-            return Err(OpenWebUiError::ContextLimitExceeded.into());
+        let reply = retry::with_retries("Message send", self.retry_delay, || {
+            self.try_send_message(&chat_messages, overrides)
+        })
+        .await?;
+
+        tracing::info!(
+            "Message sent successfully, answer length: {} chars, reasoning: {} chars",
+            reply.answer.len(),
+            reply.reasoning.as_ref().map_or(0, String::len)
+        );
+        Ok(reply)
+    }
+
+    /// Stream a reply over server-sent events, calling `on_delta` with each piece of content
+    ///
+    /// Unless `stream` is enabled this sends a single request and delivers the answer as one piece.
+    /// Streaming requests are not retried, since deltas may already have been delivered.
+    pub async fn stream_message_with(
+        &self,
+        messages: Vec<(String, String)>,
+        overrides: &GenerationOverrides,
+        on_delta: &mut (dyn for<'d> FnMut(&'d str) + Send),
+    ) -> AppResult<LlmReply> {
+        if !self.config.stream {
+            let reply = self.send_message_with(messages, overrides).await?;
+            on_delta(&reply.answer);
+            return Ok(reply);
         }
 
-        // Attempt with retry logic
-        let max_retries = 3;
-        let mut last_error = None;
+        tracing::info!("Streaming reply from OpenWebUI with {} messages in context", messages.len());
+        let chat_messages = self.chat_messages(messages, overrides)?;
+        let response = self.post_chat(&chat_messages, overrides, true).await?;
 
-        for attempt in 1..=max_retries {
-            match self.try_send_message(&chat_messages).await {
-                Ok(response) => {
-                    log::info!("Message sent successfully, response length: {} chars", response.len());
-                    return Ok(response);
+        let mut content = String::new();
+        let mut reasoning = String::new();
+        let map_err = |e: reqwest::Error| {
+            if e.is_timeout() {
+                OpenWebUiError::Timeout.into()
+            } else {
+                OpenWebUiError::MessageSendFailed(e.to_string()).into()
+            }
+        };
+        sse::read_events(response, map_err, |data| {
+            if data == "[DONE]" {
+                return Ok(false);
+            }
+
+            let chunk: ChatCompletionChunk =
+                serde_json::from_str(data).map_err(|e| OpenWebUiError::ResponseParseFailed(e.to_string()))?;
+            if let Some(delta) = chunk.choices.into_iter().next().map(|choice| choice.delta) {
+                if let Some(thought) = delta.reasoning_content {
+                    reasoning.push_str(&thought);
                 }
-                Err(e) => {
-                    last_error = Some(e);
-                    if attempt < max_retries {
-                        let delay = Duration::from_secs(2u64.pow(attempt as u32 - 1));
-                        log::warn!("Message send attempt {} failed, retrying in {:?}", attempt, delay);
-                        tokio::time::sleep(delay).await;
-                    }
+                if let Some(text) = delta.content.filter(|text| !text.is_empty()) {
+                    on_delta(&text);
+                    content.push_str(&text);
                 }
             }
+            Ok(true)
+        })
+        .await?;
+
+        Ok(LlmReply::parse(&content, Some(reasoning).filter(|r| !r.is_empty())))
+    }
+
+    /// Convert (role, content) tuples to chat messages, with any override instructions first
+    fn chat_messages(
+        &self,
+        messages: Vec<(String, String)>,
+        overrides: &GenerationOverrides,
+    ) -> AppResult<Vec<ChatMessage>> {
+        let chat_messages: Vec<ChatMessage> = overrides
+            .system_prompt
+            .iter()
+            .map(|prompt| ChatMessage {
+                role: "system".to_string(),
+                content: prompt.clone(),
+            })
+            .chain(messages.into_iter().map(|(role, content)| ChatMessage { role, content }))
+            .collect();
+
+        // Validate context length
+        let total_chars: usize = chat_messages.iter().map(|m| m.content.len()).sum();
+        if total_chars > self.config.max_context_length {
+            tracing::warn!("Context length ({}) exceeds maximum ({})", total_chars, self.config.max_context_length);
+            return Err(OpenWebUiError::ContextLimitExceeded.into());
         }
 
-        Err(last_error.unwrap())
+        Ok(chat_messages)
     }
 
-    /// Internal message sending attempt
-    async fn try_send_message(&self, messages: &[ChatMessage]) -> AppResult<String> {
+    /// Post a chat completion request, returning the response if the server accepted it
+    async fn post_chat(
+        &self,
+        messages: &[ChatMessage],
+        overrides: &GenerationOverrides,
+        stream: bool,
+    ) -> AppResult<reqwest::Response> {
         // Build request payload
+        let model = overrides.model.clone().unwrap_or_else(|| self.config.model.clone());
         let request_body = ChatCompletionRequest {
-            model: self.config.model.clone(),
+            model: model.clone(),
             messages: messages.to_vec(),
-            temperature: Some(self.config.temperature),
-            max_tokens: self.config.max_tokens,
-            stream: Some(self.config.stream),
-        };
+            temperature: Some(overrides.temperature.unwrap_or(self.config.temperature)),
+            max_tokens: overrides.max_tokens.or(self.config.max_tokens),
+            max_completion_tokens: None,
+            stream: Some(stream),
+            keep_alive: self.config.keep_alive.clone(),
+            files: overrides.files.clone(),
+            sampling: SamplingOptions::from_config(&self.config),
+        }
+        .shaped_for(self.config.api_flavor);
 
-        log::debug!("Request payload: model={}, messages={}, stream={}",
-                   request_body.model, messages.len(), self.config.stream);
+        tracing::debug!("Request payload: model={}, messages={}, stream={}",
+                   request_body.model, messages.len(), stream);
 
         // Build HTTP request
         let mut request = self.client
@@ -160,8 +731,7 @@ impl OpenWebUiClient {
         }
 
         // Send request
-        let response = request
-            .send()
+        let response = http::send("openwebui", request)
             .await
             .map_err(|e| {
                 if e.is_timeout() {
@@ -174,25 +744,24 @@ impl OpenWebUiClient {
         // Check status
         let status = response.status();
         if !status.is_success() {
-            // Try to parse error response
-            if let Ok(error_response) = response.json::<OpenWebUiErrorResponse>().await {
-                return Err(match status.as_u16() {
-                    401 | 403 => OpenWebUiError::AuthenticationFailed,
-                    404 => OpenWebUiError::ModelNotFound(self.config.model.clone()),
-                    429 => OpenWebUiError::RateLimitExceeded,
-                    _ => OpenWebUiError::MessageSendFailed(error_response.error.message),
-                }.into());
-            }
-            return Err(OpenWebUiError::MessageSendFailed(format!("HTTP {}", status)).into());
+            // The status decides the error; the body only adds a message when it has one
+            return Err(match status.as_u16() {
+                401 | 403 => OpenWebUiError::AuthenticationFailed,
+                404 => OpenWebUiError::ModelNotFound(model),
+                429 => OpenWebUiError::RateLimitExceeded,
+                _ => match response.json::<OpenWebUiErrorResponse>().await {
+                    Ok(error_response) => OpenWebUiError::MessageSendFailed(error_response.error.message),
+                    Err(_) => OpenWebUiError::MessageSendFailed(format!("HTTP {}", status)),
+                },
+            }.into());
         }
 
-        // Handle streaming vs non-streaming responses
-        if self.config.stream {
-            // For now, streaming is not fully implemented
-            // This would require handling Server-Sent Events (SSE)
-            log::warn!("Streaming responses not yet implemented, falling back to non-streaming");
-            return Err(OpenWebUiError::StreamingNotSupported.into());
-        }
+        Ok(response)
+    }
+
+    /// Internal message sending attempt
+    async fn try_send_message(&self, messages: &[ChatMessage], overrides: &GenerationOverrides) -> AppResult<LlmReply> {
+        let response = self.post_chat(messages, overrides, false).await?;
 
         // Parse non-streaming response
         let result = response
@@ -201,21 +770,192 @@ impl OpenWebUiClient {
             .map_err(|e| OpenWebUiError::ResponseParseFailed(e.to_string()))?;
 
         // Extract message from first choice
-        if let Some(choice) = result.choices.first() {
-            let response_text = choice.message.content.clone();
-
+        if let Some(choice) = result.choices.into_iter().next() {
             // Log usage if available
             if let Some(usage) = result.usage {
-                log::debug!("Token usage - prompt: {}, completion: {}, total: {}",
+                tracing::debug!("Token usage - prompt: {}, completion: {}, total: {}",
                            usage.prompt_tokens, usage.completion_tokens, usage.total_tokens);
             }
 
-            Ok(response_text)
+            Ok(choice.message.into_reply())
         } else {
             Err(OpenWebUiError::ResponseParseFailed("No choices in response".to_string()).into())
         }
     }
 
+    /// List models available on the server
+    pub async fn list_models(&self) -> AppResult<Vec<ModelInfo>> {
+        let models_endpoint = models_endpoint(&self.config.endpoint);
+        tracing::debug!("Fetching models from: {}", models_endpoint);
+
+        let mut request = self.client.get(&models_endpoint);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response = http::send("openwebui", request)
+            .await
+            .map_err(|e| {
+                if e.is_timeout() {
+                    OpenWebUiError::Timeout
+                } else {
+                    OpenWebUiError::MessageSendFailed(e.to_string())
+                }
+            })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(match status.as_u16() {
+                401 | 403 => OpenWebUiError::AuthenticationFailed,
+                _ => OpenWebUiError::MessageSendFailed(format!("Failed to fetch models: HTTP {}", status)),
+            }
+            .into());
+        }
+
+        let models_response = response
+            .json::<ModelsResponse>()
+            .await
+            .map_err(|e| OpenWebUiError::ResponseParseFailed(e.to_string()))?;
+
+        Ok(models_response.data)
+    }
+
+    /// Embed each of `texts` with `model`, using the OpenAI-compatible embeddings endpoint
+    pub async fn embed(&self, texts: &[String], model: &str) -> AppResult<Vec<Vec<f32>>> {
+        let endpoint = embeddings_endpoint(&self.config.endpoint);
+        tracing::debug!("Embedding {} texts with {} at {}", texts.len(), model, endpoint);
+
+        let mut request = self.client.post(&endpoint).json(&EmbeddingRequest { model, input: texts });
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response = http::send("openwebui", request)
+            .await
+            .map_err(|e| {
+                if e.is_timeout() {
+                    OpenWebUiError::Timeout
+                } else {
+                    OpenWebUiError::MessageSendFailed(e.to_string())
+                }
+            })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(match status.as_u16() {
+                401 | 403 => OpenWebUiError::AuthenticationFailed,
+                404 => OpenWebUiError::ModelNotFound(model.to_string()),
+                429 => OpenWebUiError::RateLimitExceeded,
+                _ => OpenWebUiError::MessageSendFailed(format!("Failed to create embeddings: HTTP {}", status)),
+            }
+            .into());
+        }
+
+        let mut result = response
+            .json::<EmbeddingResponse>()
+            .await
+            .map_err(|e| OpenWebUiError::ResponseParseFailed(e.to_string()))?;
+        if result.data.len() != texts.len() {
+            return Err(OpenWebUiError::ResponseParseFailed(format!(
+                "Expected {} embeddings, got {}",
+                texts.len(),
+                result.data.len()
+            ))
+            .into());
+        }
+
+        result.data.sort_by_key(|embedding| embedding.index);
+        Ok(result.data.into_iter().map(|embedding| embedding.embedding).collect())
+    }
+
+    /// Upload a document to the server's file store
+    ///
+    /// OpenWebUI extracts and indexes the text as part of the upload, so this
+    /// returns once the file can be retrieved from.
+    pub async fn upload_file(&self, filename: &str, content: Vec<u8>) -> AppResult<LlmFile> {
+        let endpoint = self.files_endpoint("/files/")?;
+        tracing::debug!("Uploading {} ({} bytes) to {}", filename, content.len(), endpoint);
+
+        let part = Part::bytes(content)
+            .file_name(filename.to_string())
+            .mime_str(content_type(filename))
+            .map_err(|e| OpenWebUiError::UploadFailed(e.to_string()))?;
+        let request = self.client.post(&endpoint).multipart(Form::new().part("file", part));
+        let response = self.send_files_request(request, "upload file").await?;
+
+        response
+            .json::<FileResponse>()
+            .await
+            .map(LlmFile::from)
+            .map_err(|e| OpenWebUiError::ResponseParseFailed(e.to_string()).into())
+    }
+
+    /// Files uploaded to the server
+    pub async fn list_files(&self) -> AppResult<Vec<LlmFile>> {
+        let request = self.client.get(self.files_endpoint("/files/")?);
+        let response = self.send_files_request(request, "list files").await?;
+
+        let files = response
+            .json::<Vec<FileResponse>>()
+            .await
+            .map_err(|e| OpenWebUiError::ResponseParseFailed(e.to_string()))?;
+        Ok(files.into_iter().map(LlmFile::from).collect())
+    }
+
+    /// Knowledge collections on the server
+    pub async fn list_knowledge(&self) -> AppResult<Vec<KnowledgeCollection>> {
+        let request = self.client.get(self.files_endpoint("/knowledge/")?);
+        let response = self.send_files_request(request, "list knowledge collections").await?;
+
+        response
+            .json()
+            .await
+            .map_err(|e| OpenWebUiError::ResponseParseFailed(e.to_string()).into())
+    }
+
+    /// Add an uploaded file to a knowledge collection
+    pub async fn add_to_knowledge(&self, knowledge_id: &str, file_id: &str) -> AppResult<()> {
+        let endpoint = self.files_endpoint(&format!("/knowledge/{}/file/add", knowledge_id))?;
+        let request = self.client.post(&endpoint).json(&serde_json::json!({ "file_id": file_id }));
+        self.send_files_request(request, "add file to knowledge collection").await?;
+        Ok(())
+    }
+
+    /// Endpoint of OpenWebUI's own API for `path`; fails if the server is known not to be OpenWebUI
+    fn files_endpoint(&self, path: &str) -> AppResult<String> {
+        match self.config.api_flavor {
+            Some(flavor) if flavor != ApiFlavor::OpenWebUi => Err(OpenWebUiError::FilesUnsupported.into()),
+            _ => Ok(api_v1_endpoint(&self.config.endpoint, path)),
+        }
+    }
+
+    /// Send a request to the file or knowledge API, failing on an unsuccessful status
+    async fn send_files_request(&self, mut request: reqwest::RequestBuilder, action: &str) -> AppResult<reqwest::Response> {
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response = http::send("openwebui", request)
+            .await
+            .map_err(|e| {
+                if e.is_timeout() {
+                    OpenWebUiError::Timeout
+                } else {
+                    OpenWebUiError::MessageSendFailed(e.to_string())
+                }
+            })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(match status.as_u16() {
+                401 | 403 => OpenWebUiError::AuthenticationFailed,
+                _ => OpenWebUiError::UploadFailed(format!("Failed to {}: HTTP {}", action, status)),
+            }
+            .into());
+        }
+        Ok(response)
+    }
+
     /// Check connectivity to OpenWebUI API
     pub async fn check_connectivity(&self) -> AppResult<bool> {
         // Try a minimal request to check if the service is available
@@ -231,8 +971,13 @@ impl OpenWebUiClient {
             messages: test_messages,
             temperature: Some(0.1),
             max_tokens: Some(5),
+            max_completion_tokens: None,
             stream: Some(false),
-        };
+            keep_alive: self.config.keep_alive.clone(),
+            files: Vec::new(),
+            sampling: SamplingOptions::default(),
+        }
+        .shaped_for(self.config.api_flavor);
 
         let mut request = self.client
             .post(&self.config.endpoint)
@@ -243,7 +988,7 @@ impl OpenWebUiClient {
             request = request.bearer_auth(api_key);
         }
 
-        match request.send().await {
+        match http::send("openwebui", request).await {
             Ok(resp) => {
                 let status = resp.status().as_u16();
                 // Consider 2xx and 4xx (except 404) as "connected"
@@ -251,14 +996,15 @@ impl OpenWebUiClient {
                 Ok(status < 500 && status != 404)
             }
             Err(e) => {
-                log::warn!("OpenWebUI connectivity check failed: {}", e);
+                tracing::warn!("OpenWebUI connectivity check failed: {}", e);
                 Ok(false)
             }
         }
     }
 
     /// Update configuration
-    pub fn update_config(&mut self, config: OpenWebUiConfig) {
+    pub fn update_config(&mut self, mut config: OpenWebUiConfig) {
+        config.endpoint = endpoint::openwebui_chat(&config.endpoint);
         self.config = config;
     }
 
@@ -278,9 +1024,50 @@ impl OpenWebUiClient {
     }
 }
 
+/// Models endpoint next to a chat endpoint (e.g., `/api/chat` -> `/api/models`)
+fn models_endpoint(chat_endpoint: &str) -> String {
+    let endpoint = chat_endpoint.trim_end_matches('/');
+    let base = endpoint.rfind("/chat").map_or(endpoint, |index| &endpoint[..index]);
+    format!("{}/models", base)
+}
+
+/// Embeddings endpoint next to a chat endpoint (e.g., `/api/chat` -> `/api/embeddings`)
+fn embeddings_endpoint(chat_endpoint: &str) -> String {
+    let endpoint = chat_endpoint.trim_end_matches('/');
+    let base = endpoint.rfind("/chat").map_or(endpoint, |index| &endpoint[..index]);
+    format!("{}/embeddings", base)
+}
+
+/// OpenWebUI API endpoint for `path` (e.g. "/files/") on the server of `chat_endpoint`
+fn api_v1_endpoint(chat_endpoint: &str, path: &str) -> String {
+    let endpoint = chat_endpoint.split('?').next().unwrap_or_default().trim_end_matches('/');
+    let base = endpoint.rfind("/api/").map_or(endpoint, |index| &endpoint[..index]);
+    format!("{}/api/v1{}", base, path)
+}
+
+/// MIME type to upload a document as, by its extension
+fn content_type(filename: &str) -> &'static str {
+    let extension = filename.rsplit_once('.').map(|(_, extension)| extension.to_lowercase());
+    match extension.as_deref() {
+        Some("pdf") => "application/pdf",
+        Some("docx") => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        Some("xlsx") => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        Some("pptx") => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        Some("md") => "text/markdown",
+        Some("csv") => "text/csv",
+        Some("html" | "htm") => "text/html",
+        Some("json") => "application/json",
+        Some("txt" | "log") => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::RetryConfig;
+    use crate::error::AppError;
+    use crate::test_support::*;
 
     #[test]
     fn test_openwebui_client_creation() {
@@ -292,6 +1079,18 @@ mod tests {
             max_tokens: Some(1024),
             stream: false,
             timeout_secs: 60,
+            voice_optimized: false,
+            voice_max_tokens: 200,
+            follow_up_suggestions: false,
+            top_p: None,
+            presence_penalty: None,
+            frequency_penalty: None,
+            stop: Vec::new(),
+            seed: None,
+            show_reasoning: false,
+            keep_alive: None,
+            retry: RetryConfig::default(),
+            api_flavor: None,
         };
 
         let client = OpenWebUiClient::new(config, None);
@@ -320,6 +1119,18 @@ mod tests {
             max_tokens: Some(1024),
             stream: false,
             timeout_secs: 60,
+            voice_optimized: false,
+            voice_max_tokens: 200,
+            follow_up_suggestions: false,
+            top_p: None,
+            presence_penalty: None,
+            frequency_penalty: None,
+            stop: Vec::new(),
+            seed: None,
+            show_reasoning: false,
+            keep_alive: None,
+            retry: RetryConfig::default(),
+            api_flavor: None,
         };
 
         let client = OpenWebUiClient::new(config, None).unwrap();
@@ -334,4 +1145,242 @@ mod tests {
 
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_voice_overrides() {
+        let mut config = OpenWebUiConfig {
+            endpoint: "http://localhost:3000/api/chat".to_string(),
+            model: "llama3.2".to_string(),
+            max_context_length: 4096,
+            temperature: 0.7,
+            max_tokens: Some(1024),
+            stream: false,
+            timeout_secs: 60,
+            voice_optimized: false,
+            voice_max_tokens: 200,
+            follow_up_suggestions: false,
+            top_p: None,
+            presence_penalty: None,
+            frequency_penalty: None,
+            stop: Vec::new(),
+            seed: None,
+            show_reasoning: false,
+            keep_alive: None,
+            retry: RetryConfig::default(),
+            api_flavor: None,
+        };
+        let base = GenerationOverrides::default();
+        assert_eq!(base.clone().with_voice(&config), base);
+
+        config.voice_optimized = true;
+        let overrides = GenerationOverrides::default().with_voice(&config);
+        assert_eq!(overrides.max_tokens, Some(200));
+        assert_eq!(overrides.system_prompt.as_deref(), Some(VOICE_INSTRUCTIONS));
+
+        config.max_tokens = Some(100);
+        let settings = ConversationSettings {
+            model: Some("codellama".to_string()),
+            temperature: None,
+            system_prompt: Some("Be precise.".to_string()),
+            ..ConversationSettings::default()
+        };
+        let overrides = GenerationOverrides::for_conversation(&settings).with_voice(&config);
+        assert_eq!(overrides.max_tokens, Some(100));
+        assert_eq!(overrides.model.as_deref(), Some("codellama"));
+        assert!(overrides.system_prompt.unwrap().starts_with("Be precise.\n\n"));
+    }
+
+    #[test]
+    fn test_sampling_options_serialization() {
+        let request = |sampling| ChatCompletionRequest {
+            model: "llama3.2".to_string(),
+            messages: Vec::new(),
+            temperature: None,
+            max_tokens: None,
+            max_completion_tokens: None,
+            stream: None,
+            keep_alive: None,
+            files: Vec::new(),
+            sampling,
+        };
+
+        let json = serde_json::to_value(request(SamplingOptions::default())).unwrap();
+        assert_eq!(json, serde_json::json!({"model": "llama3.2", "messages": []}));
+
+        let json = serde_json::to_value(request(SamplingOptions {
+            top_p: Some(0.9),
+            stop: vec!["\n\nUser:".to_string()],
+            seed: Some(42),
+            ..SamplingOptions::default()
+        }))
+        .unwrap();
+        assert_eq!(json["top_p"], serde_json::json!(0.9f32));
+        assert_eq!(json["stop"], serde_json::json!(["\n\nUser:"]));
+        assert_eq!(json["seed"], 42);
+        assert!(json.get("presence_penalty").is_none());
+    }
+
+    #[test]
+    fn test_reasoning_is_split_from_answer() {
+        let reply = LlmReply::parse("<think>\nThe user wants a greeting.\n</think>\n\nHello there!", None);
+        assert_eq!(reply.answer, "Hello there!");
+        assert_eq!(reply.reasoning.as_deref(), Some("The user wants a greeting."));
+
+        let reply = LlmReply::parse("Plain answer.", Some("From the reasoning field".to_string()));
+        assert_eq!(reply.answer, "Plain answer.");
+        assert_eq!(reply.reasoning.as_deref(), Some("From the reasoning field"));
+
+        assert_eq!(LlmReply::parse("Just an answer", None).reasoning, None);
+    }
+
+    #[test]
+    fn test_partial_reasoning_is_never_answer() {
+        // Still thinking, or cut off by max_tokens
+        let reply = LlmReply::parse("<think>Let me consider", None);
+        assert_eq!(reply.answer, "");
+        assert_eq!(reply.reasoning.as_deref(), Some("Let me consider"));
+
+        // Opening tag supplied by the chat template
+        let reply = LlmReply::parse("Counting letters...</think>There are three.", None);
+        assert_eq!(reply.answer, "There are three.");
+        assert_eq!(reply.reasoning.as_deref(), Some("Counting letters..."));
+    }
+
+    #[test]
+    fn test_request_is_shaped_for_flavor() {
+        let request = || ChatCompletionRequest {
+            model: "gpt-4o".to_string(),
+            messages: Vec::new(),
+            temperature: None,
+            max_tokens: Some(256),
+            max_completion_tokens: None,
+            stream: None,
+            keep_alive: Some("30m".to_string()),
+            files: vec![FileAttachment::file("3f2c")],
+            sampling: SamplingOptions::default(),
+        };
+
+        let json = serde_json::to_value(request().shaped_for(Some(ApiFlavor::OpenAi))).unwrap();
+        assert_eq!(json, serde_json::json!({"model": "gpt-4o", "messages": [], "max_completion_tokens": 256}));
+
+        let json = serde_json::to_value(request().shaped_for(Some(ApiFlavor::Ollama))).unwrap();
+        assert_eq!((json["max_tokens"].as_u64(), json["keep_alive"].as_str()), (Some(256), Some("30m")));
+        assert!(serde_json::to_value(request().shaped_for(Some(ApiFlavor::LmStudio))).unwrap().get("keep_alive").is_none());
+
+        let json = serde_json::to_value(request().shaped_for(Some(ApiFlavor::OpenWebUi))).unwrap();
+        assert_eq!(json["files"], serde_json::json!([{ "type": "file", "id": "3f2c" }]));
+    }
+
+    #[test]
+    fn test_files_endpoints() {
+        assert_eq!(api_v1_endpoint("http://localhost:3000/api/chat/completions", "/files/"), "http://localhost:3000/api/v1/files/");
+        assert_eq!(api_v1_endpoint("https://chat.example.com/webui/api/chat", "/knowledge/"), "https://chat.example.com/webui/api/v1/knowledge/");
+        assert_eq!(content_type("Manual.PDF"), "application/pdf");
+    }
+
+    #[test]
+    fn test_models_endpoint() {
+        assert_eq!(models_endpoint("http://localhost:3000/api/chat"), "http://localhost:3000/api/models");
+        assert_eq!(
+            models_endpoint("https://example.com/v1/chat/completions/"),
+            "https://example.com/v1/models"
+        );
+        assert_eq!(
+            embeddings_endpoint("https://example.com/v1/chat/completions"),
+            "https://example.com/v1/embeddings"
+        );
+    }
+
+    fn mock_client(server: &MockServer, stream: bool) -> OpenWebUiClient {
+        let config = OpenWebUiConfig {
+            endpoint: format!("{}/api/chat/completions", server.uri()),
+            model: "llama3.2".to_string(),
+            stream,
+            timeout_secs: 5,
+            ..crate::config::AppConfig::default().openwebui
+        };
+        let mut client = OpenWebUiClient::new(config, Some("test_key".to_string())).unwrap();
+        client.retry_delay = Duration::ZERO;
+        client
+    }
+
+    fn question() -> Vec<(String, String)> {
+        vec![("user".to_string(), "Hi".to_string())]
+    }
+
+    #[tokio::test]
+    async fn test_reply_is_returned() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/chat/completions"))
+            .and(header("authorization", "Bearer test_key"))
+            .respond_with(chat_completion("<think>Greet back.</think>Hello!"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let reply = mock_client(&server, false).send_message_with(question(), &GenerationOverrides::default()).await.unwrap();
+        assert_eq!(reply.answer, "Hello!");
+        assert_eq!(reply.reasoning.as_deref(), Some("Greet back."));
+    }
+
+    #[tokio::test]
+    async fn test_status_codes_map_to_errors_without_retry() {
+        for (status, body) in [(401, "Unauthorized"), (404, "{\"detail\": \"Model not found\"}")] {
+            let server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .respond_with(ResponseTemplate::new(status).set_body_string(body))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let result = mock_client(&server, false).send_message_with(question(), &GenerationOverrides::default()).await;
+            match (status, result) {
+                (401, Err(AppError::OpenWebUi(OpenWebUiError::AuthenticationFailed))) => {}
+                (404, Err(AppError::OpenWebUi(OpenWebUiError::ModelNotFound(model)))) => assert_eq!(model, "llama3.2"),
+                (_, other) => panic!("unexpected result for HTTP {}: {:?}", status, other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_delivers_deltas_in_order() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(chat_stream(&["<think>Short", " answer.</think>", "Hello", " there", "!"]))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut deltas = Vec::new();
+        let reply = mock_client(&server, true)
+            .stream_message_with(question(), &GenerationOverrides::default(), &mut |delta| deltas.push(delta.to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(deltas.concat(), "<think>Short answer.</think>Hello there!");
+        assert_eq!(reply.answer, "Hello there!");
+        assert_eq!(reply.reasoning.as_deref(), Some("Short answer."));
+    }
+
+    #[tokio::test]
+    async fn test_embeddings_are_returned_in_input_order() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/embeddings"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [
+                    {"index": 1, "embedding": [0.0, 1.0]},
+                    {"index": 0, "embedding": [1.0, 0.0]}
+                ]
+            })))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let texts = vec!["first".to_string(), "second".to_string()];
+        let embeddings = mock_client(&server, false).embed(&texts, "nomic-embed-text").await.unwrap();
+        assert_eq!(embeddings, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+        assert!(mock_client(&server, false).embed(&texts[..1], "nomic-embed-text").await.is_err());
+    }
 }
@@ -0,0 +1,290 @@
+//! Provider abstraction
+//!
+//! Transcription, chat, and speech synthesis are each handled by a provider
+//! selected in `AppConfig::providers`. Commands work with these traits so the
+//! voice pipeline does not depend on a particular service.
+
+use super::{chaos, compress};
+use super::openwebui::{GenerationOverrides, LlmReply, ModelInfo};
+use super::whisper::TranscriptionCandidate;
+use super::{AzureOpenAiClient, ElevenLabsClient, GeminiClient, MockClient, OpenRouterClient, OpenWebUiClient, WhisperClient};
+use crate::bandwidth;
+use crate::config::{ApiKeys, AppConfig, LlmProviderKind, SttProviderKind, TtsProviderKind};
+use crate::error::{AppError, AppResult};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// Request for follow-up questions, appended after the latest reply
+const FOLLOW_UP_INSTRUCTIONS: &str = "Suggest two or three short follow-up questions I might ask next. \
+Reply with one question per line and nothing else.";
+
+/// Maximum number of follow-up suggestions returned
+const MAX_FOLLOW_UPS: usize = 3;
+
+/// Image attached to the latest user message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImageAttachment {
+    /// MIME type (e.g., "image/png")
+    pub mime_type: String,
+
+    /// Base64-encoded image bytes
+    pub data: String,
+}
+
+/// Speech-to-text service
+#[async_trait]
+pub trait SttProvider: Send + Sync {
+    /// Transcribe recorded audio
+    async fn transcribe_audio(&self, audio_data: Vec<u8>, filename: &str) -> AppResult<String>;
+
+    /// Transcribe recorded audio, with alternative hypotheses after the top one where supported
+    async fn transcribe_candidates(&self, audio_data: Vec<u8>, filename: &str) -> AppResult<Vec<TranscriptionCandidate>> {
+        let text = self.transcribe_audio(audio_data, filename).await?;
+        Ok(vec![TranscriptionCandidate { text, confidence: None }])
+    }
+
+    /// Check whether the service is reachable
+    async fn check_connectivity(&self) -> AppResult<bool>;
+}
+
+/// Chat completion service
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// Send conversation messages as (role, content) tuples, applying per-request overrides
+    async fn send_message_with(
+        &self,
+        messages: Vec<(String, String)>,
+        overrides: &GenerationOverrides,
+    ) -> AppResult<LlmReply>;
+
+    /// Send messages with images attached to the latest user message
+    ///
+    /// Providers without multimodal support reject the request.
+    async fn send_message_with_images(
+        &self,
+        _messages: Vec<(String, String)>,
+        _images: &[ImageAttachment],
+        _overrides: &GenerationOverrides,
+    ) -> AppResult<LlmReply> {
+        Err(AppError::Generic("The selected LLM provider does not accept images".to_string()))
+    }
+
+    /// Stream the reply, calling `on_delta` with each piece of text as it arrives
+    ///
+    /// Deltas are raw content and may include reasoning tags. Returns the complete reply;
+    /// providers without streaming deliver the answer as a single piece.
+    async fn stream_message_with(
+        &self,
+        messages: Vec<(String, String)>,
+        overrides: &GenerationOverrides,
+        on_delta: &mut (dyn for<'d> FnMut(&'d str) + Send),
+    ) -> AppResult<LlmReply> {
+        let reply = self.send_message_with(messages, overrides).await?;
+        on_delta(&reply.answer);
+        Ok(reply)
+    }
+
+    /// List models (or deployments) that can be selected
+    async fn list_models(&self) -> AppResult<Vec<ModelInfo>>;
+
+    /// Check whether the service is reachable
+    async fn check_connectivity(&self) -> AppResult<bool>;
+
+    /// Embed each of `texts` with embedding model `model`, in order
+    ///
+    /// Providers without an embeddings endpoint reject the request.
+    async fn embed(&self, _texts: &[String], _model: &str) -> AppResult<Vec<Vec<f32>>> {
+        Err(AppError::Generic("The selected LLM provider does not create embeddings".to_string()))
+    }
+
+    /// Ask for short follow-up questions to the latest reply in `messages`
+    async fn suggest_follow_ups(
+        &self,
+        mut messages: Vec<(String, String)>,
+        overrides: GenerationOverrides,
+    ) -> AppResult<Vec<String>> {
+        messages.push(("user".to_string(), FOLLOW_UP_INSTRUCTIONS.to_string()));
+
+        let overrides = GenerationOverrides {
+            max_tokens: Some(100),
+            ..overrides
+        };
+        let reply = self.send_message_with(messages, &overrides).await?;
+
+        Ok(parse_follow_ups(&reply.answer))
+    }
+}
+
+/// Text-to-speech service
+#[async_trait]
+pub trait TtsProvider: Send + Sync {
+    /// Synthesize speech, returning encoded audio (MP3)
+    async fn synthesize_speech(&self, text: &str) -> AppResult<Vec<u8>>;
+
+    /// Check whether the service is reachable
+    async fn check_connectivity(&self) -> AppResult<bool>;
+}
+
+/// Environment variable that switches every stage to the mock provider
+const MOCK_PROVIDERS_ENV: &str = "CMAC_MOCK_PROVIDERS";
+
+/// Whether `CMAC_MOCK_PROVIDERS` is set to anything other than "0" or "false"
+pub fn mock_providers_forced() -> bool {
+    std::env::var(MOCK_PROVIDERS_ENV)
+        .is_ok_and(|value| !matches!(value.trim(), "" | "0") && !value.trim().eq_ignore_ascii_case("false"))
+}
+
+/// Whether text-to-speech is handled by the mock provider
+pub fn tts_is_mock(config: &AppConfig) -> bool {
+    config.providers.tts == TtsProviderKind::Mock || mock_providers_forced()
+}
+
+/// Create the configured speech-to-text provider
+pub fn stt_provider(config: &AppConfig, api_keys: &ApiKeys) -> AppResult<Box<dyn SttProvider>> {
+    let config = &bandwidth::apply(config);
+    let provider: Box<dyn SttProvider> = match config.providers.stt {
+        _ if mock_providers_forced() => Box::new(MockClient::new(config.mock.clone())),
+        SttProviderKind::Whisper => Box::new(WhisperClient::new(config.whisper.clone(), api_keys.whisper.clone())?),
+        SttProviderKind::AzureOpenAi => Box::new(AzureOpenAiClient::new(config, api_keys.azure.clone())?),
+        SttProviderKind::Mock => Box::new(MockClient::new(config.mock.clone())),
+    };
+    Ok(chaos::stt(compress::stt(provider, config), config))
+}
+
+/// Create the configured LLM provider
+pub fn llm_provider(config: &AppConfig, api_keys: &ApiKeys) -> AppResult<Box<dyn LlmProvider>> {
+    let config = &bandwidth::apply(config);
+    let provider: Box<dyn LlmProvider> = match config.providers.llm {
+        _ if mock_providers_forced() => Box::new(MockClient::new(config.mock.clone())),
+        LlmProviderKind::OpenWebUi => {
+            Box::new(OpenWebUiClient::new(config.openwebui.clone(), api_keys.openwebui.clone())?)
+        }
+        LlmProviderKind::AzureOpenAi => Box::new(AzureOpenAiClient::new(config, api_keys.azure.clone())?),
+        LlmProviderKind::OpenRouter => Box::new(OpenRouterClient::new(config, api_keys.openrouter.clone())?),
+        LlmProviderKind::Gemini => Box::new(GeminiClient::new(config, api_keys.gemini.clone())?),
+        LlmProviderKind::Mock => Box::new(MockClient::new(config.mock.clone())),
+    };
+    Ok(chaos::llm(provider, config))
+}
+
+/// Create the configured text-to-speech provider
+pub fn tts_provider(config: &AppConfig, api_keys: &ApiKeys) -> AppResult<Box<dyn TtsProvider>> {
+    let config = &bandwidth::apply(config);
+    let provider: Box<dyn TtsProvider> = match config.providers.tts {
+        _ if mock_providers_forced() => Box::new(MockClient::new(config.mock.clone())),
+        TtsProviderKind::ElevenLabs => {
+            Box::new(ElevenLabsClient::new(config.elevenlabs.clone(), api_keys.elevenlabs.clone())?)
+        }
+        TtsProviderKind::AzureOpenAi => Box::new(AzureOpenAiClient::new(config, api_keys.azure.clone())?),
+        TtsProviderKind::Mock => Box::new(MockClient::new(config.mock.clone())),
+    };
+    Ok(chaos::tts(provider, config))
+}
+
+#[async_trait]
+impl SttProvider for WhisperClient {
+    async fn transcribe_audio(&self, audio_data: Vec<u8>, filename: &str) -> AppResult<String> {
+        WhisperClient::transcribe_audio(self, audio_data, filename).await
+    }
+
+    async fn transcribe_candidates(&self, audio_data: Vec<u8>, filename: &str) -> AppResult<Vec<TranscriptionCandidate>> {
+        WhisperClient::transcribe_candidates(self, audio_data, filename).await
+    }
+
+    async fn check_connectivity(&self) -> AppResult<bool> {
+        WhisperClient::check_connectivity(self).await
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenWebUiClient {
+    async fn send_message_with(
+        &self,
+        messages: Vec<(String, String)>,
+        overrides: &GenerationOverrides,
+    ) -> AppResult<LlmReply> {
+        OpenWebUiClient::send_message_with(self, messages, overrides).await
+    }
+
+    async fn stream_message_with(
+        &self,
+        messages: Vec<(String, String)>,
+        overrides: &GenerationOverrides,
+        on_delta: &mut (dyn for<'d> FnMut(&'d str) + Send),
+    ) -> AppResult<LlmReply> {
+        OpenWebUiClient::stream_message_with(self, messages, overrides, on_delta).await
+    }
+
+    async fn list_models(&self) -> AppResult<Vec<ModelInfo>> {
+        OpenWebUiClient::list_models(self).await
+    }
+
+    async fn check_connectivity(&self) -> AppResult<bool> {
+        OpenWebUiClient::check_connectivity(self).await
+    }
+
+    async fn embed(&self, texts: &[String], model: &str) -> AppResult<Vec<Vec<f32>>> {
+        OpenWebUiClient::embed(self, texts, model).await
+    }
+}
+
+#[async_trait]
+impl TtsProvider for ElevenLabsClient {
+    async fn synthesize_speech(&self, text: &str) -> AppResult<Vec<u8>> {
+        ElevenLabsClient::synthesize_speech(self, text).await
+    }
+
+    async fn check_connectivity(&self) -> AppResult<bool> {
+        ElevenLabsClient::check_connectivity(self).await
+    }
+}
+
+/// Extract questions from a follow-up response, stripping list markers and quotes
+fn parse_follow_ups(response: &str) -> Vec<String> {
+    response
+        .lines()
+        .map(|line| {
+            line.trim()
+                .trim_start_matches(|c: char| c.is_ascii_digit() || matches!(c, '-' | '*' | '•' | '.' | ')'))
+                .trim()
+                .trim_matches('"')
+                .to_string()
+        })
+        .filter(|line| !line.is_empty())
+        .take(MAX_FOLLOW_UPS)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_follow_ups() {
+        let response = "1. What about tomorrow?\n\n- \"Will it rain?\"\n* How windy is it?\n4) One too many?";
+        assert_eq!(
+            parse_follow_ups(response),
+            vec!["What about tomorrow?", "Will it rain?", "How windy is it?"]
+        );
+    }
+
+    #[test]
+    fn test_providers_follow_config() {
+        let mut config = AppConfig::default();
+        let api_keys = ApiKeys::none();
+        assert!(llm_provider(&config, &api_keys).is_ok());
+
+        config.providers.llm = LlmProviderKind::AzureOpenAi;
+        config.azure.chat_deployment = "gpt-4o".to_string();
+        assert!(llm_provider(&config, &api_keys).is_ok());
+
+        config.providers.llm = LlmProviderKind::OpenRouter;
+        assert!(llm_provider(&config, &api_keys).is_ok());
+
+        config.providers.llm = LlmProviderKind::Gemini;
+        assert!(llm_provider(&config, &api_keys).is_ok());
+
+        config.providers.llm = LlmProviderKind::Mock;
+        assert!(llm_provider(&config, &api_keys).is_ok());
+    }
+}
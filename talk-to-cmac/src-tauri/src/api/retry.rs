@@ -0,0 +1,41 @@
+//! Retries for transient API failures
+//!
+//! Requests that fail for a reason that may clear up on its own (timeouts,
+//! server errors) are retried with exponential backoff. Errors that will not
+//! change on retry, such as a rejected API key, are returned at once, and so
+//! are rate limits, which would only be hit again.
+
+use crate::error::AppResult;
+use std::future::Future;
+use std::time::Duration;
+
+/// Attempts made before giving up
+pub const MAX_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry; it doubles for each one after that
+pub const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Run `attempt` until it succeeds, fails with a permanent error, or `MAX_ATTEMPTS` is reached
+pub async fn with_retries<T, Fut>(
+    action: &str,
+    base_delay: Duration,
+    mut attempt: impl FnMut() -> Fut,
+) -> AppResult<T>
+where
+    Fut: Future<Output = AppResult<T>>,
+{
+    let mut delay = base_delay;
+    let mut attempts = 1;
+
+    loop {
+        match attempt().await {
+            Err(e) if attempts < MAX_ATTEMPTS && e.is_transient() => {
+                tracing::warn!("{} attempt {} failed, retrying in {:?}: {}", action, attempts, delay, e);
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+                attempts += 1;
+            }
+            result => return result,
+        }
+    }
+}
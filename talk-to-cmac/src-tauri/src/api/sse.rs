@@ -0,0 +1,39 @@
+//! Server-sent events
+//!
+//! Reads the `data:` lines of a streaming HTTP response. Lines may be split
+//! across network chunks, so bytes are buffered until a full line arrives.
+
+use crate::error::{AppError, AppResult};
+
+/// Call `on_data` with the payload of each `data:` line until the stream ends or it returns `false`
+pub async fn read_events(
+    mut response: reqwest::Response,
+    map_err: impl Fn(reqwest::Error) -> AppError,
+    mut on_data: impl FnMut(&str) -> AppResult<bool>,
+) -> AppResult<()> {
+    let mut buffer: Vec<u8> = Vec::new();
+
+    loop {
+        let chunk = response.chunk().await.map_err(&map_err)?;
+        let finished = chunk.is_none();
+        match chunk {
+            Some(chunk) => buffer.extend_from_slice(&chunk),
+            // Flush a final line that has no trailing newline
+            None => buffer.push(b'\n'),
+        }
+
+        while let Some(end) = buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(data) = line.trim().strip_prefix("data:") {
+                if !on_data(data.trim())? {
+                    return Ok(());
+                }
+            }
+        }
+
+        if finished {
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,222 @@
+//! Jira and ServiceNow ticket creation
+//!
+//! Opens a Jira issue through the REST API v2 or a ServiceNow record through the
+//! Table API, and returns the ticket's number and a link to it.
+
+use super::http;
+use crate::config::{TicketSystem, TicketingConfig};
+use crate::error::{AppResult, TicketingError};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::Duration;
+
+/// Ticket created in the ticket system
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ticket {
+    /// Issue key or record number (e.g., "OPS-123", "INC0010042")
+    pub number: String,
+
+    /// Link to the ticket in the ticket system's web UI
+    pub url: String,
+}
+
+/// Jira create-issue response
+#[derive(Debug, Deserialize)]
+struct JiraIssue {
+    key: String,
+}
+
+/// ServiceNow Table API response
+#[derive(Debug, Deserialize)]
+struct ServiceNowResponse {
+    result: ServiceNowRecord,
+}
+
+#[derive(Debug, Deserialize)]
+struct ServiceNowRecord {
+    sys_id: String,
+    number: String,
+}
+
+/// Ticket system client
+pub struct TicketingClient {
+    client: reqwest::Client,
+    config: TicketingConfig,
+    token: Option<String>,
+}
+
+impl TicketingClient {
+    /// Create a client for the configured ticket system
+    pub fn new(config: TicketingConfig, token: Option<String>) -> AppResult<Self> {
+        let client = http::client(Duration::from_secs(config.timeout_secs))
+            .map_err(|e| TicketingError::RequestFailed(e.to_string()))?;
+
+        Ok(Self { client, config, token })
+    }
+
+    /// Open a ticket with `summary` as its title
+    pub async fn create(&self, summary: &str, description: &str) -> AppResult<Ticket> {
+        let base_url = self.config.base_url.trim_end_matches('/');
+        if base_url.is_empty() {
+            return Err(TicketingError::NotConfigured("base URL").into());
+        }
+
+        let request = match self.config.system {
+            TicketSystem::Jira => {
+                if self.config.project_key.is_empty() {
+                    return Err(TicketingError::NotConfigured("Jira project key").into());
+                }
+                self.client.post(format!("{}/rest/api/2/issue", base_url)).json(&json!({
+                    "fields": {
+                        "project": { "key": self.config.project_key },
+                        "summary": summary,
+                        "description": description,
+                        "issuetype": { "name": self.config.issue_type },
+                    }
+                }))
+            }
+            TicketSystem::ServiceNow => self
+                .client
+                .post(format!("{}/api/now/table/{}", base_url, self.config.table))
+                .header("Accept", "application/json")
+                .json(&json!({
+                    "short_description": summary,
+                    "description": description,
+                })),
+        };
+
+        let request = match (&self.token, self.config.username.is_empty()) {
+            (None, _) => return Err(TicketingError::NotConfigured("API token").into()),
+            (Some(token), true) => request.bearer_auth(token),
+            (Some(token), false) => request.basic_auth(&self.config.username, Some(token)),
+        };
+
+        tracing::info!("Opening a {:?} ticket at {}", self.config.system, base_url);
+        let response = http::send("ticketing", request).await.map_err(|e| {
+            if e.is_timeout() {
+                TicketingError::Timeout
+            } else {
+                TicketingError::RequestFailed(e.to_string())
+            }
+        })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(match status.as_u16() {
+                401 | 403 => TicketingError::AuthenticationFailed,
+                400 | 404 | 422 => TicketingError::Rejected(body),
+                _ => TicketingError::RequestFailed(format!("HTTP {}", status)),
+            }
+            .into());
+        }
+
+        let ticket = match self.config.system {
+            TicketSystem::Jira => {
+                let issue = response
+                    .json::<JiraIssue>()
+                    .await
+                    .map_err(|e| TicketingError::InvalidResponse(e.to_string()))?;
+                Ticket {
+                    url: format!("{}/browse/{}", base_url, issue.key),
+                    number: issue.key,
+                }
+            }
+            TicketSystem::ServiceNow => {
+                let record = response
+                    .json::<ServiceNowResponse>()
+                    .await
+                    .map_err(|e| TicketingError::InvalidResponse(e.to_string()))?
+                    .result;
+                Ticket {
+                    url: format!("{}/nav_to.do?uri={}.do?sys_id={}", base_url, self.config.table, record.sys_id),
+                    number: record.number,
+                }
+            }
+        };
+
+        tracing::info!("Opened ticket {}", ticket.number);
+        Ok(ticket)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+    use crate::test_support::*;
+    use wiremock::matchers::body_partial_json;
+
+    fn config(server: &MockServer, system: TicketSystem) -> TicketingConfig {
+        TicketingConfig {
+            enabled: true,
+            system,
+            base_url: format!("{}/", server.uri()),
+            project_key: "OPS".to_string(),
+            ..TicketingConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_jira_issue_is_created() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/rest/api/2/issue"))
+            .and(header("authorization", "Bearer secret"))
+            .and(body_partial_json(json!({
+                "fields": { "project": { "key": "OPS" }, "summary": "Broken compressor", "issuetype": { "name": "Task" } }
+            })))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({ "id": "10001", "key": "OPS-42" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = TicketingClient::new(config(&server, TicketSystem::Jira), Some("secret".to_string())).unwrap();
+        let ticket = client.create("Broken compressor", "Dictated").await.unwrap();
+        assert_eq!(ticket.number, "OPS-42");
+        assert_eq!(ticket.url, format!("{}/browse/OPS-42", server.uri()));
+    }
+
+    #[tokio::test]
+    async fn test_servicenow_record_is_created_with_basic_auth() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/now/table/incident"))
+            .and(header("authorization", "Basic dGVjaDpzZWNyZXQ="))
+            .and(body_partial_json(json!({ "short_description": "Broken compressor" })))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+                "result": { "sys_id": "abc123", "number": "INC0010042" }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut config = config(&server, TicketSystem::ServiceNow);
+        config.username = "tech".to_string();
+        let client = TicketingClient::new(config, Some("secret".to_string())).unwrap();
+        let ticket = client.create("Broken compressor", "Dictated").await.unwrap();
+        assert_eq!(ticket.number, "INC0010042");
+        assert_eq!(ticket.url, format!("{}/nav_to.do?uri=incident.do?sys_id=abc123", server.uri()));
+    }
+
+    #[tokio::test]
+    async fn test_rejected_credentials_and_missing_token() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+
+        let client = TicketingClient::new(config(&server, TicketSystem::Jira), Some("wrong".to_string())).unwrap();
+        assert!(matches!(
+            client.create("Broken compressor", "").await,
+            Err(AppError::Ticketing(TicketingError::AuthenticationFailed))
+        ));
+
+        let client = TicketingClient::new(config(&server, TicketSystem::Jira), None).unwrap();
+        assert!(matches!(
+            client.create("Broken compressor", "").await,
+            Err(AppError::Ticketing(TicketingError::NotConfigured(_)))
+        ));
+    }
+}
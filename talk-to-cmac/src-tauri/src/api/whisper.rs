@@ -3,8 +3,12 @@
 //! Handles audio file upload and transcription using OpenAI's Whisper API
 //! or compatible endpoints with retry logic and timeout support.
 
-use crate::config::WhisperConfig;
+use super::endpoint;
+use super::http;
+use super::retry;
+use crate::config::{AppConfig, SttProviderKind, WhisperConfig};
 use crate::error::{AppResult, WhisperError};
+use crate::log_privacy;
 use reqwest::multipart::{Form, Part};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
@@ -14,6 +18,9 @@ pub struct WhisperClient {
     client: reqwest::Client,
     config: WhisperConfig,
     api_key: Option<String>,
+
+    /// Delay before the first retry of a failed request
+    retry_delay: Duration,
 }
 
 /// Whisper API transcription response
@@ -24,6 +31,98 @@ pub struct TranscriptionResponse {
     pub language: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub duration: Option<f32>,
+
+    /// Segments with their confidence, in `verbose_json` responses
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub segments: Vec<TranscriptionSegment>,
+}
+
+/// A segment of a `verbose_json` transcription
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TranscriptionSegment {
+    /// Average log probability of the segment's tokens
+    pub avg_logprob: f32,
+}
+
+impl TranscriptionResponse {
+    /// Probability-like confidence (0.0-1.0) from the segments' average log probability
+    fn confidence(&self) -> Option<f32> {
+        if self.segments.is_empty() {
+            return None;
+        }
+        let mean = self.segments.iter().map(|segment| segment.avg_logprob).sum::<f32>() / self.segments.len() as f32;
+        Some(mean.exp().clamp(0.0, 1.0))
+    }
+}
+
+/// A transcription hypothesis
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptionCandidate {
+    pub text: String,
+
+    /// How sure the provider is (0.0-1.0), if it says
+    pub confidence: Option<f32>,
+}
+
+/// Fail if `audio_data` is over the 25 MB upload limit
+fn check_size(audio_data: &[u8]) -> AppResult<()> {
+    const MAX_SIZE: usize = 25 * 1024 * 1024;
+    if audio_data.len() > MAX_SIZE {
+        return Err(WhisperError::AudioFileTooLarge.into());
+    }
+    Ok(())
+}
+
+/// Whether two transcriptions have the same words, ignoring case and punctuation
+fn same_words(a: &str, b: &str) -> bool {
+    let words = |text: &str| {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect::<Vec<_>>()
+    };
+    words(a) == words(b)
+}
+
+/// Settings to transcribe a recording again with, instead of the configured ones
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranscriptionOverrides {
+    /// Language code, or an empty string to let the provider detect the language
+    pub language: Option<String>,
+
+    /// Whisper model, or Azure OpenAI transcription deployment
+    pub model: Option<String>,
+
+    /// Sampling temperature
+    pub temperature: Option<f32>,
+
+    /// Speech-to-text provider
+    pub provider: Option<SttProviderKind>,
+}
+
+impl TranscriptionOverrides {
+    /// `config` with these overrides applied
+    pub fn apply(&self, config: &AppConfig) -> AppConfig {
+        let mut config = config.clone();
+        if let Some(provider) = self.provider {
+            config.providers.stt = provider;
+        }
+        if let Some(language) = &self.language {
+            config.whisper.language = Some(language.trim().to_string()).filter(|language| !language.is_empty());
+        }
+        if let Some(temperature) = self.temperature {
+            config.whisper.temperature = temperature.clamp(0.0, 1.0);
+        }
+        if let Some(model) = &self.model {
+            match config.providers.stt {
+                SttProviderKind::Whisper => config.whisper.model = model.clone(),
+                SttProviderKind::AzureOpenAi => config.azure.transcription_deployment = model.clone(),
+                SttProviderKind::Mock => {}
+            }
+        }
+        config
+    }
 }
 
 /// Whisper API error response
@@ -41,16 +140,16 @@ struct WhisperErrorDetail {
 
 impl WhisperClient {
     /// Create a new Whisper client
-    pub fn new(config: WhisperConfig, api_key: Option<String>) -> AppResult<Self> {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(config.timeout_secs))
-            .build()
+    pub fn new(mut config: WhisperConfig, api_key: Option<String>) -> AppResult<Self> {
+        config.endpoint = endpoint::whisper(&config.endpoint);
+        let client = http::client(Duration::from_secs(config.timeout_secs))
             .map_err(|e| WhisperError::TranscriptionFailed(e.to_string()))?;
 
         Ok(Self {
             client,
             config,
             api_key,
+            retry_delay: retry::RETRY_BASE_DELAY,
         })
     }
 
@@ -67,36 +166,74 @@ impl WhisperClient {
         audio_data: Vec<u8>,
         filename: &str,
     ) -> AppResult<String> {
-        // Validate audio size (25MB limit)
-        const MAX_SIZE: usize = 25 * 1024 * 1024;
-        if audio_data.len() > MAX_SIZE {
-            return Err(WhisperError::AudioFileTooLarge.into());
+        check_size(&audio_data)?;
+        tracing::info!("Transcribing audio file: {} ({} bytes)", filename, audio_data.len());
+
+        let result = retry::with_retries("Transcription", self.retry_delay, || {
+            self.try_transcribe(&audio_data, filename, self.config.temperature, "json")
+        })
+        .await?;
+
+        tracing::info!("Transcription successful: {}", log_privacy::content(&result.text));
+        Ok(result.text)
+    }
+
+    /// Transcribe audio, with alternative hypotheses if the top one is uncertain
+    ///
+    /// Whisper returns a single hypothesis, so when the top one's confidence is
+    /// below `alternatives_below`, up to `alternatives` more are sampled at rising
+    /// temperatures. The top hypothesis comes first, then the distinct
+    /// alternatives by confidence. Without `alternatives`, this is
+    /// [`WhisperClient::transcribe_audio`].
+    pub async fn transcribe_candidates(
+        &self,
+        audio_data: Vec<u8>,
+        filename: &str,
+    ) -> AppResult<Vec<TranscriptionCandidate>> {
+        if self.config.alternatives == 0 {
+            let text = self.transcribe_audio(audio_data, filename).await?;
+            return Ok(vec![TranscriptionCandidate { text, confidence: None }]);
         }
 
-        log::info!("Transcribing audio file: {} ({} bytes)", filename, audio_data.len());
+        check_size(&audio_data)?;
+        tracing::info!("Transcribing audio file with alternatives: {} ({} bytes)", filename, audio_data.len());
+
+        let sample = |temperature: f32| {
+            let audio_data = &audio_data;
+            async move {
+                let result = retry::with_retries("Transcription", self.retry_delay, || {
+                    self.try_transcribe(audio_data, filename, temperature, "verbose_json")
+                })
+                .await?;
+                let confidence = result.confidence();
+                AppResult::Ok(TranscriptionCandidate { text: result.text.trim().to_string(), confidence })
+            }
+        };
 
-        // Attempt transcription with retry logic
-        let max_retries = 3;
-        let mut last_error = None;
+        let top = sample(self.config.temperature).await?;
+        tracing::info!("Transcription successful: {} (confidence {:?})", log_privacy::content(&top.text), top.confidence);
+        if top.confidence.is_none_or(|confidence| confidence >= self.config.alternatives_below) {
+            return Ok(vec![top]);
+        }
 
-        for attempt in 1..=max_retries {
-            match self.try_transcribe(&audio_data, filename).await {
-                Ok(result) => {
-                    log::info!("Transcription successful: '{}'", result.text);
-                    return Ok(result.text);
-                }
-                Err(e) => {
-                    last_error = Some(e);
-                    if attempt < max_retries {
-                        let delay = Duration::from_secs(2u64.pow(attempt as u32 - 1));
-                        log::warn!("Transcription attempt {} failed, retrying in {:?}", attempt, delay);
-                        tokio::time::sleep(delay).await;
+        let mut alternatives: Vec<TranscriptionCandidate> = Vec::new();
+        for n in 1..=self.config.alternatives {
+            let temperature = (self.config.temperature + 0.3 * n as f32).min(1.0);
+            match sample(temperature).await {
+                Ok(candidate) => {
+                    let known = std::iter::once(&top).chain(&alternatives).any(|known| same_words(&known.text, &candidate.text));
+                    if !known {
+                        alternatives.push(candidate);
                     }
                 }
+                // The top hypothesis is enough to go on
+                Err(e) => tracing::warn!("Failed to sample an alternative transcription: {}", e),
             }
         }
+        alternatives.sort_by(|a, b| b.confidence.unwrap_or(0.0).total_cmp(&a.confidence.unwrap_or(0.0)));
 
-        Err(last_error.unwrap())
+        tracing::info!("Found {} alternative transcriptions", alternatives.len());
+        Ok(std::iter::once(top).chain(alternatives).collect())
     }
 
     /// Internal transcription attempt
@@ -104,6 +241,8 @@ impl WhisperClient {
         &self,
         audio_data: &[u8],
         filename: &str,
+        temperature: f32,
+        response_format: &str,
     ) -> AppResult<TranscriptionResponse> {
         // Create multipart form
         let audio_part = Part::bytes(audio_data.to_vec())
@@ -114,15 +253,15 @@ impl WhisperClient {
         let mut form = Form::new()
             .part("file", audio_part)
             .text("model", self.config.model.clone())
-            .text("response_format", "json");
+            .text("response_format", response_format.to_string());
 
         // Add optional parameters
         if let Some(language) = &self.config.language {
             form = form.text("language", language.clone());
         }
 
-        if self.config.temperature > 0.0 {
-            form = form.text("temperature", self.config.temperature.to_string());
+        if temperature > 0.0 {
+            form = form.text("temperature", temperature.to_string());
         }
 
         // Build request
@@ -136,8 +275,7 @@ impl WhisperClient {
         }
 
         // Send request
-        let response = request
-            .send()
+        let response = http::send("whisper", request)
             .await
             .map_err(|e| {
                 if e.is_timeout() {
@@ -150,15 +288,15 @@ impl WhisperClient {
         // Check status
         let status = response.status();
         if !status.is_success() {
-            // Try to parse error response
-            if let Ok(error_response) = response.json::<WhisperErrorResponse>().await {
-                return Err(match status.as_u16() {
-                    401 | 403 => WhisperError::AuthenticationFailed,
-                    429 => WhisperError::RateLimitExceeded,
-                    _ => WhisperError::TranscriptionFailed(error_response.error.message),
-                }.into());
-            }
-            return Err(WhisperError::TranscriptionFailed(format!("HTTP {}", status)).into());
+            // The status decides the error; the body only adds a message when it has one
+            return Err(match status.as_u16() {
+                401 | 403 => WhisperError::AuthenticationFailed,
+                429 => WhisperError::RateLimitExceeded,
+                _ => match response.json::<WhisperErrorResponse>().await {
+                    Ok(error_response) => WhisperError::TranscriptionFailed(error_response.error.message),
+                    Err(_) => WhisperError::TranscriptionFailed(format!("HTTP {}", status)),
+                },
+            }.into());
         }
 
         // Parse successful response
@@ -179,11 +317,11 @@ impl WhisperClient {
     pub async fn check_connectivity(&self) -> AppResult<bool> {
         // Simple connectivity check - try to make a minimal request
         // This is a basic implementation; you might want to use a dedicated health endpoint
-        let response = self.client
+        let request = self.client
             .get(&self.config.endpoint)
-            .timeout(Duration::from_secs(5))
-            .send()
-            .await;
+            .timeout(Duration::from_secs(5));
+
+        let response = http::send("whisper", request).await;
 
         match response {
             Ok(resp) => {
@@ -191,14 +329,15 @@ impl WhisperClient {
                 Ok(resp.status().as_u16() < 500)
             }
             Err(e) => {
-                log::warn!("Whisper connectivity check failed: {}", e);
+                tracing::warn!("Whisper connectivity check failed: {}", e);
                 Ok(false)
             }
         }
     }
 
     /// Update configuration
-    pub fn update_config(&mut self, config: WhisperConfig) {
+    pub fn update_config(&mut self, mut config: WhisperConfig) {
+        config.endpoint = endpoint::whisper(&config.endpoint);
         self.config = config;
     }
 
@@ -211,6 +350,8 @@ impl WhisperClient {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::AppError;
+    use crate::test_support::*;
 
     #[test]
     fn test_whisper_client_creation() {
@@ -220,6 +361,7 @@ mod tests {
             language: Some("en".to_string()),
             temperature: 0.0,
             timeout_secs: 30,
+            ..WhisperConfig::default()
         };
 
         let client = WhisperClient::new(config, None);
@@ -235,6 +377,7 @@ mod tests {
             language: Some("en".to_string()),
             temperature: 0.0,
             timeout_secs: 30,
+            ..WhisperConfig::default()
         };
 
         let client = WhisperClient::new(config, None).unwrap();
@@ -245,4 +388,156 @@ mod tests {
 
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_transcription_overrides() {
+        let config = AppConfig::default();
+        assert_eq!(TranscriptionOverrides::default().apply(&config).whisper.model, config.whisper.model);
+
+        let overrides = TranscriptionOverrides {
+            language: Some(" ".to_string()),
+            model: Some("whisper-large".to_string()),
+            temperature: Some(2.0),
+            provider: None,
+        };
+        let applied = overrides.apply(&config);
+        assert_eq!(applied.whisper.language, None);
+        assert_eq!(applied.whisper.model, "whisper-large");
+        assert_eq!(applied.whisper.temperature, 1.0);
+
+        let azure = TranscriptionOverrides {
+            provider: Some(SttProviderKind::AzureOpenAi),
+            ..overrides
+        };
+        let applied = azure.apply(&config);
+        assert_eq!(applied.azure.transcription_deployment, "whisper-large");
+        assert_eq!(applied.whisper.model, config.whisper.model);
+    }
+
+    fn mock_client(server: &MockServer) -> WhisperClient {
+        let config = WhisperConfig {
+            endpoint: format!("{}/v1/audio/transcriptions", server.uri()),
+            model: "whisper-1".to_string(),
+            language: None,
+            temperature: 0.0,
+            timeout_secs: 5,
+            ..WhisperConfig::default()
+        };
+        let mut client = WhisperClient::new(config, Some("test_key".to_string())).unwrap();
+        client.retry_delay = Duration::ZERO;
+        client
+    }
+
+    #[tokio::test]
+    async fn test_transcription_is_returned() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/audio/transcriptions"))
+            .and(header("authorization", "Bearer test_key"))
+            .respond_with(transcription("Hello there"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let text = mock_client(&server).transcribe_audio(vec![0; 64], "test.wav").await.unwrap();
+        assert_eq!(text, "Hello there");
+    }
+
+    #[tokio::test]
+    async fn test_status_codes_map_to_errors() {
+        let server = MockServer::start().await;
+
+        // Rejected credentials are not retried, whatever the body looks like
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(401).set_body_string("Unauthorized"))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        let result = mock_client(&server).transcribe_audio(vec![0; 64], "test.wav").await;
+        assert!(matches!(result, Err(AppError::WhisperApi(WhisperError::AuthenticationFailed))));
+
+        // Server errors are retried until the attempts run out
+        Mock::given(method("POST"))
+            .respond_with(openai_error(500, "upstream failed"))
+            .expect(retry::MAX_ATTEMPTS as u64)
+            .mount(&server)
+            .await;
+        let result = mock_client(&server).transcribe_audio(vec![0; 64], "test.wav").await;
+        match result {
+            Err(AppError::WhisperApi(WhisperError::TranscriptionFailed(message))) => assert_eq!(message, "upstream failed"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transient_failure_is_retried() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(openai_error(503, "overloaded"))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(transcription("Second time lucky"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let text = mock_client(&server).transcribe_audio(vec![0; 64], "test.wav").await.unwrap();
+        assert_eq!(text, "Second time lucky");
+    }
+
+    fn verbose(text: &str, avg_logprob: f32) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "text": text,
+            "segments": [{ "avg_logprob": avg_logprob }],
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_alternatives_for_uncertain_transcriptions() {
+        let server = MockServer::start().await;
+        let mut client = mock_client(&server);
+        client.config.alternatives = 3;
+
+        Mock::given(method("POST"))
+            .respond_with(verbose("Wreck a nice beach.", -1.2))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(verbose("wreck a nice beach", -1.0))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(verbose("Recognize speech.", -0.5))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let candidates = client.transcribe_candidates(vec![0; 64], "test.wav").await.unwrap();
+        let texts: Vec<&str> = candidates.iter().map(|candidate| candidate.text.as_str()).collect();
+        assert_eq!(texts, ["Wreck a nice beach.", "Recognize speech."]);
+        assert!((candidates[0].confidence.unwrap() - (-1.2f32).exp()).abs() < 1e-5);
+    }
+
+    #[tokio::test]
+    async fn test_confident_transcriptions_have_no_alternatives() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(verbose("Hello there", -0.05))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut client = mock_client(&server);
+        client.config.alternatives = 3;
+        let candidates = client.transcribe_candidates(vec![0; 64], "test.wav").await.unwrap();
+        assert_eq!(candidates.len(), 1);
+    }
 }
@@ -0,0 +1,295 @@
+//! Audit log of outbound API calls
+//!
+//! Every request the app sends to a remote service goes through `http::send`,
+//! which appends one line here (IMAP sessions add one line per session): the service, method and endpoint, payload size,
+//! status, and duration. Query strings are left out of the endpoint since some
+//! services accept keys there. Request bodies are only recorded when
+//! `privacy.audit_log_content` is on, history encryption is off, and the
+//! conversation is not incognito, so the log never holds plaintext the history
+//! would not.
+//!
+//! Once the log reaches [`MAX_LOG_BYTES`] it is moved aside to
+//! `api_audit.1.jsonl`, replacing the previous one, so at most twice that is
+//! kept. The log is cleared when user data is purged or erased.
+
+use crate::config::{ConfigManager, PrivacyConfig};
+use crate::error::{AppResult, ConfigError};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Size at which the log is moved aside and a new one started
+pub const MAX_LOG_BYTES: u64 = 1024 * 1024;
+
+/// Bytes read at a time when reading the log from the end
+const READ_BLOCK: u64 = 64 * 1024;
+
+/// Whether request bodies are recorded: `privacy.audit_log_content` is on and history encryption off
+static INCLUDE_CONTENT: AtomicBool = AtomicBool::new(false);
+
+/// Whether the current conversation is incognito, which keeps request bodies out of the log
+static INCOGNITO: AtomicBool = AtomicBool::new(false);
+
+/// Serializes writes and rotation of the log files
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// One outbound API call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Unix timestamp of the call
+    pub timestamp: u64,
+
+    /// Service called (e.g. "whisper", "openwebui", "usage_reports")
+    pub service: String,
+
+    /// HTTP method, or the protocol of a non-HTTP session (e.g. "IMAP")
+    pub method: String,
+
+    /// Scheme, host, and path called, without the query string
+    pub endpoint: String,
+
+    /// Size of the request body in bytes, when known up front (not for streamed uploads)
+    pub payload_bytes: Option<u64>,
+
+    /// HTTP status of the response; `None` when no response arrived
+    pub status: Option<u16>,
+
+    /// Why no response arrived ("timeout", "connect", or "request")
+    pub error: Option<String>,
+
+    /// Time until the response headers arrived, in milliseconds
+    pub duration_ms: u64,
+
+    /// Request body, only recorded when content logging is on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+}
+
+impl AuditEntry {
+    /// Entry for `request`, before it is sent
+    pub fn new(service: &str, request: &reqwest::Request) -> Self {
+        let url = request.url();
+        let body = request.body().and_then(|body| body.as_bytes());
+
+        Self {
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+            service: service.to_string(),
+            method: request.method().to_string(),
+            endpoint: format!("{}{}", url.origin().ascii_serialization(), url.path()),
+            payload_bytes: body.map(|bytes| bytes.len() as u64),
+            status: None,
+            error: None,
+            duration_ms: 0,
+            content: body
+                .filter(|_| INCLUDE_CONTENT.load(Ordering::Relaxed) && !INCOGNITO.load(Ordering::Relaxed))
+                .map(|bytes| String::from_utf8_lossy(bytes).into_owned()),
+        }
+    }
+
+    /// Entry for a finished non-HTTP session, which has no status or recorded content
+    pub fn session(service: &str, protocol: &str, endpoint: String, duration: Duration, error: Option<&str>) -> Self {
+        Self {
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+            service: service.to_string(),
+            method: protocol.to_string(),
+            endpoint,
+            payload_bytes: None,
+            status: None,
+            error: error.map(str::to_string),
+            duration_ms: duration.as_millis() as u64,
+            content: None,
+        }
+    }
+
+    /// Fill in how the call ended
+    pub fn finish(&mut self, result: &reqwest::Result<reqwest::Response>, duration: Duration) {
+        self.duration_ms = duration.as_millis() as u64;
+        match result {
+            Ok(response) => self.status = Some(response.status().as_u16()),
+            Err(e) if e.is_timeout() => self.error = Some("timeout".to_string()),
+            Err(e) if e.is_connect() => self.error = Some("connect".to_string()),
+            Err(_) => self.error = Some("request".to_string()),
+        }
+    }
+}
+
+/// Record request bodies from now on if `privacy` allows it, or stop recording them
+///
+/// Bodies are never recorded while history encryption is on, since the log is plaintext.
+pub fn apply_privacy(privacy: &PrivacyConfig) {
+    INCLUDE_CONTENT.store(content_allowed(privacy), Ordering::Relaxed);
+}
+
+fn content_allowed(privacy: &PrivacyConfig) -> bool {
+    privacy.audit_log_content && !privacy.encrypt_history
+}
+
+/// Keep request bodies out of the log while the conversation is incognito
+pub fn set_incognito(incognito: bool) {
+    INCOGNITO.store(incognito, Ordering::Relaxed);
+}
+
+/// Append an entry to the log, warning instead of failing the call if it cannot be written
+pub fn record(entry: &AuditEntry) {
+    if let Err(e) = append(entry) {
+        tracing::warn!("Failed to write API audit log: {}", e);
+    }
+}
+
+/// Read the most recent log entries, newest first
+///
+/// Only the end of the log is read, continuing into the rotated log if needed.
+pub fn read(limit: usize) -> AppResult<Vec<AuditEntry>> {
+    let (current, rotated) = log_paths()?;
+    read_from(&current, &rotated, limit).map_err(|e| ConfigError::LoadFailed(e.to_string()).into())
+}
+
+/// Delete the log and the rotated log
+pub fn clear() -> AppResult<()> {
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let (current, rotated) = log_paths()?;
+    remove_logs(&current, &rotated).map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
+    Ok(())
+}
+
+fn append(entry: &AuditEntry) -> AppResult<()> {
+    let line = serde_json::to_string(entry).map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
+
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let (current, rotated) = log_paths()?;
+    append_to(&current, &rotated, &line, MAX_LOG_BYTES).map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
+    Ok(())
+}
+
+/// Append `line` to `current`, first moving it to `rotated` if it has reached `max_bytes`
+fn append_to(current: &Path, rotated: &Path, line: &str, max_bytes: u64) -> io::Result<()> {
+    if fs::metadata(current).is_ok_and(|metadata| metadata.len() >= max_bytes) {
+        fs::rename(current, rotated)?;
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(current)?;
+    writeln!(file, "{}", line)
+}
+
+fn remove_logs(current: &Path, rotated: &Path) -> io::Result<()> {
+    for path in [current, rotated] {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Newest `limit` entries across `current` and then `rotated`
+fn read_from(current: &Path, rotated: &Path, limit: usize) -> io::Result<Vec<AuditEntry>> {
+    let mut lines = tail_lines(current, limit)?;
+    if lines.len() < limit {
+        lines.extend(tail_lines(rotated, limit - lines.len())?);
+    }
+
+    Ok(lines.iter().filter_map(|line| serde_json::from_str(line).ok()).collect())
+}
+
+/// Last `limit` lines of the file at `path`, newest first, reading it backwards in blocks
+fn tail_lines(path: &Path, limit: usize) -> io::Result<Vec<String>> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    // More newlines than `limit` means the tail holds `limit` complete lines,
+    // since the log ends with a newline
+    let mut end = file.metadata()?.len();
+    let mut tail = Vec::new();
+    let mut newlines = 0;
+    while end > 0 && newlines <= limit {
+        let start = end.saturating_sub(READ_BLOCK);
+        let mut block = vec![0; (end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut block)?;
+
+        newlines += block.iter().filter(|&&byte| byte == b'\n').count();
+        block.extend_from_slice(&tail);
+        tail = block;
+        end = start;
+    }
+
+    Ok(String::from_utf8_lossy(&tail)
+        .lines()
+        .rev()
+        .take(limit)
+        .map(str::to_string)
+        .collect())
+}
+
+fn log_paths() -> AppResult<(PathBuf, PathBuf)> {
+    let data_dir = ConfigManager::new()?.data_dir();
+    Ok((data_dir.join("api_audit.jsonl"), data_dir.join("api_audit.1.jsonl")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_leaves_out_query_and_content_by_default() {
+        let request = reqwest::Client::new()
+            .post("https://generativelanguage.googleapis.com/v1beta/models?key=secret")
+            .body("{\"text\":\"hello\"}")
+            .build()
+            .unwrap();
+
+        let entry = AuditEntry::new("gemini", &request);
+        assert_eq!(entry.method, "POST");
+        assert_eq!(entry.endpoint, "https://generativelanguage.googleapis.com/v1beta/models");
+        assert_eq!(entry.payload_bytes, Some(16));
+        assert_eq!(entry.content, None);
+        assert!(!serde_json::to_string(&entry).unwrap().contains("content"));
+    }
+
+    #[test]
+    fn test_log_is_rotated_and_read_from_the_end() {
+        let dir = std::env::temp_dir().join(format!("talk-to-cmac-audit-{:x}", rand::random::<u64>()));
+        fs::create_dir_all(&dir).unwrap();
+        let (current, rotated) = (dir.join("api_audit.jsonl"), dir.join("api_audit.1.jsonl"));
+
+        let entry = |n: u64| AuditEntry::session("email", "IMAP", format!("imap://mail{}", n), Duration::ZERO, None);
+        let line_len = serde_json::to_string(&entry(0)).unwrap().len() as u64 + 1;
+        for n in 0..10 {
+            append_to(&current, &rotated, &serde_json::to_string(&entry(n)).unwrap(), 4 * line_len).unwrap();
+        }
+
+        // 0-3 were dropped by the second rotation, 4-7 are in the rotated log
+        let endpoints = |limit| -> Vec<String> {
+            read_from(&current, &rotated, limit).unwrap().into_iter().map(|entry| entry.endpoint).collect()
+        };
+        assert_eq!(endpoints(3), ["imap://mail9", "imap://mail8", "imap://mail7"]);
+        assert_eq!(endpoints(100).len(), 6);
+        assert_eq!(endpoints(100).last().unwrap(), "imap://mail4");
+
+        // As when user data is purged or erased
+        remove_logs(&current, &rotated).unwrap();
+        assert!(!current.exists() && !rotated.exists());
+        assert!(endpoints(100).is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_content_is_never_recorded_with_encrypted_history() {
+        let mut privacy = PrivacyConfig {
+            audit_log_content: true,
+            ..PrivacyConfig::default()
+        };
+        assert!(privacy.encrypt_history);
+        assert!(!content_allowed(&privacy));
+
+        privacy.encrypt_history = false;
+        assert!(content_allowed(&privacy));
+    }
+}
@@ -0,0 +1,64 @@
+//! Low-bandwidth mode
+//!
+//! `low_bandwidth.enabled` switches several settings at once for technicians
+//! on metered hotspot connections. Rather than rewriting the saved settings,
+//! which would lose them when the mode is turned off, [`apply`] lays the
+//! low-bandwidth values over a copy of the configuration wherever they take
+//! effect: when a provider client is created, when a streamed reply is
+//! synthesized, before follow-up suggestions, and in the LLM keep-alive.
+
+use crate::config::AppConfig;
+
+/// `config` with the low-bandwidth settings in effect, if the mode is enabled
+///
+/// Applying it twice changes nothing more.
+pub fn apply(config: &AppConfig) -> AppConfig {
+    let mut config = config.clone();
+    let settings = &config.low_bandwidth;
+    if !settings.enabled {
+        return config;
+    }
+
+    let max_tokens = settings.max_tokens.max(1);
+    let output_format = settings.tts_output_format.clone();
+
+    config.audio.compress_uploads = true;
+    config.audio.tts_concurrency = 1;
+    config.openwebui.max_tokens = Some(config.openwebui.max_tokens.map_or(max_tokens, |limit| limit.min(max_tokens)));
+    config.openwebui.voice_max_tokens = config.openwebui.voice_max_tokens.min(max_tokens);
+    config.openwebui.follow_up_suggestions = false;
+    config.warmup.keep_alive_interval_secs = 0;
+    if !output_format.is_empty() {
+        config.elevenlabs.output_format = Some(output_format);
+    }
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlay_only_while_enabled() {
+        let mut config = AppConfig::default();
+        config.openwebui.max_tokens = Some(1024);
+        config.openwebui.follow_up_suggestions = true;
+        config.warmup.keep_alive_interval_secs = 240;
+
+        let off = apply(&config);
+        assert_eq!(off.openwebui.max_tokens, Some(1024));
+        assert!(!off.audio.compress_uploads);
+
+        config.low_bandwidth.enabled = true;
+        let on = apply(&config);
+        assert_eq!(on.openwebui.max_tokens, Some(256));
+        assert_eq!(on.openwebui.voice_max_tokens, 200);
+        assert_eq!(on.elevenlabs.output_format.as_deref(), Some("mp3_22050_32"));
+        assert_eq!((on.audio.tts_concurrency, on.warmup.keep_alive_interval_secs), (1, 0));
+        assert!(on.audio.compress_uploads && !on.openwebui.follow_up_suggestions);
+
+        // Lower limits already set are kept
+        config.openwebui.max_tokens = Some(100);
+        assert_eq!(apply(&apply(&config)).openwebui.max_tokens, Some(100));
+    }
+}
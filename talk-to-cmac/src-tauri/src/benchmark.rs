@@ -0,0 +1,277 @@
+//! Provider latency benchmark
+//!
+//! Sends the self-test's standard transcription, chat, and synthesis requests
+//! to every configured provider of each stage several times and compares how
+//! long they take, so users can pick the fastest services for their network.
+//!
+//! A provider counts as configured when it is the selected one or its API key
+//! is set. Mock providers are only measured when selected. Requests run one at
+//! a time so providers do not compete for bandwidth.
+
+use crate::api::provider::mock_providers_forced;
+use crate::config::{ApiKeys, AppConfig, LlmProviderKind, SttProviderKind, TtsProviderKind};
+use crate::selftest;
+use crate::telemetry::Stage;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+
+/// Runs per provider when the caller does not say
+pub const DEFAULT_ITERATIONS: u32 = 3;
+
+/// Most runs per provider, to bound how long a benchmark takes
+const MAX_ITERATIONS: u32 = 10;
+
+/// Latency of one provider for one stage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderBenchmark {
+    /// "stt", "llm", or "tts"
+    pub stage: String,
+
+    /// Provider, as named in the settings
+    pub provider: String,
+
+    /// Whether this is the provider currently selected for the stage
+    pub selected: bool,
+
+    /// Requests that succeeded
+    pub successes: u32,
+
+    /// Requests that failed
+    pub failures: u32,
+
+    /// Latency statistics over successful requests (absent if none succeeded)
+    pub stats: Option<LatencyStats>,
+
+    /// Error of the most recent failed request
+    pub last_error: Option<String>,
+}
+
+/// Latency statistics (milliseconds)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatencyStats {
+    pub min_ms: u64,
+    pub median_ms: u64,
+    pub mean_ms: u64,
+    pub max_ms: u64,
+}
+
+/// Result of a benchmark run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkReport {
+    /// Requests sent to each provider
+    pub iterations: u32,
+
+    /// Results grouped by stage, fastest median first within each stage
+    pub results: Vec<ProviderBenchmark>,
+
+    /// Time taken by the whole benchmark (milliseconds)
+    pub total_ms: u64,
+}
+
+/// Benchmark every configured provider of each stage
+pub async fn run(config: &AppConfig, api_keys: &ApiKeys, iterations: u32) -> BenchmarkReport {
+    let iterations = iterations.clamp(1, MAX_ITERATIONS);
+    let started = Instant::now();
+    let mut results = Vec::new();
+
+    for (stage, candidates) in [
+        (Stage::Stt, stt_candidates(config, api_keys)),
+        (Stage::Llm, llm_candidates(config, api_keys)),
+        (Stage::Tts, tts_candidates(config, api_keys)),
+    ] {
+        let mut stage_results = Vec::new();
+        for (candidate, selected) in candidates {
+            stage_results.push(bench_provider(stage, &candidate, api_keys, selected, iterations).await);
+        }
+        stage_results.sort_by_key(|result| result.stats.as_ref().map_or(u64::MAX, |stats| stats.median_ms));
+        results.extend(stage_results);
+    }
+
+    BenchmarkReport {
+        iterations,
+        results,
+        total_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+async fn bench_provider(
+    stage: Stage,
+    config: &AppConfig,
+    api_keys: &ApiKeys,
+    selected: bool,
+    iterations: u32,
+) -> ProviderBenchmark {
+    let provider = stage.provider(config);
+    let mut durations = Vec::new();
+    let mut last_error = None;
+
+    for _ in 0..iterations {
+        let started = Instant::now();
+        let result = match stage {
+            Stage::Stt => selftest::check_stt(config, api_keys).await,
+            Stage::Llm => selftest::check_llm(config, api_keys).await,
+            Stage::Tts => selftest::check_tts(config, api_keys).await,
+        };
+        match result {
+            Ok(_) => durations.push(started.elapsed().as_millis() as u64),
+            Err(e) => last_error = Some(e.to_string()),
+        }
+    }
+
+    let stats = latency_stats(&mut durations);
+    match &stats {
+        Some(stats) => tracing::info!("Benchmark {} {}: median {} ms", stage.name(), provider, stats.median_ms),
+        None => tracing::warn!("Benchmark {} {}: every request failed", stage.name(), provider),
+    }
+
+    ProviderBenchmark {
+        stage: stage.name().to_string(),
+        provider,
+        selected,
+        successes: durations.len() as u32,
+        failures: iterations - durations.len() as u32,
+        stats,
+        last_error,
+    }
+}
+
+fn latency_stats(durations: &mut [u64]) -> Option<LatencyStats> {
+    if durations.is_empty() {
+        return None;
+    }
+    durations.sort_unstable();
+
+    let len = durations.len();
+    let median_ms = if len.is_multiple_of(2) {
+        (durations[len / 2 - 1] + durations[len / 2]) / 2
+    } else {
+        durations[len / 2]
+    };
+    Some(LatencyStats {
+        min_ms: durations[0],
+        median_ms,
+        mean_ms: durations.iter().sum::<u64>() / len as u64,
+        max_ms: durations[len - 1],
+    })
+}
+
+/// Configs selecting each speech-to-text provider to measure, with whether it is the selected one
+fn stt_candidates(config: &AppConfig, api_keys: &ApiKeys) -> Vec<(AppConfig, bool)> {
+    let configured = [SttProviderKind::Whisper, SttProviderKind::AzureOpenAi]
+        .into_iter()
+        .filter(|kind| match kind {
+            SttProviderKind::Whisper => api_keys.whisper.is_some(),
+            SttProviderKind::AzureOpenAi => azure_configured(config, api_keys),
+            SttProviderKind::Mock => false,
+        });
+    candidates(config, config.providers.stt, configured, |config, kind| config.providers.stt = kind)
+}
+
+/// Configs selecting each LLM provider to measure, with whether it is the selected one
+fn llm_candidates(config: &AppConfig, api_keys: &ApiKeys) -> Vec<(AppConfig, bool)> {
+    let configured = [
+        LlmProviderKind::OpenWebUi,
+        LlmProviderKind::AzureOpenAi,
+        LlmProviderKind::OpenRouter,
+        LlmProviderKind::Gemini,
+    ]
+    .into_iter()
+    .filter(|kind| match kind {
+        LlmProviderKind::OpenWebUi => api_keys.openwebui.is_some(),
+        LlmProviderKind::AzureOpenAi => azure_configured(config, api_keys),
+        LlmProviderKind::OpenRouter => api_keys.openrouter.is_some(),
+        LlmProviderKind::Gemini => api_keys.gemini.is_some(),
+        LlmProviderKind::Mock => false,
+    });
+    candidates(config, config.providers.llm, configured, |config, kind| config.providers.llm = kind)
+}
+
+/// Configs selecting each text-to-speech provider to measure, with whether it is the selected one
+fn tts_candidates(config: &AppConfig, api_keys: &ApiKeys) -> Vec<(AppConfig, bool)> {
+    let configured = [TtsProviderKind::ElevenLabs, TtsProviderKind::AzureOpenAi]
+        .into_iter()
+        .filter(|kind| match kind {
+            TtsProviderKind::ElevenLabs => api_keys.elevenlabs.is_some(),
+            TtsProviderKind::AzureOpenAi => azure_configured(config, api_keys),
+            TtsProviderKind::Mock => false,
+        });
+    candidates(config, config.providers.tts, configured, |config, kind| config.providers.tts = kind)
+}
+
+/// Azure needs both a key and the resource endpoint
+fn azure_configured(config: &AppConfig, api_keys: &ApiKeys) -> bool {
+    api_keys.azure.is_some() && !config.azure.endpoint.is_empty()
+}
+
+/// The selected provider first, then every other configured one
+fn candidates<K: PartialEq>(
+    config: &AppConfig,
+    selected: K,
+    configured: impl Iterator<Item = K>,
+    select: impl Fn(&mut AppConfig, K),
+) -> Vec<(AppConfig, bool)> {
+    let mut result = vec![(config.clone(), true)];
+
+    // Forced mocks replace every provider, so the others would measure the same thing
+    if mock_providers_forced() {
+        return result;
+    }
+
+    for kind in configured.filter(|kind| *kind != selected) {
+        let mut candidate = config.clone();
+        select(&mut candidate, kind);
+        result.push((candidate, false));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_stats() {
+        assert_eq!(latency_stats(&mut []), None);
+        assert_eq!(latency_stats(&mut [30, 10, 20, 100]), Some(LatencyStats {
+            min_ms: 10,
+            median_ms: 25,
+            mean_ms: 40,
+            max_ms: 100,
+        }));
+        assert_eq!(latency_stats(&mut [7]).unwrap().median_ms, 7);
+    }
+
+    #[test]
+    fn test_only_configured_providers_are_candidates() {
+        let mut config = AppConfig::default();
+        config.providers.llm = LlmProviderKind::OpenRouter;
+        let mut keys = ApiKeys::none();
+        keys.gemini = Some("key".to_string());
+        keys.azure = Some("key".to_string());
+
+        // Azure also needs an endpoint
+        let llms: Vec<_> = llm_candidates(&config, &keys)
+            .into_iter()
+            .map(|(candidate, selected)| (candidate.providers.llm, selected))
+            .collect();
+        assert_eq!(llms, [(LlmProviderKind::OpenRouter, true), (LlmProviderKind::Gemini, false)]);
+
+        config.azure.endpoint = "https://example.openai.azure.com".to_string();
+        assert_eq!(tts_candidates(&config, &keys).len(), 2);
+        assert_eq!(stt_candidates(&config, &ApiKeys::none()).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_mock_providers_are_measured() {
+        let mut config = AppConfig::default();
+        config.providers.stt = SttProviderKind::Mock;
+        config.providers.llm = LlmProviderKind::Mock;
+        config.providers.tts = TtsProviderKind::Mock;
+        config.mock.latency_ms = 0;
+
+        let report = run(&config, &ApiKeys::none(), 0).await;
+        assert_eq!(report.iterations, 1);
+        assert_eq!(report.results.len(), 3);
+        assert!(report.results.iter().all(|result| result.selected && result.successes == 1 && result.stats.is_some()));
+    }
+}
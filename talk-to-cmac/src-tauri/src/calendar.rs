@@ -0,0 +1,302 @@
+//! Calendar events
+//!
+//! Upcoming events are read from the calendar set in `tools.calendar` (see
+//! [`crate::api::calendar`]) for `speak_today_schedule` and for questions about
+//! the schedule. iCalendar data is parsed here, keeping only what is spoken or
+//! sent to the LLM: title, times, and location.
+//!
+//! Times with a `TZID` are read as local time, and recurring events only
+//! appear on their first occurrence.
+
+use crate::api::calendar::CalendarClient;
+use crate::config::{CalendarConfig, ConfigManager};
+use crate::error::{AppResult, CalendarError};
+use crate::i18n;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A calendar event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalendarEvent {
+    /// Event title
+    pub title: String,
+
+    /// Start (Unix timestamp)
+    pub start: i64,
+
+    /// End (Unix timestamp); all-day events end at midnight after their last day
+    pub end: i64,
+
+    /// Whether the event takes whole days rather than a time slot
+    pub all_day: bool,
+
+    /// Where the event takes place
+    pub location: Option<String>,
+}
+
+/// Events overlapping `from`..`to` (Unix timestamps) in the configured calendar, earliest first
+pub async fn upcoming(config: &CalendarConfig, from: i64, to: i64) -> AppResult<Vec<CalendarEvent>> {
+    if !config.enabled {
+        return Err(CalendarError::NotConfigured("the calendar is disabled").into());
+    }
+
+    let secret = match config.secret.as_str() {
+        "" => None,
+        name => ConfigManager::new()?.get_secret(name)?,
+    };
+    let (from, to) = (utc(from), utc(to));
+    CalendarClient::new(config.clone(), secret)?.events(from, to).await
+}
+
+/// Start and end of today in local time (Unix timestamps)
+pub fn today() -> (i64, i64) {
+    let date = Local::now().date_naive();
+    let start = local_midnight(date).unwrap_or_else(|| Local::now().timestamp());
+    let end = date.succ_opt().and_then(local_midnight).unwrap_or(start + 24 * 60 * 60);
+    (start, end)
+}
+
+/// Unix timestamp of midnight at the start of `date` in local time
+pub fn local_midnight(date: NaiveDate) -> Option<i64> {
+    Local
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+        .earliest()
+        .map(|time| time.timestamp())
+}
+
+/// The events overlapping `from`..`to`, earliest first
+pub fn between(mut events: Vec<CalendarEvent>, from: i64, to: i64) -> Vec<CalendarEvent> {
+    events.retain(|event| event.start < to && event.end.max(event.start + 1) > from);
+    events.sort_by_key(|event| event.start);
+    events
+}
+
+/// Read the `VEVENT`s of iCalendar text, skipping events without a readable start
+pub fn parse_ics(ics: &str) -> Vec<CalendarEvent> {
+    // Lines starting with a space or tab continue the previous one
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.trim_end_matches('\r').to_string()),
+        }
+    }
+
+    let mut events = Vec::new();
+    let mut current: Option<IcsEvent> = None;
+    for line in &lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (name, params) = name.split_once(';').unwrap_or((name, ""));
+        let name = name.to_ascii_uppercase();
+
+        if value.eq_ignore_ascii_case("VEVENT") {
+            match name.as_str() {
+                "BEGIN" => current = Some(IcsEvent::default()),
+                "END" => events.extend(current.take().and_then(IcsEvent::finish)),
+                _ => {}
+            }
+            continue;
+        }
+
+        let Some(event) = current.as_mut() else {
+            continue;
+        };
+        match name.as_str() {
+            "SUMMARY" => event.title = unescape(value),
+            "LOCATION" => event.location = Some(unescape(value)).filter(|location| !location.is_empty()),
+            "DTSTART" => event.start = parse_time(value, params),
+            "DTEND" => event.end = parse_time(value, params),
+            _ => {}
+        }
+    }
+    events
+}
+
+/// Event fields read so far
+#[derive(Default)]
+struct IcsEvent {
+    title: String,
+    location: Option<String>,
+    start: Option<(i64, bool)>,
+    end: Option<(i64, bool)>,
+}
+
+impl IcsEvent {
+    fn finish(self) -> Option<CalendarEvent> {
+        let (start, all_day) = self.start?;
+        // Without an end, an all-day event takes its day and a timed one no time
+        let end = match self.end {
+            Some((end, _)) => end,
+            None if all_day => start + 24 * 60 * 60,
+            None => start,
+        };
+        Some(CalendarEvent {
+            title: self.title,
+            start,
+            end,
+            all_day,
+            location: self.location,
+        })
+    }
+}
+
+/// Unix timestamp of an iCalendar date or date-time, and whether it is a whole day
+fn parse_time(value: &str, params: &str) -> Option<(i64, bool)> {
+    let value = value.trim();
+    if params.to_ascii_uppercase().contains("VALUE=DATE") || value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return local_midnight(date).map(|time| (time, true));
+    }
+
+    let (value, is_utc) = match value.strip_suffix('Z') {
+        Some(value) => (value, true),
+        None => (value, false),
+    };
+    let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    let timestamp = if is_utc {
+        Utc.from_utc_datetime(&time).timestamp()
+    } else {
+        Local.from_local_datetime(&time).earliest()?.timestamp()
+    };
+    Some((timestamp, false))
+}
+
+/// Undo iCalendar text escaping
+fn unescape(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => text.push('\n'),
+            Some(other) => text.push(other),
+            None => {}
+        }
+    }
+    text.trim().to_string()
+}
+
+fn utc(timestamp: i64) -> DateTime<Utc> {
+    Utc.timestamp_opt(timestamp, 0).single().unwrap_or_default()
+}
+
+fn local(timestamp: i64) -> DateTime<Local> {
+    utc(timestamp).with_timezone(&Local)
+}
+
+/// Time of day as spoken in `locale`
+fn spoken_time(locale: &str, timestamp: i64) -> String {
+    let format = if i18n::resolve(locale) == "en" { "%-I:%M %p" } else { "%H:%M" };
+    local(timestamp).format(format).to_string()
+}
+
+/// Today's schedule as a short spoken summary in `locale`
+pub fn spoken_schedule(locale: &str, events: &[CalendarEvent]) -> String {
+    if events.is_empty() {
+        return i18n::tr(locale, "schedule-empty");
+    }
+
+    let mut summary = i18n::tr(locale, "schedule-intro");
+    for event in events {
+        let item = if event.all_day {
+            i18n::tr_args(locale, "schedule-all-day", &[("title", &event.title)])
+        } else {
+            let time = spoken_time(locale, event.start);
+            i18n::tr_args(locale, "schedule-event", &[("time", &time), ("title", &event.title)])
+        };
+        summary.push(' ');
+        summary.push_str(&item);
+    }
+    summary
+}
+
+/// Events listed for the LLM, with local dates and times
+pub fn describe(events: &[CalendarEvent]) -> String {
+    if events.is_empty() {
+        return "No events.".to_string();
+    }
+
+    events
+        .iter()
+        .map(|event| {
+            let when = if event.all_day {
+                format!("{} (all day)", local(event.start).format("%a %Y-%m-%d"))
+            } else {
+                format!(
+                    "{}-{}",
+                    local(event.start).format("%a %Y-%m-%d %H:%M"),
+                    local(event.end).format("%H:%M")
+                )
+            };
+            match &event.location {
+                Some(location) => format!("- {}: {} ({})", when, event.title, location),
+                None => format!("- {}: {}", when, event.title),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ICS: &str = "BEGIN:VCALENDAR\r\n\
+        BEGIN:VEVENT\r\n\
+        SUMMARY:Compressor inspection\\, Lewisville\r\n\
+        DTSTART:20261017T140000Z\r\n\
+        DTEND:20261017T153000Z\r\n\
+        LOCATION:Building\r\n  4\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        SUMMARY:Training day\r\n\
+        DTSTART;VALUE=DATE:20261018\r\n\
+        END:VEVENT\r\n\
+        BEGIN:VEVENT\r\n\
+        SUMMARY:No start\r\n\
+        END:VEVENT\r\n\
+        END:VCALENDAR\r\n";
+
+    #[test]
+    fn test_parses_timed_and_all_day_events() {
+        let events = parse_ics(ICS);
+        assert_eq!(events.len(), 2);
+
+        let inspection = &events[0];
+        assert_eq!(inspection.title, "Compressor inspection, Lewisville");
+        assert_eq!(inspection.start, Utc.with_ymd_and_hms(2026, 10, 17, 14, 0, 0).unwrap().timestamp());
+        assert_eq!(inspection.end - inspection.start, 90 * 60);
+        assert_eq!(inspection.location.as_deref(), Some("Building 4"));
+        assert!(!inspection.all_day);
+
+        let training = &events[1];
+        assert!(training.all_day);
+        assert_eq!(training.start, local_midnight(NaiveDate::from_ymd_opt(2026, 10, 18).unwrap()).unwrap());
+        assert_eq!(training.end - training.start, 24 * 60 * 60);
+    }
+
+    #[test]
+    fn test_between_keeps_overlapping_events_in_order() {
+        let event = |title: &str, start, end| CalendarEvent {
+            title: title.to_string(),
+            start,
+            end,
+            all_day: false,
+            location: None,
+        };
+        let events = vec![event("late", 300, 400), event("early", 50, 150), event("before", 0, 100)];
+
+        let titles: Vec<String> = between(events, 100, 350).into_iter().map(|e| e.title).collect();
+        assert_eq!(titles, ["early", "late"]);
+    }
+
+    #[test]
+    fn test_empty_schedule_is_spoken() {
+        assert_eq!(spoken_schedule("en", &[]), "You have nothing on your calendar today.");
+    }
+}
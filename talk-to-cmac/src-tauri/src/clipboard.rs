@@ -0,0 +1,197 @@
+//! System clipboard and text selection
+//!
+//! There is no portable way to read the text selected in another application,
+//! so [`grab_selection`] copies it: the clipboard is cleared, the platform's
+//! copy shortcut is pressed in whatever window has focus, and the clipboard is
+//! read once the copy lands. The text that was on the clipboard before is put
+//! back afterwards; anything else it held (images, files, rich text) is lost.
+//!
+//! [`ClipboardAction`]s run a fixed prompt on the clipboard's text and put
+//! the result back in its place.
+//!
+//! Clipboard access blocks, so async callers run it on a blocking thread.
+
+use crate::config::ClipboardConfig;
+use crate::error::{AppResult, ClipboardError};
+use crate::i18n;
+use crate::translator;
+use arboard::Clipboard;
+use enigo::{Direction, Enigo, Key, Keyboard, Settings};
+use serde::{Deserialize, Serialize};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Longest selection read aloud, in characters
+pub const MAX_SELECTION_CHARS: usize = 5000;
+
+/// Longest clipboard text sent to the LLM by a [`ClipboardAction`], in characters
+pub const MAX_PROMPT_CHARS: usize = 20_000;
+
+/// How long to wait for the focused application to answer the copy shortcut
+const COPY_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Interval between clipboard checks while waiting for the copy
+const POLL_INTERVAL: Duration = Duration::from_millis(25);
+
+/// A fixed prompt run on the clipboard's text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipboardAction {
+    /// Summarize it in the language of the UI
+    Summarize,
+
+    /// Translate it into `clipboard.translate_to`
+    Translate,
+}
+
+impl ClipboardAction {
+    /// Name used for usage counts (e.g. "summarize_clipboard")
+    pub fn name(self) -> &'static str {
+        match self {
+            ClipboardAction::Summarize => "summarize_clipboard",
+            ClipboardAction::Translate => "translate_clipboard",
+        }
+    }
+
+    /// System prompt for the action
+    pub fn instructions(self, config: &ClipboardConfig, locale: &str) -> String {
+        match self {
+            ClipboardAction::Summarize => {
+                format!("{} Answer in {}.", config.summarize_prompt, i18n::tr(locale, "locale-name"))
+            }
+            ClipboardAction::Translate => format!(
+                "Translate the user's text into {}. Preserve the meaning, tone, and line breaks, \
+                 and reply with only the translation.",
+                translator::language_name(&config.translate_to)
+            ),
+        }
+    }
+}
+
+/// Text on the clipboard
+pub fn read_text() -> AppResult<String> {
+    let text = open()?.get_text().map_err(|e| match e {
+        arboard::Error::ContentNotAvailable => ClipboardError::Empty,
+        e => ClipboardError::Unavailable(e.to_string()),
+    })?;
+    if text.trim().is_empty() {
+        return Err(ClipboardError::Empty.into());
+    }
+    Ok(text)
+}
+
+/// Replace the clipboard's contents with `text`
+pub fn write_text(text: &str) -> AppResult<()> {
+    open()?
+        .set_text(text)
+        .map_err(|e| ClipboardError::Unavailable(e.to_string()).into())
+}
+
+/// Text selected in the focused application, copied through the clipboard
+///
+/// Fails with [`ClipboardError::NothingSelected`] when the copy leaves the
+/// clipboard empty, which is also what happens in applications that ignore the
+/// copy shortcut.
+pub fn grab_selection() -> AppResult<String> {
+    let mut clipboard = open()?;
+    let previous = clipboard.get_text().ok();
+    clipboard.clear().map_err(|e| ClipboardError::Unavailable(e.to_string()))?;
+
+    let copied = press_copy().map(|()| wait_for_text(&mut clipboard));
+
+    if let Some(previous) = previous {
+        if let Err(e) = clipboard.set_text(previous) {
+            tracing::warn!("Failed to restore the clipboard: {}", e);
+        }
+    }
+
+    copied?.ok_or_else(|| ClipboardError::NothingSelected.into())
+}
+
+/// At most `max_chars` characters of `text`, cut after the last whole sentence
+///
+/// Falls back to the last word boundary when no sentence ends in the second
+/// half of the allowance, and to a hard cut for text without spaces.
+pub fn limit_text(text: &str, max_chars: usize) -> &str {
+    let Some((end, _)) = text.char_indices().nth(max_chars) else {
+        return text;
+    };
+    let head = &text[..end];
+
+    // Looks past the end of `head`, so a sentence ending right at the cut counts
+    let sentence = head
+        .char_indices()
+        .rev()
+        .find(|&(i, c)| {
+            matches!(c, '.' | '!' | '?' | '\n') && text[i + c.len_utf8()..].starts_with(char::is_whitespace)
+        })
+        .map(|(i, c)| i + c.len_utf8())
+        .filter(|&cut| head[..cut].chars().count() >= max_chars / 2);
+    let cut = sentence
+        .or_else(|| head.rfind(char::is_whitespace))
+        .unwrap_or(end);
+    head[..cut].trim_end()
+}
+
+fn open() -> AppResult<Clipboard> {
+    Clipboard::new().map_err(|e| ClipboardError::Unavailable(e.to_string()).into())
+}
+
+/// Press the copy shortcut in the focused application
+///
+/// Modifiers still held from the hotkey that triggered this are released first,
+/// or the application would see e.g. Ctrl+Shift+C instead of Ctrl+C.
+fn press_copy() -> AppResult<()> {
+    let failed = |e: &dyn std::fmt::Display| ClipboardError::CopyFailed(e.to_string());
+    let mut enigo = Enigo::new(&Settings::default()).map_err(|e| failed(&e))?;
+
+    for modifier in [Key::Shift, Key::Alt, Key::Meta, Key::Control] {
+        enigo.key(modifier, Direction::Release).map_err(|e| failed(&e))?;
+    }
+
+    let command = if cfg!(target_os = "macos") { Key::Meta } else { Key::Control };
+    enigo.key(command, Direction::Press).map_err(|e| failed(&e))?;
+    let copy = enigo.key(Key::Unicode('c'), Direction::Click);
+    enigo.key(command, Direction::Release).map_err(|e| failed(&e))?;
+    copy.map_err(|e| failed(&e).into())
+}
+
+/// Text that appears on the clipboard within [`COPY_TIMEOUT`]
+fn wait_for_text(clipboard: &mut Clipboard) -> Option<String> {
+    let started = Instant::now();
+    while started.elapsed() < COPY_TIMEOUT {
+        thread::sleep(POLL_INTERVAL);
+        if let Ok(text) = clipboard.get_text() {
+            if !text.trim().is_empty() {
+                return Some(text);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_instructions() {
+        let config = ClipboardConfig {
+            translate_to: "de".to_string(),
+            ..ClipboardConfig::default()
+        };
+        assert!(ClipboardAction::Translate.instructions(&config, "en").starts_with("Translate the user's text into German."));
+        assert!(ClipboardAction::Summarize.instructions(&config, "es").ends_with(&format!("Answer in {}.", i18n::tr("es", "locale-name"))));
+    }
+
+    #[test]
+    fn test_limit_text_cuts_at_sentence_or_word() {
+        assert_eq!(limit_text("Short enough.", 50), "Short enough.");
+        assert_eq!(limit_text("One two three. Four five six seven", 24), "One two three.");
+        // No sentence ends in the second half: cut at the last word
+        assert_eq!(limit_text("A. bcdefgh ijklmnop qrstuv", 20), "A. bcdefgh ijklmnop");
+        assert_eq!(limit_text("abcdefghij", 4), "abcd");
+        assert_eq!(limit_text("ééé ééé", 5), "ééé");
+        assert_eq!(limit_text("One two three. Four", 14), "One two three.");
+    }
+}
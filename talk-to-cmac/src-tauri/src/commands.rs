@@ -3,21 +3,90 @@
 //! Defines all commands that can be invoked from the frontend, handling
 //! the complete voice assistant pipeline and configuration management.
 
-use crate::api::{ElevenLabsClient, OpenWebUiClient, WhisperClient};
-use crate::config::{AppConfig, ConfigManager, VoiceSettings};
-use crate::error::AppResult;
-use crate::state::{AppState, AppStatus, MessageRole, ServiceStatus};
+use crate::api::{
+    llm_provider, stt_provider, tts_is_mock, tts_provider, ElevenLabsClient, FileAttachment, GenerationOverrides,
+    ImageAttachment, KnowledgeCollection, LlmFile, LlmReply, MockClient, ModelInfo, OpenWebUiClient, Ticket,
+    TicketingClient, TranscriptionCandidate, TranscriptionOverrides, TtsProvider,
+};
+use crate::api::cooldown::ProviderCooldown;
+use crate::api::provider::LlmProvider;
+use crate::audit::{self, AuditEntry};
+use crate::bandwidth;
+use crate::benchmark::{self, BenchmarkReport};
+use crate::calendar::{self, CalendarEvent};
+use crate::clipboard::{self, ClipboardAction};
+use crate::crash::{self, CrashReport, LastCrash};
+use crate::crypto::PinHash;
+use crate::config::{ApiKeys, AppConfig, ConfigManager, HandoffMethod, HookStage, HotkeyAction, KeySlot, KeySource, LlmProviderKind, PipelineMode, RetentionPolicy, VoicePreset, VoiceSettings, API_KEY_SERVICES};
+use crate::context;
+use crate::downmix;
+use crate::ducking::AudioDucker;
+use crate::env_overrides::{EnvOverride, EnvOverrides};
+use crate::experiments;
+use crate::feature_flags::{self, FeatureFlag};
+use crate::gain;
+use crate::handoff::{self, Handoff};
+use crate::microphone::{self, MicrophoneInfo, MicrophoneTest};
+use crate::moderation::{self, ModerationVerdict};
+use crate::narration::{self, NarrationSummary, NarrationVoices};
+use crate::ocr;
+use crate::news::{self, NewsItem};
+use crate::pipeline::{self, SpeechChunk};
+use crate::policy::{self, PolicyVerdict};
+use crate::profanity;
+use crate::profiles::{ProfileInfo, ProfileStore};
+use crate::quick_ask;
+use crate::recordings::{RecordingInfo, RecordingKind, RecordingStore};
+use crate::redaction::{self, RedactionAuditEntry};
+use crate::retention;
+use crate::retry;
+use crate::selftest::{self, SelfTestReport};
+use crate::setup::{self, LocalServer, SetupSuggestion};
+use crate::speaker::{self, SpeakerEnrollment, SpeakerMatch, VoiceprintStore};
+use crate::speech_text::{self, SpeechText};
+use crate::telemetry::{self, Stage, StageTiming, StageTimings};
+use crate::templates;
+use crate::theme::ThemeInfo;
+use crate::tools;
+use crate::translator::{TranslationDirection, TranslationTurn};
+use crate::trim;
+use crate::usage::{self, TelemetryPayload};
+use crate::user_data;
+use crate::error::{AppError, AppResult, AudioError, ConfigError, OpenWebUiError, StorageError, TicketingError};
+use crate::history::{ConversationSummary, HistoryStore};
+use crate::hooks;
+use crate::hotkeys::{self, HotkeyBinding, HotkeyValidation};
+use crate::journal::{self, JournalEntry, RequestParameters};
+use crate::log_privacy;
+use crate::i18n::{self, LocaleInfo};
+use crate::intents::{self, ReplyModifier, VoiceIntent};
+use crate::state::{
+    AppState, AppStatus, ConversationSettings, LastResponse, MessageRole, RecentTranscription, ServiceHistory, ServiceStatus,
+    Thumbs, TranscriptSegment, TranscriptionSource,
+};
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_opener::OpenerExt;
+use tracing::Instrument;
 
 /// Process audio file and return transcription
 #[tauri::command]
 pub async fn process_audio(
     audio_data: Vec<u8>,
     filename: String,
+    app: AppHandle,
     state: State<'_, AppState>,
 ) -> Result<String, String> {
-    log::info!("Processing audio: {} bytes", audio_data.len());
+    tracing::info!("Processing audio: {} bytes", audio_data.len());
+
+    ensure_unlocked(&state)?;
+    ensure_speech_input(&state)?;
+    state.session().touch();
+    if state.is_mic_muted() {
+        return Err(localized(&state, &AppError::from(AudioError::MicrophoneMuted)));
+    }
 
     // Update status
     state.set_status(AppStatus::Transcribing);
@@ -26,88 +95,276 @@ pub async fn process_audio(
     let config = state.get_config();
     let api_keys = state.get_api_keys();
 
-    // Create Whisper client
-    let whisper_client = WhisperClient::new(config.whisper, api_keys.whisper)
-        .map_err(|e| e.to_string())?;
+    // Create speech-to-text client
+    let stt_client = state.cooldowns().stt_provider(&config, &api_keys)
+        .map_err(|e| localized(&state, &e))?;
+
+    let audio_data = prepare_recording(&config, audio_data);
+    state.set_last_recording(audio_data.clone(), &filename);
+
+    // Keep a copy for the recordings store before the client takes ownership
+    let recording = config.audio.save_recordings.then(|| audio_data.clone());
 
-    // Transcribe audio
-    let result = whisper_client
+    // Transcribe audio, masking personal information before it goes anywhere else
+    let result = stt_client
         .transcribe_audio(audio_data, &filename)
-        .await;
+        .await
+        .map(|text| redaction::apply(&config.privacy.redaction, "transcription", text))
+        .map(|text| profanity::apply(&config.profanity_filter, text));
+    let result = match result {
+        Ok(text) => Ok(hooks::run(&config.hooks, HookStage::PostTranscription, text).await),
+        Err(e) => Err(e),
+    };
 
     // Reset status
     state.set_status(AppStatus::Idle);
 
+    if let Some(audio) = recording {
+        let text = result.as_ref().ok().map(String::as_str);
+        save_recording(&state, RecordingKind::Utterance, &audio, &filename, text);
+    }
+
     match result {
         Ok(text) => {
-            log::info!("Transcription successful: '{}'", text);
+            tracing::info!("Transcription successful: {}", log_privacy::content(&text));
+            record_transcription(&app, &state, &text, TranscriptionSource::Dictation);
             Ok(text)
         }
         Err(e) => {
-            log::error!("Transcription failed: {}", e);
+            tracing::error!("Transcription failed: {}", e);
             state.set_status(AppStatus::Error {
-                message: e.to_string(),
+                message: localized(&state, &e),
             });
-            Err(e.to_string())
+            Err(localized(&state, &e))
         }
     }
 }
 
 /// Send a text message to the LLM and get response
 #[tauri::command]
+#[tracing::instrument(name = "message", skip_all, fields(request_id = tracing::field::Empty))]
 pub async fn send_message(
     message: String,
+    images: Option<Vec<ImageAttachment>>,
+    app: AppHandle,
     state: State<'_, AppState>,
+    history: State<'_, HistoryStore>,
 ) -> Result<String, String> {
-    log::info!("Sending message to LLM: '{}'", message);
+    let request = JournaledRequest::start("message", &state, false, false, message.chars().count());
+    let result = run_message(message, images, app, state.clone(), history).await;
+    let stages = state
+        .get_conversation()
+        .messages
+        .last()
+        .filter(|_| result.is_ok())
+        .and_then(|message| message.stages.clone())
+        .unwrap_or_default();
+    request.finish(result.as_ref().map(|_| stages), None);
+    result
+}
+
+async fn run_message(
+    message: String,
+    images: Option<Vec<ImageAttachment>>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+    history: State<'_, HistoryStore>,
+) -> Result<String, String> {
+    ensure_unlocked(&state)?;
+    state.session().touch();
+    let config = state.get_config();
+    let api_keys = state.get_api_keys();
+    let message = redaction::apply(&config.privacy.redaction, "message", message);
+
+    tracing::info!("Sending message to LLM: {}", log_privacy::content(&message));
+
+    // Blocked input never reaches the LLM or the conversation
+    if let Some(verdict) = moderate_input(&app, &config, api_keys.whisper.clone(), &message).await {
+        if let Some(refusal) = verdict.refusal {
+            return Ok(refusal);
+        }
+    }
+    if let Some(verdict) = check_policy(&app, &state, &config, &message).await {
+        return Ok(verdict.refusal);
+    }
 
     // Update status
     state.set_status(AppStatus::Thinking);
 
     // Add user message to conversation
     state.add_message(MessageRole::User, message.clone());
-
-    // Get configuration and API keys
-    let config = state.get_config();
-    let api_keys = state.get_api_keys();
+    persist_latest_message(&state, &history);
 
     // Create OpenWebUI client
-    let openwebui_client = OpenWebUiClient::new(config.openwebui, api_keys.openwebui)
-        .map_err(|e| e.to_string())?;
-
-    // Get conversation context
-    let messages = state.get_api_messages();
-
-    // Send message
-    let result = openwebui_client.send_message(messages).await;
+    let llm_routed = state.cooldowns().routed(Stage::Llm, &config);
+    let llm_client = state.cooldowns().llm_provider(&config, &api_keys)
+        .map_err(|e| localized(&state, &e))?;
+
+    // Get conversation context and its own generation settings
+    let mut messages = context::select(llm_client.as_ref(), &config.context, state.get_api_messages()).await;
+    tools::augment(&config, &message, images.as_deref().unwrap_or_default(), &mut messages).await;
+    hooks::run_on_query(&config.hooks, &mut messages).await;
+    let overrides = experiments::overrides(&config.experiment, &state.get_conversation());
+
+    // Send message, streaming the filtered reply so far unless images are attached
+    let started = Instant::now();
+    let images = images.unwrap_or_default();
+    let retry_messages = config.openwebui.retry.enabled.then(|| messages.clone());
+    let result = if images.is_empty() {
+        usage::record_feature("text_message");
+        let mut partial = String::new();
+        let mut on_delta = |delta: &str| {
+            partial.push_str(delta);
+            // Reasoning streams before the answer and is never shown as progress
+            let answer = LlmReply::parse(&partial, None).answer;
+            if answer.is_empty() {
+                return;
+            }
+            let progress = profanity::apply(&config.profanity_filter, answer);
+            if let Err(e) = app.emit("llm-response-progress", &progress) {
+                tracing::warn!("Failed to emit llm-response-progress: {}", e);
+            }
+        };
+        llm_client.stream_message_with(messages, &overrides, &mut on_delta).await
+    } else {
+        usage::record_feature("image_message");
+        llm_client.send_message_with_images(messages, &images, &overrides).await
+    };
+    let result = match retry_messages {
+        Some(messages) => retry_unhelpful(&app, &config, llm_client.as_ref(), messages, &images, &overrides, result).await.0,
+        None => result,
+    };
 
     // Reset status
     state.set_status(AppStatus::Idle);
 
-    match result {
-        Ok(response) => {
-            log::info!("LLM response received: {} chars", response.len());
+    match result.and_then(|reply| take_answer(&app, &config, reply)) {
+        Ok((response, _)) => {
+            let response = hooks::run(&config.hooks, HookStage::PostLlm, response).await;
+            tracing::info!("LLM response received: {}", log_privacy::content(&response));
             // Add assistant response to conversation
             state.add_message(MessageRole::Assistant, response.clone());
+            experiments::record_reply(&state.get_conversation());
+            state.set_reply_stages(StageTimings {
+                llm: Some(llm_timing(&config, &llm_routed, &overrides, started)),
+                ..StageTimings::default()
+            });
+            persist_latest_message(&state, &history);
+            suggest_follow_ups(&app, &state).await;
             Ok(response)
         }
         Err(e) => {
-            log::error!("LLM request failed: {}", e);
+            tracing::error!("LLM request failed: {}", e);
+            usage::record_error(&e);
+            experiments::record_error(&state.get_conversation());
             state.set_status(AppStatus::Error {
-                message: e.to_string(),
+                message: localized(&state, &e),
             });
-            Err(e.to_string())
+            Err(localized(&state, &e))
+        }
+    }
+}
+
+/// Transcribe the last recording again with different settings, without re-recording
+///
+/// The new transcription is returned but not added to the conversation.
+#[tauri::command]
+pub async fn retranscribe(
+    overrides: TranscriptionOverrides,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    tracing::info!("Transcribing the last recording again with {:?}", overrides);
+    ensure_unlocked(&state)?;
+    usage::record_feature("retranscribe");
+
+    let Some(recording) = state.get_last_recording() else {
+        return Err(i18n::tr(&state.get_config().ui.locale, "no-recording-to-retranscribe"));
+    };
+
+    let config = overrides.apply(&state.get_config());
+    let api_keys = state.get_api_keys();
+    let stt_client = state.cooldowns().stt_provider(&config, &api_keys)
+        .map_err(|e| localized(&state, &e))?;
+
+    state.set_status(AppStatus::Transcribing);
+    let result = telemetry::measure(Stage::Stt, &config, stt_client.transcribe_audio(recording.audio, &recording.filename))
+        .await
+        .map(|text| redaction::apply(&config.privacy.redaction, "transcription", text))
+        .map(|text| profanity::apply(&config.profanity_filter, text));
+    state.set_status(AppStatus::Idle);
+
+    match result {
+        Ok(text) => {
+            let text = hooks::run(&config.hooks, HookStage::PostTranscription, text).await;
+            record_transcription(&app, &state, &text, TranscriptionSource::Retranscription);
+            Ok(text)
+        }
+        Err(e) => {
+            tracing::error!("Transcribing again failed: {}", e);
+            Err(localized(&state, &e))
+        }
+    }
+}
+
+/// Latest transcriptions, newest first, whether they were dictated, asked, or spoken as commands
+///
+/// Kept across conversations so an utterance that went to the wrong place can be
+/// copied and used again. At most `limit` are returned.
+#[tauri::command]
+pub async fn get_recent_transcriptions(
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<RecentTranscription>, String> {
+    ensure_unlocked(&state)?;
+    let mut recent = state.recent_transcriptions();
+    if let Some(limit) = limit {
+        recent.truncate(limit);
+    }
+    Ok(recent)
+}
+
+/// Live transcript of the session, oldest first: every utterance transcribed, in any mode
+///
+/// Only segments after sequence number `since` are returned, so a caption view can catch up
+/// and then follow the `transcript-appended` events.
+#[tauri::command]
+pub async fn get_live_transcript(since: Option<u64>, state: State<'_, AppState>) -> Result<Vec<TranscriptSegment>, String> {
+    ensure_unlocked(&state)?;
+    Ok(state.live_transcript(since.unwrap_or(0)))
+}
+
+/// Keep a transcription for `get_recent_transcriptions` and the live transcript
+///
+/// A segment added to the live transcript is sent as a `transcript-appended` event.
+fn record_transcription(app: &AppHandle, state: &AppState, text: &str, source: TranscriptionSource) {
+    if let Some(segment) = state.record_transcription(text, source) {
+        if let Err(e) = app.emit("transcript-appended", segment) {
+            tracing::warn!("Failed to emit transcript-appended event: {}", e);
         }
     }
 }
 
+/// Strip leading and trailing silence from a WAV recording
+///
+/// Uses `audio.silence_threshold`; a recording without any sound is returned as it is.
+#[tauri::command]
+pub async fn trim_audio(audio_data: Vec<u8>, state: State<'_, AppState>) -> Result<Vec<u8>, String> {
+    let threshold = state.get_config().audio.silence_threshold;
+    tokio::task::spawn_blocking(move || trim::trim(&audio_data, threshold))
+        .await
+        .map_err(|e| localized(&state, &AppError::Generic(e.to_string())))?
+        .map_err(|e| localized(&state, &e))
+}
+
 /// Convert text to speech
 #[tauri::command]
 pub async fn synthesize_speech(
     text: String,
     state: State<'_, AppState>,
 ) -> Result<Vec<u8>, String> {
-    log::info!("Synthesizing speech: {} chars", text.len());
+    tracing::info!("Synthesizing speech: {} chars", text.len());
+    ensure_unlocked(&state)?;
 
     // Update status
     state.set_status(AppStatus::Speaking);
@@ -116,97 +373,468 @@ pub async fn synthesize_speech(
     let config = state.get_config();
     let api_keys = state.get_api_keys();
 
+    let text = redaction::apply(&config.privacy.redaction, "speech", text);
+
     // Create ElevenLabs client
-    let elevenlabs_client = ElevenLabsClient::new(config.elevenlabs, api_keys.elevenlabs)
-        .map_err(|e| e.to_string())?;
+    let tts_client = state.cooldowns().tts_provider(&config, &api_keys)
+        .map_err(|e| localized(&state, &e))?;
 
-    // Synthesize speech
-    let result = elevenlabs_client.synthesize_speech(&text).await;
+    // Synthesize speech; pre-TTS hooks change only what is spoken
+    let spoken = hooks::run(&config.hooks, HookStage::PreTts, text.clone()).await;
+    let result = tts_client.synthesize_speech(&spoken).await;
 
     // Reset status
     state.set_status(AppStatus::Idle);
 
     match result {
         Ok(audio_data) => {
-            log::info!("Speech synthesis successful: {} bytes", audio_data.len());
+            tracing::info!("Speech synthesis successful: {} bytes", audio_data.len());
+            save_recording(&state, RecordingKind::Reply, &audio_data, "reply.mp3", Some(&text));
+            state.set_last_response(text, audio_data.clone());
             Ok(audio_data)
         }
         Err(e) => {
-            log::error!("Speech synthesis failed: {}", e);
+            tracing::error!("Speech synthesis failed: {}", e);
             state.set_status(AppStatus::Error {
-                message: e.to_string(),
+                message: localized(&state, &e),
             });
-            Err(e.to_string())
+            Err(localized(&state, &e))
         }
     }
 }
 
 /// Process complete voice query pipeline: audio -> transcription -> LLM -> TTS
+///
+/// Runs in a `voice_query` span keyed by a fresh `request_id`, with `stt`, `llm`, and
+/// `tts` child spans, so every log line of one query can be found together. If a stage
+/// fails and `audio.speak_errors` is on, a short explanation is spoken as well.
+///
+/// Refused when `audio.pipeline_mode` takes typed queries only. The reply is spoken
+/// unless the mode writes replies only; `speak` decides instead for this query.
 #[tauri::command]
+#[tracing::instrument(name = "voice_query", skip_all, fields(request_id = tracing::field::Empty))]
 pub async fn process_voice_query(
     audio_data: Vec<u8>,
     filename: String,
+    speak: Option<bool>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+    history: State<'_, HistoryStore>,
+) -> Result<VoiceQueryResponse, String> {
+    let speak = speak.unwrap_or(ensure_speech_input(&state)?.speech_out());
+    let request = JournaledRequest::start("voice_query", &state, true, speak, audio_data.len());
+    crash::reset_stage();
+    let result = run_voice_query(audio_data, filename, speak, app.clone(), state.clone(), history).await;
+    request.finish(result.as_ref().map(|response| response.stages.clone()), crash::last_stage());
+    if result.is_err() && speak {
+        if let Some(stage) = crash::last_stage() {
+            speak_error(&app, &state, stage).await;
+        }
+    }
+    result
+}
+
+/// Answer a typed query like a spoken one, speaking the reply unless replies are written only
+///
+/// Goes through the same steps as `process_voice_query` after transcription, including
+/// voice commands and confirmations, so chat-style and voice-style frontends share one
+/// pipeline. `speak` overrides `audio.pipeline_mode` for this query.
+#[tauri::command]
+#[tracing::instrument(name = "text_query", skip_all, fields(request_id = tracing::field::Empty))]
+pub async fn process_text_query(
+    message: String,
+    speak: Option<bool>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+    history: State<'_, HistoryStore>,
+) -> Result<VoiceQueryResponse, String> {
+    ensure_unlocked(&state)?;
+    state.session().touch();
+    usage::record_feature("text_query");
+    let config = state.get_config();
+    let speak = speak.unwrap_or(config.audio.pipeline_mode.speech_out());
+    let message = redaction::apply(&config.privacy.redaction, "message", message);
+    tracing::info!("Processing text query: {}", log_privacy::content(&message));
+
+    let request = JournaledRequest::start("text_query", &state, false, speak, message.chars().count());
+    crash::reset_stage();
+    let result = answer_query(message, speak, StageTimings::default(), &app, &state, &history).await;
+    request.finish(result.as_ref().map(|response| response.stages.clone()), crash::last_stage());
+    if result.is_err() && speak {
+        if let Some(stage) = crash::last_stage() {
+            speak_error(&app, &state, stage).await;
+        }
+    }
+    result
+}
+
+/// The pipeline mode, or an error if it takes typed queries only
+fn ensure_speech_input(state: &AppState) -> Result<PipelineMode, String> {
+    let config = state.get_config();
+    if config.audio.pipeline_mode.speech_in() {
+        Ok(config.audio.pipeline_mode)
+    } else {
+        Err(i18n::tr(&config.ui.locale, "voice-input-off"))
+    }
+}
+
+/// Payload of `error-spoken`
+#[derive(Debug, Clone, Serialize)]
+pub struct SpokenError {
+    /// The explanation that was spoken
+    pub text: String,
+
+    /// Its audio, to play
+    pub audio: Vec<u8>,
+}
+
+/// Speak a short explanation of the pipeline stage that failed, if `audio.speak_errors` is on
+///
+/// A failed speech stage is explained with the fallback TTS provider, if one is set. The audio
+/// is sent as an `error-spoken` event; failures are only logged.
+async fn speak_error(app: &AppHandle, state: &AppState, stage: &str) {
+    let config = state.get_config();
+    if !config.audio.speak_errors {
+        return;
+    }
+
+    let key = match stage {
+        "stt" => "spoken-error-stt",
+        "llm" => "spoken-error-llm",
+        _ => "spoken-error-tts",
+    };
+    let text = i18n::tr(&config.ui.locale, key);
+
+    let mut tts_config = config.clone();
+    if stage == "tts" {
+        if let Some(fallback) = config.cooldown.fallback_tts {
+            tts_config.providers.tts = fallback;
+        }
+    }
+
+    let audio = match state.cooldowns().tts_provider(&tts_config, &state.get_api_keys()) {
+        Ok(tts_client) => tts_client.synthesize_speech(&text).await,
+        Err(e) => Err(e),
+    };
+    match audio {
+        Ok(audio) => {
+            if let Err(e) = app.emit("error-spoken", SpokenError { text, audio }) {
+                tracing::warn!("Failed to emit error-spoken event: {}", e);
+            }
+        }
+        Err(e) => tracing::warn!("Failed to speak the error explanation: {}", e),
+    }
+}
+
+async fn run_voice_query(
+    audio_data: Vec<u8>,
+    filename: String,
+    speak: bool,
+    app: AppHandle,
     state: State<'_, AppState>,
+    history: State<'_, HistoryStore>,
 ) -> Result<VoiceQueryResponse, String> {
-    log::info!("Processing complete voice query pipeline");
+    tracing::info!("Processing complete voice query pipeline");
+
+    ensure_unlocked(&state)?;
+    state.session().touch();
+    if state.is_mic_muted() {
+        return Err(localized(&state, &AppError::from(AudioError::MicrophoneMuted)));
+    }
+    usage::record_feature("voice_query");
+
+    if let Some(direction) = state.translator_direction() {
+        return translate_voice_turn(audio_data, filename, direction, &app, &state).await;
+    }
+
+    // Recognize the speaker first, so the rest of the query runs in their profile
+    let speaker = identify_speaker(&audio_data, &app, &state, &history);
 
     // Step 1: Transcribe audio
     state.set_status(AppStatus::Transcribing);
     let config = state.get_config();
     let api_keys = state.get_api_keys();
 
-    let whisper_client = WhisperClient::new(config.whisper.clone(), api_keys.whisper.clone())
-        .map_err(|e| e.to_string())?;
+    let stt_routed = state.cooldowns().routed(Stage::Stt, &config);
+    let stt_client = state.cooldowns().stt_provider(&config, &api_keys)
+        .map_err(|e| localized(&state, &e))?;
 
-    let transcription = whisper_client
-        .transcribe_audio(audio_data, &filename)
+    let audio_data = prepare_recording(&config, audio_data);
+    state.set_last_recording(audio_data.clone(), &filename);
+    let recording = config.audio.save_recordings.then(|| audio_data.clone());
+
+    let stt_started = Instant::now();
+    let mut candidates = telemetry::measure(Stage::Stt, &config, stt_client.transcribe_candidates(audio_data, &filename))
+        .instrument(tracing::info_span!("stt"))
         .await
         .map_err(|e| {
             state.set_status(AppStatus::Error {
-                message: e.to_string(),
+                message: localized(&state, &e),
             });
-            e.to_string()
+            localized(&state, &e)
         })?;
+    let stages = StageTimings {
+        stt: Some(StageTiming::since(Stage::Stt, &config, &stt_routed, stt_started)),
+        ..StageTimings::default()
+    };
 
-    log::info!("Transcription: '{}'", transcription);
+    // The top hypothesis is answered; any others are offered to the user to pick instead
+    for candidate in &mut candidates {
+        let text = redaction::apply(&config.privacy.redaction, "transcription", std::mem::take(&mut candidate.text));
+        candidate.text = profanity::apply(&config.profanity_filter, text);
+    }
+    let transcription = hooks::run(&config.hooks, HookStage::PostTranscription, candidates.remove(0).text).await;
+    let alternatives = candidates;
+    tracing::info!("Transcription: {}", log_privacy::content(&transcription));
+    record_transcription(&app, &state, &transcription, TranscriptionSource::Query);
 
-    // Step 2: Send to LLM
-    state.set_status(AppStatus::Thinking);
-    state.add_message(MessageRole::User, transcription.clone());
+    if let Some(audio) = recording {
+        save_recording(&state, RecordingKind::Utterance, &audio, &filename, Some(&transcription));
+    }
 
-    let openwebui_client = OpenWebUiClient::new(config.openwebui.clone(), api_keys.openwebui.clone())
-        .map_err(|e| e.to_string())?;
+    let response = answer_query(transcription, speak, stages, &app, &state, &history).await?;
+    Ok(VoiceQueryResponse {
+        speaker,
+        alternatives,
+        ..response
+    })
+}
 
-    let messages = state.get_api_messages();
-    let llm_response = openwebui_client
-        .send_message(messages)
-        .await
-        .map_err(|e| {
-            state.set_status(AppStatus::Error {
-                message: e.to_string(),
+/// Answer a transcribed or typed query: the answer to a pending confirmation, a built-in
+/// command, or else a reply from the LLM
+///
+/// The reply is spoken only with `speak`; otherwise `audio_response` is empty and
+/// nothing is synthesized. `stages` holds the timing of the transcription, if any.
+async fn answer_query(
+    transcription: String,
+    speak: bool,
+    mut stages: StageTimings,
+    app: &AppHandle,
+    state: &AppState,
+    history: &HistoryStore,
+) -> Result<VoiceQueryResponse, String> {
+    let config = state.get_config();
+    let api_keys = state.get_api_keys();
+
+    // A yes or no answers a pending confirmation; anything else is a new query and drops it
+    let mut command = None;
+    if let Some(pending) = state.take_pending_action() {
+        match intents::match_confirmation(&transcription) {
+            Some(true) => command = Some((pending, true)),
+            Some(false) => {
+                tracing::info!("Voice command cancelled: {:?}", pending);
+                let text = i18n::tr(&config.ui.locale, "action-cancelled");
+                let audio = if speak { speak_notice(state, &text).await } else { Vec::new() };
+                state.set_status(AppStatus::Idle);
+                return Ok(VoiceQueryResponse {
+                    stages,
+                    ..VoiceQueryResponse::command(transcription, pending, text, audio)
+                });
+            }
+            None => tracing::info!("No answer to the confirmation question; dropping {:?}", pending),
+        }
+    }
+
+    // Built-in commands are executed locally without an LLM round-trip
+    if command.is_none() && config.audio.voice_commands {
+        // Ticket requests go to the LLM as usual unless ticketing is set up
+        command = intents::match_intent(&transcription)
+            .filter(|intent| config.ticketing.enabled || !matches!(intent, VoiceIntent::CreateTicket { .. }))
+            .map(|intent| (intent, false));
+    }
+    if let Some((intent, confirmed)) = command {
+        usage::record_feature("voice_command");
+
+        // Commands that act outside the app run only after a spoken yes
+        if intent.takes_action() && config.audio.confirm_actions && !confirmed {
+            let question = confirmation_question(&config.ui.locale, &intent);
+            let audio = if speak { speak_notice(state, &question).await } else { Vec::new() };
+            state.set_pending_action(intent.clone());
+            state.set_status(AppStatus::Confirming);
+            return Ok(VoiceQueryResponse {
+                stages,
+                awaiting_confirmation: true,
+                ..VoiceQueryResponse::command(transcription, intent, question, audio)
             });
-            e.to_string()
-        })?;
+        }
 
-    log::info!("LLM response: {} chars", llm_response.len());
-    state.add_message(MessageRole::Assistant, llm_response.clone());
+        let result = run_voice_intent(&intent, app, state).await;
+        state.set_status(AppStatus::Idle);
+        let (llm_response, mut audio_response) = result?;
+        if !speak {
+            audio_response.clear();
+        }
 
-    // Step 3: Convert to speech
-    state.set_status(AppStatus::Speaking);
-    let elevenlabs_client = ElevenLabsClient::new(config.elevenlabs, api_keys.elevenlabs)
-        .map_err(|e| e.to_string())?;
+        return Ok(VoiceQueryResponse {
+            stages,
+            ..VoiceQueryResponse::command(transcription, intent, llm_response, audio_response)
+        });
+    }
 
-    let audio_response = elevenlabs_client
-        .synthesize_speech(&llm_response)
-        .await
-        .map_err(|e| {
-            state.set_status(AppStatus::Error {
-                message: e.to_string(),
-            });
-            e.to_string()
-        })?;
+    // Step 2: Send to LLM, unless moderation or the topic policy blocks the input
+    let moderation = moderate_input(app, &config, api_keys.whisper.clone(), &transcription).await;
+    let policy = if moderation.as_ref().is_some_and(|verdict| verdict.blocked) {
+        None
+    } else {
+        check_policy(app, state, &config, &transcription).await
+    };
+    let refusal = moderation
+        .as_ref()
+        .and_then(|verdict| verdict.refusal.clone())
+        .or_else(|| policy.as_ref().map(|verdict| verdict.refusal.clone()));
+
+    let mut follow_ups = Vec::new();
+    let mut reasoning = None;
+    let mut retried = false;
+    let mut streamed_audio = None;
+    let mut streamed_text = Vec::new();
+    let llm_response = match refusal {
+        Some(refusal) => refusal,
+        None => {
+            state.set_status(AppStatus::Thinking);
+            let prompt = apply_template(app, &config, &transcription);
+            state.add_message(MessageRole::User, prompt);
+            persist_latest_message(state, history);
+
+            let llm_routed = state.cooldowns().routed(Stage::Llm, &config);
+            let llm_client = state.cooldowns().llm_provider(&config, &api_keys)
+                .map_err(|e| localized(state, &e))?;
+
+            // Spoken replies are kept short when voice-optimized mode is on
+            let mut overrides = experiments::overrides(&config.experiment, &state.get_conversation());
+            if speak {
+                overrides = overrides.with_voice(&config.openwebui);
+            }
+            if let Some(modifier) = state.take_reply_modifier() {
+                tracing::info!("Applying spoken modifier: {:?}", modifier);
+                overrides = modifier.apply(overrides, &config.openwebui);
+            }
+            let mut messages = context::select(llm_client.as_ref(), &config.context, state.get_api_messages()).await;
+            tools::augment(&config, &transcription, &[], &mut messages).await;
+            hooks::run_on_query(&config.hooks, &mut messages).await;
+
+            // With streaming on, each sentence is spoken as soon as it has been generated,
+            // unless hooks need the whole answer before it is spoken
+            let llm_started = Instant::now();
+            let hooked = hooks::any(&config.hooks, HookStage::PostLlm) || hooks::any(&config.hooks, HookStage::PreTts);
+            let reply = if speak && config.openwebui.stream && !hooked && feature_flags::enabled(feature_flags::STREAMING) {
+                usage::record_feature("streamed_speech");
+                let tts_routed = state.cooldowns().routed(Stage::Tts, &config);
+                let tts_client: Arc<dyn TtsProvider> =
+                    state.cooldowns().tts_provider(&config, &api_keys).map_err(|e| localized(state, &e))?.into();
+                let speech = Mutex::new(SpeechText::default());
+                let prepare = |sentence: String| {
+                    let sentence = speech.lock().unwrap().push(&sentence);
+                    let sentence = profanity::apply(&config.profanity_filter, sentence);
+                    redaction::apply(&config.privacy.redaction, "speech", sentence)
+                };
+                let on_chunk = |chunk: SpeechChunk| {
+                    if chunk.index == 0 {
+                        state.set_status(AppStatus::Speaking);
+                    }
+                    streamed_text.push(chunk.text.clone());
+                    if let Err(e) = app.emit("speech-chunk", &chunk) {
+                        tracing::warn!("Failed to emit speech-chunk: {}", e);
+                    }
+                };
+                let concurrency = bandwidth::apply(&config).audio.tts_concurrency;
+                let streaming =
+                    pipeline::stream_and_speak(&*llm_client, tts_client, messages, &overrides, concurrency, prepare, on_chunk);
+                telemetry::measure(Stage::Llm, &config, streaming)
+                    .instrument(tracing::info_span!("llm", streaming = true))
+                    .await
+                    .map(|streamed| {
+                        // Speech overlaps generation, so both stages span the whole reply
+                        stages.tts = Some(StageTiming::since(Stage::Tts, &config, &tts_routed, llm_started));
+                        streamed_audio = Some(streamed.audio);
+                        streamed.reply
+                    })
+            } else {
+                let retry_messages = config.openwebui.retry.enabled.then(|| messages.clone());
+                let reply = telemetry::measure(Stage::Llm, &config, llm_client.send_message_with(messages, &overrides))
+                    .instrument(tracing::info_span!("llm", streaming = false))
+                    .await;
+                match retry_messages {
+                    Some(messages) => {
+                        let (reply, was_retried) =
+                            retry_unhelpful(app, &config, llm_client.as_ref(), messages, &[], &overrides, reply).await;
+                        retried = was_retried;
+                        reply
+                    }
+                    None => reply,
+                }
+            };
+
+            let (llm_response, shown_reasoning) = reply
+                .and_then(|reply| take_answer(app, &config, reply))
+                .map_err(|e| {
+                    experiments::record_error(&state.get_conversation());
+                    state.set_status(AppStatus::Error {
+                        message: localized(state, &e),
+                    });
+                    localized(state, &e)
+                })?;
+            let llm_response = hooks::run(&config.hooks, HookStage::PostLlm, llm_response).await;
+            reasoning = shown_reasoning;
+            stages.llm = Some(llm_timing(&config, &llm_routed, &overrides, llm_started));
+
+            tracing::info!("LLM response: {}", log_privacy::content(&llm_response));
+            state.add_message(MessageRole::Assistant, llm_response.clone());
+            experiments::record_reply(&state.get_conversation());
+            persist_latest_message(state, history);
+            follow_ups = suggest_follow_ups(app, state).await;
+
+            llm_response
+        }
+    };
+
+    // Step 3: Convert to speech, unless it was already spoken while streaming or replies are written only
+    let streamed = streamed_audio.is_some();
+    let mut spoken_text = None;
+    let audio_response = match streamed_audio {
+        Some(audio) => {
+            spoken_text = Some(streamed_text.join(" "));
+            audio
+        }
+        None if !speak => Vec::new(),
+        None => {
+            // Code blocks and markup are shown but not read aloud
+            let spoken = redaction::apply(&config.privacy.redaction, "speech", speech_text::clean(&llm_response));
+            let spoken = hooks::run(&config.hooks, HookStage::PreTts, spoken).await;
+            spoken_text = Some(spoken.clone());
+            if spoken.trim().is_empty() {
+                tracing::info!("Nothing to speak in the reply");
+                Vec::new()
+            } else {
+                state.set_status(AppStatus::Speaking);
+                let tts_client = state.cooldowns().tts_provider(&config, &api_keys)
+                    .map_err(|e| localized(state, &e))?;
+
+                let tts_routed = state.cooldowns().routed(Stage::Tts, &config);
+                let tts_started = Instant::now();
+                let audio = telemetry::measure(Stage::Tts, &config, tts_client.synthesize_speech(&spoken))
+                    .instrument(tracing::info_span!("tts"))
+                    .await
+                    .map_err(|e| {
+                        state.set_status(AppStatus::Error {
+                            message: localized(state, &e),
+                        });
+                        localized(state, &e)
+                    })?;
+                stages.tts = Some(StageTiming::since(Stage::Tts, &config, &tts_routed, tts_started));
+                audio
+            }
+        }
+    };
 
-    log::info!("Speech synthesis complete: {} bytes", audio_response.len());
+    if speak {
+        tracing::info!("Speech synthesis complete: {} bytes", audio_response.len());
+        save_recording(state, RecordingKind::Reply, &audio_response, "reply.mp3", Some(&llm_response));
+        state.set_last_response(llm_response.clone(), audio_response.clone());
+    }
+    if stages.llm.is_some() {
+        state.set_reply_stages(stages.clone());
+    }
 
     // Reset status
     state.set_status(AppStatus::Idle);
@@ -214,162 +842,2484 @@ pub async fn process_voice_query(
     Ok(VoiceQueryResponse {
         transcription,
         llm_response,
+        spoken_text,
         audio_response,
+        moderation,
+        policy,
+        follow_ups,
+        intent: None,
+        translation: None,
+        reasoning,
+        streamed,
+        speaker: None,
+        stages,
+        alternatives: Vec::new(),
+        retried,
+        awaiting_confirmation: false,
     })
 }
 
+/// Timing of the LLM stage, naming the conversation's own model if it picked one
+fn llm_timing(config: &AppConfig, routed: &AppConfig, overrides: &GenerationOverrides, started: Instant) -> StageTiming {
+    let mut timing = StageTiming::since(Stage::Llm, config, routed, started);
+    if let Some(model) = &overrides.model {
+        timing.model = Some(model.clone());
+    }
+    timing
+}
+
 /// Response structure for voice query
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VoiceQueryResponse {
     pub transcription: String,
+    /// Full reply as markdown, for display
     pub llm_response: String,
+    /// What was read aloud: the reply without code blocks or markup, after redaction and
+    /// `pre_tts` hooks; absent when the reply was not spoken or a built-in command answered
+    pub spoken_text: Option<String>,
     pub audio_response: Vec<u8>,
+    /// Present when moderation flagged the transcription
+    pub moderation: Option<ModerationVerdict>,
+    /// Present when the topic policy refused the transcription
+    pub policy: Option<PolicyVerdict>,
+    /// Suggested follow-up questions (empty unless enabled)
+    pub follow_ups: Vec<String>,
+    /// Built-in command that was executed instead of querying the LLM
+    pub intent: Option<VoiceIntent>,
+    /// Languages of the turn when translator mode handled the query
+    pub translation: Option<TranslationTurn>,
+    /// Model reasoning, when `openwebui.show_reasoning` is enabled and the model returned any
+    pub reasoning: Option<String>,
+    /// The reply was sent as `speech-chunk` events while generating; `audio_response` holds the same audio
+    pub streamed: bool,
+    /// Enrolled profile recognized as the speaker, when speaker identification is on
+    pub speaker: Option<SpeakerMatch>,
+    /// Which provider handled each stage and how long it took; stages that did not run are absent
+    pub stages: StageTimings,
+    /// Other hypotheses for an uncertain transcription, most likely first (empty unless `whisper.alternatives`)
+    pub alternatives: Vec<TranscriptionCandidate>,
+    /// The first answer was empty or a stock refusal and the query was sent again (`openwebui.retry`)
+    pub retried: bool,
+    /// The reply asks to confirm `intent`; the next voice query is taken as the answer
+    pub awaiting_confirmation: bool,
 }
 
-/// Load application configuration
-#[tauri::command]
-pub async fn load_config(state: State<'_, AppState>) -> Result<AppConfig, String> {
-    log::info!("Loading configuration");
-    Ok(state.get_config())
+impl VoiceQueryResponse {
+    /// Response to a built-in command, which skips moderation, the LLM, and follow-ups
+    fn command(transcription: String, intent: VoiceIntent, llm_response: String, audio_response: Vec<u8>) -> Self {
+        Self {
+            transcription,
+            llm_response,
+            spoken_text: None,
+            audio_response,
+            moderation: None,
+            policy: None,
+            follow_ups: Vec::new(),
+            intent: Some(intent),
+            translation: None,
+            reasoning: None,
+            streamed: false,
+            speaker: None,
+            stages: StageTimings::default(),
+            alternatives: Vec::new(),
+            retried: false,
+            awaiting_confirmation: false,
+        }
+    }
 }
 
-/// Save application configuration
-#[tauri::command]
-pub async fn save_config(
-    config: AppConfig,
-    state: State<'_, AppState>,
-) -> Result<(), String> {
-    log::info!("Saving configuration");
+/// Translate one utterance in translator mode and hand the next turn to the other speaker
+///
+/// Translations are kept out of the conversation, history, and recordings.
+async fn translate_voice_turn(
+    audio_data: Vec<u8>,
+    filename: String,
+    direction: TranslationDirection,
+    app: &AppHandle,
+    state: &AppState,
+) -> Result<VoiceQueryResponse, String> {
+    let config = state.get_config();
+    let turn = TranslationTurn::new(&config.translator, direction);
+    usage::record_feature("translator");
+    tracing::info!("Translating {} -> {}", turn.source_language, turn.target_language);
 
-    // Update state
-    state.update_config(config.clone());
+    match run_translation(audio_data, &filename, turn, &config, app, state).await {
+        Ok(response) => {
+            state.advance_translator();
+            state.set_status(AppStatus::Idle);
+            Ok(response)
+        }
+        Err(e) => {
+            tracing::error!("Translation failed: {}", e);
+            usage::record_error(&e);
+            state.set_status(AppStatus::Error {
+                message: localized(state, &e),
+            });
+            Err(localized(state, &e))
+        }
+    }
+}
 
-    // Persist to disk
-    let config_manager = ConfigManager::new().map_err(|e| e.to_string())?;
-    config_manager.save(&config).map_err(|e| e.to_string())?;
+async fn run_translation(
+    audio_data: Vec<u8>,
+    filename: &str,
+    turn: TranslationTurn,
+    config: &AppConfig,
+    app: &AppHandle,
+    state: &AppState,
+) -> AppResult<VoiceQueryResponse> {
+    let api_keys = state.get_api_keys();
 
-    log::info!("Configuration saved successfully");
-    Ok(())
+    // Each turn pins the transcription language and speaks with the target language's voice
+    let mut turn_config = config.clone();
+    turn_config.whisper.language = Some(turn.source_language.clone());
+    if let Some(voice_id) = &turn.voice_id {
+        turn_config.elevenlabs.voice_id = voice_id.clone();
+        turn_config.azure.speech_voice = voice_id.clone();
+    }
+
+    let cooldowns = state.cooldowns();
+    let mut stages = StageTimings::default();
+
+    state.set_status(AppStatus::Transcribing);
+    let (routed, started) = (cooldowns.routed(Stage::Stt, &turn_config), Instant::now());
+    let transcription = cooldowns.stt_provider(&turn_config, &api_keys)?
+        .transcribe_audio(prepare_recording(config, audio_data), filename)
+        .instrument(tracing::info_span!("stt"))
+        .await?;
+    stages.stt = Some(StageTiming::since(Stage::Stt, &turn_config, &routed, started));
+    let transcription = redaction::apply(&config.privacy.redaction, "transcription", transcription);
+    record_transcription(app, state, &transcription, TranscriptionSource::Translation);
+
+    state.set_status(AppStatus::Thinking);
+    let overrides = GenerationOverrides {
+        system_prompt: Some(turn.instructions()),
+        temperature: Some(0.2),
+        ..GenerationOverrides::default()
+    };
+    let (routed, started) = (cooldowns.routed(Stage::Llm, &turn_config), Instant::now());
+    let translation = cooldowns.llm_provider(&turn_config, &api_keys)?
+        .send_message_with(vec![("user".to_string(), transcription.clone())], &overrides)
+        .instrument(tracing::info_span!("llm", streaming = false))
+        .await?
+        .answer;
+    stages.llm = Some(StageTiming::since(Stage::Llm, &turn_config, &routed, started));
+    let translation = profanity::apply(&config.profanity_filter, translation);
+
+    state.set_status(AppStatus::Speaking);
+    let (routed, started) = (cooldowns.routed(Stage::Tts, &turn_config), Instant::now());
+    let audio_response = cooldowns.tts_provider(&turn_config, &api_keys)?
+        .synthesize_speech(&translation)
+        .instrument(tracing::info_span!("tts"))
+        .await?;
+    stages.tts = Some(StageTiming::since(Stage::Tts, &turn_config, &routed, started));
+    state.set_last_response(translation.clone(), audio_response.clone());
+
+    Ok(VoiceQueryResponse {
+        transcription,
+        spoken_text: Some(translation.clone()),
+        llm_response: translation,
+        audio_response,
+        moderation: None,
+        policy: None,
+        follow_ups: Vec::new(),
+        intent: None,
+        translation: Some(turn),
+        reasoning: None,
+        streamed: false,
+        speaker: None,
+        stages,
+        alternatives: Vec::new(),
+        retried: false,
+        awaiting_confirmation: false,
+    })
 }
 
-/// Update API key for a service
-#[tauri::command]
-pub async fn update_api_key(
-    service: String,
-    api_key: String,
-    state: State<'_, AppState>,
-) -> Result<(), String> {
-    log::info!("Updating API key for service: {}", service);
+/// An error's message in the configured locale
+fn localized(state: &AppState, error: &AppError) -> String {
+    error.localized(&state.get_config().ui.locale)
+}
 
-    // Store in keyring
-    let config_manager = ConfigManager::new().map_err(|e| e.to_string())?;
-    config_manager
-        .store_api_key(&service, &api_key)
-        .map_err(|e| e.to_string())?;
+/// Fail with the localized "session locked" error while the session is locked
+fn ensure_unlocked(state: &AppState) -> Result<(), String> {
+    state.session().ensure_unlocked().map_err(|e| localized(state, &e))
+}
 
-    // Update state
-    let mut api_keys = state.get_api_keys();
-    match service.as_str() {
-        "whisper" => api_keys.whisper = Some(api_key),
-        "openwebui" => api_keys.openwebui = Some(api_key),
-        "elevenlabs" => api_keys.elevenlabs = Some(api_key),
-        _ => return Err(format!("Unknown service: {}", service)),
+/// Fail with the localized kiosk mode error while kiosk mode is on
+fn ensure_not_kiosk(state: &AppState) -> Result<(), String> {
+    if state.get_config().kiosk.enabled {
+        return Err(localized(state, &AppError::KioskMode));
     }
-    state.update_api_keys(api_keys);
-
-    log::info!("API key updated for {}", service);
     Ok(())
 }
 
-/// Check connectivity to all services
-#[tauri::command]
-pub async fn check_connectivity(state: State<'_, AppState>) -> Result<ConnectivityResponse, String> {
-    log::info!("Checking connectivity to all services");
+/// Short random ID correlating the log lines of one request
+fn new_request_id() -> String {
+    format!("{:08x}", rand::random::<u32>())
+}
 
-    let config = state.get_config();
-    let api_keys = state.get_api_keys();
+/// A pipeline request being timed for the request journal
+struct JournaledRequest {
+    id: String,
+    kind: &'static str,
+    started_at: SystemTime,
+    parameters: RequestParameters,
+}
 
-    // Check Whisper
-    state.update_service_status("whisper", ServiceStatus::Checking);
-    let whisper_status = match WhisperClient::new(config.whisper.clone(), api_keys.whisper.clone()) {
-        Ok(client) => {
-            match client.check_connectivity().await {
-                Ok(true) => ServiceStatus::Connected,
-                Ok(false) => ServiceStatus::Disconnected {
-                    reason: "Service unreachable".to_string(),
-                },
-                Err(e) => ServiceStatus::Disconnected {
-                    reason: e.to_string(),
-                },
-            }
+impl JournaledRequest {
+    /// Start timing a request of `kind` and tag the current span with a fresh `request_id`
+    fn start(kind: &'static str, state: &AppState, spoken: bool, speak: bool, input_size: usize) -> Self {
+        let id = new_request_id();
+        tracing::Span::current().record("request_id", id.as_str());
+        let config = bandwidth::apply(&state.get_config());
+        Self {
+            id,
+            kind,
+            started_at: SystemTime::now(),
+            parameters: RequestParameters::new(&config, spoken, speak, input_size),
         }
-        Err(e) => ServiceStatus::Disconnected {
-            reason: e.to_string(),
-        },
-    };
-    state.update_service_status("whisper", whisper_status.clone());
+    }
 
-    // Check OpenWebUI
-    state.update_service_status("openwebui", ServiceStatus::Checking);
-    let openwebui_status = match OpenWebUiClient::new(config.openwebui.clone(), api_keys.openwebui.clone()) {
-        Ok(client) => {
-            match client.check_connectivity().await {
-                Ok(true) => ServiceStatus::Connected,
-                Ok(false) => ServiceStatus::Disconnected {
-                    reason: "Service unreachable".to_string(),
-                },
-                Err(e) => ServiceStatus::Disconnected {
-                    reason: e.to_string(),
-                },
-            }
-        }
-        Err(e) => ServiceStatus::Disconnected {
-            reason: e.to_string(),
-        },
-    };
-    state.update_service_status("openwebui", openwebui_status.clone());
+    /// Record how the request ended in the journal
+    fn finish(self, result: Result<StageTimings, &String>, failed_stage: Option<&str>) {
+        let outcome = result.map_err(|error| (failed_stage, error.as_str()));
+        journal::record(&JournalEntry::new(&self.id, self.kind, self.started_at, self.parameters, outcome));
+    }
+}
 
-    // Check ElevenLabs
-    state.update_service_status("elevenlabs", ServiceStatus::Checking);
-    let elevenlabs_status = match ElevenLabsClient::new(config.elevenlabs, api_keys.elevenlabs) {
-        Ok(client) => {
-            match client.check_connectivity().await {
-                Ok(true) => ServiceStatus::Connected,
-                Ok(false) => ServiceStatus::Disconnected {
-                    reason: "Service unreachable".to_string(),
-                },
-                Err(e) => ServiceStatus::Disconnected {
-                    reason: e.to_string(),
-                },
-            }
-        }
-        Err(e) => ServiceStatus::Disconnected {
-            reason: e.to_string(),
-        },
+/// Send the query once more, with the retry instruction, if `reply` is empty or a stock refusal
+///
+/// Emits `llm-retry` with the reason, so the UI can drop what it showed of the first answer.
+/// Returns the reply to use and whether the query was sent again.
+async fn retry_unhelpful(
+    app: &AppHandle,
+    config: &AppConfig,
+    llm_client: &dyn LlmProvider,
+    mut messages: Vec<(String, String)>,
+    images: &[ImageAttachment],
+    overrides: &GenerationOverrides,
+    reply: AppResult<LlmReply>,
+) -> (AppResult<LlmReply>, bool) {
+    let reason = match &reply {
+        Ok(reply) => retry::check(&config.openwebui.retry, &reply.answer),
+        Err(_) => None,
+    };
+    let Some(reason) = reason else {
+        return (reply, false);
     };
-    state.update_service_status("elevenlabs", elevenlabs_status.clone());
 
-    log::info!("Connectivity check complete");
+    tracing::info!("Asking again after an unhelpful answer ({:?})", reason);
+    usage::record_feature("llm_retry");
+    if let Err(e) = app.emit("llm-retry", reason) {
+        tracing::warn!("Failed to emit llm-retry: {}", e);
+    }
 
-    Ok(ConnectivityResponse {
-        whisper: whisper_status,
-        openwebui: openwebui_status,
-        elevenlabs: elevenlabs_status,
-    })
+    retry::rephrase(&config.openwebui.retry, &mut messages);
+    let reply = if images.is_empty() {
+        llm_client.send_message_with(messages, overrides).await
+    } else {
+        llm_client.send_message_with_images(messages, images, overrides).await
+    };
+    (reply, true)
 }
 
-/// Connectivity response structure
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ConnectivityResponse {
+/// Split an LLM reply into the filtered answer and the reasoning to show, if enabled
+///
+/// Reasoning goes to the UI as an `llm-reasoning` event and is never part of the answer,
+/// so it is not stored in the conversation or spoken.
+fn take_answer(app: &AppHandle, config: &AppConfig, reply: LlmReply) -> AppResult<(String, Option<String>)> {
+    if reply.answer.is_empty() && reply.reasoning.is_some() {
+        return Err(OpenWebUiError::ReasoningOnly.into());
+    }
+
+    let reasoning = reply
+        .reasoning
+        .filter(|_| config.openwebui.show_reasoning)
+        .map(|reasoning| profanity::apply(&config.profanity_filter, reasoning));
+    if let Some(reasoning) = &reasoning {
+        if let Err(e) = app.emit("llm-reasoning", reasoning) {
+            tracing::warn!("Failed to emit llm-reasoning: {}", e);
+        }
+    }
+
+    Ok((profanity::apply(&config.profanity_filter, reply.answer), reasoning))
+}
+
+/// Execute a built-in voice command, returning the reply text and any audio to play
+async fn run_voice_intent(
+    intent: &VoiceIntent,
+    app: &AppHandle,
+    state: &AppState,
+) -> Result<(String, Vec<u8>), String> {
+    tracing::info!("Executing voice command: {:?}", intent);
+    let locale = state.get_config().ui.locale;
+
+    match intent {
+        VoiceIntent::ClearConversation => {
+            state.clear_conversation();
+            Ok((i18n::tr(&locale, "conversation-cleared"), Vec::new()))
+        }
+        VoiceIntent::StopTalking => {
+            let ducker = app.state::<AudioDucker>();
+            if ducker.is_ducked() {
+                if let Err(e) = ducker.restore() {
+                    tracing::warn!("{}", e);
+                }
+            }
+            Ok((String::new(), Vec::new()))
+        }
+        VoiceIntent::SwitchVoice { voice } => {
+            ensure_not_kiosk(state)?;
+            switch_voice(voice, app, state).await
+        }
+        VoiceIntent::RepeatLast => match last_response_audio(state).await? {
+            Some(last) => Ok((last.text, last.audio)),
+            None => Ok((i18n::tr(&locale, "nothing-to-repeat"), Vec::new())),
+        },
+        VoiceIntent::CreateTicket { summary } => {
+            let ticket = open_ticket(state, summary, VOICE_TICKET_DESCRIPTION)
+                .await
+                .map_err(|e| localized(state, &e))?;
+
+            // The number is spoken; the link is only shown
+            let spoken = i18n::tr_args(&locale, "ticket-created", &[("number", &ticket.number)]);
+            let audio = speak_notice(state, &spoken).await;
+            Ok((format!("{} {}", spoken, ticket.url), audio))
+        }
+        VoiceIntent::AdjustNextReply { modifier } => {
+            state.set_reply_modifier(*modifier);
+            let key = match modifier {
+                ReplyModifier::Brief => "next-reply-brief",
+                ReplyModifier::Creative => "next-reply-creative",
+                ReplyModifier::Precise => "next-reply-precise",
+            };
+            Ok((i18n::tr(&locale, key), Vec::new()))
+        }
+    }
+}
+
+/// Question asking the user to confirm a voice command that takes action
+fn confirmation_question(locale: &str, intent: &VoiceIntent) -> String {
+    match intent {
+        VoiceIntent::CreateTicket { summary } => i18n::tr_args(locale, "confirm-create-ticket", &[("summary", summary)]),
+        _ => i18n::tr(locale, "confirm-action"),
+    }
+}
+
+/// Speech of a short reply to a voice command; empty, with a warning logged, if synthesis fails
+async fn speak_notice(state: &AppState, text: &str) -> Vec<u8> {
+    match state.cooldowns().tts_provider(&state.get_config(), &state.get_api_keys()) {
+        Ok(tts) => tts.synthesize_speech(text).await,
+        Err(e) => Err(e),
+    }
+    .unwrap_or_else(|e| {
+        tracing::warn!("Failed to speak '{}': {}", log_privacy::content(text), e);
+        Vec::new()
+    })
+}
+
+/// Description of tickets opened by voice command
+const VOICE_TICKET_DESCRIPTION: &str = "Opened by voice command from Talk to CMAC.";
+
+/// Open a ticket in the configured ticket system, with the token from the keyring
+async fn open_ticket(state: &AppState, summary: &str, description: &str) -> AppResult<Ticket> {
+    let config = state.get_config().ticketing;
+    if !config.enabled {
+        return Err(TicketingError::NotConfigured("ticketing is disabled").into());
+    }
+
+    let token = ConfigManager::new()?.get_secret(&config.token_secret)?;
+    let mut chars = summary.trim().chars();
+    let summary: String = chars.next().map(|first| first.to_uppercase().chain(chars).collect()).unwrap_or_default();
+
+    usage::record_feature("ticket");
+    TicketingClient::new(config, token)?.create(&summary, description).await
+}
+
+/// Audio of the last reply, synthesizing (and caching) it only if it was never spoken
+///
+/// Returns `None` when the conversation has no assistant reply yet.
+async fn last_response_audio(state: &AppState) -> Result<Option<LastResponse>, String> {
+    if let Some(last) = state.get_last_response() {
+        return Ok(Some(last));
+    }
+
+    let last_reply = state
+        .get_conversation()
+        .messages
+        .iter()
+        .rev()
+        .find(|message| message.role == MessageRole::Assistant)
+        .map(|message| message.content.clone());
+
+    let Some(text) = last_reply else {
+        return Ok(None);
+    };
+
+    state.set_status(AppStatus::Speaking);
+    let config = state.get_config();
+    let spoken = redaction::apply(&config.privacy.redaction, "speech", text.clone());
+    let audio = state.cooldowns().tts_provider(&config, &state.get_api_keys())
+        .map_err(|e| localized(state, &e))?
+        .synthesize_speech(&spoken)
+        .await
+        .map_err(|e| localized(state, &e))?;
+
+    state.set_last_response(text, audio);
+    Ok(state.get_last_response())
+}
+
+/// Switch to the first ElevenLabs voice whose name and labels contain every word of `query`
+async fn switch_voice(query: &str, app: &AppHandle, state: &AppState) -> Result<(String, Vec<u8>), String> {
+    let mut config = state.get_config();
+
+    // A preset named like the request wins over the voice library
+    if config.elevenlabs.presets.iter().any(|preset| preset.name.eq_ignore_ascii_case(query)) {
+        let preset = apply_preset(app, state, query).map_err(|e| localized(state, &e))?;
+        return Ok((i18n::tr_args(&config.ui.locale, "voice-switched", &[("name", &preset.name)]), Vec::new()));
+    }
+
+    let voices = ElevenLabsClient::new(config.elevenlabs.clone(), state.get_api_keys().elevenlabs)
+        .map_err(|e| localized(state, &e))?
+        .list_voices()
+        .await
+        .map_err(|e| localized(state, &e))?;
+
+    let matched = voices.into_iter().find(|voice| {
+        let mut haystack = voice.name.to_lowercase();
+        for value in voice.labels.iter().flat_map(|labels| labels.values()) {
+            haystack.push(' ');
+            haystack.push_str(&value.to_lowercase());
+        }
+        query.split_whitespace().all(|word| haystack.contains(word))
+    });
+
+    let Some(voice) = matched else {
+        return Ok((i18n::tr_args(&config.ui.locale, "voice-not-found", &[("query", &query)]), Vec::new()));
+    };
+
+    config.elevenlabs.voice_id = voice.voice_id.clone();
+    let config_manager = ConfigManager::new().map_err(|e| localized(state, &e))?;
+    config_manager.save(&config).map_err(|e| localized(state, &e))?;
+    let reply = i18n::tr_args(&config.ui.locale, "voice-switched", &[("name", &voice.name)]);
+    state.update_config(config);
+    refresh_voice_presets_menu(app);
+
+    tracing::info!("Switched voice to '{}' ({})", voice.name, voice.voice_id);
+    Ok((reply, Vec::new()))
+}
+
+/// Expand a voice-activated template if the transcription starts with its trigger phrase
+///
+/// Emits `template-triggered` with the template name; otherwise returns the transcription unchanged.
+fn apply_template(app: &AppHandle, config: &AppConfig, transcription: &str) -> String {
+    let Some(matched) = templates::match_template(&config.templates, transcription) else {
+        return transcription.to_string();
+    };
+
+    tracing::info!("Running template '{}'", matched.name);
+    if let Err(e) = app.emit("template-triggered", &matched.name) {
+        tracing::warn!("Failed to emit template-triggered: {}", e);
+    }
+    matched.prompt
+}
+
+/// Moderate user input and report flagged input via the `moderation-flagged` event
+async fn moderate_input(
+    app: &AppHandle,
+    config: &AppConfig,
+    api_key: Option<String>,
+    text: &str,
+) -> Option<ModerationVerdict> {
+    let verdict = moderation::review(&config.moderation, api_key, text).await?;
+
+    if let Err(e) = app.emit("moderation-flagged", &verdict) {
+        tracing::warn!("Failed to emit moderation-flagged: {}", e);
+    }
+    Some(verdict)
+}
+
+/// Check user input against the topic policy and report refusals via the `policy-refused` event
+async fn check_policy(app: &AppHandle, state: &AppState, config: &AppConfig, text: &str) -> Option<PolicyVerdict> {
+    if !config.policy.enabled {
+        return None;
+    }
+
+    let classifier = if config.policy.llm_classifier {
+        match state.cooldowns().llm_provider(config, &state.get_api_keys()) {
+            Ok(client) => Some(client),
+            Err(e) => {
+                tracing::warn!("Topic classifier unavailable: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let verdict = policy::review(&config.policy, classifier.as_deref(), text).await?;
+
+    usage::record_feature("policy_refusal");
+    if let Err(e) = app.emit("policy-refused", &verdict) {
+        tracing::warn!("Failed to emit policy-refused: {}", e);
+    }
+    Some(verdict)
+}
+
+/// Ask for follow-up questions to the latest reply and report them via the `follow-up-suggestions` event
+///
+/// Returns an empty list when disabled or if the request fails, so suggestions never break a query.
+async fn suggest_follow_ups(app: &AppHandle, state: &AppState) -> Vec<String> {
+    let config = bandwidth::apply(&state.get_config());
+    if !config.openwebui.follow_up_suggestions {
+        return Vec::new();
+    }
+
+    let result = match state.cooldowns().llm_provider(&config, &state.get_api_keys()) {
+        Ok(client) => {
+            let overrides = experiments::overrides(&config.experiment, &state.get_conversation());
+            let messages = context::select(client.as_ref(), &config.context, state.get_api_messages()).await;
+            client.suggest_follow_ups(messages, overrides).await
+        }
+        Err(e) => Err(e),
+    };
+
+    let follow_ups: Vec<String> = match result {
+        Ok(follow_ups) => follow_ups
+            .into_iter()
+            .map(|question| profanity::apply(&config.profanity_filter, question))
+            .collect(),
+        Err(e) => {
+            tracing::warn!("Failed to generate follow-up suggestions: {}", e);
+            return Vec::new();
+        }
+    };
+
+    if !follow_ups.is_empty() {
+        if let Err(e) = app.emit("follow-up-suggestions", &follow_ups) {
+            tracing::warn!("Failed to emit follow-up-suggestions: {}", e);
+        }
+    }
+    follow_ups
+}
+
+/// Write the newest message of the current conversation to persistent history
+///
+/// Skipped for incognito conversations and when the retention policy keeps
+/// nothing; failures are logged only.
+fn persist_latest_message(state: &AppState, history: &HistoryStore) {
+    if !state.get_config().privacy.retention.keeps_history() {
+        return;
+    }
+
+    let conversation = state.get_conversation();
+    if conversation.ephemeral {
+        return;
+    }
+
+    if let Some(message) = conversation.messages.last() {
+        if let Err(e) = history.append_message(&conversation, message) {
+            tracing::warn!("Failed to save message to history: {}", e);
+        }
+    }
+}
+
+/// A recording as it is transcribed: reduced to the configured input channel, amplified if quiet,
+/// then stripped of leading and trailing silence
+fn prepare_recording(config: &AppConfig, audio: Vec<u8>) -> Vec<u8> {
+    let audio = downmix::apply(&config.audio, audio);
+    let audio = gain::apply(&config.audio, audio);
+    trim::apply(&config.audio, audio)
+}
+
+/// Save audio to the recordings store if enabled, then apply the retention limits
+///
+/// Failures are logged rather than returned so a full disk never breaks a query.
+fn save_recording(
+    state: &AppState,
+    kind: RecordingKind,
+    audio: &[u8],
+    filename: &str,
+    text: Option<&str>,
+) {
+    let config = state.get_config();
+    if !config.audio.save_recordings
+        || config.privacy.retention == RetentionPolicy::Nothing
+        || state.is_incognito()
+    {
+        return;
+    }
+
+    let format = std::path::Path::new(filename)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("wav");
+
+    let result = RecordingStore::open_default().and_then(|store| {
+        store.save(kind, audio, format, text)?;
+        store.prune(
+            retention::recording_retention_days(&config),
+            config.audio.max_recordings,
+        )
+    });
+
+    if let Err(e) = result {
+        tracing::warn!("Failed to save recording: {}", e);
+    }
+}
+
+/// List saved recordings, newest first
+#[tauri::command]
+pub async fn list_recordings(state: State<'_, AppState>) -> Result<Vec<RecordingInfo>, String> {
+    ensure_unlocked(&state)?;
+    ensure_not_kiosk(&state)?;
+    RecordingStore::open_default()
+        .and_then(|store| store.list())
+        .map_err(|e| localized(&state, &e))
+}
+
+/// Get the audio bytes of a saved recording for playback
+#[tauri::command]
+pub async fn play_recording(id: String, state: State<'_, AppState>) -> Result<Vec<u8>, String> {
+    tracing::info!("Loading recording {}", id);
+    ensure_unlocked(&state)?;
+    ensure_not_kiosk(&state)?;
+    RecordingStore::open_default()
+        .and_then(|store| store.read(&id))
+        .map_err(|e| localized(&state, &e))
+}
+
+/// Delete a saved recording
+#[tauri::command]
+pub async fn delete_recording(id: String, state: State<'_, AppState>) -> Result<(), String> {
+    ensure_unlocked(&state)?;
+    ensure_not_kiosk(&state)?;
+    RecordingStore::open_default()
+        .and_then(|store| store.delete(&id))
+        .map_err(|e| localized(&state, &e))
+}
+
+/// List conversations stored in history, most recently updated first
+///
+/// Lists the active conversations, or the archived ones if `archived` is true.
+#[tauri::command]
+pub async fn list_conversations(
+    archived: Option<bool>,
+    state: State<'_, AppState>,
+    history: State<'_, HistoryStore>,
+) -> Result<Vec<ConversationSummary>, String> {
+    ensure_unlocked(&state)?;
+    ensure_not_kiosk(&state)?;
+    history.list_conversations(archived.unwrap_or(false)).map_err(|e| localized(&state, &e))
+}
+
+/// Move an archived conversation back to the active list, returning whether it was archived
+#[tauri::command]
+pub async fn restore_conversation(
+    id: String,
+    state: State<'_, AppState>,
+    history: State<'_, HistoryStore>,
+) -> Result<bool, String> {
+    ensure_unlocked(&state)?;
+    ensure_not_kiosk(&state)?;
+    history.restore_conversation(&id).map_err(|e| localized(&state, &e))
+}
+
+/// Load a conversation stored in history
+#[tauri::command]
+pub async fn get_stored_conversation(
+    id: String,
+    state: State<'_, AppState>,
+    history: State<'_, HistoryStore>,
+) -> Result<Option<crate::state::ConversationContext>, String> {
+    ensure_unlocked(&state)?;
+    ensure_not_kiosk(&state)?;
+    let max_messages = state.get_conversation().max_messages;
+    history.get_conversation(&id, max_messages).map_err(|e| localized(&state, &e))
+}
+
+/// Unlock passphrase-protected conversation history
+#[tauri::command]
+pub async fn unlock_history(
+    passphrase: String,
+    state: State<'_, AppState>,
+    history: State<'_, HistoryStore>,
+) -> Result<(), String> {
+    history.unlock(&passphrase).map_err(|e| localized(&state, &e))
+}
+
+/// Set, change, or remove (`null`) the passphrase protecting conversation history
+#[tauri::command]
+pub async fn set_history_passphrase(
+    passphrase: Option<String>,
+    state: State<'_, AppState>,
+    history: State<'_, HistoryStore>,
+) -> Result<(), String> {
+    ensure_not_kiosk(&state)?;
+    let passphrase = passphrase.filter(|p| !p.is_empty());
+    let config_manager = ConfigManager::new().map_err(|e| localized(&state, &e))?;
+
+    history
+        .set_passphrase(passphrase.as_deref(), || config_manager.get_or_create_history_key())
+        .map_err(|e| localized(&state, &e))
+}
+
+/// Get the most recent PII redaction audit entries, newest first
+#[tauri::command]
+pub async fn get_redaction_audit(
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<RedactionAuditEntry>, String> {
+    ensure_not_kiosk(&state)?;
+    redaction::read_audit(limit.unwrap_or(100)).map_err(|e| localized(&state, &e))
+}
+
+/// Get the most recent outbound API calls, newest first
+#[tauri::command]
+pub async fn get_audit_log(
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<AuditEntry>, String> {
+    ensure_not_kiosk(&state)?;
+    audit::read(limit.unwrap_or(100)).map_err(|e| localized(&state, &e))
+}
+
+/// Get the most recent pipeline requests with their settings, timings, and outcome, newest first
+#[tauri::command]
+pub async fn get_request_journal(
+    limit: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<JournalEntry>, String> {
+    ensure_not_kiosk(&state)?;
+    journal::read(limit.unwrap_or(100)).map_err(|e| localized(&state, &e))
+}
+
+/// Feature flags that are set, and whether each is on for this install
+#[tauri::command]
+pub async fn get_feature_flags(state: State<'_, AppState>) -> Result<Vec<FeatureFlag>, String> {
+    ensure_not_kiosk(&state)?;
+    Ok(feature_flags::list())
+}
+
+/// Show exactly what anonymous usage reporting would send
+///
+/// Includes the counts so far and any reports queued while offline, whether or
+/// not reporting is enabled.
+#[tauri::command]
+pub async fn view_telemetry_payload(state: State<'_, AppState>) -> Result<TelemetryPayload, String> {
+    usage::payload(&state.get_config().usage_reports).map_err(|e| localized(&state, &e))
+}
+
+/// Open a Jira issue or ServiceNow record, returning its number and link
+///
+/// Needs `ticketing.enabled` and the API token stored under `ticketing.token_secret`.
+#[tauri::command]
+pub async fn create_ticket(
+    summary: String,
+    description: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Ticket, String> {
+    ensure_unlocked(&state)?;
+    if summary.trim().is_empty() {
+        return Err(localized(&state, &AppError::from(ConfigError::InvalidValue("A ticket needs a summary".to_string()))));
+    }
+
+    open_ticket(&state, &summary, description.as_deref().unwrap_or_default())
+        .await
+        .map_err(|e| localized(&state, &e))
+}
+
+/// Pass the current conversation to human support, as set in `handoff`
+///
+/// `reason` is what the user needs help with, if they said. The package is POSTed to
+/// `handoff.endpoint`, or a pre-filled email or web link is opened in the default app.
+#[tauri::command]
+pub async fn request_handoff(reason: Option<String>, app: AppHandle, state: State<'_, AppState>) -> Result<Handoff, String> {
+    tracing::info!("Handing off the conversation to support");
+    ensure_unlocked(&state)?;
+
+    let config = state.get_config();
+    if !config.handoff.enabled {
+        return Err(localized(&state, &AppError::from(ConfigError::MissingConfig("handoff.enabled".to_string()))));
+    }
+
+    let package = handoff::package(&config, &state.get_conversation(), reason);
+    if package.transcript.is_empty() {
+        return Err(i18n::tr(&config.ui.locale, "no-conversation-to-hand-off"));
+    }
+
+    let link = match config.handoff.method {
+        HandoffMethod::Post => {
+            handoff::post(&config.handoff, &package).await.map_err(|e| localized(&state, &e))?;
+            None
+        }
+        HandoffMethod::Email | HandoffMethod::Url => {
+            let link = handoff::link(&config.handoff, &package).map_err(|e| localized(&state, &e))?;
+            app.opener()
+                .open_url(link.clone(), None::<&str>)
+                .map_err(|e| localized(&state, &AppError::Generic(format!("Could not open {}: {}", link, e))))?;
+            Some(link)
+        }
+    };
+
+    usage::record_feature("handoff");
+    Ok(Handoff {
+        method: config.handoff.method,
+        messages: package.transcript.len(),
+        recordings: package.recordings.len(),
+        link,
+    })
+}
+
+/// Today's schedule, spoken
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScheduleBriefing {
+    /// The spoken summary
+    pub text: String,
+
+    /// Synthesized speech of `text` (empty if speech synthesis failed)
+    pub audio: Vec<u8>,
+
+    /// Today's events, earliest first
+    pub events: Vec<CalendarEvent>,
+}
+
+/// Read today's events from the configured calendar and speak a summary of them
+///
+/// Needs `tools.calendar.enabled`. A failed speech synthesis still returns the
+/// text and events.
+#[tauri::command]
+pub async fn speak_today_schedule(state: State<'_, AppState>) -> Result<ScheduleBriefing, String> {
+    tracing::info!("Speaking today's schedule");
+    ensure_unlocked(&state)?;
+    usage::record_feature("schedule_briefing");
+
+    let config = state.get_config();
+    let (start, end) = calendar::today();
+    let events = calendar::upcoming(&config.tools.calendar, start, end)
+        .await
+        .map_err(|e| localized(&state, &e))?;
+
+    let text = calendar::spoken_schedule(&config.ui.locale, &events);
+    let text = redaction::apply(&config.privacy.redaction, "speech", text);
+    let spoken = hooks::run(&config.hooks, HookStage::PreTts, text.clone()).await;
+    let audio = match state.cooldowns().tts_provider(&config, &state.get_api_keys()) {
+        Ok(tts) => tts.synthesize_speech(&spoken).await,
+        Err(e) => Err(e),
+    }
+    .unwrap_or_else(|e| {
+        tracing::warn!("Failed to speak today's schedule: {}", e);
+        Vec::new()
+    });
+
+    if !audio.is_empty() {
+        state.set_last_response(text.clone(), audio.clone());
+    }
+    Ok(ScheduleBriefing { text, audio, events })
+}
+
+/// Read the text in the image file at `path` with Tesseract
+///
+/// Works whether or not `tools.ocr.enabled` is on, which only controls images sent with messages.
+#[tauri::command]
+pub async fn ocr_image(path: String, state: State<'_, AppState>) -> Result<String, String> {
+    tracing::info!("Recognizing text in an image");
+    ensure_unlocked(&state)?;
+    usage::record_feature("ocr");
+
+    let config = state.get_config();
+    ocr::recognize_file(&config.tools.ocr, std::path::Path::new(&path))
+        .await
+        .map_err(|e| localized(&state, &e))
+}
+
+/// The news briefing, spoken
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewsBriefing {
+    /// The LLM's summary of the items
+    pub text: String,
+
+    /// Synthesized speech of `text` (empty if speech synthesis failed)
+    pub audio: Vec<u8>,
+
+    /// The items summarized, feed by feed
+    pub items: Vec<NewsItem>,
+}
+
+/// Read the newest items of the configured news feeds, have the LLM summarize them, and speak the summary
+///
+/// Feeds that fail are skipped. A failed speech synthesis still returns the text and items.
+#[tauri::command]
+pub async fn give_news_briefing(state: State<'_, AppState>) -> Result<NewsBriefing, String> {
+    tracing::info!("Giving the news briefing");
+    ensure_unlocked(&state)?;
+    usage::record_feature("news_briefing");
+
+    let config = state.get_config();
+    if config.news.feeds.is_empty() {
+        return Err(localized(&state, &AppError::from(ConfigError::MissingConfig("news feeds".to_string()))));
+    }
+
+    state.set_status(AppStatus::Thinking);
+    let items = news::latest(&config.news).await;
+    let text = if items.is_empty() {
+        i18n::tr(&config.ui.locale, "news-empty")
+    } else {
+        let overrides = GenerationOverrides {
+            system_prompt: Some(news::instructions(&config.news, &config.ui.locale)),
+            temperature: Some(0.3),
+            ..GenerationOverrides::default()
+        };
+        let listing = redaction::apply(&config.privacy.redaction, "tool", news::describe(&items));
+        let reply = match state.cooldowns().llm_provider(&config, &state.get_api_keys()) {
+            Ok(llm) => llm.send_message_with(vec![("user".to_string(), listing)], &overrides).await,
+            Err(e) => Err(e),
+        };
+        match reply {
+            Ok(reply) => profanity::apply(&config.profanity_filter, reply.answer),
+            Err(e) => {
+                state.set_status(AppStatus::Idle);
+                return Err(localized(&state, &e));
+            }
+        }
+    };
+
+    state.set_status(AppStatus::Speaking);
+    let text = redaction::apply(&config.privacy.redaction, "speech", text);
+    let spoken = hooks::run(&config.hooks, HookStage::PreTts, text.clone()).await;
+    let audio = match state.cooldowns().tts_provider(&config, &state.get_api_keys()) {
+        Ok(tts) => tts.synthesize_speech(&spoken).await,
+        Err(e) => Err(e),
+    }
+    .unwrap_or_else(|e| {
+        tracing::warn!("Failed to speak the news briefing: {}", e);
+        Vec::new()
+    });
+    state.set_status(AppStatus::Idle);
+
+    if !audio.is_empty() {
+        state.set_last_response(text.clone(), audio.clone());
+    }
+    Ok(NewsBriefing { text, audio, items })
+}
+
+/// Get the crash report left by the previous run, if it crashed
+///
+/// The conversation open at the time of the crash has already been restored.
+#[tauri::command]
+pub async fn get_last_crash_report(last_crash: State<'_, LastCrash>) -> Result<Option<CrashReport>, String> {
+    Ok(last_crash.0.clone())
+}
+
+/// Permanently delete all stored conversations, recordings, queued usage reports, the request journal, and the API audit log
+///
+/// The in-memory conversation is cleared as well so nothing from before the
+/// purge can be written back to history.
+#[tauri::command]
+pub async fn purge_all_data(
+    state: State<'_, AppState>,
+    history: State<'_, HistoryStore>,
+) -> Result<(), String> {
+    ensure_not_kiosk(&state)?;
+    tracing::info!("Purging all stored data");
+
+    state.clear_conversation();
+    state.clear_recent_transcriptions();
+    retention::purge_all(&history).map_err(|e| localized(&state, &e))
+}
+
+/// Write every stored conversation, the settings, and the usage counts to a zip file at `path`
+///
+/// API keys are not included.
+#[tauri::command]
+pub async fn export_all_user_data(
+    path: String,
+    state: State<'_, AppState>,
+    history: State<'_, HistoryStore>,
+) -> Result<(), String> {
+    tracing::info!("Exporting all user data to {}", path);
+    ensure_unlocked(&state)?;
+    ensure_not_kiosk(&state)?;
+
+    let config = state.get_config();
+    let archive = usage::payload(&config.usage_reports)
+        .and_then(|usage| user_data::export(&history, &config, &usage))
+        .map_err(|e| localized(&state, &e))?;
+    std::fs::write(&path, &archive).map_err(|e| localized(&state, &StorageError::ExportFailed(e.to_string()).into()))
+}
+
+/// Get the token that `erase_all_user_data` must be called with; valid for five minutes
+#[tauri::command]
+pub async fn request_erase_token(state: State<'_, AppState>) -> Result<String, String> {
+    ensure_unlocked(&state)?;
+    ensure_not_kiosk(&state)?;
+    Ok(user_data::issue_erase_token())
+}
+
+/// Permanently delete everything the active profile has stored and reset its settings
+///
+/// Erases conversations, recordings, usage counts, the request journal and API
+/// audit log, the voiceprint, and the secrets stored in the keyring. `token` must come from `request_erase_token`.
+#[tauri::command]
+pub async fn erase_all_user_data(
+    token: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+    history: State<'_, HistoryStore>,
+) -> Result<(), String> {
+    ensure_unlocked(&state)?;
+    ensure_not_kiosk(&state)?;
+    user_data::redeem_erase_token(&token).map_err(|e| localized(&state, &e))?;
+    tracing::info!("Erasing all user data");
+
+    state.clear_conversation();
+    state.clear_recent_transcriptions();
+    retention::purge_all(&history).map_err(|e| localized(&state, &e))?;
+    usage::clear_counts();
+
+    let app_dir = ConfigManager::app_dir().map_err(|e| localized(&state, &e))?;
+    VoiceprintStore::new(&app_dir)
+        .remove(&ProfileStore::new(&app_dir).active())
+        .map_err(|e| localized(&state, &e))?;
+
+    let config_manager = ConfigManager::new().map_err(|e| localized(&state, &e))?;
+    for name in config_manager.list_secret_names().map_err(|e| localized(&state, &e))? {
+        config_manager.delete_secret(&name).map_err(|e| localized(&state, &e))?;
+    }
+    apply_config(&app, &state, AppConfig::default())?;
+
+    // Keys set in the environment still apply
+    let (_, api_keys) = config_manager.load_with_keys().map_err(|e| localized(&state, &e))?;
+    state.update_api_keys(api_keys);
+    Ok(())
+}
+
+/// Notify the backend that response audio has started playing
+///
+/// Ducks other applications' audio when `audio.duck_system_audio` is enabled.
+#[tauri::command]
+pub async fn playback_started(
+    state: State<'_, AppState>,
+    ducker: State<'_, AudioDucker>,
+) -> Result<(), String> {
+    tracing::debug!("Playback started");
+    state.set_status(AppStatus::Speaking);
+
+    let config = state.get_config();
+    if config.audio.duck_system_audio {
+        // Failing to duck should never block playback
+        if let Err(e) = ducker.duck(config.audio.duck_level) {
+            tracing::warn!("{}", e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Notify the backend that response audio has finished or was stopped
+///
+/// Restores any audio sessions ducked by `playback_started`.
+#[tauri::command]
+pub async fn playback_finished(
+    state: State<'_, AppState>,
+    ducker: State<'_, AudioDucker>,
+) -> Result<(), String> {
+    tracing::debug!("Playback finished");
+
+    if ducker.is_ducked() {
+        if let Err(e) = ducker.restore() {
+            tracing::warn!("{}", e);
+        }
+    }
+
+    if state.get_status() == AppStatus::Speaking {
+        state.set_status(AppStatus::Idle);
+    }
+
+    Ok(())
+}
+
+/// Payload of the `mic-muted-changed` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MicMutedPayload {
+    pub muted: bool,
+}
+
+/// Apply a microphone mute change and notify the frontend and tray
+///
+/// The frontend tears down its capture stream when it receives `mic-muted-changed`
+/// with `muted: true`, so the microphone is released rather than merely ignored.
+pub fn apply_mic_mute(app: &AppHandle, muted: bool) {
+    let state = app.state::<AppState>();
+    state.set_mic_muted(muted);
+
+    if let Some(item) = app.try_state::<crate::TrayMuteItem>() {
+        let _ = item.0.set_checked(muted);
+    }
+
+    if let Err(e) = app.emit("mic-muted-changed", MicMutedPayload { muted }) {
+        tracing::warn!("Failed to emit mic-muted-changed: {}", e);
+    }
+}
+
+/// Mute or unmute the microphone
+#[tauri::command]
+pub async fn set_mic_muted(muted: bool, app: AppHandle) -> Result<(), String> {
+    apply_mic_mute(&app, muted);
+    Ok(())
+}
+
+/// Toggle the microphone mute, returning the new muted state
+#[tauri::command]
+pub async fn toggle_mic_mute(app: AppHandle, state: State<'_, AppState>) -> Result<bool, String> {
+    let muted = !state.is_mic_muted();
+    apply_mic_mute(&app, muted);
+    Ok(muted)
+}
+
+/// Every hotkey action with its shortcut and whether it is registered
+#[tauri::command]
+pub async fn list_hotkeys(app: AppHandle, state: State<'_, AppState>) -> Result<Vec<HotkeyBinding>, String> {
+    Ok(hotkeys::bindings(&state.get_config().ui.hotkeys, |shortcut| hotkeys::is_registered(&app, shortcut)))
+}
+
+/// Check a shortcut typed or captured in the settings before binding it
+///
+/// Fails if the shortcut cannot be parsed. Otherwise reports its canonical form, the
+/// action already using it, and whether another application holds it; the latter is
+/// found by briefly registering the shortcut.
+#[tauri::command]
+pub async fn validate_hotkey(
+    accelerator: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<HotkeyValidation, String> {
+    let shortcut = hotkeys::parse(accelerator.trim()).map_err(|e| localized(&state, &e))?;
+    let bound_to = state
+        .get_config()
+        .ui
+        .hotkeys
+        .iter()
+        .find(|(_, bound)| hotkeys::parse(bound).is_ok_and(|bound| bound == shortcut))
+        .map(|(action, _)| *action);
+
+    Ok(HotkeyValidation {
+        normalized: hotkeys::normalize(&shortcut),
+        bound_to,
+        available: hotkeys::is_available(&app, &shortcut),
+    })
+}
+
+/// Bind a hotkey action to a global shortcut, replacing its previous one
+///
+/// Fails without changing anything if the shortcut is invalid, bound to another
+/// action, or held by another application.
+#[tauri::command]
+pub async fn register_hotkey(
+    action: HotkeyAction,
+    accelerator: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    // Hotkeys stay released while the session is locked
+    ensure_unlocked(&state)?;
+    ensure_not_kiosk(&state)?;
+    let accelerator = accelerator.trim().to_string();
+    let mut config = state.get_config();
+
+    let shortcut = hotkeys::parse(&accelerator).map_err(|e| localized(&state, &e))?;
+    if let Some(other) = hotkeys::find_conflict(&config.ui.hotkeys, action, &shortcut) {
+        return Err(localized(&state, &AppError::from(ConfigError::InvalidValue(format!(
+            "'{}' is already the {} hotkey",
+            accelerator,
+            other.name()
+        )))));
+    }
+
+    let previous = config.ui.hotkeys.insert(action, accelerator.clone());
+    if let Some(previous) = &previous {
+        hotkeys::unregister(&app, previous);
+    }
+    if let Err(e) = hotkeys::register(&app, action, &accelerator) {
+        if let Some(previous) = &previous {
+            let _ = hotkeys::register(&app, action, previous);
+        }
+        return Err(localized(&state, &e));
+    }
+
+    ConfigManager::new()
+        .and_then(|manager| manager.save(&config))
+        .map_err(|e| localized(&state, &e))?;
+    state.update_config(config);
+    tracing::info!("Bound {} hotkey to {}", action.name(), accelerator);
+    Ok(())
+}
+
+/// Remove the shortcut of a hotkey action
+#[tauri::command]
+pub async fn unregister_hotkey(
+    action: HotkeyAction,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    ensure_not_kiosk(&state)?;
+    let mut config = state.get_config();
+    let Some(accelerator) = config.ui.hotkeys.remove(&action) else {
+        return Ok(());
+    };
+    hotkeys::unregister(&app, &accelerator);
+
+    ConfigManager::new()
+        .and_then(|manager| manager.save(&config))
+        .map_err(|e| localized(&state, &e))?;
+    state.update_config(config);
+    tracing::info!("Removed {} hotkey", action.name());
+    Ok(())
+}
+
+/// Open the quick-ask popup next to the tray icon
+///
+/// Async because creating a window from a synchronous command deadlocks on Windows.
+#[tauri::command]
+pub async fn show_quick_ask(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    quick_ask::show(&app).map_err(|e| localized(&state, &e))
+}
+
+/// Hide the quick-ask popup
+#[tauri::command]
+pub async fn hide_quick_ask(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    quick_ask::hide(&app).map_err(|e| localized(&state, &e))
+}
+
+/// Selected text that was read aloud
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpokenSelection {
+    /// The text spoken, cut to the first 5000 characters at a sentence boundary
+    pub text: String,
+
+    /// Its audio, to play
+    pub audio: Vec<u8>,
+}
+
+/// Read the text selected in the focused application aloud
+///
+/// The selection is copied with the platform's copy shortcut; see the `clipboard` module.
+/// Bound to the `speak_selection` hotkey, where the audio arrives as a `selection-spoken` event.
+#[tauri::command]
+pub async fn speak_selection(state: State<'_, AppState>) -> Result<SpokenSelection, String> {
+    ensure_unlocked(&state)?;
+    read_selection_aloud(&state).await.map_err(|e| localized(&state, &e))
+}
+
+/// Run by the `speak_selection` hotkey: read the selection aloud and emit `selection-spoken`
+pub(crate) async fn speak_selection_hotkey(app: AppHandle) {
+    let state = app.state::<AppState>();
+    if state.session().ensure_unlocked().is_err() {
+        return;
+    }
+    match read_selection_aloud(&state).await {
+        Ok(spoken) => {
+            if let Err(e) = app.emit("selection-spoken", spoken) {
+                tracing::warn!("Failed to emit selection-spoken event: {}", e);
+            }
+        }
+        Err(e) => tracing::warn!("Could not read the selection aloud: {}", e),
+    }
+}
+
+async fn read_selection_aloud(state: &AppState) -> AppResult<SpokenSelection> {
+    let selection = tokio::task::spawn_blocking(clipboard::grab_selection)
+        .await
+        .map_err(|e| AppError::Generic(e.to_string()))??;
+    let text = clipboard::limit_text(selection.trim(), clipboard::MAX_SELECTION_CHARS).to_string();
+    tracing::info!("Reading the selection aloud: {} chars", text.len());
+    usage::record_feature("speak_selection");
+
+    let config = state.get_config();
+    let text = redaction::apply(&config.privacy.redaction, "speech", text);
+    let tts_client = state.cooldowns().tts_provider(&config, &state.get_api_keys())?;
+    let spoken = hooks::run(&config.hooks, HookStage::PreTts, text.clone()).await;
+
+    state.set_status(AppStatus::Speaking);
+    let audio = tts_client.synthesize_speech(&spoken).await;
+    state.set_status(AppStatus::Idle);
+    let audio = audio?;
+
+    state.set_last_response(text.clone(), audio.clone());
+    Ok(SpokenSelection { text, audio })
+}
+
+/// Result of a clipboard action, already on the clipboard
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClipboardResult {
+    pub action: ClipboardAction,
+
+    /// The summary or translation
+    pub text: String,
+
+    /// Synthesized speech of `text` (empty if not spoken or if speech synthesis failed)
+    pub audio: Vec<u8>,
+}
+
+/// Summarize the text on the clipboard and put the summary in its place
+///
+/// The summary follows `clipboard.summarize_prompt`, in the language of the UI locale. It is
+/// spoken if `speak` is true, or if `speak` is omitted and `clipboard.speak_results` is on.
+#[tauri::command]
+pub async fn summarize_clipboard(speak: Option<bool>, state: State<'_, AppState>) -> Result<ClipboardResult, String> {
+    ensure_unlocked(&state)?;
+    run_clipboard_action(ClipboardAction::Summarize, speak, &state)
+        .await
+        .map_err(|e| localized(&state, &e))
+}
+
+/// Translate the text on the clipboard into `clipboard.translate_to` and put the translation in its place
+///
+/// Spoken like `summarize_clipboard`.
+#[tauri::command]
+pub async fn translate_clipboard(speak: Option<bool>, state: State<'_, AppState>) -> Result<ClipboardResult, String> {
+    ensure_unlocked(&state)?;
+    run_clipboard_action(ClipboardAction::Translate, speak, &state)
+        .await
+        .map_err(|e| localized(&state, &e))
+}
+
+/// Run by the clipboard hotkeys: run `action` and emit `clipboard-action-done`
+pub(crate) async fn clipboard_action_hotkey(app: AppHandle, action: ClipboardAction) {
+    let state = app.state::<AppState>();
+    if state.session().ensure_unlocked().is_err() {
+        return;
+    }
+    match run_clipboard_action(action, None, &state).await {
+        Ok(result) => {
+            if let Err(e) = app.emit("clipboard-action-done", result) {
+                tracing::warn!("Failed to emit clipboard-action-done event: {}", e);
+            }
+        }
+        Err(e) => tracing::warn!("Clipboard action {} failed: {}", action.name(), e),
+    }
+}
+
+async fn run_clipboard_action(action: ClipboardAction, speak: Option<bool>, state: &AppState) -> AppResult<ClipboardResult> {
+    let text = tokio::task::spawn_blocking(clipboard::read_text)
+        .await
+        .map_err(|e| AppError::Generic(e.to_string()))??;
+    let text = clipboard::limit_text(text.trim(), clipboard::MAX_PROMPT_CHARS).to_string();
+    tracing::info!("Running {} on {} chars", action.name(), text.len());
+    usage::record_feature(action.name());
+
+    let config = state.get_config();
+    let text = redaction::apply(&config.privacy.redaction, "message", text);
+    let overrides = GenerationOverrides {
+        system_prompt: Some(action.instructions(&config.clipboard, &config.ui.locale)),
+        temperature: Some(0.2),
+        ..GenerationOverrides::default()
+    };
+
+    state.set_status(AppStatus::Thinking);
+    let reply = match state.cooldowns().llm_provider(&config, &state.get_api_keys()) {
+        Ok(llm) => llm.send_message_with(vec![("user".to_string(), text)], &overrides).await,
+        Err(e) => Err(e),
+    };
+    let reply = match reply {
+        Ok(reply) => profanity::apply(&config.profanity_filter, reply.answer),
+        Err(e) => {
+            state.set_status(AppStatus::Idle);
+            return Err(e);
+        }
+    };
+
+    let copy = reply.clone();
+    let written = tokio::task::spawn_blocking(move || clipboard::write_text(&copy))
+        .await
+        .map_err(|e| AppError::Generic(e.to_string()))
+        .and_then(|written| written);
+    if let Err(e) = written {
+        state.set_status(AppStatus::Idle);
+        return Err(e);
+    }
+
+    let mut audio = Vec::new();
+    if speak.unwrap_or(config.clipboard.speak_results) {
+        state.set_status(AppStatus::Speaking);
+        let text = redaction::apply(&config.privacy.redaction, "speech", reply.clone());
+        let spoken = hooks::run(&config.hooks, HookStage::PreTts, text.clone()).await;
+        audio = match state.cooldowns().tts_provider(&config, &state.get_api_keys()) {
+            Ok(tts) => tts.synthesize_speech(&spoken).await,
+            Err(e) => Err(e),
+        }
+        .unwrap_or_else(|e| {
+            tracing::warn!("Failed to speak the {} result: {}", action.name(), e);
+            Vec::new()
+        });
+        if !audio.is_empty() {
+            state.set_last_response(text, audio.clone());
+        }
+    }
+    state.set_status(AppStatus::Idle);
+
+    Ok(ClipboardResult { action, text: reply, audio })
+}
+
+/// Verify the default microphone is present, permitted, and free before recording
+#[tauri::command]
+pub async fn check_microphone_access(state: State<'_, AppState>) -> Result<MicrophoneInfo, String> {
+    tracing::info!("Checking microphone access");
+
+    tokio::task::spawn_blocking(microphone::check_access)
+        .await
+        .map_err(|e| localized(&state, &AppError::Generic(e.to_string())))?
+        .map_err(|e| localized(&state, &e))
+}
+
+/// Record a few seconds from the default microphone, returning its level and the recording
+#[tauri::command]
+pub async fn test_microphone(state: State<'_, AppState>) -> Result<MicrophoneTest, String> {
+    tracing::info!("Testing the microphone");
+    ensure_unlocked(&state)?;
+    if state.is_mic_muted() {
+        return Err(localized(&state, &AppError::from(AudioError::MicrophoneMuted)));
+    }
+
+    tokio::task::spawn_blocking(microphone::record_test)
+        .await
+        .map_err(|e| localized(&state, &AppError::Generic(e.to_string())))?
+        .map_err(|e| localized(&state, &e))
+}
+
+/// Load application configuration
+#[tauri::command]
+pub async fn load_config(state: State<'_, AppState>) -> Result<AppConfig, String> {
+    tracing::info!("Loading configuration");
+    Ok(state.get_config())
+}
+
+/// Save application configuration
+#[tauri::command]
+pub async fn save_config(
+    config: AppConfig,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    ensure_not_kiosk(&state)?;
+    tracing::info!("Saving configuration");
+    apply_config(&app, &state, config)
+}
+
+/// Update one section of the configuration (e.g. "audio") with only the fields that changed
+///
+/// Objects in `values` are merged into the section key by key; other values
+/// replace it. Returns the whole updated configuration.
+#[tauri::command]
+pub async fn update_config_section(
+    section: String,
+    values: serde_json::Value,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<AppConfig, String> {
+    ensure_not_kiosk(&state)?;
+    tracing::info!("Updating {} settings", section);
+    let config = state
+        .get_config()
+        .with_section(&section, values)
+        .map_err(|e| localized(&state, &e))?;
+    apply_config(&app, &state, config)?;
+    Ok(state.get_config())
+}
+
+/// Use and persist a new configuration, updating the theme and tray labels if they changed
+fn apply_config(app: &AppHandle, state: &AppState, mut config: AppConfig) -> Result<(), String> {
+    let previous = state.get_config();
+
+    // Kiosk mode is only entered through set_kiosk_mode, which checks the session PIN
+    config.kiosk.enabled = previous.kiosk.enabled;
+    // A flavor probed at another server does not apply to the new one
+    if config.openwebui.api_flavor == previous.openwebui.api_flavor
+        && setup::server_base(&config.openwebui.endpoint) != setup::server_base(&previous.openwebui.endpoint)
+    {
+        config.openwebui.api_flavor = None;
+    }
+    let theme_changed = previous.ui.theme != config.ui.theme;
+    let locale_changed = previous.ui.locale != config.ui.locale;
+
+    // Update state
+    state.update_config(config.clone());
+
+    // Persist to disk
+    let config_manager = ConfigManager::new().map_err(|e| localized(state, &e))?;
+    config_manager.save(&config).map_err(|e| localized(state, &e))?;
+
+    if theme_changed {
+        emit_theme_changed(app, system_theme(app));
+    }
+    if locale_changed {
+        relabel_tray(app, &config.ui.locale);
+    }
+    refresh_voice_presets_menu(app);
+
+    tracing::info!("Configuration saved successfully");
+    Ok(())
+}
+
+/// The theme setting, the operating system theme, and the theme to show
+#[tauri::command]
+pub async fn get_theme(app: AppHandle, state: State<'_, AppState>) -> Result<ThemeInfo, String> {
+    Ok(ThemeInfo::new(&state.get_config().ui.theme, system_theme(&app)))
+}
+
+/// Operating system theme as reported for the main window
+pub fn system_theme(app: &AppHandle) -> Option<&'static str> {
+    theme_name(app.get_webview_window("main")?.theme().ok()?)
+}
+
+/// "light" or "dark"
+pub fn theme_name(theme: tauri::Theme) -> Option<&'static str> {
+    match theme {
+        tauri::Theme::Light => Some("light"),
+        tauri::Theme::Dark => Some("dark"),
+        _ => None,
+    }
+}
+
+/// Announce the theme to show as a `theme-changed` event
+///
+/// `system` is the operating system theme, passed in when it has just changed.
+pub fn emit_theme_changed(app: &AppHandle, system: Option<&str>) {
+    let info = ThemeInfo::new(&app.state::<AppState>().get_config().ui.theme, system);
+    tracing::info!("Theme is now {} (setting: {})", info.effective, info.setting);
+    if let Err(e) = app.emit("theme-changed", &info) {
+        tracing::warn!("Failed to emit theme-changed: {}", e);
+    }
+}
+
+/// Locales the backend has messages for
+#[tauri::command]
+pub async fn list_locales() -> Result<Vec<LocaleInfo>, String> {
+    Ok(i18n::locales())
+}
+
+/// Profiles on this computer, the default one first
+#[tauri::command]
+pub async fn list_profiles(state: State<'_, AppState>) -> Result<Vec<ProfileInfo>, String> {
+    ProfileStore::open_default()
+        .and_then(|profiles| profiles.list())
+        .map_err(|e| localized(&state, &e))
+}
+
+/// Add a profile, protected by a PIN of 4 to 12 digits if one is given
+///
+/// The profile starts with a copy of the current settings, minus key slots, and
+/// with no API keys, history, or recordings. It does not become active.
+#[tauri::command]
+pub async fn create_profile(
+    name: String,
+    pin: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<ProfileInfo, String> {
+    ensure_not_kiosk(&state)?;
+    let app_dir = ConfigManager::app_dir().map_err(|e| localized(&state, &e))?;
+    let profile = ProfileStore::new(&app_dir)
+        .create(&name, pin.as_deref())
+        .map_err(|e| localized(&state, &e))?;
+
+    let mut config = state.get_config();
+    config.key_slots.clear();
+    ConfigManager::for_profile(&app_dir, &profile.id)
+        .and_then(|m| m.save(&config))
+        .map_err(|e| localized(&state, &e))?;
+
+    Ok(profile)
+}
+
+/// Switch to another profile, entering its PIN if it has one
+#[tauri::command]
+pub async fn switch_profile(
+    id: String,
+    pin: Option<String>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+    history: State<'_, HistoryStore>,
+) -> Result<ProfileInfo, String> {
+    ensure_unlocked(&state)?;
+    ensure_not_kiosk(&state)?;
+    let profile = ProfileStore::open_default()
+        .and_then(|profiles| profiles.switch(&id, pin.as_deref()))
+        .map_err(|e| localized(&state, &e))?;
+
+    activate_profile(&app, &state, &history, &profile)?;
+    Ok(profile)
+}
+
+/// Load the profile that was just made active into the running app
+///
+/// Settings, API keys, and history are reloaded, hotkeys re-registered, and a new
+/// conversation started. Emits `profile-switched` with the profile.
+fn activate_profile(app: &AppHandle, state: &AppState, history: &HistoryStore, profile: &ProfileInfo) -> Result<(), String> {
+    let config_manager = ConfigManager::new().map_err(|e| localized(state, &e))?;
+    let (config, api_keys) = config_manager.load_with_keys().map_err(|e| localized(state, &e))?;
+    let slot_keys = config_manager.load_slot_keys(&config.key_slots);
+
+    let previous = state.get_config();
+    #[cfg(desktop)]
+    for accelerator in previous.ui.hotkeys.values() {
+        hotkeys::unregister(app, accelerator);
+    }
+    state.switch_profile(config.clone(), api_keys, slot_keys);
+    #[cfg(desktop)]
+    hotkeys::register_all(app, &config.ui.hotkeys);
+
+    if let Err(e) = history.reopen(&config_manager.data_dir().join("history.db")) {
+        tracing::error!("Failed to open the history of profile '{}': {}", profile.id, e);
+    } else if let Err(e) =
+        history.configure_encryption(config.privacy.encrypt_history, || config_manager.get_or_create_history_key())
+    {
+        tracing::error!("Failed to configure history encryption: {}", e);
+    }
+
+    if previous.ui.locale != config.ui.locale {
+        relabel_tray(app, &config.ui.locale);
+    }
+    if previous.ui.theme != config.ui.theme {
+        emit_theme_changed(app, system_theme(app));
+    }
+    refresh_voice_presets_menu(app);
+    if let Err(e) = app.emit("profile-switched", profile) {
+        tracing::warn!("Failed to emit profile-switched: {}", e);
+    }
+    Ok(())
+}
+
+/// Set or, with no `pin`, remove the PIN of the active profile
+///
+/// `current_pin` is required when the profile already has a PIN.
+#[tauri::command]
+pub async fn set_profile_pin(
+    current_pin: Option<String>,
+    pin: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<ProfileInfo, String> {
+    ensure_not_kiosk(&state)?;
+    let profiles = ProfileStore::open_default().map_err(|e| localized(&state, &e))?;
+    profiles
+        .set_pin(&profiles.active(), current_pin.as_deref(), pin.as_deref())
+        .map_err(|e| localized(&state, &e))
+}
+
+/// Enroll the active profile's voice from recorded WAV samples, replacing any earlier enrollment
+///
+/// At least three samples with a few seconds of speech each are needed.
+#[tauri::command]
+pub async fn enroll_speaker(samples: Vec<Vec<u8>>, state: State<'_, AppState>) -> Result<SpeakerEnrollment, String> {
+    ensure_not_kiosk(&state)?;
+    let embeddings = samples
+        .iter()
+        .map(|sample| speaker::embed(sample))
+        .collect::<AppResult<Vec<_>>>()
+        .map_err(|e| localized(&state, &e))?;
+
+    let app_dir = ConfigManager::app_dir().map_err(|e| localized(&state, &e))?;
+    VoiceprintStore::new(&app_dir)
+        .enroll(&ProfileStore::new(&app_dir).active(), &embeddings)
+        .map_err(|e| localized(&state, &e))
+}
+
+/// Remove the active profile's voiceprint, returning whether it was enrolled
+#[tauri::command]
+pub async fn delete_speaker_enrollment(state: State<'_, AppState>) -> Result<bool, String> {
+    ensure_not_kiosk(&state)?;
+    let app_dir = ConfigManager::app_dir().map_err(|e| localized(&state, &e))?;
+    VoiceprintStore::new(&app_dir)
+        .remove(&ProfileStore::new(&app_dir).active())
+        .map_err(|e| localized(&state, &e))
+}
+
+/// Recognize the speaker of an utterance and switch to their profile unless it has a PIN
+///
+/// Does nothing unless `speaker_id.enabled`, nor in kiosk mode. Emits `speaker-identified` with the
+/// match; failures are logged and leave the active profile as it is.
+fn identify_speaker(audio: &[u8], app: &AppHandle, state: &AppState, history: &HistoryStore) -> Option<SpeakerMatch> {
+    let config = state.get_config();
+    if !config.speaker_id.enabled || config.kiosk.enabled {
+        return None;
+    }
+
+    let embedding = match speaker::embed(audio) {
+        Ok(embedding) => embedding,
+        Err(e) => {
+            tracing::debug!("Speaker not identified: {}", e);
+            return None;
+        }
+    };
+    let app_dir = ConfigManager::app_dir().ok()?;
+    let mut matched = match VoiceprintStore::new(&app_dir).identify(&embedding, config.speaker_id.min_similarity) {
+        Ok(matched) => matched?,
+        Err(e) => {
+            tracing::warn!("Failed to read voiceprints: {}", e);
+            return None;
+        }
+    };
+
+    let profiles = ProfileStore::new(&app_dir);
+    let profile = profiles.list().ok()?.into_iter().find(|profile| profile.id == matched.profile_id)?;
+    if !profile.active && !profile.has_pin {
+        let switched = profiles
+            .switch(&profile.id, None)
+            .map_err(|e| localized(state, &e))
+            .and_then(|profile| activate_profile(app, state, history, &profile));
+        match switched {
+            Ok(()) => matched.switched = true,
+            Err(e) => tracing::warn!("Failed to switch to the speaker's profile: {}", e),
+        }
+    }
+
+    tracing::info!("Speaker identified as profile '{}' ({:.2})", matched.profile_id, matched.similarity);
+    if let Err(e) = app.emit("speaker-identified", &matched) {
+        tracing::warn!("Failed to emit speaker-identified: {}", e);
+    }
+    Some(matched)
+}
+
+/// Interval between auto-lock checks
+const AUTO_LOCK_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Interval between checks for an idle kiosk conversation
+const KIOSK_RESET_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Payload of the `session-lock-changed` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionLockPayload {
+    pub locked: bool,
+}
+
+/// Whether the session is locked
+#[tauri::command]
+pub async fn is_session_locked(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(state.session().is_locked())
+}
+
+/// Set or, with no `pin`, remove the session PIN of the active profile
+///
+/// `current_pin` is required when a session PIN is already set.
+#[tauri::command]
+pub async fn set_session_pin(
+    current_pin: Option<String>,
+    pin: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state.session().ensure_unlocked().map_err(|e| localized(&state, &e))?;
+    let config_manager = ConfigManager::new().map_err(|e| localized(&state, &e))?;
+
+    if let Some(stored) = config_manager.get_session_pin().map_err(|e| localized(&state, &e))? {
+        let matches = match &current_pin {
+            Some(current_pin) => stored.matches(current_pin).map_err(|e| localized(&state, &e))?,
+            None => false,
+        };
+        if !matches {
+            return Err(localized(&state, &ConfigError::IncorrectPin.into()));
+        }
+    }
+
+    let hash = pin.as_deref().map(PinHash::new).transpose().map_err(|e| localized(&state, &e))?;
+    config_manager.set_session_pin(hash.as_ref()).map_err(|e| localized(&state, &e))?;
+    tracing::info!("{} the session PIN", if hash.is_some() { "Set" } else { "Removed" });
+    Ok(())
+}
+
+/// Lock the session until the session PIN is entered
+///
+/// Fails if no session PIN is set, since the session could not be unlocked.
+#[tauri::command]
+pub async fn lock_session(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    let has_pin = ConfigManager::new()
+        .and_then(|manager| manager.get_session_pin())
+        .map_err(|e| localized(&state, &e))?
+        .is_some();
+    if !has_pin {
+        return Err(localized(&state, &AppError::from(ConfigError::InvalidValue(
+            "Set a session PIN before locking the session".to_string(),
+        ))));
+    }
+
+    apply_session_lock(&app, &state);
+    Ok(())
+}
+
+/// Unlock the session with its PIN, re-registering the hotkeys
+#[tauri::command]
+pub async fn unlock_session(pin: String, app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    let stored = ConfigManager::new()
+        .and_then(|manager| manager.get_session_pin())
+        .map_err(|e| localized(&state, &e))?;
+
+    // Without a stored PIN (e.g. the keyring entry was removed) the lock cannot be lifted any other way
+    let unlocked = state
+        .session()
+        .unlock(|| stored.as_ref().map_or(Ok(true), |stored| stored.matches(&pin)))
+        .map_err(|e| localized(&state, &e))?;
+    if !unlocked {
+        return Ok(());
+    }
+
+    #[cfg(desktop)]
+    hotkeys::register_all(&app, &state.get_config().ui.hotkeys);
+    tracing::info!("Session unlocked");
+    if let Err(e) = app.emit("session-lock-changed", SessionLockPayload { locked: false }) {
+        tracing::warn!("Failed to emit session-lock-changed: {}", e);
+    }
+    Ok(())
+}
+
+/// Note user activity in the window (typing, clicks), postponing the auto-lock
+#[tauri::command]
+pub async fn report_activity(state: State<'_, AppState>) -> Result<(), String> {
+    state.session().touch();
+    Ok(())
+}
+
+/// Lock the session, release the hotkeys, and notify the frontend with `session-lock-changed`
+fn apply_session_lock(app: &AppHandle, state: &AppState) {
+    if !state.session().lock() {
+        return;
+    }
+
+    #[cfg(desktop)]
+    for accelerator in state.get_config().ui.hotkeys.values() {
+        hotkeys::unregister(app, accelerator);
+    }
+    tracing::info!("Session locked");
+    if let Err(e) = app.emit("session-lock-changed", SessionLockPayload { locked: true }) {
+        tracing::warn!("Failed to emit session-lock-changed: {}", e);
+    }
+}
+
+/// Spawn the background task that locks the session after `privacy.auto_lock_minutes` of inactivity
+///
+/// The session is only locked automatically once a session PIN is set.
+pub fn spawn_auto_lock_task(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(AUTO_LOCK_CHECK_INTERVAL).await;
+
+            let state = app.state::<AppState>();
+            let timeout = std::time::Duration::from_secs(state.get_config().privacy.auto_lock_minutes.saturating_mul(60));
+            if !state.session().should_auto_lock(timeout) {
+                continue;
+            }
+            match ConfigManager::new().and_then(|manager| manager.get_session_pin()) {
+                Ok(Some(_)) => apply_session_lock(&app, &state),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Auto-lock skipped, session PIN unreadable: {}", e),
+            }
+        }
+    });
+}
+
+/// Payload of the `kiosk-mode-changed` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KioskModePayload {
+    pub enabled: bool,
+}
+
+/// Turn kiosk mode on or off with the session PIN
+///
+/// A session PIN must be set first, so that kiosk mode can be left again. Turning
+/// it on starts a new conversation. Emits `kiosk-mode-changed` with `{ enabled }`.
+#[tauri::command]
+pub async fn set_kiosk_mode(
+    enabled: bool,
+    pin: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    ensure_unlocked(&state)?;
+    let config_manager = ConfigManager::new().map_err(|e| localized(&state, &e))?;
+    let Some(stored) = config_manager.get_session_pin().map_err(|e| localized(&state, &e))? else {
+        return Err(localized(&state, &AppError::from(ConfigError::InvalidValue(
+            "Set a session PIN before using kiosk mode".to_string(),
+        ))));
+    };
+    state
+        .session()
+        .check_pin(|| stored.matches(&pin))
+        .map_err(|e| localized(&state, &e))?;
+
+    let mut config = state.get_config();
+    if config.kiosk.enabled == enabled {
+        return Ok(());
+    }
+    config.kiosk.enabled = enabled;
+    config_manager.save(&config).map_err(|e| localized(&state, &e))?;
+    state.update_config(config);
+    if enabled {
+        state.clear_conversation();
+    }
+
+    tracing::info!("Kiosk mode {}", if enabled { "enabled" } else { "disabled" });
+    if let Err(e) = app.emit("kiosk-mode-changed", KioskModePayload { enabled }) {
+        tracing::warn!("Failed to emit kiosk-mode-changed: {}", e);
+    }
+    Ok(())
+}
+
+/// Spawn the background task that clears an idle kiosk conversation for the next user
+///
+/// Emits `kiosk-reset` when a conversation is cleared.
+pub fn spawn_kiosk_reset_task(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(KIOSK_RESET_CHECK_INTERVAL).await;
+
+            let state = app.state::<AppState>();
+            let kiosk = state.get_config().kiosk;
+            let timeout = std::time::Duration::from_secs(kiosk.reset_after_secs);
+            if !kiosk.enabled || timeout.is_zero() || state.session().idle_for() < timeout {
+                continue;
+            }
+            if state.get_conversation().messages.is_empty() {
+                continue;
+            }
+
+            state.clear_conversation();
+            tracing::info!("Kiosk idle for {}s, cleared the conversation", kiosk.reset_after_secs);
+            if let Err(e) = app.emit("kiosk-reset", ()) {
+                tracing::warn!("Failed to emit kiosk-reset: {}", e);
+            }
+        }
+    });
+}
+
+/// Label the tray menu in `locale`
+fn relabel_tray(app: &AppHandle, locale: &str) {
+    if let Some(items) = app.try_state::<crate::TrayMenuItems>() {
+        for (label, item) in &items.0 {
+            let _ = item.set_text(i18n::tr(locale, label));
+        }
+    }
+    if let Some(item) = app.try_state::<crate::TrayMuteItem>() {
+        let _ = item.0.set_text(i18n::tr(locale, "tray-mute"));
+    }
+    if let Some(menu) = app.try_state::<crate::TrayVoiceMenu>() {
+        let _ = menu.0.set_text(i18n::tr(locale, "tray-voice"));
+    }
+}
+
+/// Update API key for a service
+#[tauri::command]
+pub async fn update_api_key(
+    service: String,
+    api_key: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    ensure_not_kiosk(&state)?;
+    tracing::info!("Updating API key for service: {}", service);
+
+    // Store in keyring
+    let config_manager = ConfigManager::new().map_err(|e| localized(&state, &e))?;
+    config_manager
+        .store_api_key(&service, &api_key)
+        .map_err(|e| localized(&state, &e))?;
+
+    // Update state
+    if !state.update_api_key(&service, api_key) {
+        return Err(localized(&state, &ConfigError::InvalidValue(format!("Unknown service: {}", service)).into()));
+    }
+
+    tracing::info!("API key updated for {}", service);
+    Ok(())
+}
+
+/// List the key slots with whether each has a stored key and is in use
+#[tauri::command]
+pub async fn list_key_slots(state: State<'_, AppState>) -> Result<Vec<KeySlotStatus>, String> {
+    let config = state.get_config();
+    let selected = state.get_conversation().settings.key_slots;
+    let config_manager = ConfigManager::new().map_err(|e| localized(&state, &e))?;
+
+    Ok(config
+        .key_slots
+        .iter()
+        .map(|slot| {
+            let in_use = match selected.get(&slot.service) {
+                Some(id) => *id == slot.id,
+                None => slot.default,
+            };
+            KeySlotStatus {
+                stored: matches!(config_manager.get_secret(&slot.secret_name()), Ok(Some(_))),
+                in_use,
+                slot: slot.clone(),
+            }
+        })
+        .collect())
+}
+
+/// Store an additional labeled API key for a service
+#[tauri::command]
+pub async fn add_key_slot(
+    service: String,
+    label: String,
+    api_key: String,
+    state: State<'_, AppState>,
+) -> Result<KeySlot, String> {
+    ensure_not_kiosk(&state)?;
+    let label = label.trim().to_string();
+    if !API_KEY_SERVICES.contains(&service.as_str()) {
+        return Err(localized(&state, &ConfigError::InvalidValue(format!("Unknown service: {}", service)).into()));
+    }
+    if label.is_empty() || api_key.trim().is_empty() {
+        return Err(i18n::tr(&state.get_config().ui.locale, "key-slot-incomplete"));
+    }
+
+    let mut config = state.get_config();
+    let slot = KeySlot {
+        service,
+        id: format!("{:06x}", rand::random::<u32>() & 0xff_ffff),
+        label,
+        default: false,
+    };
+
+    let config_manager = ConfigManager::new().map_err(|e| localized(&state, &e))?;
+    config_manager
+        .store_secret(&slot.secret_name(), &api_key)
+        .map_err(|e| localized(&state, &e))?;
+    config.key_slots.push(slot.clone());
+    config_manager.save(&config).map_err(|e| localized(&state, &e))?;
+
+    state.update_config(config);
+    state.set_slot_key(&slot, api_key);
+
+    tracing::info!("Added {} key slot '{}'", slot.service, slot.label);
+    Ok(slot)
+}
+
+/// Delete a key slot and its stored key
+#[tauri::command]
+pub async fn remove_key_slot(service: String, id: String, state: State<'_, AppState>) -> Result<(), String> {
+    ensure_not_kiosk(&state)?;
+    let mut config = state.get_config();
+    let index = config
+        .key_slots
+        .iter()
+        .position(|slot| slot.service == service && slot.id == id)
+        .ok_or_else(|| format!("No {} key slot '{}'", service, id))?;
+    let slot = config.key_slots.remove(index);
+
+    let config_manager = ConfigManager::new().map_err(|e| localized(&state, &e))?;
+    config_manager
+        .delete_secret(&slot.secret_name())
+        .map_err(|e| localized(&state, &e))?;
+    config_manager.save(&config).map_err(|e| localized(&state, &e))?;
+
+    state.update_config(config);
+    state.remove_slot_key(&slot);
+
+    tracing::info!("Removed {} key slot '{}'", slot.service, slot.label);
+    Ok(())
+}
+
+/// Make a key slot the default for its service, or clear the default with `id: null`
+#[tauri::command]
+pub async fn set_default_key_slot(
+    service: String,
+    id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    ensure_not_kiosk(&state)?;
+    let mut config = state.get_config();
+    if let Some(id) = &id {
+        if !config.key_slots.iter().any(|slot| slot.service == service && slot.id == *id) {
+            return Err(localized(&state, &ConfigError::InvalidValue(format!("No {} key slot '{}'", service, id)).into()));
+        }
+    }
+
+    for slot in config.key_slots.iter_mut().filter(|slot| slot.service == service) {
+        slot.default = id.as_ref() == Some(&slot.id);
+    }
+
+    ConfigManager::new()
+        .and_then(|manager| manager.save(&config))
+        .map_err(|e| localized(&state, &e))?;
+    state.update_config(config);
+    Ok(())
+}
+
+/// Key slot with its status
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeySlotStatus {
+    #[serde(flatten)]
+    pub slot: KeySlot,
+
+    /// Whether the keyring holds a key for the slot
+    pub stored: bool,
+
+    /// Whether the current conversation uses the slot
+    pub in_use: bool,
+}
+
+/// Where each service's API key comes from, without revealing any key
+#[tauri::command]
+pub async fn get_key_status(state: State<'_, AppState>) -> Result<Vec<KeyStatus>, String> {
+    let config_manager = ConfigManager::new().map_err(|e| localized(&state, &e))?;
+    Ok(key_status(&config_manager, &state.get_api_keys()))
+}
+
+/// Whether setup has not been completed yet, so the setup wizard should be shown
+#[tauri::command]
+pub async fn is_first_run(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(!state.get_config().setup.completed)
+}
+
+/// LLM servers running on this machine (OpenWebUI, Ollama, or another OpenAI-compatible server)
+#[tauri::command]
+pub async fn detect_local_servers(state: State<'_, AppState>) -> Result<Vec<LocalServer>, String> {
+    ensure_not_kiosk(&state)?;
+    Ok(setup::detect_local_servers().await)
+}
+
+/// OpenWebUI and Ollama servers elsewhere on the local network, found by a scan of this machine's subnet
+#[tauri::command]
+pub async fn discover_network_servers(state: State<'_, AppState>) -> Result<Vec<LocalServer>, String> {
+    ensure_not_kiosk(&state)?;
+    usage::record_feature("discover_network_servers");
+    setup::discover_network_servers().await.map_err(|e| localized(&state, &e))
+}
+
+/// Detect the kind of server at the configured LLM endpoint and shape requests for it
+///
+/// Saves the detected `openwebui.api_flavor`, and moves `openwebui.endpoint` to
+/// the server's chat path if it pointed elsewhere. Returns `None`, changing
+/// nothing, if the server could not be identified.
+#[tauri::command]
+pub async fn probe_llm_api(app: AppHandle, state: State<'_, AppState>) -> Result<Option<LocalServer>, String> {
+    ensure_not_kiosk(&state)?;
+    probe_llm_api_and_apply(&app, &state).await
+}
+
+/// Probe the configured LLM endpoint and save what was found; also run at startup while the flavor is unknown
+pub(crate) async fn probe_llm_api_and_apply(app: &AppHandle, state: &AppState) -> Result<Option<LocalServer>, String> {
+    let config = state.get_config();
+    let api_key = state.get_api_keys().openwebui;
+    let Some(server) = setup::probe_llm(&config.openwebui, api_key.as_deref()).await else {
+        return Ok(None);
+    };
+
+    // Re-read, so settings saved while the probe ran are kept
+    let mut config = state.get_config();
+    let changed = setup::apply_probe(&mut config.openwebui, &server);
+    if !changed.is_empty() {
+        tracing::info!("Probe changed: {}", changed.join(", "));
+        apply_config(app, state, config)?;
+    }
+    Ok(Some(server))
+}
+
+/// Settings suggested for a first run, from `servers` (detected again if not given) and the keys set
+///
+/// Nothing is saved; the frontend saves the suggested configuration once accepted.
+#[tauri::command]
+pub async fn suggest_setup(
+    servers: Option<Vec<LocalServer>>,
+    state: State<'_, AppState>,
+) -> Result<SetupSuggestion, String> {
+    ensure_not_kiosk(&state)?;
+    let servers = match servers {
+        Some(servers) => servers,
+        None => setup::detect_local_servers().await,
+    };
+    Ok(setup::suggest(&state.get_config(), &servers, &state.get_api_keys()))
+}
+
+/// Check an API key for a service before it is stored, against the configured endpoint
+///
+/// A rejected key is reported as disconnected with the authentication error.
+#[tauri::command]
+pub async fn test_api_key(
+    service: String,
+    api_key: String,
+    state: State<'_, AppState>,
+) -> Result<ServiceStatus, String> {
+    ensure_not_kiosk(&state)?;
+    tracing::info!("Testing API key for service: {}", service);
+    let config = state.get_config();
+
+    Ok(match setup::test_key(&service, &api_key, &config).await {
+        Ok(true) => ServiceStatus::Connected,
+        Ok(false) => ServiceStatus::Disconnected {
+            reason: i18n::tr(&config.ui.locale, "service-unreachable"),
+        },
+        Err(e) => ServiceStatus::Disconnected {
+            reason: e.localized(&config.ui.locale),
+        },
+    })
+}
+
+/// Mark setup as done, so the setup wizard is not shown again
+#[tauri::command]
+pub async fn complete_setup(app: AppHandle, state: State<'_, AppState>) -> Result<(), String> {
+    ensure_not_kiosk(&state)?;
+    tracing::info!("Setup completed");
+    let mut config = state.get_config();
+    config.setup.completed = true;
+    apply_config(&app, &state, config)
+}
+
+/// Export the configuration as JSON for troubleshooting
+///
+/// API keys are never included; only where each one comes from.
+#[tauri::command]
+pub async fn export_config(state: State<'_, AppState>) -> Result<String, String> {
+    ensure_not_kiosk(&state)?;
+    let config_manager = ConfigManager::new().map_err(|e| localized(&state, &e))?;
+    let export = ConfigExport {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        config: state.get_config(),
+        keys: key_status(&config_manager, &state.get_api_keys()),
+    };
+    serde_json::to_string_pretty(&export).map_err(|e| localized(&state, &ConfigError::from(e).into()))
+}
+
+fn key_status(config_manager: &ConfigManager, api_keys: &ApiKeys) -> Vec<KeyStatus> {
+    let loaded = [
+        &api_keys.whisper,
+        &api_keys.openwebui,
+        &api_keys.elevenlabs,
+        &api_keys.azure,
+        &api_keys.openrouter,
+        &api_keys.gemini,
+    ];
+    API_KEY_SERVICES
+        .iter()
+        .zip(loaded)
+        .map(|(service, key)| KeyStatus {
+            service: service.to_string(),
+            source: config_manager.api_key_source(service),
+            loaded: key.is_some(),
+        })
+        .collect()
+}
+
+/// API key status of one service
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeyStatus {
+    pub service: String,
+
+    /// Where the key would be read from at launch
+    #[serde(flatten)]
+    pub source: KeySource,
+
+    /// Whether the running app has a key for the service
+    pub loaded: bool,
+}
+
+/// Configuration export (never contains API keys)
+#[derive(Debug, Serialize)]
+pub struct ConfigExport {
+    pub app_version: String,
+    pub config: AppConfig,
+    pub keys: Vec<KeyStatus>,
+}
+
+/// Set a variable in the managed `.env`, or remove it when `value` is null
+///
+/// API keys take effect immediately; other variables are read at launch.
+#[tauri::command]
+pub async fn set_env_override(
+    name: String,
+    value: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    ensure_not_kiosk(&state)?;
+    EnvOverrides::open_default()
+        .and_then(|overrides| overrides.set(&name, value.as_deref()))
+        .map_err(|e| localized(&state, &e))?;
+
+    if name.ends_with("_API_KEY") {
+        let (_, api_keys) = ConfigManager::new()
+            .and_then(|manager| manager.load_with_keys())
+            .map_err(|e| localized(&state, &e))?;
+        state.update_api_keys(api_keys);
+    }
+
+    tracing::info!("Environment override {} {}", name, if value.is_some() { "set" } else { "removed" });
+    Ok(())
+}
+
+/// List the variables in the managed `.env`, with values masked
+#[tauri::command]
+pub async fn list_env_overrides(state: State<'_, AppState>) -> Result<Vec<EnvOverride>, String> {
+    EnvOverrides::open_default()
+        .and_then(|overrides| overrides.list())
+        .map_err(|e| localized(&state, &e))
+}
+
+/// Store a named secret (e.g. a webhook token) in the system keyring
+#[tauri::command]
+pub async fn store_secret(name: String, value: String, state: State<'_, AppState>) -> Result<(), String> {
+    ensure_not_kiosk(&state)?;
+    ConfigManager::new()
+        .and_then(|manager| manager.store_secret(&name, &value))
+        .map_err(|e| localized(&state, &e))
+}
+
+/// Delete a named secret, returning whether it existed
+#[tauri::command]
+pub async fn delete_secret(name: String, state: State<'_, AppState>) -> Result<bool, String> {
+    ensure_not_kiosk(&state)?;
+    ConfigManager::new()
+        .and_then(|manager| manager.delete_secret(&name))
+        .map_err(|e| localized(&state, &e))
+}
+
+/// List the names of stored secrets (values are never returned to the frontend)
+#[tauri::command]
+pub async fn list_secret_names(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    ConfigManager::new()
+        .and_then(|manager| manager.list_secret_names())
+        .map_err(|e| localized(&state, &e))
+}
+
+/// Check connectivity to all services at once
+///
+/// The results of a check finished less than 30 seconds ago are returned again,
+/// marked `cached`, unless `force` is set.
+#[tauri::command]
+pub async fn check_connectivity(force: Option<bool>, state: State<'_, AppState>) -> Result<ConnectivityResponse, String> {
+    if !force.unwrap_or(false) {
+        if let Some(cached) = state.cached_connectivity() {
+            tracing::debug!("Using connectivity checked at {}", cached.last_checked);
+            return Ok(ConnectivityResponse {
+                whisper: cached.whisper,
+                openwebui: cached.openwebui,
+                elevenlabs: cached.elevenlabs,
+                cached: true,
+            });
+        }
+    }
+    tracing::info!("Checking connectivity to all services");
+
+    let config = state.get_config();
+    let api_keys = state.get_api_keys();
+
+    // Speech-to-text, LLM, and text-to-speech are reported as "whisper", "openwebui", and "elevenlabs"
+    let (whisper_status, openwebui_status, elevenlabs_status) = tokio::join!(
+        probe_service(&state, &config, "whisper", Stage::Stt, async {
+            stt_provider(&config, &api_keys)?.check_connectivity().await
+        }),
+        probe_service(&state, &config, "openwebui", Stage::Llm, async {
+            llm_provider(&config, &api_keys)?.check_connectivity().await
+        }),
+        probe_service(&state, &config, "elevenlabs", Stage::Tts, async {
+            tts_provider(&config, &api_keys)?.check_connectivity().await
+        }),
+    );
+    state.cache_connectivity();
+
+    tracing::info!("Connectivity check complete");
+
+    Ok(ConnectivityResponse {
+        whisper: whisper_status,
+        openwebui: openwebui_status,
+        elevenlabs: elevenlabs_status,
+        cached: false,
+    })
+}
+
+/// Run one service's connectivity check, showing it as checking until `check` finishes
+async fn probe_service(
+    state: &AppState,
+    config: &AppConfig,
+    service: &str,
+    stage: Stage,
+    check: impl std::future::Future<Output = AppResult<bool>>,
+) -> ServiceStatus {
+    state.update_service_status(service, ServiceStatus::Checking);
+    let started = Instant::now();
+    let status = match check.await {
+        Ok(true) => ServiceStatus::Connected,
+        Ok(false) => ServiceStatus::Disconnected {
+            reason: i18n::tr(&config.ui.locale, "service-unreachable"),
+        },
+        Err(e) => ServiceStatus::Disconnected {
+            reason: e.localized(&config.ui.locale),
+        },
+    };
+    state.update_service_status(service, status.clone());
+    state.record_health_check(service, stage.provider(config), &status, started.elapsed().as_millis() as u64);
+    status
+}
+
+/// Recent connectivity check results of one service (e.g. "whisper"), or of all
+///
+/// Services are named as in `check_connectivity`; the history is kept in memory.
+#[tauri::command]
+pub async fn get_service_history(
+    service: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<ServiceHistory>, String> {
+    Ok(state.get_service_history(service.as_deref()))
+}
+
+/// Resume a provider paused after a rate limit or exhausted quota (e.g. "elevenlabs"), or all with `None`
+///
+/// Returns how many cooldowns ended.
+#[tauri::command]
+pub async fn clear_cooldown(
+    provider: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let cleared = state.cooldowns().clear(provider.as_deref());
+    tracing::info!("Cleared {} provider cooldown(s)", cleared);
+    Ok(cleared)
+}
+
+/// Connectivity response structure
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConnectivityResponse {
     pub whisper: ServiceStatus,
     pub openwebui: ServiceStatus,
     pub elevenlabs: ServiceStatus,
+
+    /// Whether these are the results of an earlier check
+    pub cached: bool,
+}
+
+/// Run every pipeline stage once with built-in inputs and report each result
+#[tauri::command]
+pub async fn run_self_test(state: State<'_, AppState>) -> Result<SelfTestReport, String> {
+    tracing::info!("Running pipeline self-test");
+
+    let report = selftest::run(&state.get_config(), &state.get_api_keys()).await;
+
+    tracing::info!(
+        "Self-test {} in {} ms",
+        if report.passed { "passed" } else { "failed" },
+        report.total_ms
+    );
+    Ok(report)
+}
+
+/// Time the standard requests against every configured provider of each stage
+///
+/// Each provider receives `iterations` requests (default 3, at most 10).
+#[tauri::command]
+pub async fn benchmark_providers(
+    iterations: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<BenchmarkReport, String> {
+    let iterations = iterations.unwrap_or(benchmark::DEFAULT_ITERATIONS);
+    tracing::info!("Benchmarking providers ({} runs each)", iterations);
+
+    let report = benchmark::run(&state.get_config(), &state.get_api_keys(), iterations).await;
+
+    tracing::info!("Benchmark complete in {} ms", report.total_ms);
+    Ok(report)
 }
 
 /// Get current application state
 #[tauri::command]
-pub async fn get_app_state(state: State<'_, AppState>) -> Result<AppStateResponse, String> {
-    log::debug!("Getting application state");
+pub async fn get_app_state(
+    state: State<'_, AppState>,
+    history: State<'_, HistoryStore>,
+) -> Result<AppStateResponse, String> {
+    tracing::debug!("Getting application state");
 
     let status = state.get_status();
     let conversation = state.get_conversation();
@@ -379,6 +3329,13 @@ pub async fn get_app_state(state: State<'_, AppState>) -> Result<AppStateRespons
         status,
         message_count: conversation.messages.len(),
         connectivity,
+        mic_muted: state.is_mic_muted(),
+        incognito: conversation.ephemeral,
+        history_locked: history.is_locked(),
+        translator: state
+            .translator_direction()
+            .map(|direction| TranslationTurn::new(&state.get_config().translator, direction)),
+        cooldowns: state.cooldowns().active(),
     })
 }
 
@@ -388,59 +3345,513 @@ pub struct AppStateResponse {
     pub status: AppStatus,
     pub message_count: usize,
     pub connectivity: crate::state::ConnectivityStatus,
+    pub mic_muted: bool,
+    pub incognito: bool,
+    pub history_locked: bool,
+    /// Languages of the next turn while translator mode is on
+    pub translator: Option<TranslationTurn>,
+    /// Providers paused after a rate limit or exhausted quota
+    pub cooldowns: Vec<ProviderCooldown>,
 }
 
 /// Clear conversation history
 #[tauri::command]
 pub async fn clear_conversation(state: State<'_, AppState>) -> Result<(), String> {
-    log::info!("Clearing conversation history");
+    tracing::info!("Clearing conversation history");
     state.clear_conversation();
     Ok(())
 }
 
+/// Start an incognito session
+///
+/// The current conversation is discarded, and nothing from the new one is written
+/// to history or saved as a recording.
+#[tauri::command]
+pub async fn start_incognito_session(state: State<'_, AppState>) -> Result<(), String> {
+    state.start_incognito();
+    Ok(())
+}
+
+/// Leave the incognito session, wiping its messages from memory
+#[tauri::command]
+pub async fn end_incognito_session(state: State<'_, AppState>) -> Result<(), String> {
+    if state.is_incognito() {
+        state.clear_conversation();
+        tracing::info!("Incognito session ended");
+    }
+    Ok(())
+}
+
 /// Get conversation history
 #[tauri::command]
 pub async fn get_conversation(state: State<'_, AppState>) -> Result<crate::state::ConversationContext, String> {
-    log::debug!("Getting conversation history");
+    tracing::debug!("Getting conversation history");
+    ensure_unlocked(&state)?;
     Ok(state.get_conversation())
 }
 
+/// Continue the current conversation from one of its messages in a new conversation
+///
+/// The new conversation contains the messages up to and including `message_id` and
+/// becomes the current one; the original stays in history. Forks of an incognito
+/// conversation are incognito too.
+#[tauri::command]
+pub async fn fork_conversation(
+    message_id: String,
+    state: State<'_, AppState>,
+    history: State<'_, HistoryStore>,
+) -> Result<crate::state::ConversationContext, String> {
+    ensure_unlocked(&state)?;
+    let fork = state
+        .fork_conversation(&message_id)
+        .ok_or_else(|| format!("No message '{}' in the current conversation", message_id))?;
+
+    if !fork.ephemeral && state.get_config().privacy.retention.keeps_history() {
+        for message in &fork.messages {
+            if let Err(e) = history.append_message(&fork, message) {
+                tracing::warn!("Failed to save forked message to history: {}", e);
+                break;
+            }
+        }
+    }
+    Ok(fork)
+}
+
+/// Rate a reply in the current conversation with thumbs up or down and an optional comment
+///
+/// The rating is stored with the message in history, so it appears in exports, and
+/// counted (without the comment) in anonymous usage reports. Rating a reply again
+/// replaces its rating.
+#[tauri::command]
+pub async fn rate_response(
+    message_id: String,
+    thumbs: Thumbs,
+    comment: Option<String>,
+    state: State<'_, AppState>,
+    history: State<'_, HistoryStore>,
+) -> Result<(), String> {
+    ensure_unlocked(&state)?;
+    let config = state.get_config();
+    let comment = comment
+        .map(|comment| comment.trim().to_string())
+        .filter(|comment| !comment.is_empty())
+        .map(|comment| redaction::apply(&config.privacy.redaction, "message", comment));
+
+    let message = state
+        .rate_message(&message_id, thumbs, comment)
+        .ok_or_else(|| format!("No reply '{}' in the current conversation", message_id))?;
+
+    let event = match thumbs {
+        Thumbs::Up => "thumbs_up",
+        Thumbs::Down => "thumbs_down",
+    };
+    usage::record_feature(event);
+    if let Some(variant) = &message.variant {
+        usage::record_variant(variant, event);
+    }
+
+    let conversation = state.get_conversation();
+    if !conversation.ephemeral && config.privacy.retention.keeps_history() {
+        if let Some(rating) = &message.rating {
+            match history.rate_message(&conversation.id, &message_id, rating) {
+                Ok(true) => {}
+                Ok(false) => tracing::warn!("Rated message {} is not in history", message_id),
+                Err(e) => tracing::warn!("Failed to save rating to history: {}", e),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Speak the current conversation into a single MP3 file at `path`
+///
+/// User and assistant turns can be given different voices; by default both use the
+/// configured voice. System messages are skipped.
+#[tauri::command]
+pub async fn export_conversation_audio(
+    path: String,
+    user_voice_id: Option<String>,
+    assistant_voice_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<NarrationSummary, String> {
+    tracing::info!("Exporting conversation audio to {}", path);
+    ensure_unlocked(&state)?;
+    ensure_not_kiosk(&state)?;
+
+    let voices = NarrationVoices {
+        user: user_voice_id,
+        assistant: assistant_voice_id,
+    };
+    let conversation = state.get_conversation();
+    let (audio, turns) = narration::narrate(
+        &conversation.messages,
+        &voices,
+        &state.get_config(),
+        &state.get_api_keys(),
+        state.cooldowns(),
+    )
+    .await
+    .map_err(|e| localized(&state, &e))?;
+
+    std::fs::write(&path, &audio).map_err(|e| localized(&state, &AudioError::WriteFailed(e.to_string()).into()))?;
+    usage::record_feature("conversation_audio_export");
+    Ok(NarrationSummary {
+        turns,
+        size_bytes: audio.len(),
+    })
+}
+
+/// Override the model, temperature, or system prompt for the current conversation, or change its attached files
+///
+/// Unset fields fall back to the global OpenWebUI configuration; blank strings are treated as unset.
+#[tauri::command]
+pub async fn set_conversation_settings(
+    settings: ConversationSettings,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    ensure_not_kiosk(&state)?;
+    if let Some(temperature) = settings.temperature {
+        if !(0.0..=2.0).contains(&temperature) {
+            return Err(localized(&state, &AppError::from(ConfigError::InvalidValue(format!(
+                "temperature must be between 0.0 and 2.0, got {}",
+                temperature
+            )))));
+        }
+    }
+
+    let slots = state.get_config().key_slots;
+    for (service, id) in &settings.key_slots {
+        if !slots.iter().any(|slot| slot.service == *service && slot.id == *id) {
+            return Err(localized(&state, &ConfigError::InvalidValue(format!("No {} key slot '{}'", service, id)).into()));
+        }
+    }
+
+    let non_blank = |value: Option<String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    let settings = ConversationSettings {
+        model: non_blank(settings.model),
+        temperature: settings.temperature,
+        system_prompt: non_blank(settings.system_prompt),
+        key_slots: settings.key_slots,
+        files: settings.files,
+    };
+
+    tracing::info!("Updating conversation settings: {:?}", settings);
+    state.set_conversation_settings(settings);
+    Ok(())
+}
+
+/// Turn translator mode on or off
+///
+/// While on, voice queries are translated between `translator.language_a` and
+/// `translator.language_b` on alternating turns, starting with language A.
+#[tauri::command]
+pub async fn set_translator_mode(enabled: bool, state: State<'_, AppState>) -> Result<(), String> {
+    ensure_not_kiosk(&state)?;
+    state.set_translator_mode(enabled);
+    Ok(())
+}
+
+/// Get the text of the last spoken reply
+#[tauri::command]
+pub async fn get_last_response(state: State<'_, AppState>) -> Result<Option<LastResponse>, String> {
+    ensure_unlocked(&state)?;
+    Ok(state.get_last_response())
+}
+
+/// Get the audio of the last reply for replay, reusing the cached audio when available
+#[tauri::command]
+pub async fn repeat_last_response(state: State<'_, AppState>) -> Result<Vec<u8>, String> {
+    tracing::info!("Repeating last response");
+    ensure_unlocked(&state)?;
+    usage::record_feature("repeat_response");
+
+    let result = last_response_audio(&state).await;
+    state.set_status(AppStatus::Idle);
+
+    match result? {
+        Some(last) => Ok(last.audio),
+        None => Err(i18n::tr(&state.get_config().ui.locale, "no-response-to-repeat")),
+    }
+}
+
+/// List models available from the configured LLM provider
+#[tauri::command]
+pub async fn list_models(state: State<'_, AppState>) -> Result<Vec<ModelInfo>, String> {
+    tracing::info!("Listing available models");
+
+    let config = state.get_config();
+    let api_keys = state.get_api_keys();
+
+    let llm_client = llm_provider(&config, &api_keys)
+        .map_err(|e| localized(&state, &e))?;
+
+    llm_client.list_models().await.map_err(|e| localized(&state, &e))
+}
+
+/// Files uploaded to the OpenWebUI server, its knowledge collections, and what the conversation has attached
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LlmFiles {
+    pub files: Vec<LlmFile>,
+    pub collections: Vec<KnowledgeCollection>,
+
+    /// Files and collections attached to the current conversation
+    pub attached: Vec<FileAttachment>,
+}
+
+/// Upload a document to the OpenWebUI server and attach it to the current conversation
+///
+/// With `knowledge_id`, the file is also added to that knowledge collection. Replies in the
+/// conversation are answered from its attachments while the LLM provider is OpenWebUI.
+#[tauri::command]
+pub async fn upload_file_to_llm(
+    path: String,
+    knowledge_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<LlmFile, String> {
+    tracing::info!("Uploading a file to the LLM server");
+    ensure_unlocked(&state)?;
+    ensure_not_kiosk(&state)?;
+    usage::record_feature("llm_file_upload");
+
+    upload_to_llm(std::path::Path::new(&path), knowledge_id.as_deref(), &state)
+        .await
+        .map_err(|e| localized(&state, &e))
+}
+
+async fn upload_to_llm(path: &std::path::Path, knowledge_id: Option<&str>, state: &AppState) -> AppResult<LlmFile> {
+    let content = tokio::fs::read(path)
+        .await
+        .map_err(|e| OpenWebUiError::UploadFailed(format!("{}: {}", path.display(), e)))?;
+    let filename = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+
+    let client = OpenWebUiClient::new(state.get_config().openwebui, state.get_api_keys().openwebui)?;
+    let file = client.upload_file(&filename, content).await?;
+    if let Some(knowledge_id) = knowledge_id {
+        client.add_to_knowledge(knowledge_id, &file.id).await?;
+    }
+
+    tracing::info!("Uploaded file {} ({} bytes)", file.id, file.size.unwrap_or_default());
+    state.attach_to_conversation(FileAttachment::file(&file.id));
+    Ok(file)
+}
+
+/// Files and knowledge collections on the OpenWebUI server, for attaching to the conversation
+///
+/// Attach a collection, or detach anything, with `set_conversation_settings`.
+#[tauri::command]
+pub async fn list_llm_files(state: State<'_, AppState>) -> Result<LlmFiles, String> {
+    ensure_not_kiosk(&state)?;
+    let client = OpenWebUiClient::new(state.get_config().openwebui, state.get_api_keys().openwebui)
+        .map_err(|e| localized(&state, &e))?;
+
+    let (files, collections) = tokio::try_join!(client.list_files(), client.list_knowledge())
+        .map_err(|e| localized(&state, &e))?;
+    Ok(LlmFiles {
+        files,
+        collections,
+        attached: state.get_conversation().settings.files,
+    })
+}
+
+/// Switch the default LLM model after checking it exists on the server
+///
+/// The model is set on the configuration as it is after the check, so settings
+/// changed while the server was queried are kept. Emits `model-changed` with the
+/// new model name.
+#[tauri::command]
+pub async fn set_active_model(
+    model: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    ensure_not_kiosk(&state)?;
+    let model = model.trim().to_string();
+    tracing::info!("Switching active model to '{}'", model);
+
+    let llm_client = llm_provider(&state.get_config(), &state.get_api_keys())
+        .map_err(|e| localized(&state, &e))?;
+
+    let models = llm_client.list_models().await.map_err(|e| localized(&state, &e))?;
+    if !models.iter().any(|available| available.id == model) {
+        return Err(localized(&state, &OpenWebUiError::ModelNotFound(model).into()));
+    }
+
+    let mut config = state.get_config();
+    match config.providers.llm {
+        LlmProviderKind::OpenWebUi => config.openwebui.model = model.clone(),
+        LlmProviderKind::AzureOpenAi => config.azure.chat_deployment = model.clone(),
+        LlmProviderKind::OpenRouter => config.openrouter.model = model.clone(),
+        LlmProviderKind::Gemini => config.gemini.model = model.clone(),
+        LlmProviderKind::Mock => {}
+    }
+    apply_config(&app, &state, config)?;
+
+    if let Err(e) = app.emit("model-changed", &model) {
+        tracing::warn!("Failed to emit model-changed: {}", e);
+    }
+    Ok(())
+}
+
 /// List available ElevenLabs voices
 #[tauri::command]
 pub async fn list_voices(state: State<'_, AppState>) -> Result<Vec<crate::api::elevenlabs::Voice>, String> {
-    log::info!("Listing available voices");
+    tracing::info!("Listing available voices");
 
     let config = state.get_config();
     let api_keys = state.get_api_keys();
 
-    let elevenlabs_client = ElevenLabsClient::new(config.elevenlabs, api_keys.elevenlabs)
-        .map_err(|e| e.to_string())?;
+    if tts_is_mock(&config) {
+        return Ok(MockClient::voices());
+    }
+
+    let elevenlabs_client = ElevenLabsClient::new(config.elevenlabs.clone(), api_keys.elevenlabs)
+        .map_err(|e| localized(&state, &e))?;
 
     elevenlabs_client
         .list_voices()
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| localized(&state, &e))
 }
 
 /// Update voice settings
 #[tauri::command]
 pub async fn update_voice_settings(
     settings: VoiceSettings,
+    app: AppHandle,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
-    log::info!("Updating voice settings");
+    ensure_not_kiosk(&state)?;
+    tracing::info!("Updating voice settings");
 
     let mut config = state.get_config();
     config.elevenlabs.voice_settings = settings;
     state.update_config(config.clone());
 
     // Persist to disk
-    let config_manager = ConfigManager::new().map_err(|e| e.to_string())?;
-    config_manager.save(&config).map_err(|e| e.to_string())?;
+    let config_manager = ConfigManager::new().map_err(|e| localized(&state, &e))?;
+    config_manager.save(&config).map_err(|e| localized(&state, &e))?;
+    refresh_voice_presets_menu(&app);
 
     Ok(())
 }
 
+/// Prefix of the tray menu ids of voice presets, followed by the preset name
+pub const VOICE_PRESET_MENU_PREFIX: &str = "voice-preset:";
+
+/// Voice preset with whether it is in use
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VoicePresetStatus {
+    #[serde(flatten)]
+    pub preset: VoicePreset,
+
+    /// Whether the voice, model, and settings in use are the preset's
+    pub active: bool,
+}
+
+/// Every voice preset, with the one in use marked
+#[tauri::command]
+pub async fn list_voice_presets(state: State<'_, AppState>) -> Result<Vec<VoicePresetStatus>, String> {
+    let elevenlabs = state.get_config().elevenlabs;
+    let active = elevenlabs.active_preset().map(|preset| preset.name.clone());
+    Ok(elevenlabs
+        .presets
+        .into_iter()
+        .map(|preset| VoicePresetStatus {
+            active: active.as_ref() == Some(&preset.name),
+            preset,
+        })
+        .collect())
+}
+
+/// Switch to the voice, model, and settings of a preset
+#[tauri::command]
+pub async fn apply_voice_preset(name: String, app: AppHandle, state: State<'_, AppState>) -> Result<VoicePreset, String> {
+    apply_preset(&app, &state, &name).map_err(|e| localized(&state, &e))
+}
+
+/// Save the voice, model, and settings in use as a preset, replacing one of the same name
+#[tauri::command]
+pub async fn save_voice_preset(name: String, app: AppHandle, state: State<'_, AppState>) -> Result<VoicePreset, String> {
+    ensure_not_kiosk(&state)?;
+    let mut config = state.get_config();
+    let preset = config.elevenlabs.save_preset(&name).map_err(|e| localized(&state, &e))?;
+    ConfigManager::new()
+        .and_then(|config_manager| config_manager.save(&config))
+        .map_err(|e| localized(&state, &e))?;
+    state.update_config(config);
+    refresh_voice_presets_menu(&app);
+
+    tracing::info!("Saved voice preset '{}'", preset.name);
+    Ok(preset)
+}
+
+/// Delete a voice preset, returning whether it existed
+#[tauri::command]
+pub async fn delete_voice_preset(name: String, app: AppHandle, state: State<'_, AppState>) -> Result<bool, String> {
+    ensure_not_kiosk(&state)?;
+    let mut config = state.get_config();
+    if !config.elevenlabs.delete_preset(&name) {
+        return Ok(false);
+    }
+    ConfigManager::new()
+        .and_then(|config_manager| config_manager.save(&config))
+        .map_err(|e| localized(&state, &e))?;
+    state.update_config(config);
+    refresh_voice_presets_menu(&app);
+
+    tracing::info!("Deleted voice preset '{}'", name);
+    Ok(true)
+}
+
+/// Switch to voice preset `name`, save the config, and emit `voice-preset-applied`
+///
+/// Shared by `apply_voice_preset` and the tray menu. Fails in kiosk mode.
+pub fn apply_preset(app: &AppHandle, state: &AppState, name: &str) -> AppResult<VoicePreset> {
+    let mut config = state.get_config();
+    if config.kiosk.enabled {
+        return Err(AppError::KioskMode);
+    }
+
+    let preset = config.elevenlabs.apply_preset(name)?;
+    ConfigManager::new()?.save(&config)?;
+    state.update_config(config);
+    refresh_voice_presets_menu(app);
+
+    tracing::info!("Applied voice preset '{}'", preset.name);
+    if let Err(e) = app.emit("voice-preset-applied", &preset) {
+        tracing::warn!("Failed to emit voice-preset-applied: {}", e);
+    }
+    Ok(preset)
+}
+
+/// Rebuild the tray's voice submenu from the presets, checking the one in use
+pub fn refresh_voice_presets_menu(app: &AppHandle) {
+    use tauri::menu::CheckMenuItemBuilder;
+
+    let Some(menu) = app.try_state::<crate::TrayVoiceMenu>() else {
+        return;
+    };
+    let elevenlabs = app.state::<AppState>().get_config().elevenlabs;
+    let active = elevenlabs.active_preset().map(|preset| preset.name.clone());
+
+    for item in menu.0.items().unwrap_or_default() {
+        let _ = menu.0.remove(&item);
+    }
+    for preset in &elevenlabs.presets {
+        let item = CheckMenuItemBuilder::new(&preset.name)
+            .id(format!("{}{}", VOICE_PRESET_MENU_PREFIX, preset.name))
+            .checked(active.as_ref() == Some(&preset.name))
+            .build(app);
+        match item {
+            Ok(item) => {
+                let _ = menu.0.append(&item);
+            }
+            Err(e) => tracing::warn!("Failed to add voice preset '{}' to the tray: {}", preset.name, e),
+        }
+    }
+    let _ = menu.0.set_enabled(!elevenlabs.presets.is_empty());
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -453,6 +3864,9 @@ mod tests {
             whisper: None,
             openwebui: None,
             elevenlabs: None,
+            azure: None,
+            openrouter: None,
+            gemini: None,
         };
         let state = AppState::new(config, api_keys);
 
@@ -467,6 +3881,9 @@ mod tests {
             whisper: None,
             openwebui: None,
             elevenlabs: None,
+            azure: None,
+            openrouter: None,
+            gemini: None,
         };
         let state = AppState::new(config, api_keys);
 
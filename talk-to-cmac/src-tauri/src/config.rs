@@ -2,34 +2,165 @@
 //!
 //! Handles loading, saving, and managing application configuration including
 //! API endpoints, preferences, and secure storage of API keys using the system keyring.
+//! API keys are named secrets; integrations can store their own credentials the same way.
 
+use crate::api::endpoint;
+use crate::crypto::PinHash;
 use crate::error::{AppResult, AppError, ConfigError};
+use crate::profiles::{ProfileStore, DEFAULT_PROFILE};
 use keyring::Entry;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Application configuration structure
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AppConfig {
     /// Whisper API configuration
+    #[serde(default)]
     pub whisper: WhisperConfig,
 
     /// OpenWebUI configuration
+    #[serde(default)]
     pub openwebui: OpenWebUiConfig,
 
     /// ElevenLabs configuration
+    #[serde(default)]
     pub elevenlabs: ElevenLabsConfig,
 
     /// Audio preferences
+    #[serde(default)]
     pub audio: AudioConfig,
 
     /// UI preferences
+    #[serde(default)]
     pub ui: UiConfig,
+
+    /// Privacy and data retention settings
+    #[serde(default)]
+    pub privacy: PrivacyConfig,
+
+    /// Profanity filter for transcriptions and responses
+    #[serde(default)]
+    pub profanity_filter: ProfanityConfig,
+
+    /// Moderation of user input before it is sent to the LLM
+    #[serde(default)]
+    pub moderation: ModerationConfig,
+
+    /// Topics queries may or may not be about
+    #[serde(default)]
+    pub policy: PolicyConfig,
+
+    /// Prompt templates run by saying their trigger phrase
+    #[serde(default)]
+    pub templates: Vec<PromptTemplate>,
+
+    /// Languages and voices for translator mode
+    #[serde(default)]
+    pub translator: TranslatorConfig,
+
+    /// Which service handles transcription, chat, and speech
+    #[serde(default)]
+    pub providers: ProviderConfig,
+
+    /// Azure OpenAI resource and deployments
+    #[serde(default)]
+    pub azure: AzureOpenAiConfig,
+
+    /// OpenRouter model and app attribution
+    #[serde(default)]
+    pub openrouter: OpenRouterConfig,
+
+    /// Google Gemini model and safety settings
+    #[serde(default)]
+    pub gemini: GeminiConfig,
+
+    /// Connections opened at launch to speed up the first query
+    #[serde(default)]
+    pub warmup: WarmupConfig,
+
+    /// Canned responses of the offline `mock` providers
+    #[serde(default)]
+    pub mock: MockConfig,
+
+    /// Fault injection for resilience testing (debug builds only)
+    #[serde(default)]
+    pub chaos: ChaosConfig,
+
+    /// OpenTelemetry export of traces and pipeline metrics
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+
+    /// Opt-in anonymous usage reports
+    #[serde(default)]
+    pub usage_reports: UsageReportsConfig,
+
+    /// Additional labeled API keys per service (the keys themselves are in the keyring)
+    #[serde(default)]
+    pub key_slots: Vec<KeySlot>,
+
+    /// Pauses after a provider's rate limit or quota is hit, and where to fail over
+    #[serde(default)]
+    pub cooldown: CooldownConfig,
+
+    /// Recognizing which profile's user is speaking
+    #[serde(default)]
+    pub speaker_id: SpeakerIdConfig,
+
+    /// Lockdown for public-facing terminals
+    #[serde(default)]
+    pub kiosk: KioskConfig,
+
+    /// Which past messages are sent with each query
+    #[serde(default)]
+    pub context: ContextConfig,
+
+    /// Jira or ServiceNow instance tickets are opened in
+    #[serde(default)]
+    pub ticketing: TicketingConfig,
+
+    /// Where conversations are passed to human support
+    #[serde(default)]
+    pub handoff: HandoffConfig,
+
+    /// Data sources queried for the LLM before a query is sent
+    #[serde(default)]
+    pub tools: ToolsConfig,
+
+    /// RSS and Atom feeds summarized by `give_news_briefing`
+    #[serde(default)]
+    pub news: NewsConfig,
+
+    /// Summarize-clipboard and translate-clipboard actions
+    #[serde(default)]
+    pub clipboard: ClipboardConfig,
+
+    /// Settings laid over the others to save data on metered connections
+    #[serde(default)]
+    pub low_bandwidth: LowBandwidthConfig,
+
+    /// Commands or scripts that rewrite text between pipeline stages, run in order
+    #[serde(default)]
+    pub hooks: Vec<HookConfig>,
+
+    /// Where fleet-wide feature flag overrides are downloaded from
+    #[serde(default)]
+    pub feature_flags: FeatureFlagsConfig,
+
+    /// System prompts compared on real conversations
+    #[serde(default)]
+    pub experiment: PromptExperimentConfig,
+
+    /// First-run setup progress; settings saved before it existed count as set up
+    #[serde(default = "SetupConfig::completed")]
+    pub setup: SetupConfig,
 }
 
 /// Whisper API configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct WhisperConfig {
     /// Whisper API endpoint
     pub endpoint: String,
@@ -45,10 +176,34 @@ pub struct WhisperConfig {
 
     /// Timeout in seconds
     pub timeout_secs: u64,
+
+    /// Alternative hypotheses sampled when a transcription is uncertain (0 = off)
+    pub alternatives: usize,
+
+    /// Confidence (0.0-1.0) below which alternatives are sampled
+    pub alternatives_below: f32,
+}
+
+impl Default for WhisperConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: std::env::var("WHISPER_BASE_URL")
+                .map(|url| endpoint::whisper(&url))
+                .unwrap_or_else(|_| "https://api.openai.com/v1/audio/transcriptions".to_string()),
+            model: std::env::var("WHISPER_MODEL")
+                .unwrap_or_else(|_| "whisper-1".to_string()),
+            language: Some("en".to_string()),
+            temperature: 0.0,
+            timeout_secs: 30,
+            alternatives: 0,
+            alternatives_below: 0.6,
+        }
+    }
 }
 
 /// OpenWebUI configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct OpenWebUiConfig {
     /// OpenWebUI API endpoint
     pub endpoint: String,
@@ -70,151 +225,2355 @@ pub struct OpenWebUiConfig {
 
     /// Timeout in seconds
     pub timeout_secs: u64,
+
+    /// Ask for short, plain-prose answers to spoken queries
+    #[serde(default)]
+    pub voice_optimized: bool,
+
+    /// Maximum tokens to generate for spoken queries when voice-optimized
+    #[serde(default = "default_voice_max_tokens")]
+    pub voice_max_tokens: usize,
+
+    /// Ask the LLM for short follow-up questions after each reply
+    #[serde(default)]
+    pub follow_up_suggestions: bool,
+
+    /// Nucleus sampling probability mass (0.0-1.0)
+    #[serde(default)]
+    pub top_p: Option<f32>,
+
+    /// Penalty for tokens that already appeared (-2.0-2.0)
+    #[serde(default)]
+    pub presence_penalty: Option<f32>,
+
+    /// Penalty proportional to how often tokens appeared (-2.0-2.0)
+    #[serde(default)]
+    pub frequency_penalty: Option<f32>,
+
+    /// Sequences that end generation (most providers accept up to four)
+    #[serde(default)]
+    pub stop: Vec<String>,
+
+    /// Seed for reproducible sampling, where the model supports it
+    #[serde(default)]
+    pub seed: Option<u64>,
+
+    /// Send the reasoning of thinking models to the UI (it is never stored or spoken)
+    #[serde(default)]
+    pub show_reasoning: bool,
+
+    /// How long Ollama keeps the model loaded after a request (e.g., "30m"; "-1m" = indefinitely)
+    #[serde(default)]
+    pub keep_alive: Option<String>,
+
+    /// Asking again when the answer is empty or a stock refusal
+    #[serde(default)]
+    pub retry: RetryConfig,
+
+    /// Kind of server at `endpoint`, detected by `probe_llm_api`; `None` until probed
+    #[serde(default)]
+    pub api_flavor: Option<ApiFlavor>,
+}
+
+impl Default for OpenWebUiConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: std::env::var("OPENWEBUI_BASE_URL")
+                .map(|url| endpoint::openwebui_chat(&url))
+                .unwrap_or_else(|_| "http://localhost:3000/api/chat".to_string()),
+            model: std::env::var("OPENWEBUI_MODEL_NAME")
+                .unwrap_or_else(|_| "llama3.2".to_string()),
+            max_context_length: 4096,
+            temperature: 0.7,
+            max_tokens: Some(1024),
+            stream: false,
+            timeout_secs: 60,
+            voice_optimized: false,
+            voice_max_tokens: default_voice_max_tokens(),
+            follow_up_suggestions: false,
+            top_p: None,
+            presence_penalty: None,
+            frequency_penalty: None,
+            stop: Vec::new(),
+            seed: None,
+            show_reasoning: false,
+            keep_alive: None,
+            retry: RetryConfig::default(),
+            api_flavor: None,
+        }
+    }
+}
+
+fn default_voice_max_tokens() -> usize {
+    200
+}
+
+/// Kind of server behind an OpenAI-style chat endpoint
+///
+/// The servers differ in where the chat endpoint is and in which request
+/// fields they accept, so requests are shaped to the one detected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ApiFlavor {
+    #[serde(rename = "openwebui")]
+    OpenWebUi,
+
+    /// OpenAI's own API
+    #[serde(rename = "openai")]
+    OpenAi,
+
+    #[serde(rename = "ollama")]
+    Ollama,
+
+    #[serde(rename = "lm_studio")]
+    LmStudio,
+
+    /// Any other server with an OpenAI-compatible API (e.g. llama.cpp, vLLM)
+    #[serde(rename = "openai_compatible")]
+    OpenAiCompatible,
+}
+
+impl ApiFlavor {
+    /// Path of the chat completions endpoint below the server's base URL
+    pub fn chat_path(self) -> &'static str {
+        match self {
+            ApiFlavor::OpenWebUi => "/api/chat/completions",
+            _ => "/v1/chat/completions",
+        }
+    }
+
+    /// Whether the server takes Ollama's `keep_alive` field (OpenWebUI passes it on to Ollama)
+    pub fn accepts_keep_alive(self) -> bool {
+        matches!(self, ApiFlavor::OpenWebUi | ApiFlavor::Ollama)
+    }
+
+    /// Whether the server answers from attached files and knowledge collections (`files`)
+    pub fn accepts_files(self) -> bool {
+        self == ApiFlavor::OpenWebUi
+    }
+
+    /// Whether the token limit is sent as `max_completion_tokens`, which OpenAI's newer models require
+    pub fn uses_max_completion_tokens(self) -> bool {
+        self == ApiFlavor::OpenAi
+    }
+}
+
+/// One more attempt at an unhelpful answer
+///
+/// An answer is unhelpful when it is empty or, if short, opens with one of
+/// `refusal_phrases`. The query is then sent once more with `instruction`
+/// added to it. Answers spoken while streaming are never retried.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryConfig {
+    pub enabled: bool,
+
+    /// Openings of boilerplate refusals, matched ignoring case
+    pub refusal_phrases: Vec<String>,
+
+    /// Added to the query for the second attempt
+    pub instruction: String,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            refusal_phrases: [
+                "I'm sorry, but I can't",
+                "I'm sorry, I can't",
+                "I can't help with",
+                "I cannot help with",
+                "I'm unable to",
+                "I am unable to",
+                "As an AI",
+            ]
+            .map(String::from)
+            .to_vec(),
+            instruction: "Answer this directly and helpfully. If part of it cannot be answered, \
+                          answer the rest and say briefly what is missing."
+                .to_string(),
+        }
+    }
+}
+
+/// ElevenLabs configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ElevenLabsConfig {
+    /// ElevenLabs API endpoint
+    pub endpoint: String,
+
+    /// Voice ID to use
+    pub voice_id: String,
+
+    /// Model ID (e.g., "eleven_monolingual_v1")
+    pub model_id: String,
+
+    /// Voice settings
+    pub voice_settings: VoiceSettings,
+
+    /// Timeout in seconds
+    pub timeout_secs: u64,
+
+    /// Named voices with their settings, switched between from the settings or the tray
+    pub presets: Vec<VoicePreset>,
+
+    /// Audio format and bitrate requested (e.g., "mp3_22050_32"; unset = the API's default, "mp3_44100_128")
+    pub output_format: Option<String>,
+}
+
+impl Default for ElevenLabsConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: std::env::var("ELEVENLABS_BASE_URL")
+                .map(|url| endpoint::elevenlabs(&url))
+                .unwrap_or_else(|_| "https://api.elevenlabs.io/v1/text-to-speech".to_string()),
+            voice_id: std::env::var("ELEVENLABS_VOICE_ID")
+                .unwrap_or_else(|_| "21m00Tcm4TlvDq8ikWAM".to_string()), // Default voice
+            model_id: "eleven_monolingual_v1".to_string(),
+            voice_settings: VoiceSettings::default(),
+            timeout_secs: 30,
+            presets: Vec::new(),
+            output_format: None,
+        }
+    }
+}
+
+/// Longest accepted voice preset name
+const MAX_PRESET_NAME_LEN: usize = 40;
+
+impl ElevenLabsConfig {
+    /// The preset whose voice, model, and settings are in use, if any
+    pub fn active_preset(&self) -> Option<&VoicePreset> {
+        self.presets.iter().find(|preset| {
+            preset.voice_id == self.voice_id
+                && preset.model_id == self.model_id
+                && preset.voice_settings == self.voice_settings
+        })
+    }
+
+    /// Use the voice, model, and settings of the preset named `name` (ignoring case)
+    pub fn apply_preset(&mut self, name: &str) -> AppResult<VoicePreset> {
+        let preset = self
+            .presets
+            .iter()
+            .find(|preset| preset.name.eq_ignore_ascii_case(name.trim()))
+            .cloned()
+            .ok_or_else(|| ConfigError::InvalidValue(format!("Unknown voice preset: {}", name)))?;
+
+        self.voice_id = preset.voice_id.clone();
+        self.model_id = preset.model_id.clone();
+        self.voice_settings = preset.voice_settings.clone();
+        Ok(preset)
+    }
+
+    /// Save the voice, model, and settings in use as preset `name`, replacing one of the same name
+    pub fn save_preset(&mut self, name: &str) -> AppResult<VoicePreset> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_PRESET_NAME_LEN {
+            return Err(ConfigError::InvalidValue(format!(
+                "Voice preset names must be 1 to {} characters",
+                MAX_PRESET_NAME_LEN
+            ))
+            .into());
+        }
+
+        let preset = VoicePreset {
+            name: name.to_string(),
+            voice_id: self.voice_id.clone(),
+            model_id: self.model_id.clone(),
+            voice_settings: self.voice_settings.clone(),
+        };
+        match self.presets.iter_mut().find(|existing| existing.name.eq_ignore_ascii_case(name)) {
+            Some(existing) => *existing = preset.clone(),
+            None => self.presets.push(preset.clone()),
+        }
+        Ok(preset)
+    }
+
+    /// Remove the preset named `name`, returning whether it existed
+    pub fn delete_preset(&mut self, name: &str) -> bool {
+        let before = self.presets.len();
+        self.presets.retain(|preset| !preset.name.eq_ignore_ascii_case(name.trim()));
+        self.presets.len() != before
+    }
+}
+
+/// A named voice with its model and settings (e.g., "Calm", "Energetic")
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VoicePreset {
+    pub name: String,
+    pub voice_id: String,
+    pub model_id: String,
+    pub voice_settings: VoiceSettings,
+}
+
+/// Voice synthesis settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VoiceSettings {
+    /// Stability (0.0-1.0)
+    pub stability: f32,
+
+    /// Similarity boost (0.0-1.0)
+    pub similarity_boost: f32,
+
+    /// Style (0.0-1.0)
+    pub style: Option<f32>,
+
+    /// Use speaker boost
+    pub use_speaker_boost: bool,
+}
+
+impl Default for VoiceSettings {
+    fn default() -> Self {
+        Self {
+            stability: 0.5,
+            similarity_boost: 0.75,
+            style: Some(0.0),
+            use_speaker_boost: true,
+        }
+    }
+}
+
+/// Audio configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioConfig {
+    /// Sample rate for recording (Hz)
+    pub sample_rate: u32,
+
+    /// Bit depth
+    pub bit_depth: u16,
+
+    /// Number of channels (1 = mono, 2 = stereo)
+    pub channels: u16,
+
+    /// Recording format
+    pub format: String,
+
+    /// Silence detection threshold (0.0-1.0)
+    pub silence_threshold: f32,
+
+    /// Silence duration before stopping (seconds)
+    pub silence_duration: f32,
+
+    /// Maximum recording duration (seconds)
+    pub max_duration: u32,
+
+    /// Cancel a recording in which no speech (above `silence_threshold`) is heard
+    /// within this many seconds (0 = never)
+    pub listen_timeout_secs: f32,
+
+    /// Lower other applications' volume while the assistant is speaking
+    #[serde(default)]
+    pub duck_system_audio: bool,
+
+    /// Fraction of their original volume other applications are lowered to (0.0-1.0)
+    #[serde(default = "default_duck_level")]
+    pub duck_level: f32,
+
+    /// Keep a copy of each recorded utterance and spoken reply on disk
+    #[serde(default)]
+    pub save_recordings: bool,
+
+    /// Delete saved recordings older than this many days (0 = keep forever)
+    #[serde(default = "default_recording_retention_days")]
+    pub recording_retention_days: u32,
+
+    /// Maximum number of saved recordings to keep (0 = unlimited)
+    #[serde(default = "default_max_recordings")]
+    pub max_recordings: usize,
+
+    /// Handle built-in spoken commands ("clear conversation", "stop talking") locally
+    #[serde(default = "default_voice_commands")]
+    pub voice_commands: bool,
+
+    /// Ask for a spoken yes before running voice commands that act outside the app (opening a ticket)
+    #[serde(default = "default_confirm_actions")]
+    pub confirm_actions: bool,
+
+    /// Sentences of a streamed reply synthesized at the same time, ahead of playback
+    #[serde(default = "default_tts_concurrency")]
+    pub tts_concurrency: usize,
+
+    /// Amplify quiet recordings before transcription
+    pub auto_gain: bool,
+
+    /// Speech level (RMS, 0.0-1.0) quiet recordings are amplified to
+    pub auto_gain_target: f32,
+
+    /// Most a recording is amplified by (10.0 = +20 dB)
+    pub auto_gain_max: f32,
+
+    /// Channel of a stereo recording that is transcribed
+    pub input_channel: InputChannel,
+
+    /// Cut leading and trailing silence (below `silence_threshold`) from recordings before upload
+    pub auto_trim: bool,
+
+    /// Upload WAV recordings as FLAC, which is lossless and roughly half the size
+    pub compress_uploads: bool,
+
+    /// Speak a short explanation when a voice query fails
+    pub speak_errors: bool,
+
+    /// Latest transcriptions kept in memory for `get_recent_transcriptions` (0 = none)
+    pub recent_transcriptions: usize,
+
+    /// Whether queries are spoken or typed, and replies spoken or written
+    pub pipeline_mode: PipelineMode,
+}
+
+/// Which ends of a query use speech
+///
+/// A mode without speech input refuses recordings, so speech-to-text never runs; one
+/// without speech output returns replies as text only, so text-to-speech never runs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineMode {
+    /// Spoken queries, spoken replies
+    #[default]
+    Voice,
+
+    /// Typed queries, written replies
+    Text,
+
+    /// Spoken queries, written replies
+    VoiceInTextOut,
+
+    /// Typed queries, spoken replies
+    TextInVoiceOut,
+}
+
+impl PipelineMode {
+    /// Whether recordings are accepted
+    pub fn speech_in(self) -> bool {
+        matches!(self, PipelineMode::Voice | PipelineMode::VoiceInTextOut)
+    }
+
+    /// Whether replies are spoken
+    pub fn speech_out(self) -> bool {
+        matches!(self, PipelineMode::Voice | PipelineMode::TextInVoiceOut)
+    }
+}
+
+/// Channel of a multi-channel recording that is transcribed
+///
+/// Stereo audio interfaces often carry the microphone on one channel only.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InputChannel {
+    /// Every channel, as recorded
+    #[default]
+    All,
+
+    /// The channels mixed down to mono
+    Mix,
+
+    /// The first (left) channel
+    Left,
+
+    /// The second (right) channel
+    Right,
+
+    /// Whichever channel is loudest over the recording
+    Loudest,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: 16000,
+            bit_depth: 16,
+            channels: 1,
+            format: "wav".to_string(),
+            silence_threshold: 0.01,
+            silence_duration: 2.0,
+            max_duration: 300,
+            listen_timeout_secs: 8.0,
+            duck_system_audio: false,
+            duck_level: default_duck_level(),
+            save_recordings: false,
+            recording_retention_days: default_recording_retention_days(),
+            max_recordings: default_max_recordings(),
+            voice_commands: default_voice_commands(),
+            confirm_actions: default_confirm_actions(),
+            tts_concurrency: default_tts_concurrency(),
+            auto_gain: false,
+            auto_gain_target: 0.1,
+            auto_gain_max: 10.0,
+            input_channel: InputChannel::default(),
+            auto_trim: false,
+            compress_uploads: false,
+            speak_errors: false,
+            recent_transcriptions: 20,
+            pipeline_mode: PipelineMode::default(),
+        }
+    }
+}
+
+fn default_voice_commands() -> bool {
+    true
+}
+
+fn default_confirm_actions() -> bool {
+    true
+}
+
+fn default_tts_concurrency() -> usize {
+    2
+}
+
+fn default_duck_level() -> f32 {
+    0.2
+}
+
+fn default_recording_retention_days() -> u32 {
+    30
+}
+
+fn default_max_recordings() -> usize {
+    500
+}
+
+/// Severity of a profane word; filtering applies at and above the configured level
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProfanitySeverity {
+    #[default]
+    Mild,
+    Moderate,
+    Severe,
+}
+
+/// Profanity filter settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProfanityConfig {
+    /// Mask profanity in transcriptions and LLM responses before display and speech
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Lowest severity of built-in words to mask
+    #[serde(default)]
+    pub min_severity: ProfanitySeverity,
+
+    /// Additional words to always mask
+    #[serde(default)]
+    pub custom_words: Vec<String>,
+
+    /// Built-in words to never mask
+    #[serde(default)]
+    pub allowed_words: Vec<String>,
+}
+
+/// Where user input is classified for moderation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationProvider {
+    /// Only the configured local patterns
+    #[default]
+    Local,
+
+    /// Local patterns plus the OpenAI moderation endpoint (uses the Whisper/OpenAI API key)
+    OpenAi,
+}
+
+/// How much of the user's and the LLM's text is written to the log
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogPrivacy {
+    /// Neither the text nor its length
+    None,
+
+    /// Only the length of the text
+    #[default]
+    Metadata,
+
+    /// The full text, in debug builds only; release builds log metadata instead
+    FullContentDevOnly,
+}
+
+/// What happens to input that moderation flags
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationAction {
+    /// Withhold the input from the LLM and reply with the refusal message
+    #[default]
+    Block,
+
+    /// Forward the input but report it to the frontend
+    Flag,
+}
+
+/// Content moderation settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModerationConfig {
+    /// Check user input before sending it to the LLM
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Classification provider
+    #[serde(default)]
+    pub provider: ModerationProvider,
+
+    /// Action for flagged input
+    #[serde(default)]
+    pub action: ModerationAction,
+
+    /// Moderation endpoint for the OpenAI provider
+    #[serde(default = "default_moderation_endpoint")]
+    pub endpoint: String,
+
+    /// Moderation model for the OpenAI provider
+    #[serde(default = "default_moderation_model")]
+    pub model: String,
+
+    /// Case-insensitive regular expressions that flag input locally
+    #[serde(default)]
+    pub blocked_patterns: Vec<String>,
+
+    /// Reply used in place of an LLM response when input is blocked
+    #[serde(default = "default_refusal_message")]
+    pub refusal_message: String,
+
+    /// Timeout in seconds for the moderation request
+    #[serde(default = "default_moderation_timeout")]
+    pub timeout_secs: u64,
+}
+
+impl Default for ModerationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            provider: ModerationProvider::default(),
+            action: ModerationAction::default(),
+            endpoint: default_moderation_endpoint(),
+            model: default_moderation_model(),
+            blocked_patterns: Vec::new(),
+            refusal_message: default_refusal_message(),
+            timeout_secs: default_moderation_timeout(),
+        }
+    }
+}
+
+fn default_moderation_endpoint() -> String {
+    "https://api.openai.com/v1/moderations".to_string()
+}
+
+fn default_moderation_model() -> String {
+    "omni-moderation-latest".to_string()
+}
+
+fn default_refusal_message() -> String {
+    "Sorry, I can't help with that request.".to_string()
+}
+
+fn default_moderation_timeout() -> u64 {
+    10
+}
+
+/// Topic policy for user input (see [`crate::policy`])
+///
+/// A query about a `denied` topic is refused. If `allowed` lists any topics,
+/// a query about none of them is refused too, which keeps a kiosk on the
+/// subjects it is there for.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Topics that are always refused
+    #[serde(default)]
+    pub denied: Vec<TopicRule>,
+
+    /// Topics that are answered; if empty, every topic not denied is
+    #[serde(default)]
+    pub allowed: Vec<TopicRule>,
+
+    /// Ask the LLM which topic a query is about when no keyword matches
+    #[serde(default)]
+    pub llm_classifier: bool,
+
+    /// Reply spoken in place of an answer when a query is refused
+    #[serde(default = "default_policy_refusal")]
+    pub refusal_message: String,
+}
+
+impl Default for PolicyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            denied: Vec::new(),
+            allowed: Vec::new(),
+            llm_classifier: false,
+            refusal_message: default_policy_refusal(),
+        }
+    }
+}
+
+/// A topic and the words that identify it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopicRule {
+    /// Topic name, also given to the LLM classifier (e.g., "politics")
+    pub name: String,
+
+    /// Words or phrases that place a query in the topic (case-insensitive, whole words)
+    #[serde(default)]
+    pub keywords: Vec<String>,
+}
+
+fn default_policy_refusal() -> String {
+    "Sorry, I can only help with questions about our products and services.".to_string()
+}
+
+/// Prompt template bound to spoken trigger phrases
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    /// Display name
+    pub name: String,
+
+    /// Phrases that run the template when a transcription starts with them
+    #[serde(default)]
+    pub triggers: Vec<String>,
+
+    /// Prompt sent to the LLM; `{input}` is replaced with what was said after the trigger
+    pub prompt: String,
+}
+
+/// A/B test of system prompts
+///
+/// Each new conversation is assigned one of `variants` at random, in proportion
+/// to their weights. Its messages are tagged "experiment/variant", and usage
+/// reports count conversations, replies, errors, and ratings per variant. A
+/// conversation's own system prompt takes precedence over its variant's.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PromptExperimentConfig {
+    pub enabled: bool,
+
+    /// Experiment name, part of every variant tag
+    pub name: String,
+
+    pub variants: Vec<PromptVariant>,
+}
+
+/// One system prompt of an experiment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptVariant {
+    pub name: String,
+
+    /// System prompt sent with the variant's conversations (empty = none, as a control)
+    #[serde(default)]
+    pub system_prompt: String,
+
+    /// Share of new conversations, relative to the other variants
+    #[serde(default = "default_variant_weight")]
+    pub weight: u32,
+}
+
+fn default_variant_weight() -> u32 {
+    1
+}
+
+/// Translator mode settings
+///
+/// Turns alternate: speech in `language_a` is spoken back in `language_b`, then the reverse.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranslatorConfig {
+    /// Language of the first speaker (ISO 639-1 code, e.g., "en")
+    #[serde(default = "default_language_a")]
+    pub language_a: String,
+
+    /// Language of the second speaker (ISO 639-1 code, e.g., "es")
+    #[serde(default = "default_language_b")]
+    pub language_b: String,
+
+    /// ElevenLabs voice for speech in language A (defaults to the configured voice)
+    #[serde(default)]
+    pub voice_a: Option<String>,
+
+    /// ElevenLabs voice for speech in language B (defaults to the configured voice)
+    #[serde(default)]
+    pub voice_b: Option<String>,
+}
+
+impl Default for TranslatorConfig {
+    fn default() -> Self {
+        Self {
+            language_a: default_language_a(),
+            language_b: default_language_b(),
+            voice_a: None,
+            voice_b: None,
+        }
+    }
+}
+
+fn default_language_a() -> String {
+    "en".to_string()
+}
+
+fn default_language_b() -> String {
+    "es".to_string()
+}
+
+/// Speech-to-text provider
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SttProviderKind {
+    /// OpenAI Whisper or a compatible endpoint (`whisper` settings)
+    #[default]
+    #[serde(rename = "whisper")]
+    Whisper,
+
+    /// Azure OpenAI transcription deployment (`azure` settings)
+    #[serde(rename = "azure_openai")]
+    AzureOpenAi,
+
+    /// Canned transcription for offline development (`mock` settings)
+    #[serde(rename = "mock")]
+    Mock,
+}
+
+/// LLM provider
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LlmProviderKind {
+    /// OpenWebUI or another OpenAI-compatible chat endpoint (`openwebui` settings)
+    #[default]
+    #[serde(rename = "openwebui")]
+    OpenWebUi,
+
+    /// Azure OpenAI chat deployment (`azure` settings)
+    #[serde(rename = "azure_openai")]
+    AzureOpenAi,
+
+    /// OpenRouter (`openrouter` settings)
+    #[serde(rename = "openrouter")]
+    OpenRouter,
+
+    /// Google Gemini (`gemini` settings)
+    #[serde(rename = "gemini")]
+    Gemini,
+
+    /// Canned replies for offline development (`mock` settings)
+    #[serde(rename = "mock")]
+    Mock,
+}
+
+/// Text-to-speech provider
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TtsProviderKind {
+    /// ElevenLabs (`elevenlabs` settings)
+    #[default]
+    #[serde(rename = "elevenlabs")]
+    ElevenLabs,
+
+    /// Azure OpenAI speech deployment (`azure` settings)
+    #[serde(rename = "azure_openai")]
+    AzureOpenAi,
+
+    /// Silent audio for offline development (`mock` settings)
+    #[serde(rename = "mock")]
+    Mock,
+}
+
+/// Provider selection for each pipeline stage
+///
+/// Generation settings (temperature, token limits, language) are taken from the
+/// `openwebui` and `whisper` sections regardless of provider.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProviderConfig {
+    #[serde(default)]
+    pub stt: SttProviderKind,
+
+    #[serde(default)]
+    pub llm: LlmProviderKind,
+
+    #[serde(default)]
+    pub tts: TtsProviderKind,
+}
+
+/// Azure OpenAI configuration
+///
+/// Azure routes requests by deployment name rather than model name; a stage
+/// only works if its deployment is set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AzureOpenAiConfig {
+    /// Resource endpoint (e.g., "https://my-resource.openai.azure.com")
+    #[serde(default)]
+    pub endpoint: String,
+
+    /// REST API version sent as the `api-version` query parameter
+    #[serde(default = "default_azure_api_version")]
+    pub api_version: String,
+
+    /// Chat completions deployment
+    #[serde(default)]
+    pub chat_deployment: String,
+
+    /// Audio transcription (Whisper) deployment
+    #[serde(default)]
+    pub transcription_deployment: String,
+
+    /// Text-to-speech deployment
+    #[serde(default)]
+    pub speech_deployment: String,
+
+    /// Voice for speech synthesis (e.g., "alloy")
+    #[serde(default = "default_azure_voice")]
+    pub speech_voice: String,
+
+    /// Timeout in seconds
+    #[serde(default = "default_azure_timeout")]
+    pub timeout_secs: u64,
+}
+
+impl Default for AzureOpenAiConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: String::new(),
+            api_version: default_azure_api_version(),
+            chat_deployment: String::new(),
+            transcription_deployment: String::new(),
+            speech_deployment: String::new(),
+            speech_voice: default_azure_voice(),
+            timeout_secs: default_azure_timeout(),
+        }
+    }
+}
+
+fn default_azure_api_version() -> String {
+    "2024-10-21".to_string()
+}
+
+fn default_azure_voice() -> String {
+    "alloy".to_string()
+}
+
+fn default_azure_timeout() -> u64 {
+    60
+}
+
+/// OpenRouter configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenRouterConfig {
+    /// API base URL
+    #[serde(default = "default_openrouter_endpoint")]
+    pub endpoint: String,
+
+    /// Model ID (e.g., "anthropic/claude-3.5-sonnet")
+    #[serde(default = "default_openrouter_model")]
+    pub model: String,
+
+    /// Site URL sent as `HTTP-Referer` for app attribution
+    #[serde(default = "default_openrouter_referer")]
+    pub referer: String,
+
+    /// App name sent as `X-Title`
+    #[serde(default = "default_openrouter_title")]
+    pub title: String,
+
+    /// Timeout in seconds
+    #[serde(default = "default_openrouter_timeout")]
+    pub timeout_secs: u64,
+}
+
+impl Default for OpenRouterConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: default_openrouter_endpoint(),
+            model: default_openrouter_model(),
+            referer: default_openrouter_referer(),
+            title: default_openrouter_title(),
+            timeout_secs: default_openrouter_timeout(),
+        }
+    }
+}
+
+fn default_openrouter_endpoint() -> String {
+    "https://openrouter.ai/api/v1".to_string()
+}
+
+fn default_openrouter_model() -> String {
+    "openai/gpt-4o-mini".to_string()
+}
+
+fn default_openrouter_referer() -> String {
+    "https://github.com/cojovi/cmac_chat_module_win86".to_string()
+}
+
+fn default_openrouter_title() -> String {
+    "Talk to CMAC".to_string()
+}
+
+fn default_openrouter_timeout() -> u64 {
+    60
+}
+
+/// Google Gemini configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeminiConfig {
+    /// API base URL
+    #[serde(default = "default_gemini_endpoint")]
+    pub endpoint: String,
+
+    /// Model name (e.g., "gemini-2.0-flash")
+    #[serde(default = "default_gemini_model")]
+    pub model: String,
+
+    /// Blocking thresholds per harm category; Gemini's defaults apply to unlisted categories
+    #[serde(default)]
+    pub safety_settings: Vec<GeminiSafetySetting>,
+
+    /// Timeout in seconds
+    #[serde(default = "default_gemini_timeout")]
+    pub timeout_secs: u64,
+}
+
+impl Default for GeminiConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: default_gemini_endpoint(),
+            model: default_gemini_model(),
+            safety_settings: Vec::new(),
+            timeout_secs: default_gemini_timeout(),
+        }
+    }
+}
+
+/// Gemini safety setting, passed to the API as-is
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeminiSafetySetting {
+    /// Harm category (e.g., "HARM_CATEGORY_HARASSMENT")
+    pub category: String,
+
+    /// Blocking threshold (e.g., "BLOCK_ONLY_HIGH", "BLOCK_NONE")
+    pub threshold: String,
+}
+
+fn default_gemini_endpoint() -> String {
+    "https://generativelanguage.googleapis.com/v1beta".to_string()
+}
+
+fn default_gemini_model() -> String {
+    "gemini-2.0-flash".to_string()
+}
+
+fn default_gemini_timeout() -> u64 {
+    60
+}
+
+/// Startup warm-up
+///
+/// Connects to the configured speech, LLM, and voice services at launch so the
+/// first voice query does not wait for DNS, TCP, and TLS setup.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarmupConfig {
+    /// Connect to every configured service when the app starts
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Also send a one-token request to a local LLM so the model is loaded
+    #[serde(default = "default_prime_local_llm")]
+    pub prime_local_llm: bool,
+
+    /// Seconds between keep-alive requests to an OpenWebUI model while idle (0 = off)
+    #[serde(default)]
+    pub keep_alive_interval_secs: u64,
+}
+
+impl Default for WarmupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            prime_local_llm: default_prime_local_llm(),
+            keep_alive_interval_secs: 0,
+        }
+    }
+}
+
+fn default_prime_local_llm() -> bool {
+    true
+}
+
+/// Offline mock providers
+///
+/// Used when a stage's provider is `mock`, or for every stage when the
+/// `CMAC_MOCK_PROVIDERS` environment variable is set. No network requests are made.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MockConfig {
+    /// Text returned for every transcription
+    #[serde(default = "default_mock_transcription")]
+    pub transcription: String,
+
+    /// Replies used in turn (built-in replies if empty)
+    #[serde(default)]
+    pub responses: Vec<String>,
+
+    /// Simulated processing time of each request (milliseconds)
+    #[serde(default = "default_mock_latency_ms")]
+    pub latency_ms: u64,
+}
+
+impl Default for MockConfig {
+    fn default() -> Self {
+        Self {
+            transcription: default_mock_transcription(),
+            responses: Vec::new(),
+            latency_ms: default_mock_latency_ms(),
+        }
+    }
+}
+
+fn default_mock_transcription() -> String {
+    "What's the weather like today?".to_string()
+}
+
+fn default_mock_latency_ms() -> u64 {
+    400
+}
+
+/// Fault injection
+///
+/// Wraps every provider call with random latency, dropped responses, and bursts
+/// of rate-limit and server errors. Ignored in release builds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChaosConfig {
+    /// Inject faults into provider calls
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Upper bound of the random delay added to each call (milliseconds)
+    #[serde(default = "default_chaos_max_latency_ms")]
+    pub max_latency_ms: u64,
+
+    /// Chance that a call hangs and then times out (0.0-1.0)
+    #[serde(default = "default_chaos_drop_rate")]
+    pub drop_rate: f32,
+
+    /// Chance that a call starts a burst of 429/500 errors (0.0-1.0)
+    #[serde(default = "default_chaos_error_rate")]
+    pub error_rate: f32,
+
+    /// Consecutive calls to the same stage that fail once a burst starts
+    #[serde(default = "default_chaos_burst_length")]
+    pub burst_length: u32,
+}
+
+impl Default for ChaosConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_latency_ms: default_chaos_max_latency_ms(),
+            drop_rate: default_chaos_drop_rate(),
+            error_rate: default_chaos_error_rate(),
+            burst_length: default_chaos_burst_length(),
+        }
+    }
+}
+
+fn default_chaos_max_latency_ms() -> u64 {
+    1500
+}
+
+fn default_chaos_drop_rate() -> f32 {
+    0.05
+}
+
+fn default_chaos_error_rate() -> f32 {
+    0.1
+}
+
+fn default_chaos_burst_length() -> u32 {
+    3
+}
+
+/// Provider cooldowns
+///
+/// A provider that reports a rate limit (HTTP 429) or an exhausted quota or
+/// credit balance (HTTP 402) is not called again until its cooldown ends.
+/// Meanwhile its stage uses the fallback provider, if one is set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CooldownConfig {
+    /// Pause after a rate limit (seconds)
+    #[serde(default = "default_rate_limit_cooldown_secs")]
+    pub rate_limit_secs: u64,
+
+    /// Pause after an exhausted quota or credit balance (seconds)
+    #[serde(default = "default_quota_cooldown_secs")]
+    pub quota_secs: u64,
+
+    /// Speech-to-text provider used while the selected one cools down
+    #[serde(default)]
+    pub fallback_stt: Option<SttProviderKind>,
+
+    /// LLM provider used while the selected one cools down
+    #[serde(default)]
+    pub fallback_llm: Option<LlmProviderKind>,
+
+    /// Text-to-speech provider used while the selected one cools down
+    #[serde(default)]
+    pub fallback_tts: Option<TtsProviderKind>,
+}
+
+impl Default for CooldownConfig {
+    fn default() -> Self {
+        Self {
+            rate_limit_secs: default_rate_limit_cooldown_secs(),
+            quota_secs: default_quota_cooldown_secs(),
+            fallback_stt: None,
+            fallback_llm: None,
+            fallback_tts: None,
+        }
+    }
+}
+
+/// Speaker identification settings
+///
+/// When enabled, each spoken query is compared with the voiceprints enrolled
+/// for the profiles, and the app switches to the best match if it scores at
+/// least `min_similarity` and has no PIN.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpeakerIdConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Lowest voiceprint similarity (0.0-1.0) accepted as a match
+    #[serde(default = "default_min_speaker_similarity")]
+    pub min_similarity: f32,
+}
+
+impl Default for SpeakerIdConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_similarity: default_min_speaker_similarity(),
+        }
+    }
+}
+
+fn default_min_speaker_similarity() -> f32 {
+    0.9
+}
+
+/// Kiosk mode settings
+///
+/// In kiosk mode settings cannot be changed, the model, voice, and system prompt
+/// stay as configured, history cannot be browsed or exported, and the
+/// conversation is cleared once a user walks away. It is turned on and off with
+/// `set_kiosk_mode`, which needs the session PIN, not by saving the config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KioskConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Clear the conversation after this many seconds without activity (0 = never)
+    #[serde(default = "default_kiosk_reset_secs")]
+    pub reset_after_secs: u64,
+}
+
+impl Default for KioskConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            reset_after_secs: default_kiosk_reset_secs(),
+        }
+    }
+}
+
+fn default_kiosk_reset_secs() -> u64 {
+    120
+}
+
+/// Messages sent with each query when only the latest ones are sent
+pub const RECENT_CONTEXT_MESSAGES: usize = 20;
+
+/// Which past messages are sent with each query
+///
+/// By default the latest 20 messages are. With `semantic` on, a longer history
+/// is kept and each query is sent with the latest `recent_messages` plus the
+/// `relevant_messages` older ones most similar to it (see [`crate::context`]).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContextConfig {
+    /// Send the past messages most relevant to the query instead of only the latest
+    pub semantic: bool,
+
+    /// Older messages picked by similarity to the query
+    pub relevant_messages: usize,
+
+    /// Latest messages always sent, so follow-ups like "and tomorrow?" keep their context
+    pub recent_messages: usize,
+
+    /// Messages kept in memory to pick from
+    pub history_size: usize,
+
+    /// Model that embeds messages (e.g., "nomic-embed-text")
+    pub embedding_model: String,
+}
+
+impl Default for ContextConfig {
+    fn default() -> Self {
+        Self {
+            semantic: false,
+            relevant_messages: 8,
+            recent_messages: 6,
+            history_size: 200,
+            embedding_model: "nomic-embed-text".to_string(),
+        }
+    }
+}
+
+impl ContextConfig {
+    /// User and assistant messages kept in the conversation
+    pub fn message_limit(&self) -> usize {
+        if self.semantic {
+            self.history_size.max(RECENT_CONTEXT_MESSAGES)
+        } else {
+            RECENT_CONTEXT_MESSAGES
+        }
+    }
+}
+
+/// Data sources queried for the LLM (see [`crate::tools`])
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolsConfig {
+    /// REST search endpoints run when a query mentions one of their trigger phrases
+    pub lookups: Vec<LookupConfig>,
+
+    /// Calendar whose upcoming events are sent with questions about the schedule
+    pub calendar: CalendarConfig,
+
+    /// Mailbox whose unread messages are sent with questions about the inbox
+    pub email: EmailConfig,
+
+    /// Local folders searched for documents a query asks to find
+    pub files: FileSearchConfig,
+
+    /// Text recognition in images, for `ocr_image` and attached images
+    pub ocr: OcrConfig,
+
+    /// Characters of results added to a query, across all sources
+    pub max_result_chars: usize,
+}
+
+impl Default for ToolsConfig {
+    fn default() -> Self {
+        Self {
+            lookups: Vec::new(),
+            calendar: CalendarConfig::default(),
+            email: EmailConfig::default(),
+            files: FileSearchConfig::default(),
+            ocr: OcrConfig::default(),
+            max_result_chars: 4000,
+        }
+    }
+}
+
+/// REST search endpoint queried when a trigger phrase is said
+///
+/// "What's the service history for the Johnson account" with the trigger
+/// "service history for" and the ignored word "account" searches for "johnson".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LookupConfig {
+    /// Display name, also shown to the LLM with the results
+    pub name: String,
+
+    /// Phrases that run the lookup; the words after the phrase are the search term
+    pub triggers: Vec<String>,
+
+    /// Search URL; `{query}` is replaced with the URL-encoded search term
+    pub url: String,
+
+    /// Name of the keyring secret holding the token (empty sends no credentials)
+    pub secret: String,
+
+    /// Header the token is sent in
+    pub auth_header: String,
+
+    /// Text put before the token in the header (e.g., "Bearer ")
+    pub auth_prefix: String,
+
+    /// JSON pointer to the results in the response (e.g., "/data/items"; empty = whole response)
+    pub results_pointer: String,
+
+    /// Results passed to the LLM when the response is a list
+    pub max_results: usize,
+
+    /// Words dropped from the search term (e.g., "account", "customer")
+    pub ignore_words: Vec<String>,
+
+    /// Timeout in seconds
+    pub timeout_secs: u64,
+}
+
+impl Default for LookupConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            triggers: Vec::new(),
+            url: String::new(),
+            secret: String::new(),
+            auth_header: "Authorization".to_string(),
+            auth_prefix: "Bearer ".to_string(),
+            results_pointer: String::new(),
+            max_results: 5,
+            ignore_words: Vec::new(),
+            timeout_secs: 10,
+        }
+    }
+}
+
+/// Where calendar events are read from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CalendarSource {
+    /// An iCalendar (.ics) feed URL
+    #[default]
+    Ics,
+
+    /// A CalDAV calendar collection
+    #[serde(rename = "caldav")]
+    CalDav,
+
+    /// Microsoft Graph (Outlook / Microsoft 365) calendar view
+    Graph,
+}
+
+/// Calendar settings
+///
+/// Credentials are read from the keyring secret named `secret`: the password
+/// for basic authentication when `username` is set, otherwise a bearer token
+/// (an access token with `Calendars.Read` for Microsoft Graph).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CalendarConfig {
+    /// Read the calendar for `speak_today_schedule` and schedule questions
+    pub enabled: bool,
+
+    /// Kind of calendar `url` points to
+    pub source: CalendarSource,
+
+    /// ICS feed, CalDAV collection, or Graph calendar view URL
+    pub url: String,
+
+    /// User for basic authentication; empty sends the secret as a bearer token
+    pub username: String,
+
+    /// Name of the keyring secret holding the password or token (empty sends no credentials)
+    pub secret: String,
+
+    /// Phrases that add upcoming events to a query
+    pub triggers: Vec<String>,
+
+    /// Days of upcoming events sent with a query
+    pub days_ahead: u32,
+
+    /// Timeout in seconds
+    pub timeout_secs: u64,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            source: CalendarSource::default(),
+            url: String::new(),
+            username: String::new(),
+            secret: String::new(),
+            triggers: ["my schedule", "my calendar", "my meetings", "my appointments", "am i free"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            days_ahead: 7,
+            timeout_secs: 15,
+        }
+    }
+}
+
+/// Mailbox read over IMAP (implicit TLS) for questions about the inbox
+///
+/// Access is read-only: the mailbox is examined rather than selected and
+/// messages are never marked as read. The password (or app password) is read
+/// from the keyring secret named `secret`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmailConfig {
+    /// Read unread messages for inbox questions
+    pub enabled: bool,
+
+    /// IMAP server (e.g., "outlook.office365.com")
+    pub host: String,
+
+    /// IMAPS port
+    pub port: u16,
+
+    /// Login user, usually the email address
+    pub username: String,
+
+    /// Name of the keyring secret holding the password
+    pub secret: String,
+
+    /// Mailbox to read
+    pub mailbox: String,
+
+    /// Phrases that add unread messages to a query
+    pub triggers: Vec<String>,
+
+    /// Newest unread messages sent with a query
+    pub max_messages: usize,
+
+    /// Characters of each message's text sent with it
+    pub snippet_chars: usize,
+
+    /// Timeout in seconds for the whole IMAP session
+    pub timeout_secs: u64,
+}
+
+impl Default for EmailConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: String::new(),
+            port: 993,
+            username: String::new(),
+            secret: "email".to_string(),
+            mailbox: "INBOX".to_string(),
+            triggers: ["my inbox", "my email", "my emails", "my mail", "unread email", "unread emails", "new emails"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            max_messages: 10,
+            snippet_chars: 200,
+            timeout_secs: 15,
+        }
+    }
+}
+
+/// Text recognition with the Tesseract command-line tool
+///
+/// Tesseract is not bundled; it has to be installed with the language data
+/// for `languages`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OcrConfig {
+    /// Read the text of images sent with a message and send it along
+    pub enabled: bool,
+
+    /// Tesseract executable, found on the `PATH` unless a full path is given
+    pub tesseract_path: String,
+
+    /// Tesseract languages, joined with `+` (e.g., "eng+spa")
+    pub languages: String,
+
+    /// Timeout in seconds per image
+    pub timeout_secs: u64,
+}
+
+impl Default for OcrConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            tesseract_path: "tesseract".to_string(),
+            languages: "eng".to_string(),
+            timeout_secs: 30,
+        }
+    }
+}
+
+/// RSS and Atom feeds summarized by `give_news_briefing`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NewsConfig {
+    /// Feeds read, in order
+    pub feeds: Vec<NewsFeed>,
+
+    /// Newest items taken from each feed
+    pub items_per_feed: usize,
+
+    /// Items published longer ago than this are left out (hours, 0 = no limit)
+    pub max_age_hours: u64,
+
+    /// Instructions for the LLM summary; the answer language is added to them
+    pub prompt: String,
+
+    /// Timeout in seconds per feed
+    pub timeout_secs: u64,
+}
+
+impl Default for NewsConfig {
+    fn default() -> Self {
+        Self {
+            feeds: Vec::new(),
+            items_per_feed: 5,
+            max_age_hours: 24,
+            prompt: "Summarize these news items as a short spoken briefing of a few sentences. \
+                     Group related stories, say which source a story comes from when it helps, \
+                     and leave out links, lists, and formatting."
+                .to_string(),
+            timeout_secs: 15,
+        }
+    }
+}
+
+/// Summarize-clipboard and translate-clipboard actions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClipboardConfig {
+    /// Instructions for the summary; the answer language is added to them
+    pub summarize_prompt: String,
+
+    /// Language the clipboard is translated into (ISO 639-1 code, e.g., "en")
+    pub translate_to: String,
+
+    /// Speak the result as well as putting it on the clipboard
+    pub speak_results: bool,
+}
+
+impl Default for ClipboardConfig {
+    fn default() -> Self {
+        Self {
+            summarize_prompt: "Summarize the user's text in a few sentences, keeping names, numbers, \
+                               and anything the reader is asked to do."
+                .to_string(),
+            translate_to: "en".to_string(),
+            speak_results: true,
+        }
+    }
+}
+
+/// Low-bandwidth mode, for metered or slow connections
+///
+/// While enabled, recordings are uploaded compressed, ElevenLabs speech is requested
+/// at `tts_output_format`, answers are cut to `max_tokens`, streamed replies are
+/// synthesized one sentence at a time, and no follow-up suggestions or keep-alive
+/// requests are sent. The other settings are left as they are, so they return when
+/// the mode is turned off.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LowBandwidthConfig {
+    pub enabled: bool,
+
+    /// Most tokens generated per answer, spoken or not
+    pub max_tokens: usize,
+
+    /// ElevenLabs output format used instead of `elevenlabs.output_format`
+    pub tts_output_format: String,
+}
+
+impl Default for LowBandwidthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_tokens: 256,
+            tts_output_format: "mp3_22050_32".to_string(),
+        }
+    }
+}
+
+/// First-run setup
+///
+/// A fresh install starts with `completed` off, so the frontend can offer the
+/// setup wizard until `complete_setup` is called.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SetupConfig {
+    pub completed: bool,
+}
+
+impl SetupConfig {
+    /// Setup already done, for settings saved by versions without a wizard
+    fn completed() -> Self {
+        Self { completed: true }
+    }
+}
+
+/// Point in the pipeline where a hook runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookStage {
+    /// On each transcription, before it is shown or answered
+    PostTranscription,
+
+    /// On the user's message as sent to the LLM; the conversation keeps the original
+    PreLlm,
+
+    /// On the LLM's answer, before it is shown, stored, or spoken
+    PostLlm,
+
+    /// On text about to be spoken; what is shown is unchanged
+    PreTts,
+}
+
+/// How a hook transforms the text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookKind {
+    /// An external program, given the text on standard input; its standard output is the new text
+    #[default]
+    Command,
+
+    /// A Rhai script, given `text` and `stage`; its result is the new text (needs the `scripting` feature)
+    Rhai,
+}
+
+/// A hook rewriting text at one pipeline stage
+///
+/// A hook that fails, times out, or returns nothing leaves the text as it was.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookConfig {
+    /// Name used in the log
+    pub name: String,
+
+    #[serde(default = "default_hook_enabled")]
+    pub enabled: bool,
+
+    pub stage: HookStage,
+
+    #[serde(default)]
+    pub kind: HookKind,
+
+    /// Program run by a command hook, found on the `PATH` unless a full path is given
+    #[serde(default)]
+    pub program: String,
+
+    /// Arguments for the program
+    #[serde(default)]
+    pub args: Vec<String>,
+
+    /// Path of the `.rhai` file run by a script hook
+    #[serde(default)]
+    pub script: String,
+
+    /// Timeout in seconds
+    #[serde(default = "default_hook_timeout")]
+    pub timeout_secs: u64,
+}
+
+fn default_hook_enabled() -> bool {
+    true
+}
+
+fn default_hook_timeout() -> u64 {
+    5
+}
+
+/// A news feed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NewsFeed {
+    /// Source name given to the LLM (e.g., "Reuters")
+    pub name: String,
+
+    /// RSS or Atom feed URL
+    pub url: String,
+}
+
+/// Local document search
+///
+/// "Find the spec sheet for the model 4500" with the trigger "find the"
+/// searches `folders` for "spec sheet model 4500": file names are matched for
+/// every file, contents only for files with one of `text_extensions`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FileSearchConfig {
+    /// Search the folders when a query mentions a trigger phrase
+    pub enabled: bool,
+
+    /// Folders searched, including their subfolders
+    pub folders: Vec<String>,
+
+    /// Phrases that start a search; the words after the phrase are the search term
+    pub triggers: Vec<String>,
+
+    /// Extensions of files whose contents are searched (lowercase, without the dot)
+    pub text_extensions: Vec<String>,
+
+    /// Larger files are matched by name only
+    pub max_file_bytes: u64,
+
+    /// Files looked at per search, across all folders
+    pub max_files: usize,
+
+    /// Best matches sent with a query
+    pub max_results: usize,
+
+    /// Characters of matching text sent per file
+    pub snippet_chars: usize,
+}
+
+impl Default for FileSearchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            folders: Vec::new(),
+            triggers: ["find the", "find my", "search my documents for", "look in my files for"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            text_extensions: ["txt", "md", "csv", "json", "xml", "html", "htm", "log", "ini", "yaml", "yml"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            max_file_bytes: 2 * 1024 * 1024,
+            max_files: 10_000,
+            max_results: 5,
+            snippet_chars: 300,
+        }
+    }
+}
+
+/// Ticket system behind `create_ticket` and the "open a ticket for ..." voice command
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TicketSystem {
+    /// Jira issues, through the REST API v2
+    #[default]
+    Jira,
+
+    /// ServiceNow records, through the Table API
+    #[serde(rename = "servicenow")]
+    ServiceNow,
+}
+
+/// Ticket creation settings
+///
+/// The API token is stored in the keyring with `store_secret` under
+/// `token_secret`. With a `username`, requests use basic authentication
+/// (Jira Cloud takes the account email); without one, the token is sent as a
+/// bearer token (Jira personal access tokens, ServiceNow OAuth tokens).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TicketingConfig {
+    /// Open tickets by voice command and `create_ticket`
+    pub enabled: bool,
+
+    /// Which ticket system `base_url` points to
+    pub system: TicketSystem,
+
+    /// Instance URL (e.g., "https://example.atlassian.net"), without a trailing path
+    pub base_url: String,
+
+    /// Jira project key new issues are filed under (e.g., "OPS")
+    pub project_key: String,
+
+    /// Jira issue type of new issues
+    pub issue_type: String,
+
+    /// ServiceNow table new records are created in
+    pub table: String,
+
+    /// User for basic authentication; empty sends the token as a bearer token
+    pub username: String,
+
+    /// Name of the keyring secret holding the API token
+    pub token_secret: String,
+
+    /// Timeout in seconds
+    pub timeout_secs: u64,
+}
+
+impl Default for TicketingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            system: TicketSystem::default(),
+            base_url: String::new(),
+            project_key: String::new(),
+            issue_type: "Task".to_string(),
+            table: "incident".to_string(),
+            username: String::new(),
+            token_secret: "ticketing".to_string(),
+            timeout_secs: 15,
+        }
+    }
+}
+
+/// How a conversation is passed to human support
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HandoffMethod {
+    /// POST the whole package as JSON to `endpoint`
+    #[default]
+    Post,
+
+    /// Open a pre-filled email to `email`
+    Email,
+
+    /// Open `url` with the subject and summary filled in
+    Url,
+}
+
+/// Handoff to human support (see [`crate::handoff`])
+///
+/// A bearer token for `endpoint`, if it needs one, is stored in the keyring
+/// with `store_secret` under `token_secret`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HandoffConfig {
+    /// Allow `request_handoff`
+    pub enabled: bool,
+
+    pub method: HandoffMethod,
+
+    /// URL the package is POSTed to
+    pub endpoint: String,
+
+    /// Name of the keyring secret holding a bearer token for `endpoint`
+    pub token_secret: String,
+
+    /// Support address pre-filled emails are sent to
+    pub email: String,
+
+    /// Link opened with `{subject}` and `{body}` replaced, URL-encoded
+    /// (e.g., "https://support.example.com/new?title={subject}&details={body}")
+    pub url: String,
+
+    /// Include the saved recordings' audio in the POSTed package, base64-encoded
+    pub attach_audio: bool,
+
+    /// Timeout in seconds for the POST
+    pub timeout_secs: u64,
+}
+
+impl Default for HandoffConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            method: HandoffMethod::default(),
+            endpoint: String::new(),
+            token_secret: "handoff".to_string(),
+            email: String::new(),
+            url: String::new(),
+            attach_audio: false,
+            timeout_secs: 30,
+        }
+    }
+}
+
+fn default_rate_limit_cooldown_secs() -> u64 {
+    60
+}
+
+fn default_quota_cooldown_secs() -> u64 {
+    60 * 60
+}
+
+/// OpenTelemetry export
+///
+/// Sends traces and pipeline metrics to an OTLP/HTTP collector so many installs
+/// can be watched centrally. Requires a build with the `telemetry` feature, and
+/// changes take effect on the next launch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryConfig {
+    /// Export traces and metrics
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Collector base URL; `/v1/traces` and `/v1/metrics` are appended
+    #[serde(default = "default_telemetry_endpoint")]
+    pub endpoint: String,
+
+    /// Name identifying this install (defaults to the computer's host name)
+    #[serde(default)]
+    pub instance_id: Option<String>,
+
+    /// Seconds between metric exports
+    #[serde(default = "default_telemetry_export_interval_secs")]
+    pub export_interval_secs: u64,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: default_telemetry_endpoint(),
+            instance_id: None,
+            export_interval_secs: default_telemetry_export_interval_secs(),
+        }
+    }
+}
+
+fn default_telemetry_endpoint() -> String {
+    "http://localhost:4318".to_string()
+}
+
+fn default_telemetry_export_interval_secs() -> u64 {
+    60
+}
+
+/// Remote feature flags
+///
+/// Flags are read from `feature_flags.json` in the app directory and, if
+/// `remote_url` is set, from that URL, whose flags take precedence. See
+/// [`crate::feature_flags`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeatureFlagsConfig {
+    /// URL of a JSON object of flags (empty = local flags only)
+    pub remote_url: String,
+
+    /// Minutes between downloads of the remote flags
+    pub refresh_minutes: u64,
+}
+
+impl Default for FeatureFlagsConfig {
+    fn default() -> Self {
+        Self {
+            remote_url: String::new(),
+            refresh_minutes: 60,
+        }
+    }
+}
+
+/// Anonymous usage reports
+///
+/// Reports contain only counts of features used and error categories, never
+/// conversation content. Nothing is collected to disk or sent unless enabled.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageReportsConfig {
+    /// Queue and send usage reports
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// URL reports are posted to (reports stay queued while empty)
+    #[serde(default)]
+    pub endpoint: String,
+}
+
+/// UI preferences
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiConfig {
+    /// Theme ("light", "dark", or "system" to follow the operating system)
+    pub theme: String,
+
+    /// Show transcription in real-time
+    pub show_transcription: bool,
+
+    /// Show thinking indicator
+    pub show_thinking: bool,
+
+    /// Auto-minimize after response
+    pub auto_minimize: bool,
+
+    /// Window always on top
+    pub always_on_top: bool,
+
+    /// Language of text produced by the backend, such as spoken replies, errors, and tray labels ("en", "es")
+    #[serde(default = "default_locale")]
+    pub locale: String,
+
+    /// Global shortcut of each hotkey action (e.g. "CommandOrControl+Shift+Space")
+    #[serde(default = "default_hotkeys")]
+    pub hotkeys: BTreeMap<HotkeyAction, String>,
+
+    /// Former single recording hotkey, read from old config files into `hotkeys`
+    #[serde(default, skip_serializing)]
+    pub global_hotkey: Option<String>,
+
+    /// Former mute hotkey, read from old config files into `hotkeys`
+    #[serde(default, skip_serializing)]
+    pub mute_hotkey: Option<String>,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            theme: "dark".to_string(),
+            show_transcription: true,
+            show_thinking: true,
+            auto_minimize: false,
+            always_on_top: true,
+            locale: default_locale(),
+            hotkeys: default_hotkeys(),
+            global_hotkey: None,
+            mute_hotkey: None,
+        }
+    }
+}
+
+impl UiConfig {
+    /// Move the hotkeys of an old config file into `hotkeys`
+    fn migrate_hotkeys(&mut self) {
+        for (action, hotkey) in [
+            (HotkeyAction::ToggleRecord, self.global_hotkey.take()),
+            (HotkeyAction::Mute, self.mute_hotkey.take()),
+        ] {
+            if let Some(hotkey) = hotkey {
+                self.hotkeys.insert(action, hotkey);
+            }
+        }
+    }
+}
+
+/// Something a global hotkey can do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HotkeyAction {
+    /// Start or stop recording
+    ToggleRecord,
+
+    /// Record while the keys are held
+    PushToTalk,
+
+    /// Stop the reply being spoken
+    StopSpeaking,
+
+    /// Speak the last reply again
+    RepeatLast,
+
+    /// Show the window on top of other applications
+    ShowOverlay,
+
+    /// Toggle the microphone mute
+    Mute,
+
+    /// Open or close the quick-ask popup
+    QuickAsk,
+
+    /// Read the text selected in any application aloud
+    SpeakSelection,
+
+    /// Replace the clipboard's text with a summary of it
+    SummarizeClipboard,
+
+    /// Replace the clipboard's text with a translation of it
+    TranslateClipboard,
+}
+
+impl HotkeyAction {
+    pub const ALL: [HotkeyAction; 10] = [
+        HotkeyAction::ToggleRecord,
+        HotkeyAction::PushToTalk,
+        HotkeyAction::StopSpeaking,
+        HotkeyAction::RepeatLast,
+        HotkeyAction::ShowOverlay,
+        HotkeyAction::Mute,
+        HotkeyAction::QuickAsk,
+        HotkeyAction::SpeakSelection,
+        HotkeyAction::SummarizeClipboard,
+        HotkeyAction::TranslateClipboard,
+    ];
+
+    /// Name used in settings and events (e.g. "toggle_record")
+    pub fn name(self) -> &'static str {
+        match self {
+            HotkeyAction::ToggleRecord => "toggle_record",
+            HotkeyAction::PushToTalk => "push_to_talk",
+            HotkeyAction::StopSpeaking => "stop_speaking",
+            HotkeyAction::RepeatLast => "repeat_last",
+            HotkeyAction::ShowOverlay => "show_overlay",
+            HotkeyAction::Mute => "mute",
+            HotkeyAction::QuickAsk => "quick_ask",
+            HotkeyAction::SpeakSelection => "speak_selection",
+            HotkeyAction::SummarizeClipboard => "summarize_clipboard",
+            HotkeyAction::TranslateClipboard => "translate_clipboard",
+        }
+    }
+}
+
+fn default_locale() -> String {
+    crate::i18n::FALLBACK.to_string()
+}
+
+fn default_hotkeys() -> BTreeMap<HotkeyAction, String> {
+    BTreeMap::from([
+        (HotkeyAction::ToggleRecord, "CommandOrControl+Shift+Space".to_string()),
+        (HotkeyAction::Mute, "CommandOrControl+Shift+M".to_string()),
+    ])
 }
 
-/// ElevenLabs configuration
+/// Privacy and data retention settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ElevenLabsConfig {
-    /// ElevenLabs API endpoint
-    pub endpoint: String,
+pub struct PrivacyConfig {
+    /// How long conversation history and saved recordings are kept
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+
+    /// Encrypt stored conversation history at rest
+    #[serde(default = "default_encrypt_history")]
+    pub encrypt_history: bool,
+
+    /// Mask personal information before text is sent to remote services
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+
+    /// Lock the session after this many minutes without activity (0 = never)
+    ///
+    /// Only applies once a session PIN is set.
+    #[serde(default)]
+    pub auto_lock_minutes: u64,
+
+    /// Archive conversations idle for this many days (0 = never)
+    #[serde(default = "default_archive_after_days")]
+    pub archive_after_days: u32,
+
+    /// Record request bodies in the audit log of outbound API calls
+    ///
+    /// Off by default: the log then holds only metadata such as endpoints and sizes.
+    /// Bodies are never recorded while `encrypt_history` is on or in incognito.
+    #[serde(default)]
+    pub audit_log_content: bool,
+
+    /// How much of transcriptions and LLM replies appears in the log
+    #[serde(default)]
+    pub log_content: LogPrivacy,
+}
 
-    /// Voice ID to use
-    pub voice_id: String,
+impl Default for PrivacyConfig {
+    fn default() -> Self {
+        Self {
+            retention: RetentionPolicy::default(),
+            encrypt_history: default_encrypt_history(),
+            redaction: RedactionConfig::default(),
+            auto_lock_minutes: 0,
+            archive_after_days: default_archive_after_days(),
+            audit_log_content: false,
+            log_content: LogPrivacy::default(),
+        }
+    }
+}
 
-    /// Model ID (e.g., "eleven_monolingual_v1")
-    pub model_id: String,
+fn default_encrypt_history() -> bool {
+    true
+}
 
-    /// Voice settings
-    pub voice_settings: VoiceSettings,
+fn default_archive_after_days() -> u32 {
+    30
+}
 
-    /// Timeout in seconds
-    pub timeout_secs: u64,
+/// Category of personal information that can be redacted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiKind {
+    Email,
+    Phone,
+    Ssn,
+    CreditCard,
 }
 
-/// Voice synthesis settings
+/// PII redaction settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct VoiceSettings {
-    /// Stability (0.0-1.0)
-    pub stability: f32,
-
-    /// Similarity boost (0.0-1.0)
-    pub similarity_boost: f32,
+pub struct RedactionConfig {
+    /// Redact text before sending it to the LLM and TTS services
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Built-in categories to redact
+    #[serde(default = "default_pii_kinds")]
+    pub kinds: Vec<PiiKind>,
+
+    /// Additional regular expressions to redact (e.g., names or project codenames)
+    #[serde(default)]
+    pub custom_patterns: Vec<String>,
+}
 
-    /// Style (0.0-1.0)
-    pub style: Option<f32>,
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            kinds: default_pii_kinds(),
+            custom_patterns: Vec::new(),
+        }
+    }
+}
 
-    /// Use speaker boost
-    pub use_speaker_boost: bool,
+fn default_pii_kinds() -> Vec<PiiKind> {
+    vec![PiiKind::Email, PiiKind::Phone, PiiKind::Ssn, PiiKind::CreditCard]
 }
 
-/// Audio configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AudioConfig {
-    /// Sample rate for recording (Hz)
-    pub sample_rate: u32,
+/// Retention policy for conversation history and saved recordings
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum RetentionPolicy {
+    /// Keep data for this many days (0 = keep forever)
+    Days { days: u32 },
 
-    /// Bit depth
-    pub bit_depth: u16,
+    /// Keep only the most recent conversations
+    Conversations { count: usize },
 
-    /// Number of channels (1 = mono, 2 = stereo)
-    pub channels: u16,
+    /// Never persist history, and delete saved recordings
+    Nothing,
+}
 
-    /// Recording format
-    pub format: String,
+/// Conversations are ephemeral unless a retention policy that keeps history is chosen
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        RetentionPolicy::Nothing
+    }
+}
 
-    /// Silence detection threshold (0.0-1.0)
-    pub silence_threshold: f32,
+impl RetentionPolicy {
+    /// Whether conversations may be written to history at all
+    pub fn keeps_history(&self) -> bool {
+        !matches!(self, RetentionPolicy::Nothing | RetentionPolicy::Conversations { count: 0 })
+    }
+}
 
-    /// Silence duration before stopping (seconds)
-    pub silence_duration: f32,
+impl AppConfig {
+    /// A copy with one top-level section (e.g. "audio") updated from `patch`
+    ///
+    /// Objects in `patch` are merged into the current settings key by key, so
+    /// only the changed fields need to be sent; any other value, including
+    /// `null`, replaces the current one.
+    pub fn with_section(&self, section: &str, patch: serde_json::Value) -> AppResult<AppConfig> {
+        let mut config = serde_json::to_value(self).map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
+        let Some(current) = config.get_mut(section) else {
+            return Err(ConfigError::InvalidValue(format!("Unknown config section: {}", section)).into());
+        };
+        merge_json(current, patch);
 
-    /// Maximum recording duration (seconds)
-    pub max_duration: u32,
+        serde_json::from_value(config)
+            .map_err(|e| ConfigError::InvalidValue(format!("Invalid {} settings: {}", section, e)).into())
+    }
 }
 
-/// UI preferences
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct UiConfig {
-    /// Theme ("light" or "dark")
-    pub theme: String,
-
-    /// Show transcription in real-time
-    pub show_transcription: bool,
+/// Merge `patch` into `target`, recursing into objects
+fn merge_json(target: &mut serde_json::Value, patch: serde_json::Value) {
+    match (target, patch) {
+        (serde_json::Value::Object(target), serde_json::Value::Object(patch)) => {
+            for (key, value) in patch {
+                match target.get_mut(&key) {
+                    Some(current) => merge_json(current, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (target, patch) => *target = patch,
+    }
+}
 
-    /// Show thinking indicator
-    pub show_thinking: bool,
+/// A labeled API key for a service, e.g. a personal and a company ElevenLabs account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeySlot {
+    /// Service the key is for (as in `update_api_key`)
+    pub service: String,
 
-    /// Auto-minimize after response
-    pub auto_minimize: bool,
+    /// Identifier, unique per service
+    pub id: String,
 
-    /// Window always on top
-    pub always_on_top: bool,
+    /// Name shown in the settings
+    pub label: String,
 
-    /// Global hotkey
-    pub global_hotkey: Option<String>,
+    /// Use this key instead of the service's unnamed key unless a conversation picks another
+    #[serde(default)]
+    pub default: bool,
 }
 
-impl Default for AppConfig {
-    fn default() -> Self {
-        Self {
-            whisper: WhisperConfig {
-                endpoint: std::env::var("WHISPER_BASE_URL")
-                    .map(|url| format!("{}/audio/transcriptions", url))
-                    .unwrap_or_else(|_| "https://api.openai.com/v1/audio/transcriptions".to_string()),
-                model: std::env::var("WHISPER_MODEL")
-                    .unwrap_or_else(|_| "whisper-1".to_string()),
-                language: Some("en".to_string()),
-                temperature: 0.0,
-                timeout_secs: 30,
-            },
-            openwebui: OpenWebUiConfig {
-                endpoint: std::env::var("OPENWEBUI_BASE_URL")
-                    .map(|url| format!("{}/api/chat", url))
-                    .unwrap_or_else(|_| "http://localhost:3000/api/chat".to_string()),
-                model: std::env::var("OPENWEBUI_MODEL_NAME")
-                    .unwrap_or_else(|_| "llama3.2".to_string()),
-                max_context_length: 4096,
-                temperature: 0.7,
-                max_tokens: Some(1024),
-                stream: false,
-                timeout_secs: 60,
-            },
-            elevenlabs: ElevenLabsConfig {
-                endpoint: std::env::var("ELEVENLABS_BASE_URL")
-                    .map(|url| format!("{}/text-to-speech", url))
-                    .unwrap_or_else(|_| "https://api.elevenlabs.io/v1/text-to-speech".to_string()),
-                voice_id: std::env::var("ELEVENLABS_VOICE_ID")
-                    .unwrap_or_else(|_| "21m00Tcm4TlvDq8ikWAM".to_string()), // Default voice
-                model_id: "eleven_monolingual_v1".to_string(),
-                voice_settings: VoiceSettings {
-                    stability: 0.5,
-                    similarity_boost: 0.75,
-                    style: Some(0.0),
-                    use_speaker_boost: true,
-                },
-                timeout_secs: 30,
-            },
-            audio: AudioConfig {
-                sample_rate: 16000,
-                bit_depth: 16,
-                channels: 1,
-                format: "wav".to_string(),
-                silence_threshold: 0.01,
-                silence_duration: 2.0,
-                max_duration: 300,
-            },
-            ui: UiConfig {
-                theme: "dark".to_string(),
-                show_transcription: true,
-                show_thinking: true,
-                auto_minimize: false,
-                always_on_top: true,
-                global_hotkey: Some("CommandOrControl+Shift+Space".to_string()),
-            },
-        }
+impl KeySlot {
+    /// Keyring entry holding the key
+    pub fn secret_name(&self) -> String {
+        format!("{}.{}", self.service, self.id)
     }
 }
 
+/// Keyring service of the default profile; other profiles append their ID
+const KEYRING_SERVICE: &str = "com.cmac.talk-to-cmac";
+
 /// Configuration manager with secure key storage
 pub struct ConfigManager {
     config_path: PathBuf,
@@ -222,18 +2581,48 @@ pub struct ConfigManager {
 }
 
 impl ConfigManager {
-    /// Create a new configuration manager
+    /// Create a configuration manager for the active profile
     pub fn new() -> AppResult<Self> {
-        let config_dir = Self::get_config_dir()?;
+        let app_dir = Self::app_dir()?;
+        let profile = ProfileStore::new(&app_dir).active();
+        Self::for_profile(&app_dir, &profile)
+    }
+
+    /// Create a configuration manager for `profile` under the app data directory `app_dir`
+    ///
+    /// The default profile's data lives in `app_dir` itself; other profiles have
+    /// a subdirectory and a keyring service of their own.
+    pub fn for_profile(app_dir: &Path, profile: &str) -> AppResult<Self> {
+        let (config_dir, keyring_service) = if profile == DEFAULT_PROFILE {
+            (app_dir.to_path_buf(), KEYRING_SERVICE.to_string())
+        } else {
+            (app_dir.join("profiles").join(profile), format!("{}.{}", KEYRING_SERVICE, profile))
+        };
         fs::create_dir_all(&config_dir)
             .map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
 
         Ok(Self {
             config_path: config_dir.join("config.json"),
-            keyring_service: "com.cmac.talk-to-cmac".to_string(),
+            keyring_service,
         })
     }
 
+    /// App data directory shared by all profiles (profile list, managed `.env`)
+    pub fn app_dir() -> AppResult<PathBuf> {
+        let app_dir = Self::get_config_dir()?;
+        fs::create_dir_all(&app_dir)
+            .map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
+        Ok(app_dir)
+    }
+
+    /// Directory holding the profile's config file and other data (recordings, history)
+    pub fn data_dir(&self) -> PathBuf {
+        self.config_path
+            .parent()
+            .map(PathBuf::from)
+            .unwrap_or_default()
+    }
+
     /// Get the configuration directory
     fn get_config_dir() -> AppResult<PathBuf> {
         #[cfg(target_os = "macos")]
@@ -261,17 +2650,18 @@ impl ConfigManager {
     /// Load configuration from disk
     pub fn load(&self) -> AppResult<AppConfig> {
         if !self.config_path.exists() {
-            log::info!("Config file not found, using defaults");
+            tracing::info!("Config file not found, using defaults");
             return Ok(AppConfig::default());
         }
 
         let contents = fs::read_to_string(&self.config_path)
             .map_err(|e| ConfigError::LoadFailed(e.to_string()))?;
 
-        let config: AppConfig = serde_json::from_str(&contents)
+        let mut config: AppConfig = serde_json::from_str(&contents)
             .map_err(|e| ConfigError::ParseError(e.to_string()))?;
+        config.ui.migrate_hotkeys();
 
-        log::info!("Configuration loaded successfully");
+        tracing::info!("Configuration loaded successfully");
         Ok(config)
     }
 
@@ -283,30 +2673,55 @@ impl ConfigManager {
         fs::write(&self.config_path, contents)
             .map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
 
-        log::info!("Configuration saved successfully");
+        tracing::info!("Configuration saved successfully");
         Ok(())
     }
 
     /// Store API key securely in system keyring
     pub fn store_api_key(&self, service: &str, api_key: &str) -> AppResult<()> {
-        let entry = Entry::new(&self.keyring_service, service)
-            .map_err(|e| ConfigError::KeyringError(e.to_string()))?;
+        self.store_secret(service, api_key)
+    }
 
-        entry.set_password(api_key)
-            .map_err(|e| ConfigError::KeyringError(e.to_string()))?;
+    /// Retrieve API key from the environment or the system keyring
+    pub fn get_api_key(&self, service: &str) -> AppResult<String> {
+        match self.find_api_key(service) {
+            Ok(Some((key, source))) => {
+                match &source {
+                    KeySource::Environment { variable } => {
+                        tracing::info!("✓ Using {} from environment variable: {}", service, variable)
+                    }
+                    _ => tracing::info!("✓ Using {} from system keyring", service),
+                }
+                Ok(key)
+            }
+            result => {
+                let reason = result.err().map_or_else(|| "no entry".to_string(), |e| e.to_string());
+                tracing::error!("✗ No API key found for {} (tried env vars and keyring): {}", service, reason);
+                Err(AppError::Config(ConfigError::MissingConfig(format!(
+                    "API key not found for '{}'. Set {} environment variable or store via app settings.",
+                    service,
+                    api_key_env_var(service)
+                ))))
+            }
+        }
+    }
 
-        log::info!("API key stored for service: {}", service);
-        Ok(())
+    /// Where the API key for a service would be read from, without revealing it
+    pub fn api_key_source(&self, service: &str) -> KeySource {
+        match self.find_api_key(service) {
+            Ok(Some((_, source))) => source,
+            Ok(None) => KeySource::Missing,
+            Err(e) => KeySource::Unavailable { reason: e.to_string() },
+        }
     }
 
-    /// Retrieve API key from system keyring
-    pub fn get_api_key(&self, service: &str) -> AppResult<String> {
+    /// Look up an API key and its source, in priority order
+    fn find_api_key(&self, service: &str) -> AppResult<Option<(String, KeySource)>> {
         // PRIORITY 1: Check standard environment variables (from .env file or system)
-        let env_var_name = format!("{}_API_KEY", service.to_uppercase().replace('-', "_"));
+        let env_var_name = api_key_env_var(service);
         if let Ok(key) = std::env::var(&env_var_name) {
             if !key.is_empty() && key != "your-openai-api-key-here" && key != "your-openwebui-api-key-here" && key != "your-elevenlabs-api-key-here" {
-                log::info!("✓ Using {} from environment variable: {}", service, env_var_name);
-                return Ok(key);
+                return Ok(Some((key, KeySource::Environment { variable: env_var_name })));
             }
         }
 
@@ -315,48 +2730,163 @@ impl ConfigManager {
             "whisper" => vec!["OPENAI_API_KEY"],
             "openwebui" => vec!["OPENWEBUI_API_KEY"],
             "elevenlabs" => vec!["ELEVENLABS_API_KEY"],
+            "azure" => vec!["AZURE_OPENAI_API_KEY"],
+            "openrouter" => vec!["OPENROUTER_API_KEY"],
+            "gemini" => vec!["GEMINI_API_KEY", "GOOGLE_API_KEY"],
             _ => vec![],
         };
 
         for alt_name in alt_names {
             if let Ok(key) = std::env::var(alt_name) {
                 if !key.is_empty() && !key.contains("your-") && !key.contains("-api-key-here") {
-                    log::info!("✓ Using {} from environment variable: {}", service, alt_name);
-                    return Ok(key);
+                    return Ok(Some((key, KeySource::Environment { variable: alt_name.to_string() })));
                 }
             }
         }
 
         // PRIORITY 3: Try keyring
-        let entry = Entry::new(&self.keyring_service, service)
+        Ok(self.get_secret(service)?.map(|key| (key, KeySource::Keyring)))
+    }
+
+    /// Delete API key from system keyring
+    pub fn delete_api_key(&self, service: &str) -> AppResult<()> {
+        self.delete_secret(service)?;
+        Ok(())
+    }
+
+    /// Store a named secret (API key, webhook token, ...) in the system keyring
+    ///
+    /// Names are recorded in the data directory so they can be listed; values
+    /// never leave the keyring.
+    pub fn store_secret(&self, name: &str, value: &str) -> AppResult<()> {
+        validate_secret_name(name)?;
+        let entry = Entry::new(&self.keyring_service, name)
+            .map_err(|e| ConfigError::KeyringError(e.to_string()))?;
+
+        entry.set_password(value)
+            .map_err(|e| ConfigError::KeyringError(e.to_string()))?;
+
+        let mut names = read_secret_names(&self.secret_index_path())?;
+        if names.insert(name.to_string()) {
+            write_secret_names(&self.secret_index_path(), &names)?;
+        }
+
+        tracing::info!("Secret stored: {}", name);
+        Ok(())
+    }
+
+    /// Retrieve a named secret from the system keyring, if it is set
+    pub fn get_secret(&self, name: &str) -> AppResult<Option<String>> {
+        validate_secret_name(name)?;
+        let entry = Entry::new(&self.keyring_service, name)
+            .map_err(|e| ConfigError::KeyringError(e.to_string()))?;
+
+        match entry.get_password() {
+            Ok(value) => Ok(Some(value)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(ConfigError::KeyringError(e.to_string()).into()),
+        }
+    }
+
+    /// Delete a named secret, returning whether it existed
+    pub fn delete_secret(&self, name: &str) -> AppResult<bool> {
+        validate_secret_name(name)?;
+        let entry = Entry::new(&self.keyring_service, name)
+            .map_err(|e| ConfigError::KeyringError(e.to_string()))?;
+
+        let existed = match entry.delete_credential() {
+            Ok(()) => true,
+            Err(keyring::Error::NoEntry) => false,
+            Err(e) => return Err(ConfigError::KeyringError(e.to_string()).into()),
+        };
+
+        let mut names = read_secret_names(&self.secret_index_path())?;
+        if names.remove(name) {
+            write_secret_names(&self.secret_index_path(), &names)?;
+        }
+
+        tracing::info!("Secret deleted: {}", name);
+        Ok(existed)
+    }
+
+    /// Names of the secrets stored through this app, sorted
+    pub fn list_secret_names(&self) -> AppResult<Vec<String>> {
+        Ok(read_secret_names(&self.secret_index_path())?.into_iter().collect())
+    }
+
+    fn secret_index_path(&self) -> PathBuf {
+        self.data_dir().join("secret_names.json")
+    }
+
+    /// Get the history encryption key from the keyring, generating and storing one on first use
+    pub fn get_or_create_history_key(&self) -> AppResult<[u8; 32]> {
+        use base64::Engine;
+        let engine = base64::engine::general_purpose::STANDARD;
+
+        let entry = Entry::new(&self.keyring_service, HISTORY_KEY_ENTRY)
             .map_err(|e| ConfigError::KeyringError(e.to_string()))?;
 
         match entry.get_password() {
-            Ok(key) => {
-                log::info!("✓ Using {} from system keyring", service);
+            Ok(encoded) => engine
+                .decode(encoded)
+                .ok()
+                .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                .ok_or_else(|| ConfigError::KeyringError("Stored history key is invalid".to_string()).into()),
+            Err(keyring::Error::NoEntry) => {
+                let key = crate::crypto::StorageCipher::generate_key();
+                entry
+                    .set_password(&engine.encode(key))
+                    .map_err(|e| ConfigError::KeyringError(e.to_string()))?;
+                tracing::info!("Generated new history encryption key");
                 Ok(key)
             }
-            Err(e) => {
-                log::error!("✗ No API key found for {} (tried env vars and keyring): {}", service, e);
-                Err(AppError::Config(ConfigError::MissingConfig(format!(
-                    "API key not found for '{}'. Set {} environment variable or store via app settings.",
-                    service,
-                    env_var_name
-                ))))
-            }
+            Err(e) => Err(ConfigError::KeyringError(e.to_string()).into()),
         }
     }
 
-    /// Delete API key from system keyring
-    pub fn delete_api_key(&self, service: &str) -> AppResult<()> {
-        let entry = Entry::new(&self.keyring_service, service)
+    /// The hashed session PIN, if one is set
+    pub fn get_session_pin(&self) -> AppResult<Option<PinHash>> {
+        let entry = Entry::new(&self.keyring_service, SESSION_PIN_ENTRY)
             .map_err(|e| ConfigError::KeyringError(e.to_string()))?;
 
-        entry.delete_credential()
+        match entry.get_password() {
+            Ok(stored) => serde_json::from_str(&stored)
+                .map(Some)
+                .map_err(|_| ConfigError::KeyringError("Stored session PIN is invalid".to_string()).into()),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(ConfigError::KeyringError(e.to_string()).into()),
+        }
+    }
+
+    /// Store the hashed session PIN, or remove it with `None`
+    pub fn set_session_pin(&self, pin: Option<&PinHash>) -> AppResult<()> {
+        let entry = Entry::new(&self.keyring_service, SESSION_PIN_ENTRY)
             .map_err(|e| ConfigError::KeyringError(e.to_string()))?;
 
-        log::info!("API key deleted for service: {}", service);
-        Ok(())
+        let result = match pin {
+            Some(pin) => {
+                let stored = serde_json::to_string(pin).map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
+                entry.set_password(&stored)
+            }
+            None => match entry.delete_credential() {
+                Err(keyring::Error::NoEntry) => Ok(()),
+                result => result,
+            },
+        };
+        result.map_err(|e| ConfigError::KeyringError(e.to_string()).into())
+    }
+
+    /// Read the keys of the given slots from the keyring, skipping any that are not stored
+    pub fn load_slot_keys(&self, slots: &[KeySlot]) -> SlotKeys {
+        let mut keys = SlotKeys::default();
+        for slot in slots {
+            match self.get_secret(&slot.secret_name()) {
+                Ok(Some(key)) => keys.insert(slot, key),
+                Ok(None) => tracing::warn!("No key stored for {} slot '{}'", slot.service, slot.label),
+                Err(e) => tracing::warn!("Failed to read {} slot '{}': {}", slot.service, slot.label, e),
+            }
+        }
+        keys
     }
 
     /// Load configuration with API keys
@@ -366,17 +2896,180 @@ impl ConfigManager {
             whisper: self.get_api_key("whisper").ok(),
             openwebui: self.get_api_key("openwebui").ok(),
             elevenlabs: self.get_api_key("elevenlabs").ok(),
+            azure: self.get_api_key("azure").ok(),
+            openrouter: self.get_api_key("openrouter").ok(),
+            gemini: self.get_api_key("gemini").ok(),
         };
         Ok((config, keys))
     }
 }
 
+/// Services with an API key, as named in `update_api_key`
+pub const API_KEY_SERVICES: [&str; 6] = ["whisper", "openwebui", "elevenlabs", "azure", "openrouter", "gemini"];
+
+/// Where an API key is read from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum KeySource {
+    /// Environment variable (including `.env`), which takes priority over the keyring
+    Environment { variable: String },
+
+    /// System keyring
+    Keyring,
+
+    /// No key is set
+    Missing,
+
+    /// The keyring could not be read
+    Unavailable { reason: String },
+}
+
+/// Primary environment variable for a service's API key (e.g. `WHISPER_API_KEY`)
+fn api_key_env_var(service: &str) -> String {
+    format!("{}_API_KEY", service.to_uppercase().replace('-', "_"))
+}
+
+/// Keyring entry holding the history encryption key, managed separately from secrets
+const HISTORY_KEY_ENTRY: &str = "history-key";
+
+/// Keyring entry holding the hashed session PIN, managed separately from secrets
+const SESSION_PIN_ENTRY: &str = "session-pin";
+
+/// Longest accepted secret name
+const MAX_SECRET_NAME_LEN: usize = 64;
+
+/// Check that a secret name is 1-64 ASCII letters, digits, '.', '_', or '-'
+fn validate_secret_name(name: &str) -> AppResult<()> {
+    if name.is_empty() || name.len() > MAX_SECRET_NAME_LEN {
+        return Err(ConfigError::InvalidValue(format!(
+            "Secret names must be 1 to {} characters",
+            MAX_SECRET_NAME_LEN
+        ))
+        .into());
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-')) {
+        return Err(ConfigError::InvalidValue(format!(
+            "Secret name '{}' may only contain letters, digits, '.', '_', and '-'",
+            name
+        ))
+        .into());
+    }
+    if name == HISTORY_KEY_ENTRY || name == SESSION_PIN_ENTRY {
+        return Err(ConfigError::InvalidValue(format!("Secret name '{}' is reserved", name)).into());
+    }
+    Ok(())
+}
+
+fn read_secret_names(path: &Path) -> AppResult<BTreeSet<String>> {
+    if !path.exists() {
+        return Ok(BTreeSet::new());
+    }
+    let contents = fs::read_to_string(path).map_err(|e| ConfigError::LoadFailed(e.to_string()))?;
+    Ok(serde_json::from_str(&contents).map_err(|e| ConfigError::ParseError(e.to_string()))?)
+}
+
+fn write_secret_names(path: &Path, names: &BTreeSet<String>) -> AppResult<()> {
+    let contents = serde_json::to_string_pretty(names).map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
+    fs::write(path, contents).map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
+    Ok(())
+}
+
 /// API keys structure (not stored in config file)
-#[derive(Debug, Clone)]
+///
+/// Deliberately not `Serialize`, and `Debug` shows only which keys are set,
+/// so keys cannot end up in exports, crash reports, or logs.
+#[derive(Clone)]
 pub struct ApiKeys {
     pub whisper: Option<String>,
     pub openwebui: Option<String>,
     pub elevenlabs: Option<String>,
+    pub azure: Option<String>,
+    pub openrouter: Option<String>,
+    pub gemini: Option<String>,
+}
+
+impl ApiKeys {
+    /// No keys for any service
+    pub fn none() -> Self {
+        Self {
+            whisper: None,
+            openwebui: None,
+            elevenlabs: None,
+            azure: None,
+            openrouter: None,
+            gemini: None,
+        }
+    }
+
+    /// Key field of a service, by the name used in `update_api_key`
+    pub fn service_mut(&mut self, service: &str) -> Option<&mut Option<String>> {
+        match service {
+            "whisper" => Some(&mut self.whisper),
+            "openwebui" => Some(&mut self.openwebui),
+            "elevenlabs" => Some(&mut self.elevenlabs),
+            "azure" => Some(&mut self.azure),
+            "openrouter" => Some(&mut self.openrouter),
+            "gemini" => Some(&mut self.gemini),
+            _ => None,
+        }
+    }
+
+    /// Swap in slot keys: the conversation's choice for a service, else its default slot
+    pub fn with_slots(mut self, slots: &[KeySlot], slot_keys: &SlotKeys, selected: &BTreeMap<String, String>) -> Self {
+        for service in API_KEY_SERVICES {
+            let chosen = selected
+                .get(service)
+                .and_then(|id| slots.iter().find(|slot| slot.service == service && &slot.id == id))
+                .or_else(|| slots.iter().find(|slot| slot.service == service && slot.default));
+
+            if let Some(key) = chosen.and_then(|slot| slot_keys.get(slot)) {
+                if let Some(field) = self.service_mut(service) {
+                    *field = Some(key.to_string());
+                }
+            }
+        }
+        self
+    }
+}
+
+/// Keys of the configured key slots, loaded from the keyring
+///
+/// `Debug` lists only the slots, never the keys.
+#[derive(Clone, Default)]
+pub struct SlotKeys(HashMap<String, String>);
+
+impl SlotKeys {
+    pub fn insert(&mut self, slot: &KeySlot, key: String) {
+        self.0.insert(slot.secret_name(), key);
+    }
+
+    pub fn remove(&mut self, slot: &KeySlot) {
+        self.0.remove(&slot.secret_name());
+    }
+
+    pub fn get(&self, slot: &KeySlot) -> Option<&str> {
+        self.0.get(&slot.secret_name()).map(String::as_str)
+    }
+}
+
+impl std::fmt::Debug for SlotKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+impl std::fmt::Debug for ApiKeys {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let redact = |key: &Option<String>| key.as_ref().map(|_| "<redacted>");
+        f.debug_struct("ApiKeys")
+            .field("whisper", &redact(&self.whisper))
+            .field("openwebui", &redact(&self.openwebui))
+            .field("elevenlabs", &redact(&self.elevenlabs))
+            .field("azure", &redact(&self.azure))
+            .field("openrouter", &redact(&self.openrouter))
+            .field("gemini", &redact(&self.gemini))
+            .finish()
+    }
 }
 
 #[cfg(test)]
@@ -397,4 +3090,171 @@ mod tests {
         let deserialized: AppConfig = serde_json::from_str(&json).unwrap();
         assert_eq!(config.whisper.model, deserialized.whisper.model);
     }
+
+    #[test]
+    fn test_missing_fields_keep_the_rest() {
+        let config: AppConfig = serde_json::from_str(
+            r#"{"audio": {"sample_rate": 44100}, "ui": {"theme": "light"}, "whisper": {"model": "large"}}"#,
+        )
+        .unwrap();
+        assert_eq!(config.audio.sample_rate, 44100);
+        assert_eq!(config.audio.channels, 1);
+        assert_eq!(config.ui.theme, "light");
+        assert!(config.ui.always_on_top);
+        assert_eq!(config.whisper.model, "large");
+        assert_eq!(config.elevenlabs.voice_settings.stability, 0.5);
+        // Settings from before the setup wizard are not a first run; a fresh install is
+        assert!(config.setup.completed);
+        assert!(!AppConfig::default().setup.completed);
+    }
+
+    #[test]
+    fn test_pipeline_modes() {
+        let config: AppConfig = serde_json::from_str(r#"{"audio": {"pipeline_mode": "text_in_voice_out"}}"#).unwrap();
+        let mode = config.audio.pipeline_mode;
+        assert!(!mode.speech_in() && mode.speech_out());
+        assert!(PipelineMode::default().speech_in() && PipelineMode::default().speech_out());
+        assert!(PipelineMode::VoiceInTextOut.speech_in() && !PipelineMode::VoiceInTextOut.speech_out());
+        assert!(!PipelineMode::Text.speech_in() && !PipelineMode::Text.speech_out());
+    }
+
+    #[test]
+    fn test_section_update_merges_fields() {
+        let config = AppConfig::default();
+        let updated = config
+            .with_section("elevenlabs", serde_json::json!({"voice_id": "abc", "voice_settings": {"stability": 0.9}}))
+            .unwrap();
+        assert_eq!(updated.elevenlabs.voice_id, "abc");
+        assert_eq!(updated.elevenlabs.voice_settings.stability, 0.9);
+        assert_eq!(updated.elevenlabs.voice_settings.similarity_boost, 0.75);
+        assert_eq!(updated.whisper.model, config.whisper.model);
+
+        let updated = config.with_section("whisper", serde_json::json!({"language": null})).unwrap();
+        assert_eq!(updated.whisper.language, None);
+
+        assert!(config.with_section("nonsense", serde_json::json!({})).is_err());
+        assert!(config.with_section("audio", serde_json::json!({"sample_rate": "fast"})).is_err());
+    }
+
+    #[test]
+    fn test_voice_presets() {
+        let mut elevenlabs = ElevenLabsConfig::default();
+        elevenlabs.voice_settings.stability = 0.9;
+        elevenlabs.save_preset(" Calm ").unwrap();
+        assert_eq!(elevenlabs.active_preset().unwrap().name, "Calm");
+
+        elevenlabs.voice_id = "energetic-voice".to_string();
+        elevenlabs.voice_settings.stability = 0.2;
+        assert!(elevenlabs.active_preset().is_none());
+        elevenlabs.save_preset("Energetic").unwrap();
+        assert!(elevenlabs.save_preset("").is_err());
+
+        let calm = elevenlabs.apply_preset("calm").unwrap();
+        assert_eq!(elevenlabs.voice_id, calm.voice_id);
+        assert_eq!(elevenlabs.voice_settings.stability, 0.9);
+        assert!(elevenlabs.apply_preset("Whisper").is_err());
+
+        assert!(elevenlabs.delete_preset("CALM"));
+        assert!(!elevenlabs.delete_preset("Calm"));
+        assert_eq!(elevenlabs.presets.len(), 1);
+    }
+
+    #[test]
+    fn test_old_hotkeys_move_into_keymap() {
+        let mut ui = serde_json::to_value(AppConfig::default().ui).unwrap();
+        ui.as_object_mut().unwrap().remove("hotkeys");
+        ui["global_hotkey"] = "Alt+Space".into();
+        let mut ui: UiConfig = serde_json::from_value(ui).unwrap();
+
+        ui.migrate_hotkeys();
+        assert_eq!(ui.hotkeys[&HotkeyAction::ToggleRecord], "Alt+Space");
+        assert_eq!(ui.hotkeys[&HotkeyAction::Mute], "CommandOrControl+Shift+M");
+
+        let saved = serde_json::to_value(&ui).unwrap();
+        assert!(saved.get("global_hotkey").is_none());
+        assert_eq!(saved["hotkeys"]["toggle_record"], "Alt+Space");
+    }
+
+    #[test]
+    fn test_secret_names() {
+        assert!(validate_secret_name("whisper").is_ok());
+        assert!(validate_secret_name("slack-webhook.token_2").is_ok());
+        assert!(validate_secret_name("").is_err());
+        assert!(validate_secret_name("has space").is_err());
+        assert!(validate_secret_name(&"a".repeat(MAX_SECRET_NAME_LEN + 1)).is_err());
+        assert!(validate_secret_name(HISTORY_KEY_ENTRY).is_err());
+    }
+
+    #[test]
+    fn test_key_source_reports_environment_without_the_key() {
+        let manager = ConfigManager::new().unwrap();
+        assert_eq!(manager.api_key_source("key-status-test"), KeySource::Missing);
+
+        std::env::set_var("KEY_STATUS_TEST_API_KEY", "sk-secret");
+        let source = manager.api_key_source("key-status-test");
+        std::env::remove_var("KEY_STATUS_TEST_API_KEY");
+        assert_eq!(source, KeySource::Environment { variable: "KEY_STATUS_TEST_API_KEY".to_string() });
+        assert!(!serde_json::to_string(&source).unwrap().contains("sk-secret"));
+    }
+
+    #[test]
+    fn test_api_keys_debug_is_redacted() {
+        let keys = ApiKeys {
+            whisper: Some("sk-secret".to_string()),
+            openwebui: None,
+            elevenlabs: None,
+            azure: None,
+            openrouter: None,
+            gemini: None,
+        };
+        let debug = format!("{:?}", keys);
+        assert!(!debug.contains("sk-secret"));
+        assert!(debug.contains("whisper: Some(\"<redacted>\")"));
+    }
+
+    #[test]
+    fn test_key_slots_follow_conversation_then_default() {
+        let slot = |id: &str, default| KeySlot {
+            service: "elevenlabs".to_string(),
+            id: id.to_string(),
+            label: id.to_string(),
+            default,
+        };
+        let slots = vec![slot("personal", false), slot("company", true)];
+        let mut slot_keys = SlotKeys::default();
+        slot_keys.insert(&slots[0], "personal-key".to_string());
+        slot_keys.insert(&slots[1], "company-key".to_string());
+        let base = ApiKeys {
+            whisper: Some("whisper-key".to_string()),
+            openwebui: None,
+            elevenlabs: Some("unnamed-key".to_string()),
+            azure: None,
+            openrouter: None,
+            gemini: None,
+        };
+
+        let keys = base.clone().with_slots(&slots, &slot_keys, &BTreeMap::new());
+        assert_eq!(keys.elevenlabs.as_deref(), Some("company-key"));
+        assert_eq!(keys.whisper.as_deref(), Some("whisper-key"));
+
+        let selected = BTreeMap::from([("elevenlabs".to_string(), "personal".to_string())]);
+        let keys = base.clone().with_slots(&slots, &slot_keys, &selected);
+        assert_eq!(keys.elevenlabs.as_deref(), Some("personal-key"));
+
+        // A slot whose key is not stored falls back to the unnamed key
+        let keys = base.with_slots(&slots, &SlotKeys::default(), &selected);
+        assert_eq!(keys.elevenlabs.as_deref(), Some("unnamed-key"));
+        assert!(!format!("{:?}", slot_keys).contains("personal-key"));
+    }
+
+    #[test]
+    fn test_secret_index_round_trip() {
+        let path = std::env::temp_dir().join(format!("talk-to-cmac-secrets-{:x}.json", rand::random::<u64>()));
+        assert!(read_secret_names(&path).unwrap().is_empty());
+
+        let names: BTreeSet<String> = ["webhook", "gemini"].into_iter().map(String::from).collect();
+        write_secret_names(&path, &names).unwrap();
+        assert_eq!(read_secret_names(&path).unwrap().into_iter().collect::<Vec<_>>(), ["gemini", "webhook"]);
+        fs::remove_file(path).unwrap();
+    }
 }
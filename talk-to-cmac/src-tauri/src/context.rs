@@ -0,0 +1,211 @@
+//! Context selection
+//!
+//! Each query is normally sent with the latest 20 messages, so in a long
+//! session that hops between topics the messages about the current one may
+//! already be gone. With `context.semantic` on, a longer history is kept and a
+//! query is sent with the latest `recent_messages` plus the `relevant_messages`
+//! older ones whose embeddings are most similar to the query's. Picked messages
+//! keep their order, and system messages are always sent.
+//!
+//! Embeddings are cached by model and text, so each message is embedded once
+//! however many queries it is compared with. If the LLM provider cannot embed
+//! text, the latest messages are sent instead.
+
+use crate::api::provider::LlmProvider;
+use crate::config::ContextConfig;
+use crate::error::{AppError, AppResult};
+use crate::speaker::similarity;
+use crate::usage;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Mutex, OnceLock};
+
+/// Characters of a message that are embedded; the start says what a long reply is about
+const MAX_EMBEDDED_CHARS: usize = 2000;
+
+/// Embeddings cached before the cache is cleared
+const MAX_CACHED: usize = 5000;
+
+fn cache() -> &'static Mutex<HashMap<u64, Vec<f32>>> {
+    static CACHE: OnceLock<Mutex<HashMap<u64, Vec<f32>>>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+/// Messages to send for the query that ends `messages`
+///
+/// `messages` are (role, content) tuples as from `AppState::get_api_messages`:
+/// system messages first, then the conversation, ending with the query.
+pub async fn select(
+    llm: &dyn LlmProvider,
+    config: &ContextConfig,
+    messages: Vec<(String, String)>,
+) -> Vec<(String, String)> {
+    let pinned = messages.iter().take_while(|(role, _)| role == "system").count();
+    let budget = config.relevant_messages + config.recent_messages.max(1);
+    if !config.semantic || messages.len() - pinned <= budget {
+        return messages;
+    }
+
+    let split = messages.len() - config.recent_messages.max(1);
+    let query = &messages[messages.len() - 1].1;
+    let texts: Vec<&str> = std::iter::once(query.as_str())
+        .chain(messages[pinned..split].iter().map(|(_, content)| content.as_str()))
+        .collect();
+
+    let picked = match embed_cached(llm, &config.embedding_model, &texts).await {
+        Ok(embeddings) => {
+            usage::record_feature("semantic_context");
+            let picked = most_similar(&embeddings[0], &embeddings[1..], config.relevant_messages);
+            tracing::debug!("Sending {} of {} older messages, picked by similarity", picked.len(), split - pinned);
+            picked.into_iter().map(|index| pinned + index).collect()
+        }
+        Err(e) => {
+            tracing::warn!("Failed to embed messages, sending the latest ones instead: {}", e);
+            (messages.len() - budget..split).collect::<Vec<_>>()
+        }
+    };
+
+    let mut selected = messages[..pinned].to_vec();
+    selected.extend(picked.into_iter().map(|index| messages[index].clone()));
+    selected.extend_from_slice(&messages[split..]);
+    selected
+}
+
+/// Indices of the `count` candidates most similar to `query`, in ascending order
+fn most_similar(query: &[f32], candidates: &[Vec<f32>], count: usize) -> Vec<usize> {
+    let mut ranked: Vec<(usize, f32)> = candidates
+        .iter()
+        .map(|candidate| similarity(query, candidate))
+        .enumerate()
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let mut picked: Vec<usize> = ranked.into_iter().take(count).map(|(index, _)| index).collect();
+    picked.sort_unstable();
+    picked
+}
+
+/// Embeddings of `texts`, asking the provider only for those not cached yet
+async fn embed_cached(llm: &dyn LlmProvider, model: &str, texts: &[&str]) -> AppResult<Vec<Vec<f32>>> {
+    let texts: Vec<String> = texts.iter().map(|text| text.chars().take(MAX_EMBEDDED_CHARS).collect()).collect();
+    let keys: Vec<u64> = texts.iter().map(|text| cache_key(model, text)).collect();
+
+    let mut embeddings: Vec<Option<Vec<f32>>> = {
+        let cache = cache().lock().unwrap();
+        keys.iter().map(|key| cache.get(key).cloned()).collect()
+    };
+    let missing: Vec<usize> = (0..texts.len()).filter(|index| embeddings[*index].is_none()).collect();
+    if missing.is_empty() {
+        return Ok(embeddings.into_iter().flatten().collect());
+    }
+
+    let inputs: Vec<String> = missing.iter().map(|index| texts[*index].clone()).collect();
+    let created = llm.embed(&inputs, model).await?;
+    if created.len() != inputs.len() {
+        return Err(AppError::Generic(format!("Expected {} embeddings, got {}", inputs.len(), created.len())));
+    }
+
+    let mut cache = cache().lock().unwrap();
+    if cache.len() + created.len() > MAX_CACHED {
+        cache.clear();
+    }
+    for (index, embedding) in missing.into_iter().zip(created) {
+        cache.insert(keys[index], embedding.clone());
+        embeddings[index] = Some(embedding);
+    }
+    Ok(embeddings.into_iter().flatten().collect())
+}
+
+fn cache_key(model: &str, text: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (model, text).hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::MockClient;
+    use crate::config::MockConfig;
+
+    fn conversation(turns: &[(&str, &str)]) -> Vec<(String, String)> {
+        std::iter::once(("system".to_string(), "You are CMAC.".to_string()))
+            .chain(turns.iter().flat_map(|(question, answer)| {
+                [("user".to_string(), question.to_string()), ("assistant".to_string(), answer.to_string())]
+            }))
+            .collect()
+    }
+
+    fn config() -> ContextConfig {
+        ContextConfig {
+            semantic: true,
+            relevant_messages: 2,
+            recent_messages: 1,
+            ..ContextConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_most_similar_keeps_order() {
+        let candidates = vec![vec![1.0, 0.0], vec![0.0, 1.0], vec![0.7, 0.7], vec![-1.0, 0.0]];
+        assert_eq!(most_similar(&[1.0, 0.1], &candidates, 2), vec![0, 2]);
+        assert_eq!(most_similar(&[1.0, 0.1], &candidates, 10), vec![0, 1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_relevant_messages_are_picked() {
+        let mut messages = conversation(&[
+            ("How hot is the sourdough oven?", "Bake sourdough at 250 degrees."),
+            ("Who won the chess tournament?", "The chess tournament went to Ding."),
+            ("Any news on the football league?", "The football league resumes Sunday."),
+        ]);
+        messages.push(("user".to_string(), "How long should sourdough bake in the oven?".to_string()));
+        let llm = MockClient::new(MockConfig::default());
+
+        let selected = select(&llm, &config(), messages.clone()).await;
+        let contents: Vec<&str> = selected.iter().map(|(_, content)| content.as_str()).collect();
+        assert_eq!(
+            contents,
+            [
+                "You are CMAC.",
+                "How hot is the sourdough oven?",
+                "Bake sourdough at 250 degrees.",
+                "How long should sourdough bake in the oven?",
+            ]
+        );
+
+        let off = ContextConfig { semantic: false, ..config() };
+        assert_eq!(select(&llm, &off, messages).await.len(), 8);
+    }
+
+    #[tokio::test]
+    async fn test_latest_messages_are_sent_without_embeddings() {
+        struct NoEmbeddings;
+
+        #[async_trait::async_trait]
+        impl LlmProvider for NoEmbeddings {
+            async fn send_message_with(
+                &self,
+                _messages: Vec<(String, String)>,
+                _overrides: &crate::api::openwebui::GenerationOverrides,
+            ) -> AppResult<crate::api::openwebui::LlmReply> {
+                unreachable!()
+            }
+
+            async fn list_models(&self) -> AppResult<Vec<crate::api::openwebui::ModelInfo>> {
+                unreachable!()
+            }
+
+            async fn check_connectivity(&self) -> AppResult<bool> {
+                unreachable!()
+            }
+        }
+
+        let mut messages = conversation(&[("One?", "1"), ("Two?", "2")]);
+        messages.push(("user".to_string(), "Three?".to_string()));
+        let selected = select(&NoEmbeddings, &config(), messages).await;
+        let contents: Vec<&str> = selected.iter().map(|(_, content)| content.as_str()).collect();
+        assert_eq!(contents, ["You are CMAC.", "Two?", "2", "Three?"]);
+    }
+}
@@ -0,0 +1,292 @@
+//! Crash handling
+//!
+//! A panic hook writes a crash report (message, backtrace, app status, and the
+//! last pipeline stage reached) to the app data directory and saves the current
+//! conversation before aborting. On the next launch the conversation is
+//! restored and the report is available through `get_last_crash_report`.
+//!
+//! The hook runs on the panicking thread, possibly while the state lock is
+//! held, so it never waits on a lock and leaves out anything it cannot read.
+//!
+//! With `privacy.encrypt_history` on, the saved conversation is encrypted with
+//! the history key, which is read when the hook is installed since the keyring
+//! cannot be trusted mid-panic. Without a key it is not saved at all.
+
+use crate::config::AppConfig;
+use crate::crypto::{self, StorageCipher};
+use crate::state::{AppState, AppStatus, ConversationContext};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Crash report file in the data directory
+const REPORT_FILE: &str = "crash_report.json";
+
+/// Conversation saved at the crash, restored on the next launch
+const SESSION_FILE: &str = "crash_session.json";
+
+/// Pipeline stage most recently started
+static LAST_STAGE: Mutex<Option<&'static str>> = Mutex::new(None);
+
+/// What was known about the app when it crashed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReport {
+    /// Unix timestamp of the crash
+    pub timestamp: u64,
+
+    /// Version of the app that crashed
+    pub app_version: String,
+
+    /// Panic message
+    pub message: String,
+
+    /// Source file and line of the panic
+    pub location: Option<String>,
+
+    /// Name of the panicking thread
+    pub thread: Option<String>,
+
+    /// Stack trace of the panicking thread
+    pub backtrace: String,
+
+    /// App status at the time (absent if the state could not be read)
+    pub status: Option<AppStatus>,
+
+    /// Pipeline stage most recently started ("stt", "llm", or "tts")
+    pub last_stage: Option<String>,
+
+    /// Whether the conversation was saved and restored on the next launch
+    pub conversation_saved: bool,
+}
+
+/// Crash report left by the previous run, taken at launch
+pub struct LastCrash(pub Option<CrashReport>);
+
+/// Note the pipeline stage a request has reached
+pub fn enter_stage(stage: &'static str) {
+    if let Ok(mut last) = LAST_STAGE.lock() {
+        *last = Some(stage);
+    }
+}
+
+/// Forget the stage of the previous request, as a new one starts
+pub fn reset_stage() {
+    if let Ok(mut last) = LAST_STAGE.lock() {
+        *last = None;
+    }
+}
+
+/// Pipeline stage most recently started, if any since the last reset
+pub fn last_stage() -> Option<&'static str> {
+    LAST_STAGE.lock().ok().and_then(|last| *last)
+}
+
+/// Install the panic hook that records crashes in `data_dir`
+///
+/// `cipher` encrypts the saved conversation when history encryption is on.
+pub fn install(state: AppState, data_dir: PathBuf, cipher: Option<StorageCipher>) {
+    let default_hook = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        let snapshot = state.try_snapshot();
+        let conversation_saved = match &snapshot {
+            Some((_, conversation, config)) => save_conversation(&data_dir, conversation, config, cipher.as_ref()),
+            None => false,
+        };
+
+        let report = CrashReport {
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            message: panic_message(info.payload()),
+            location: info.location().map(|location| location.to_string()),
+            thread: std::thread::current().name().map(str::to_string),
+            backtrace: Backtrace::force_capture().to_string(),
+            status: snapshot.map(|(status, ..)| status),
+            last_stage: LAST_STAGE.try_lock().ok().and_then(|stage| stage.map(str::to_string)),
+            conversation_saved,
+        };
+        let written = serde_json::to_string_pretty(&report)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| fs::write(data_dir.join(REPORT_FILE), contents).map_err(|e| e.to_string()));
+
+        default_hook(info);
+        match written {
+            Ok(()) => eprintln!("Crash report written to {}", data_dir.join(REPORT_FILE).display()),
+            Err(e) => eprintln!("Failed to write crash report: {}", e),
+        }
+        std::process::abort();
+    }));
+}
+
+/// Take the previous run's crash report, restoring the conversation it saved
+///
+/// An encrypted conversation is dropped if `cipher` cannot decrypt it.
+pub fn recover(state: &AppState, data_dir: &Path, cipher: Option<&StorageCipher>) -> Option<CrashReport> {
+    let report_path = data_dir.join(REPORT_FILE);
+    let session_path = data_dir.join(SESSION_FILE);
+
+    let report: Option<CrashReport> = read_json(&report_path);
+    if let Some(conversation) = read_session(&session_path, cipher) {
+        state.restore_conversation(conversation);
+    }
+
+    for path in [report_path, session_path] {
+        if path.exists() {
+            if let Err(e) = fs::remove_file(&path) {
+                tracing::warn!("Failed to remove {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    if let Some(report) = &report {
+        tracing::warn!("Recovered from a crash: {}", report.message);
+    }
+    report
+}
+
+/// Save the conversation unless it is incognito, history is not kept, or it would have to be
+/// encrypted without a key
+fn save_conversation(
+    data_dir: &Path,
+    conversation: &ConversationContext,
+    config: &AppConfig,
+    cipher: Option<&StorageCipher>,
+) -> bool {
+    if conversation.ephemeral || conversation.messages.is_empty() || !config.privacy.retention.keeps_history() {
+        return false;
+    }
+
+    let Ok(contents) = serde_json::to_string(conversation) else {
+        return false;
+    };
+    let contents = match (config.privacy.encrypt_history, cipher) {
+        (false, _) => contents,
+        (true, Some(cipher)) => match cipher.encrypt(&contents) {
+            Ok(encrypted) => encrypted,
+            Err(_) => return false,
+        },
+        (true, None) => return false,
+    };
+    fs::write(data_dir.join(SESSION_FILE), contents).is_ok()
+}
+
+/// Conversation saved at the crash, decrypted if it was encrypted
+fn read_session(path: &Path, cipher: Option<&StorageCipher>) -> Option<ConversationContext> {
+    let contents = fs::read_to_string(path).ok()?;
+    let contents = if crypto::is_encrypted(&contents) {
+        let Some(cipher) = cipher else {
+            tracing::warn!("Ignoring encrypted {}: no history key", path.display());
+            return None;
+        };
+        cipher
+            .decrypt(&contents)
+            .inspect_err(|e| tracing::warn!("Ignoring unreadable {}: {}", path.display(), e))
+            .ok()?
+    } else {
+        contents
+    };
+    serde_json::from_str(&contents)
+        .inspect_err(|e| tracing::warn!("Ignoring unreadable {}: {}", path.display(), e))
+        .ok()
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Option<T> {
+    let contents = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents)
+        .inspect_err(|e| tracing::warn!("Ignoring unreadable {}: {}", path.display(), e))
+        .ok()
+}
+
+/// Text of a panic payload (`panic!` produces either a `&str` or a `String`)
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{ApiKeys, RetentionPolicy};
+    use crate::state::MessageRole;
+
+    fn state() -> AppState {
+        AppState::new(AppConfig::default(), ApiKeys::none())
+    }
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("talk-to-cmac-crash-{:x}", rand::random::<u64>()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_panic_messages() {
+        assert_eq!(panic_message(&"static message"), "static message");
+        assert_eq!(panic_message(&"owned message".to_string()), "owned message");
+        assert_eq!(panic_message(&42), "unknown panic");
+    }
+
+    #[test]
+    fn test_conversation_restored_after_crash() {
+        let dir = temp_dir();
+        let crashed = state();
+        let mut config = crashed.get_config();
+        config.privacy.retention = RetentionPolicy::Days { days: 30 };
+        crashed.update_config(config);
+        crashed.add_message(MessageRole::User, "What's the weather?".to_string());
+        let cipher = StorageCipher::from_key(&StorageCipher::generate_key());
+        let (_, conversation, config) = crashed.try_snapshot().unwrap();
+        assert!(save_conversation(&dir, &conversation, &config, Some(&cipher)));
+        let saved = fs::read_to_string(dir.join(SESSION_FILE)).unwrap();
+        assert!(crypto::is_encrypted(&saved) && !saved.contains("weather"));
+        fs::write(dir.join(REPORT_FILE), r#"{"timestamp":1,"app_version":"0.1.0","message":"boom",
+            "location":null,"thread":"main","backtrace":"","status":"thinking","last_stage":"llm",
+            "conversation_saved":true}"#).unwrap();
+
+        let relaunched = state();
+        let report = recover(&relaunched, &dir, Some(&cipher)).expect("report is recovered");
+        assert_eq!(report.last_stage.as_deref(), Some("llm"));
+        assert_eq!(relaunched.get_conversation().id, conversation.id);
+        assert_eq!(relaunched.get_conversation().messages.len(), 1);
+
+        // Both files are consumed, so the next launch starts clean
+        assert!(recover(&state(), &dir, Some(&cipher)).is_none());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_incognito_conversations_are_not_saved() {
+        let dir = temp_dir();
+        let session = state();
+        session.start_incognito();
+        session.add_message(MessageRole::User, "Private".to_string());
+
+        let (_, conversation, config) = session.try_snapshot().unwrap();
+        assert!(!save_conversation(&dir, &conversation, &config, None));
+        assert!(!dir.join(SESSION_FILE).exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_encrypted_history_is_not_saved_without_key() {
+        let dir = temp_dir();
+        let session = state();
+        let mut config = session.get_config();
+        config.privacy.retention = RetentionPolicy::Days { days: 30 };
+        session.update_config(config);
+        session.add_message(MessageRole::User, "Private".to_string());
+
+        let (_, conversation, config) = session.try_snapshot().unwrap();
+        assert!(config.privacy.encrypt_history);
+        assert!(!save_conversation(&dir, &conversation, &config, None));
+        assert!(!dir.join(SESSION_FILE).exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
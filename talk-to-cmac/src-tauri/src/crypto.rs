@@ -0,0 +1,176 @@
+//! At-rest encryption for stored conversation text
+//!
+//! Message contents are encrypted field by field with AES-256-GCM. The key is
+//! either a random key held in the system keyring or, when the user sets a
+//! passphrase, derived from that passphrase with Argon2. PINs (for profiles and
+//! the session lock) are kept only as salted Argon2 hashes.
+
+use crate::error::{AppResult, ConfigError, StorageError};
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+/// Prefix marking a stored value as encrypted (version 1 format)
+const ENCRYPTED_PREFIX: &str = "enc1:";
+
+/// Length of the AES-GCM nonce in bytes
+const NONCE_LEN: usize = 12;
+
+/// Cipher used to encrypt and decrypt stored text
+#[derive(Clone)]
+pub struct StorageCipher {
+    cipher: Aes256Gcm,
+}
+
+impl StorageCipher {
+    /// Create a cipher from a raw 256-bit key
+    pub fn from_key(key: &[u8; 32]) -> Self {
+        Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)),
+        }
+    }
+
+    /// Derive a cipher from a passphrase and salt using Argon2
+    pub fn from_passphrase(passphrase: &str, salt: &[u8]) -> AppResult<Self> {
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| StorageError::EncryptionFailed(e.to_string()))?;
+        Ok(Self::from_key(&key))
+    }
+
+    /// Generate a new random 256-bit key
+    pub fn generate_key() -> [u8; 32] {
+        rand::random()
+    }
+
+    /// Generate a new random salt for passphrase derivation
+    pub fn generate_salt() -> [u8; 16] {
+        rand::random()
+    }
+
+    /// Encrypt text into a self-describing string (`enc1:` + base64 of nonce and ciphertext)
+    pub fn encrypt(&self, plaintext: &str) -> AppResult<String> {
+        let nonce_bytes: [u8; NONCE_LEN] = rand::random();
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce_bytes), plaintext.as_bytes())
+            .map_err(|e| StorageError::EncryptionFailed(e.to_string()))?;
+
+        let mut payload = nonce_bytes.to_vec();
+        payload.extend_from_slice(&ciphertext);
+        Ok(format!("{}{}", ENCRYPTED_PREFIX, BASE64.encode(payload)))
+    }
+
+    /// Decrypt a value produced by [`StorageCipher::encrypt`]
+    ///
+    /// Values without the encryption prefix were written before encryption was
+    /// enabled and are returned unchanged.
+    pub fn decrypt(&self, stored: &str) -> AppResult<String> {
+        let Some(encoded) = stored.strip_prefix(ENCRYPTED_PREFIX) else {
+            return Ok(stored.to_string());
+        };
+
+        let payload = BASE64
+            .decode(encoded)
+            .map_err(|e| StorageError::DecryptionFailed(e.to_string()))?;
+        if payload.len() < NONCE_LEN {
+            return Err(StorageError::DecryptionFailed("Ciphertext too short".to_string()).into());
+        }
+
+        let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| StorageError::DecryptionFailed("Wrong key or corrupted data".to_string()))?;
+
+        String::from_utf8(plaintext).map_err(|e| StorageError::DecryptionFailed(e.to_string()).into())
+    }
+}
+
+/// Whether a stored value is encrypted
+pub fn is_encrypted(stored: &str) -> bool {
+    stored.starts_with(ENCRYPTED_PREFIX)
+}
+
+/// Argon2 hash of a PIN with its salt, both base64
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PinHash {
+    salt: String,
+    hash: String,
+}
+
+impl PinHash {
+    /// Hash a PIN of 4 to 12 digits
+    pub fn new(pin: &str) -> AppResult<Self> {
+        if !(4..=12).contains(&pin.len()) || !pin.chars().all(|c| c.is_ascii_digit()) {
+            return Err(ConfigError::InvalidValue("A PIN must be 4 to 12 digits".to_string()).into());
+        }
+        let salt = StorageCipher::generate_salt();
+        Ok(Self {
+            salt: BASE64.encode(salt),
+            hash: BASE64.encode(derive_pin(pin, &salt)?),
+        })
+    }
+
+    /// Whether `pin` is the hashed PIN
+    pub fn matches(&self, pin: &str) -> AppResult<bool> {
+        let salt = BASE64.decode(&self.salt).map_err(|e| ConfigError::ParseError(e.to_string()))?;
+        Ok(BASE64.encode(derive_pin(pin, &salt)?) == self.hash)
+    }
+}
+
+fn derive_pin(pin: &str, salt: &[u8]) -> AppResult<[u8; 32]> {
+    let mut hash = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(pin.as_bytes(), salt, &mut hash)
+        .map_err(|e| StorageError::EncryptionFailed(e.to_string()))?;
+    Ok(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let cipher = StorageCipher::from_key(&StorageCipher::generate_key());
+        let encrypted = cipher.encrypt("What's my bank balance?").unwrap();
+
+        assert!(is_encrypted(&encrypted));
+        assert!(!encrypted.contains("bank"));
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), "What's my bank balance?");
+    }
+
+    #[test]
+    fn test_wrong_key_fails_and_plaintext_passes_through() {
+        let cipher = StorageCipher::from_key(&StorageCipher::generate_key());
+        let other = StorageCipher::from_key(&StorageCipher::generate_key());
+        let encrypted = cipher.encrypt("secret").unwrap();
+
+        assert!(other.decrypt(&encrypted).is_err());
+        assert_eq!(other.decrypt("legacy plaintext").unwrap(), "legacy plaintext");
+    }
+
+    #[test]
+    fn test_passphrase_derivation_is_deterministic() {
+        let salt = StorageCipher::generate_salt();
+        let a = StorageCipher::from_passphrase("correct horse", &salt).unwrap();
+        let b = StorageCipher::from_passphrase("correct horse", &salt).unwrap();
+
+        let encrypted = a.encrypt("hello").unwrap();
+        assert_eq!(b.decrypt(&encrypted).unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_pin_hash() {
+        let hash = PinHash::new("4821").unwrap();
+        assert!(hash.matches("4821").unwrap());
+        assert!(!hash.matches("4822").unwrap());
+        assert!(PinHash::new("12").is_err());
+        assert!(PinHash::new("12ab").is_err());
+    }
+}
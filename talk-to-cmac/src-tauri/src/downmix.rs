@@ -0,0 +1,81 @@
+//! Input channel selection
+//!
+//! Stereo audio interfaces often carry the microphone on only one channel, so
+//! a recording uploaded as it is would be half silence. `audio.input_channel`
+//! picks the channel to transcribe, or mixes the channels down to mono, before
+//! the recording is sent anywhere. Mono recordings, and anything but 16-bit
+//! PCM WAV, are left as they are.
+
+use crate::config::{AudioConfig, InputChannel};
+use crate::wav;
+
+/// `wav` reduced to the channel `config` selects; unchanged if it keeps every channel
+pub fn apply(config: &AudioConfig, wav: Vec<u8>) -> Vec<u8> {
+    if config.input_channel == InputChannel::All {
+        return wav;
+    }
+
+    let (channels, sample_rate) = match wav::decode_channels(&wav) {
+        Ok(decoded) if decoded.0.len() > 1 => decoded,
+        Ok(_) => return wav,
+        Err(e) => {
+            tracing::debug!("Not selecting a channel of the recording: {}", e);
+            return wav;
+        }
+    };
+
+    let samples = select(config.input_channel, channels);
+    wav::encode(&samples, sample_rate)
+}
+
+/// Samples of the `selection` of `channels` (at least two)
+fn select(selection: InputChannel, mut channels: Vec<Vec<f32>>) -> Vec<f32> {
+    let index = match selection {
+        InputChannel::All | InputChannel::Mix => return wav::mix(&channels),
+        InputChannel::Left => 0,
+        InputChannel::Right => 1,
+        InputChannel::Loudest => {
+            let energy = |channel: &Vec<f32>| channel.iter().map(|sample| sample * sample).sum::<f32>();
+            let loudest = (0..channels.len())
+                .max_by(|a, b| energy(&channels[*a]).total_cmp(&energy(&channels[*b])))
+                .unwrap_or(0);
+            tracing::debug!("Transcribing channel {} of {}, the loudest", loudest + 1, channels.len());
+            loudest
+        }
+    };
+    channels.swap_remove(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stereo() -> Vec<Vec<f32>> {
+        vec![vec![0.0, 0.0, 0.0], vec![0.4, -0.4, 0.2]]
+    }
+
+    #[test]
+    fn test_select_channel() {
+        assert_eq!(select(InputChannel::Left, stereo()), [0.0, 0.0, 0.0]);
+        assert_eq!(select(InputChannel::Right, stereo()), [0.4, -0.4, 0.2]);
+        assert_eq!(select(InputChannel::Loudest, stereo()), [0.4, -0.4, 0.2]);
+        assert_eq!(select(InputChannel::Mix, stereo()), [0.2, -0.2, 0.1]);
+    }
+
+    #[test]
+    fn test_apply_leaves_mono_and_unselected_audio() {
+        let mono = wav::encode(&[0.1, 0.2], 16_000);
+        let config = AudioConfig {
+            input_channel: InputChannel::Right,
+            ..AudioConfig::default()
+        };
+        assert_eq!(apply(&config, mono.clone()), mono);
+        assert_eq!(apply(&config, b"not audio".to_vec()), b"not audio");
+
+        let stereo = wav::encode_channels(&[&[0.0], &[0.5]], 16_000);
+        assert_eq!(apply(&AudioConfig::default(), stereo.clone()), stereo);
+        let (samples, _) = wav::decode(&apply(&config, stereo)).unwrap();
+        assert_eq!(samples.len(), 1);
+        assert!((samples[0] - 0.5).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,174 @@
+//! System audio ducking
+//!
+//! Lowers the volume of other applications while the assistant is speaking and
+//! restores their original levels afterwards. On Windows this uses the Core Audio
+//! session API (ISimpleAudioVolume); on other platforms ducking is a no-op.
+
+use crate::error::AppResult;
+use std::sync::Mutex;
+
+/// Volume level of another application's audio session before it was ducked
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+struct SavedVolume {
+    /// Process owning the audio session
+    pid: u32,
+
+    /// Original session volume (0.0-1.0)
+    volume: f32,
+}
+
+/// Tracks which audio sessions were ducked so they can be restored
+#[derive(Default)]
+pub struct AudioDucker {
+    saved: Mutex<Option<Vec<SavedVolume>>>,
+}
+
+impl AudioDucker {
+    /// Create a new ducker with nothing ducked
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lower every other application's audio session to `level` times its current volume
+    ///
+    /// Calling this while already ducked is a no-op, so overlapping playback
+    /// notifications never compound the reduction.
+    pub fn duck(&self, level: f32) -> AppResult<()> {
+        let mut saved = self.saved.lock().unwrap();
+        if saved.is_some() {
+            return Ok(());
+        }
+
+        let level = level.clamp(0.0, 1.0);
+        let sessions = platform::duck_sessions(level)?;
+        tracing::info!("Ducked {} audio session(s) to {:.0}%", sessions.len(), level * 100.0);
+        *saved = Some(sessions);
+        Ok(())
+    }
+
+    /// Restore the volumes saved by the last call to [`AudioDucker::duck`]
+    pub fn restore(&self) -> AppResult<()> {
+        let Some(sessions) = self.saved.lock().unwrap().take() else {
+            return Ok(());
+        };
+
+        platform::restore_sessions(&sessions)?;
+        tracing::info!("Restored {} audio session(s)", sessions.len());
+        Ok(())
+    }
+
+    /// Whether other applications are currently ducked
+    pub fn is_ducked(&self) -> bool {
+        self.saved.lock().unwrap().is_some()
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::SavedVolume;
+    use crate::error::{AppResult, AudioError};
+    use windows::core::Interface;
+    use windows::Win32::Media::Audio::{
+        eMultimedia, eRender, IAudioSessionControl2, IAudioSessionManager2, IMMDeviceEnumerator,
+        ISimpleAudioVolume, MMDeviceEnumerator,
+    };
+    use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_ALL, COINIT_MULTITHREADED};
+
+    /// Visit every audio session on the default output device that belongs to another process
+    fn for_each_session(
+        mut visit: impl FnMut(u32, &ISimpleAudioVolume) -> windows::core::Result<()>,
+    ) -> windows::core::Result<()> {
+        let own_pid = std::process::id();
+
+        unsafe {
+            // Already-initialized threads return S_FALSE or RPC_E_CHANGED_MODE, both fine here
+            let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+
+            let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)?;
+            let device = enumerator.GetDefaultAudioEndpoint(eRender, eMultimedia)?;
+            let manager: IAudioSessionManager2 = device.Activate(CLSCTX_ALL, None)?;
+            let sessions = manager.GetSessionEnumerator()?;
+
+            for index in 0..sessions.GetCount()? {
+                let control = sessions.GetSession(index)?;
+                let control2: IAudioSessionControl2 = control.cast()?;
+                let pid = control2.GetProcessId()?;
+
+                // Skip our own playback and the shared system-sounds session
+                if pid == own_pid || pid == 0 {
+                    continue;
+                }
+
+                let volume: ISimpleAudioVolume = control.cast()?;
+                visit(pid, &volume)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn duck_sessions(level: f32) -> AppResult<Vec<SavedVolume>> {
+        let mut saved = Vec::new();
+
+        for_each_session(|pid, volume| unsafe {
+            let original = volume.GetMasterVolume()?;
+            volume.SetMasterVolume(original * level, std::ptr::null())?;
+            saved.push(SavedVolume { pid, volume: original });
+            Ok(())
+        })
+        .map_err(|e| AudioError::DeviceError(format!("Failed to duck system audio: {}", e)))?;
+
+        Ok(saved)
+    }
+
+    pub fn restore_sessions(saved: &[SavedVolume]) -> AppResult<()> {
+        for_each_session(|pid, volume| unsafe {
+            // Sessions started while ducked were never lowered, so leave them alone
+            if let Some(entry) = saved.iter().find(|s| s.pid == pid) {
+                volume.SetMasterVolume(entry.volume, std::ptr::null())?;
+            }
+            Ok(())
+        })
+        .map_err(|e| AudioError::DeviceError(format!("Failed to restore system audio: {}", e)).into())
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use super::SavedVolume;
+    use crate::error::AppResult;
+
+    pub fn duck_sessions(_level: f32) -> AppResult<Vec<SavedVolume>> {
+        tracing::debug!("System audio ducking is only supported on Windows");
+        Ok(Vec::new())
+    }
+
+    pub fn restore_sessions(_saved: &[SavedVolume]) -> AppResult<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn test_duck_and_restore_toggle_state() {
+        let ducker = AudioDucker::new();
+        assert!(!ducker.is_ducked());
+
+        ducker.duck(0.2).unwrap();
+        assert!(ducker.is_ducked());
+
+        ducker.restore().unwrap();
+        assert!(!ducker.is_ducked());
+    }
+
+    #[test]
+    fn test_restore_without_duck_is_noop() {
+        let ducker = AudioDucker::new();
+        assert!(ducker.restore().is_ok());
+    }
+}
@@ -0,0 +1,276 @@
+//! Email summaries
+//!
+//! Unread messages are read from the mailbox set in `tools.email` (see
+//! [`crate::api::imap`]) for questions about the inbox. Only the sender,
+//! subject, date, and the start of the text are kept; the text is taken from
+//! the plain-text part when there is one, otherwise from the HTML stripped of
+//! its tags.
+
+use crate::api::imap::ImapClient;
+use crate::config::{ConfigManager, EmailConfig};
+use crate::error::{AppResult, EmailError};
+use crate::html;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Local};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// Deepest nesting of multipart bodies searched for text
+const MAX_MIME_DEPTH: usize = 3;
+
+/// An unread message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmailSummary {
+    /// Sender's display name, or address when it has none
+    pub from: String,
+
+    /// Subject line
+    pub subject: String,
+
+    /// When the message was sent, as given in its `Date` header
+    pub date: String,
+
+    /// Start of the message text, on one line
+    pub snippet: String,
+}
+
+/// The newest unread messages in the configured mailbox, newest first
+pub async fn unread(config: &EmailConfig) -> AppResult<Vec<EmailSummary>> {
+    if !config.enabled {
+        return Err(EmailError::NotConfigured("email is disabled").into());
+    }
+
+    let password = ConfigManager::new()?
+        .get_secret(&config.secret)?
+        .ok_or(EmailError::NotConfigured("IMAP password"))?;
+    ImapClient::new(config.clone(), password).unread().await
+}
+
+/// Summarize a message from its header fields and the start of its body
+pub fn summarize(headers: &[u8], body: &[u8], snippet_chars: usize) -> EmailSummary {
+    let headers = parse_headers(&String::from_utf8_lossy(headers));
+    let text = body_text(&headers, body, 0).unwrap_or_default();
+
+    // Quoted replies repeat older messages
+    let text = text
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('>'))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let snippet = match text.char_indices().nth(snippet_chars) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text,
+    };
+
+    EmailSummary {
+        from: display_name(&decode_words(header(&headers, "from"))),
+        subject: decode_words(header(&headers, "subject")),
+        date: header(&headers, "date").to_string(),
+        snippet,
+    }
+}
+
+/// Messages listed for the LLM, with local send times
+pub fn describe(messages: &[EmailSummary]) -> String {
+    if messages.is_empty() {
+        return "No unread messages.".to_string();
+    }
+
+    messages
+        .iter()
+        .map(|message| {
+            let date = DateTime::parse_from_rfc2822(&message.date)
+                .map(|date| date.with_timezone(&Local).format("%a %Y-%m-%d %H:%M").to_string())
+                .unwrap_or_else(|_| message.date.clone());
+            format!(
+                "- From {} ({}): {}\n  {}",
+                message.from, date, message.subject, message.snippet
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Header fields with lowercase names, continuation lines unfolded
+fn parse_headers(text: &str) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+    }
+    headers
+}
+
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> &'a str {
+    headers
+        .iter()
+        .find(|(field, _)| field == name)
+        .map(|(_, value)| value.as_str())
+        .unwrap_or_default()
+}
+
+/// Value of the `name` parameter of a header such as `Content-Type`
+fn param<'a>(value: &'a str, name: &str) -> Option<&'a str> {
+    value.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        key.trim().eq_ignore_ascii_case(name).then_some(value.trim().trim_matches('"'))
+    })
+}
+
+/// Readable text of a body with the given headers, preferring plain text over HTML
+fn body_text(headers: &[(String, String)], body: &[u8], depth: usize) -> Option<String> {
+    let content_type = header(headers, "content-type");
+    let mime = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+
+    if mime.starts_with("multipart/") {
+        let boundary = param(content_type, "boundary").filter(|_| depth < MAX_MIME_DEPTH)?;
+        let body = String::from_utf8_lossy(body).replace("\r\n", "\n");
+        let parts: Vec<(Vec<(String, String)>, &str)> = body
+            .split(&format!("--{}", boundary))
+            .skip(1)
+            .filter(|part| !part.starts_with("--"))
+            .map(|part| {
+                let part = part.strip_prefix('\n').unwrap_or(part);
+                let (headers, body) = match part.strip_prefix('\n') {
+                    Some(body) => ("", body),
+                    None => part.split_once("\n\n").unwrap_or((part, "")),
+                };
+                (parse_headers(headers), body)
+            })
+            .collect();
+
+        let is_html = |headers: &[(String, String)]| {
+            header(headers, "content-type").to_ascii_lowercase().starts_with("text/html")
+        };
+        return parts
+            .iter()
+            .filter(|(headers, _)| !is_html(headers))
+            .chain(parts.iter().filter(|(headers, _)| is_html(headers)))
+            .find_map(|(headers, body)| body_text(headers, body.as_bytes(), depth + 1));
+    }
+
+    if !(mime.is_empty() || mime.starts_with("text/")) {
+        return None;
+    }
+    let decoded = match header(headers, "content-transfer-encoding").to_ascii_lowercase().as_str() {
+        "base64" => {
+            // The body may be cut off mid-way, so only whole groups are decoded
+            let mut encoded: String = String::from_utf8_lossy(body).split_whitespace().collect();
+            encoded.truncate(encoded.len() / 4 * 4);
+            BASE64.decode(encoded).unwrap_or_default()
+        }
+        "quoted-printable" => decode_quoted_printable(body),
+        _ => body.to_vec(),
+    };
+    let text = decode_charset(&decoded, param(content_type, "charset").unwrap_or("utf-8"));
+
+    Some(if mime == "text/html" { html::to_text(&text) } else { text })
+}
+
+/// Decode quoted-printable text, dropping soft line breaks
+fn decode_quoted_printable(body: &[u8]) -> Vec<u8> {
+    let hex = |byte: u8| (byte as char).to_digit(16);
+    let mut decoded = Vec::with_capacity(body.len());
+    let mut i = 0;
+    while i < body.len() {
+        match &body[i..] {
+            [b'=', b'\r', b'\n', ..] => i += 3,
+            [b'=', b'\n', ..] => i += 2,
+            [b'=', high, low, ..] if hex(*high).is_some() && hex(*low).is_some() => {
+                decoded.push((hex(*high).unwrap_or(0) * 16 + hex(*low).unwrap_or(0)) as u8);
+                i += 3;
+            }
+            [byte, ..] => {
+                decoded.push(*byte);
+                i += 1;
+            }
+            [] => break,
+        }
+    }
+    decoded
+}
+
+/// Text of `bytes` in `charset`; Latin-1 is read as such, anything else as UTF-8
+fn decode_charset(bytes: &[u8], charset: &str) -> String {
+    match charset.to_ascii_lowercase().as_str() {
+        "iso-8859-1" | "latin1" | "windows-1252" => bytes.iter().map(|&byte| byte as char).collect(),
+        _ => String::from_utf8_lossy(bytes).into_owned(),
+    }
+}
+
+/// Decode RFC 2047 encoded words (`=?UTF-8?Q?...?=`) in a header value
+fn decode_words(value: &str) -> String {
+    static ADJACENT: OnceLock<Regex> = OnceLock::new();
+    static WORD: OnceLock<Regex> = OnceLock::new();
+    let adjacent = ADJACENT.get_or_init(|| Regex::new(r"\?=\s+=\?").expect("adjacent word pattern is valid"));
+    let word = WORD.get_or_init(|| Regex::new(r"=\?([^?]+)\?([bBqQ])\?([^?]*)\?=").expect("encoded word pattern is valid"));
+
+    // Whitespace between encoded words is not part of the text
+    let value = adjacent.replace_all(value, "?==?");
+    word.replace_all(&value, |caps: &regex::Captures| {
+        let bytes = if caps[2].eq_ignore_ascii_case("b") {
+            BASE64.decode(&caps[3]).unwrap_or_default()
+        } else {
+            decode_quoted_printable(caps[3].replace('_', " ").as_bytes())
+        };
+        decode_charset(&bytes, &caps[1])
+    })
+    .into_owned()
+}
+
+/// Display name of a `From` value, or its address when it has none
+fn display_name(from: &str) -> String {
+    match from.split_once('<') {
+        Some((name, _)) if !name.trim().trim_matches('"').is_empty() => name.trim().trim_matches('"').to_string(),
+        Some((_, address)) => address.trim_end_matches('>').trim().to_string(),
+        None => from.trim().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_part_of_multipart_message_is_summarized() {
+        let headers = b"From: \"Dispatch\" <dispatch@example.com>\r\n\
+            Subject: =?UTF-8?B?VXJnZW50Og==?= =?UTF-8?Q?_site_visit?=\r\n\
+            Date: Fri, 16 Oct 2026 08:12:00 -0500\r\n\
+            Content-Type: multipart/alternative; boundary=\"b1\"\r\n\r\n";
+        let body = b"--b1\r\n\
+            Content-Type: text/html\r\n\r\n\
+            <p>Ignored</p>\r\n\
+            --b1\r\n\
+            Content-Type: text/plain; charset=utf-8\r\n\
+            Content-Transfer-Encoding: quoted-printable\r\n\r\n\
+            Please confirm the visit=\r\n at 9 a.m.=E2=80=94thanks.\r\n\
+            > earlier message\r\n\
+            --b1--\r\n";
+
+        let summary = summarize(headers, body, 200);
+        assert_eq!(summary.from, "Dispatch");
+        assert_eq!(summary.subject, "Urgent: site visit");
+        assert_eq!(summary.date, "Fri, 16 Oct 2026 08:12:00 -0500");
+        assert_eq!(summary.snippet, "Please confirm the visit at 9 a.m.\u{2014}thanks.");
+    }
+
+    #[test]
+    fn test_html_only_message_is_stripped_and_cut() {
+        let headers = b"From: alerts@example.com\r\nContent-Type: text/html\r\n\r\n";
+        let body = b"<html><head><style>p { color: red; }</style></head><body><p>Unit&nbsp;4500 is offline</p></body></html>";
+
+        let summary = summarize(headers, body, 12);
+        assert_eq!(summary.from, "alerts@example.com");
+        assert_eq!(summary.snippet, "Unit 4500 is...");
+    }
+}
@@ -0,0 +1,217 @@
+//! Managed environment overrides
+//!
+//! A `.env` file in the app data directory, edited from the settings UI, so
+//! developers can switch between test and production keys without touching
+//! the system environment. It is loaded at launch before the project `.env`,
+//! so its values win over that file but not over variables already set in the
+//! process environment.
+//!
+//! Only variables the app reads can be set: API keys (`*_API_KEY`), the
+//! endpoint and model defaults, `CMAC_*` switches, and `RUST_LOG`.
+
+use crate::config::ConfigManager;
+use crate::error::{AppResult, ConfigError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Variables read by the app besides `*_API_KEY` and `CMAC_*`
+const KNOWN_VARIABLES: &[&str] = &[
+    "WHISPER_BASE_URL",
+    "WHISPER_MODEL",
+    "OPENWEBUI_BASE_URL",
+    "OPENWEBUI_MODEL_NAME",
+    "ELEVENLABS_BASE_URL",
+    "ELEVENLABS_VOICE_ID",
+    "RUST_LOG",
+];
+
+/// Longest accepted value
+const MAX_VALUE_LEN: usize = 4096;
+
+/// Header written at the top of the managed file
+const HEADER: &str = "# Managed by Talk to CMAC. Edit from the settings, not by hand.\n";
+
+/// A managed variable, with its value masked
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvOverride {
+    pub name: String,
+
+    /// Value with all but the last four characters hidden ("••••abcd")
+    pub masked_value: String,
+
+    /// Whether the process environment currently holds this value
+    ///
+    /// False when a variable set outside the app took priority.
+    pub active: bool,
+}
+
+/// The managed `.env` file
+pub struct EnvOverrides {
+    path: PathBuf,
+}
+
+impl EnvOverrides {
+    /// Overrides stored in the given file
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Overrides in the app data directory, shared by all profiles
+    pub fn open_default() -> AppResult<Self> {
+        Ok(Self::new(ConfigManager::app_dir()?.join(".env")))
+    }
+
+    /// Load the managed variables into the process environment
+    ///
+    /// Variables that are already set keep their values.
+    pub fn apply(&self) -> AppResult<usize> {
+        let overrides = self.load()?;
+        let mut applied = 0;
+        for (name, value) in overrides {
+            if std::env::var_os(&name).is_none() {
+                std::env::set_var(name, value);
+                applied += 1;
+            }
+        }
+        Ok(applied)
+    }
+
+    /// Managed variables by name
+    pub fn load(&self) -> AppResult<BTreeMap<String, String>> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        dotenvy::from_path_iter(&self.path)
+            .and_then(|iter| iter.collect())
+            .map_err(|e| ConfigError::ParseError(format!("{}: {}", self.path.display(), e)).into())
+    }
+
+    /// Managed variables with masked values
+    pub fn list(&self) -> AppResult<Vec<EnvOverride>> {
+        Ok(self
+            .load()?
+            .into_iter()
+            .map(|(name, value)| EnvOverride {
+                active: std::env::var(&name).is_ok_and(|current| current == value),
+                masked_value: mask(&value),
+                name,
+            })
+            .collect())
+    }
+
+    /// Set a variable, or remove it with `None`, updating the file and the process environment
+    pub fn set(&self, name: &str, value: Option<&str>) -> AppResult<()> {
+        validate_name(name)?;
+        if let Some(value) = value {
+            validate_value(value)?;
+        }
+
+        let mut overrides = self.load()?;
+        match value {
+            Some(value) => {
+                overrides.insert(name.to_string(), value.to_string());
+                std::env::set_var(name, value);
+            }
+            None => {
+                if overrides.remove(name).is_some() {
+                    std::env::remove_var(name);
+                }
+            }
+        }
+        write(&self.path, &overrides)
+    }
+}
+
+fn write(path: &Path, overrides: &BTreeMap<String, String>) -> AppResult<()> {
+    let mut contents = HEADER.to_string();
+    for (name, value) in overrides {
+        let escaped = value.replace('\\', "\\\\").replace('"', "\\\"").replace('$', "\\$");
+        contents.push_str(&format!("{}=\"{}\"\n", name, escaped));
+    }
+    fs::write(path, contents).map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
+    Ok(())
+}
+
+/// Accept only variables the app reads, so the file cannot change `PATH` and the like
+fn validate_name(name: &str) -> AppResult<()> {
+    let well_formed = name.starts_with(|c: char| c.is_ascii_uppercase())
+        && name.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+    let known = name.ends_with("_API_KEY") || name.starts_with("CMAC_") || KNOWN_VARIABLES.contains(&name);
+
+    if !well_formed || !known {
+        return Err(ConfigError::InvalidValue(format!(
+            "{} is not a variable the app reads (use *_API_KEY, CMAC_*, or a known endpoint or model variable)",
+            name
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+fn validate_value(value: &str) -> AppResult<()> {
+    if value.trim().is_empty() {
+        return Err(ConfigError::InvalidValue("Value is empty; remove the variable instead".to_string()).into());
+    }
+    if value.len() > MAX_VALUE_LEN || value.contains(['\n', '\r', '\0']) {
+        return Err(ConfigError::InvalidValue(format!(
+            "Values must be a single line of at most {} characters",
+            MAX_VALUE_LEN
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+fn mask(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() < 12 {
+        return "••••".to_string();
+    }
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("••••{}", tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_known_variables_are_accepted() {
+        assert!(validate_name("OPENROUTER_API_KEY").is_ok());
+        assert!(validate_name("CMAC_MOCK_PROVIDERS").is_ok());
+        assert!(validate_name("WHISPER_BASE_URL").is_ok());
+        assert!(validate_name("PATH").is_err());
+        assert!(validate_name("LD_PRELOAD").is_err());
+        assert!(validate_name("lower_API_KEY").is_err());
+        assert!(validate_value("two\nlines").is_err());
+        assert!(validate_value("  ").is_err());
+    }
+
+    #[test]
+    fn test_values_round_trip_through_the_file() {
+        let path = std::env::temp_dir().join(format!("talk-to-cmac-env-{:x}", rand::random::<u64>()));
+        let overrides = EnvOverrides::new(&path);
+        let value = r#"sk-"quoted" \back$lash"#;
+
+        overrides.set("CMAC_ENV_TEST_API_KEY", Some(value)).unwrap();
+        assert_eq!(std::env::var("CMAC_ENV_TEST_API_KEY").unwrap(), value);
+        assert_eq!(overrides.load().unwrap().get("CMAC_ENV_TEST_API_KEY").map(String::as_str), Some(value));
+
+        let listed = overrides.list().unwrap();
+        assert_eq!(listed[0].masked_value, "••••lash");
+        assert!(listed[0].active);
+
+        overrides.set("CMAC_ENV_TEST_API_KEY", None).unwrap();
+        assert!(std::env::var("CMAC_ENV_TEST_API_KEY").is_err());
+        assert!(overrides.load().unwrap().is_empty());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_mask_hides_short_values() {
+        assert_eq!(mask("short"), "••••");
+        assert_eq!(mask("sk-or-v1-abcdef1234"), "••••1234");
+    }
+}
@@ -3,6 +3,7 @@
 //! This module defines comprehensive error handling for all backend operations
 //! including API interactions, configuration management, and audio processing.
 
+use crate::i18n;
 use thiserror::Error;
 
 /// Main error type for the application
@@ -20,6 +21,42 @@ pub enum AppError {
     #[error("ElevenLabs API error: {0}")]
     ElevenLabs(#[from] ElevenLabsError),
 
+    /// Errors related to the moderation API
+    #[error("Moderation API error: {0}")]
+    Moderation(#[from] ModerationError),
+
+    /// Errors related to Azure OpenAI interactions
+    #[error("Azure OpenAI error: {0}")]
+    Azure(#[from] AzureError),
+
+    /// Errors related to OpenRouter interactions
+    #[error("OpenRouter error: {0}")]
+    OpenRouter(#[from] OpenRouterError),
+
+    /// Errors related to Google Gemini interactions
+    #[error("Gemini error: {0}")]
+    Gemini(#[from] GeminiError),
+
+    /// Errors related to ticket creation in Jira or ServiceNow
+    #[error("Ticketing error: {0}")]
+    Ticketing(#[from] TicketingError),
+
+    /// Errors related to reading calendars
+    #[error("Calendar error: {0}")]
+    Calendar(#[from] CalendarError),
+
+    /// Errors related to reading email over IMAP
+    #[error("Email error: {0}")]
+    Email(#[from] EmailError),
+
+    /// Errors related to text recognition
+    #[error("OCR error: {0}")]
+    Ocr(#[from] OcrError),
+
+    /// Errors related to the system clipboard
+    #[error("Clipboard error: {0}")]
+    Clipboard(#[from] ClipboardError),
+
     /// Network-related errors
     #[error("Network error: {0}")]
     Network(#[from] NetworkError),
@@ -32,10 +69,26 @@ pub enum AppError {
     #[error("Audio error: {0}")]
     Audio(#[from] AudioError),
 
+    /// Local storage errors
+    #[error("Storage error: {0}")]
+    Storage(#[from] StorageError),
+
     /// State management errors
     #[error("State error: {0}")]
     State(String),
 
+    /// A provider is paused after hitting its rate limit or quota
+    #[error("{provider} is paused after hitting its rate limit or quota; try again in {remaining_secs}s")]
+    CoolingDown { provider: String, remaining_secs: u64 },
+
+    /// The session is locked until its PIN is entered
+    #[error("The session is locked. Enter your PIN to unlock it")]
+    SessionLocked,
+
+    /// The action is disabled in kiosk mode
+    #[error("Not available in kiosk mode")]
+    KioskMode,
+
     /// Generic errors
     #[error("Application error: {0}")]
     Generic(String),
@@ -90,8 +143,14 @@ pub enum OpenWebUiError {
     #[error("OpenWebUI API rate limit exceeded")]
     RateLimitExceeded,
 
-    #[error("Streaming not supported")]
-    StreamingNotSupported,
+    #[error("The model stopped while reasoning, before giving an answer")]
+    ReasoningOnly,
+
+    #[error("Failed to upload file: {0}")]
+    UploadFailed(String),
+
+    #[error("The LLM server does not store files; only OpenWebUI does")]
+    FilesUnsupported,
 }
 
 /// Errors specific to ElevenLabs API operations
@@ -122,6 +181,195 @@ pub enum ElevenLabsError {
     QuotaExceeded,
 }
 
+/// Errors specific to moderation API operations
+#[derive(Error, Debug)]
+pub enum ModerationError {
+    #[error("Moderation request failed: {0}")]
+    RequestFailed(String),
+
+    #[error("Moderation API authentication failed")]
+    AuthenticationFailed,
+
+    #[error("Invalid moderation response: {0}")]
+    InvalidResponse(String),
+}
+
+/// Errors specific to Azure OpenAI operations
+#[derive(Error, Debug)]
+pub enum AzureError {
+    #[error("Azure OpenAI request failed: {0}")]
+    RequestFailed(String),
+
+    #[error("Azure OpenAI authentication failed")]
+    AuthenticationFailed,
+
+    #[error("Deployment not found: {0}")]
+    DeploymentNotFound(String),
+
+    #[error("Azure OpenAI {0} is not configured")]
+    NotConfigured(&'static str),
+
+    #[error("Context limit exceeded")]
+    ContextLimitExceeded,
+
+    #[error("Invalid Azure OpenAI response: {0}")]
+    InvalidResponse(String),
+
+    #[error("Azure OpenAI rate limit exceeded")]
+    RateLimitExceeded,
+
+    #[error("Azure OpenAI request timed out")]
+    Timeout,
+}
+
+/// Errors specific to OpenRouter operations
+#[derive(Error, Debug)]
+pub enum OpenRouterError {
+    #[error("OpenRouter request failed: {0}")]
+    RequestFailed(String),
+
+    #[error("OpenRouter authentication failed")]
+    AuthenticationFailed,
+
+    #[error("OpenRouter account has insufficient credits")]
+    InsufficientCredits,
+
+    #[error("Model not found: {0}")]
+    ModelNotFound(String),
+
+    #[error("Context limit exceeded")]
+    ContextLimitExceeded,
+
+    #[error("Invalid OpenRouter response: {0}")]
+    InvalidResponse(String),
+
+    #[error("OpenRouter rate limit exceeded")]
+    RateLimitExceeded,
+
+    #[error("OpenRouter request timed out")]
+    Timeout,
+}
+
+/// Errors specific to Google Gemini operations
+#[derive(Error, Debug)]
+pub enum GeminiError {
+    #[error("Gemini request failed: {0}")]
+    RequestFailed(String),
+
+    #[error("Gemini authentication failed")]
+    AuthenticationFailed,
+
+    #[error("Model not found: {0}")]
+    ModelNotFound(String),
+
+    #[error("Context limit exceeded")]
+    ContextLimitExceeded,
+
+    #[error("Response blocked by Gemini safety settings: {0}")]
+    Blocked(String),
+
+    #[error("Invalid Gemini response: {0}")]
+    InvalidResponse(String),
+
+    #[error("Gemini rate limit exceeded")]
+    RateLimitExceeded,
+
+    #[error("Gemini request timed out")]
+    Timeout,
+}
+
+/// Errors specific to ticket creation
+#[derive(Error, Debug)]
+pub enum TicketingError {
+    #[error("Ticketing is not configured: {0}")]
+    NotConfigured(&'static str),
+
+    #[error("Ticket request failed: {0}")]
+    RequestFailed(String),
+
+    #[error("Ticket system authentication failed")]
+    AuthenticationFailed,
+
+    #[error("Ticket system rejected the ticket: {0}")]
+    Rejected(String),
+
+    #[error("Invalid ticket system response: {0}")]
+    InvalidResponse(String),
+
+    #[error("Ticket request timed out")]
+    Timeout,
+}
+
+/// Errors specific to reading calendars
+#[derive(Error, Debug)]
+pub enum CalendarError {
+    #[error("Calendar is not configured: {0}")]
+    NotConfigured(&'static str),
+
+    #[error("Calendar request failed: {0}")]
+    RequestFailed(String),
+
+    #[error("Calendar authentication failed")]
+    AuthenticationFailed,
+
+    #[error("Invalid calendar response: {0}")]
+    InvalidResponse(String),
+}
+
+/// Errors specific to reading email over IMAP
+#[derive(Error, Debug)]
+pub enum EmailError {
+    #[error("Email is not configured: {0}")]
+    NotConfigured(&'static str),
+
+    #[error("Could not connect to the mail server: {0}")]
+    ConnectionFailed(String),
+
+    #[error("Mail server login failed")]
+    AuthenticationFailed,
+
+    #[error("The {0} cannot contain line breaks or NUL characters")]
+    InvalidText(&'static str),
+
+    #[error("Mail server refused the command: {0}")]
+    Rejected(String),
+
+    #[error("Invalid mail server response: {0}")]
+    InvalidResponse(String),
+
+    #[error("Mail server timed out")]
+    Timeout,
+}
+
+/// Errors specific to text recognition
+#[derive(Error, Debug)]
+pub enum OcrError {
+    #[error("Tesseract could not be started ({0}); is it installed?")]
+    NotAvailable(String),
+
+    #[error("Text recognition failed: {0}")]
+    Failed(String),
+
+    #[error("Text recognition timed out")]
+    Timeout,
+}
+
+/// Errors specific to the system clipboard
+#[derive(Error, Debug)]
+pub enum ClipboardError {
+    #[error("Clipboard is not available: {0}")]
+    Unavailable(String),
+
+    #[error("Could not copy the selection: {0}")]
+    CopyFailed(String),
+
+    #[error("No text is selected")]
+    NothingSelected,
+
+    #[error("The clipboard has no text")]
+    Empty,
+}
+
 /// Network-related errors
 #[derive(Error, Debug)]
 pub enum NetworkError {
@@ -170,6 +418,40 @@ pub enum ConfigError {
 
     #[error("Configuration parse error: {0}")]
     ParseError(String),
+
+    #[error("Incorrect PIN")]
+    IncorrectPin,
+
+    #[error("Too many incorrect PINs; try again in {0}s")]
+    PinAttemptsExceeded(u64),
+}
+
+/// Local storage (conversation history) errors
+#[derive(Error, Debug)]
+pub enum StorageError {
+    #[error("Failed to open history database: {0}")]
+    OpenFailed(String),
+
+    #[error("History query failed: {0}")]
+    QueryFailed(String),
+
+    #[error("History is locked. Enter your passphrase to unlock it")]
+    Locked,
+
+    #[error("Incorrect history passphrase")]
+    WrongPassphrase,
+
+    #[error("Failed to encrypt history: {0}")]
+    EncryptionFailed(String),
+
+    #[error("Failed to decrypt history: {0}")]
+    DecryptionFailed(String),
+
+    #[error("Failed to export data: {0}")]
+    ExportFailed(String),
+
+    #[error("Erasing needs a valid confirmation token; request a new one")]
+    InvalidConfirmation,
 }
 
 /// Audio processing errors
@@ -195,6 +477,131 @@ pub enum AudioError {
 
     #[error("Audio buffer underflow")]
     BufferUnderflow,
+
+    #[error("Microphone is muted")]
+    MicrophoneMuted,
+
+    #[error("No microphone found. Connect a microphone or enable one in your system sound settings")]
+    NoInputDevice,
+
+    #[error("Microphone access denied. Allow microphone access for this app in your system privacy settings")]
+    MicrophonePermissionDenied,
+
+    #[error("Microphone '{0}' is in use by another application. Close it and try again")]
+    DeviceBusy(String),
+}
+
+impl AppError {
+    /// Whether retrying the same request might succeed
+    ///
+    /// Network failures, timeouts, and server errors are transient; rejected
+    /// credentials, missing voices or models, rate limits, and exhausted quotas
+    /// are not. A rate-limited provider is paused by its cooldown instead, since
+    /// retrying within seconds only spends more of the limit.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            AppError::WhisperApi(e) => matches!(
+                e,
+                WhisperError::TranscriptionFailed(_) | WhisperError::Timeout
+            ),
+            AppError::OpenWebUi(e) => matches!(
+                e,
+                OpenWebUiError::MessageSendFailed(_) | OpenWebUiError::Timeout
+            ),
+            AppError::ElevenLabs(e) => matches!(
+                e,
+                ElevenLabsError::SynthesisFailed(_) | ElevenLabsError::Timeout
+            ),
+            AppError::Network(_) => true,
+            _ => false,
+        }
+    }
+
+    /// Whether a provider rejected the request for exceeding its rate limit (HTTP 429)
+    pub fn is_rate_limited(&self) -> bool {
+        matches!(
+            self,
+            AppError::WhisperApi(WhisperError::RateLimitExceeded)
+                | AppError::OpenWebUi(OpenWebUiError::RateLimitExceeded)
+                | AppError::ElevenLabs(ElevenLabsError::RateLimitExceeded)
+                | AppError::Azure(AzureError::RateLimitExceeded)
+                | AppError::OpenRouter(OpenRouterError::RateLimitExceeded)
+                | AppError::Gemini(GeminiError::RateLimitExceeded)
+        )
+    }
+
+    /// Whether a provider's quota or credit balance is used up (HTTP 402)
+    pub fn is_quota_exhausted(&self) -> bool {
+        matches!(
+            self,
+            AppError::ElevenLabs(ElevenLabsError::QuotaExceeded) | AppError::OpenRouter(OpenRouterError::InsufficientCredits)
+        )
+    }
+
+    /// Content-free category of the error, such as `whisper/Timeout`
+    ///
+    /// Only the error's source and kind are kept; messages, which can contain
+    /// user text or server responses, are dropped.
+    pub fn category(&self) -> String {
+        let (source, inner) = self.parts();
+        let detail = inner.map(|e| format!("{:?}", e)).unwrap_or_default();
+
+        // The variant name is everything before its fields
+        let kind: String = detail.chars().take_while(char::is_ascii_alphanumeric).collect();
+        if kind.is_empty() {
+            source.to_string()
+        } else {
+            format!("{}/{}", source, kind)
+        }
+    }
+
+    /// The error message in `locale`
+    ///
+    /// The message is looked up as `error-<source>-<kind>` (the category), then as
+    /// `error-<source>` with the English detail as `$detail`. Errors without a
+    /// translation keep their English message.
+    pub fn localized(&self, locale: &str) -> String {
+        if let AppError::CoolingDown { provider, remaining_secs } = self {
+            let args: [(&str, &dyn std::fmt::Display); 2] = [("provider", provider), ("seconds", remaining_secs)];
+            return i18n::message(locale, "error-cooldown", &args).unwrap_or_else(|| self.to_string());
+        }
+
+        let (source, inner) = self.parts();
+        let detail = match self {
+            AppError::State(detail) | AppError::Generic(detail) => detail.clone(),
+            _ => inner.map(ToString::to_string).unwrap_or_default(),
+        };
+        i18n::message(locale, &format!("error-{}", self.category().replace('/', "-")), &[])
+            .or_else(|| i18n::message(locale, &format!("error-{}", source), &[("detail", &detail)]))
+            .unwrap_or_else(|| self.to_string())
+    }
+
+    /// Source of the error and the wrapped service or subsystem error, if any
+    fn parts(&self) -> (&'static str, Option<&dyn std::error::Error>) {
+        match self {
+            AppError::WhisperApi(e) => ("whisper", Some(e)),
+            AppError::OpenWebUi(e) => ("openwebui", Some(e)),
+            AppError::ElevenLabs(e) => ("elevenlabs", Some(e)),
+            AppError::Moderation(e) => ("moderation", Some(e)),
+            AppError::Azure(e) => ("azure", Some(e)),
+            AppError::OpenRouter(e) => ("openrouter", Some(e)),
+            AppError::Gemini(e) => ("gemini", Some(e)),
+            AppError::Ticketing(e) => ("ticketing", Some(e)),
+            AppError::Calendar(e) => ("calendar", Some(e)),
+            AppError::Email(e) => ("email", Some(e)),
+            AppError::Ocr(e) => ("ocr", Some(e)),
+            AppError::Clipboard(e) => ("clipboard", Some(e)),
+            AppError::Network(e) => ("network", Some(e)),
+            AppError::Config(e) => ("config", Some(e)),
+            AppError::Audio(e) => ("audio", Some(e)),
+            AppError::Storage(e) => ("storage", Some(e)),
+            AppError::CoolingDown { .. } => ("cooldown", None),
+            AppError::SessionLocked => ("session-locked", None),
+            AppError::KioskMode => ("kiosk", None),
+            AppError::State(_) => ("state", None),
+            AppError::Generic(_) => ("generic", None),
+        }
+    }
 }
 
 /// Convert AppError to a Tauri-compatible error string
@@ -268,4 +675,36 @@ mod tests {
         let app_error: AppError = network_error.into();
         assert!(matches!(app_error, AppError::Network(_)));
     }
+
+    #[test]
+    fn test_only_transient_errors_are_retried() {
+        assert!(AppError::from(OpenWebUiError::Timeout).is_transient());
+        assert!(!AppError::from(ElevenLabsError::RateLimitExceeded).is_transient());
+        assert!(!AppError::from(WhisperError::AuthenticationFailed).is_transient());
+        assert!(!AppError::from(ElevenLabsError::QuotaExceeded).is_transient());
+        assert!(!AppError::from(OpenWebUiError::ContextLimitExceeded).is_transient());
+    }
+
+    #[test]
+    fn test_categories_drop_messages() {
+        let error = AppError::from(WhisperError::TranscriptionFailed("my bank PIN is 1234".to_string()));
+        assert_eq!(error.category(), "whisper/TranscriptionFailed");
+        assert_eq!(AppError::from(NetworkError::Timeout).category(), "network/Timeout");
+        assert_eq!(AppError::Generic("details".to_string()).category(), "generic");
+    }
+
+    #[test]
+    fn test_localized_messages_fall_back_to_english() {
+        let muted = AppError::from(AudioError::MicrophoneMuted);
+        assert_eq!(muted.localized("es"), "El micrófono está silenciado");
+        assert_eq!(muted.localized("en"), muted.to_string());
+
+        // Untranslated kinds keep the English detail under a translated source
+        let error = AppError::from(WhisperError::TranscriptionFailed("bad audio".to_string()));
+        assert_eq!(error.localized("es"), "Error de la API de Whisper: Failed to transcribe audio: bad audio");
+
+        let cooling = AppError::CoolingDown { provider: "whisper".to_string(), remaining_secs: 30 };
+        assert!(cooling.localized("es-MX").ends_with("en 30 s"));
+        assert_eq!(cooling.localized("de"), cooling.to_string());
+    }
 }
@@ -0,0 +1,132 @@
+//! Prompt experiments
+//!
+//! While `experiment.enabled` is on, each new conversation is assigned one of
+//! the experiment's system prompt variants at random, weighted by
+//! `weight`, and keeps it for its whole life, including after it is restored
+//! from history. The variant is named by a tag, "experiment/variant", stored
+//! on the conversation and each of its messages, so exports show which prompt
+//! produced a reply. Anonymous usage reports count conversations, replies,
+//! errors, and ratings (see `rate_response`) per tag.
+//!
+//! A conversation whose variant is no longer configured (the experiment was
+//! renamed or ended) gets no variant prompt.
+
+use crate::api::GenerationOverrides;
+use crate::config::{PromptExperimentConfig, PromptVariant};
+use crate::state::{ConversationContext, MessageRole};
+use crate::usage;
+use rand::Rng;
+
+/// Tag of a randomly chosen variant, or `None` if no experiment is running
+pub fn assign(config: &PromptExperimentConfig) -> Option<String> {
+    let total: u32 = config.variants.iter().map(|variant| variant.weight).sum();
+    if !config.enabled || total == 0 {
+        return None;
+    }
+    pick(config, rand::thread_rng().gen_range(0..total)).map(|variant| tag(config, variant))
+}
+
+/// Generation overrides of `conversation`, with its variant's system prompt unless it sets its own
+pub fn overrides(config: &PromptExperimentConfig, conversation: &ConversationContext) -> GenerationOverrides {
+    let mut overrides = GenerationOverrides::for_conversation(&conversation.settings);
+    if overrides.system_prompt.is_none() {
+        overrides.system_prompt = conversation
+            .variant
+            .as_deref()
+            .and_then(|assigned| config.variants.iter().find(|variant| tag(config, variant) == assigned))
+            .map(|variant| variant.system_prompt.trim())
+            .filter(|prompt| !prompt.is_empty())
+            .map(str::to_string);
+    }
+    overrides
+}
+
+/// Count a reply in `conversation` for its variant, and the conversation itself on its first reply
+pub fn record_reply(conversation: &ConversationContext) {
+    let Some(variant) = &conversation.variant else {
+        return;
+    };
+    let replies = conversation.messages.iter().filter(|m| m.role == MessageRole::Assistant).count();
+    if replies == 1 {
+        usage::record_variant(variant, "conversations");
+    }
+    usage::record_variant(variant, "replies");
+}
+
+/// Count a failed reply in `conversation` for its variant
+pub fn record_error(conversation: &ConversationContext) {
+    if let Some(variant) = &conversation.variant {
+        usage::record_variant(variant, "errors");
+    }
+}
+
+/// The variant covering `roll` of the cumulative weights
+fn pick(config: &PromptExperimentConfig, mut roll: u32) -> Option<&PromptVariant> {
+    config.variants.iter().find(|variant| {
+        if roll < variant.weight {
+            return true;
+        }
+        roll -= variant.weight;
+        false
+    })
+}
+
+fn tag(config: &PromptExperimentConfig, variant: &PromptVariant) -> String {
+    format!("{}/{}", config.name, variant.name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{ConversationSettings, Message};
+
+    fn experiment() -> PromptExperimentConfig {
+        let variant = |name: &str, system_prompt: &str, weight| PromptVariant {
+            name: name.to_string(),
+            system_prompt: system_prompt.to_string(),
+            weight,
+        };
+        PromptExperimentConfig {
+            enabled: true,
+            name: "tone".to_string(),
+            variants: vec![variant("control", "", 1), variant("friendly", "Be warm and brief.", 3)],
+        }
+    }
+
+    #[test]
+    fn test_variants_are_picked_by_weight() {
+        let config = experiment();
+        let names: Vec<&str> = (0..4).map(|roll| pick(&config, roll).unwrap().name.as_str()).collect();
+        assert_eq!(names, ["control", "friendly", "friendly", "friendly"]);
+        assert!(pick(&config, 4).is_none());
+
+        assert!(assign(&PromptExperimentConfig { enabled: false, ..experiment() }).is_none());
+        assert!(assign(&config).is_some_and(|tag| tag == "tone/control" || tag == "tone/friendly"));
+    }
+
+    #[test]
+    fn test_variant_prompt_applies_unless_conversation_sets_one() {
+        let config = experiment();
+        let mut conversation = ConversationContext {
+            id: "c".to_string(),
+            messages: Vec::<Message>::new(),
+            max_messages: 10,
+            started_at: 0,
+            updated_at: 0,
+            ephemeral: false,
+            settings: ConversationSettings::default(),
+            variant: Some("tone/friendly".to_string()),
+        };
+        assert_eq!(overrides(&config, &conversation).system_prompt.as_deref(), Some("Be warm and brief."));
+
+        conversation.variant = Some("tone/control".to_string());
+        assert_eq!(overrides(&config, &conversation).system_prompt, None);
+
+        conversation.variant = Some("tone/friendly".to_string());
+        conversation.settings.system_prompt = Some("You are a coding assistant.".to_string());
+        assert_eq!(
+            overrides(&config, &conversation).system_prompt.as_deref(),
+            Some("You are a coding assistant.")
+        );
+    }
+}
@@ -0,0 +1,275 @@
+//! Feature flags
+//!
+//! Flags switch subsystems off, or on for only part of a fleet, without a new
+//! build. A flag is `true`, `false`, or a number from 0 to 100: the percentage
+//! of installs that get the feature. Each install is placed in a stable bucket
+//! per flag, so it keeps its answer as a rollout percentage rises.
+//!
+//! Flags come from `feature_flags.json` in the app directory and, if
+//! `feature_flags.remote_url` is set, from that URL, whose flags take
+//! precedence. The last remote flags downloaded are kept on disk, so a fleet
+//! override still applies when the app starts offline; clearing `remote_url`
+//! deletes them. A flag that is not set anywhere is on: it can only turn off a
+//! feature whose own setting is on.
+
+use crate::api::http;
+use crate::config::{ConfigManager, FeatureFlagsConfig};
+use crate::error::{AppResult, ConfigError, NetworkError};
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::RwLock;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// Sentences spoken while the answer is still being generated
+pub const STREAMING: &str = "streaming";
+
+/// Data sources queried before a query is sent (`tools`)
+pub const TOOLS: &str = "tools";
+
+/// Flags set by the local file
+const LOCAL_FILE: &str = "feature_flags.json";
+
+/// The last flags downloaded from `remote_url`
+const REMOTE_CACHE_FILE: &str = "feature_flags_remote.json";
+
+/// Random number placing this install in rollout buckets; never sent anywhere
+const SEED_FILE: &str = "rollout_seed";
+
+/// Timeout for downloading the remote flags
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(15);
+
+static FLAGS: RwLock<Flags> = RwLock::new(Flags::new());
+
+/// Value of a flag: on or off, or on for a percentage of installs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FlagValue {
+    Enabled(bool),
+    Percent(u8),
+}
+
+/// Where the value of a flag came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlagSource {
+    Local,
+    Remote,
+}
+
+/// A flag as it applies to this install
+#[derive(Debug, Clone, Serialize)]
+pub struct FeatureFlag {
+    pub name: String,
+    pub value: FlagValue,
+    pub source: FlagSource,
+
+    /// Whether the feature is on here
+    pub enabled: bool,
+}
+
+#[derive(Debug)]
+struct Flags {
+    local: BTreeMap<String, FlagValue>,
+    remote: BTreeMap<String, FlagValue>,
+    seed: u64,
+}
+
+impl Flags {
+    const fn new() -> Self {
+        Self {
+            local: BTreeMap::new(),
+            remote: BTreeMap::new(),
+            seed: 0,
+        }
+    }
+
+    fn value(&self, name: &str) -> Option<(FlagValue, FlagSource)> {
+        self.remote
+            .get(name)
+            .map(|value| (*value, FlagSource::Remote))
+            .or_else(|| self.local.get(name).map(|value| (*value, FlagSource::Local)))
+    }
+
+    fn is_enabled(&self, name: &str) -> bool {
+        match self.value(name) {
+            None => true,
+            Some((value, _)) => applies(value, self.seed, name),
+        }
+    }
+}
+
+/// Whether the feature behind flag `name` may be used
+pub fn enabled(name: &str) -> bool {
+    FLAGS.read().unwrap().is_enabled(name)
+}
+
+/// Every flag that is set, by name
+pub fn list() -> Vec<FeatureFlag> {
+    let flags = FLAGS.read().unwrap();
+    let mut names: Vec<&String> = flags.local.keys().chain(flags.remote.keys()).collect();
+    names.sort();
+    names.dedup();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let (value, source) = flags.value(name)?;
+            Some(FeatureFlag {
+                name: name.clone(),
+                value,
+                source,
+                enabled: applies(value, flags.seed, name),
+            })
+        })
+        .collect()
+}
+
+/// Read the local flags, the last remote flags downloaded, and this install's rollout seed
+///
+/// Without a `remote_url` the downloaded flags no longer apply and their copy is deleted.
+pub fn load(config: &FeatureFlagsConfig) {
+    let dir = match ConfigManager::app_dir() {
+        Ok(dir) => dir,
+        Err(e) => {
+            tracing::warn!("Feature flags not loaded: {}", e);
+            return;
+        }
+    };
+
+    let remote_path = dir.join(REMOTE_CACHE_FILE);
+    if config.remote_url.trim().is_empty() && remote_path.exists() {
+        if let Err(e) = fs::remove_file(&remote_path) {
+            tracing::warn!("Failed to remove {}: {}", remote_path.display(), e);
+        }
+    }
+
+    let mut flags = FLAGS.write().unwrap();
+    flags.local = read_flags(&dir.join(LOCAL_FILE));
+    flags.remote = if config.remote_url.trim().is_empty() {
+        BTreeMap::new()
+    } else {
+        read_flags(&remote_path)
+    };
+    flags.seed = seed(&dir);
+}
+
+/// Load the flags now, then download the remote ones and reload every `refresh_minutes`
+pub fn spawn_refresh_task(app: AppHandle) {
+    load(&app.state::<AppState>().get_config().feature_flags);
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let config = app.state::<AppState>().get_config().feature_flags;
+            if let Err(e) = refresh(&config).await {
+                tracing::warn!("Remote feature flags not updated: {}", e);
+            }
+
+            tokio::time::sleep(Duration::from_secs(config.refresh_minutes.max(1) * 60)).await;
+        }
+    });
+}
+
+async fn refresh(config: &FeatureFlagsConfig) -> AppResult<()> {
+    load(config);
+    if config.remote_url.trim().is_empty() {
+        return Ok(());
+    }
+
+    let request = http::client(DOWNLOAD_TIMEOUT)
+        .map_err(NetworkError::from)?
+        .get(&config.remote_url);
+    let response = http::send("feature_flags", request).await.map_err(NetworkError::from)?;
+    if !response.status().is_success() {
+        return Err(NetworkError::RequestFailed(format!("HTTP {}", response.status())).into());
+    }
+    let body = response
+        .text()
+        .await
+        .map_err(|e| NetworkError::RequestFailed(e.to_string()))?;
+    let remote: BTreeMap<String, FlagValue> = serde_json::from_str(&body).map_err(ConfigError::from)?;
+
+    let path = ConfigManager::app_dir()?.join(REMOTE_CACHE_FILE);
+    fs::write(&path, &body).map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
+    tracing::info!("Loaded {} remote feature flag(s)", remote.len());
+    FLAGS.write().unwrap().remote = remote;
+    Ok(())
+}
+
+/// Flags in a JSON file; a missing or unreadable file sets none
+fn read_flags(path: &Path) -> BTreeMap<String, FlagValue> {
+    let Ok(contents) = fs::read_to_string(path) else {
+        return BTreeMap::new();
+    };
+    serde_json::from_str(&contents).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring feature flags in {}: {}", path.display(), e);
+        BTreeMap::new()
+    })
+}
+
+/// This install's rollout seed, created on first use
+fn seed(dir: &Path) -> u64 {
+    let path = dir.join(SEED_FILE);
+    if let Some(seed) = fs::read_to_string(&path).ok().and_then(|seed| seed.trim().parse().ok()) {
+        return seed;
+    }
+    let seed: u64 = rand::random();
+    if let Err(e) = fs::write(&path, seed.to_string()) {
+        tracing::warn!("Rollout seed not saved: {}", e);
+    }
+    seed
+}
+
+/// Whether `value` turns flag `name` on for the install with `seed`
+fn applies(value: FlagValue, seed: u64, name: &str) -> bool {
+    match value {
+        FlagValue::Enabled(enabled) => enabled,
+        FlagValue::Percent(percent) => bucket(seed, name) < u64::from(percent),
+    }
+}
+
+/// Bucket 0-99 of the install for flag `name`, the same on every run (FNV-1a)
+fn bucket(seed: u64, name: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in seed.to_le_bytes().iter().chain(name.as_bytes()) {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash % 100
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flags(local: &str, remote: &str, seed: u64) -> Flags {
+        Flags {
+            local: serde_json::from_str(local).unwrap(),
+            remote: serde_json::from_str(remote).unwrap(),
+            seed,
+        }
+    }
+
+    #[test]
+    fn test_remote_flags_take_precedence() {
+        let flags = flags(r#"{"streaming": false, "tools": false}"#, r#"{"tools": true}"#, 1);
+        assert!(!flags.is_enabled(STREAMING));
+        assert!(flags.is_enabled(TOOLS));
+        assert!(flags.is_enabled("unset"));
+        assert_eq!(flags.value(TOOLS), Some((FlagValue::Enabled(true), FlagSource::Remote)));
+    }
+
+    #[test]
+    fn test_rollout_percentage() {
+        assert!(!flags("{}", r#"{"tools": 0}"#, 7).is_enabled(TOOLS));
+        assert!(flags("{}", r#"{"tools": 100}"#, 7).is_enabled(TOOLS));
+
+        // Roughly the given share of installs, each keeping its answer as the share grows
+        let on = (0..1000).filter(|seed| applies(FlagValue::Percent(30), *seed, TOOLS)).count();
+        assert!((200..400).contains(&on), "{} of 1000 installs", on);
+        assert!((0..1000)
+            .filter(|seed| applies(FlagValue::Percent(30), *seed, TOOLS))
+            .all(|seed| applies(FlagValue::Percent(60), seed, TOOLS)));
+    }
+}
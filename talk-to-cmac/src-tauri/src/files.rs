@@ -0,0 +1,221 @@
+//! Local file search
+//!
+//! Searches the folders in `tools.files` for documents matching a search term
+//! and returns the best matches, each with the lines that match best, for the
+//! LLM to answer from. Every file is matched by name; text files (by extension)
+//! are matched by content as well, so PDFs and other binary documents are only
+//! found by name.
+//!
+//! Hidden files and folders are skipped, and symbolic links are not followed.
+
+use crate::config::FileSearchConfig;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Deepest subfolder searched below a configured folder
+const MAX_DEPTH: usize = 8;
+
+/// Words of a search term that don't help find a file
+const STOP_WORDS: &[&str] = &["a", "an", "the", "for", "of", "on", "in", "to", "and", "with", "about", "my", "our"];
+
+/// A file matching a search
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileMatch {
+    pub path: PathBuf,
+
+    /// Higher is better; name matches count more than content matches
+    pub score: usize,
+
+    /// Best matching lines, for text files
+    pub snippet: Option<String>,
+}
+
+/// Files in the configured folders matching `term`, best first, at most `max_results`
+///
+/// A file matches when at least half of the term's keywords appear in its name or text.
+pub fn search(config: &FileSearchConfig, term: &str) -> Vec<FileMatch> {
+    let keywords = keywords(term);
+    if keywords.is_empty() {
+        return Vec::new();
+    }
+
+    let mut files = Vec::new();
+    for folder in &config.folders {
+        collect_files(Path::new(folder), 0, config.max_files, &mut files);
+    }
+
+    let mut matches: Vec<FileMatch> = files.iter().filter_map(|path| score(config, path, &keywords)).collect();
+    matches.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+    matches.truncate(config.max_results);
+    matches
+}
+
+/// Matches listed for the LLM
+pub fn describe(matches: &[FileMatch]) -> String {
+    if matches.is_empty() {
+        return "No matching files.".to_string();
+    }
+
+    matches
+        .iter()
+        .map(|file| match &file.snippet {
+            Some(snippet) => format!("- {}\n  {}", file.path.display(), snippet),
+            None => format!("- {}", file.path.display()),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Lowercase words of `term` worth searching for
+fn keywords(term: &str) -> Vec<String> {
+    let mut keywords: Vec<String> = term
+        .split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|word| !word.is_empty() && !STOP_WORDS.contains(&word.as_str()))
+        .collect();
+    keywords.dedup();
+    keywords
+}
+
+/// Add the files under `dir` to `files`, up to `limit` in all
+fn collect_files(dir: &Path, depth: usize, limit: usize, files: &mut Vec<PathBuf>) {
+    if depth > MAX_DEPTH {
+        return;
+    }
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            if depth == 0 {
+                tracing::warn!("Cannot search {}: {}", dir.display(), e);
+            }
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        if files.len() >= limit {
+            return;
+        }
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        match entry.file_type() {
+            Ok(kind) if kind.is_dir() => collect_files(&entry.path(), depth + 1, limit, files),
+            Ok(kind) if kind.is_file() => files.push(entry.path()),
+            _ => {}
+        }
+    }
+}
+
+/// How well `path` matches `keywords`, if it does
+fn score(config: &FileSearchConfig, path: &Path, keywords: &[String]) -> Option<FileMatch> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    let text = read_text(config, path);
+    let lowercase = text.as_deref().map(str::to_lowercase);
+
+    let (mut found, mut in_name) = (0, 0);
+    for keyword in keywords {
+        let named = contains_word(&name, keyword);
+        if named || lowercase.as_deref().is_some_and(|text| contains_word(text, keyword)) {
+            found += 1;
+        }
+        in_name += usize::from(named);
+    }
+    if found == 0 || found * 2 < keywords.len() {
+        return None;
+    }
+
+    Some(FileMatch {
+        path: path.to_path_buf(),
+        score: found * 10 + in_name * 5,
+        snippet: text.and_then(|text| snippet(&text, keywords, config.snippet_chars)),
+    })
+}
+
+/// Contents of a text file no larger than `max_file_bytes`
+fn read_text(config: &FileSearchConfig, path: &Path) -> Option<String> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    if !config.text_extensions.iter().any(|text| text.eq_ignore_ascii_case(&extension)) {
+        return None;
+    }
+    if fs::metadata(path).ok()?.len() > config.max_file_bytes {
+        return None;
+    }
+    fs::read(path).ok().map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Whether `word` appears in `text` on its own rather than inside a longer word
+fn contains_word(text: &str, word: &str) -> bool {
+    text.match_indices(word).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + word.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+/// The line with the most keywords and its neighbours, on one line, at most `max_chars`
+fn snippet(text: &str, keywords: &[String], max_chars: usize) -> Option<String> {
+    let lines: Vec<&str> = text.lines().collect();
+    let (best, hits) = lines
+        .iter()
+        .map(|line| {
+            let line = line.to_lowercase();
+            keywords.iter().filter(|keyword| contains_word(&line, keyword)).count()
+        })
+        .enumerate()
+        .max_by_key(|&(index, hits)| (hits, std::cmp::Reverse(index)))?;
+    if hits == 0 {
+        return None;
+    }
+
+    let context = &lines[best.saturating_sub(1)..(best + 2).min(lines.len())];
+    let snippet = context.join(" ").split_whitespace().collect::<Vec<_>>().join(" ");
+    Some(match snippet.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}...", &snippet[..end]),
+        None => snippet,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_files_match_by_name_and_content() {
+        let dir = std::env::temp_dir().join(format!("talk-to-cmac-files-{:x}", rand::random::<u64>()));
+        fs::create_dir_all(dir.join("manuals")).unwrap();
+        fs::create_dir_all(dir.join(".cache")).unwrap();
+        fs::write(dir.join("manuals").join("Model 4500 Spec Sheet.pdf"), [0u8, 1, 2]).unwrap();
+        fs::write(
+            dir.join("notes.txt"),
+            "Site visit notes\nThe model 4500 spec sheet lists 120 psi.\nCall back Monday.\n",
+        )
+        .unwrap();
+        fs::write(dir.join("model 45000.txt"), "Unrelated").unwrap();
+        fs::write(dir.join(".cache").join("spec sheet 4500.txt"), "Hidden").unwrap();
+
+        let config = FileSearchConfig {
+            folders: vec![dir.to_string_lossy().into_owned()],
+            ..FileSearchConfig::default()
+        };
+        let matches = search(&config, "spec sheet for the model 4500");
+        fs::remove_dir_all(&dir).unwrap();
+
+        let names: Vec<String> = matches
+            .iter()
+            .map(|file| file.path.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["Model 4500 Spec Sheet.pdf", "notes.txt"]);
+        assert_eq!(matches[0].snippet, None);
+        assert_eq!(
+            matches[1].snippet.as_deref(),
+            Some("Site visit notes The model 4500 spec sheet lists 120 psi. Call back Monday.")
+        );
+    }
+
+    #[test]
+    fn test_keywords_drop_stop_words() {
+        assert_eq!(keywords("spec sheet for the model 4500"), ["spec", "sheet", "model", "4500"]);
+    }
+}
@@ -0,0 +1,118 @@
+//! Automatic gain control
+//!
+//! A laptop microphone at arm's length records speech so quietly that Whisper
+//! mis-hears it. With `audio.auto_gain` on, each recording is amplified before
+//! transcription so its speech reaches `audio.auto_gain_target` RMS, by at most
+//! `audio.auto_gain_max` and never so far that the loudest sample clips.
+//!
+//! The level is measured over the speech only: 20 ms frames within 30 dB of the
+//! loudest one, so pauses do not make a recording seem quieter than it is.
+//! Recordings are only ever amplified, and anything but 16-bit PCM WAV is left
+//! as it is.
+
+use crate::config::AudioConfig;
+use crate::wav;
+
+/// Loudest a sample may become
+const MAX_PEAK: f32 = 0.98;
+
+/// Frames quieter than the loudest one by more than this factor (30 dB) are not speech
+const SPEECH_RANGE: f32 = 0.03;
+
+/// Gains this close to 1.0 leave the recording as it is
+const MIN_GAIN: f32 = 1.05;
+
+/// `wav` amplified as `config` asks; unchanged if AGC is off or not needed
+pub fn apply(config: &AudioConfig, wav: Vec<u8>) -> Vec<u8> {
+    if !config.auto_gain {
+        return wav;
+    }
+
+    let (mut samples, sample_rate) = match wav::decode(&wav) {
+        Ok(decoded) => decoded,
+        Err(e) => {
+            tracing::debug!("Not adjusting the gain of the recording: {}", e);
+            return wav;
+        }
+    };
+
+    let gain = gain(&samples, sample_rate, config.auto_gain_target, config.auto_gain_max);
+    if gain < MIN_GAIN {
+        return wav;
+    }
+
+    tracing::debug!("Amplifying the recording by {:.1} dB", 20.0 * gain.log10());
+    for sample in &mut samples {
+        *sample *= gain;
+    }
+    wav::encode(&samples, sample_rate)
+}
+
+/// Gain that brings the speech in `samples` to `target` RMS, within `max_gain` and without clipping
+fn gain(samples: &[f32], sample_rate: u32, target: f32, max_gain: f32) -> f32 {
+    let frame_len = (sample_rate as usize / 50).max(1);
+    let frames: Vec<f32> = samples
+        .chunks(frame_len)
+        .map(|frame| frame.iter().map(|sample| sample * sample).sum::<f32>() / frame.len() as f32)
+        .collect();
+    let loudest = frames.iter().copied().fold(0.0f32, f32::max);
+    if loudest == 0.0 {
+        return 1.0;
+    }
+
+    let speech: Vec<f32> = frames.into_iter().filter(|power| *power >= loudest * SPEECH_RANGE * SPEECH_RANGE).collect();
+    let level = (speech.iter().sum::<f32>() / speech.len() as f32).sqrt();
+    let peak = samples.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+
+    (target / level).min(max_gain).min(MAX_PEAK / peak).max(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(amplitude: f32, seconds: f32) -> Vec<f32> {
+        (0..(16_000.0 * seconds) as usize)
+            .map(|i| amplitude * (i as f32 * 0.1).sin())
+            .collect()
+    }
+
+    fn config() -> AudioConfig {
+        AudioConfig {
+            auto_gain: true,
+            auto_gain_target: 0.1,
+            auto_gain_max: 10.0,
+            ..AudioConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_gain_ignores_pauses_and_limits() {
+        // A quiet sine has an RMS of amplitude / sqrt(2); a second of silence does not count
+        let mut quiet = tone(0.02, 1.0);
+        quiet.extend(tone(0.0, 1.0));
+        let amount = gain(&quiet, 16_000, 0.1, 10.0);
+        assert!((amount - 0.1 / (0.02 / 2f32.sqrt())).abs() < 0.1, "gain {}", amount);
+
+        assert_eq!(gain(&tone(0.001, 1.0), 16_000, 0.1, 10.0), 10.0);
+        assert!((gain(&tone(0.1, 1.0), 16_000, 1.0, 20.0) - 9.8).abs() < 0.01);
+        assert_eq!(gain(&tone(0.5, 1.0), 16_000, 0.1, 10.0), 1.0);
+        assert_eq!(gain(&tone(0.0, 1.0), 16_000, 0.1, 10.0), 1.0);
+    }
+
+    #[test]
+    fn test_apply_amplifies_quiet_recordings() {
+        let quiet = wav::encode(&tone(0.02, 1.0), 16_000);
+        let amplified = apply(&config(), quiet.clone());
+        let (samples, sample_rate) = wav::decode(&amplified).unwrap();
+        assert_eq!(sample_rate, 16_000);
+        let peak = samples.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+        assert!((peak - 0.1 * 2f32.sqrt()).abs() < 0.01, "peak {}", peak);
+
+        let off = AudioConfig { auto_gain: false, ..config() };
+        assert_eq!(apply(&off, quiet.clone()), quiet);
+        let loud = wav::encode(&tone(0.5, 1.0), 16_000);
+        assert_eq!(apply(&config(), loud.clone()), loud);
+        assert_eq!(apply(&config(), b"not audio".to_vec()), b"not audio");
+    }
+}
@@ -0,0 +1,292 @@
+//! Handoff to human support
+//!
+//! A user the assistant cannot help can pass the conversation to a person
+//! without repeating it. The package holds the transcript, the recordings
+//! saved while the conversation was open, and details of the app and the
+//! model that answered. Depending on `handoff.method` it is POSTed as JSON to
+//! `handoff.endpoint`, or opened as a pre-filled email to `handoff.email` or
+//! as `handoff.url` with `{subject}` and `{body}` filled in. Email and web
+//! links have a practical length limit, so they carry only the latest
+//! messages that fit and name the recordings instead of attaching them.
+
+use crate::api::http;
+use crate::api::lookup::encode_component;
+use crate::config::{AppConfig, ConfigManager, HandoffConfig, HandoffMethod};
+use crate::error::{AppResult, ConfigError, NetworkError};
+use crate::recordings::{RecordingInfo, RecordingStore};
+use crate::state::{ConversationContext, Message, MessageRole};
+use crate::telemetry::Stage;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Characters of transcript put in an email or web link
+const MAX_LINK_BODY_CHARS: usize = 1500;
+
+/// The conversation as handed to support
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandoffPackage {
+    pub conversation_id: String,
+
+    /// Unix timestamp of the conversation's first message
+    pub started_at: u64,
+
+    /// Unix timestamp of the handoff
+    pub requested_at: u64,
+
+    /// What the user said they need help with
+    pub reason: Option<String>,
+
+    pub app_version: String,
+
+    /// LLM provider as named in the settings (e.g., "openwebui")
+    pub llm_provider: String,
+
+    /// Model that answered, including a conversation's own choice
+    pub model: Option<String>,
+
+    pub locale: String,
+
+    /// Prompt experiment variant of the conversation
+    pub variant: Option<String>,
+
+    pub transcript: Vec<Message>,
+
+    /// Recordings saved since the conversation started, oldest first
+    pub recordings: Vec<HandoffRecording>,
+}
+
+/// A saved recording in a handoff package
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandoffRecording {
+    #[serde(flatten)]
+    pub info: RecordingInfo,
+
+    /// Where the audio file is on this computer
+    pub path: String,
+
+    /// Base64-encoded audio, with `attach_audio` on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audio: Option<String>,
+}
+
+/// How a conversation was handed off
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Handoff {
+    pub method: HandoffMethod,
+
+    /// Messages in the transcript sent
+    pub messages: usize,
+
+    /// Recordings in the package
+    pub recordings: usize,
+
+    /// Email or web link opened, for the `email` and `url` methods
+    pub link: Option<String>,
+}
+
+/// Package `conversation` for support
+pub fn package(config: &AppConfig, conversation: &ConversationContext, reason: Option<String>) -> HandoffPackage {
+    let recordings = recordings_since(conversation.started_at, config.handoff.attach_audio);
+    HandoffPackage {
+        conversation_id: conversation.id.clone(),
+        started_at: conversation.started_at,
+        requested_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+        reason: reason.map(|reason| reason.trim().to_string()).filter(|reason| !reason.is_empty()),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        llm_provider: Stage::Llm.provider(config),
+        model: conversation.settings.model.clone().or_else(|| Stage::Llm.model(config)),
+        locale: config.ui.locale.clone(),
+        variant: conversation.variant.clone(),
+        transcript: conversation.messages.clone(),
+        recordings,
+    }
+}
+
+/// POST `package` to the configured endpoint
+pub async fn post(config: &HandoffConfig, package: &HandoffPackage) -> AppResult<()> {
+    if config.endpoint.trim().is_empty() {
+        return Err(ConfigError::MissingConfig("handoff.endpoint".to_string()).into());
+    }
+
+    let mut request = http::client(Duration::from_secs(config.timeout_secs))
+        .map_err(NetworkError::from)?
+        .post(&config.endpoint)
+        .json(package);
+    if !config.token_secret.is_empty() {
+        if let Some(token) = ConfigManager::new()?.get_secret(&config.token_secret)? {
+            request = request.bearer_auth(token);
+        }
+    }
+
+    let response = http::send("handoff", request).await.map_err(NetworkError::from)?;
+    if !response.status().is_success() {
+        return Err(NetworkError::RequestFailed(format!("HTTP {}", response.status())).into());
+    }
+    tracing::info!("Handed off conversation {} to {}", package.conversation_id, config.endpoint);
+    Ok(())
+}
+
+/// Email or web link carrying a summary of `package`, for the `email` and `url` methods
+pub fn link(config: &HandoffConfig, package: &HandoffPackage) -> AppResult<String> {
+    let subject = encode_component(&subject(package));
+    let body = encode_component(&body(package));
+    match config.method {
+        HandoffMethod::Email if !config.email.trim().is_empty() => {
+            Ok(format!("mailto:{}?subject={}&body={}", config.email.trim(), subject, body))
+        }
+        HandoffMethod::Url if !config.url.trim().is_empty() => {
+            Ok(config.url.trim().replace("{subject}", &subject).replace("{body}", &body))
+        }
+        HandoffMethod::Email => Err(ConfigError::MissingConfig("handoff.email".to_string()).into()),
+        HandoffMethod::Url => Err(ConfigError::MissingConfig("handoff.url".to_string()).into()),
+        HandoffMethod::Post => Err(ConfigError::InvalidValue("POSTed handoffs have no link".to_string()).into()),
+    }
+}
+
+fn subject(package: &HandoffPackage) -> String {
+    match &package.reason {
+        Some(reason) => format!("Assistant handoff: {}", reason),
+        None => "Assistant handoff".to_string(),
+    }
+}
+
+/// Plain-text summary: the details, then as many of the latest messages as fit
+fn body(package: &HandoffPackage) -> String {
+    let mut header = vec![
+        format!("Conversation {} started {}", package.conversation_id, local_time(package.started_at)),
+        format!(
+            "App {}, {} {}",
+            package.app_version,
+            package.llm_provider,
+            package.model.as_deref().unwrap_or_default()
+        )
+        .trim_end()
+        .to_string(),
+    ];
+    if let Some(reason) = &package.reason {
+        header.push(format!("Reason: {}", reason));
+    }
+    if !package.recordings.is_empty() {
+        let ids: Vec<&str> = package.recordings.iter().map(|recording| recording.info.id.as_str()).collect();
+        header.push(format!("Recordings: {}", ids.join(", ")));
+    }
+    let header = header.join("\n");
+
+    let mut budget = MAX_LINK_BODY_CHARS.saturating_sub(header.chars().count());
+    let mut lines = Vec::new();
+    for message in package.transcript.iter().rev() {
+        let speaker = match message.role {
+            MessageRole::User => "User",
+            MessageRole::Assistant => "Assistant",
+            MessageRole::System => continue,
+        };
+        let line = format!("{}: {}", speaker, message.content);
+        let length = line.chars().count() + 2;
+        if length > budget {
+            lines.push("[earlier messages left out]".to_string());
+            break;
+        }
+        budget -= length;
+        lines.push(line);
+    }
+    lines.reverse();
+
+    format!("{}\n\n{}", header, lines.join("\n\n"))
+}
+
+/// Saved recordings made at or after `since`, oldest first
+fn recordings_since(since: u64, attach_audio: bool) -> Vec<HandoffRecording> {
+    let store = match RecordingStore::open_default() {
+        Ok(store) => store,
+        Err(e) => {
+            tracing::warn!("Recordings left out of the handoff: {}", e);
+            return Vec::new();
+        }
+    };
+    let mut recordings: Vec<HandoffRecording> = store
+        .list()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|info| info.created_at >= since)
+        .map(|info| HandoffRecording {
+            path: store.audio_path(&info).display().to_string(),
+            audio: attach_audio.then(|| store.read(&info.id).ok()).flatten().map(|audio| BASE64.encode(audio)),
+            info,
+        })
+        .collect();
+    recordings.reverse();
+    recordings
+}
+
+fn local_time(timestamp: u64) -> String {
+    DateTime::from_timestamp(timestamp as i64, 0)
+        .map(|time| time.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: MessageRole, content: &str) -> Message {
+        Message {
+            id: String::new(),
+            role,
+            content: content.to_string(),
+            timestamp: 0,
+            stages: None,
+            variant: None,
+            rating: None,
+        }
+    }
+
+    fn handoff(transcript: Vec<Message>) -> HandoffPackage {
+        HandoffPackage {
+            conversation_id: "c1".to_string(),
+            started_at: 0,
+            requested_at: 0,
+            reason: Some("Unit still won't start".to_string()),
+            app_version: "0.1.0".to_string(),
+            llm_provider: "openwebui".to_string(),
+            model: Some("llama3".to_string()),
+            locale: "en".to_string(),
+            variant: None,
+            transcript,
+            recordings: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_email_link_is_encoded() {
+        let config = HandoffConfig {
+            method: HandoffMethod::Email,
+            email: "support@example.com".to_string(),
+            ..HandoffConfig::default()
+        };
+        let package = handoff(vec![message(MessageRole::User, "Reset & retry?")]);
+
+        let link = link(&config, &package).unwrap();
+        assert!(link.starts_with("mailto:support@example.com?subject=Assistant%20handoff%3A%20Unit%20still"));
+        assert!(link.contains("User%3A%20Reset%20%26%20retry%3F"));
+    }
+
+    #[test]
+    fn test_body_keeps_latest_messages_that_fit() {
+        let long = "x".repeat(MAX_LINK_BODY_CHARS / 2);
+        let package = handoff(vec![
+            message(MessageRole::User, &long),
+            message(MessageRole::Assistant, &long),
+            message(MessageRole::System, "hidden"),
+            message(MessageRole::User, "Can a person help?"),
+        ]);
+
+        let body = body(&package);
+        assert!(body.contains("Reason: Unit still won't start"));
+        assert!(body.contains("[earlier messages left out]"));
+        assert!(body.ends_with(&format!("Assistant: {}\n\nUser: Can a person help?", long)));
+        assert!(!body.contains("hidden"));
+    }
+}
@@ -0,0 +1,799 @@
+//! Persistent conversation history
+//!
+//! Stores conversations and their messages in a local SQLite database under the
+//! app data directory so they survive restarts, and applies the retention policy
+//! configured in [`crate::config::PrivacyConfig`]. Message contents are encrypted
+//! at rest (see [`crate::crypto`]) unless encryption is turned off.
+//!
+//! Conversations idle for `privacy.archive_after_days` are archived: they leave
+//! the list of active conversations but stay stored and listable until restored,
+//! or until a new message is added to them.
+
+use crate::config::RetentionPolicy;
+use crate::crypto::{self, StorageCipher};
+use crate::error::{AppResult, StorageError};
+use crate::state::{ConversationContext, ConversationSettings, Message, MessageRole, Rating, Thumbs};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS conversations (
+        id TEXT PRIMARY KEY,
+        started_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL,
+        archived_at INTEGER
+    );
+    CREATE TABLE IF NOT EXISTS messages (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        conversation_id TEXT NOT NULL REFERENCES conversations(id) ON DELETE CASCADE,
+        role TEXT NOT NULL,
+        content TEXT NOT NULL,
+        timestamp INTEGER NOT NULL,
+        variant TEXT,
+        uid TEXT,
+        rating TEXT,
+        rating_comment TEXT,
+        rated_at INTEGER
+    );
+    CREATE INDEX IF NOT EXISTS idx_messages_conversation ON messages(conversation_id);
+    CREATE TABLE IF NOT EXISTS meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+";
+
+/// Meta key holding the base64 salt used to derive the passphrase key
+const META_PASSPHRASE_SALT: &str = "passphrase_salt";
+
+/// Meta key holding a known value encrypted with the passphrase key
+const META_PASSPHRASE_CHECK: &str = "passphrase_check";
+
+/// Plaintext of the passphrase check value
+const PASSPHRASE_CHECK_VALUE: &str = "talk-to-cmac";
+
+/// Summary of a stored conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationSummary {
+    /// Conversation ID
+    pub id: String,
+
+    /// Number of stored messages
+    pub message_count: usize,
+
+    /// Started timestamp
+    pub started_at: u64,
+
+    /// Last updated timestamp
+    pub updated_at: u64,
+
+    /// When the conversation was archived, if it is
+    pub archived_at: Option<u64>,
+}
+
+/// Encryption state of the history store
+#[derive(Default)]
+struct Encryption {
+    /// Cipher for reading and writing message contents (None if no key is available)
+    cipher: Option<StorageCipher>,
+
+    /// Whether new messages are encrypted
+    enabled: bool,
+
+    /// Whether a passphrase must be entered before history can be used
+    locked: bool,
+}
+
+/// Columns added since the first release (table, column, type), added to older databases on open
+const ADDED_COLUMNS: &[(&str, &str, &str)] = &[
+    ("conversations", "archived_at", "INTEGER"),
+    ("messages", "variant", "TEXT"),
+    ("messages", "uid", "TEXT"),
+    ("messages", "rating", "TEXT"),
+    ("messages", "rating_comment", "TEXT"),
+    ("messages", "rated_at", "INTEGER"),
+];
+
+/// Enable foreign keys and create the tables of a new database, or add columns an older one lacks
+fn prepare(conn: Connection) -> AppResult<Connection> {
+    conn.execute_batch("PRAGMA foreign_keys = ON;")
+        .and_then(|_| conn.execute_batch(SCHEMA))
+        .and_then(|_| {
+            for (table, column, kind) in ADDED_COLUMNS {
+                let present = conn
+                    .prepare(&format!("SELECT 1 FROM pragma_table_info('{}') WHERE name = ?1", table))?
+                    .exists([column])?;
+                if !present {
+                    conn.execute_batch(&format!("ALTER TABLE {} ADD COLUMN {} {};", table, column, kind))?;
+                }
+            }
+            Ok(())
+        })
+        .map_err(|e| StorageError::OpenFailed(e.to_string()))?;
+    Ok(conn)
+}
+
+/// SQLite-backed conversation history
+pub struct HistoryStore {
+    conn: Mutex<Connection>,
+    encryption: Mutex<Encryption>,
+}
+
+impl HistoryStore {
+    /// Open (and create if needed) the history database at `path`
+    pub fn open(path: &Path) -> AppResult<Self> {
+        let conn = Connection::open(path).map_err(|e| StorageError::OpenFailed(e.to_string()))?;
+        Self::init(conn)
+    }
+
+    /// Open a history store that lives only in memory
+    pub fn open_in_memory() -> AppResult<Self> {
+        let conn = Connection::open_in_memory().map_err(|e| StorageError::OpenFailed(e.to_string()))?;
+        Self::init(conn)
+    }
+
+    fn init(conn: Connection) -> AppResult<Self> {
+        Ok(Self {
+            conn: Mutex::new(prepare(conn)?),
+            encryption: Mutex::new(Encryption::default()),
+        })
+    }
+
+    /// Switch to the database at `path`, such as another profile's
+    ///
+    /// The store is locked until [`HistoryStore::configure_encryption`] sets it up
+    /// for the new database.
+    pub fn reopen(&self, path: &Path) -> AppResult<()> {
+        let conn = Connection::open(path).map_err(|e| StorageError::OpenFailed(e.to_string()))?;
+        let conn = prepare(conn)?;
+
+        *self.encryption.lock().unwrap() = Encryption {
+            locked: true,
+            ..Encryption::default()
+        };
+        *self.conn.lock().unwrap() = conn;
+        Ok(())
+    }
+
+    /// Set up at-rest encryption on launch
+    ///
+    /// If a passphrase has been set the store starts locked until [`HistoryStore::unlock`]
+    /// succeeds. Otherwise `keyring_key` supplies the key; if it is unavailable while
+    /// encryption is enabled, the store stays locked rather than writing plaintext.
+    pub fn configure_encryption(
+        &self,
+        enabled: bool,
+        keyring_key: impl FnOnce() -> AppResult<[u8; 32]>,
+    ) -> AppResult<()> {
+        if self.has_passphrase()? {
+            *self.encryption.lock().unwrap() = Encryption {
+                cipher: None,
+                enabled: true,
+                locked: true,
+            };
+            tracing::info!("History is passphrase protected and locked");
+            return Ok(());
+        }
+
+        let cipher = match keyring_key() {
+            Ok(key) => Some(StorageCipher::from_key(&key)),
+            Err(e) => {
+                tracing::warn!("History encryption key unavailable: {}", e);
+                None
+            }
+        };
+
+        let locked = enabled && cipher.is_none();
+        if locked {
+            tracing::error!("History encryption is enabled but no key is available; history will not be saved");
+        }
+
+        *self.encryption.lock().unwrap() = Encryption { cipher, enabled, locked };
+        Ok(())
+    }
+
+    /// Whether history is waiting for a passphrase (or has no usable key)
+    pub fn is_locked(&self) -> bool {
+        self.encryption.lock().unwrap().locked
+    }
+
+    /// Whether a passphrase has been set for this history
+    pub fn has_passphrase(&self) -> AppResult<bool> {
+        Ok(self.get_meta(META_PASSPHRASE_SALT)?.is_some())
+    }
+
+    /// Unlock passphrase-protected history
+    pub fn unlock(&self, passphrase: &str) -> AppResult<()> {
+        let (Some(salt), Some(check)) = (
+            self.get_meta(META_PASSPHRASE_SALT)?,
+            self.get_meta(META_PASSPHRASE_CHECK)?,
+        ) else {
+            return Err(StorageError::DecryptionFailed("No passphrase has been set".to_string()).into());
+        };
+
+        let salt = BASE64
+            .decode(salt)
+            .map_err(|e| StorageError::DecryptionFailed(e.to_string()))?;
+        let cipher = StorageCipher::from_passphrase(passphrase, &salt)?;
+
+        match cipher.decrypt(&check) {
+            Ok(value) if value == PASSPHRASE_CHECK_VALUE => {}
+            _ => return Err(StorageError::WrongPassphrase.into()),
+        }
+
+        *self.encryption.lock().unwrap() = Encryption {
+            cipher: Some(cipher),
+            enabled: true,
+            locked: false,
+        };
+        tracing::info!("History unlocked");
+        Ok(())
+    }
+
+    /// Set, change, or remove (`None`) the history passphrase
+    ///
+    /// All stored messages are re-encrypted with the new key. Removing the
+    /// passphrase switches back to the key held in the keyring.
+    pub fn set_passphrase(
+        &self,
+        passphrase: Option<&str>,
+        keyring_key: impl FnOnce() -> AppResult<[u8; 32]>,
+    ) -> AppResult<()> {
+        let mut encryption = self.encryption.lock().unwrap();
+        if encryption.locked {
+            return Err(StorageError::Locked.into());
+        }
+
+        let (new_cipher, salt) = match passphrase {
+            Some(passphrase) => {
+                let salt = StorageCipher::generate_salt();
+                (StorageCipher::from_passphrase(passphrase, &salt)?, Some(salt))
+            }
+            None => (StorageCipher::from_key(&keyring_key()?), None),
+        };
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().map_err(|e| StorageError::QueryFailed(e.to_string()))?;
+
+        let rows: Vec<(i64, String, Option<String>)> = {
+            let mut stmt = tx
+                .prepare("SELECT id, content, rating_comment FROM messages")
+                .map_err(|e| StorageError::QueryFailed(e.to_string()))?;
+            let rows = stmt
+                .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+                .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+                .map_err(|e| StorageError::QueryFailed(e.to_string()))?;
+            rows
+        };
+
+        for (id, content, comment) in rows {
+            let plaintext = decode_content(encryption.cipher.as_ref(), &content)?;
+            let comment = comment
+                .map(|comment| decode_content(encryption.cipher.as_ref(), &comment).and_then(|c| new_cipher.encrypt(&c)))
+                .transpose()?;
+            tx.execute(
+                "UPDATE messages SET content = ?1, rating_comment = ?2 WHERE id = ?3",
+                params![new_cipher.encrypt(&plaintext)?, comment, id],
+            )
+            .map_err(|e| StorageError::QueryFailed(e.to_string()))?;
+        }
+
+        match salt {
+            Some(salt) => {
+                let check = new_cipher.encrypt(PASSPHRASE_CHECK_VALUE)?;
+                tx.execute(
+                    "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2), (?3, ?4)",
+                    params![META_PASSPHRASE_SALT, BASE64.encode(salt), META_PASSPHRASE_CHECK, check],
+                )
+            }
+            None => tx.execute(
+                "DELETE FROM meta WHERE key IN (?1, ?2)",
+                params![META_PASSPHRASE_SALT, META_PASSPHRASE_CHECK],
+            ),
+        }
+        .map_err(|e| StorageError::QueryFailed(e.to_string()))?;
+
+        tx.commit().map_err(|e| StorageError::QueryFailed(e.to_string()))?;
+
+        encryption.cipher = Some(new_cipher);
+        encryption.enabled = encryption.enabled || passphrase.is_some();
+        tracing::info!(
+            "History passphrase {}",
+            if passphrase.is_some() { "set" } else { "removed" }
+        );
+        Ok(())
+    }
+
+    /// `text` as it is stored: encrypted unless encryption is off
+    fn encode(&self, text: &str) -> AppResult<String> {
+        let encryption = self.encryption.lock().unwrap();
+        if encryption.locked {
+            return Err(StorageError::Locked.into());
+        }
+        match (&encryption.cipher, encryption.enabled) {
+            (Some(cipher), true) => cipher.encrypt(text),
+            (None, true) => Err(StorageError::Locked.into()),
+            (_, false) => Ok(text.to_string()),
+        }
+    }
+
+    /// Append a message to a conversation, creating the conversation if needed
+    pub fn append_message(&self, conversation: &ConversationContext, message: &Message) -> AppResult<()> {
+        let content = self.encode(&message.content)?;
+        let rating = message.rating.as_ref();
+        let comment = rating
+            .and_then(|rating| rating.comment.as_deref())
+            .map(|comment| self.encode(comment))
+            .transpose()?;
+
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute(
+            "INSERT INTO conversations (id, started_at, updated_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(id) DO UPDATE SET updated_at = excluded.updated_at, archived_at = NULL",
+            params![conversation.id, conversation.started_at as i64, message.timestamp as i64],
+        )
+        .and_then(|_| {
+            conn.execute(
+                "INSERT INTO messages (conversation_id, role, content, timestamp, variant, uid, rating, rating_comment, rated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    conversation.id,
+                    role_to_str(&message.role),
+                    content,
+                    message.timestamp as i64,
+                    message.variant,
+                    message.id,
+                    rating.map(|rating| thumbs_to_str(rating.thumbs)),
+                    comment,
+                    rating.map(|rating| rating.rated_at as i64),
+                ],
+            )
+        })
+        .map_err(|e| StorageError::QueryFailed(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// Store the rating of a message, returning whether the message was found
+    ///
+    /// Messages are found by the ID they had in the app, or, for messages stored
+    /// before those IDs were kept, by the ID they were loaded with.
+    pub fn rate_message(&self, conversation_id: &str, message_id: &str, rating: &Rating) -> AppResult<bool> {
+        let comment = rating.comment.as_deref().map(|comment| self.encode(comment)).transpose()?;
+
+        let conn = self.conn.lock().unwrap();
+        let updated = conn
+            .execute(
+                "UPDATE messages SET rating = ?1, rating_comment = ?2, rated_at = ?3
+                 WHERE conversation_id = ?4 AND (uid = ?5 OR ((uid IS NULL OR uid = '') AND CAST(id AS TEXT) = ?5))",
+                params![thumbs_to_str(rating.thumbs), comment, rating.rated_at as i64, conversation_id, message_id],
+            )
+            .map_err(|e| StorageError::QueryFailed(e.to_string()))?;
+        Ok(updated > 0)
+    }
+
+    /// List stored conversations, active or `archived`, most recently updated first
+    pub fn list_conversations(&self, archived: bool) -> AppResult<Vec<ConversationSummary>> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT c.id, c.started_at, c.updated_at, c.archived_at, COUNT(m.id)
+                 FROM conversations c LEFT JOIN messages m ON m.conversation_id = c.id
+                 WHERE (c.archived_at IS NOT NULL) = ?1
+                 GROUP BY c.id ORDER BY c.updated_at DESC",
+            )
+            .map_err(|e| StorageError::QueryFailed(e.to_string()))?;
+
+        let rows = stmt
+            .query_map(params![archived], |row| {
+                Ok(ConversationSummary {
+                    id: row.get(0)?,
+                    started_at: row.get::<_, i64>(1)? as u64,
+                    updated_at: row.get::<_, i64>(2)? as u64,
+                    archived_at: row.get::<_, Option<i64>>(3)?.map(|archived_at| archived_at as u64),
+                    message_count: row.get::<_, i64>(4)? as usize,
+                })
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| StorageError::QueryFailed(e.to_string()))?;
+
+        Ok(rows)
+    }
+
+    /// Load a stored conversation with all of its messages
+    pub fn get_conversation(&self, id: &str, max_messages: usize) -> AppResult<Option<ConversationContext>> {
+        let cipher = {
+            let encryption = self.encryption.lock().unwrap();
+            if encryption.locked {
+                return Err(StorageError::Locked.into());
+            }
+            encryption.cipher.clone()
+        };
+
+        let conn = self.conn.lock().unwrap();
+
+        let header = conn
+            .query_row(
+                "SELECT started_at, updated_at FROM conversations WHERE id = ?1",
+                params![id],
+                |row| Ok((row.get::<_, i64>(0)? as u64, row.get::<_, i64>(1)? as u64)),
+            )
+            .optional()
+            .map_err(|e| StorageError::QueryFailed(e.to_string()))?;
+
+        let Some((started_at, updated_at)) = header else {
+            return Ok(None);
+        };
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, role, content, timestamp, variant, uid, rating, rating_comment, rated_at
+                 FROM messages WHERE conversation_id = ?1 ORDER BY id",
+            )
+            .map_err(|e| StorageError::QueryFailed(e.to_string()))?;
+
+        let rows = stmt
+            .query_map(params![id], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                    (
+                        row.get::<_, Option<String>>(6)?,
+                        row.get::<_, Option<String>>(7)?,
+                        row.get::<_, Option<i64>>(8)?,
+                    ),
+                ))
+            })
+            .and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| StorageError::QueryFailed(e.to_string()))?;
+
+        let messages = rows
+            .into_iter()
+            .map(|(id, role, content, timestamp, variant, uid, (thumbs, comment, rated_at))| {
+                let rating = match thumbs.as_deref().and_then(thumbs_from_str) {
+                    Some(thumbs) => Some(Rating {
+                        thumbs,
+                        comment: comment.map(|comment| decode_content(cipher.as_ref(), &comment)).transpose()?,
+                        rated_at: rated_at.unwrap_or_default() as u64,
+                    }),
+                    None => None,
+                };
+                Ok(Message {
+                    id: uid.filter(|uid| !uid.is_empty()).unwrap_or_else(|| id.to_string()),
+                    role: role_from_str(&role),
+                    content: decode_content(cipher.as_ref(), &content)?,
+                    timestamp: timestamp as u64,
+                    stages: None,
+                    variant,
+                    rating,
+                })
+            })
+            .collect::<AppResult<Vec<_>>>()?;
+
+        // A restored conversation keeps the prompt variant it was assigned
+        let variant = messages.iter().rev().find_map(|message| message.variant.clone());
+        Ok(Some(ConversationContext {
+            id: id.to_string(),
+            messages,
+            max_messages,
+            started_at,
+            updated_at,
+            ephemeral: false,
+            settings: ConversationSettings::default(),
+            variant,
+        }))
+    }
+
+    /// Delete conversations the retention policy no longer allows, returning how many were removed
+    pub fn apply_retention(&self, policy: &RetentionPolicy) -> AppResult<usize> {
+        let conn = self.conn.lock().unwrap();
+
+        let result = match *policy {
+            RetentionPolicy::Days { days: 0 } => Ok(0),
+            RetentionPolicy::Days { days } => {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+                let cutoff = now.saturating_sub(u64::from(days) * 24 * 60 * 60);
+                conn.execute("DELETE FROM conversations WHERE updated_at < ?1", params![cutoff as i64])
+            }
+            RetentionPolicy::Conversations { count } => conn.execute(
+                "DELETE FROM conversations WHERE id NOT IN
+                 (SELECT id FROM conversations ORDER BY updated_at DESC LIMIT ?1)",
+                params![count as i64],
+            ),
+            RetentionPolicy::Nothing => conn.execute("DELETE FROM conversations", []),
+        };
+        let deleted = result.map_err(|e| StorageError::QueryFailed(e.to_string()))?;
+
+        if deleted > 0 {
+            tracing::info!("Retention removed {} stored conversation(s)", deleted);
+        }
+        Ok(deleted)
+    }
+
+    /// Archive conversations not updated for `days` (0 = never), returning how many were archived
+    pub fn archive_idle(&self, days: u32) -> AppResult<usize> {
+        if days == 0 {
+            return Ok(0);
+        }
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let cutoff = now.saturating_sub(u64::from(days) * 24 * 60 * 60);
+        let archived = self
+            .conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE conversations SET archived_at = ?1 WHERE archived_at IS NULL AND updated_at < ?2",
+                params![now as i64, cutoff as i64],
+            )
+            .map_err(|e| StorageError::QueryFailed(e.to_string()))?;
+
+        if archived > 0 {
+            tracing::info!("Archived {} conversation(s) idle for {} days", archived, days);
+        }
+        Ok(archived)
+    }
+
+    /// Move an archived conversation back to the active list, returning whether it was archived
+    pub fn restore_conversation(&self, id: &str) -> AppResult<bool> {
+        let restored = self
+            .conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE conversations SET archived_at = NULL WHERE id = ?1 AND archived_at IS NOT NULL",
+                params![id],
+            )
+            .map_err(|e| StorageError::QueryFailed(e.to_string()))?;
+        Ok(restored > 0)
+    }
+
+    /// Delete every stored conversation and reclaim the space on disk
+    pub fn purge_all(&self) -> AppResult<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute_batch("DELETE FROM messages; DELETE FROM conversations; VACUUM;")
+            .map_err(|e| StorageError::QueryFailed(e.to_string()))?;
+
+        tracing::info!("Conversation history purged");
+        Ok(())
+    }
+}
+
+impl HistoryStore {
+    fn get_meta(&self, key: &str) -> AppResult<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row("SELECT value FROM meta WHERE key = ?1", params![key], |row| row.get(0))
+            .optional()
+            .map_err(|e| StorageError::QueryFailed(e.to_string()).into())
+    }
+}
+
+/// Decrypt stored message content, passing through rows written as plaintext
+fn decode_content(cipher: Option<&StorageCipher>, content: &str) -> AppResult<String> {
+    match cipher {
+        Some(cipher) => cipher.decrypt(content),
+        None if crypto::is_encrypted(content) => {
+            Err(StorageError::DecryptionFailed("No encryption key available".to_string()).into())
+        }
+        None => Ok(content.to_string()),
+    }
+}
+
+fn thumbs_to_str(thumbs: Thumbs) -> &'static str {
+    match thumbs {
+        Thumbs::Up => "up",
+        Thumbs::Down => "down",
+    }
+}
+
+fn thumbs_from_str(thumbs: &str) -> Option<Thumbs> {
+    match thumbs {
+        "up" => Some(Thumbs::Up),
+        "down" => Some(Thumbs::Down),
+        _ => None,
+    }
+}
+
+fn role_to_str(role: &MessageRole) -> &'static str {
+    match role {
+        MessageRole::User => "user",
+        MessageRole::Assistant => "assistant",
+        MessageRole::System => "system",
+    }
+}
+
+fn role_from_str(role: &str) -> MessageRole {
+    match role {
+        "assistant" => MessageRole::Assistant,
+        "system" => MessageRole::System,
+        _ => MessageRole::User,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conversation(id: &str, updated_at: u64) -> ConversationContext {
+        ConversationContext {
+            id: id.to_string(),
+            messages: Vec::new(),
+            max_messages: 20,
+            started_at: updated_at,
+            updated_at,
+            ephemeral: false,
+            settings: ConversationSettings::default(),
+            variant: None,
+        }
+    }
+
+    fn message(content: &str, timestamp: u64) -> Message {
+        Message {
+            id: String::new(),
+            role: MessageRole::User,
+            content: content.to_string(),
+            timestamp,
+            stages: None,
+            variant: None,
+            rating: None,
+        }
+    }
+
+    #[test]
+    fn test_append_and_load_conversation() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let conv = conversation("abc", 100);
+
+        store.append_message(&conv, &message("Hello", 100)).unwrap();
+        store.append_message(&conv, &message("Again", 101)).unwrap();
+
+        let loaded = store.get_conversation("abc", 20).unwrap().unwrap();
+        assert_eq!(loaded.messages.len(), 2);
+        assert_eq!(loaded.messages[1].content, "Again");
+        assert_eq!(loaded.updated_at, 101);
+        assert!(store.get_conversation("missing", 20).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_rating_is_stored_with_message() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let conv = conversation("abc", 100);
+        let reply = Message {
+            id: "m-2".to_string(),
+            role: MessageRole::Assistant,
+            ..message("The filter is behind the lower panel.", 101)
+        };
+        store.append_message(&conv, &message("Where is the filter?", 100)).unwrap();
+        store.append_message(&conv, &reply).unwrap();
+
+        let rating = Rating {
+            thumbs: Thumbs::Down,
+            comment: Some("Wrong model".to_string()),
+            rated_at: 102,
+        };
+        assert!(store.rate_message("abc", "m-2", &rating).unwrap());
+        assert!(!store.rate_message("abc", "m-9", &rating).unwrap());
+
+        let loaded = store.get_conversation("abc", 20).unwrap().unwrap();
+        assert_eq!(loaded.messages[1].id, "m-2");
+        assert_eq!(loaded.messages[1].rating, Some(rating));
+        assert_eq!(loaded.messages[0].rating, None);
+    }
+
+    #[test]
+    fn test_retention_by_count_keeps_most_recent() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        for (i, id) in ["a", "b", "c"].iter().enumerate() {
+            store.append_message(&conversation(id, 0), &message("hi", i as u64)).unwrap();
+        }
+
+        assert_eq!(store.apply_retention(&RetentionPolicy::Conversations { count: 1 }).unwrap(), 2);
+        let remaining = store.list_conversations(false).unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, "c");
+        assert_eq!(remaining[0].message_count, 1);
+    }
+
+    #[test]
+    fn test_encrypted_storage_and_passphrase() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let key = StorageCipher::generate_key();
+        store.configure_encryption(true, || Ok(key)).unwrap();
+
+        store.append_message(&conversation("abc", 1), &message("my secret", 1)).unwrap();
+        let raw: String = store
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT content FROM messages", [], |row| row.get(0))
+            .unwrap();
+        assert!(crypto::is_encrypted(&raw));
+
+        store.set_passphrase(Some("hunter2"), || Ok(key)).unwrap();
+        store.configure_encryption(true, || Ok(key)).unwrap();
+        assert!(store.is_locked());
+        assert!(store.get_conversation("abc", 20).is_err());
+
+        assert!(store.unlock("wrong").is_err());
+        store.unlock("hunter2").unwrap();
+        let loaded = store.get_conversation("abc", 20).unwrap().unwrap();
+        assert_eq!(loaded.messages[0].content, "my secret");
+    }
+
+    #[test]
+    fn test_retention_by_days_and_purge() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        store.append_message(&conversation("old", 0), &message("old", 0)).unwrap();
+        store.append_message(&conversation("new", now), &message("new", now)).unwrap();
+
+        assert_eq!(store.apply_retention(&RetentionPolicy::Days { days: 30 }).unwrap(), 1);
+        assert!(store.get_conversation("new", 20).unwrap().is_some());
+
+        store.purge_all().unwrap();
+        assert!(store.list_conversations(false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_idle_conversations_are_archived_and_restored() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        store.append_message(&conversation("idle", 0), &message("old", 0)).unwrap();
+        store.append_message(&conversation("recent", now), &message("new", now)).unwrap();
+
+        assert_eq!(store.archive_idle(0).unwrap(), 0);
+        assert_eq!(store.archive_idle(30).unwrap(), 1);
+        let active = store.list_conversations(false).unwrap();
+        let archived = store.list_conversations(true).unwrap();
+        assert_eq!((active.len(), active[0].id.as_str()), (1, "recent"));
+        assert_eq!(archived[0].id, "idle");
+        assert!(archived[0].archived_at.is_some());
+        assert!(store.get_conversation("idle", 20).unwrap().is_some());
+
+        assert!(store.restore_conversation("idle").unwrap());
+        assert!(!store.restore_conversation("idle").unwrap());
+        assert!(store.list_conversations(true).unwrap().is_empty());
+
+        // A new message brings an archived conversation back too
+        store.archive_idle(30).unwrap();
+        store.append_message(&conversation("idle", 0), &message("back", now)).unwrap();
+        assert_eq!(store.list_conversations(false).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_older_databases_gain_the_archive_column() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE conversations (id TEXT PRIMARY KEY, started_at INTEGER NOT NULL, updated_at INTEGER NOT NULL);
+             INSERT INTO conversations VALUES ('abc', 1, 1);",
+        )
+        .unwrap();
+        let store = HistoryStore::init(conn).unwrap();
+        assert_eq!(store.list_conversations(false).unwrap()[0].archived_at, None);
+    }
+
+    #[test]
+    fn test_reopen_switches_database() {
+        let store = HistoryStore::open_in_memory().unwrap();
+        store.append_message(&conversation("abc", 1), &message("first profile", 1)).unwrap();
+
+        let path = std::env::temp_dir().join(format!("talk-to-cmac-history-{:x}.db", rand::random::<u64>()));
+        store.reopen(&path).unwrap();
+        assert!(store.is_locked());
+
+        store.configure_encryption(false, || Ok(StorageCipher::generate_key())).unwrap();
+        assert!(!store.is_locked());
+        assert!(store.list_conversations(false).unwrap().is_empty());
+        let _ = std::fs::remove_file(path);
+    }
+}
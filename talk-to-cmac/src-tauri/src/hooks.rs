@@ -0,0 +1,197 @@
+//! Text hooks
+//!
+//! Hooks let a deployment rewrite text between pipeline stages without
+//! changing the app: expanding jargon after transcription, adding context
+//! before the LLM, stripping boilerplate from answers, or spelling out part
+//! numbers before speech. Each enabled hook for a stage runs in the order
+//! configured in `hooks`, each given the text the previous one returned.
+//!
+//! A command hook is given the text on standard input and the stage in the
+//! `TALK_TO_CMAC_HOOK_STAGE` environment variable; its standard output is the
+//! new text. A Rhai hook (with the `scripting` feature) sees `text` and
+//! `stage` variables; the script's result is the new text, or, if it returns
+//! nothing, whatever it left in `text`.
+//!
+//! A hook that fails, times out, or returns empty text is logged and skipped.
+
+use crate::config::{HookConfig, HookKind, HookStage};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Hides the console window hook commands would otherwise open on Windows
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+/// Environment variable naming the stage for command hooks
+const STAGE_VARIABLE: &str = "TALK_TO_CMAC_HOOK_STAGE";
+
+/// `text` after the enabled hooks for `stage`
+pub async fn run(hooks: &[HookConfig], stage: HookStage, mut text: String) -> String {
+    for hook in hooks.iter().filter(|hook| hook.enabled && hook.stage == stage) {
+        let result = match hook.kind {
+            HookKind::Command => run_command(hook, stage, &text).await,
+            HookKind::Rhai => run_script(hook, stage, &text).await,
+        };
+        match result {
+            Ok(rewritten) if !rewritten.trim().is_empty() => text = rewritten,
+            Ok(_) => tracing::warn!("Hook '{}' returned no text; keeping the text as it was", hook.name),
+            Err(e) => tracing::warn!("Hook '{}' failed: {}", hook.name, e),
+        }
+    }
+    text
+}
+
+/// Run the `pre_llm` hooks on the latest user message of `messages`, as sent to the LLM
+pub async fn run_on_query(hooks: &[HookConfig], messages: &mut [(String, String)]) {
+    if !any(hooks, HookStage::PreLlm) {
+        return;
+    }
+    if let Some((_, content)) = messages.iter_mut().rev().find(|(role, _)| role == "user") {
+        *content = run(hooks, HookStage::PreLlm, std::mem::take(content)).await;
+    }
+}
+
+/// Whether any enabled hook runs at `stage`
+pub fn any(hooks: &[HookConfig], stage: HookStage) -> bool {
+    hooks.iter().any(|hook| hook.enabled && hook.stage == stage)
+}
+
+/// Name of `stage` as given to hooks
+fn stage_name(stage: HookStage) -> &'static str {
+    match stage {
+        HookStage::PostTranscription => "post_transcription",
+        HookStage::PreLlm => "pre_llm",
+        HookStage::PostLlm => "post_llm",
+        HookStage::PreTts => "pre_tts",
+    }
+}
+
+async fn run_command(hook: &HookConfig, stage: HookStage, text: &str) -> Result<String, String> {
+    let mut command = Command::new(&hook.program);
+    command
+        .args(&hook.args)
+        .env(STAGE_VARIABLE, stage_name(stage))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(windows)]
+    command.creation_flags(CREATE_NO_WINDOW);
+
+    let mut child = command.spawn().map_err(|e| format!("cannot run {}: {}", hook.program, e))?;
+    let mut stdin = child.stdin.take().ok_or("no standard input")?;
+
+    // Input is written while the output is read, so a filter that streams its output
+    // cannot fill its pipe and wait on us while we wait on it
+    let input = text.to_string();
+    let writer = tokio::spawn(async move {
+        let written = stdin.write_all(input.as_bytes()).await;
+        drop(stdin);
+        written
+    });
+    let output = tokio::time::timeout(Duration::from_secs(hook.timeout_secs), child.wait_with_output())
+        .await
+        .map_err(|_| format!("timed out after {} s", hook.timeout_secs))?
+        .map_err(|e| e.to_string())?;
+
+    // A hook may exit without reading all of its input; only other write errors count
+    match writer.await.map_err(|e| e.to_string())? {
+        Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => return Err(e.to_string()),
+        _ => {}
+    }
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{}: {}", output.status, stderr.lines().last().unwrap_or_default().trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim_end().to_string())
+}
+
+#[cfg(feature = "scripting")]
+async fn run_script(hook: &HookConfig, stage: HookStage, text: &str) -> Result<String, String> {
+    use rhai::{Dynamic, Engine, ImmutableString, Scope};
+    use std::time::Instant;
+
+    let path = std::path::PathBuf::from(&hook.script);
+    let timeout = Duration::from_secs(hook.timeout_secs);
+    let text = text.to_string();
+
+    tokio::task::spawn_blocking(move || {
+        // Scripts are stopped once they run past the timeout
+        let started = Instant::now();
+        let mut engine = Engine::new();
+        engine.on_progress(move |_| (started.elapsed() > timeout).then_some(Dynamic::UNIT));
+
+        let mut scope = Scope::new();
+        scope.push("text", text);
+        scope.push_constant("stage", stage_name(stage).to_string());
+
+        let result = engine
+            .eval_file_with_scope::<Dynamic>(&mut scope, path)
+            .map_err(|e| e.to_string())?;
+        if result.is_unit() {
+            return scope
+                .get_value::<ImmutableString>("text")
+                .map(|text| text.to_string())
+                .ok_or_else(|| "the script removed `text`".to_string());
+        }
+        result.into_string().map_err(|kind| format!("the script returned {} instead of text", kind))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(not(feature = "scripting"))]
+async fn run_script(_hook: &HookConfig, _stage: HookStage, _text: &str) -> Result<String, String> {
+    Err("this build does not include Rhai scripting (the `scripting` feature)".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hook(stage: HookStage, program: &str, args: &[&str]) -> HookConfig {
+        HookConfig {
+            name: program.to_string(),
+            enabled: true,
+            stage,
+            kind: HookKind::Command,
+            program: program.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            script: String::new(),
+            timeout_secs: 5,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failing_hook_keeps_text() {
+        let hooks = [hook(HookStage::PreTts, "talk-to-cmac-no-such-hook", &[])];
+        assert_eq!(run(&hooks, HookStage::PreTts, "Unit 4500".to_string()).await, "Unit 4500");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_hooks_for_stage_run_in_order() {
+        let mut disabled = hook(HookStage::PostLlm, "sed", &["s/4500/9999/"]);
+        disabled.enabled = false;
+        let hooks = [
+            hook(HookStage::PostLlm, "tr", &["a-z", "A-Z"]),
+            hook(HookStage::PreTts, "sed", &["s/UNIT/unit/"]),
+            disabled,
+            hook(HookStage::PostLlm, "sed", &["s/UNIT/Model/"]),
+        ];
+
+        assert_eq!(run(&hooks, HookStage::PostLlm, "unit 4500 is offline".to_string()).await, "Model 4500 IS OFFLINE");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_large_text_through_streaming_filter() {
+        // Far more than a pipe buffer holds, so `cat` blocks on output until it is read
+        let text = "unit 4500 is offline\n".repeat(50_000);
+        let hooks = [hook(HookStage::PostLlm, "cat", &[])];
+        assert_eq!(run(&hooks, HookStage::PostLlm, text.clone()).await, text.trim_end());
+    }
+}
@@ -0,0 +1,278 @@
+//! Global hotkeys
+//!
+//! Each [`HotkeyAction`] can be bound to one global shortcut in `ui.hotkeys`.
+//! Bindings are registered at launch and can be changed while the app runs;
+//! a shortcut already bound to another action is refused, as is one held by
+//! another application.
+//!
+//! Muting, showing the window, opening the quick-ask popup, reading the
+//! selection aloud and the clipboard actions are handled here. The other
+//! actions concern recording and playback, which live in the frontend, so they
+//! are forwarded as a `hotkey` event (`{ action, pressed }`); only
+//! `push_to_talk` also reports the release.
+
+use crate::clipboard::ClipboardAction;
+use crate::commands;
+use crate::config::HotkeyAction;
+use crate::error::{AppError, AppResult, ConfigError};
+use crate::quick_ask;
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Modifiers, Shortcut, ShortcutState};
+
+/// A hotkey action and its shortcut
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotkeyBinding {
+    pub action: HotkeyAction,
+
+    /// Shortcut, if the action is bound
+    pub accelerator: Option<String>,
+
+    /// Whether the shortcut is currently registered with the operating system
+    pub registered: bool,
+}
+
+/// Result of checking a shortcut from the settings UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotkeyValidation {
+    /// The shortcut in canonical form (e.g. "Control+Shift+K")
+    pub normalized: String,
+
+    /// Action already bound to the same keys
+    pub bound_to: Option<HotkeyAction>,
+
+    /// Whether the app can register the shortcut (false if another application holds it)
+    pub available: bool,
+}
+
+/// Payload of the `hotkey` event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotkeyEvent {
+    pub action: HotkeyAction,
+
+    /// True when the keys went down, false when they were released
+    pub pressed: bool,
+}
+
+/// Parse a shortcut such as "CommandOrControl+Shift+Space"
+pub fn parse(accelerator: &str) -> AppResult<Shortcut> {
+    accelerator
+        .parse()
+        .map_err(|e| ConfigError::InvalidValue(format!("Invalid shortcut '{}': {}", accelerator, e)).into())
+}
+
+/// Canonical form of a shortcut: modifiers in a fixed order, then the key ("Control+Alt+Shift+Super+K")
+///
+/// The result parses back to the same shortcut. `CommandOrControl` resolves to the
+/// platform's modifier.
+pub fn normalize(shortcut: &Shortcut) -> String {
+    let mut parts: Vec<String> = [
+        (Modifiers::CONTROL, "Control"),
+        (Modifiers::ALT, "Alt"),
+        (Modifiers::SHIFT, "Shift"),
+        (Modifiers::SUPER, "Super"),
+    ]
+    .into_iter()
+    .filter(|(modifier, _)| shortcut.mods.contains(*modifier))
+    .map(|(_, name)| name.to_string())
+    .collect();
+
+    let key = shortcut.key.to_string();
+    let key = key
+        .strip_prefix("Key")
+        .or_else(|| key.strip_prefix("Digit"))
+        .filter(|rest| rest.len() == 1)
+        .unwrap_or(&key);
+    parts.push(key.to_string());
+    parts.join("+")
+}
+
+/// Action other than `action` already bound to the same keys as `shortcut`
+///
+/// Shortcuts are compared after parsing, so "Ctrl+Shift+K" and "shift+control+k"
+/// conflict. Unparseable bindings never conflict.
+pub fn find_conflict(
+    hotkeys: &BTreeMap<HotkeyAction, String>,
+    action: HotkeyAction,
+    shortcut: &Shortcut,
+) -> Option<HotkeyAction> {
+    hotkeys
+        .iter()
+        .filter(|(other, _)| **other != action)
+        .find(|(_, accelerator)| parse(accelerator).is_ok_and(|bound| bound.id() == shortcut.id()))
+        .map(|(other, _)| *other)
+}
+
+/// Every action with its binding
+pub fn bindings(
+    hotkeys: &BTreeMap<HotkeyAction, String>,
+    is_registered: impl Fn(&Shortcut) -> bool,
+) -> Vec<HotkeyBinding> {
+    HotkeyAction::ALL
+        .into_iter()
+        .map(|action| {
+            let accelerator = hotkeys.get(&action).cloned();
+            HotkeyBinding {
+                action,
+                registered: accelerator
+                    .as_deref()
+                    .and_then(|accelerator| parse(accelerator).ok())
+                    .is_some_and(|shortcut| is_registered(&shortcut)),
+                accelerator,
+            }
+        })
+        .collect()
+}
+
+/// Register every configured hotkey, logging the ones that fail
+pub fn register_all(app: &AppHandle, hotkeys: &BTreeMap<HotkeyAction, String>) {
+    for (action, accelerator) in hotkeys {
+        match register(app, *action, accelerator) {
+            Ok(()) => tracing::info!("Registered {} hotkey: {}", action.name(), accelerator),
+            Err(e) => tracing::warn!("Failed to register {} hotkey: {}", action.name(), e),
+        }
+    }
+}
+
+/// Register `accelerator` to trigger `action`
+pub fn register(app: &AppHandle, action: HotkeyAction, accelerator: &str) -> AppResult<()> {
+    let shortcut = parse(accelerator)?;
+    app.global_shortcut()
+        .on_shortcut(shortcut, move |app, _shortcut, event| trigger(app, action, event.state == ShortcutState::Pressed))
+        .map_err(|e| {
+            AppError::from(ConfigError::InvalidValue(format!(
+                "Shortcut '{}' is unavailable, possibly taken by another application: {}",
+                accelerator, e
+            )))
+        })
+}
+
+/// Release `accelerator` if it is registered
+pub fn unregister(app: &AppHandle, accelerator: &str) {
+    let Ok(shortcut) = parse(accelerator) else {
+        return;
+    };
+    let shortcuts = app.global_shortcut();
+    if shortcuts.is_registered(shortcut) {
+        if let Err(e) = shortcuts.unregister(shortcut) {
+            tracing::warn!("Failed to unregister hotkey {}: {}", accelerator, e);
+        }
+    }
+}
+
+/// Whether the app holds or could register `shortcut`
+///
+/// A shortcut not yet held is registered and released again, which fails if
+/// another application has it.
+pub fn is_available(app: &AppHandle, shortcut: &Shortcut) -> bool {
+    let shortcuts = app.global_shortcut();
+    if shortcuts.is_registered(*shortcut) {
+        return true;
+    }
+    match shortcuts.register(*shortcut) {
+        Ok(()) => {
+            if let Err(e) = shortcuts.unregister(*shortcut) {
+                tracing::warn!("Failed to release test registration of {}: {}", normalize(shortcut), e);
+            }
+            true
+        }
+        Err(e) => {
+            tracing::debug!("Shortcut {} is unavailable: {}", normalize(shortcut), e);
+            false
+        }
+    }
+}
+
+/// Whether `shortcut` is registered by this app
+pub fn is_registered(app: &AppHandle, shortcut: &Shortcut) -> bool {
+    app.global_shortcut().is_registered(*shortcut)
+}
+
+fn trigger(app: &AppHandle, action: HotkeyAction, pressed: bool) {
+    if !pressed && action != HotkeyAction::PushToTalk {
+        return;
+    }
+    tracing::debug!("Hotkey {} {}", action.name(), if pressed { "pressed" } else { "released" });
+
+    match action {
+        HotkeyAction::Mute => {
+            let muted = !app.state::<AppState>().is_mic_muted();
+            commands::apply_mic_mute(app, muted);
+        }
+        HotkeyAction::ShowOverlay => {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.unminimize();
+                let _ = window.show();
+                let _ = window.set_always_on_top(true);
+                let _ = window.set_focus();
+            }
+        }
+        HotkeyAction::QuickAsk => {
+            if let Err(e) = quick_ask::toggle(app) {
+                tracing::warn!("{}", e);
+            }
+        }
+        HotkeyAction::SpeakSelection => {
+            tauri::async_runtime::spawn(commands::speak_selection_hotkey(app.clone()));
+        }
+        HotkeyAction::SummarizeClipboard => {
+            tauri::async_runtime::spawn(commands::clipboard_action_hotkey(app.clone(), ClipboardAction::Summarize));
+        }
+        HotkeyAction::TranslateClipboard => {
+            tauri::async_runtime::spawn(commands::clipboard_action_hotkey(app.clone(), ClipboardAction::Translate));
+        }
+        _ => {
+            if let Err(e) = app.emit("hotkey", HotkeyEvent { action, pressed }) {
+                tracing::warn!("Failed to emit hotkey: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conflicts_compare_parsed_shortcuts() {
+        let hotkeys = BTreeMap::from([
+            (HotkeyAction::ToggleRecord, "CommandOrControl+Shift+Space".to_string()),
+            (HotkeyAction::Mute, "Ctrl+Shift+M".to_string()),
+        ]);
+
+        let shortcut = parse("shift+control+m").unwrap();
+        assert_eq!(find_conflict(&hotkeys, HotkeyAction::RepeatLast, &shortcut), Some(HotkeyAction::Mute));
+        // Rebinding an action to its own keys is not a conflict
+        assert_eq!(find_conflict(&hotkeys, HotkeyAction::Mute, &shortcut), None);
+        assert_eq!(find_conflict(&hotkeys, HotkeyAction::RepeatLast, &parse("Alt+R").unwrap()), None);
+        assert!(parse("Shift+NoSuchKey").is_err());
+    }
+
+    #[test]
+    fn test_normalized_shortcuts_round_trip() {
+        for (input, normalized) in [
+            ("shift+ctrl+k", "Control+Shift+K"),
+            ("Alt+Super+Digit5", "Alt+Super+5"),
+            ("CONTROL+F12", "Control+F12"),
+            ("Alt+Space", "Alt+Space"),
+        ] {
+            let shortcut = parse(input).unwrap();
+            assert_eq!(normalize(&shortcut), normalized);
+            assert_eq!(parse(normalized).unwrap(), shortcut);
+        }
+    }
+
+    #[test]
+    fn test_bindings_list_every_action() {
+        let hotkeys = BTreeMap::from([(HotkeyAction::PushToTalk, "Alt+Space".to_string())]);
+        let bindings = bindings(&hotkeys, |_| true);
+
+        assert_eq!(bindings.len(), HotkeyAction::ALL.len());
+        let push_to_talk = bindings.iter().find(|binding| binding.action == HotkeyAction::PushToTalk).unwrap();
+        assert_eq!(push_to_talk.accelerator.as_deref(), Some("Alt+Space"));
+        assert!(push_to_talk.registered);
+        assert!(bindings.iter().filter(|binding| binding.action != HotkeyAction::PushToTalk).all(|binding| !binding.registered));
+    }
+}
@@ -0,0 +1,65 @@
+//! Plain text from HTML
+//!
+//! Enough to pass email bodies and feed summaries to the LLM: tags, scripts,
+//! and styles are dropped and character references decoded. Layout is lost.
+
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Text of `html` without tags, scripts, styles, or the document head
+pub fn to_text(html: &str) -> String {
+    static BLOCKS: OnceLock<Regex> = OnceLock::new();
+    static TAGS: OnceLock<Regex> = OnceLock::new();
+    let blocks = BLOCKS.get_or_init(|| {
+        Regex::new(r"(?is)<(?:style|script|head)\b.*?</(?:style|script|head)>").expect("block pattern is valid")
+    });
+    let tags = TAGS.get_or_init(|| Regex::new(r"(?s)<[^>]*>").expect("tag pattern is valid"));
+
+    let text = blocks.replace_all(html, " ");
+    unescape(&tags.replace_all(&text, " "))
+}
+
+/// Decode character references (`&amp;`, `&#8217;`, `&#x2014;`, ...)
+pub fn unescape(text: &str) -> String {
+    static REFERENCE: OnceLock<Regex> = OnceLock::new();
+    let reference = REFERENCE
+        .get_or_init(|| Regex::new(r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z]+);").expect("reference pattern is valid"));
+
+    reference
+        .replace_all(text, |caps: &regex::Captures| {
+            let name = &caps[1];
+            let code = match name.strip_prefix('#') {
+                Some(hex) if hex.starts_with(['x', 'X']) => u32::from_str_radix(&hex[1..], 16).ok(),
+                Some(decimal) => decimal.parse().ok(),
+                None => match name {
+                    "amp" => Some('&' as u32),
+                    "lt" => Some('<' as u32),
+                    "gt" => Some('>' as u32),
+                    "quot" => Some('"' as u32),
+                    "apos" => Some('\'' as u32),
+                    "nbsp" => Some(' ' as u32),
+                    _ => None,
+                },
+            };
+            match code.and_then(char::from_u32) {
+                Some(c) => c.to_string(),
+                None => caps[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_text() {
+        let html = "<html><head><style>p { color: red; }</style></head>\
+            <body><p>Unit&nbsp;4500 &amp; 4600 &#8212; offline &copy;</p></body></html>";
+        assert_eq!(
+            to_text(html).split_whitespace().collect::<Vec<_>>().join(" "),
+            "Unit 4500 & 4600 \u{2014} offline &copy;"
+        );
+    }
+}
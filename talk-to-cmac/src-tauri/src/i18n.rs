@@ -0,0 +1,155 @@
+//! Localization of backend-generated text
+//!
+//! Spoken replies to voice commands, command errors, and tray labels are looked
+//! up by message id in the locale set as `ui.locale`. Messages are kept in
+//! Fluent files under `locales/`, one per language, embedded at build time.
+//! Only the part of Fluent these messages need is read: `id = text` lines with
+//! `{ $name }` placeables, and comments.
+//!
+//! A locale with a region ("es-MX") uses its language's messages, and a message
+//! missing from a locale falls back to English. Error messages are translated
+//! separately by [`AppError::localized`](crate::error::AppError::localized),
+//! whose English text lives in error.rs.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+/// Locale used when the setting names a language without messages
+pub const FALLBACK: &str = "en";
+
+/// Fluent source of each supported language
+const SOURCES: &[(&str, &str)] = &[
+    ("en", include_str!("locales/en.ftl")),
+    ("es", include_str!("locales/es.ftl")),
+];
+
+/// A supported locale, for the settings UI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LocaleInfo {
+    /// Language code to store as `ui.locale`
+    pub code: String,
+
+    /// Name of the language in itself (e.g. "Español")
+    pub name: String,
+}
+
+type Bundle = HashMap<&'static str, &'static str>;
+
+fn bundles() -> &'static HashMap<&'static str, Bundle> {
+    static BUNDLES: OnceLock<HashMap<&'static str, Bundle>> = OnceLock::new();
+    BUNDLES.get_or_init(|| SOURCES.iter().map(|(code, source)| (*code, parse(source))).collect())
+}
+
+/// Read the messages of a Fluent file, skipping comments and blank lines
+fn parse(source: &'static str) -> Bundle {
+    source
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(id, text)| (id.trim(), text.trim()))
+        .filter(|(id, _)| !id.is_empty())
+        .collect()
+}
+
+/// Every supported locale
+pub fn locales() -> Vec<LocaleInfo> {
+    SOURCES
+        .iter()
+        .map(|(code, _)| LocaleInfo {
+            code: code.to_string(),
+            name: tr(code, "locale-name"),
+        })
+        .collect()
+}
+
+/// Supported language of `locale` ("es-MX" and "es_mx" resolve to "es"), or English
+pub fn resolve(locale: &str) -> &'static str {
+    let language = locale.split(['-', '_']).next().unwrap_or_default().trim().to_lowercase();
+    SOURCES
+        .iter()
+        .map(|(code, _)| *code)
+        .find(|code| *code == language)
+        .unwrap_or(FALLBACK)
+}
+
+/// Message `id` in `locale` with its placeables filled from `args`, falling back to English
+///
+/// `None` if neither the locale nor English has the message.
+pub fn message(locale: &str, id: &str, args: &[(&str, &dyn Display)]) -> Option<String> {
+    let bundles = bundles();
+    let text = [resolve(locale), FALLBACK]
+        .into_iter()
+        .find_map(|code| bundles.get(code)?.get(id))?;
+    Some(format_args(text, args))
+}
+
+/// Message `id` in `locale`; the id itself if the message does not exist
+pub fn tr(locale: &str, id: &str) -> String {
+    tr_args(locale, id, &[])
+}
+
+/// Message `id` in `locale` with its placeables filled from `args`
+pub fn tr_args(locale: &str, id: &str, args: &[(&str, &dyn Display)]) -> String {
+    message(locale, id, args).unwrap_or_else(|| {
+        tracing::warn!("Missing message '{}'", id);
+        id.to_string()
+    })
+}
+
+/// Replace each `{ $name }` in `text`; placeables without an argument are kept as written
+fn format_args(text: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut formatted = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}').map(|end| start + end) else {
+            break;
+        };
+        formatted.push_str(&rest[..start]);
+        let placeable = &rest[start..=end];
+        let name = placeable[1..placeable.len() - 1].trim().trim_start_matches('$');
+        match args.iter().find(|(arg, _)| *arg == name) {
+            Some((_, value)) => formatted.push_str(&value.to_string()),
+            None => formatted.push_str(placeable),
+        }
+        rest = &rest[end + 1..];
+    }
+    formatted.push_str(rest);
+    formatted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_messages_fall_back_to_english() {
+        assert_eq!(tr("es", "conversation-cleared"), "Conversación borrada.");
+        assert_eq!(tr("es-MX", "tray-quit"), "Salir");
+        assert_eq!(tr("fr", "tray-quit"), "Quit");
+        assert_eq!(tr("en", "no-such-message"), "no-such-message");
+        assert_eq!(message("es", "no-such-message", &[]), None);
+        assert_eq!(resolve("ES_mx"), "es");
+    }
+
+    #[test]
+    fn test_placeables_are_filled() {
+        assert_eq!(
+            tr_args("en", "voice-switched", &[("name", &"Rachel")]),
+            "Switched to the Rachel voice."
+        );
+        assert_eq!(format_args("{ $a } and { $b }", &[("a", &1)]), "1 and { $b }");
+    }
+
+    #[test]
+    fn test_every_locale_has_the_english_messages() {
+        let bundles = bundles();
+        for (code, bundle) in bundles {
+            for id in bundles[FALLBACK].keys() {
+                assert!(bundle.contains_key(id), "{} is missing '{}'", code, id);
+            }
+        }
+        assert_eq!(locales().len(), SOURCES.len());
+    }
+}
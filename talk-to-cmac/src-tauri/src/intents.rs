@@ -0,0 +1,297 @@
+//! Local voice command grammar
+//!
+//! Recognizes a small set of built-in app-control phrases in transcriptions so
+//! they can be executed locally without an LLM round-trip. The whole utterance
+//! must be a command (ignoring case, punctuation, and a leading or trailing
+//! "please"), so "clear conversation" matches but "how do I clear a
+//! conversation?" goes to the LLM as usual.
+//!
+//! Spoken modifiers ("answer briefly", "be more creative") are commands too:
+//! they change the token limit or temperature of the next reply only, and
+//! leave the settings alone.
+
+use crate::api::GenerationOverrides;
+use crate::config::OpenWebUiConfig;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// Built-in command recognized in a transcription
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "intent", rename_all = "snake_case")]
+pub enum VoiceIntent {
+    /// Start a new conversation
+    ClearConversation,
+
+    /// Stop the reply that is currently playing
+    StopTalking,
+
+    /// Switch the ElevenLabs voice by name or label (e.g., "Spanish")
+    SwitchVoice { voice: String },
+
+    /// Speak the last reply again
+    RepeatLast,
+
+    /// Open a ticket with the rest of the utterance as its summary
+    CreateTicket { summary: String },
+
+    /// Change how the next reply is generated
+    AdjustNextReply { modifier: ReplyModifier },
+}
+
+impl VoiceIntent {
+    /// Whether the command acts outside the app, so it is confirmed before it runs
+    pub fn takes_action(&self) -> bool {
+        matches!(self, VoiceIntent::CreateTicket { .. })
+    }
+}
+
+/// One-shot change to the generation settings of the next reply
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReplyModifier {
+    /// Cap the reply at [`BRIEF_MAX_TOKENS`]
+    Brief,
+
+    /// Raise the temperature by [`CREATIVE_TEMPERATURE_STEP`]
+    Creative,
+
+    /// Lower the temperature to at most [`PRECISE_TEMPERATURE`]
+    Precise,
+}
+
+/// Token limit of a reply asked to be brief
+pub const BRIEF_MAX_TOKENS: usize = 80;
+
+/// How much more creative a reply asked to be creative is
+pub const CREATIVE_TEMPERATURE_STEP: f32 = 0.5;
+
+/// Highest temperature a reply is raised to
+const MAX_TEMPERATURE: f32 = 1.5;
+
+/// Temperature of a reply asked to be precise
+pub const PRECISE_TEMPERATURE: f32 = 0.2;
+
+impl ReplyModifier {
+    /// `overrides`, for a model configured as `config`, with this modifier applied on top
+    ///
+    /// Limits are only ever tightened toward what was asked: a brief reply keeps a
+    /// lower cap already in effect, and a precise one keeps a lower temperature.
+    pub fn apply(self, mut overrides: GenerationOverrides, config: &OpenWebUiConfig) -> GenerationOverrides {
+        let temperature = overrides.temperature.unwrap_or(config.temperature);
+        match self {
+            ReplyModifier::Brief => {
+                let limit = overrides.max_tokens.or(config.max_tokens).unwrap_or(BRIEF_MAX_TOKENS);
+                overrides.max_tokens = Some(limit.min(BRIEF_MAX_TOKENS));
+            }
+            ReplyModifier::Creative => {
+                overrides.temperature = Some(temperature.max((temperature + CREATIVE_TEMPERATURE_STEP).min(MAX_TEMPERATURE)));
+            }
+            ReplyModifier::Precise => overrides.temperature = Some(temperature.min(PRECISE_TEMPERATURE)),
+        }
+        overrides
+    }
+}
+
+const CLEAR_PHRASES: &[&str] = &[
+    "clear conversation",
+    "clear the conversation",
+    "clear chat",
+    "clear the chat",
+    "new conversation",
+    "start over",
+];
+
+const STOP_PHRASES: &[&str] = &["stop", "stop talking", "stop speaking", "be quiet", "quiet", "thats enough"];
+
+const REPEAT_PHRASES: &[&str] = &[
+    "repeat",
+    "repeat that",
+    "repeat that again",
+    "say that again",
+    "what did you say",
+    "come again",
+];
+
+const BRIEF_PHRASES: &[&str] = &[
+    "answer briefly",
+    "be brief",
+    "keep it short",
+    "keep it brief",
+    "make it short",
+    "short answer",
+    "give me a short answer",
+];
+
+const CREATIVE_PHRASES: &[&str] = &["be creative", "be more creative", "get creative", "get more creative"];
+
+const PRECISE_PHRASES: &[&str] = &[
+    "be precise",
+    "be more precise",
+    "be more focused",
+    "be more factual",
+    "stick to the facts",
+];
+
+const YES_PHRASES: &[&str] = &[
+    "yes", "yeah", "yep", "sure", "ok", "okay", "correct", "confirm", "do it", "go ahead", "yes please", "please do",
+    "si", "sí", "claro",
+];
+
+const NO_PHRASES: &[&str] = &["no", "nope", "cancel", "dont", "do not", "never mind", "no thanks", "stop", "cancela"];
+
+/// Whether a transcription answers a confirmation question with yes (`true`) or no (`false`)
+pub fn match_confirmation(transcription: &str) -> Option<bool> {
+    let text = normalize(transcription);
+    if YES_PHRASES.contains(&text.as_str()) {
+        return Some(true);
+    }
+    if NO_PHRASES.contains(&text.as_str()) {
+        return Some(false);
+    }
+    None
+}
+
+/// Match a transcription against the built-in command grammar
+pub fn match_intent(transcription: &str) -> Option<VoiceIntent> {
+    let text = normalize(transcription);
+    let text = text.strip_prefix("please ").unwrap_or(&text);
+    let text = text.strip_suffix(" please").unwrap_or(text);
+
+    if CLEAR_PHRASES.contains(&text) {
+        return Some(VoiceIntent::ClearConversation);
+    }
+    if STOP_PHRASES.contains(&text) {
+        return Some(VoiceIntent::StopTalking);
+    }
+    if REPEAT_PHRASES.contains(&text) {
+        return Some(VoiceIntent::RepeatLast);
+    }
+    let modifier = if BRIEF_PHRASES.contains(&text) {
+        Some(ReplyModifier::Brief)
+    } else if CREATIVE_PHRASES.contains(&text) {
+        Some(ReplyModifier::Creative)
+    } else if PRECISE_PHRASES.contains(&text) {
+        Some(ReplyModifier::Precise)
+    } else {
+        None
+    };
+    if let Some(modifier) = modifier {
+        return Some(VoiceIntent::AdjustNextReply { modifier });
+    }
+
+    static CREATE_TICKET: OnceLock<Regex> = OnceLock::new();
+    let create_ticket = CREATE_TICKET.get_or_init(|| {
+        Regex::new(r"^(?:open|create|file|raise|log) (?:a |an )?(?:new )?(?:ticket|incident) (?:for|about) (.+)$")
+            .expect("ticket pattern is valid")
+    });
+    if let Some(caps) = create_ticket.captures(text) {
+        return Some(VoiceIntent::CreateTicket {
+            summary: caps[1].to_string(),
+        });
+    }
+
+    static SWITCH_VOICE: OnceLock<Regex> = OnceLock::new();
+    let switch_voice = SWITCH_VOICE.get_or_init(|| {
+        Regex::new(r"^(?:switch to|change to|use) (?:the |a )?(.+?) voice$").expect("voice pattern is valid")
+    });
+    switch_voice.captures(text).map(|caps| VoiceIntent::SwitchVoice {
+        voice: caps[1].to_string(),
+    })
+}
+
+/// Lowercase, drop punctuation (apostrophes included), and collapse whitespace
+fn normalize(text: &str) -> String {
+    text.chars()
+        .filter(|c| c.is_alphanumeric() || c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_fixed_phrases() {
+        assert_eq!(match_intent("Clear the conversation."), Some(VoiceIntent::ClearConversation));
+        assert_eq!(match_intent("Stop talking, please!"), Some(VoiceIntent::StopTalking));
+        assert_eq!(match_intent("Sorry, what did you say?"), None);
+        assert_eq!(match_intent("Repeat that"), Some(VoiceIntent::RepeatLast));
+    }
+
+    #[test]
+    fn test_matches_voice_switch() {
+        assert_eq!(
+            match_intent("Switch to Spanish voice."),
+            Some(VoiceIntent::SwitchVoice {
+                voice: "spanish".to_string()
+            })
+        );
+        assert_eq!(
+            match_intent("Please use the British male voice"),
+            Some(VoiceIntent::SwitchVoice {
+                voice: "british male".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_matches_ticket_requests() {
+        assert_eq!(
+            match_intent("Open a ticket for the broken compressor at the Lewisville site."),
+            Some(VoiceIntent::CreateTicket {
+                summary: "the broken compressor at the lewisville site".to_string()
+            })
+        );
+        assert_eq!(match_intent("How do I open a ticket for a broken compressor?"), None);
+    }
+
+    #[test]
+    fn test_matches_confirmations() {
+        assert_eq!(match_confirmation("Yes, please."), Some(true));
+        assert_eq!(match_confirmation("Go ahead!"), Some(true));
+        assert_eq!(match_confirmation("Don't."), Some(false));
+        assert_eq!(match_confirmation("Never mind"), Some(false));
+        assert_eq!(match_confirmation("Yes, but call it urgent"), None);
+        assert!(!VoiceIntent::RepeatLast.takes_action());
+    }
+
+    #[test]
+    fn test_matches_reply_modifiers() {
+        assert_eq!(
+            match_intent("Answer briefly, please."),
+            Some(VoiceIntent::AdjustNextReply {
+                modifier: ReplyModifier::Brief
+            })
+        );
+        assert_eq!(
+            match_intent("Be more creative!"),
+            Some(VoiceIntent::AdjustNextReply {
+                modifier: ReplyModifier::Creative
+            })
+        );
+        assert_eq!(match_intent("How do I keep it short in an email?"), None);
+
+        let config = OpenWebUiConfig {
+            temperature: 0.5,
+            max_tokens: Some(40),
+            ..OpenWebUiConfig::default()
+        };
+        let brief = ReplyModifier::Brief.apply(GenerationOverrides::default(), &config);
+        assert_eq!(brief.max_tokens, Some(40));
+        let creative = ReplyModifier::Creative.apply(GenerationOverrides::default(), &config);
+        assert_eq!(creative.temperature, Some(1.0));
+        let precise = ReplyModifier::Precise.apply(creative, &config);
+        assert_eq!((precise.temperature, precise.max_tokens), (Some(PRECISE_TEMPERATURE), None));
+    }
+
+    #[test]
+    fn test_ignores_commands_inside_questions() {
+        assert_eq!(match_intent("How do I clear a conversation in Slack?"), None);
+        assert_eq!(match_intent("Stop the music and tell me a joke"), None);
+    }
+}
@@ -0,0 +1,237 @@
+//! Request journal
+//!
+//! Every pipeline request (`process_voice_query`, `process_text_query`, and
+//! `send_message`) leaves one line here when it finishes: its `request_id`,
+//! which also tags its log lines, the settings it ran with, how long each stage
+//! took, and how it ended. When someone reports that "it failed an hour ago",
+//! support can see which provider and model were asked, which stage failed,
+//! and with what error.
+//!
+//! Only settings are recorded, never API keys, transcriptions, or replies. The
+//! journal keeps the last [`MAX_ENTRIES`] requests and is cleared when user
+//! data is purged or erased.
+
+use crate::config::{AppConfig, ConfigManager, PipelineMode};
+use crate::error::{AppResult, ConfigError};
+use crate::telemetry::{Stage, StageTimings};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Requests kept in the journal; older ones are dropped as new ones are recorded
+pub const MAX_ENTRIES: usize = 1000;
+
+/// Longest error message kept, in characters
+const MAX_ERROR_CHARS: usize = 300;
+
+/// Serializes rewrites of the journal file
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Settings a request ran with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RequestParameters {
+    /// Speech-to-text provider, for spoken queries
+    pub stt_provider: Option<String>,
+
+    /// Speech-to-text model or deployment
+    pub stt_model: Option<String>,
+
+    /// LLM provider, as named in the settings
+    pub llm_provider: String,
+
+    /// LLM model or deployment
+    pub llm_model: Option<String>,
+
+    /// Text-to-speech provider, when the reply was to be spoken
+    pub tts_provider: Option<String>,
+
+    pub temperature: f32,
+
+    /// Token limit in effect, after low-bandwidth mode
+    pub max_tokens: Option<usize>,
+
+    /// Replies were streamed
+    pub stream: bool,
+
+    pub pipeline_mode: PipelineMode,
+
+    /// Low-bandwidth mode was on
+    pub low_bandwidth: bool,
+
+    /// Size of the recording in bytes, or of the typed message in characters
+    pub input_size: usize,
+}
+
+impl RequestParameters {
+    /// Parameters of a request under `config`, which already has low-bandwidth mode applied
+    ///
+    /// `spoken` adds the speech-to-text stage and `speak` the text-to-speech stage.
+    pub fn new(config: &AppConfig, spoken: bool, speak: bool, input_size: usize) -> Self {
+        Self {
+            stt_provider: spoken.then(|| Stage::Stt.provider(config)),
+            stt_model: spoken.then(|| Stage::Stt.model(config)).flatten(),
+            llm_provider: Stage::Llm.provider(config),
+            llm_model: Stage::Llm.model(config),
+            tts_provider: speak.then(|| Stage::Tts.provider(config)),
+            temperature: config.openwebui.temperature,
+            max_tokens: config.openwebui.max_tokens,
+            stream: config.openwebui.stream,
+            pipeline_mode: config.audio.pipeline_mode,
+            low_bandwidth: config.low_bandwidth.enabled,
+            input_size,
+        }
+    }
+}
+
+/// One finished pipeline request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// ID tagging the request's log lines
+    pub request_id: String,
+
+    /// Command that handled it ("voice_query", "text_query", or "message")
+    pub kind: String,
+
+    /// Unix timestamp of the start of the request
+    pub started_at: u64,
+
+    /// Time until the request finished, in milliseconds
+    pub duration_ms: u64,
+
+    pub parameters: RequestParameters,
+
+    /// Providers and timings of the stages that ran, for requests that succeeded
+    pub stages: Option<StageTimings>,
+
+    /// The request succeeded
+    pub ok: bool,
+
+    /// Pipeline stage that was running when the request failed ("stt", "llm", or "tts")
+    pub failed_stage: Option<String>,
+
+    /// Error shown to the user, shortened
+    pub error: Option<String>,
+}
+
+impl JournalEntry {
+    /// Entry for a request that ended with `outcome`: its stage timings, or the stage that failed and the error
+    pub fn new(
+        request_id: &str,
+        kind: &str,
+        started_at: SystemTime,
+        parameters: RequestParameters,
+        outcome: Result<StageTimings, (Option<&str>, &str)>,
+    ) -> Self {
+        let duration_ms = started_at.elapsed().map_or(0, |elapsed| elapsed.as_millis() as u64);
+        let (stages, failed_stage, error) = match outcome {
+            Ok(stages) => (Some(stages), None, None),
+            Err((stage, error)) => (
+                None,
+                stage.map(str::to_string),
+                Some(error.chars().take(MAX_ERROR_CHARS).collect()),
+            ),
+        };
+
+        Self {
+            request_id: request_id.to_string(),
+            kind: kind.to_string(),
+            started_at: started_at.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs()),
+            duration_ms,
+            parameters,
+            ok: error.is_none(),
+            stages,
+            failed_stage,
+            error,
+        }
+    }
+}
+
+/// Add an entry to the journal, warning instead of failing the request if it cannot be written
+pub fn record(entry: &JournalEntry) {
+    if let Err(e) = append(entry) {
+        tracing::warn!("Failed to write the request journal: {}", e);
+    }
+}
+
+/// Read the most recent journal entries, newest first
+pub fn read(limit: usize) -> AppResult<Vec<JournalEntry>> {
+    let path = journal_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = fs::read_to_string(&path).map_err(|e| ConfigError::LoadFailed(e.to_string()))?;
+    Ok(contents
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .take(limit)
+        .collect())
+}
+
+/// Delete every entry
+pub fn clear() -> AppResult<()> {
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let path = journal_path()?;
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
+    }
+    Ok(())
+}
+
+fn append(entry: &JournalEntry) -> AppResult<()> {
+    let line = serde_json::to_string(entry).map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
+
+    let _guard = WRITE_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let path = journal_path()?;
+    let contents = if path.exists() {
+        fs::read_to_string(&path).map_err(|e| ConfigError::LoadFailed(e.to_string()))?
+    } else {
+        String::new()
+    };
+
+    fs::write(&path, with_entry(&contents, &line, MAX_ENTRIES)).map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
+    Ok(())
+}
+
+/// `contents` with `line` added at the end, keeping at most `max` lines
+fn with_entry(contents: &str, line: &str, max: usize) -> String {
+    let lines: Vec<&str> = contents.lines().chain([line]).collect();
+    let kept = &lines[lines.len().saturating_sub(max)..];
+
+    let mut journal = kept.join("\n");
+    journal.push('\n');
+    journal
+}
+
+fn journal_path() -> AppResult<PathBuf> {
+    Ok(ConfigManager::new()?.data_dir().join("request_journal.jsonl"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_keeps_newest_entries() {
+        let journal = (1..=5).fold(String::new(), |journal, n| with_entry(&journal, &n.to_string(), 3));
+        assert_eq!(journal, "3\n4\n5\n");
+    }
+
+    #[test]
+    fn test_failed_entry_records_stage_and_short_error() {
+        let mut config = AppConfig::default();
+        config.low_bandwidth.enabled = true;
+        let parameters = RequestParameters::new(&config, true, false, 32_000);
+        assert!(parameters.stt_provider.is_some() && parameters.tts_provider.is_none());
+
+        let error = "x".repeat(1000);
+        let entry = JournalEntry::new("0badf00d", "voice_query", SystemTime::now(), parameters, Err((Some("llm"), &error)));
+        assert!(!entry.ok);
+        assert_eq!(entry.failed_stage.as_deref(), Some("llm"));
+        assert_eq!(entry.error.map(|e| e.len()), Some(MAX_ERROR_CHARS));
+        assert_eq!(entry.stages, None);
+    }
+}
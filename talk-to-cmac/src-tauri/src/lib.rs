@@ -5,29 +5,134 @@
 
 // Module declarations
 mod api;
+mod audit;
+mod bandwidth;
+mod benchmark;
+mod calendar;
+mod clipboard;
 mod commands;
 mod config;
+mod context;
+mod crash;
+mod crypto;
+mod downmix;
+mod ducking;
+mod email;
+mod env_overrides;
 mod error;
+mod experiments;
+mod feature_flags;
+mod files;
+mod gain;
+mod handoff;
+mod history;
+mod hooks;
+mod hotkeys;
+mod html;
+mod i18n;
+mod intents;
+mod journal;
+mod log_privacy;
+mod microphone;
+mod moderation;
+mod narration;
+mod news;
+mod ocr;
+mod pipeline;
+mod policy;
+mod profanity;
+mod profiles;
+mod quick_ask;
+mod recordings;
+mod redaction;
+mod retention;
+mod retry;
+mod selftest;
+mod session;
+mod setup;
+mod speaker;
+mod speech_text;
 mod state;
+mod telemetry;
+mod templates;
+mod theme;
+mod tools;
+#[cfg(test)]
+mod test_support;
+mod translator;
+mod trim;
+mod usage;
+mod user_data;
+mod warmup;
+mod wav;
 
 use config::{AppConfig, ConfigManager};
+use crypto::StorageCipher;
+use ducking::AudioDucker;
+use history::HistoryStore;
 use state::AppState;
 use tauri::Manager;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+/// Tray menu entry mirroring the microphone mute state
+pub(crate) struct TrayMuteItem(pub tauri::menu::CheckMenuItem<tauri::Wry>);
+
+/// Tray menu entries with the message id of their label, to relabel them when the locale changes
+pub(crate) struct TrayMenuItems(pub Vec<(&'static str, tauri::menu::MenuItem<tauri::Wry>)>);
+
+/// Tray submenu listing the voice presets
+pub(crate) struct TrayVoiceMenu(pub tauri::menu::Submenu<tauri::Wry>);
+
+/// The tray icon, whose position places the quick-ask popup
+pub(crate) struct TrayHandle(pub tauri::tray::TrayIcon<tauri::Wry>);
 
 /// Initialize and run the Tauri application
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // Load the .env managed from the settings first, so it wins over the project's
+    let env_overrides = env_overrides::EnvOverrides::open_default().and_then(|overrides| overrides.apply());
+
     // Load .env file (will load from project root or parent directories)
     // This allows us to use environment variables for API keys during development
     match dotenvy::dotenv() {
-        Ok(path) => log::info!("Loaded .env file from: {:?}", path),
-        Err(e) => log::warn!("No .env file found or error loading it: {} (this is OK if using system keyring)", e),
+        Ok(path) => tracing::info!("Loaded .env file from: {:?}", path),
+        Err(e) => tracing::warn!("No .env file found or error loading it: {} (this is OK if using system keyring)", e),
+    }
+
+    // Telemetry export is set up with tracing, so its settings are read before the rest of the config
+    let telemetry_config = ConfigManager::new()
+        .and_then(|manager| manager.load())
+        .map(|config| config.telemetry)
+        .unwrap_or_default();
+    let (telemetry_layer, telemetry_error) = match telemetry::layer(&telemetry_config) {
+        Ok(layer) => (layer, None),
+        Err(e) => (None, Some(e)),
+    };
+
+    // Initialize tracing (log records from dependencies are forwarded to it).
+    // Closing spans report their duration, so each pipeline stage is timed.
+    tracing_subscriber::registry()
+        .with(telemetry_layer)
+        .with(EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")))
+        .with(tracing_subscriber::fmt::layer().with_span_events(FmtSpan::CLOSE))
+        .init();
+
+    match telemetry_error {
+        Some(e) => tracing::warn!("Telemetry export disabled: {}", e),
+        None if telemetry_config.enabled => tracing::info!("Exporting telemetry to {}", telemetry_config.endpoint),
+        None => {}
     }
 
-    // Initialize logger
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    match env_overrides {
+        Ok(0) => {}
+        Ok(count) => tracing::info!("Applied {} managed environment override(s)", count),
+        Err(e) => tracing::warn!("Failed to load managed environment overrides: {}", e),
+    }
 
-    log::info!("Starting Talk to CMAC application");
+    tracing::info!("Starting Talk to CMAC application");
 
     tauri::Builder::default()
         // Register plugins
@@ -36,48 +141,119 @@ pub fn run() {
         .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         // Setup hook to initialize application state
         .setup(|app| {
-            log::info!("Initializing application state");
+            tracing::info!("Initializing application state");
 
             // Load configuration
             let config_manager = ConfigManager::new().map_err(|e| {
-                log::error!("Failed to create config manager: {}", e);
+                tracing::error!("Failed to create config manager: {}", e);
                 e.to_string()
             })?;
 
             let (config, api_keys) = config_manager.load_with_keys().unwrap_or_else(|e| {
-                log::warn!("Failed to load config, using defaults: {}", e);
-                (AppConfig::default(), config::ApiKeys {
-                    whisper: None,
-                    openwebui: None,
-                    elevenlabs: None,
-                })
+                tracing::warn!("Failed to load config, using defaults: {}", e);
+                (AppConfig::default(), config::ApiKeys::none())
             });
 
-            log::info!("Configuration loaded");
+            tracing::info!("Configuration loaded");
 
             // Create application state
             let app_state = AppState::new(config.clone(), api_keys);
+            app_state.set_slot_keys(config_manager.load_slot_keys(&config.key_slots));
+
+            // Open persistent history, falling back to memory so the app still runs
+            let history = HistoryStore::open(&config_manager.data_dir().join("history.db"))
+                .or_else(|e| {
+                    tracing::error!("Failed to open conversation history, history will not persist: {}", e);
+                    HistoryStore::open_in_memory()
+                })
+                .map_err(|e| e.to_string())?;
+
+            if let Err(e) = history.configure_encryption(config.privacy.encrypt_history, || {
+                config_manager.get_or_create_history_key()
+            }) {
+                tracing::error!("Failed to configure history encryption: {}", e);
+            }
+
+            // Restore a conversation saved by a crash, then record any future crash the same way.
+            // Crash files are shared by all profiles; the profile active at a crash is still active at the next launch.
+            let app_dir = ConfigManager::app_dir().map_err(|e| e.to_string())?;
+            // With encrypted history the saved conversation uses the history key; passphrase-protected
+            // history has no key until it is unlocked, so its conversations are not saved
+            let crash_cipher = (config.privacy.encrypt_history && !history.has_passphrase().unwrap_or(true))
+                .then(|| config_manager.get_or_create_history_key())
+                .and_then(|key| key.inspect_err(|e| tracing::warn!("Crash recovery cannot encrypt: {}", e)).ok())
+                .map(|key| StorageCipher::from_key(&key));
+            let last_crash = crash::recover(&app_state, &app_dir, crash_cipher.as_ref());
+            crash::install(app_state.clone(), app_dir, crash_cipher);
 
             // Manage state
+            app.manage(crash::LastCrash(last_crash));
             app.manage(app_state);
+            app.manage(AudioDucker::new());
+            app.manage(history);
+
+            // Enforce the retention policy now and periodically
+            retention::spawn_cleanup_task(app.handle().clone());
+
+            // Lock the session after the configured time without activity
+            commands::spawn_auto_lock_task(app.handle().clone());
+
+            // Clear the conversation between kiosk users
+            commands::spawn_kiosk_reset_task(app.handle().clone());
+
+            // Open connections early so the first query is fast
+            warmup::spawn_warmup_task(app.handle().clone());
+            warmup::spawn_keep_alive_task(app.handle().clone());
+
+            // Queue and send anonymous usage reports if the user opted in
+            usage::spawn_reporting_task(app.handle().clone());
+
+            // Apply fleet-wide feature flags, refreshing them periodically
+            feature_flags::spawn_refresh_task(app.handle().clone());
 
             // Setup system tray if on desktop
             #[cfg(desktop)]
             {
                 use tauri::tray::{TrayIconBuilder, MouseButton, MouseButtonState};
-                use tauri::menu::{MenuBuilder, MenuItemBuilder};
+                use tauri::menu::{CheckMenuItemBuilder, MenuBuilder, MenuItemBuilder, SubmenuBuilder};
 
-                log::info!("Setting up system tray");
+                tracing::info!("Setting up system tray");
+
+                let locale = &config.ui.locale;
+                let mute_item = CheckMenuItemBuilder::new(i18n::tr(locale, "tray-mute"))
+                    .id("mute")
+                    .checked(false)
+                    .build(app)?;
+                let item = |id: &str, label: &'static str| MenuItemBuilder::new(i18n::tr(locale, label)).id(id).build(app);
+                let show_item = item("show", "tray-show")?;
+                let hide_item = item("hide", "tray-hide")?;
+                let quick_ask_item = item("quick-ask", "tray-quick-ask")?;
+                let quit_item = item("quit", "tray-quit")?;
+                let voice_menu = SubmenuBuilder::new(app, i18n::tr(locale, "tray-voice")).build()?;
 
                 // Create tray menu
                 let menu = MenuBuilder::new(app)
-                    .item(&MenuItemBuilder::new("Show").id("show").build(app)?)
-                    .item(&MenuItemBuilder::new("Hide").id("hide").build(app)?)
+                    .item(&show_item)
+                    .item(&hide_item)
+                    .item(&quick_ask_item)
                     .separator()
-                    .item(&MenuItemBuilder::new("Quit").id("quit").build(app)?)
+                    .item(&mute_item)
+                    .item(&voice_menu)
+                    .separator()
+                    .item(&quit_item)
                     .build()?;
 
-                let _tray = TrayIconBuilder::new()
+                app.manage(TrayMenuItems(vec![
+                    ("tray-show", show_item),
+                    ("tray-hide", hide_item),
+                    ("tray-quick-ask", quick_ask_item),
+                    ("tray-quit", quit_item),
+                ]));
+                app.manage(TrayMuteItem(mute_item));
+                app.manage(TrayVoiceMenu(voice_menu));
+                commands::refresh_voice_presets_menu(app.handle());
+
+                let tray = TrayIconBuilder::new()
                     .menu(&menu)
                     .on_menu_event(|app, event| {
                         match event.id().as_ref() {
@@ -92,9 +268,30 @@ pub fn run() {
                                     let _ = window.hide();
                                 }
                             }
+                            "quick-ask" => {
+                                if let Err(e) = quick_ask::show(app) {
+                                    tracing::warn!("{}", e);
+                                }
+                            }
+                            "mute" => {
+                                let muted = !app.state::<AppState>().is_mic_muted();
+                                commands::apply_mic_mute(app, muted);
+                            }
                             "quit" => {
+                                // Never leave other applications ducked after exit
+                                if let Err(e) = app.state::<AudioDucker>().restore() {
+                                    tracing::warn!("{}", e);
+                                }
                                 app.exit(0);
                             }
+                            id if id.starts_with(commands::VOICE_PRESET_MENU_PREFIX) => {
+                                let name = &id[commands::VOICE_PRESET_MENU_PREFIX.len()..];
+                                if let Err(e) = commands::apply_preset(app, &app.state::<AppState>(), name) {
+                                    tracing::warn!("Failed to apply voice preset '{}': {}", name, e);
+                                    // Undo the check the click toggled
+                                    commands::refresh_voice_presets_menu(app);
+                                }
+                            }
                             _ => {}
                         }
                     })
@@ -113,48 +310,161 @@ pub fn run() {
                         }
                     })
                     .build(app)?;
+                app.manage(TrayHandle(tray));
 
-                log::info!("System tray initialized");
+                tracing::info!("System tray initialized");
             }
 
-            // Setup global hotkey
-            if let Some(hotkey) = &config.ui.global_hotkey {
-                log::info!("Registering global hotkey: {}", hotkey);
-
-                // Note: Global hotkey registration needs to be done after the app is running
-                // This is a placeholder for the actual implementation
-                // In practice, you would use tauri_plugin_global_shortcut
-            }
+            // Register the global hotkeys
+            #[cfg(desktop)]
+            hotkeys::register_all(app.handle(), &config.ui.hotkeys);
 
             // Configure window
             if let Some(window) = app.get_webview_window("main") {
                 if config.ui.always_on_top {
                     let _ = window.set_always_on_top(true);
                 }
-                log::info!("Main window configured");
+                tracing::info!("Main window configured");
             }
 
-            log::info!("Application setup complete");
+            tracing::info!("Application setup complete");
             Ok(())
         })
         // Register all Tauri commands
         .invoke_handler(tauri::generate_handler![
             commands::process_audio,
+            commands::trim_audio,
+            commands::retranscribe,
+            commands::get_recent_transcriptions,
+            commands::get_live_transcript,
             commands::send_message,
             commands::synthesize_speech,
             commands::process_voice_query,
+            commands::process_text_query,
             commands::load_config,
             commands::save_config,
+            commands::update_config_section,
+            commands::get_theme,
+            commands::list_locales,
+            commands::list_profiles,
+            commands::create_profile,
+            commands::switch_profile,
+            commands::set_profile_pin,
+            commands::enroll_speaker,
+            commands::delete_speaker_enrollment,
+            commands::is_session_locked,
+            commands::set_session_pin,
+            commands::lock_session,
+            commands::unlock_session,
+            commands::report_activity,
+            commands::set_kiosk_mode,
             commands::update_api_key,
+            commands::list_key_slots,
+            commands::add_key_slot,
+            commands::remove_key_slot,
+            commands::set_default_key_slot,
+            commands::get_key_status,
+            commands::is_first_run,
+            commands::detect_local_servers,
+            commands::discover_network_servers,
+            commands::probe_llm_api,
+            commands::suggest_setup,
+            commands::test_api_key,
+            commands::complete_setup,
+            commands::export_config,
+            commands::set_env_override,
+            commands::list_env_overrides,
+            commands::store_secret,
+            commands::delete_secret,
+            commands::list_secret_names,
             commands::check_connectivity,
+            commands::get_service_history,
+            commands::clear_cooldown,
+            commands::run_self_test,
+            commands::benchmark_providers,
             commands::get_app_state,
             commands::clear_conversation,
             commands::get_conversation,
+            commands::fork_conversation,
+            commands::rate_response,
+            commands::export_conversation_audio,
+            commands::set_conversation_settings,
+            commands::set_translator_mode,
+            commands::get_last_response,
+            commands::repeat_last_response,
+            commands::list_models,
+            commands::upload_file_to_llm,
+            commands::list_llm_files,
+            commands::set_active_model,
             commands::list_voices,
             commands::update_voice_settings,
+            commands::list_voice_presets,
+            commands::apply_voice_preset,
+            commands::save_voice_preset,
+            commands::delete_voice_preset,
+            commands::playback_started,
+            commands::playback_finished,
+            commands::set_mic_muted,
+            commands::toggle_mic_mute,
+            commands::list_hotkeys,
+            commands::validate_hotkey,
+            commands::register_hotkey,
+            commands::unregister_hotkey,
+            commands::show_quick_ask,
+            commands::hide_quick_ask,
+            commands::speak_selection,
+            commands::summarize_clipboard,
+            commands::translate_clipboard,
+            commands::check_microphone_access,
+            commands::test_microphone,
+            commands::list_recordings,
+            commands::play_recording,
+            commands::delete_recording,
+            commands::list_conversations,
+            commands::restore_conversation,
+            commands::get_stored_conversation,
+            commands::purge_all_data,
+            commands::export_all_user_data,
+            commands::request_erase_token,
+            commands::erase_all_user_data,
+            commands::start_incognito_session,
+            commands::end_incognito_session,
+            commands::unlock_history,
+            commands::set_history_passphrase,
+            commands::get_redaction_audit,
+            commands::get_audit_log,
+            commands::get_request_journal,
+            commands::get_feature_flags,
+            commands::view_telemetry_payload,
+            commands::get_last_crash_report,
+            commands::create_ticket,
+            commands::request_handoff,
+            commands::speak_today_schedule,
+            commands::give_news_briefing,
+            commands::ocr_image,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| match event {
+            tauri::RunEvent::Exit => {
+                if let Err(e) = usage::queue_pending(&app.state::<AppState>().get_config().usage_reports) {
+                    tracing::warn!("Failed to queue usage report: {}", e);
+                }
+                telemetry::shutdown();
+            }
+            // Follow dark-mode switches while the theme setting is "system"
+            tauri::RunEvent::WindowEvent {
+                label,
+                event: tauri::WindowEvent::ThemeChanged(theme),
+                ..
+            } if label == "main" => {
+                let setting = app.state::<AppState>().get_config().ui.theme;
+                if theme::ThemeInfo::new(&setting, None).follows_system() {
+                    commands::emit_theme_changed(app, commands::theme_name(theme));
+                }
+            }
+            _ => {}
+        });
 }
 
 #[cfg(test)]
@@ -174,6 +484,9 @@ mod tests {
             whisper: None,
             openwebui: None,
             elevenlabs: None,
+            azure: None,
+            openrouter: None,
+            gemini: None,
         };
         let state = AppState::new(config, api_keys);
         assert_eq!(state.get_status(), state::AppStatus::Idle);
@@ -0,0 +1,54 @@
+# English messages, the fallback for every other locale
+#
+# Error messages are not listed here: their English text comes from error.rs.
+
+locale-name = English
+
+## Spoken replies to voice commands
+
+conversation-cleared = Conversation cleared.
+nothing-to-repeat = There's nothing to repeat yet.
+voice-not-found = I couldn't find a { $query } voice.
+voice-switched = Switched to the { $name } voice.
+ticket-created = Opened ticket { $number }.
+confirm-create-ticket = Should I open a ticket for { $summary }? Say yes or no.
+confirm-action = Should I go ahead? Say yes or no.
+action-cancelled = Okay, I won't.
+next-reply-brief = Okay, I'll keep the next answer short.
+next-reply-creative = Okay, I'll be more creative with the next answer.
+next-reply-precise = Okay, I'll stick to the facts in the next answer.
+
+## Spoken schedule
+
+schedule-empty = You have nothing on your calendar today.
+schedule-intro = Here's your schedule for today.
+schedule-event = At { $time }, { $title }.
+schedule-all-day = All day, { $title }.
+
+## News briefing
+
+news-empty = There's no recent news from your feeds.
+
+## Command errors
+
+no-response-to-repeat = No response to repeat
+no-recording-to-retranscribe = No recording to transcribe again
+voice-input-off = Voice input is turned off. Type your question instead.
+no-conversation-to-hand-off = There is no conversation to hand off
+
+## Spoken explanations of a failed voice query
+
+spoken-error-stt = Sorry, I couldn't understand the recording. The speech recognition service failed.
+spoken-error-llm = Sorry, I couldn't reach the language model.
+spoken-error-tts = Sorry, I have an answer but couldn't speak it. You can read it in the app.
+key-slot-incomplete = A key slot needs a label and a key
+service-unreachable = Service unreachable
+
+## Tray menu
+
+tray-show = Show
+tray-hide = Hide
+tray-quick-ask = Quick Ask…
+tray-mute = Mute Microphone
+tray-voice = Voice
+tray-quit = Quit
@@ -0,0 +1,111 @@
+# Spanish messages
+
+locale-name = Español
+
+## Spoken replies to voice commands
+
+conversation-cleared = Conversación borrada.
+nothing-to-repeat = Todavía no hay nada que repetir.
+voice-not-found = No encontré ninguna voz { $query }.
+voice-switched = Ahora uso la voz { $name }.
+ticket-created = Abrí el ticket { $number }.
+confirm-create-ticket = ¿Abro un ticket por { $summary }? Responde sí o no.
+confirm-action = ¿Continúo? Responde sí o no.
+action-cancelled = De acuerdo, no lo haré.
+next-reply-brief = De acuerdo, la próxima respuesta será breve.
+next-reply-creative = De acuerdo, seré más creativo en la próxima respuesta.
+next-reply-precise = De acuerdo, me ceñiré a los hechos en la próxima respuesta.
+
+## Agenda hablada
+
+schedule-empty = No tienes nada en tu calendario hoy.
+schedule-intro = Esta es tu agenda de hoy.
+schedule-event = A las { $time }, { $title }.
+schedule-all-day = Todo el día, { $title }.
+
+## Resumen de noticias
+
+news-empty = No hay noticias recientes en tus fuentes.
+
+## Command errors
+
+no-response-to-repeat = No hay ninguna respuesta que repetir
+no-recording-to-retranscribe = No hay ninguna grabación que volver a transcribir
+voice-input-off = La entrada de voz está desactivada. Escribe tu pregunta.
+no-conversation-to-hand-off = No hay ninguna conversación que transferir
+
+## Spoken explanations of a failed voice query
+
+spoken-error-stt = Lo siento, no pude entender la grabación. Falló el servicio de reconocimiento de voz.
+spoken-error-llm = Lo siento, no pude contactar con el modelo de lenguaje.
+spoken-error-tts = Lo siento, tengo una respuesta pero no pude decirla. Puedes leerla en la aplicación.
+key-slot-incomplete = Una clave necesita un nombre y un valor
+service-unreachable = Servicio inaccesible
+
+## Tray menu
+
+tray-show = Mostrar
+tray-hide = Ocultar
+tray-quick-ask = Pregunta rápida…
+tray-mute = Silenciar micrófono
+tray-voice = Voz
+tray-quit = Salir
+
+## Errors
+##
+## An error is looked up by its category (see AppError::category), then by its
+## source with the English detail as $detail.
+
+error-whisper = Error de la API de Whisper: { $detail }
+error-openwebui = Error de la API de OpenWebUI: { $detail }
+error-elevenlabs = Error de la API de ElevenLabs: { $detail }
+error-moderation = Error de la API de moderación: { $detail }
+error-azure = Error de Azure OpenAI: { $detail }
+error-openrouter = Error de OpenRouter: { $detail }
+error-gemini = Error de Gemini: { $detail }
+error-ticketing = Error del sistema de tickets: { $detail }
+error-calendar = Error del calendario: { $detail }
+error-email = Error del correo: { $detail }
+error-ocr = Error del reconocimiento de texto: { $detail }
+error-ocr-NotAvailable = No se pudo iniciar Tesseract. ¿Está instalado?
+error-clipboard = Error del portapapeles: { $detail }
+error-clipboard-NothingSelected = No hay texto seleccionado
+error-clipboard-Empty = El portapapeles no contiene texto
+error-network = Error de red: { $detail }
+error-config = Error de configuración: { $detail }
+error-audio = Error de audio: { $detail }
+error-storage = Error de almacenamiento: { $detail }
+error-state = Error de estado: { $detail }
+error-generic = Error de la aplicación: { $detail }
+error-cooldown = { $provider } está en pausa tras alcanzar su límite de uso o su cuota; inténtalo de nuevo en { $seconds } s
+
+error-whisper-AuthenticationFailed = La API de Whisper rechazó la clave
+error-whisper-Timeout = La API de Whisper no respondió a tiempo
+error-whisper-RateLimitExceeded = Se superó el límite de uso de la API de Whisper
+error-whisper-EmptyResponse = La API de Whisper devolvió una respuesta vacía
+error-whisper-AudioFileTooLarge = El audio es demasiado grande: el máximo es 25 MB
+error-openwebui-AuthenticationFailed = La API de OpenWebUI rechazó la clave
+error-openwebui-Timeout = La API de OpenWebUI no respondió a tiempo
+error-openwebui-RateLimitExceeded = Se superó el límite de uso de la API de OpenWebUI
+error-openwebui-ContextLimitExceeded = La conversación supera el contexto del modelo
+error-openwebui-ReasoningOnly = El modelo se detuvo mientras razonaba, antes de responder
+error-openwebui-FilesUnsupported = El servidor del modelo no guarda archivos; solo OpenWebUI lo hace
+error-elevenlabs-AuthenticationFailed = La API de ElevenLabs rechazó la clave
+error-elevenlabs-Timeout = La API de ElevenLabs no respondió a tiempo
+error-elevenlabs-RateLimitExceeded = Se superó el límite de uso de la API de ElevenLabs
+error-elevenlabs-QuotaExceeded = Se agotó la cuota de ElevenLabs
+error-openrouter-InsufficientCredits = La cuenta de OpenRouter no tiene créditos suficientes
+error-network-Timeout = La conexión tardó demasiado
+error-network-NoConnection = No hay conexión a internet
+error-network-DnsResolutionFailed = No se pudo resolver el nombre del servidor
+error-network-ConnectionRefused = Conexión rechazada
+error-config-IncorrectPin = PIN incorrecto
+error-config-PinAttemptsExceeded = Demasiados PIN incorrectos; inténtalo de nuevo dentro de un minuto
+error-kiosk = No disponible en el modo quiosco
+error-session-locked = La sesión está bloqueada. Introduce tu PIN para desbloquearla
+error-storage-Locked = El historial está bloqueado. Introduce tu frase de contraseña para desbloquearlo
+error-storage-WrongPassphrase = Frase de contraseña del historial incorrecta
+error-storage-InvalidConfirmation = Para borrarlo todo hace falta un código de confirmación válido; solicita uno nuevo
+error-audio-MicrophoneMuted = El micrófono está silenciado
+error-audio-NoInputDevice = No se encontró ningún micrófono. Conecta uno o actívalo en la configuración de sonido del sistema
+error-audio-MicrophonePermissionDenied = Acceso al micrófono denegado. Permite que esta aplicación use el micrófono en la configuración de privacidad del sistema
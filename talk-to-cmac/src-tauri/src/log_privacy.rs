@@ -0,0 +1,57 @@
+//! Log privacy
+//!
+//! Transcriptions and LLM replies are written to the log through `content`,
+//! which shows as much of the text as `privacy.log_content` allows: nothing,
+//! its length, or (in debug builds only) the text itself.
+
+use crate::config::LogPrivacy;
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Current level, mirroring `privacy.log_content`
+static LEVEL: AtomicU8 = AtomicU8::new(LogPrivacy::Metadata as u8);
+
+/// Text as it may appear in the log
+pub struct Content<'a> {
+    text: &'a str,
+    level: LogPrivacy,
+}
+
+impl fmt::Display for Content<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.level {
+            LogPrivacy::None => write!(f, "[redacted]"),
+            LogPrivacy::FullContentDevOnly if cfg!(debug_assertions) => write!(f, "'{}'", self.text),
+            _ => write!(f, "[{} chars]", self.text.chars().count()),
+        }
+    }
+}
+
+/// Set how much text appears in the log from now on
+pub fn set_level(level: LogPrivacy) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// `text` for a log message, shown as the current level allows
+pub fn content(text: &str) -> Content<'_> {
+    let level = match LEVEL.load(Ordering::Relaxed) {
+        0 => LogPrivacy::None,
+        2 => LogPrivacy::FullContentDevOnly,
+        _ => LogPrivacy::Metadata,
+    };
+    Content { text, level }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels() {
+        let shown = |level| Content { text: "Où est mon colis ?", level }.to_string();
+
+        assert_eq!(shown(LogPrivacy::None), "[redacted]");
+        assert_eq!(shown(LogPrivacy::Metadata), "[18 chars]");
+        assert_eq!(shown(LogPrivacy::FullContentDevOnly), "'Où est mon colis ?'");
+    }
+}
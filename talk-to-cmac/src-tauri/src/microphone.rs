@@ -0,0 +1,314 @@
+//! Microphone availability checks
+//!
+//! Verifies that an input device exists, that the operating system grants this
+//! application microphone access, and that the device can actually be opened,
+//! so recording problems surface before audio is captured rather than as a
+//! transcription failure afterwards. A short test recording also reports the
+//! input level, catching a muted, wrong, or overdriven microphone.
+
+use crate::error::{AppResult, AudioError};
+use crate::wav;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, Sample, SizedSample};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Length of a test recording
+pub const TEST_DURATION: Duration = Duration::from_secs(3);
+
+/// Peak level below which a test recording counts as silent (-40 dBFS)
+const SILENT_PEAK: f32 = 0.01;
+
+/// Peak level at which a test recording counts as clipped
+const CLIPPED_PEAK: f32 = 0.99;
+
+/// Details about the default input device once it has been verified
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MicrophoneInfo {
+    /// Device name as reported by the audio host
+    pub device_name: String,
+
+    /// Default sample rate of the device in Hz
+    pub sample_rate: u32,
+
+    /// Default channel count of the device
+    pub channels: u16,
+}
+
+/// Levels and audio of a test recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MicrophoneTest {
+    pub device_name: String,
+
+    /// Highest absolute sample level (0.0-1.0)
+    pub peak: f32,
+
+    /// Root mean square level (0.0-1.0)
+    pub rms: f32,
+
+    /// Nothing louder than background noise was heard: muted or wrong microphone
+    pub silent: bool,
+
+    /// The signal hit full scale: the input gain is too high
+    pub clipped: bool,
+
+    /// The recording as 16-bit mono WAV, to play back
+    pub audio: Vec<u8>,
+}
+
+/// Check that the default microphone is present, permitted, and not held exclusively
+///
+/// This briefly opens an input stream on the default device, so it should be run
+/// from a blocking context.
+pub fn check_access() -> AppResult<MicrophoneInfo> {
+    if platform::access_denied() {
+        return Err(AudioError::MicrophonePermissionDenied.into());
+    }
+
+    let host = cpal::default_host();
+    let device = host
+        .default_input_device()
+        .ok_or(AudioError::NoInputDevice)?;
+    let device_name = device.name().unwrap_or_else(|_| "Unknown device".to_string());
+
+    let supported = input_config(&device, &device_name)?;
+
+    let stream = device
+        .build_input_stream_raw(
+            &supported.config(),
+            supported.sample_format(),
+            |_data, _info| {},
+            |err| tracing::debug!("Microphone check stream error: {}", err),
+            None,
+        )
+        .map_err(|e| build_error(&device_name, e))?;
+
+    stream.play().map_err(|e| play_error(&device_name, e))?;
+    drop(stream);
+
+    tracing::info!("Microphone '{}' is available", device_name);
+
+    Ok(MicrophoneInfo {
+        device_name,
+        sample_rate: supported.sample_rate().0,
+        channels: supported.channels(),
+    })
+}
+
+/// Record [`TEST_DURATION`] from the default microphone and measure its level
+///
+/// Blocks for the length of the recording, so it should be run from a blocking context.
+pub fn record_test() -> AppResult<MicrophoneTest> {
+    if platform::access_denied() {
+        return Err(AudioError::MicrophonePermissionDenied.into());
+    }
+
+    let device = cpal::default_host()
+        .default_input_device()
+        .ok_or(AudioError::NoInputDevice)?;
+    let device_name = device.name().unwrap_or_else(|_| "Unknown device".to_string());
+    let supported = input_config(&device, &device_name)?;
+    let config = supported.config();
+
+    let samples = Arc::new(Mutex::new(Vec::new()));
+    let stream = match supported.sample_format() {
+        cpal::SampleFormat::F32 => capture::<f32>(&device, &config, samples.clone()),
+        cpal::SampleFormat::I16 => capture::<i16>(&device, &config, samples.clone()),
+        cpal::SampleFormat::I32 => capture::<i32>(&device, &config, samples.clone()),
+        cpal::SampleFormat::U16 => capture::<u16>(&device, &config, samples.clone()),
+        other => {
+            return Err(AudioError::DeviceError(format!("Unsupported sample format: {}", other)).into());
+        }
+    }
+    .map_err(|e| build_error(&device_name, e))?;
+
+    stream.play().map_err(|e| play_error(&device_name, e))?;
+    std::thread::sleep(TEST_DURATION);
+    drop(stream);
+
+    let samples = std::mem::take(&mut *samples.lock().unwrap());
+    let (peak, rms) = levels(&samples);
+    tracing::info!("Microphone test on '{}': peak {:.3}, RMS {:.3}", device_name, peak, rms);
+
+    Ok(MicrophoneTest {
+        device_name,
+        peak,
+        rms,
+        silent: peak < SILENT_PEAK,
+        clipped: peak >= CLIPPED_PEAK,
+        audio: wav::encode(&samples, config.sample_rate.0),
+    })
+}
+
+/// Input stream appending each frame of `T` samples, mixed down to mono, to `samples`
+fn capture<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    samples: Arc<Mutex<Vec<f32>>>,
+) -> Result<cpal::Stream, cpal::BuildStreamError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = usize::from(config.channels.max(1));
+    device.build_input_stream(
+        config,
+        move |data: &[T], _info| {
+            let mut samples = samples.lock().unwrap();
+            for frame in data.chunks(channels) {
+                let sum: f32 = frame.iter().map(|sample| f32::from_sample(*sample)).sum();
+                samples.push(sum / frame.len() as f32);
+            }
+        },
+        |err| tracing::warn!("Microphone test stream error: {}", err),
+        None,
+    )
+}
+
+/// Peak and RMS level of `samples` (-1.0 to 1.0)
+fn levels(samples: &[f32]) -> (f32, f32) {
+    if samples.is_empty() {
+        return (0.0, 0.0);
+    }
+    let peak = samples.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+    let rms = (samples.iter().map(|sample| sample * sample).sum::<f32>() / samples.len() as f32).sqrt();
+    (peak, rms)
+}
+
+/// Default input configuration of `device`
+fn input_config(device: &cpal::Device, device_name: &str) -> AppResult<cpal::SupportedStreamConfig> {
+    device.default_input_config().map_err(|e| {
+        match e {
+            cpal::DefaultStreamConfigError::DeviceNotAvailable => AudioError::NoInputDevice,
+            cpal::DefaultStreamConfigError::StreamTypeNotSupported => {
+                AudioError::DeviceError(format!("'{}' does not support audio input", device_name))
+            }
+            cpal::DefaultStreamConfigError::BackendSpecific { err } => {
+                classify_backend_error(device_name, &err.description)
+            }
+        }
+        .into()
+    })
+}
+
+fn build_error(device_name: &str, error: cpal::BuildStreamError) -> AudioError {
+    match error {
+        cpal::BuildStreamError::DeviceNotAvailable => AudioError::NoInputDevice,
+        cpal::BuildStreamError::BackendSpecific { err } => classify_backend_error(device_name, &err.description),
+        other => AudioError::DeviceError(other.to_string()),
+    }
+}
+
+fn play_error(device_name: &str, error: cpal::PlayStreamError) -> AudioError {
+    match error {
+        cpal::PlayStreamError::DeviceNotAvailable => AudioError::NoInputDevice,
+        cpal::PlayStreamError::BackendSpecific { err } => classify_backend_error(device_name, &err.description),
+    }
+}
+
+/// Map a host-specific error message onto an actionable audio error
+fn classify_backend_error(device_name: &str, description: &str) -> AudioError {
+    let lower = description.to_lowercase();
+
+    // E_ACCESSDENIED on WASAPI, EACCES/EPERM on ALSA
+    if lower.contains("access is denied")
+        || lower.contains("access denied")
+        || lower.contains("permission denied")
+        || lower.contains("0x80070005")
+    {
+        return AudioError::MicrophonePermissionDenied;
+    }
+
+    // AUDCLNT_E_DEVICE_IN_USE on WASAPI, EBUSY on ALSA
+    if lower.contains("in use")
+        || lower.contains("busy")
+        || lower.contains("0x8889000a")
+    {
+        return AudioError::DeviceBusy(device_name.to_string());
+    }
+
+    AudioError::DeviceError(description.to_string())
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::core::{w, PCWSTR};
+    use windows::Win32::Foundation::ERROR_SUCCESS;
+    use windows::Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_SZ};
+
+    const CONSENT_STORE: PCWSTR =
+        w!("Software\\Microsoft\\Windows\\CurrentVersion\\CapabilityAccessManager\\ConsentStore\\microphone");
+    const CONSENT_STORE_DESKTOP: PCWSTR = w!(
+        "Software\\Microsoft\\Windows\\CurrentVersion\\CapabilityAccessManager\\ConsentStore\\microphone\\NonPackaged"
+    );
+
+    /// Read a consent value ("Allow" or "Deny") from the privacy settings store
+    fn consent_value(subkey: PCWSTR) -> Option<String> {
+        let mut buffer = [0u16; 16];
+        let mut size = std::mem::size_of_val(&buffer) as u32;
+
+        let status = unsafe {
+            RegGetValueW(
+                HKEY_CURRENT_USER,
+                subkey,
+                w!("Value"),
+                RRF_RT_REG_SZ,
+                None,
+                Some(buffer.as_mut_ptr().cast()),
+                Some(&mut size),
+            )
+        };
+        if status != ERROR_SUCCESS {
+            return None;
+        }
+
+        let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+        Some(String::from_utf16_lossy(&buffer[..len]))
+    }
+
+    /// Whether Windows privacy settings block microphone access for desktop apps
+    pub fn access_denied() -> bool {
+        [CONSENT_STORE, CONSENT_STORE_DESKTOP]
+            .into_iter()
+            .any(|key| consent_value(key).is_some_and(|v| v.eq_ignore_ascii_case("Deny")))
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    /// Other platforms report denied access when the device is opened
+    pub fn access_denied() -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_permission_denied() {
+        let err = classify_backend_error("Mic", "Access is denied. (0x80070005)");
+        assert!(matches!(err, AudioError::MicrophonePermissionDenied));
+    }
+
+    #[test]
+    fn test_classify_device_busy() {
+        let err = classify_backend_error("USB Mic", "ALSA function 'snd_pcm_open' failed: Device or resource busy");
+        assert!(matches!(err, AudioError::DeviceBusy(ref name) if name == "USB Mic"));
+    }
+
+    #[test]
+    fn test_classify_unknown_error() {
+        let err = classify_backend_error("Mic", "Something unexpected");
+        assert!(matches!(err, AudioError::DeviceError(_)));
+    }
+
+    #[test]
+    fn test_levels() {
+        let (peak, rms) = levels(&[0.5, -0.5, 0.5, -0.5]);
+        assert_eq!((peak, rms), (0.5, 0.5));
+        assert_eq!(levels(&[]), (0.0, 0.0));
+    }
+}
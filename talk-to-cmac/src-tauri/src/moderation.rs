@@ -0,0 +1,119 @@
+//! Content moderation
+//!
+//! Checks user input against local rules and, optionally, the OpenAI moderation
+//! endpoint before it is sent to the LLM. Depending on configuration, flagged
+//! input is either blocked with a refusal or forwarded and reported.
+
+use crate::api::ModerationClient;
+use crate::config::{ModerationAction, ModerationConfig, ModerationProvider};
+use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
+
+/// Outcome of moderating a piece of user input
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModerationVerdict {
+    /// Whether the input was withheld from the LLM
+    pub blocked: bool,
+
+    /// Categories that flagged the input (e.g., "harassment", "local_rule")
+    pub categories: Vec<String>,
+
+    /// Refusal returned in place of an LLM response when blocked
+    pub refusal: Option<String>,
+}
+
+/// Moderate user input, returning a verdict only if it was flagged
+///
+/// If the remote moderation service cannot be reached, the input is allowed
+/// through (local rules still apply) and a warning is logged.
+pub async fn review(config: &ModerationConfig, api_key: Option<String>, text: &str) -> Option<ModerationVerdict> {
+    if !config.enabled {
+        return None;
+    }
+
+    let mut categories = local_matches(&config.blocked_patterns, text);
+
+    if config.provider == ModerationProvider::OpenAi {
+        let result = match ModerationClient::new(config.clone(), api_key) {
+            Ok(client) => client.check(text).await,
+            Err(e) => Err(e),
+        };
+
+        match result {
+            Ok(flagged) => categories.extend(flagged),
+            Err(e) => tracing::warn!("Moderation check failed, allowing input: {}", e),
+        }
+    }
+
+    if categories.is_empty() {
+        return None;
+    }
+
+    let blocked = config.action == ModerationAction::Block;
+    tracing::warn!(
+        "Input flagged by moderation ({}): {:?}",
+        if blocked { "blocked" } else { "forwarded" },
+        categories
+    );
+
+    Some(ModerationVerdict {
+        blocked,
+        categories,
+        refusal: blocked.then(|| config.refusal_message.clone()),
+    })
+}
+
+/// Categories for every local rule that matches (case-insensitive regexes)
+fn local_matches(patterns: &[String], text: &str) -> Vec<String> {
+    let matched = patterns.iter().any(|pattern| {
+        match RegexBuilder::new(pattern).case_insensitive(true).build() {
+            Ok(regex) => regex.is_match(text),
+            Err(e) => {
+                tracing::warn!("Ignoring invalid moderation pattern '{}': {}", pattern, e);
+                false
+            }
+        }
+    });
+
+    if matched {
+        vec!["local_rule".to_string()]
+    } else {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local_config(action: ModerationAction) -> ModerationConfig {
+        ModerationConfig {
+            enabled: true,
+            provider: ModerationProvider::Local,
+            action,
+            blocked_patterns: vec![r"\bhow to (make|build) a bomb\b".to_string()],
+            ..ModerationConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_local_rule_blocks_with_refusal() {
+        let config = local_config(ModerationAction::Block);
+        let verdict = review(&config, None, "How to build a bomb").await.unwrap();
+
+        assert!(verdict.blocked);
+        assert_eq!(verdict.categories, vec!["local_rule"]);
+        assert_eq!(verdict.refusal.as_deref(), Some(config.refusal_message.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_flag_action_forwards_and_clean_input_passes() {
+        let config = local_config(ModerationAction::Flag);
+
+        let verdict = review(&config, None, "how to make a bomb").await.unwrap();
+        assert!(!verdict.blocked);
+        assert!(verdict.refusal.is_none());
+
+        assert!(review(&config, None, "What's the weather?").await.is_none());
+    }
+}
@@ -0,0 +1,154 @@
+//! Narrated conversation export
+//!
+//! Speaks every user and assistant turn of a conversation with the configured
+//! text-to-speech provider and joins the results into one MP3, with a short
+//! pause between turns, so a session can be shared as a single audio file.
+//! User and assistant turns can use different voices. System messages are not
+//! spoken.
+//!
+//! MP3 frames are self-contained, so the clips are joined by concatenation
+//! after dropping each clip's ID3 tag; players handle the change in bitrate
+//! between synthesized speech and the silent gaps.
+
+use crate::api::cooldown::Cooldowns;
+use crate::api::mock::silent_mp3;
+use crate::config::{ApiKeys, AppConfig};
+use crate::error::{AppError, AppResult};
+use crate::redaction;
+use crate::state::{Message, MessageRole};
+use serde::{Deserialize, Serialize};
+
+/// Pause between turns
+const TURN_GAP_MS: u64 = 700;
+
+/// Voices to speak each side of the conversation with (`None` keeps the configured voice)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NarrationVoices {
+    pub user: Option<String>,
+    pub assistant: Option<String>,
+}
+
+/// Summary of an exported narration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NarrationSummary {
+    /// Turns spoken
+    pub turns: usize,
+
+    /// Size of the MP3 (bytes)
+    pub size_bytes: usize,
+}
+
+/// Synthesize the user and assistant turns of `messages` into one MP3
+pub async fn narrate(
+    messages: &[Message],
+    voices: &NarrationVoices,
+    config: &AppConfig,
+    api_keys: &ApiKeys,
+    cooldowns: &Cooldowns,
+) -> AppResult<(Vec<u8>, usize)> {
+    let user_tts = cooldowns.tts_provider(&with_voice(config, voices.user.as_deref()), api_keys)?;
+    let assistant_tts = cooldowns.tts_provider(&with_voice(config, voices.assistant.as_deref()), api_keys)?;
+    let gap = silent_mp3(TURN_GAP_MS);
+
+    let mut audio = Vec::new();
+    let mut turns = 0;
+    for message in messages {
+        let tts = match message.role {
+            MessageRole::User => &user_tts,
+            MessageRole::Assistant => &assistant_tts,
+            MessageRole::System => continue,
+        };
+        if message.content.trim().is_empty() {
+            continue;
+        }
+
+        let text = redaction::apply(&config.privacy.redaction, "speech", message.content.clone());
+        let clip = tts.synthesize_speech(&text).await?;
+        if turns > 0 {
+            audio.extend_from_slice(&gap);
+        }
+        audio.extend_from_slice(strip_id3(&clip));
+        turns += 1;
+    }
+
+    if turns == 0 {
+        return Err(AppError::Generic("The conversation has no turns to narrate".to_string()));
+    }
+    tracing::info!("Narrated {} turn(s) into {} bytes of audio", turns, audio.len());
+    Ok((audio, turns))
+}
+
+/// Config speaking with `voice_id` instead of the configured voice
+fn with_voice(config: &AppConfig, voice_id: Option<&str>) -> AppConfig {
+    let mut config = config.clone();
+    if let Some(voice_id) = voice_id {
+        config.elevenlabs.voice_id = voice_id.to_string();
+        config.azure.speech_voice = voice_id.to_string();
+    }
+    config
+}
+
+/// Audio without its leading ID3v2 tag, which may only appear at the start of a file
+fn strip_id3(audio: &[u8]) -> &[u8] {
+    if audio.len() < 10 || &audio[..3] != b"ID3" {
+        return audio;
+    }
+
+    // Tag size is stored as four 7-bit bytes and excludes the 10-byte header and optional footer
+    let size = audio[6..10].iter().fold(0usize, |size, byte| (size << 7) | usize::from(byte & 0x7F));
+    let footer = if audio[5] & 0x10 != 0 { 10 } else { 0 };
+    &audio[(10 + size + footer).min(audio.len())..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::TtsProviderKind;
+
+    fn message(role: MessageRole, content: &str) -> Message {
+        Message {
+            id: String::new(),
+            role,
+            content: content.to_string(),
+            timestamp: 0,
+            stages: None,
+            variant: None,
+            rating: None,
+        }
+    }
+
+    #[test]
+    fn test_strip_id3() {
+        let mut tagged = b"ID3\x04\x00\x00\x00\x00\x01\x00".to_vec();
+        tagged.extend_from_slice(&[0; 128]);
+        tagged.extend_from_slice(&[0xFF, 0xFB, 0x90, 0x64]);
+        assert_eq!(strip_id3(&tagged), [0xFF, 0xFB, 0x90, 0x64]);
+
+        let untagged = [0xFF, 0xFB, 0x90, 0x64];
+        assert_eq!(strip_id3(&untagged), untagged);
+        assert_eq!(strip_id3(b"ID3\x04\x00\x00\x7F\x7F\x7F\x7F"), b"");
+    }
+
+    #[tokio::test]
+    async fn test_turns_are_joined_with_gaps() {
+        let mut config = AppConfig::default();
+        config.providers.tts = TtsProviderKind::Mock;
+        config.mock.latency_ms = 0;
+        let api_keys = ApiKeys::none();
+        let messages = [
+            message(MessageRole::System, "You are a support agent."),
+            message(MessageRole::User, "My router keeps rebooting."),
+            message(MessageRole::Assistant, "Let's check the firmware version first."),
+        ];
+
+        let (audio, turns) = narrate(&messages, &NarrationVoices::default(), &config, &api_keys, &Cooldowns::default())
+            .await
+            .unwrap();
+        assert_eq!(turns, 2);
+        let expected = silent_mp3(26 * 60).len() + silent_mp3(TURN_GAP_MS).len() + silent_mp3(39 * 60).len();
+        assert_eq!(audio.len(), expected);
+
+        let empty = narrate(&messages[..1], &NarrationVoices::default(), &config, &api_keys, &Cooldowns::default()).await;
+        assert!(empty.is_err());
+    }
+}
@@ -0,0 +1,278 @@
+//! News briefing
+//!
+//! Reads the newest items of the feeds in `news.feeds` (see
+//! [`crate::api::feeds`]) for `give_news_briefing`, which has the LLM turn them
+//! into a short spoken summary. RSS 2.0 `item`s and Atom `entry`s are read;
+//! only the title, link, date, and a plain-text summary are kept.
+//!
+//! A feed that fails is skipped with a warning, so one broken feed doesn't
+//! stop the briefing.
+
+use crate::api::feeds::FeedClient;
+use crate::config::NewsConfig;
+use crate::html;
+use crate::i18n;
+use chrono::{DateTime, Local, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::time::Duration;
+
+/// Characters of each item's summary given to the LLM
+const SUMMARY_CHARS: usize = 400;
+
+/// A news item
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NewsItem {
+    /// Name of the feed it came from
+    pub source: String,
+
+    pub title: String,
+
+    /// Link to the full story (empty if the feed gives none)
+    pub link: String,
+
+    /// Publication time (Unix timestamp), when the feed gives one
+    pub published: Option<i64>,
+
+    /// Plain-text summary or description
+    pub summary: String,
+}
+
+/// The newest items of each configured feed, feed by feed
+pub async fn latest(config: &NewsConfig) -> Vec<NewsItem> {
+    let client = match FeedClient::new(Duration::from_secs(config.timeout_secs)) {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::warn!("Cannot read news feeds: {}", e);
+            return Vec::new();
+        }
+    };
+    let oldest = (config.max_age_hours > 0)
+        .then(|| Utc::now().timestamp() - (config.max_age_hours * 60 * 60) as i64);
+
+    let mut latest = Vec::new();
+    for feed in &config.feeds {
+        match client.fetch(feed).await {
+            Ok(items) => latest.extend(newest(items, config.items_per_feed, oldest)),
+            Err(e) => tracing::warn!("News feed '{}' failed: {}", feed.name, e),
+        }
+    }
+    latest
+}
+
+/// The `count` most recent of `items` published after `oldest`; undated items are kept, last
+fn newest(mut items: Vec<NewsItem>, count: usize, oldest: Option<i64>) -> Vec<NewsItem> {
+    items.retain(|item| match (item.published, oldest) {
+        (Some(published), Some(oldest)) => published >= oldest,
+        _ => true,
+    });
+    // Stable, so undated items keep their feed order
+    items.sort_by_key(|item| Reverse(item.published.unwrap_or(i64::MIN)));
+    items.truncate(count);
+    items
+}
+
+/// System prompt for the summary, asking for an answer in the language of `locale`
+pub fn instructions(config: &NewsConfig, locale: &str) -> String {
+    format!("{} Answer in {}.", config.prompt, i18n::tr(locale, "locale-name"))
+}
+
+/// Items listed for the LLM
+pub fn describe(items: &[NewsItem]) -> String {
+    items
+        .iter()
+        .map(|item| {
+            let published = item
+                .published
+                .and_then(|time| Utc.timestamp_opt(time, 0).single())
+                .map(|time| format!(" ({})", time.with_timezone(&Local).format("%a %H:%M")))
+                .unwrap_or_default();
+            let summary = match item.summary.char_indices().nth(SUMMARY_CHARS) {
+                Some((end, _)) => format!("{}...", &item.summary[..end]),
+                None => item.summary.clone(),
+            };
+            format!("[{}] {}{}\n{}", item.source, item.title, published, summary)
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Read the items of an RSS or Atom feed, skipping items without a title
+pub fn parse_feed(xml: &str, source: &str) -> Vec<NewsItem> {
+    let (entry, atom) = if find_element(xml, "item", 0).is_some() { ("item", false) } else { ("entry", true) };
+
+    let mut items = Vec::new();
+    let mut from = 0;
+    while let Some(element) = find_element(xml, entry, from) {
+        from = element.end;
+        let content = element.content;
+
+        let title = one_line(&html::to_text(&text(field(content, "title"))));
+        if title.is_empty() {
+            continue;
+        }
+        let link = if atom {
+            find_element(content, "link", 0)
+                .and_then(|link| attribute(link.attributes, "href"))
+                .map(html::unescape)
+                .unwrap_or_default()
+        } else {
+            text(field(content, "link")).trim().to_string()
+        };
+        let published = ["pubDate", "published", "updated", "dc:date"].iter().find_map(|name| {
+            let date = text(field(content, name));
+            let date = date.trim();
+            DateTime::parse_from_rfc2822(date)
+                .or_else(|_| DateTime::parse_from_rfc3339(date))
+                .ok()
+                .map(|date| date.timestamp())
+        });
+        let summary = ["description", "summary", "content"]
+            .iter()
+            .map(|name| one_line(&html::to_text(&text(field(content, name)))))
+            .find(|summary| !summary.is_empty())
+            .unwrap_or_default();
+
+        items.push(NewsItem {
+            source: source.to_string(),
+            title,
+            link,
+            published,
+            summary,
+        });
+    }
+    items
+}
+
+/// An element found in a document
+struct Element<'a> {
+    attributes: &'a str,
+    content: &'a str,
+
+    /// Byte offset just past the element
+    end: usize,
+}
+
+/// The first `<name>` element at or after byte `from` of `xml`
+fn find_element<'a>(xml: &'a str, name: &str, mut from: usize) -> Option<Element<'a>> {
+    let open = format!("<{}", name);
+    let close = format!("</{}>", name);
+    while let Some(found) = xml[from..].find(&open) {
+        let start = from + found + open.len();
+        from = start;
+
+        // `<link` must not match `<linkedin`
+        if !xml[start..].starts_with(|c: char| c == '>' || c == '/' || c.is_whitespace()) {
+            continue;
+        }
+        let tag_end = start + xml[start..].find('>')?;
+        let attributes = &xml[start..tag_end];
+        if let Some(attributes) = attributes.strip_suffix('/') {
+            return Some(Element { attributes, content: "", end: tag_end + 1 });
+        }
+        let content_end = tag_end + 1 + xml[tag_end + 1..].find(&close)?;
+        return Some(Element {
+            attributes,
+            content: &xml[tag_end + 1..content_end],
+            end: content_end + close.len(),
+        });
+    }
+    None
+}
+
+/// Content of the first `<name>` element of `xml`, or nothing
+fn field<'a>(xml: &'a str, name: &str) -> &'a str {
+    find_element(xml, name, 0).map(|element| element.content).unwrap_or_default()
+}
+
+/// Value of attribute `name` in the attributes of a start tag
+fn attribute<'a>(attributes: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = attributes;
+    while let Some(found) = rest.find(name) {
+        let before = rest[..found].chars().next_back();
+        let after = rest[found + name.len()..].trim_start();
+        rest = &rest[found + name.len()..];
+        if !before.is_some_and(char::is_whitespace) {
+            continue;
+        }
+        let Some(value) = after.strip_prefix('=').map(str::trim_start) else {
+            continue;
+        };
+        let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        return value[1..].split(quote).next();
+    }
+    None
+}
+
+/// Character data of an element: CDATA sections as they are, other text unescaped
+fn text(content: &str) -> String {
+    let content = content.trim();
+    match content.strip_prefix("<![CDATA[").and_then(|data| data.strip_suffix("]]>")) {
+        Some(data) => data.to_string(),
+        None => html::unescape(content),
+    }
+}
+
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rss_items_are_read() {
+        let xml = r#"<?xml version="1.0"?>
+            <rss version="2.0"><channel><title>Trade News</title>
+            <item>
+              <title>Refrigerant rules &amp; deadlines</title>
+              <link>https://example.com/refrigerant</link>
+              <pubDate>Fri, 16 Oct 2026 14:00:00 GMT</pubDate>
+              <description><![CDATA[<p>New <b>R-454B</b> guidance.</p>]]></description>
+            </item>
+            <item><title></title><description>No title</description></item>
+            </channel></rss>"#;
+
+        let items = parse_feed(xml, "Trade News");
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].title, "Refrigerant rules & deadlines");
+        assert_eq!(items[0].link, "https://example.com/refrigerant");
+        assert_eq!(items[0].published, Some(Utc.with_ymd_and_hms(2026, 10, 16, 14, 0, 0).unwrap().timestamp()));
+        assert_eq!(items[0].summary, "New R-454B guidance.");
+    }
+
+    #[test]
+    fn test_atom_entries_are_read() {
+        let xml = r#"<feed xmlns="http://www.w3.org/2005/Atom"><title>Blog</title>
+            <link href="https://example.com/"/>
+            <entry>
+              <title type="html">Heat pump sales &lt;em&gt;up&lt;/em&gt;</title>
+              <link rel="alternate" href="https://example.com/heat-pumps?a=1&amp;b=2"/>
+              <updated>2026-10-16T09:30:00Z</updated>
+              <summary>Sales rose 12%.</summary>
+            </entry></feed>"#;
+
+        let items = parse_feed(xml, "Blog");
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].title, "Heat pump sales up");
+        assert_eq!(items[0].link, "https://example.com/heat-pumps?a=1&b=2");
+        assert_eq!(items[0].published, Some(Utc.with_ymd_and_hms(2026, 10, 16, 9, 30, 0).unwrap().timestamp()));
+        assert_eq!(items[0].summary, "Sales rose 12%.");
+    }
+
+    #[test]
+    fn test_newest_items_within_age_limit() {
+        let item = |title: &str, published| NewsItem {
+            source: "Feed".to_string(),
+            title: title.to_string(),
+            link: String::new(),
+            published,
+            summary: String::new(),
+        };
+        let items = vec![item("old", Some(100)), item("undated", None), item("new", Some(300)), item("mid", Some(200))];
+
+        let titles: Vec<String> = newest(items, 2, Some(150)).into_iter().map(|item| item.title).collect();
+        assert_eq!(titles, ["new", "mid"]);
+    }
+}
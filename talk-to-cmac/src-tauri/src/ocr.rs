@@ -0,0 +1,94 @@
+//! Text recognition in images
+//!
+//! Runs the Tesseract command-line tool set in `tools.ocr`, feeding it the
+//! image on standard input and reading the text from standard output, so no
+//! temporary files are written. Used by `ocr_image` and, when enabled, for
+//! images sent with a message, whose text is then sent to the LLM as well.
+
+use crate::config::OcrConfig;
+use crate::error::{AppResult, OcrError};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Hides the console window Tesseract would otherwise open on Windows
+#[cfg(windows)]
+const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+/// Text in the image file at `path`
+pub async fn recognize_file(config: &OcrConfig, path: &Path) -> AppResult<String> {
+    let image = tokio::fs::read(path)
+        .await
+        .map_err(|e| OcrError::Failed(format!("{}: {}", path.display(), e)))?;
+    recognize(config, &image).await
+}
+
+/// Text in `image` (any format Tesseract reads: PNG, JPEG, TIFF, ...)
+pub async fn recognize(config: &OcrConfig, image: &[u8]) -> AppResult<String> {
+    let mut command = Command::new(&config.tesseract_path);
+    command
+        .args(["stdin", "stdout", "-l", &config.languages])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(windows)]
+    command.creation_flags(CREATE_NO_WINDOW);
+
+    let mut child = command.spawn().map_err(|e| OcrError::NotAvailable(e.to_string()))?;
+    let mut stdin = child.stdin.take().ok_or_else(|| OcrError::Failed("no standard input".to_string()))?;
+
+    let run = async {
+        // Closing standard input tells Tesseract the image is complete
+        stdin.write_all(image).await?;
+        drop(stdin);
+        child.wait_with_output().await
+    };
+    let output = tokio::time::timeout(Duration::from_secs(config.timeout_secs), run)
+        .await
+        .map_err(|_| OcrError::Timeout)?
+        .map_err(|e| OcrError::Failed(e.to_string()))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(OcrError::Failed(stderr.lines().last().unwrap_or_default().trim().to_string()).into());
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    tracing::info!("Recognized {} characters of text", text.trim().chars().count());
+    Ok(clean(&text))
+}
+
+/// Recognized text without the blank lines and form feeds Tesseract pads it with
+fn clean(text: &str) -> String {
+    text.lines()
+        .map(|line| line.trim_end_matches('\u{c}').trim_end())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+
+    #[tokio::test]
+    async fn test_missing_tesseract_is_reported() {
+        let config = OcrConfig {
+            tesseract_path: "talk-to-cmac-no-such-tesseract".to_string(),
+            ..OcrConfig::default()
+        };
+        assert!(matches!(
+            recognize(&config, &[0x89, b'P', b'N', b'G']).await,
+            Err(AppError::Ocr(OcrError::NotAvailable(_)))
+        ));
+    }
+
+    #[test]
+    fn test_clean_drops_padding() {
+        assert_eq!(clean("MODEL 4500\n\nSERIAL 12-345  \n\u{c}"), "MODEL 4500\nSERIAL 12-345");
+    }
+}
@@ -0,0 +1,389 @@
+//! Streaming voice pipeline
+//!
+//! Speaks the LLM's reply while it is still being generated. Streamed text is
+//! split into sentences, and each sentence is synthesized as soon as it is
+//! complete, so the first sentence can play while the rest is generating.
+//! Up to a configured number of sentences are synthesized at once, hiding
+//! synthesis latency behind playback, but chunks are always delivered in the
+//! order they must be played.
+//!
+//! Tagged reasoning is not spoken: only the start of a reply is held back while
+//! it may still be an opening `<think>` tag, and text inside a reasoning block
+//! is dropped (see [`AnswerStream`]).
+
+use crate::api::provider::{LlmProvider, TtsProvider};
+use crate::api::{AnswerStream, GenerationOverrides, LlmReply};
+use crate::error::{AppError, AppResult};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::Instrument;
+
+/// Shortest text treated as a sentence; shorter fragments ("Yes.", "1.") join the next one
+const MIN_SENTENCE_CHARS: usize = 8;
+
+/// Words ending in a period that do not end a sentence
+const ABBREVIATIONS: &[&str] = &[
+    "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "vs", "etc", "e.g", "i.e", "approx",
+];
+
+/// Synthesized audio for one sentence of a streamed reply
+#[derive(Debug, Clone, Serialize)]
+pub struct SpeechChunk {
+    /// Position in the reply, starting at 0
+    pub index: usize,
+
+    /// Text that was spoken
+    pub text: String,
+
+    /// Encoded audio (MP3)
+    pub audio: Vec<u8>,
+}
+
+/// Reply and complete audio of a streamed voice turn
+#[derive(Debug)]
+pub struct StreamedReply {
+    pub reply: LlmReply,
+
+    /// All chunks concatenated in order
+    pub audio: Vec<u8>,
+}
+
+/// Splits streamed text into sentences
+#[derive(Debug, Default)]
+pub struct SentenceSegmenter {
+    buffer: String,
+}
+
+impl SentenceSegmenter {
+    /// Add streamed text, returning the sentences it completed
+    pub fn push(&mut self, text: &str) -> Vec<String> {
+        self.buffer.push_str(text);
+
+        let mut sentences = Vec::new();
+        while let Some(end) = sentence_end(&self.buffer) {
+            let sentence = self.buffer[..end].trim().to_string();
+            self.buffer = self.buffer[end..].trim_start().to_string();
+            sentences.push(sentence);
+        }
+        sentences
+    }
+
+    /// Remaining text once the stream has ended
+    pub fn finish(self) -> Option<String> {
+        let rest = self.buffer.trim();
+        (!rest.is_empty()).then(|| rest.to_string())
+    }
+}
+
+/// Stream a reply and synthesize it sentence by sentence while it generates
+///
+/// `prepare` turns a sentence into the text to speak (filtering, redaction), and
+/// `on_chunk` receives each chunk of audio in order as soon as it is ready. At most
+/// `concurrency` sentences are synthesized at once. Reasoning is never spoken. Fails if
+/// either generation or synthesis fails.
+pub async fn stream_and_speak(
+    llm: &dyn LlmProvider,
+    tts: Arc<dyn TtsProvider>,
+    messages: Vec<(String, String)>,
+    overrides: &GenerationOverrides,
+    concurrency: usize,
+    prepare: impl Fn(String) -> String + Send + Sync,
+    mut on_chunk: impl FnMut(SpeechChunk) + Send,
+) -> AppResult<StreamedReply> {
+    let (sentences, mut queue) = mpsc::unbounded_channel::<String>();
+
+    // Stream the reply, queueing each sentence of the answer as it completes
+    let generate = async move {
+        let mut segmenter = SentenceSegmenter::default();
+        let mut answer = AnswerStream::default();
+
+        let mut on_delta = |delta: &str| {
+            for sentence in segmenter.push(&answer.push(delta)) {
+                let _ = sentences.send(sentence);
+            }
+        };
+        let reply = llm.stream_message_with(messages, overrides, &mut on_delta).await?;
+
+        for sentence in segmenter.push(&answer.finish()) {
+            let _ = sentences.send(sentence);
+        }
+        if let Some(rest) = segmenter.finish() {
+            let _ = sentences.send(rest);
+        }
+        Ok::<_, AppError>(reply)
+    };
+
+    // Synthesize queued sentences a few at a time, delivering them in order
+    let speak = async {
+        let concurrency = concurrency.max(1);
+        let mut in_flight: VecDeque<(String, JoinHandle<AppResult<Vec<u8>>>)> = VecDeque::new();
+        let mut receiving = true;
+        let mut audio = Vec::new();
+        let mut index = 0;
+
+        let result = loop {
+            tokio::select! {
+                sentence = queue.recv(), if receiving && in_flight.len() < concurrency => match sentence {
+                    Some(sentence) => {
+                        let text = prepare(sentence);
+                        if text.trim().is_empty() {
+                            continue;
+                        }
+                        let tts = Arc::clone(&tts);
+                        let spoken = text.clone();
+                        // Spawned tasks would otherwise lose the query's span
+                        let span = tracing::info_span!("tts", chars = spoken.len());
+                        let synthesis = async move { tts.synthesize_speech(&spoken).await }.instrument(span);
+                        in_flight.push_back((text, tokio::spawn(synthesis)));
+                    }
+                    None => receiving = false,
+                },
+                joined = async { (&mut in_flight[0].1).await }, if !in_flight.is_empty() => {
+                    let (text, _) = in_flight.pop_front().expect("a sentence is in flight");
+                    let chunk = match joined.map_err(|e| AppError::Generic(e.to_string())).and_then(|r| r) {
+                        Ok(chunk) => chunk,
+                        Err(e) => break Err(e),
+                    };
+                    tracing::debug!("Synthesized sentence {} ({} bytes)", index, chunk.len());
+                    audio.extend_from_slice(&chunk);
+                    on_chunk(SpeechChunk { index, text, audio: chunk });
+                    index += 1;
+                }
+                else => break Ok(audio),
+            }
+        };
+
+        // Synthesis still running after a failure is no longer needed
+        for (_, handle) in in_flight {
+            handle.abort();
+        }
+        result
+    };
+
+    let (reply, audio) = tokio::try_join!(generate, speak)?;
+    Ok(StreamedReply { reply, audio })
+}
+
+/// Byte index just past the first complete sentence in `text`, if there is one
+///
+/// A sentence ends at a line break, at CJK end punctuation, or at `.`, `!`, `?`, or `…`
+/// (with any closing quotes) followed by whitespace. Waiting for the whitespace keeps
+/// decimals and a still-streaming word like "3." from being cut.
+fn sentence_end(text: &str) -> Option<usize> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+
+    for (i, &(pos, c)) in chars.iter().enumerate() {
+        let end = match c {
+            '\n' => pos,
+            '。' | '！' | '？' => pos + c.len_utf8(),
+            '.' | '!' | '?' | '…' => {
+                let mut next = i + 1;
+                while chars
+                    .get(next)
+                    .is_some_and(|&(_, c)| matches!(c, '.' | '!' | '?' | '"' | '\'' | '”' | '’' | ')'))
+                {
+                    next += 1;
+                }
+                match chars.get(next) {
+                    Some(&(end, after)) if after.is_whitespace() && !(c == '.' && is_abbreviation(&text[..pos])) => {
+                        end
+                    }
+                    _ => continue,
+                }
+            }
+            _ => continue,
+        };
+
+        if text[..end].trim().chars().count() >= MIN_SENTENCE_CHARS {
+            return Some(end);
+        }
+    }
+    None
+}
+
+/// Whether the word before a period is an abbreviation or an initial
+fn is_abbreviation(before: &str) -> bool {
+    let word = before
+        .rsplit(char::is_whitespace)
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches(['(', '"', '\''])
+        .to_lowercase();
+
+    ABBREVIATIONS.contains(&word.as_str()) || (word.chars().count() == 1 && word.chars().all(char::is_alphabetic))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use crate::api::ModelInfo;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_sentences_complete_across_deltas() {
+        let mut segmenter = SentenceSegmenter::default();
+        assert!(segmenter.push("The weather is").is_empty());
+        assert!(segmenter.push(" sunny today.").is_empty());
+        assert_eq!(segmenter.push(" Expect a high"), vec!["The weather is sunny today."]);
+        assert_eq!(segmenter.push(" of 24 degrees!\nEnjoy"), vec!["Expect a high of 24 degrees!"]);
+        assert_eq!(segmenter.finish().as_deref(), Some("Enjoy"));
+    }
+
+    #[test]
+    fn test_abbreviations_and_short_fragments_are_kept_together() {
+        let mut segmenter = SentenceSegmenter::default();
+        let sentences = segmenter.push("Dr. Smith paid $3.50 for it. Yes. That is all, e.g. nothing else. ");
+        assert_eq!(
+            sentences,
+            vec!["Dr. Smith paid $3.50 for it.", "Yes. That is all, e.g. nothing else."]
+        );
+        assert_eq!(segmenter.finish(), None);
+    }
+
+    /// Streams its reply in the given deltas
+    struct ScriptedLlm(Vec<&'static str>);
+
+    impl ScriptedLlm {
+        /// Streams `reply` a word at a time
+        fn words(reply: &'static str) -> Self {
+            Self(reply.split_inclusive(' ').collect())
+        }
+    }
+
+    #[async_trait]
+    impl LlmProvider for ScriptedLlm {
+        async fn send_message_with(
+            &self,
+            _messages: Vec<(String, String)>,
+            _overrides: &GenerationOverrides,
+        ) -> AppResult<LlmReply> {
+            unreachable!()
+        }
+
+        async fn stream_message_with(
+            &self,
+            _messages: Vec<(String, String)>,
+            _overrides: &GenerationOverrides,
+            on_delta: &mut (dyn for<'d> FnMut(&'d str) + Send),
+        ) -> AppResult<LlmReply> {
+            for delta in &self.0 {
+                on_delta(delta);
+                tokio::task::yield_now().await;
+            }
+            Ok(LlmReply::parse(&self.0.concat(), None))
+        }
+
+        async fn list_models(&self) -> AppResult<Vec<ModelInfo>> {
+            Ok(Vec::new())
+        }
+
+        async fn check_connectivity(&self) -> AppResult<bool> {
+            Ok(true)
+        }
+    }
+
+    #[derive(Default)]
+    struct EchoTts {
+        active: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl TtsProvider for EchoTts {
+        async fn synthesize_speech(&self, text: &str) -> AppResult<Vec<u8>> {
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(active, Ordering::SeqCst);
+
+            // Later sentences finish faster, which must not reorder the chunks
+            tokio::time::sleep(std::time::Duration::from_millis(20 / (text.len() as u64 % 4 + 1))).await;
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            Ok(text.as_bytes().to_vec())
+        }
+
+        async fn check_connectivity(&self) -> AppResult<bool> {
+            Ok(true)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chunks_are_spoken_in_order_without_reasoning() {
+        let llm = ScriptedLlm::words("<think>Plan the answer first.</think> First sentence here. Second one follows. Done");
+        let tts = Arc::new(EchoTts::default());
+        let mut chunks = Vec::new();
+
+        let streamed = stream_and_speak(
+            &llm,
+            tts.clone(),
+            Vec::new(),
+            &GenerationOverrides::default(),
+            2,
+            |text| text.to_uppercase(),
+            |chunk| chunks.push(chunk),
+        )
+        .await
+        .unwrap();
+
+        let texts: Vec<_> = chunks.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, vec!["FIRST SENTENCE HERE.", "SECOND ONE FOLLOWS.", "DONE"]);
+        assert_eq!(chunks.iter().map(|c| c.index).collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(streamed.audio, b"FIRST SENTENCE HERE.SECOND ONE FOLLOWS.DONE");
+        assert_eq!(streamed.reply.reasoning.as_deref(), Some("Plan the answer first."));
+        assert!(tts.peak.load(Ordering::SeqCst) <= 2);
+    }
+
+    #[tokio::test]
+    async fn test_synthesis_runs_ahead_of_delivery() {
+        let llm = ScriptedLlm::words("One sentence is first. A second sentence here. The third sentence. And the fourth one.");
+        let tts = Arc::new(EchoTts::default());
+        let mut chunks = Vec::new();
+
+        stream_and_speak(&llm, tts.clone(), Vec::new(), &GenerationOverrides::default(), 3, |text| text, |chunk| {
+            chunks.push(chunk.index)
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(chunks, vec![0, 1, 2, 3]);
+        assert!(tts.peak.load(Ordering::SeqCst) > 1);
+        assert!(tts.peak.load(Ordering::SeqCst) <= 3);
+    }
+
+    async fn spoken(llm: ScriptedLlm) -> Vec<String> {
+        let mut chunks = Vec::new();
+        stream_and_speak(&llm, Arc::new(EchoTts::default()), Vec::new(), &GenerationOverrides::default(), 2, |text| text, |chunk| {
+            chunks.push(chunk.text)
+        })
+        .await
+        .unwrap();
+        chunks
+    }
+
+    #[test]
+    fn test_untagged_reply_is_released_while_streaming() {
+        let mut answer = AnswerStream::default();
+        let mut segmenter = SentenceSegmenter::default();
+        let mut released = Vec::new();
+        for delta in ["Hello there, ", "nice to meet you. ", "How can I help?"] {
+            released.extend(segmenter.push(&answer.push(delta)));
+        }
+
+        // The first sentence is ready before the stream ends
+        assert_eq!(released, vec!["Hello there, nice to meet you."]);
+        released.extend(segmenter.push(&answer.finish()));
+        released.extend(segmenter.finish());
+        assert_eq!(released, vec!["Hello there, nice to meet you.", "How can I help?"]);
+    }
+
+    #[tokio::test]
+    async fn test_tag_split_across_deltas_is_not_spoken() {
+        let llm = ScriptedLlm(vec!["<", "think", ">Check the units.", "</th", "ink>The answer ", "is 42 meters. ", "Anything else?"]);
+        assert_eq!(spoken(llm).await, vec!["The answer is 42 meters.", "Anything else?"]);
+
+        let llm = ScriptedLlm(vec!["Sure. Here it is now. <", "thi", "nk>Hidden</think> Shown at the end."]);
+        assert_eq!(spoken(llm).await, vec!["Sure. Here it is now.", "Shown at the end."]);
+    }
+}
@@ -0,0 +1,166 @@
+//! Topic policy
+//!
+//! Customer-facing deployments must not discuss everything a general-purpose
+//! model will. Each query is placed in a topic from `policy.denied` or
+//! `policy.allowed` by keyword, or, if no keyword matches and
+//! `policy.llm_classifier` is on, by asking the LLM. A query in a denied
+//! topic is refused with `policy.refusal_message`; so is a query in no
+//! allowed topic when any are listed. Refused queries never reach the
+//! conversation or the LLM that answers.
+//!
+//! If the classifier cannot be reached, the query counts as being about none
+//! of the topics.
+
+use crate::api::provider::LlmProvider;
+use crate::api::GenerationOverrides;
+use crate::config::{PolicyConfig, TopicRule};
+use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
+
+/// Reply of the classifier for a query in none of the topics
+const NO_TOPIC: &str = "none";
+
+/// A query the policy refused
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyVerdict {
+    /// Denied topic of the query, or `None` if it was in no allowed topic
+    pub topic: Option<String>,
+
+    /// Reply used in place of an LLM response
+    pub refusal: String,
+}
+
+/// Check `text` against the policy, returning a verdict only if it is refused
+///
+/// `classifier` is only used when `llm_classifier` is on and no keyword matches.
+pub async fn review(config: &PolicyConfig, classifier: Option<&dyn LlmProvider>, text: &str) -> Option<PolicyVerdict> {
+    if !config.enabled || (config.denied.is_empty() && config.allowed.is_empty()) {
+        return None;
+    }
+
+    let mut topic = keyword_topic(config, text);
+    if topic.is_none() && config.llm_classifier {
+        if let Some(classifier) = classifier {
+            topic = classify(config, classifier, text).await;
+        }
+    }
+
+    let denied = topic.filter(|topic| config.denied.iter().any(|rule| rule.name == *topic));
+    let refused = denied.is_some() || (topic.is_none() && !config.allowed.is_empty());
+    if !refused {
+        return None;
+    }
+
+    tracing::warn!("Query refused by the topic policy ({})", denied.unwrap_or("not an allowed topic"));
+    Some(PolicyVerdict {
+        topic: denied.map(str::to_string),
+        refusal: config.refusal_message.clone(),
+    })
+}
+
+/// Topic with a keyword in `text`, checking denied topics first
+fn keyword_topic<'a>(config: &'a PolicyConfig, text: &str) -> Option<&'a str> {
+    config
+        .denied
+        .iter()
+        .chain(&config.allowed)
+        .find(|rule| rule.keywords.iter().any(|keyword| contains_phrase(text, keyword)))
+        .map(|rule| rule.name.as_str())
+}
+
+/// Whether `text` contains `phrase` as whole words, ignoring case
+fn contains_phrase(text: &str, phrase: &str) -> bool {
+    let phrase = phrase.trim();
+    if phrase.is_empty() {
+        return false;
+    }
+    RegexBuilder::new(&format!(r"\b{}\b", regex::escape(phrase)))
+        .case_insensitive(true)
+        .build()
+        .is_ok_and(|regex| regex.is_match(text))
+}
+
+/// Topic the LLM places `text` in, if any
+async fn classify<'a>(config: &'a PolicyConfig, classifier: &dyn LlmProvider, text: &str) -> Option<&'a str> {
+    let rules: Vec<&TopicRule> = config.denied.iter().chain(&config.allowed).collect();
+    let names: Vec<&str> = rules.iter().map(|rule| rule.name.as_str()).collect();
+    let overrides = GenerationOverrides {
+        temperature: Some(0.0),
+        system_prompt: Some(format!(
+            "Classify the user's message into one of these topics: {}. Reply with the topic name only, \
+             or \"{}\" if it fits none of them.",
+            names.join(", "),
+            NO_TOPIC
+        )),
+        max_tokens: Some(20),
+        ..GenerationOverrides::default()
+    };
+
+    match classifier.send_message_with(vec![("user".to_string(), text.to_string())], &overrides).await {
+        Ok(reply) => parse_topic(&names, &reply.answer),
+        Err(e) => {
+            tracing::warn!("Topic classification failed: {}", e);
+            None
+        }
+    }
+}
+
+/// Topic named in the classifier's reply, ignoring case and surrounding punctuation
+fn parse_topic<'a>(names: &[&'a str], reply: &str) -> Option<&'a str> {
+    let reply = reply.trim().trim_matches(|c: char| !c.is_alphanumeric());
+    names.iter().copied().find(|name| name.eq_ignore_ascii_case(reply))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str, keywords: &[&str]) -> TopicRule {
+        TopicRule {
+            name: name.to_string(),
+            keywords: keywords.iter().map(|keyword| keyword.to_string()).collect(),
+        }
+    }
+
+    fn kiosk() -> PolicyConfig {
+        PolicyConfig {
+            enabled: true,
+            denied: vec![rule("politics", &["election", "prime minister"])],
+            allowed: vec![rule("products", &["filter", "warranty"])],
+            ..PolicyConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_denied_and_off_topic_queries_are_refused() {
+        let config = kiosk();
+
+        let verdict = review(&config, None, "Who won the Election? And what about the filter?").await.unwrap();
+        assert_eq!(verdict.topic.as_deref(), Some("politics"));
+        assert_eq!(verdict.refusal, config.refusal_message);
+
+        assert!(review(&config, None, "How long is the warranty?").await.is_none());
+        assert!(review(&config, None, "Tell me a joke").await.unwrap().topic.is_none());
+        // Keywords match whole words only
+        assert!(review(&config, None, "Are there filters for it?").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_without_allowed_topics_only_denied_ones_are_refused() {
+        let config = PolicyConfig {
+            allowed: Vec::new(),
+            ..kiosk()
+        };
+        assert!(review(&config, None, "Tell me a joke").await.is_none());
+        assert!(review(&config, None, "What did the prime minister say?").await.is_some());
+        assert!(review(&PolicyConfig::default(), None, "election").await.is_none());
+    }
+
+    #[test]
+    fn test_parse_topic() {
+        let names = ["politics", "products"];
+        assert_eq!(parse_topic(&names, " Products.\n"), Some("products"));
+        assert_eq!(parse_topic(&names, "\"politics\""), Some("politics"));
+        assert_eq!(parse_topic(&names, "none"), None);
+    }
+}
@@ -0,0 +1,123 @@
+//! Profanity filter
+//!
+//! Masks profane words in transcriptions and LLM responses before they are
+//! displayed or spoken, for deployments in customer-facing areas. Words are
+//! matched case-insensitively on word boundaries, including common suffixes.
+
+use crate::config::{ProfanityConfig, ProfanitySeverity};
+use regex::{Captures, Regex};
+
+/// Built-in word list with the severity of each entry
+const BUILTIN_WORDS: &[(&str, ProfanitySeverity)] = &[
+    ("damn", ProfanitySeverity::Mild),
+    ("hell", ProfanitySeverity::Mild),
+    ("crap", ProfanitySeverity::Mild),
+    ("bloody", ProfanitySeverity::Mild),
+    ("ass", ProfanitySeverity::Moderate),
+    ("asshole", ProfanitySeverity::Moderate),
+    ("bastard", ProfanitySeverity::Moderate),
+    ("bitch", ProfanitySeverity::Moderate),
+    ("dick", ProfanitySeverity::Moderate),
+    ("piss", ProfanitySeverity::Moderate),
+    ("shit", ProfanitySeverity::Moderate),
+    ("bullshit", ProfanitySeverity::Moderate),
+    ("cock", ProfanitySeverity::Severe),
+    ("cunt", ProfanitySeverity::Severe),
+    ("fuck", ProfanitySeverity::Severe),
+    ("motherfucker", ProfanitySeverity::Severe),
+];
+
+/// Compiled profanity filter
+pub struct ProfanityFilter {
+    pattern: Option<Regex>,
+}
+
+impl ProfanityFilter {
+    /// Build a filter for built-in words at or above the configured severity plus custom words
+    pub fn new(config: &ProfanityConfig) -> Self {
+        let allowed: Vec<String> = config.allowed_words.iter().map(|w| w.to_lowercase()).collect();
+
+        let words: Vec<String> = BUILTIN_WORDS
+            .iter()
+            .filter(|(_, severity)| *severity >= config.min_severity)
+            .map(|(word, _)| word.to_string())
+            .chain(config.custom_words.iter().map(|w| w.trim().to_lowercase()))
+            .filter(|word| !word.is_empty() && !allowed.contains(word))
+            .map(|word| regex::escape(&word))
+            .collect();
+
+        if words.is_empty() {
+            return Self { pattern: None };
+        }
+
+        let pattern = format!(r"(?i)\b(?:{})(?:s|es|ed|er|ers|ing|in|y|ty|py|head|heads)?\b", words.join("|"));
+        Self {
+            pattern: Regex::new(&pattern).ok(),
+        }
+    }
+
+    /// Replace each profane word with its first letter followed by asterisks
+    pub fn filter(&self, text: &str) -> String {
+        let Some(pattern) = &self.pattern else {
+            return text.to_string();
+        };
+
+        pattern
+            .replace_all(text, |caps: &Captures| {
+                let word = &caps[0];
+                let mut chars = word.chars();
+                let first = chars.next().map(String::from).unwrap_or_default();
+                format!("{}{}", first, "*".repeat(chars.count()))
+            })
+            .into_owned()
+    }
+}
+
+/// Filter `text` if the profanity filter is enabled
+pub fn apply(config: &ProfanityConfig, text: String) -> String {
+    if !config.enabled {
+        return text;
+    }
+
+    let filtered = ProfanityFilter::new(config).filter(&text);
+    if filtered != text {
+        tracing::info!("Profanity filter masked words in text");
+    }
+    filtered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(min_severity: ProfanitySeverity) -> ProfanityConfig {
+        ProfanityConfig {
+            enabled: true,
+            min_severity,
+            ..ProfanityConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_masks_words_and_suffixes() {
+        let filter = ProfanityFilter::new(&config(ProfanitySeverity::Mild));
+        assert_eq!(filter.filter("Well DAMN, that's shitty fucking luck"), "Well D***, that's s***** f****** luck");
+        assert_eq!(filter.filter("Please assist the class"), "Please assist the class");
+    }
+
+    #[test]
+    fn test_severity_threshold() {
+        let filter = ProfanityFilter::new(&config(ProfanitySeverity::Severe));
+        assert_eq!(filter.filter("damn, fuck"), "damn, f***");
+    }
+
+    #[test]
+    fn test_custom_and_allowed_words() {
+        let filter = ProfanityFilter::new(&ProfanityConfig {
+            custom_words: vec!["frak".to_string()],
+            allowed_words: vec!["hell".to_string()],
+            ..config(ProfanitySeverity::Mild)
+        });
+        assert_eq!(filter.filter("What the hell, frak it"), "What the hell, f*** it");
+    }
+}
@@ -0,0 +1,297 @@
+//! User profiles
+//!
+//! Several people can share one computer, each with a profile of their own:
+//! settings (voice, system prompt, hotkeys, ...), conversation history,
+//! recordings, and API keys are kept apart. The profile list and the active
+//! profile are stored in `profiles.json` in the app data directory.
+//!
+//! The default profile uses the app data directory and keyring service
+//! directly, so an install from before profiles existed becomes the default
+//! profile. Every other profile gets `profiles/<id>/` and the keyring service
+//! `com.cmac.talk-to-cmac.<id>`. A profile can be protected by a PIN, stored
+//! only as a salted Argon2 hash.
+
+use crate::config::ConfigManager;
+use crate::crypto::PinHash;
+use crate::error::{AppResult, ConfigError};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// ID of the profile that always exists
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Longest accepted profile name
+const MAX_NAME_LEN: usize = 64;
+
+/// A stored profile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Profile {
+    id: String,
+    name: String,
+
+    /// PIN hash, if the profile is protected
+    #[serde(default)]
+    pin: Option<PinHash>,
+
+    created_at: u64,
+}
+
+/// Contents of `profiles.json`
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProfileList {
+    #[serde(default)]
+    active: Option<String>,
+
+    #[serde(default)]
+    profiles: Vec<Profile>,
+}
+
+/// A profile as shown in the profile picker
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileInfo {
+    pub id: String,
+    pub name: String,
+
+    /// Whether switching to the profile asks for a PIN
+    pub has_pin: bool,
+
+    /// Whether this is the profile in use
+    pub active: bool,
+}
+
+/// The profiles in `profiles.json`
+pub struct ProfileStore {
+    path: PathBuf,
+}
+
+impl ProfileStore {
+    /// Profiles of the app data directory `app_dir`
+    pub fn new(app_dir: &Path) -> Self {
+        Self {
+            path: app_dir.join("profiles.json"),
+        }
+    }
+
+    /// Profiles of this computer's app data directory
+    pub fn open_default() -> AppResult<Self> {
+        Ok(Self::new(&ConfigManager::app_dir()?))
+    }
+
+    /// ID of the active profile; the default profile if none is set or the file cannot be read
+    pub fn active(&self) -> String {
+        match self.load() {
+            Ok(list) => active_id(&list),
+            Err(e) => {
+                tracing::warn!("Failed to read profiles, using the default profile: {}", e);
+                DEFAULT_PROFILE.to_string()
+            }
+        }
+    }
+
+    /// Every profile, the default one first
+    pub fn list(&self) -> AppResult<Vec<ProfileInfo>> {
+        let list = self.load()?;
+        let active = active_id(&list);
+        Ok(list.profiles.iter().map(|profile| info(profile, &active)).collect())
+    }
+
+    /// Add a profile named `name`, protected by `pin` if given
+    ///
+    /// The ID is derived from the name. The new profile does not become active.
+    pub fn create(&self, name: &str, pin: Option<&str>) -> AppResult<ProfileInfo> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LEN {
+            return Err(ConfigError::InvalidValue(format!(
+                "Profile names must be 1 to {} characters",
+                MAX_NAME_LEN
+            ))
+            .into());
+        }
+
+        let mut list = self.load()?;
+        if list.profiles.iter().any(|profile| profile.name.eq_ignore_ascii_case(name)) {
+            return Err(ConfigError::InvalidValue(format!("A profile named '{}' already exists", name)).into());
+        }
+
+        let profile = Profile {
+            id: unique_id(&list, name),
+            name: name.to_string(),
+            pin: pin.map(PinHash::new).transpose()?,
+            created_at: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs(),
+        };
+        let created = info(&profile, &active_id(&list));
+        list.profiles.push(profile);
+        self.save(&list)?;
+
+        tracing::info!("Created profile '{}'", created.id);
+        Ok(created)
+    }
+
+    /// Make profile `id` the active one, checking its PIN
+    pub fn switch(&self, id: &str, pin: Option<&str>) -> AppResult<ProfileInfo> {
+        let mut list = self.load()?;
+        let profile = find(&list, id)?;
+        check_pin(profile, pin)?;
+
+        let switched = info(profile, id);
+        list.active = Some(id.to_string());
+        self.save(&list)?;
+
+        tracing::info!("Switched to profile '{}'", id);
+        Ok(switched)
+    }
+
+    /// Set (or with `None`, remove) the PIN of profile `id`, checking the current one
+    pub fn set_pin(&self, id: &str, current_pin: Option<&str>, pin: Option<&str>) -> AppResult<ProfileInfo> {
+        let mut list = self.load()?;
+        check_pin(find(&list, id)?, current_pin)?;
+
+        let active = active_id(&list);
+        let profile = list.profiles.iter_mut().find(|profile| profile.id == id).expect("profile was found above");
+        profile.pin = pin.map(PinHash::new).transpose()?;
+        let updated = info(profile, &active);
+        self.save(&list)?;
+
+        tracing::info!("{} the PIN of profile '{}'", if pin.is_some() { "Set" } else { "Removed" }, id);
+        Ok(updated)
+    }
+
+    /// Stored profiles, with the default profile added if it is not stored yet
+    fn load(&self) -> AppResult<ProfileList> {
+        let mut list: ProfileList = if self.path.exists() {
+            let contents = fs::read_to_string(&self.path).map_err(|e| ConfigError::LoadFailed(e.to_string()))?;
+            serde_json::from_str(&contents).map_err(|e| ConfigError::ParseError(e.to_string()))?
+        } else {
+            ProfileList::default()
+        };
+
+        if !list.profiles.iter().any(|profile| profile.id == DEFAULT_PROFILE) {
+            list.profiles.insert(
+                0,
+                Profile {
+                    id: DEFAULT_PROFILE.to_string(),
+                    name: "Default".to_string(),
+                    pin: None,
+                    created_at: 0,
+                },
+            );
+        }
+        Ok(list)
+    }
+
+    fn save(&self, list: &ProfileList) -> AppResult<()> {
+        let contents = serde_json::to_string_pretty(list).map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
+        fs::write(&self.path, contents).map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// The active profile, falling back to the default one if it no longer exists
+fn active_id(list: &ProfileList) -> String {
+    list.active
+        .as_deref()
+        .filter(|active| list.profiles.iter().any(|profile| profile.id == *active))
+        .unwrap_or(DEFAULT_PROFILE)
+        .to_string()
+}
+
+fn find<'a>(list: &'a ProfileList, id: &str) -> AppResult<&'a Profile> {
+    list.profiles
+        .iter()
+        .find(|profile| profile.id == id)
+        .ok_or_else(|| ConfigError::InvalidValue(format!("Unknown profile: {}", id)).into())
+}
+
+fn info(profile: &Profile, active: &str) -> ProfileInfo {
+    ProfileInfo {
+        id: profile.id.clone(),
+        name: profile.name.clone(),
+        has_pin: profile.pin.is_some(),
+        active: profile.id == active,
+    }
+}
+
+/// Lowercase letters, digits, and dashes of `name`, made unique with a number
+fn unique_id(list: &ProfileList, name: &str) -> String {
+    let slug = name
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    let base = if slug.is_empty() { "profile".to_string() } else { slug };
+
+    let taken = |id: &str| list.profiles.iter().any(|profile| profile.id == id);
+    (1..)
+        .map(|n| if n == 1 { base.clone() } else { format!("{}-{}", base, n) })
+        .find(|id| !taken(id))
+        .expect("some numbered ID is free")
+}
+
+fn check_pin(profile: &Profile, pin: Option<&str>) -> AppResult<()> {
+    let Some(stored) = &profile.pin else {
+        return Ok(());
+    };
+    let matches = match pin {
+        Some(pin) => stored.matches(pin)?,
+        None => false,
+    };
+    if !matches {
+        return Err(ConfigError::IncorrectPin.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> ProfileStore {
+        let dir = std::env::temp_dir().join(format!("talk-to-cmac-profiles-{:x}", rand::random::<u64>()));
+        fs::create_dir_all(&dir).unwrap();
+        ProfileStore::new(&dir)
+    }
+
+    #[test]
+    fn test_default_profile_always_exists() {
+        let store = store();
+        assert_eq!(store.active(), DEFAULT_PROFILE);
+
+        let profiles = store.list().unwrap();
+        assert_eq!(profiles.len(), 1);
+        assert!(profiles[0].active && !profiles[0].has_pin);
+    }
+
+    #[test]
+    fn test_profiles_get_unique_ids() {
+        let store = store();
+        assert_eq!(store.create("Night Shift", None).unwrap().id, "night-shift");
+        assert!(store.create("night shift", None).unwrap_err().to_string().contains("already exists"));
+        assert_eq!(store.create("Night Shift!", None).unwrap().id, "night-shift-2");
+        assert_eq!(store.create("Jürgen", None).unwrap().id, "j-rgen");
+        assert_eq!(store.create("ßß", None).unwrap().id, "profile");
+        assert!(store.create("  ", None).is_err());
+    }
+
+    #[test]
+    fn test_switching_checks_the_pin() {
+        let store = store();
+        let created = store.create("Dana", Some("4821")).unwrap();
+        assert!(created.has_pin && !created.active);
+        assert!(store.create("Lee", Some("12")).is_err());
+
+        assert!(matches!(
+            store.switch("dana", Some("0000")),
+            Err(crate::error::AppError::Config(ConfigError::IncorrectPin))
+        ));
+        assert!(store.switch("dana", None).is_err());
+        assert_eq!(store.active(), DEFAULT_PROFILE);
+
+        assert!(store.switch("dana", Some("4821")).unwrap().active);
+        assert_eq!(store.active(), "dana");
+        assert!(!store.set_pin("dana", Some("4821"), None).unwrap().has_pin);
+        assert!(store.switch("nobody", None).is_err());
+    }
+}
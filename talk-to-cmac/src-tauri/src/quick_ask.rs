@@ -0,0 +1,184 @@
+//! Quick-ask popup
+//!
+//! A small borderless window with a single text field, for asking something
+//! without bringing up the main window. It is created the first time it is
+//! opened, from the tray menu or the `quick_ask` hotkey, and hidden again when
+//! it loses focus. The frontend renders it instead of the chat when its window
+//! label is [`LABEL`]; a question typed there goes through `send_message` like
+//! any other and the reply is spoken.
+//!
+//! The popup opens next to the tray icon, on the side facing the middle of the
+//! screen. Where the tray icon's position is unknown (opened by hotkey before
+//! the tray was shown, or on desktops that do not report it), it opens
+//! centered in the top third of the screen, like a launcher.
+
+use crate::error::{AppError, AppResult};
+use crate::i18n;
+use crate::state::AppState;
+use tauri::{AppHandle, Manager, PhysicalPosition, PhysicalSize, WebviewUrl, WebviewWindow, WebviewWindowBuilder, WindowEvent};
+
+/// Label of the popup window
+pub const LABEL: &str = "quick-ask";
+
+/// Logical size of the popup
+const WIDTH: f64 = 380.0;
+const HEIGHT: f64 = 150.0;
+
+/// Logical gap between the popup and the tray icon or screen edge
+const MARGIN: f64 = 8.0;
+
+/// Open the popup next to the tray icon and focus it
+pub fn show(app: &AppHandle) -> AppResult<()> {
+    let window = match app.get_webview_window(LABEL) {
+        Some(window) => window,
+        None => create(app)?,
+    };
+
+    if let Err(e) = place(app, &window) {
+        tracing::warn!("Quick-ask popup not positioned: {}", e);
+    }
+    window.show().map_err(window_error)?;
+    window.set_focus().map_err(window_error)?;
+    Ok(())
+}
+
+/// Hide the popup if it is open
+pub fn hide(app: &AppHandle) -> AppResult<()> {
+    if let Some(window) = app.get_webview_window(LABEL) {
+        window.hide().map_err(window_error)?;
+    }
+    Ok(())
+}
+
+/// Hide the popup if it is showing, otherwise open it
+pub fn toggle(app: &AppHandle) -> AppResult<()> {
+    let visible = app
+        .get_webview_window(LABEL)
+        .is_some_and(|window| window.is_visible().unwrap_or(false));
+    if visible {
+        hide(app)
+    } else {
+        show(app)
+    }
+}
+
+fn create(app: &AppHandle) -> AppResult<WebviewWindow> {
+    let locale = app.state::<AppState>().get_config().ui.locale;
+    let window = WebviewWindowBuilder::new(app, LABEL, WebviewUrl::default())
+        .title(i18n::tr(&locale, "tray-quick-ask"))
+        .inner_size(WIDTH, HEIGHT)
+        .resizable(false)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .visible(false)
+        .build()
+        .map_err(window_error)?;
+
+    let popup = window.clone();
+    window.on_window_event(move |event| {
+        if let WindowEvent::Focused(false) = event {
+            let _ = popup.hide();
+        }
+    });
+    tracing::info!("Quick-ask popup created");
+    Ok(window)
+}
+
+/// Move `window` next to the tray icon, or to the top third of its screen
+fn place(app: &AppHandle, window: &WebviewWindow) -> AppResult<()> {
+    let tray = app
+        .try_state::<crate::TrayHandle>()
+        .and_then(|tray| tray.0.rect().ok().flatten());
+    let monitor = match &tray {
+        Some(rect) => {
+            let scale = window.scale_factor().map_err(window_error)?;
+            let anchor = rect.position.to_physical::<f64>(scale);
+            app.monitor_from_point(anchor.x, anchor.y).map_err(window_error)?
+        }
+        None => window.current_monitor().map_err(window_error)?,
+    };
+    let Some(monitor) = monitor.or(window.primary_monitor().map_err(window_error)?) else {
+        return Ok(());
+    };
+
+    let scale = monitor.scale_factor();
+    let size = PhysicalSize::new(WIDTH * scale, HEIGHT * scale);
+    let screen = (monitor.position().cast::<f64>(), monitor.size().cast::<f64>());
+    let tray = tray.map(|rect| (rect.position.to_physical::<f64>(scale), rect.size.to_physical::<f64>(scale)));
+    let position = popup_position(tray, size, screen, MARGIN * scale);
+
+    window.set_position(position).map_err(window_error)?;
+    Ok(())
+}
+
+/// Top-left corner of a popup of `size` on `screen`, next to `tray` if known
+///
+/// The popup is centered on the tray icon and opens below it when the icon is
+/// in the top half of the screen (a menu bar), above it otherwise (a taskbar),
+/// then is kept `margin` inside the screen.
+fn popup_position(
+    tray: Option<(PhysicalPosition<f64>, PhysicalSize<f64>)>,
+    size: PhysicalSize<f64>,
+    screen: (PhysicalPosition<f64>, PhysicalSize<f64>),
+    margin: f64,
+) -> PhysicalPosition<i32> {
+    let (origin, extent) = screen;
+    let (x, y) = match tray {
+        Some((icon, icon_size)) => {
+            let x = icon.x + icon_size.width / 2.0 - size.width / 2.0;
+            let below = icon.y + icon_size.height / 2.0 < origin.y + extent.height / 2.0;
+            let y = if below {
+                icon.y + icon_size.height + margin
+            } else {
+                icon.y - size.height - margin
+            };
+            (x, y)
+        }
+        None => (
+            origin.x + (extent.width - size.width) / 2.0,
+            origin.y + extent.height / 3.0 - size.height / 2.0,
+        ),
+    };
+
+    let clamp = |value: f64, start: f64, length: f64, popup: f64| {
+        value.min(start + length - popup - margin).max(start + margin)
+    };
+    PhysicalPosition::new(
+        clamp(x, origin.x, extent.width, size.width).round() as i32,
+        clamp(y, origin.y, extent.height, size.height).round() as i32,
+    )
+}
+
+fn window_error(e: tauri::Error) -> AppError {
+    AppError::Generic(format!("Quick-ask window: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: (PhysicalPosition<f64>, PhysicalSize<f64>) =
+        (PhysicalPosition { x: 0.0, y: 0.0 }, PhysicalSize { width: 1920.0, height: 1080.0 });
+    const POPUP: PhysicalSize<f64> = PhysicalSize { width: 380.0, height: 150.0 };
+
+    fn icon(x: f64, y: f64) -> Option<(PhysicalPosition<f64>, PhysicalSize<f64>)> {
+        Some((PhysicalPosition::new(x, y), PhysicalSize::new(24.0, 24.0)))
+    }
+
+    #[test]
+    fn test_popup_opens_toward_middle_of_screen() {
+        // Taskbar at the bottom: above the icon, pulled in from the right edge
+        assert_eq!(popup_position(icon(1850.0, 1050.0), POPUP, SCREEN, 8.0), PhysicalPosition::new(1532, 892));
+        // Menu bar at the top: below the icon
+        assert_eq!(popup_position(icon(1000.0, 0.0), POPUP, SCREEN, 8.0), PhysicalPosition::new(822, 32));
+    }
+
+    #[test]
+    fn test_popup_without_tray_is_centered_in_top_third() {
+        assert_eq!(popup_position(None, POPUP, SCREEN, 8.0), PhysicalPosition::new(770, 285));
+
+        let second = (PhysicalPosition::new(1920.0, -200.0), PhysicalSize::new(1280.0, 1024.0));
+        assert_eq!(popup_position(None, POPUP, second, 8.0), PhysicalPosition::new(2370, 66));
+    }
+}
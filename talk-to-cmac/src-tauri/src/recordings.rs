@@ -0,0 +1,266 @@
+//! Saved recordings
+//!
+//! Optionally keeps a copy of each recorded utterance and each spoken reply under
+//! the app data directory so mis-transcriptions can be audited later. Every
+//! recording is stored as an audio file plus a small JSON sidecar with its metadata.
+
+use crate::config::ConfigManager;
+use crate::error::{AppResult, AudioError};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// What a saved recording contains
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingKind {
+    /// Audio recorded from the microphone
+    Utterance,
+
+    /// Synthesized speech played back to the user
+    Reply,
+}
+
+/// Metadata for a saved recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingInfo {
+    /// Unique recording ID (sorts chronologically)
+    pub id: String,
+
+    /// Whether this is user audio or a spoken reply
+    pub kind: RecordingKind,
+
+    /// Audio file extension (e.g., "wav", "mp3")
+    pub format: String,
+
+    /// Size of the audio file in bytes
+    pub size_bytes: u64,
+
+    /// Transcription of the utterance, or the text that was spoken
+    pub text: Option<String>,
+
+    /// Unix timestamp when the recording was saved
+    pub created_at: u64,
+}
+
+/// Directory-backed store of saved recordings
+pub struct RecordingStore {
+    dir: PathBuf,
+}
+
+impl RecordingStore {
+    /// Open (and create if needed) a recordings directory
+    pub fn new(dir: impl Into<PathBuf>) -> AppResult<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).map_err(|e| AudioError::WriteFailed(e.to_string()))?;
+        Ok(Self { dir })
+    }
+
+    /// Open the recordings directory under the app data directory
+    pub fn open_default() -> AppResult<Self> {
+        Self::new(ConfigManager::new()?.data_dir().join("recordings"))
+    }
+
+    /// Save audio and its metadata, returning the new recording's info
+    pub fn save(
+        &self,
+        kind: RecordingKind,
+        audio: &[u8],
+        format: &str,
+        text: Option<&str>,
+    ) -> AppResult<RecordingInfo> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let id = format!("{:013}-{:08x}", now.as_millis(), rand::random::<u32>());
+
+        let info = RecordingInfo {
+            id,
+            kind,
+            format: sanitize_format(format),
+            size_bytes: audio.len() as u64,
+            text: text.map(str::to_string),
+            created_at: now.as_secs(),
+        };
+
+        fs::write(self.audio_path(&info), audio).map_err(|e| AudioError::WriteFailed(e.to_string()))?;
+
+        let metadata = serde_json::to_string_pretty(&info)
+            .map_err(|e| AudioError::WriteFailed(e.to_string()))?;
+        fs::write(self.metadata_path(&info.id), metadata)
+            .map_err(|e| AudioError::WriteFailed(e.to_string()))?;
+
+        tracing::info!("Saved {:?} recording {} ({} bytes)", info.kind, info.id, info.size_bytes);
+        Ok(info)
+    }
+
+    /// List saved recordings, newest first
+    pub fn list(&self) -> AppResult<Vec<RecordingInfo>> {
+        let entries = fs::read_dir(&self.dir).map_err(|e| AudioError::ReadFailed(e.to_string()))?;
+
+        let mut recordings: Vec<RecordingInfo> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| {
+                let contents = fs::read_to_string(&path).ok()?;
+                serde_json::from_str(&contents).ok()
+            })
+            .collect();
+
+        recordings.sort_by(|a, b| b.id.cmp(&a.id));
+        Ok(recordings)
+    }
+
+    /// Read the audio bytes of a saved recording
+    pub fn read(&self, id: &str) -> AppResult<Vec<u8>> {
+        let info = self.info(id)?;
+        fs::read(self.audio_path(&info)).map_err(|e| AudioError::ReadFailed(e.to_string()).into())
+    }
+
+    /// Delete a saved recording and its metadata
+    pub fn delete(&self, id: &str) -> AppResult<()> {
+        let info = self.info(id)?;
+        remove_if_exists(&self.audio_path(&info))?;
+        remove_if_exists(&self.metadata_path(id))?;
+
+        tracing::info!("Deleted recording {}", id);
+        Ok(())
+    }
+
+    /// Delete recordings older than `retention_days` or beyond the newest `max_recordings`
+    ///
+    /// A value of 0 disables the corresponding limit. Returns how many were deleted.
+    pub fn prune(&self, retention_days: u32, max_recordings: usize) -> AppResult<usize> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let cutoff = now.saturating_sub(u64::from(retention_days) * 24 * 60 * 60);
+
+        let mut deleted = 0;
+        for (index, info) in self.list()?.iter().enumerate() {
+            let too_old = retention_days > 0 && info.created_at < cutoff;
+            let over_limit = max_recordings > 0 && index >= max_recordings;
+
+            if too_old || over_limit {
+                self.delete(&info.id)?;
+                deleted += 1;
+            }
+        }
+
+        if deleted > 0 {
+            tracing::info!("Pruned {} saved recording(s)", deleted);
+        }
+        Ok(deleted)
+    }
+
+    /// Delete every saved recording, returning how many were deleted
+    pub fn purge_all(&self) -> AppResult<usize> {
+        let recordings = self.list()?;
+        for info in &recordings {
+            self.delete(&info.id)?;
+        }
+        Ok(recordings.len())
+    }
+
+    /// Load metadata for a recording, rejecting IDs that could escape the directory
+    fn info(&self, id: &str) -> AppResult<RecordingInfo> {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(AudioError::ReadFailed(format!("Invalid recording ID: {}", id)).into());
+        }
+
+        let contents = fs::read_to_string(self.metadata_path(id))
+            .map_err(|_| AudioError::ReadFailed(format!("Recording not found: {}", id)))?;
+
+        serde_json::from_str(&contents).map_err(|e| AudioError::ReadFailed(e.to_string()).into())
+    }
+
+    /// Where the audio of a recording is stored
+    pub fn audio_path(&self, info: &RecordingInfo) -> PathBuf {
+        self.dir.join(format!("{}.{}", info.id, info.format))
+    }
+
+    fn metadata_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+}
+
+/// Keep only a short alphanumeric extension so metadata can't point outside the store
+fn sanitize_format(format: &str) -> String {
+    let format: String = format
+        .trim_start_matches('.')
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .take(8)
+        .collect::<String>()
+        .to_lowercase();
+
+    if format.is_empty() || format == "json" {
+        "bin".to_string()
+    } else {
+        format
+    }
+}
+
+fn remove_if_exists(path: &Path) -> AppResult<()> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(AudioError::WriteFailed(e.to_string()).into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store() -> (RecordingStore, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("talk-to-cmac-recordings-{:x}", rand::random::<u64>()));
+        (RecordingStore::new(&dir).unwrap(), dir)
+    }
+
+    #[test]
+    fn test_save_list_read_delete() {
+        let (store, dir) = temp_store();
+
+        let info = store
+            .save(RecordingKind::Utterance, b"RIFF....", "wav", Some("hello"))
+            .unwrap();
+        assert_eq!(info.size_bytes, 8);
+
+        let listed = store.list().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].text.as_deref(), Some("hello"));
+        assert_eq!(store.read(&info.id).unwrap(), b"RIFF....");
+
+        store.delete(&info.id).unwrap();
+        assert!(store.list().unwrap().is_empty());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_rejects_path_traversal_ids() {
+        let (store, dir) = temp_store();
+
+        assert!(store.read("../config").is_err());
+        assert!(store.delete("a/b").is_err());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_prune_keeps_newest() {
+        let (store, dir) = temp_store();
+
+        for _ in 0..3 {
+            store.save(RecordingKind::Reply, b"ID3", "mp3", None).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+        let newest = store.list().unwrap()[0].id.clone();
+
+        assert_eq!(store.prune(0, 1).unwrap(), 2);
+        let remaining = store.list().unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].id, newest);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,248 @@
+//! PII redaction
+//!
+//! Masks emails, phone numbers, SSNs, credit card numbers, and user-defined
+//! patterns in text before it is sent to the LLM or TTS services. Each redaction
+//! is recorded in an audit log (category and count only, never the original text).
+
+use crate::config::{ConfigManager, PiiKind, RedactionConfig};
+use crate::error::{AppResult, ConfigError};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Label used for matches of user-defined patterns
+const CUSTOM_LABEL: &str = "CUSTOM";
+
+/// Result of redacting a piece of text
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redaction {
+    /// Text with sensitive values replaced by placeholders such as `[EMAIL]`
+    pub text: String,
+
+    /// Number of values redacted per category
+    pub counts: BTreeMap<String, usize>,
+}
+
+/// Entry in the redaction audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionAuditEntry {
+    /// Unix timestamp of the redaction
+    pub timestamp: u64,
+
+    /// Where the text came from ("transcription", "message", or "speech")
+    pub source: String,
+
+    /// Number of values redacted per category
+    pub counts: BTreeMap<String, usize>,
+}
+
+/// A single redaction rule
+struct Rule {
+    label: &'static str,
+    pattern: Regex,
+    validate: Option<fn(&str) -> bool>,
+}
+
+/// Compiled set of redaction rules
+pub struct Redactor {
+    rules: Vec<Rule>,
+}
+
+impl Redactor {
+    /// Build a redactor for the enabled categories and custom patterns
+    ///
+    /// Custom patterns that fail to compile are skipped with a warning.
+    pub fn new(config: &RedactionConfig) -> Self {
+        // Order matters: card numbers and SSNs would otherwise be eaten by the phone pattern
+        let builtin = [
+            (PiiKind::CreditCard, "CARD", r"\b(?:\d[ -]?){12,18}\d\b", Some(luhn_valid as fn(&str) -> bool)),
+            (PiiKind::Ssn, "SSN", r"\b\d{3}-\d{2}-\d{4}\b", None),
+            (
+                PiiKind::Phone,
+                "PHONE",
+                r"(?:\+\d{1,3}[\s.-]?)?(?:\(\d{3}\)|\b\d{3})[\s.-]?\d{3}[\s.-]?\d{4}\b",
+                None,
+            ),
+            (PiiKind::Email, "EMAIL", r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b", None),
+        ];
+
+        let mut rules: Vec<Rule> = builtin
+            .into_iter()
+            .filter(|(kind, ..)| config.kinds.contains(kind))
+            .map(|(_, label, pattern, validate)| Rule {
+                label,
+                pattern: Regex::new(pattern).expect("built-in redaction pattern is valid"),
+                validate,
+            })
+            .collect();
+
+        for pattern in &config.custom_patterns {
+            match Regex::new(pattern) {
+                Ok(pattern) => rules.push(Rule {
+                    label: CUSTOM_LABEL,
+                    pattern,
+                    validate: None,
+                }),
+                Err(e) => tracing::warn!("Ignoring invalid redaction pattern '{}': {}", pattern, e),
+            }
+        }
+
+        Self { rules }
+    }
+
+    /// Replace every match of every rule with a `[LABEL]` placeholder
+    pub fn redact(&self, text: &str) -> Redaction {
+        let mut text = text.to_string();
+        let mut counts = BTreeMap::new();
+
+        for rule in &self.rules {
+            let mut count = 0;
+            text = rule
+                .pattern
+                .replace_all(&text, |caps: &regex::Captures| {
+                    let matched = &caps[0];
+                    if rule.validate.is_some_and(|validate| !validate(matched)) {
+                        return matched.to_string();
+                    }
+                    count += 1;
+                    format!("[{}]", rule.label)
+                })
+                .into_owned();
+
+            if count > 0 {
+                *counts.entry(rule.label.to_string()).or_insert(0) += count;
+            }
+        }
+
+        Redaction { text, counts }
+    }
+}
+
+/// Redact `text` if redaction is enabled, recording what was masked in the audit log
+pub fn apply(config: &RedactionConfig, source: &str, text: String) -> String {
+    if !config.enabled {
+        return text;
+    }
+
+    let redaction = Redactor::new(config).redact(&text);
+    if redaction.counts.is_empty() {
+        return text;
+    }
+
+    tracing::info!("Redacted {:?} from {}", redaction.counts, source);
+
+    let entry = RedactionAuditEntry {
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs(),
+        source: source.to_string(),
+        counts: redaction.counts,
+    };
+    if let Err(e) = append_audit(&entry) {
+        tracing::warn!("Failed to write redaction audit log: {}", e);
+    }
+
+    redaction.text
+}
+
+/// Read the most recent audit log entries, newest first
+pub fn read_audit(limit: usize) -> AppResult<Vec<RedactionAuditEntry>> {
+    let path = audit_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = fs::read_to_string(&path).map_err(|e| ConfigError::LoadFailed(e.to_string()))?;
+    Ok(contents
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .take(limit)
+        .collect())
+}
+
+fn append_audit(entry: &RedactionAuditEntry) -> AppResult<()> {
+    let line = serde_json::to_string(entry).map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(audit_path()?)
+        .map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
+    writeln!(file, "{}", line).map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
+    Ok(())
+}
+
+fn audit_path() -> AppResult<PathBuf> {
+    Ok(ConfigManager::new()?.data_dir().join("redaction_audit.jsonl"))
+}
+
+/// Luhn checksum, used to avoid masking arbitrary long numbers as card numbers
+fn luhn_valid(candidate: &str) -> bool {
+    let digits: Vec<u32> = candidate.chars().filter_map(|c| c.to_digit(10)).collect();
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| {
+            if i % 2 == 1 {
+                let doubled = d * 2;
+                if doubled > 9 { doubled - 9 } else { doubled }
+            } else {
+                d
+            }
+        })
+        .sum();
+
+    sum.is_multiple_of(10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redacts_builtin_categories() {
+        let redactor = Redactor::new(&RedactionConfig::default());
+        let result = redactor.redact(
+            "Email jane.doe@example.com or call (555) 123-4567. SSN 123-45-6789, card 4111 1111 1111 1111.",
+        );
+
+        assert_eq!(
+            result.text,
+            "Email [EMAIL] or call [PHONE]. SSN [SSN], card [CARD]."
+        );
+        assert_eq!(result.counts.get("EMAIL"), Some(&1));
+        assert_eq!(result.counts.get("CARD"), Some(&1));
+    }
+
+    #[test]
+    fn test_card_requires_valid_checksum() {
+        let redactor = Redactor::new(&RedactionConfig {
+            kinds: vec![PiiKind::CreditCard],
+            ..RedactionConfig::default()
+        });
+
+        let result = redactor.redact("Order 1234 5678 9012 3456 shipped");
+        assert!(result.counts.is_empty());
+        assert_eq!(result.text, "Order 1234 5678 9012 3456 shipped");
+    }
+
+    #[test]
+    fn test_custom_patterns_and_invalid_patterns() {
+        let redactor = Redactor::new(&RedactionConfig {
+            kinds: Vec::new(),
+            custom_patterns: vec![r"(?i)project\s+falcon".to_string(), "(".to_string()],
+            ..RedactionConfig::default()
+        });
+
+        let result = redactor.redact("Status of Project Falcon?");
+        assert_eq!(result.text, "Status of [CUSTOM]?");
+    }
+}
@@ -0,0 +1,93 @@
+//! Data retention enforcement
+//!
+//! Applies the configured [`RetentionPolicy`] to stored conversation history and
+//! saved recordings, and archives idle conversations, both periodically from a
+//! background task and on demand.
+
+use crate::audit;
+use crate::config::{AppConfig, RetentionPolicy};
+use crate::error::AppResult;
+use crate::history::HistoryStore;
+use crate::journal;
+use crate::recordings::RecordingStore;
+use crate::state::AppState;
+use crate::usage::UsageQueue;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+/// How often the background task re-applies the retention policy
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Effective retention in days for saved recordings (0 = keep forever)
+///
+/// The stricter of the privacy policy and the recordings' own setting wins.
+pub fn recording_retention_days(config: &AppConfig) -> u32 {
+    let own = config.audio.recording_retention_days;
+    match config.privacy.retention {
+        RetentionPolicy::Days { days } if days > 0 && (own == 0 || days < own) => days,
+        _ => own,
+    }
+}
+
+/// Apply the retention policy to history and saved recordings, and archive idle conversations, once
+pub fn enforce(config: &AppConfig, history: &HistoryStore) -> AppResult<()> {
+    history.apply_retention(&config.privacy.retention)?;
+    history.archive_idle(config.privacy.archive_after_days)?;
+
+    let recordings = RecordingStore::open_default()?;
+    if config.privacy.retention == RetentionPolicy::Nothing {
+        recordings.purge_all()?;
+    } else {
+        recordings.prune(recording_retention_days(config), config.audio.max_recordings)?;
+    }
+
+    Ok(())
+}
+
+/// Delete all stored conversations and recordings, queued usage reports, the request journal, and the API audit log
+pub fn purge_all(history: &HistoryStore) -> AppResult<()> {
+    history.purge_all()?;
+    let deleted = RecordingStore::open_default()?.purge_all()?;
+    UsageQueue::open_default()?.clear()?;
+    journal::clear()?;
+    audit::clear()?;
+
+    tracing::info!("Purged all stored data ({} recording(s))", deleted);
+    Ok(())
+}
+
+/// Spawn the background task that enforces retention at startup and then hourly
+pub fn spawn_cleanup_task(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let config = app.state::<AppState>().get_config();
+            let history = app.state::<HistoryStore>();
+
+            if let Err(e) = enforce(&config, &history) {
+                tracing::warn!("Retention cleanup failed: {}", e);
+            }
+
+            tokio::time::sleep(CLEANUP_INTERVAL).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recording_retention_uses_stricter_limit() {
+        let mut config = AppConfig::default();
+        config.audio.recording_retention_days = 30;
+
+        config.privacy.retention = RetentionPolicy::Days { days: 7 };
+        assert_eq!(recording_retention_days(&config), 7);
+
+        config.privacy.retention = RetentionPolicy::Days { days: 90 };
+        assert_eq!(recording_retention_days(&config), 30);
+
+        config.privacy.retention = RetentionPolicy::Days { days: 0 };
+        assert_eq!(recording_retention_days(&config), 30);
+    }
+}
@@ -0,0 +1,86 @@
+//! Second attempts at unhelpful answers
+//!
+//! With `openwebui.retry` enabled, an answer that is empty or a stock refusal
+//! ("I'm sorry, but I can't ...") is thrown away and the query sent once more
+//! with the configured instruction added, which is often enough to get a
+//! usable answer instead of a dead end. Only short answers count as refusals,
+//! so a long answer that declines one detail is kept.
+
+use crate::config::RetryConfig;
+use serde::Serialize;
+
+/// Answers longer than this are never taken for a refusal
+const MAX_REFUSAL_CHARS: usize = 300;
+
+/// Why an answer was retried, sent with the `llm-retry` event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryReason {
+    Empty,
+    Refusal,
+}
+
+/// Why `answer` should be retried, if it should
+pub fn check(config: &RetryConfig, answer: &str) -> Option<RetryReason> {
+    if !config.enabled {
+        return None;
+    }
+    let answer = answer.trim();
+    if answer.is_empty() {
+        return Some(RetryReason::Empty);
+    }
+    if answer.chars().count() > MAX_REFUSAL_CHARS {
+        return None;
+    }
+
+    // Models write both straight and curly apostrophes
+    let answer = answer.replace('\u{2019}', "'").to_lowercase();
+    config
+        .refusal_phrases
+        .iter()
+        .map(|phrase| phrase.replace('\u{2019}', "'").to_lowercase())
+        .any(|phrase| !phrase.is_empty() && answer.starts_with(&phrase))
+        .then_some(RetryReason::Refusal)
+}
+
+/// Add the retry instruction to the latest user message of `messages`
+pub fn rephrase(config: &RetryConfig, messages: &mut [(String, String)]) {
+    if let Some((_, content)) = messages.iter_mut().rev().find(|(role, _)| role == "user") {
+        content.push_str("\n\n");
+        content.push_str(&config.instruction);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled() -> RetryConfig {
+        RetryConfig {
+            enabled: true,
+            ..RetryConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_empty_and_refusal_answers_are_retried() {
+        let config = enabled();
+        assert_eq!(check(&config, "  \n"), Some(RetryReason::Empty));
+        assert_eq!(check(&config, "I\u{2019}m sorry, but I can\u{2019}t help with that."), Some(RetryReason::Refusal));
+        assert_eq!(check(&config, "The filter is behind the lower panel."), None);
+        assert_eq!(check(&config, &format!("I'm unable to {}", "see the unit, but ".repeat(30))), None);
+        assert_eq!(check(&RetryConfig::default(), ""), None);
+    }
+
+    #[test]
+    fn test_rephrase_extends_latest_user_message() {
+        let mut messages = vec![
+            ("user".to_string(), "Hi".to_string()),
+            ("assistant".to_string(), "Hello".to_string()),
+            ("user".to_string(), "How do I reset the unit?".to_string()),
+        ];
+        rephrase(&enabled(), &mut messages);
+        assert_eq!(messages[0].1, "Hi");
+        assert!(messages[2].1.starts_with("How do I reset the unit?\n\nAnswer this directly"));
+    }
+}
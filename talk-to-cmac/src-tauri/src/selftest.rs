@@ -0,0 +1,203 @@
+//! Pipeline self-test
+//!
+//! Runs every stage of the voice pipeline with fixed inputs: a built-in
+//! 2-second WAV clip for speech-to-text, a trivial prompt for the LLM, and a
+//! short sentence for text-to-speech. Unlike the connectivity check, this
+//! catches wrong models, voices, deployments, and keys, not just unreachable
+//! hosts. Stages are independent, so one failure does not hide the others.
+//! The same requests are used by the provider benchmark.
+
+use crate::api::{llm_provider, stt_provider, tts_provider, GenerationOverrides};
+use crate::config::{ApiKeys, AppConfig};
+use crate::error::{AppError, AppResult, WhisperError};
+use crate::telemetry::Stage;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+use std::future::Future;
+use std::time::Instant;
+
+/// Sample rate of the test clip (what Whisper expects)
+const SAMPLE_RATE: u32 = 16_000;
+
+/// Length of the test clip
+const CLIP_SECONDS: u32 = 2;
+
+/// Prompt sent to the LLM
+const TEST_PROMPT: &str = "This is a connection test. Reply with the single word: ready";
+
+/// Text synthesized by the text-to-speech stage
+const TEST_SPEECH: &str = "Self-test complete.";
+
+/// Result of one pipeline stage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageResult {
+    /// "stt", "llm", or "tts"
+    pub stage: String,
+
+    /// Provider that handled the stage, as named in the settings
+    pub provider: String,
+
+    /// Whether the stage worked
+    pub passed: bool,
+
+    /// Time taken (milliseconds)
+    pub duration_ms: u64,
+
+    /// What the stage returned, or why it failed
+    pub detail: String,
+}
+
+/// Result of a full self-test
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfTestReport {
+    /// Whether every stage passed
+    pub passed: bool,
+
+    /// Results in pipeline order
+    pub stages: Vec<StageResult>,
+
+    /// Time taken by the whole test (milliseconds)
+    pub total_ms: u64,
+}
+
+/// Run each pipeline stage once with the configured providers
+pub async fn run(config: &AppConfig, api_keys: &ApiKeys) -> SelfTestReport {
+    let started = Instant::now();
+
+    let stt = run_stage(Stage::Stt, config, check_stt(config, api_keys)).await;
+    let llm = run_stage(Stage::Llm, config, check_llm(config, api_keys)).await;
+    let tts = run_stage(Stage::Tts, config, check_tts(config, api_keys)).await;
+
+    let stages = vec![stt, llm, tts];
+    SelfTestReport {
+        passed: stages.iter().all(|stage| stage.passed),
+        stages,
+        total_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+/// Transcribe the test clip with the configured speech-to-text provider
+pub(crate) async fn check_stt(config: &AppConfig, api_keys: &ApiKeys) -> AppResult<String> {
+    match stt_provider(config, api_keys)?.transcribe_audio(test_clip(), "self-test.wav").await {
+        Ok(text) => Ok(format!("Transcribed: \"{}\"", text)),
+        // The clip is a tone, so hearing no words means the service processed it
+        Err(AppError::WhisperApi(WhisperError::EmptyResponse)) => Ok("Audio accepted (no speech in test tone)".to_string()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Send the test prompt to the configured LLM provider
+pub(crate) async fn check_llm(config: &AppConfig, api_keys: &ApiKeys) -> AppResult<String> {
+    let messages = vec![("user".to_string(), TEST_PROMPT.to_string())];
+    let reply = llm_provider(config, api_keys)?
+        .send_message_with(messages, &GenerationOverrides::default())
+        .await?;
+    if reply.answer.trim().is_empty() {
+        return Err(AppError::Generic("The model returned an empty reply".to_string()));
+    }
+    Ok(format!("Replied: \"{}\"", reply.answer.trim()))
+}
+
+/// Synthesize the test sentence with the configured text-to-speech provider
+pub(crate) async fn check_tts(config: &AppConfig, api_keys: &ApiKeys) -> AppResult<String> {
+    let audio = tts_provider(config, api_keys)?.synthesize_speech(TEST_SPEECH).await?;
+    if audio.is_empty() {
+        return Err(AppError::Generic("The service returned no audio".to_string()));
+    }
+    Ok(format!("Synthesized {} bytes of audio", audio.len()))
+}
+
+async fn run_stage(stage: Stage, config: &AppConfig, work: impl Future<Output = AppResult<String>>) -> StageResult {
+    let started = Instant::now();
+    let result = work.await;
+    let duration_ms = started.elapsed().as_millis() as u64;
+
+    match &result {
+        Ok(detail) => tracing::info!("Self-test {} passed in {} ms: {}", stage.name(), duration_ms, detail),
+        Err(e) => tracing::warn!("Self-test {} failed in {} ms: {}", stage.name(), duration_ms, e),
+    }
+
+    StageResult {
+        stage: stage.name().to_string(),
+        provider: stage.provider(config),
+        passed: result.is_ok(),
+        duration_ms,
+        detail: result.unwrap_or_else(|e| e.to_string()),
+    }
+}
+
+/// Built-in test clip: 2 seconds of a quiet 440 Hz tone as 16 kHz, 16-bit mono WAV
+fn test_clip() -> Vec<u8> {
+    let samples = SAMPLE_RATE * CLIP_SECONDS;
+    let data_len = samples * 2;
+
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes()); // format chunk size
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); // mono
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes()); // byte rate
+    wav.extend_from_slice(&2u16.to_le_bytes()); // block align
+    wav.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+
+    for i in 0..samples {
+        let t = i as f32 / SAMPLE_RATE as f32;
+        let sample = (2.0 * PI * 440.0 * t).sin() * 0.1 * f32::from(i16::MAX);
+        wav.extend_from_slice(&(sample as i16).to_le_bytes());
+    }
+    wav
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{LlmProviderKind, MockConfig, SttProviderKind, TtsProviderKind};
+
+    #[test]
+    fn test_clip_is_two_seconds_of_pcm() {
+        let clip = test_clip();
+        assert_eq!(&clip[..4], b"RIFF");
+        assert_eq!(&clip[8..16], b"WAVEfmt ");
+        assert_eq!(clip.len(), 44 + 2 * 16_000 * 2);
+        assert_eq!(u32::from_le_bytes(clip[4..8].try_into().unwrap()) as usize, clip.len() - 8);
+    }
+
+    #[tokio::test]
+    async fn test_mock_pipeline_passes_every_stage() {
+        let mut config = AppConfig::default();
+        config.providers.stt = SttProviderKind::Mock;
+        config.providers.llm = LlmProviderKind::Mock;
+        config.providers.tts = TtsProviderKind::Mock;
+        config.mock = MockConfig {
+            latency_ms: 0,
+            ..MockConfig::default()
+        };
+        let api_keys = ApiKeys::none();
+
+        let report = run(&config, &api_keys).await;
+        assert!(report.passed);
+        let stages: Vec<_> = report.stages.iter().map(|stage| (stage.stage.as_str(), stage.provider.as_str())).collect();
+        assert_eq!(stages, [("stt", "mock"), ("llm", "mock"), ("tts", "mock")]);
+    }
+
+    #[tokio::test]
+    async fn test_failures_are_reported_per_stage() {
+        // ElevenLabs stays selected but has no API key
+        let mut config = AppConfig::default();
+        config.providers.stt = SttProviderKind::Mock;
+        config.providers.llm = LlmProviderKind::Mock;
+        config.mock.latency_ms = 0;
+        let api_keys = ApiKeys::none();
+
+        let report = run(&config, &api_keys).await;
+        assert!(!report.passed);
+        assert!(report.stages[0].passed && report.stages[1].passed);
+        assert!(!report.stages[2].passed);
+        assert!(report.stages[2].detail.contains("authentication"));
+    }
+}
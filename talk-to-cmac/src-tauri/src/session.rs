@@ -0,0 +1,206 @@
+//! Session lock
+//!
+//! An assistant left running on an unattended desk can be locked, by the user or
+//! after `privacy.auto_lock_minutes` without activity. While the session is
+//! locked, conversation history and recordings are hidden, global hotkeys are
+//! released, and voice and text queries are refused until the session PIN is
+//! entered. The PIN is kept in the profile's keyring entry, only as a salted
+//! Argon2 hash.
+//!
+//! After [`MAX_ATTEMPTS`] wrong PINs in a row, the PIN is refused for
+//! [`LOCKOUT`] to slow down guessing.
+
+use crate::error::{AppError, AppResult, ConfigError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Wrong PINs accepted in a row before PIN entry is paused
+pub const MAX_ATTEMPTS: u32 = 5;
+
+/// How long PIN entry is paused after too many wrong PINs
+pub const LOCKOUT: Duration = Duration::from_secs(60);
+
+/// Whether the session is locked, and when it was last used
+#[derive(Clone)]
+pub struct SessionLock {
+    inner: Arc<Mutex<LockState>>,
+}
+
+struct LockState {
+    locked: bool,
+    last_activity: Instant,
+
+    /// Wrong PINs since the last successful unlock or lockout
+    failures: u32,
+
+    /// End of the pause after too many wrong PINs
+    retry_after: Option<Instant>,
+}
+
+impl Default for SessionLock {
+    fn default() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(LockState {
+                locked: false,
+                last_activity: Instant::now(),
+                failures: 0,
+                retry_after: None,
+            })),
+        }
+    }
+}
+
+impl SessionLock {
+    /// Whether the session is locked
+    pub fn is_locked(&self) -> bool {
+        self.inner.lock().unwrap().locked
+    }
+
+    /// Fail with [`AppError::SessionLocked`] if the session is locked
+    pub fn ensure_unlocked(&self) -> AppResult<()> {
+        if self.is_locked() {
+            return Err(AppError::SessionLocked);
+        }
+        Ok(())
+    }
+
+    /// Lock the session, returning whether it was unlocked before
+    pub fn lock(&self) -> bool {
+        let mut state = self.inner.lock().unwrap();
+        !std::mem::replace(&mut state.locked, true)
+    }
+
+    /// Record user activity, postponing the auto-lock
+    pub fn touch(&self) {
+        let mut state = self.inner.lock().unwrap();
+        if !state.locked {
+            state.last_activity = Instant::now();
+        }
+    }
+
+    /// Whether an unlocked session has been idle for `timeout`; never with a zero timeout
+    pub fn should_auto_lock(&self, timeout: Duration) -> bool {
+        self.should_auto_lock_at(timeout, Instant::now())
+    }
+
+    fn should_auto_lock_at(&self, timeout: Duration, now: Instant) -> bool {
+        let state = self.inner.lock().unwrap();
+        !timeout.is_zero() && !state.locked && now.saturating_duration_since(state.last_activity) >= timeout
+    }
+
+    /// How long since the last activity
+    pub fn idle_for(&self) -> Duration {
+        self.inner.lock().unwrap().last_activity.elapsed()
+    }
+
+    /// Unlock the session if `verify` accepts the entered PIN
+    ///
+    /// Returns whether the session was locked. A wrong PIN fails as in
+    /// [`SessionLock::check_pin`].
+    pub fn unlock(&self, verify: impl FnOnce() -> AppResult<bool>) -> AppResult<bool> {
+        let mut state = self.inner.lock().unwrap();
+        if !state.locked {
+            return Ok(false);
+        }
+
+        state.check_pin(verify)?;
+        state.locked = false;
+        state.last_activity = Instant::now();
+        Ok(true)
+    }
+
+    /// Check a PIN entered for anything the session PIN guards, such as leaving kiosk mode
+    ///
+    /// A wrong PIN fails with [`ConfigError::IncorrectPin`]; attempts during the
+    /// pause after [`MAX_ATTEMPTS`] wrong PINs fail without calling `verify`.
+    pub fn check_pin(&self, verify: impl FnOnce() -> AppResult<bool>) -> AppResult<()> {
+        self.inner.lock().unwrap().check_pin(verify)
+    }
+}
+
+impl LockState {
+    fn check_pin(&mut self, verify: impl FnOnce() -> AppResult<bool>) -> AppResult<()> {
+        let now = Instant::now();
+        if let Some(retry_after) = self.retry_after.filter(|retry_after| *retry_after > now) {
+            let remaining = retry_after.duration_since(now).as_secs().max(1);
+            return Err(ConfigError::PinAttemptsExceeded(remaining).into());
+        }
+
+        if !verify()? {
+            self.failures += 1;
+            if self.failures >= MAX_ATTEMPTS {
+                tracing::warn!("{} wrong session PINs, pausing PIN entry for {}s", self.failures, LOCKOUT.as_secs());
+                self.failures = 0;
+                self.retry_after = Some(now + LOCKOUT);
+            }
+            return Err(ConfigError::IncorrectPin.into());
+        }
+
+        self.failures = 0;
+        self.retry_after = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_and_unlock() {
+        let session = SessionLock::default();
+        assert!(session.ensure_unlocked().is_ok());
+        assert!(!session.unlock(|| Ok(false)).unwrap());
+
+        assert!(session.lock());
+        assert!(!session.lock());
+        assert!(matches!(session.ensure_unlocked(), Err(AppError::SessionLocked)));
+
+        assert!(matches!(session.unlock(|| Ok(false)), Err(AppError::Config(ConfigError::IncorrectPin))));
+        assert!(session.is_locked());
+        assert!(session.unlock(|| Ok(true)).unwrap());
+        assert!(!session.is_locked());
+    }
+
+    #[test]
+    fn test_too_many_wrong_pins_pause_unlocking() {
+        let session = SessionLock::default();
+        session.lock();
+        for _ in 0..MAX_ATTEMPTS {
+            assert!(session.unlock(|| Ok(false)).is_err());
+        }
+
+        let result = session.unlock(|| panic!("the PIN is not checked during the pause"));
+        assert!(matches!(result, Err(AppError::Config(ConfigError::PinAttemptsExceeded(_)))));
+        assert!(session.is_locked());
+    }
+
+    #[test]
+    fn test_pin_checks_share_the_pause() {
+        let session = SessionLock::default();
+        assert!(session.check_pin(|| Ok(true)).is_ok());
+        for _ in 0..MAX_ATTEMPTS {
+            assert!(session.check_pin(|| Ok(false)).is_err());
+        }
+        assert!(!session.is_locked());
+
+        session.lock();
+        assert!(matches!(
+            session.unlock(|| Ok(true)),
+            Err(AppError::Config(ConfigError::PinAttemptsExceeded(_)))
+        ));
+    }
+
+    #[test]
+    fn test_auto_lock_after_inactivity() {
+        let session = SessionLock::default();
+        let later = Instant::now() + Duration::from_secs(600);
+
+        assert!(session.should_auto_lock_at(Duration::from_secs(300), later));
+        assert!(!session.should_auto_lock_at(Duration::from_secs(900), later));
+        assert!(!session.should_auto_lock_at(Duration::ZERO, later));
+
+        session.lock();
+        assert!(!session.should_auto_lock_at(Duration::from_secs(300), later));
+    }
+}
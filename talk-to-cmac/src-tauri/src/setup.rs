@@ -0,0 +1,492 @@
+//! First-run setup
+//!
+//! Support for the setup wizard the frontend shows while `setup.completed` is
+//! off: finding LLM servers already running on this machine, suggesting
+//! settings from what was found and which keys are set, and testing each API
+//! key as it is entered, before it is stored.
+//!
+//! Servers are recognized by what they answer on the ports they listen on by
+//! default: OpenWebUI describes itself at `/api/config`, Ollama reports its
+//! version at `/api/version`, LM Studio lists its models at `/api/v0/models`,
+//! and other OpenAI-compatible servers (llama.cpp, vLLM) list theirs at
+//! `/v1/models`. The configured LLM endpoint is probed the same way, so
+//! requests can be shaped for the kind of server behind it (see
+//! [`ApiFlavor`]).
+//!
+//! Servers elsewhere on the local network are found by scanning the machine's
+//! /24 subnet for the OpenWebUI and Ollama ports. Neither server announces
+//! itself over mDNS, so a port scan is the only way to find them. Only private
+//! (RFC 1918) networks are scanned.
+
+use crate::api::azure::AzureOpenAiClient;
+use crate::api::endpoint;
+use crate::api::gemini::GeminiClient;
+use crate::api::http;
+use crate::api::openrouter::OpenRouterClient;
+use crate::api::openwebui::OpenWebUiClient;
+use crate::api::provider::LlmProvider;
+use crate::config::{ApiFlavor, ApiKeys, AppConfig, LlmProviderKind, OpenWebUiConfig, PipelineMode, SttProviderKind, TtsProviderKind};
+use crate::error::{AppResult, ConfigError, ElevenLabsError, NetworkError, WhisperError};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Ports LLM servers listen on by default: OpenWebUI (3000, or 8080 in its own
+/// container), Ollama, LM Studio, and llama.cpp or vLLM
+pub const COMMON_PORTS: [u16; 5] = [3000, 8080, 11434, 1234, 8000];
+
+/// How long a port may take to answer before it is taken as closed
+const PROBE_TIMEOUT: Duration = Duration::from_millis(1500);
+
+/// Ports scanned on other hosts: OpenWebUI and Ollama
+pub const NETWORK_PORTS: [u16; 3] = [3000, 8080, 11434];
+
+/// How long a host may take to accept a connection during a network scan
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// Connections attempted at once during a network scan
+const SCAN_CONCURRENCY: usize = 64;
+
+/// How long a key test may take
+const KEY_TEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An LLM server that answered on this machine or the local network, or at the configured endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LocalServer {
+    pub kind: ApiFlavor,
+
+    /// Scheme, host, and port (e.g. "http://localhost:11434" or "http://192.168.1.20:3000")
+    pub base_url: String,
+
+    /// Chat completions endpoint to use as `openwebui.endpoint`
+    pub chat_endpoint: String,
+
+    /// Version the server reported, if any
+    pub version: Option<String>,
+
+    /// Models the server offers; empty for OpenWebUI, which only lists them with a key
+    pub models: Vec<String>,
+}
+
+/// Settings suggested for a first run
+#[derive(Debug, Clone, Serialize)]
+pub struct SetupSuggestion {
+    /// The current settings with the suggestions applied
+    pub config: AppConfig,
+
+    /// Settings that differ from the current ones (e.g. "openwebui.endpoint")
+    pub changed: Vec<String>,
+}
+
+/// LLM servers running on this machine, in the order of [`COMMON_PORTS`]
+pub async fn detect_local_servers() -> Vec<LocalServer> {
+    let mut probes = JoinSet::new();
+    for port in COMMON_PORTS {
+        probes.spawn(async move { (port, identify(&format!("http://localhost:{}", port), None).await) });
+    }
+
+    let mut found = Vec::new();
+    while let Some(probe) = probes.join_next().await {
+        if let Ok((port, Some(server))) = probe {
+            found.push((port, server));
+        }
+    }
+    found.sort_by_key(|(port, _)| COMMON_PORTS.iter().position(|common| common == port));
+
+    tracing::info!("Found {} local LLM server(s)", found.len());
+    found.into_iter().map(|(_, server)| server).collect()
+}
+
+/// LLM servers on the local network, ordered by address and port
+///
+/// Fails if this machine has no network address; finds nothing when it is not
+/// on a private network.
+pub async fn discover_network_servers() -> AppResult<Vec<LocalServer>> {
+    let local = local_address().ok_or(NetworkError::NoConnection)?;
+    let Some(hosts) = scan_range(local) else {
+        tracing::info!("Not scanning {}: not a private network", local);
+        return Ok(Vec::new());
+    };
+    tracing::info!("Scanning {} hosts near {} for LLM servers", hosts.len(), local);
+
+    let permits = Arc::new(Semaphore::new(SCAN_CONCURRENCY));
+    let mut probes = JoinSet::new();
+    for host in hosts {
+        for port in NETWORK_PORTS {
+            let permits = permits.clone();
+            probes.spawn(async move {
+                let open = {
+                    let _permit = permits.acquire().await.ok()?;
+                    tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect((host, port))).await
+                };
+                match open {
+                    Ok(Ok(_)) => identify(&format!("http://{}:{}", host, port), None).await.map(|server| (host, port, server)),
+                    _ => None,
+                }
+            });
+        }
+    }
+
+    let mut found = Vec::new();
+    while let Some(probe) = probes.join_next().await {
+        if let Ok(Some(server)) = probe {
+            found.push(server);
+        }
+    }
+    found.sort_by_key(|(host, port, _)| (*host, *port));
+
+    tracing::info!("Found {} LLM server(s) on the network", found.len());
+    Ok(found.into_iter().map(|(_, _, server)| server).collect())
+}
+
+/// Address of this machine on the network its default route goes through
+///
+/// Connecting a UDP socket sends nothing; it only picks the outgoing interface.
+fn local_address() -> Option<Ipv4Addr> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).ok()?;
+    socket.connect((Ipv4Addr::new(192, 0, 2, 1), 9)).ok()?;
+    match socket.local_addr().ok()?.ip() {
+        IpAddr::V4(ip) if !ip.is_unspecified() && !ip.is_loopback() => Some(ip),
+        _ => None,
+    }
+}
+
+/// Hosts of the /24 subnet around `local`, or `None` if it is not a private address
+fn scan_range(local: Ipv4Addr) -> Option<Vec<Ipv4Addr>> {
+    if !local.is_private() {
+        return None;
+    }
+    let [a, b, c, _] = local.octets();
+    Some((1..=254).map(|d| Ipv4Addr::new(a, b, c, d)).collect())
+}
+
+/// The LLM server answering at `base_url`, if there is one
+///
+/// `api_key` is sent with each probe, for servers that only answer with one.
+pub async fn identify(base_url: &str, api_key: Option<&str>) -> Option<LocalServer> {
+    let base_url = base_url.trim_end_matches('/');
+    let found = |kind: ApiFlavor, version: Option<&str>, models: Vec<String>| LocalServer {
+        kind,
+        base_url: base_url.to_string(),
+        chat_endpoint: format!("{}{}", base_url, kind.chat_path()),
+        version: version.map(str::to_string),
+        models,
+    };
+
+    if base_url.contains("://api.openai.com") {
+        let models = get_json(&format!("{}/v1/models", base_url), api_key).await;
+        return Some(found(ApiFlavor::OpenAi, None, names(models.as_ref(), "data", "id")));
+    }
+
+    if let Some(config) = get_json(&format!("{}/api/config", base_url), api_key).await {
+        if config["name"].as_str().is_some_and(|name| name.contains("WebUI")) {
+            return Some(found(ApiFlavor::OpenWebUi, config["version"].as_str(), Vec::new()));
+        }
+    }
+
+    if let Some(version) = get_json(&format!("{}/api/version", base_url), api_key).await {
+        if let Some(version) = version["version"].as_str() {
+            let tags = get_json(&format!("{}/api/tags", base_url), api_key).await;
+            return Some(found(ApiFlavor::Ollama, Some(version), names(tags.as_ref(), "models", "name")));
+        }
+    }
+
+    // LM Studio's own REST API sits next to its OpenAI-compatible one
+    if let Some(models) = get_json(&format!("{}/api/v0/models", base_url), api_key).await {
+        if models["data"].is_array() {
+            return Some(found(ApiFlavor::LmStudio, None, names(Some(&models), "data", "id")));
+        }
+    }
+
+    let models = get_json(&format!("{}/v1/models", base_url), api_key).await?;
+    models["data"]
+        .is_array()
+        .then(|| found(ApiFlavor::OpenAiCompatible, None, names(Some(&models), "data", "id")))
+}
+
+/// Kind of server at the configured LLM endpoint, if it answers
+pub async fn probe_llm(config: &OpenWebUiConfig, api_key: Option<&str>) -> Option<LocalServer> {
+    let server = identify(&server_base(&config.endpoint), api_key).await;
+    match &server {
+        Some(server) => tracing::info!("{} is {:?} {}", server.base_url, server.kind, server.version.as_deref().unwrap_or("")),
+        None => tracing::info!("Could not tell what kind of server {} is", config.endpoint),
+    }
+    server
+}
+
+/// Record the probed `server` in `config`, moving the endpoint to the server's
+/// chat path unless it already points at one the server answers
+///
+/// Returns the settings that changed.
+pub fn apply_probe(config: &mut OpenWebUiConfig, server: &LocalServer) -> Vec<String> {
+    let mut changed = Vec::new();
+    if config.api_flavor != Some(server.kind) {
+        config.api_flavor = Some(server.kind);
+        changed.push("openwebui.api_flavor".to_string());
+    }
+
+    let endpoint = endpoint::openwebui_chat(&config.endpoint);
+    let endpoint = endpoint.split('?').next().unwrap_or_default();
+    let usable = endpoint.ends_with(server.kind.chat_path())
+        || (server.kind == ApiFlavor::OpenWebUi && endpoint.ends_with("/api/chat"));
+    if !usable {
+        config.endpoint = server.chat_endpoint.clone();
+        changed.push("openwebui.endpoint".to_string());
+    }
+    changed
+}
+
+/// Scheme, host, port, and any path prefix of the chat endpoint `configured`, up to its API path
+pub fn server_base(configured: &str) -> String {
+    let endpoint = endpoint::openwebui_chat(configured);
+    let url = endpoint.split('?').next().unwrap_or_default();
+    let host = url.find("://").map_or(0, |index| index + 3);
+    let path = ["/api/", "/v1/"]
+        .iter()
+        .filter_map(|prefix| url[host..].find(prefix))
+        .min();
+    match path {
+        Some(index) => url[..host + index].to_string(),
+        None => url.trim_end_matches("/chat/completions").to_string(),
+    }
+}
+
+/// Settings for a first run: `config` pointed at a server found on this machine
+/// and at the providers the entered keys are for
+///
+/// The first OpenWebUI server found is preferred, then Ollama, then LM Studio,
+/// then any other.
+/// Without a local server or an OpenWebUI key, a hosted LLM provider with a key
+/// is chosen instead. The pipeline mode drops speech input or output when no
+/// provider for it can be used yet.
+pub fn suggest(config: &AppConfig, servers: &[LocalServer], keys: &ApiKeys) -> SetupSuggestion {
+    let mut suggested = config.clone();
+    let azure_ready = keys.azure.is_some() && !config.azure.endpoint.is_empty();
+
+    let server = [ApiFlavor::OpenWebUi, ApiFlavor::Ollama, ApiFlavor::LmStudio, ApiFlavor::OpenAiCompatible]
+        .iter()
+        .find_map(|kind| servers.iter().find(|server| server.kind == *kind));
+    match server {
+        Some(server) => {
+            suggested.providers.llm = LlmProviderKind::OpenWebUi;
+            suggested.openwebui.endpoint = server.chat_endpoint.clone();
+            suggested.openwebui.api_flavor = Some(server.kind);
+            if !server.models.is_empty() && !server.models.contains(&config.openwebui.model) {
+                suggested.openwebui.model = server.models[0].clone();
+            }
+        }
+        None if config.providers.llm == LlmProviderKind::OpenWebUi && keys.openwebui.is_none() => {
+            if keys.openrouter.is_some() {
+                suggested.providers.llm = LlmProviderKind::OpenRouter;
+            } else if keys.gemini.is_some() {
+                suggested.providers.llm = LlmProviderKind::Gemini;
+            } else if azure_ready {
+                suggested.providers.llm = LlmProviderKind::AzureOpenAi;
+            }
+        }
+        None => {}
+    }
+
+    if config.providers.stt == SttProviderKind::Whisper && keys.whisper.is_none() && azure_ready {
+        suggested.providers.stt = SttProviderKind::AzureOpenAi;
+    }
+    if config.providers.tts == TtsProviderKind::ElevenLabs && keys.elevenlabs.is_none() && azure_ready {
+        suggested.providers.tts = TtsProviderKind::AzureOpenAi;
+    }
+
+    // Whisper-compatible servers other than OpenAI's may not need a key
+    let speech_in = match suggested.providers.stt {
+        SttProviderKind::Whisper => keys.whisper.is_some() || !config.whisper.endpoint.contains("api.openai.com"),
+        SttProviderKind::AzureOpenAi => azure_ready,
+        SttProviderKind::Mock => true,
+    };
+    let speech_out = match suggested.providers.tts {
+        TtsProviderKind::ElevenLabs => keys.elevenlabs.is_some(),
+        TtsProviderKind::AzureOpenAi => azure_ready,
+        TtsProviderKind::Mock => true,
+    };
+    suggested.audio.pipeline_mode = match (speech_in, speech_out) {
+        (true, true) => PipelineMode::Voice,
+        (true, false) => PipelineMode::VoiceInTextOut,
+        (false, true) => PipelineMode::TextInVoiceOut,
+        (false, false) => PipelineMode::Text,
+    };
+
+    let settings = [
+        ("providers.stt", suggested.providers.stt != config.providers.stt),
+        ("providers.llm", suggested.providers.llm != config.providers.llm),
+        ("providers.tts", suggested.providers.tts != config.providers.tts),
+        ("openwebui.endpoint", suggested.openwebui.endpoint != config.openwebui.endpoint),
+        ("openwebui.model", suggested.openwebui.model != config.openwebui.model),
+        ("openwebui.api_flavor", suggested.openwebui.api_flavor != config.openwebui.api_flavor),
+        ("audio.pipeline_mode", suggested.audio.pipeline_mode != config.audio.pipeline_mode),
+    ];
+    let changed = settings
+        .iter()
+        .filter(|(_, differs)| *differs)
+        .map(|(name, _)| name.to_string())
+        .collect();
+
+    SetupSuggestion { config: suggested, changed }
+}
+
+/// Check `api_key` for `service` (named as in `update_api_key`) against the configured endpoint
+///
+/// Returns whether the service answered; a key it rejects fails with the
+/// service's authentication error.
+pub async fn test_key(service: &str, api_key: &str, config: &AppConfig) -> AppResult<bool> {
+    match service {
+        "whisper" => {
+            let transcriptions = endpoint::whisper(&config.whisper.endpoint);
+            let base = transcriptions.split("/audio/").next().unwrap_or_default();
+            let request = client()?.get(format!("{}/models", base)).bearer_auth(api_key);
+            match http::send("whisper", request).await {
+                Ok(resp) if matches!(resp.status().as_u16(), 401 | 403) => Err(WhisperError::AuthenticationFailed.into()),
+                Ok(resp) => Ok(resp.status().as_u16() < 500),
+                Err(_) => Ok(false),
+            }
+        }
+        "elevenlabs" => {
+            let base = endpoint::elevenlabs(&config.elevenlabs.endpoint).replace("/text-to-speech", "");
+            let request = client()?.get(format!("{}/user", base)).header("xi-api-key", api_key);
+            match http::send("elevenlabs", request).await {
+                Ok(resp) if matches!(resp.status().as_u16(), 401 | 403) => Err(ElevenLabsError::AuthenticationFailed.into()),
+                Ok(resp) => Ok(resp.status().as_u16() < 500),
+                Err(_) => Ok(false),
+            }
+        }
+        "openwebui" => OpenWebUiClient::new(config.openwebui.clone(), Some(api_key.to_string()))?
+            .list_models()
+            .await
+            .map(|_| true),
+        "azure" => AzureOpenAiClient::new(config, Some(api_key.to_string()))?.check_connectivity().await,
+        "openrouter" => OpenRouterClient::new(config, Some(api_key.to_string()))?.check_connectivity().await,
+        "gemini" => GeminiClient::new(config, Some(api_key.to_string()))?.check_connectivity().await,
+        _ => Err(ConfigError::InvalidValue(format!("Unknown service: {}", service)).into()),
+    }
+}
+
+fn client() -> AppResult<reqwest::Client> {
+    http::client(KEY_TEST_TIMEOUT).map_err(|e| ConfigError::LoadFailed(e.to_string()).into())
+}
+
+/// JSON answer to a GET on a server, if it answers in time with success
+async fn get_json(url: &str, api_key: Option<&str>) -> Option<serde_json::Value> {
+    let client = http::client(PROBE_TIMEOUT).ok()?;
+    let mut request = client.get(url);
+    if let Some(key) = api_key {
+        request = request.bearer_auth(key);
+    }
+    let response = http::send("setup", request).await.ok()?;
+    if !response.status().is_success() {
+        return None;
+    }
+    response.json().await.ok()
+}
+
+/// The `field` of each object in the `list` array of `value`
+fn names(value: Option<&serde_json::Value>, list: &str, field: &str) -> Vec<String> {
+    value
+        .and_then(|value| value[list].as_array())
+        .map(|items| items.iter().filter_map(|item| item[field].as_str().map(str::to_string)).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn server(kind: ApiFlavor, models: &[&str]) -> LocalServer {
+        LocalServer {
+            kind,
+            base_url: "http://localhost:11434".to_string(),
+            chat_endpoint: "http://localhost:11434/v1/chat/completions".to_string(),
+            version: None,
+            models: models.iter().map(|model| model.to_string()).collect(),
+        }
+    }
+
+    /// Keys with only `service` set
+    fn keys(service: &str) -> ApiKeys {
+        let mut keys = ApiKeys::none();
+        *keys.service_mut(service).unwrap() = Some("sk-test".to_string());
+        keys
+    }
+
+    #[tokio::test]
+    async fn test_identifies_ollama() {
+        let ollama = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/version"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "version": "0.5.7" })))
+            .mount(&ollama)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "models": [{ "name": "llama3.2:latest" }] })))
+            .mount(&ollama)
+            .await;
+
+        let found = identify(&ollama.uri(), None).await.unwrap();
+        assert_eq!(found.kind, ApiFlavor::Ollama);
+        assert_eq!(found.version.as_deref(), Some("0.5.7"));
+        assert_eq!(found.models, ["llama3.2:latest"]);
+        assert_eq!(found.chat_endpoint, format!("{}/v1/chat/completions", ollama.uri()));
+    }
+
+    #[test]
+    fn test_only_private_subnets_are_scanned() {
+        let hosts = scan_range(Ipv4Addr::new(192, 168, 1, 37)).unwrap();
+        assert_eq!(hosts.len(), 254);
+        assert_eq!((hosts[0], hosts[253]), (Ipv4Addr::new(192, 168, 1, 1), Ipv4Addr::new(192, 168, 1, 254)));
+        assert!(scan_range(Ipv4Addr::new(10, 20, 30, 40)).is_some());
+        assert_eq!(scan_range(Ipv4Addr::new(203, 0, 113, 5)), None);
+    }
+
+    #[test]
+    fn test_suggestion_uses_local_server_and_available_speech() {
+        let suggestion = suggest(&AppConfig::default(), &[server(ApiFlavor::Ollama, &["qwen2.5"])], &keys("whisper"));
+
+        assert_eq!(suggestion.config.openwebui.endpoint, "http://localhost:11434/v1/chat/completions");
+        assert_eq!(suggestion.config.openwebui.model, "qwen2.5");
+        assert_eq!(suggestion.config.audio.pipeline_mode, PipelineMode::VoiceInTextOut);
+        assert_eq!(suggestion.config.openwebui.api_flavor, Some(ApiFlavor::Ollama));
+        assert_eq!(
+            suggestion.changed,
+            ["openwebui.endpoint", "openwebui.model", "openwebui.api_flavor", "audio.pipeline_mode"]
+        );
+
+        // Without a local server, a hosted provider with a key is used
+        let suggestion = suggest(&AppConfig::default(), &[], &keys("openrouter"));
+        assert_eq!(suggestion.config.providers.llm, LlmProviderKind::OpenRouter);
+        assert!(suggestion.changed.contains(&"providers.llm".to_string()));
+    }
+
+    #[test]
+    fn test_probe_keeps_working_endpoints() {
+        assert_eq!(server_base("http://localhost:3000/api/chat/completions"), "http://localhost:3000");
+        assert_eq!(server_base("https://example.com/llm/v1/chat/completions?x=1"), "https://example.com/llm");
+        assert_eq!(server_base("http://localhost:11434/"), "http://localhost:11434");
+
+        let mut config = OpenWebUiConfig {
+            endpoint: "http://localhost:3000/api/chat".to_string(),
+            ..OpenWebUiConfig::default()
+        };
+        let mut openwebui = server(ApiFlavor::OpenWebUi, &[]);
+        openwebui.chat_endpoint = "http://localhost:3000/api/chat/completions".to_string();
+        assert_eq!(apply_probe(&mut config, &openwebui), ["openwebui.api_flavor"]);
+        assert_eq!(config.endpoint, "http://localhost:3000/api/chat");
+
+        // Ollama has no /api/chat/completions, so the endpoint moves to its OpenAI-compatible one
+        config.endpoint = "http://localhost:11434".to_string();
+        assert_eq!(apply_probe(&mut config, &server(ApiFlavor::Ollama, &[])), ["openwebui.api_flavor", "openwebui.endpoint"]);
+        assert_eq!(config.endpoint, "http://localhost:11434/v1/chat/completions");
+        assert!(apply_probe(&mut config, &server(ApiFlavor::Ollama, &[])).is_empty());
+    }
+}
@@ -0,0 +1,405 @@
+//! Speaker identification
+//!
+//! A voiceprint summarizes the timbre of a voice: the mean and spread of the
+//! mel-frequency cepstral coefficients (MFCCs) over the voiced frames of a
+//! recording. That is enough to tell a handful of enrolled users apart in a
+//! quiet room, but not to authenticate anyone, so a match never opens a
+//! profile protected by a PIN.
+//!
+//! Each profile enrolls with a few recorded samples, whose voiceprints are
+//! averaged and stored in `voiceprints.json` in the app data directory.
+//! Recordings must be 16-bit PCM WAV, as the frontend sends them.
+
+use crate::error::{AppResult, AudioError, ConfigError};
+use crate::wav;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::f32::consts::PI;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Samples needed to enroll a voice
+pub const MIN_ENROLLMENT_SAMPLES: usize = 3;
+
+/// Cepstral coefficients kept per frame (the first, overall loudness, is dropped)
+const COEFFICIENTS: usize = 12;
+
+/// Mel filters the spectrum is reduced to
+const MEL_FILTERS: usize = 26;
+
+/// Voiced frames needed for a voiceprint (half a second)
+const MIN_VOICED_FRAMES: usize = 50;
+
+/// Frames quieter than this fraction of the loudest frame count as silence
+const SILENCE_RATIO: f32 = 0.01;
+
+/// Best enrolled match for an utterance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeakerMatch {
+    pub profile_id: String,
+
+    /// Cosine similarity of the voiceprints (1.0 is identical)
+    pub similarity: f32,
+
+    /// Whether the app switched to the profile; false if it was already active or has a PIN
+    #[serde(default)]
+    pub switched: bool,
+}
+
+/// Result of enrolling a profile's voice
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeakerEnrollment {
+    pub profile_id: String,
+    pub samples: usize,
+
+    /// Lowest similarity of a sample to the enrolled voiceprint; low values suggest a noisy sample
+    pub consistency: f32,
+}
+
+/// Stored voiceprint of one profile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Voiceprint {
+    samples: usize,
+    embedding: Vec<f32>,
+}
+
+/// Voiceprints of all profiles in `voiceprints.json`
+pub struct VoiceprintStore {
+    path: PathBuf,
+}
+
+impl VoiceprintStore {
+    /// Voiceprints of the app data directory `app_dir`
+    pub fn new(app_dir: &Path) -> Self {
+        Self {
+            path: app_dir.join("voiceprints.json"),
+        }
+    }
+
+    /// Enroll `profile_id` with the voiceprints of its samples, replacing any earlier enrollment
+    pub fn enroll(&self, profile_id: &str, samples: &[Vec<f32>]) -> AppResult<SpeakerEnrollment> {
+        if samples.len() < MIN_ENROLLMENT_SAMPLES {
+            return Err(ConfigError::InvalidValue(format!(
+                "Enrolling a voice needs at least {} samples",
+                MIN_ENROLLMENT_SAMPLES
+            ))
+            .into());
+        }
+
+        let mut embedding = vec![0.0; samples[0].len()];
+        for sample in samples {
+            for (sum, value) in embedding.iter_mut().zip(sample) {
+                *sum += value / samples.len() as f32;
+            }
+        }
+        let consistency = samples
+            .iter()
+            .map(|sample| similarity(sample, &embedding))
+            .fold(1.0, f32::min);
+
+        let mut voiceprints = self.load()?;
+        voiceprints.insert(
+            profile_id.to_string(),
+            Voiceprint {
+                samples: samples.len(),
+                embedding,
+            },
+        );
+        self.save(&voiceprints)?;
+
+        tracing::info!("Enrolled the voice of profile '{}' ({} samples)", profile_id, samples.len());
+        Ok(SpeakerEnrollment {
+            profile_id: profile_id.to_string(),
+            samples: samples.len(),
+            consistency,
+        })
+    }
+
+    /// Remove the voiceprint of `profile_id`, returning whether it was enrolled
+    pub fn remove(&self, profile_id: &str) -> AppResult<bool> {
+        let mut voiceprints = self.load()?;
+        let removed = voiceprints.remove(profile_id).is_some();
+        if removed {
+            self.save(&voiceprints)?;
+        }
+        Ok(removed)
+    }
+
+    /// The enrolled profile whose voiceprint is most similar to `embedding`, if it reaches `min_similarity`
+    pub fn identify(&self, embedding: &[f32], min_similarity: f32) -> AppResult<Option<SpeakerMatch>> {
+        Ok(self
+            .load()?
+            .into_iter()
+            .map(|(profile_id, voiceprint)| SpeakerMatch {
+                similarity: similarity(embedding, &voiceprint.embedding),
+                profile_id,
+                switched: false,
+            })
+            .filter(|candidate| candidate.similarity >= min_similarity)
+            .max_by(|a, b| a.similarity.total_cmp(&b.similarity)))
+    }
+
+    fn load(&self) -> AppResult<BTreeMap<String, Voiceprint>> {
+        if !self.path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let contents = fs::read_to_string(&self.path).map_err(|e| ConfigError::LoadFailed(e.to_string()))?;
+        Ok(serde_json::from_str(&contents).map_err(|e| ConfigError::ParseError(e.to_string()))?)
+    }
+
+    fn save(&self, voiceprints: &BTreeMap<String, Voiceprint>) -> AppResult<()> {
+        let contents = serde_json::to_string(voiceprints).map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
+        fs::write(&self.path, contents).map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// Voiceprint of a WAV recording
+pub fn embed(wav: &[u8]) -> AppResult<Vec<f32>> {
+    let (samples, sample_rate) = wav::decode(wav)?;
+    let frames = mfcc_frames(&samples, sample_rate);
+    if frames.len() < MIN_VOICED_FRAMES {
+        return Err(AudioError::InvalidFormat("Too little speech to recognize the voice".to_string()).into());
+    }
+
+    // Mean and standard deviation of each coefficient, weighted up with its index
+    // so the small higher coefficients, which carry much of the timbre, count too
+    let count = frames.len() as f32;
+    let mut embedding = Vec::with_capacity(2 * COEFFICIENTS);
+    let means: Vec<f32> = (0..COEFFICIENTS)
+        .map(|k| frames.iter().map(|frame| frame[k]).sum::<f32>() / count)
+        .collect();
+    for (k, mean) in means.iter().enumerate() {
+        embedding.push(mean * (k + 1) as f32);
+    }
+    for (k, mean) in means.iter().enumerate() {
+        let variance = frames.iter().map(|frame| (frame[k] - mean).powi(2)).sum::<f32>() / count;
+        embedding.push(variance.sqrt() * (k + 1) as f32);
+    }
+    Ok(embedding)
+}
+
+/// Cosine similarity of two voiceprints or other embeddings (1.0 is identical)
+pub fn similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norms = a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|y| y * y).sum::<f32>().sqrt();
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+/// Cepstral coefficients 1..=12 of each voiced 25 ms frame, with 10 ms hops
+fn mfcc_frames(samples: &[f32], sample_rate: u32) -> Vec<[f32; COEFFICIENTS]> {
+    let frame_len = (sample_rate as usize * 25 / 1000).max(1);
+    let hop = (sample_rate as usize / 100).max(1);
+    let fft_len = frame_len.next_power_of_two();
+    let filters = mel_filters(fft_len, sample_rate);
+    let window: Vec<f32> = (0..frame_len)
+        .map(|n| 0.54 - 0.46 * (2.0 * PI * n as f32 / (frame_len - 1).max(1) as f32).cos())
+        .collect();
+
+    // Pre-emphasis lifts the high frequencies that speech attenuates
+    let emphasized: Vec<f32> = samples
+        .iter()
+        .enumerate()
+        .map(|(i, sample)| sample - 0.97 * if i > 0 { samples[i - 1] } else { 0.0 })
+        .collect();
+
+    let mut frames = Vec::new();
+    let mut energies = Vec::new();
+    let mut start = 0;
+    while start + frame_len <= emphasized.len() {
+        let frame = &emphasized[start..start + frame_len];
+        energies.push(frame.iter().map(|x| x * x).sum::<f32>());
+
+        let spectrum = power_spectrum(frame, &window, fft_len);
+        let log_mel: Vec<f32> = filters
+            .iter()
+            .map(|filter| filter.iter().map(|(bin, weight)| spectrum[*bin] * weight).sum::<f32>().max(1e-10).ln())
+            .collect();
+
+        let mut coefficients = [0.0; COEFFICIENTS];
+        for (k, coefficient) in coefficients.iter_mut().enumerate() {
+            *coefficient = log_mel
+                .iter()
+                .enumerate()
+                .map(|(m, value)| value * (PI * (k + 1) as f32 * (m as f32 + 0.5) / MEL_FILTERS as f32).cos())
+                .sum();
+        }
+        frames.push(coefficients);
+        start += hop;
+    }
+
+    let loudest = energies.iter().copied().fold(0.0, f32::max);
+    frames
+        .into_iter()
+        .zip(energies)
+        .filter(|(_, energy)| loudest > 0.0 && *energy >= loudest * SILENCE_RATIO)
+        .map(|(frame, _)| frame)
+        .collect()
+}
+
+/// Triangular filters spaced evenly on the mel scale from 80 Hz to 7.6 kHz, as (bin, weight) pairs
+fn mel_filters(fft_len: usize, sample_rate: u32) -> Vec<Vec<(usize, f32)>> {
+    let to_mel = |hz: f32| 2595.0 * (1.0 + hz / 700.0).log10();
+    let to_hz = |mel: f32| 700.0 * (10f32.powf(mel / 2595.0) - 1.0);
+    let nyquist = sample_rate as f32 / 2.0;
+    let (low, high) = (to_mel(80.0), to_mel(7600.0f32.min(nyquist)));
+
+    let edges: Vec<f32> = (0..MEL_FILTERS + 2)
+        .map(|i| to_hz(low + (high - low) * i as f32 / (MEL_FILTERS + 1) as f32) * fft_len as f32 / sample_rate as f32)
+        .collect();
+
+    edges
+        .windows(3)
+        .map(|edge| {
+            let (left, center, right) = (edge[0], edge[1], edge[2]);
+            (left.ceil() as usize..=right.floor() as usize)
+                .filter(|bin| *bin <= fft_len / 2)
+                .map(|bin| {
+                    let position = bin as f32;
+                    let weight = if position <= center {
+                        (position - left) / (center - left).max(f32::EPSILON)
+                    } else {
+                        (right - position) / (right - center).max(f32::EPSILON)
+                    };
+                    (bin, weight.max(0.0))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Power of each frequency bin (0 to `fft_len / 2`) of a windowed frame, zero-padded to `fft_len`
+fn power_spectrum(frame: &[f32], window: &[f32], fft_len: usize) -> Vec<f32> {
+    let mut re = vec![0.0f32; fft_len];
+    let mut im = vec![0.0f32; fft_len];
+    for (i, (sample, weight)) in frame.iter().zip(window).enumerate() {
+        re[i] = sample * weight;
+    }
+    fft(&mut re, &mut im);
+    (0..=fft_len / 2).map(|bin| re[bin] * re[bin] + im[bin] * im[bin]).collect()
+}
+
+/// In-place radix-2 FFT; the length must be a power of two
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * cos - im[b] * sin;
+                let t_im = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two seconds of a buzzy "voice": harmonics of `pitch` shaped by a resonance at `formant`
+    fn voice(pitch: f32, formant: f32, seed: u32) -> Vec<u8> {
+        let sample_rate = 16_000u32;
+        let mut noise = seed;
+        let samples: Vec<i16> = (0..2 * sample_rate)
+            .map(|i| {
+                let t = i as f32 / sample_rate as f32;
+                let mut value = 0.0;
+                let mut harmonic = pitch;
+                while harmonic < 7000.0 {
+                    let gain = 1.0 / (1.0 + ((harmonic - formant) / 300.0).powi(2));
+                    value += gain * (2.0 * PI * harmonic * t).sin();
+                    harmonic += pitch;
+                }
+                noise = noise.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                value += (noise >> 16) as f32 / 65_536.0 * 0.05;
+                (value * 0.2 * f32::from(i16::MAX)).clamp(-32_767.0, 32_767.0) as i16
+            })
+            .collect();
+
+        let data_len = samples.len() as u32 * 2;
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&sample_rate.to_le_bytes());
+        wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&data_len.to_le_bytes());
+        for sample in samples {
+            wav.extend_from_slice(&sample.to_le_bytes());
+        }
+        wav
+    }
+
+    #[test]
+    fn test_same_voice_is_more_similar() {
+        let low = embed(&voice(110.0, 500.0, 1)).unwrap();
+        let low_again = embed(&voice(112.0, 520.0, 2)).unwrap();
+        let high = embed(&voice(220.0, 2000.0, 3)).unwrap();
+
+        assert_eq!(low.len(), 2 * COEFFICIENTS);
+        assert!(similarity(&low, &low_again) > similarity(&low, &high));
+        assert!((similarity(&low, &low) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_invalid_audio_is_rejected() {
+        assert!(embed(b"OggS not a wav").is_err());
+        // A header without enough speech
+        assert!(embed(&voice(110.0, 500.0, 1)[..44 + 2 * 1600]).is_err());
+    }
+
+    #[test]
+    fn test_identify_picks_the_closest_enrolled_voice() {
+        let dir = std::env::temp_dir().join(format!("talk-to-cmac-voiceprints-{:x}", rand::random::<u64>()));
+        fs::create_dir_all(&dir).unwrap();
+        let store = VoiceprintStore::new(&dir);
+
+        let samples = |pitch: f32, formant: f32| -> Vec<Vec<f32>> {
+            (0..3).map(|seed| embed(&voice(pitch + seed as f32, formant, seed)).unwrap()).collect()
+        };
+        assert!(store.enroll("dana", &samples(110.0, 500.0)[..2]).is_err());
+        let enrollment = store.enroll("dana", &samples(110.0, 500.0)).unwrap();
+        assert!(enrollment.consistency > 0.9);
+        store.enroll("lee", &samples(220.0, 2000.0)).unwrap();
+
+        let utterance = embed(&voice(111.0, 510.0, 9)).unwrap();
+        assert_eq!(store.identify(&utterance, 0.0).unwrap().unwrap().profile_id, "dana");
+        assert_eq!(store.identify(&utterance, 1.01).unwrap(), None);
+
+        assert!(store.remove("dana").unwrap());
+        assert_eq!(store.identify(&utterance, 0.0).unwrap().unwrap().profile_id, "lee");
+    }
+}
@@ -0,0 +1,99 @@
+//! Spoken text from markdown replies
+//!
+//! Replies are shown as markdown but read aloud as plain prose: code blocks,
+//! tables' divider rows, rules, and URLs are dropped, links and images are read
+//! by their text, and heading, list, quote, and emphasis markers are removed.
+//! Inline code is read as its text. The screen keeps the formatted reply; only
+//! the speech is cleaned.
+//!
+//! Streamed replies arrive a sentence at a time and a code block spans many
+//! sentences, so [`SpeechText`] remembers whether it is inside one between calls.
+
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Markdown-to-speech cleaner that keeps track of open code blocks across pushes
+#[derive(Debug, Default)]
+pub struct SpeechText {
+    in_code: bool,
+}
+
+impl SpeechText {
+    /// Spoken form of the next piece of the reply; empty if none of it is spoken
+    pub fn push(&mut self, markdown: &str) -> String {
+        let mut lines = Vec::new();
+        for line in markdown.lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                self.in_code = !self.in_code;
+                continue;
+            }
+            if self.in_code {
+                continue;
+            }
+            let spoken = spoken_line(trimmed);
+            if !spoken.is_empty() {
+                lines.push(spoken);
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// Spoken form of a whole reply
+pub fn clean(markdown: &str) -> String {
+    SpeechText::default().push(markdown)
+}
+
+/// One line outside code blocks, without markup
+fn spoken_line(line: &str) -> String {
+    static RULE: OnceLock<Regex> = OnceLock::new();
+    static PREFIX: OnceLock<Regex> = OnceLock::new();
+    static LINK: OnceLock<Regex> = OnceLock::new();
+    static URL: OnceLock<Regex> = OnceLock::new();
+    let rule = RULE.get_or_init(|| Regex::new(r"^(?:[-*_]\s*){3,}$|^\|?[\s:|-]*-[\s:|-]*\|?$").expect("rule pattern is valid"));
+    let prefix = PREFIX.get_or_init(|| Regex::new(r"^(?:#{1,6}\s+|>\s*|[-*+]\s+)+").expect("prefix pattern is valid"));
+    let link = LINK.get_or_init(|| Regex::new(r"!?\[([^\]]*)\]\([^)]*\)").expect("link pattern is valid"));
+    let url = URL.get_or_init(|| Regex::new(r"<?https?://[^\s>)]+>?").expect("URL pattern is valid"));
+
+    if rule.is_match(line) {
+        return String::new();
+    }
+
+    let line = prefix.replace(line, "");
+    let line = link.replace_all(&line, "$1");
+    let line = url.replace_all(&line, "");
+    let line = line.replace("**", "").replace("__", "").replace("~~", "").replace(['*', '`'], "");
+
+    // Table rows are read cell by cell
+    let line = if line.starts_with('|') {
+        line.split('|').map(str::trim).filter(|cell| !cell.is_empty()).collect::<Vec<_>>().join(", ")
+    } else {
+        line
+    };
+    line.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_and_markup_are_not_spoken() {
+        let reply = "## Reversing a string\n\nUse **`chars().rev()`**:\n\n```rust\nlet reversed: String = s.chars().rev().collect();\n```\n\n\
+            - It handles [Unicode](https://doc.rust-lang.org/std/primitive.char.html) scalars.\n\n| Method | Cost |\n|---|---|\n| rev | O(n) |";
+        assert_eq!(
+            clean(reply),
+            "Reversing a string\nUse chars().rev():\nIt handles Unicode scalars.\nMethod, Cost\nrev, O(n)"
+        );
+    }
+
+    #[test]
+    fn test_code_block_spans_pushes() {
+        let mut speech = SpeechText::default();
+        assert_eq!(speech.push("Run this:\n```bash"), "Run this:");
+        assert_eq!(speech.push("cargo build --release"), "");
+        assert_eq!(speech.push("```\nThen start the app."), "Then start the app.");
+        assert_eq!(clean("See https://example.com/docs for more, or snake_case names."), "See for more, or snake_case names.");
+    }
+}
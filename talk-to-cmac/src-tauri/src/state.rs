@@ -3,8 +3,18 @@
 //! Manages the global application state including conversation context,
 //! current processing state, and API connection status with thread-safe access.
 
-use crate::config::{ApiKeys, AppConfig};
+use crate::api::cooldown::Cooldowns;
+use crate::api::FileAttachment;
+use crate::audit;
+use crate::experiments;
+use crate::intents::{ReplyModifier, VoiceIntent};
+use crate::log_privacy;
+use crate::config::{ApiKeys, AppConfig, KeySlot, SlotKeys};
+use crate::session::SessionLock;
+use crate::telemetry::StageTimings;
+use crate::translator::TranslationDirection;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
 use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -12,6 +22,12 @@ use std::time::{SystemTime, UNIX_EPOCH};
 #[derive(Clone)]
 pub struct AppState {
     inner: Arc<Mutex<AppStateInner>>,
+
+    /// Providers paused after a rate limit or exhausted quota
+    cooldowns: Cooldowns,
+
+    /// Whether the session is locked, and when it was last used
+    session: SessionLock,
 }
 
 /// Inner application state
@@ -28,8 +44,121 @@ struct AppStateInner {
     /// API keys
     pub api_keys: ApiKeys,
 
+    /// Keys of the configured key slots
+    pub slot_keys: SlotKeys,
+
     /// API connection status
     pub connectivity: ConnectivityStatus,
+
+    /// Microphone privacy mute; while set no audio is accepted for processing
+    pub mic_muted: bool,
+
+    /// Most recent spoken reply, kept so it can be replayed without another TTS request
+    pub last_response: Option<LastResponse>,
+
+    /// Most recent recording as it was transcribed, kept so it can be transcribed again
+    pub last_recording: Option<LastRecording>,
+
+    /// Direction of the next turn while translator mode is on
+    pub translator: Option<TranslationDirection>,
+
+    /// Voice command waiting for a spoken yes or no
+    pub pending_action: Option<PendingAction>,
+
+    /// Spoken modifier for the next reply only
+    pub reply_modifier: Option<ReplyModifier>,
+
+    /// Latest transcriptions, oldest first, kept across conversations
+    pub recent_transcriptions: VecDeque<RecentTranscription>,
+
+    /// Live transcript of the session, oldest first
+    pub live_transcript: VecDeque<TranscriptSegment>,
+
+    /// Sequence number of the last live transcript segment
+    pub last_segment_seq: u64,
+}
+
+/// Segments kept in the live transcript; older ones are dropped
+const LIVE_TRANSCRIPT_SEGMENTS: usize = 500;
+
+/// Seconds a voice command waits for its confirmation before it is dropped
+const CONFIRMATION_TIMEOUT_SECS: u64 = 30;
+
+/// A voice command the user was asked to confirm
+#[derive(Debug, Clone)]
+pub struct PendingAction {
+    pub intent: VoiceIntent,
+
+    /// When the confirmation question was asked
+    pub asked_at: u64,
+}
+
+/// Text and synthesized audio of the most recent spoken reply
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LastResponse {
+    /// Reply text
+    pub text: String,
+
+    /// Synthesized audio
+    #[serde(skip)]
+    pub audio: Vec<u8>,
+
+    /// When the audio was synthesized
+    pub created_at: u64,
+}
+
+/// Audio of the most recent transcribed recording
+#[derive(Debug, Clone)]
+pub struct LastRecording {
+    /// Audio as it was sent for transcription
+    pub audio: Vec<u8>,
+
+    /// File name the audio was uploaded as
+    pub filename: String,
+}
+
+/// A recording as it was transcribed, whatever it was then used for
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentTranscription {
+    pub text: String,
+
+    /// What the recording was transcribed for
+    pub source: TranscriptionSource,
+
+    /// When the transcription finished
+    pub created_at: u64,
+}
+
+/// One utterance of the live transcript
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptSegment {
+    /// Increases by one with each segment, and is never reused during a run
+    pub seq: u64,
+
+    pub text: String,
+
+    /// What the recording was transcribed for
+    pub source: TranscriptionSource,
+
+    /// When the transcription finished
+    pub created_at: u64,
+}
+
+/// What a recording was transcribed for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscriptionSource {
+    /// Dictated into the text field (`process_audio`)
+    Dictation,
+
+    /// A voice query or voice command
+    Query,
+
+    /// The last recording transcribed again with other settings
+    Retranscription,
+
+    /// A turn in translator mode
+    Translation,
 }
 
 /// Application status enum
@@ -54,6 +183,9 @@ pub enum AppStatus {
     /// Converting response to speech
     Speaking,
 
+    /// Waiting for a spoken yes or no before running a voice command
+    Confirming,
+
     /// Error state
     Error { message: String },
 }
@@ -75,11 +207,48 @@ pub struct ConversationContext {
 
     /// Last updated timestamp
     pub updated_at: u64,
+
+    /// Incognito conversation that is never written to history or recordings
+    #[serde(default)]
+    pub ephemeral: bool,
+
+    /// Generation settings that override the global OpenWebUI configuration
+    #[serde(default)]
+    pub settings: ConversationSettings,
+
+    /// Prompt experiment variant ("experiment/variant") assigned when the conversation started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+}
+
+/// Per-conversation generation settings; unset fields fall back to the global configuration
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConversationSettings {
+    /// Model to use instead of `openwebui.model`
+    pub model: Option<String>,
+
+    /// Temperature to use instead of `openwebui.temperature`
+    pub temperature: Option<f32>,
+
+    /// System prompt prepended to the conversation
+    pub system_prompt: Option<String>,
+
+    /// Key slot to use per service (service -> slot id) instead of the default
+    #[serde(default)]
+    pub key_slots: BTreeMap<String, String>,
+
+    /// Files and knowledge collections on the OpenWebUI server to answer from
+    #[serde(default)]
+    pub files: Vec<FileAttachment>,
 }
 
 /// Message structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
+    /// Message ID, unique within the conversation
+    #[serde(default)]
+    pub id: String,
+
     /// Message role (user, assistant, system)
     pub role: MessageRole,
 
@@ -88,6 +257,38 @@ pub struct Message {
 
     /// Timestamp
     pub timestamp: u64,
+
+    /// Providers and timings of the stages that produced a reply (kept in memory only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stages: Option<StageTimings>,
+
+    /// Prompt experiment variant of the conversation when the message was added
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+
+    /// The user's rating of an assistant message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<Rating>,
+}
+
+/// Thumbs up or down on a reply
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Thumbs {
+    Up,
+    Down,
+}
+
+/// A user's rating of a reply, with an optional comment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rating {
+    pub thumbs: Thumbs,
+
+    /// What was good or wrong about the reply
+    pub comment: Option<String>,
+
+    /// When it was rated
+    pub rated_at: u64,
 }
 
 /// Message role enum
@@ -113,6 +314,67 @@ pub struct ConnectivityStatus {
 
     /// Last checked timestamp
     pub last_checked: u64,
+
+    /// Recent check results per service, oldest first (see `get_service_history`)
+    #[serde(skip)]
+    pub history: BTreeMap<String, VecDeque<HealthCheck>>,
+
+    /// Until when (Unix timestamp) the statuses are reused instead of checking again
+    #[serde(skip)]
+    pub fresh_until: u64,
+}
+
+/// Check results kept per service
+const MAX_HEALTH_CHECKS: usize = 100;
+
+/// How long the results of a connectivity check are reused
+pub const CONNECTIVITY_TTL_SECS: u64 = 30;
+
+/// Result of one connectivity check
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthCheck {
+    /// Unix timestamp of the check
+    pub timestamp: u64,
+
+    /// Provider checked, as named in the settings
+    pub provider: String,
+
+    /// Whether the service answered
+    pub connected: bool,
+
+    /// Time the check took (milliseconds)
+    pub response_ms: u64,
+
+    /// Why the service was unreachable
+    pub error: Option<String>,
+}
+
+/// Check history of one service with summary figures
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceHistory {
+    pub service: String,
+
+    /// Recent checks, oldest first
+    pub checks: Vec<HealthCheck>,
+
+    /// Share of checks that succeeded (0.0 - 1.0; absent before the first check)
+    pub success_rate: Option<f32>,
+
+    /// Average response time of successful checks (milliseconds)
+    pub average_response_ms: Option<u64>,
+}
+
+impl ServiceHistory {
+    fn new(service: &str, checks: &VecDeque<HealthCheck>) -> Self {
+        let connected: Vec<&HealthCheck> = checks.iter().filter(|check| check.connected).collect();
+        Self {
+            service: service.to_string(),
+            success_rate: (!checks.is_empty()).then(|| connected.len() as f32 / checks.len() as f32),
+            average_response_ms: (!connected.is_empty())
+                .then(|| connected.iter().map(|check| check.response_ms).sum::<u64>() / connected.len() as u64),
+            checks: checks.iter().cloned().collect(),
+        }
+    }
 }
 
 /// Individual service status
@@ -136,6 +398,8 @@ impl AppState {
     /// Create a new application state
     pub fn new(config: AppConfig, api_keys: ApiKeys) -> Self {
         let now = current_timestamp();
+        audit::apply_privacy(&config.privacy);
+        log_privacy::set_level(config.privacy.log_content);
 
         Self {
             inner: Arc::new(Mutex::new(AppStateInner {
@@ -143,22 +407,49 @@ impl AppState {
                 conversation: ConversationContext {
                     id: generate_id(),
                     messages: Vec::new(),
-                    max_messages: 20,
+                    max_messages: config.context.message_limit(),
                     started_at: now,
                     updated_at: now,
+                    ephemeral: false,
+                    settings: ConversationSettings::default(),
+                    variant: experiments::assign(&config.experiment),
                 },
                 config,
                 api_keys,
+                slot_keys: SlotKeys::default(),
                 connectivity: ConnectivityStatus {
                     whisper: ServiceStatus::Unknown,
                     openwebui: ServiceStatus::Unknown,
                     elevenlabs: ServiceStatus::Unknown,
                     last_checked: 0,
+                    history: BTreeMap::new(),
+                    fresh_until: 0,
                 },
+                mic_muted: false,
+                last_response: None,
+                last_recording: None,
+                translator: None,
+                pending_action: None,
+                reply_modifier: None,
+                recent_transcriptions: VecDeque::new(),
+                live_transcript: VecDeque::new(),
+                last_segment_seq: 0,
             })),
+            cooldowns: Cooldowns::default(),
+            session: SessionLock::default(),
         }
     }
 
+    /// Provider cooldowns, also used to create pipeline clients that respect them
+    pub fn cooldowns(&self) -> &Cooldowns {
+        &self.cooldowns
+    }
+
+    /// The session lock
+    pub fn session(&self) -> &SessionLock {
+        &self.session
+    }
+
     /// Get current status
     pub fn get_status(&self) -> AppStatus {
         let state = self.inner.lock().unwrap();
@@ -168,10 +459,28 @@ impl AppState {
     /// Set application status
     pub fn set_status(&self, status: AppStatus) {
         let mut state = self.inner.lock().unwrap();
-        log::info!("Status changed: {:?} -> {:?}", state.status, status);
+        tracing::info!("Status changed: {:?} -> {:?}", state.status, status);
         state.status = status;
     }
 
+    /// Check whether the microphone is muted
+    pub fn is_mic_muted(&self) -> bool {
+        let state = self.inner.lock().unwrap();
+        state.mic_muted
+    }
+
+    /// Mute or unmute the microphone
+    ///
+    /// Muting while recording drops back to idle, since the capture stream is torn down.
+    pub fn set_mic_muted(&self, muted: bool) {
+        let mut state = self.inner.lock().unwrap();
+        tracing::info!("Microphone {}", if muted { "muted" } else { "unmuted" });
+        state.mic_muted = muted;
+        if muted && matches!(state.status, AppStatus::Recording | AppStatus::Listening) {
+            state.status = AppStatus::Idle;
+        }
+    }
+
     /// Get configuration
     pub fn get_config(&self) -> AppConfig {
         let state = self.inner.lock().unwrap();
@@ -181,43 +490,126 @@ impl AppState {
     /// Update configuration
     pub fn update_config(&self, config: AppConfig) {
         let mut state = self.inner.lock().unwrap();
+        state.conversation.max_messages = config.context.message_limit();
+        audit::apply_privacy(&config.privacy);
+        log_privacy::set_level(config.privacy.log_content);
         state.config = config;
+        state.connectivity.fresh_until = 0;
     }
 
-    /// Get API keys
+    /// Get the API keys in effect for the current conversation
+    ///
+    /// A key slot picked by the conversation, or else a service's default slot,
+    /// replaces the service's unnamed key.
     pub fn get_api_keys(&self) -> ApiKeys {
         let state = self.inner.lock().unwrap();
-        state.api_keys.clone()
+        state.api_keys.clone().with_slots(
+            &state.config.key_slots,
+            &state.slot_keys,
+            &state.conversation.settings.key_slots,
+        )
     }
 
     /// Update API keys
     pub fn update_api_keys(&self, api_keys: ApiKeys) {
         let mut state = self.inner.lock().unwrap();
         state.api_keys = api_keys;
+        state.connectivity.fresh_until = 0;
+    }
+
+    /// Update the unnamed API key of one service; returns false for an unknown service
+    pub fn update_api_key(&self, service: &str, api_key: String) -> bool {
+        let mut state = self.inner.lock().unwrap();
+        match state.api_keys.service_mut(service) {
+            Some(field) => {
+                *field = Some(api_key);
+                state.connectivity.fresh_until = 0;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replace the keys of all key slots
+    pub fn set_slot_keys(&self, slot_keys: SlotKeys) {
+        let mut state = self.inner.lock().unwrap();
+        state.slot_keys = slot_keys;
+        state.connectivity.fresh_until = 0;
+    }
+
+    /// Add or replace the key of one slot
+    pub fn set_slot_key(&self, slot: &KeySlot, key: String) {
+        let mut state = self.inner.lock().unwrap();
+        state.slot_keys.insert(slot, key);
+        state.connectivity.fresh_until = 0;
+    }
+
+    /// Forget the key of one slot
+    pub fn remove_slot_key(&self, slot: &KeySlot) {
+        let mut state = self.inner.lock().unwrap();
+        state.slot_keys.remove(slot);
     }
 
     /// Add message to conversation
+    ///
+    /// Beyond `max_messages` user and assistant messages, the oldest are dropped;
+    /// system messages are never dropped.
     pub fn add_message(&self, role: MessageRole, content: String) {
         let mut state = self.inner.lock().unwrap();
         let now = current_timestamp();
 
         let message = Message {
+            id: generate_id(),
             role,
             content,
             timestamp: now,
+            stages: None,
+            variant: state.conversation.variant.clone(),
+            rating: None,
         };
 
         state.conversation.messages.push(message);
         state.conversation.updated_at = now;
 
-        // Trim old messages if exceeding max
+        // Trim old messages if exceeding max; system messages are pinned so the persona survives
         let max = state.conversation.max_messages;
-        if state.conversation.messages.len() > max {
-            let start = state.conversation.messages.len() - max;
-            state.conversation.messages = state.conversation.messages[start..].to_vec();
+        let trimmable = state.conversation.messages.iter().filter(|m| m.role != MessageRole::System).count();
+        let mut excess = trimmable.saturating_sub(max);
+        state.conversation.messages.retain(|m| {
+            if excess > 0 && m.role != MessageRole::System {
+                excess -= 1;
+                return false;
+            }
+            true
+        });
+
+        tracing::debug!("Message added. Total messages: {}", state.conversation.messages.len());
+    }
+
+    /// Note which providers produced the latest assistant message and how long they took
+    pub fn set_reply_stages(&self, stages: StageTimings) {
+        let mut state = self.inner.lock().unwrap();
+        if let Some(message) = state.conversation.messages.iter_mut().rev().find(|m| m.role == MessageRole::Assistant) {
+            message.stages = Some(stages);
         }
+    }
 
-        log::debug!("Message added. Total messages: {}", state.conversation.messages.len());
+    /// Rate an assistant message of the current conversation, replacing any earlier rating
+    ///
+    /// Returns the rated message, or `None` if there is no such assistant message.
+    pub fn rate_message(&self, message_id: &str, thumbs: Thumbs, comment: Option<String>) -> Option<Message> {
+        let mut state = self.inner.lock().unwrap();
+        let message = state
+            .conversation
+            .messages
+            .iter_mut()
+            .find(|m| m.id == message_id && m.role == MessageRole::Assistant)?;
+        message.rating = Some(Rating {
+            thumbs,
+            comment,
+            rated_at: current_timestamp(),
+        });
+        Some(message.clone())
     }
 
     /// Get conversation history
@@ -227,8 +619,82 @@ impl AppState {
     }
 
     /// Clear conversation history
+    ///
+    /// This also leaves an incognito session; the new conversation is persisted normally.
     pub fn clear_conversation(&self) {
+        self.reset_conversation(false);
+        tracing::info!("Conversation cleared");
+    }
+
+    /// Discard the current conversation and start an incognito one
+    pub fn start_incognito(&self) {
+        self.reset_conversation(true);
+        tracing::info!("Incognito session started");
+    }
+
+    /// Whether the current conversation is incognito
+    pub fn is_incognito(&self) -> bool {
+        let state = self.inner.lock().unwrap();
+        state.conversation.ephemeral
+    }
+
+    /// Replace the conversation, e.g. with one saved before a crash
+    pub fn restore_conversation(&self, conversation: ConversationContext) {
+        let mut state = self.inner.lock().unwrap();
+        tracing::info!("Restored conversation with {} message(s)", conversation.messages.len());
+        audit::set_incognito(conversation.ephemeral);
+        state.conversation = conversation;
+    }
+
+    /// Continue from an earlier message in a new conversation
+    ///
+    /// The new conversation holds copies of the messages up to and including
+    /// `message_id`, with the same settings and incognito flag, and becomes the
+    /// current one. Returns `None` if no such message is in the conversation.
+    pub fn fork_conversation(&self, message_id: &str) -> Option<ConversationContext> {
+        let mut state = self.inner.lock().unwrap();
+        let index = state.conversation.messages.iter().position(|message| message.id == message_id)?;
+
+        let now = current_timestamp();
+        let fork = ConversationContext {
+            id: generate_id(),
+            messages: state.conversation.messages[..=index].to_vec(),
+            max_messages: state.conversation.max_messages,
+            started_at: now,
+            updated_at: now,
+            ephemeral: state.conversation.ephemeral,
+            settings: state.conversation.settings.clone(),
+            variant: state.conversation.variant.clone(),
+        };
+        tracing::info!(
+            "Forked conversation {} at message {} of {}",
+            state.conversation.id,
+            index + 1,
+            state.conversation.messages.len()
+        );
+
+        state.last_response = None;
+        state.last_recording = None;
+        state.conversation = fork.clone();
+        Some(fork)
+    }
+
+    /// Status, conversation, and configuration without waiting for the lock
+    ///
+    /// For the panic hook, which must never block: returns `None` if the state is
+    /// locked or poisoned, as it is when the panic happened while it was held.
+    pub fn try_snapshot(&self) -> Option<(AppStatus, ConversationContext, AppConfig)> {
+        let state = self.inner.try_lock().ok()?;
+        Some((state.status.clone(), state.conversation.clone(), state.config.clone()))
+    }
+
+    fn reset_conversation(&self, ephemeral: bool) {
         let mut state = self.inner.lock().unwrap();
+        state.last_response = None;
+        state.last_recording = None;
+        state.pending_action = None;
+        state.reply_modifier = None;
+        audit::set_incognito(ephemeral);
         let now = current_timestamp();
         state.conversation = ConversationContext {
             id: generate_id(),
@@ -236,15 +702,195 @@ impl AppState {
             max_messages: state.conversation.max_messages,
             started_at: now,
             updated_at: now,
+            ephemeral,
+            settings: ConversationSettings::default(),
+            variant: experiments::assign(&state.config.experiment),
         };
-        log::info!("Conversation cleared");
     }
 
-    /// Get messages for API context (formatted for LLM)
+    /// Take on another profile's settings and keys in a new conversation
+    ///
+    /// The conversation, last reply, translator turn, and provider cooldowns
+    /// belong to the previous profile and are dropped.
+    pub fn switch_profile(&self, config: AppConfig, api_keys: ApiKeys, slot_keys: SlotKeys) {
+        self.reset_conversation(false);
+        self.cooldowns.clear(None);
+
+        let mut state = self.inner.lock().unwrap();
+        state.config = config;
+        state.api_keys = api_keys;
+        state.slot_keys = slot_keys;
+        state.connectivity.fresh_until = 0;
+        state.translator = None;
+        state.recent_transcriptions.clear();
+        state.live_transcript.clear();
+    }
+
+    /// Turn translator mode on (starting with language A) or off
+    pub fn set_translator_mode(&self, enabled: bool) {
+        let mut state = self.inner.lock().unwrap();
+        state.translator = enabled.then_some(TranslationDirection::AToB);
+        tracing::info!("Translator mode {}", if enabled { "enabled" } else { "disabled" });
+    }
+
+    /// Direction of the next translated turn, or `None` when translator mode is off
+    pub fn translator_direction(&self) -> Option<TranslationDirection> {
+        let state = self.inner.lock().unwrap();
+        state.translator
+    }
+
+    /// Hand the next turn to the other speaker
+    pub fn advance_translator(&self) {
+        let mut state = self.inner.lock().unwrap();
+        state.translator = state.translator.map(TranslationDirection::flip);
+    }
+
+    /// Remember the most recent spoken reply for replay
+    pub fn set_last_response(&self, text: String, audio: Vec<u8>) {
+        let mut state = self.inner.lock().unwrap();
+        state.last_response = Some(LastResponse {
+            text,
+            audio,
+            created_at: current_timestamp(),
+        });
+    }
+
+    /// Get the most recent spoken reply, if any
+    pub fn get_last_response(&self) -> Option<LastResponse> {
+        let state = self.inner.lock().unwrap();
+        state.last_response.clone()
+    }
+
+    /// Remember the most recent recording for transcribing again
+    pub fn set_last_recording(&self, audio: Vec<u8>, filename: &str) {
+        let mut state = self.inner.lock().unwrap();
+        state.last_recording = Some(LastRecording {
+            audio,
+            filename: filename.to_string(),
+        });
+    }
+
+    /// Get the most recent recording, if any
+    pub fn get_last_recording(&self) -> Option<LastRecording> {
+        let state = self.inner.lock().unwrap();
+        state.last_recording.clone()
+    }
+
+    /// Add a transcription to the recent ones and the live transcript
+    ///
+    /// Recent transcriptions beyond `audio.recent_transcriptions` are dropped. A
+    /// retranscription repeats an utterance already in the live transcript, so it
+    /// is only kept as a recent one. Returns the segment added to the live
+    /// transcript. Nothing is kept in incognito sessions or kiosk mode.
+    pub fn record_transcription(&self, text: &str, source: TranscriptionSource) -> Option<TranscriptSegment> {
+        let mut state = self.inner.lock().unwrap();
+        if text.trim().is_empty() || state.conversation.ephemeral || state.config.kiosk.enabled {
+            return None;
+        }
+        let created_at = current_timestamp();
+
+        let limit = state.config.audio.recent_transcriptions;
+        if limit > 0 {
+            state.recent_transcriptions.push_back(RecentTranscription {
+                text: text.to_string(),
+                source,
+                created_at,
+            });
+            while state.recent_transcriptions.len() > limit {
+                state.recent_transcriptions.pop_front();
+            }
+        }
+
+        if source == TranscriptionSource::Retranscription {
+            return None;
+        }
+        state.last_segment_seq += 1;
+        let segment = TranscriptSegment {
+            seq: state.last_segment_seq,
+            text: text.to_string(),
+            source,
+            created_at,
+        };
+        state.live_transcript.push_back(segment.clone());
+        if state.live_transcript.len() > LIVE_TRANSCRIPT_SEGMENTS {
+            state.live_transcript.pop_front();
+        }
+        Some(segment)
+    }
+
+    /// Recent transcriptions, newest first
+    pub fn recent_transcriptions(&self) -> Vec<RecentTranscription> {
+        let state = self.inner.lock().unwrap();
+        state.recent_transcriptions.iter().rev().cloned().collect()
+    }
+
+    /// Live transcript segments after sequence number `since`, oldest first
+    pub fn live_transcript(&self, since: u64) -> Vec<TranscriptSegment> {
+        let state = self.inner.lock().unwrap();
+        state.live_transcript.iter().filter(|segment| segment.seq > since).cloned().collect()
+    }
+
+    /// Forget the recent transcriptions and the live transcript
+    pub fn clear_recent_transcriptions(&self) {
+        let mut state = self.inner.lock().unwrap();
+        state.recent_transcriptions.clear();
+        state.live_transcript.clear();
+    }
+
+    /// Hold a voice command until the user confirms it, replacing any other
+    pub fn set_pending_action(&self, intent: VoiceIntent) {
+        let mut state = self.inner.lock().unwrap();
+        state.pending_action = Some(PendingAction {
+            intent,
+            asked_at: current_timestamp(),
+        });
+    }
+
+    /// Take the voice command waiting for confirmation, unless it was asked about too long ago
+    pub fn take_pending_action(&self) -> Option<VoiceIntent> {
+        let mut state = self.inner.lock().unwrap();
+        let pending = state.pending_action.take()?;
+        if current_timestamp().saturating_sub(pending.asked_at) > CONFIRMATION_TIMEOUT_SECS {
+            tracing::info!("Dropping an unconfirmed voice command: {:?}", pending.intent);
+            return None;
+        }
+        Some(pending.intent)
+    }
+
+    /// Apply `modifier` to the next reply, replacing any other
+    pub fn set_reply_modifier(&self, modifier: ReplyModifier) {
+        self.inner.lock().unwrap().reply_modifier = Some(modifier);
+    }
+
+    /// Take the modifier for the reply about to be generated
+    pub fn take_reply_modifier(&self) -> Option<ReplyModifier> {
+        self.inner.lock().unwrap().reply_modifier.take()
+    }
+
+    /// Replace the generation settings of the current conversation
+    pub fn set_conversation_settings(&self, settings: ConversationSettings) {
+        let mut state = self.inner.lock().unwrap();
+        state.conversation.settings = settings;
+        state.conversation.updated_at = current_timestamp();
+    }
+
+    /// Attach a file or knowledge collection to the current conversation, unless it already is
+    pub fn attach_to_conversation(&self, attachment: FileAttachment) {
+        let mut state = self.inner.lock().unwrap();
+        if !state.conversation.settings.files.contains(&attachment) {
+            state.conversation.settings.files.push(attachment);
+            state.conversation.updated_at = current_timestamp();
+        }
+    }
+
+    /// Get messages for API context (formatted for LLM), system messages first
     pub fn get_api_messages(&self) -> Vec<(String, String)> {
         let state = self.inner.lock().unwrap();
-        state.conversation.messages
-            .iter()
+        let (system, rest): (Vec<_>, Vec<_>) =
+            state.conversation.messages.iter().partition(|m| m.role == MessageRole::System);
+        system
+            .into_iter()
+            .chain(rest)
             .map(|m| {
                 let role = match m.role {
                     MessageRole::User => "user".to_string(),
@@ -268,6 +914,20 @@ impl AppState {
         state.connectivity.clone()
     }
 
+    /// Statuses of a connectivity check finished less than [`CONNECTIVITY_TTL_SECS`] ago
+    ///
+    /// Changing the settings or API keys drops them.
+    pub fn cached_connectivity(&self) -> Option<ConnectivityStatus> {
+        let state = self.inner.lock().unwrap();
+        (current_timestamp() < state.connectivity.fresh_until).then(|| state.connectivity.clone())
+    }
+
+    /// Reuse the current statuses for [`CONNECTIVITY_TTL_SECS`]
+    pub fn cache_connectivity(&self) {
+        let mut state = self.inner.lock().unwrap();
+        state.connectivity.fresh_until = current_timestamp() + CONNECTIVITY_TTL_SECS;
+    }
+
     /// Update individual service status
     pub fn update_service_status(&self, service: &str, status: ServiceStatus) {
         let mut state = self.inner.lock().unwrap();
@@ -275,11 +935,42 @@ impl AppState {
             "whisper" => state.connectivity.whisper = status,
             "openwebui" => state.connectivity.openwebui = status,
             "elevenlabs" => state.connectivity.elevenlabs = status,
-            _ => log::warn!("Unknown service: {}", service),
+            _ => tracing::warn!("Unknown service: {}", service),
         }
         state.connectivity.last_checked = current_timestamp();
     }
 
+    /// Record the outcome of a connectivity check, dropping the oldest beyond the limit
+    pub fn record_health_check(&self, service: &str, provider: String, status: &ServiceStatus, response_ms: u64) {
+        let mut state = self.inner.lock().unwrap();
+        let checks = state.connectivity.history.entry(service.to_string()).or_default();
+        checks.push_back(HealthCheck {
+            timestamp: current_timestamp(),
+            provider,
+            connected: matches!(status, ServiceStatus::Connected),
+            response_ms,
+            error: match status {
+                ServiceStatus::Disconnected { reason } => Some(reason.clone()),
+                _ => None,
+            },
+        });
+        if checks.len() > MAX_HEALTH_CHECKS {
+            checks.pop_front();
+        }
+    }
+
+    /// Check history of one service, or of every checked service
+    pub fn get_service_history(&self, service: Option<&str>) -> Vec<ServiceHistory> {
+        let state = self.inner.lock().unwrap();
+        state
+            .connectivity
+            .history
+            .iter()
+            .filter(|(name, _)| service.is_none_or(|service| service == name.as_str()))
+            .map(|(name, checks)| ServiceHistory::new(name, checks))
+            .collect()
+    }
+
     /// Check if all services are connected
     pub fn all_services_connected(&self) -> bool {
         let state = self.inner.lock().unwrap();
@@ -316,6 +1007,9 @@ mod tests {
             whisper: None,
             openwebui: None,
             elevenlabs: None,
+            azure: None,
+            openrouter: None,
+            gemini: None,
         };
         let state = AppState::new(config, keys);
         assert_eq!(state.get_status(), AppStatus::Idle);
@@ -328,12 +1022,83 @@ mod tests {
             whisper: None,
             openwebui: None,
             elevenlabs: None,
+            azure: None,
+            openrouter: None,
+            gemini: None,
         };
         let state = AppState::new(config, keys);
         state.set_status(AppStatus::Listening);
         assert_eq!(state.get_status(), AppStatus::Listening);
     }
 
+    #[test]
+    fn test_mic_mute_stops_recording() {
+        let state = AppState::new(AppConfig::default(), ApiKeys::none());
+        state.set_status(AppStatus::Recording);
+
+        state.set_mic_muted(true);
+        assert!(state.is_mic_muted());
+        assert_eq!(state.get_status(), AppStatus::Idle);
+
+        state.set_mic_muted(false);
+        assert!(!state.is_mic_muted());
+    }
+
+    #[test]
+    fn test_incognito_session_lifecycle() {
+        let state = AppState::new(AppConfig::default(), ApiKeys::none());
+        state.add_message(MessageRole::User, "Before".to_string());
+
+        state.start_incognito();
+        assert!(state.is_incognito());
+        assert!(state.get_conversation().messages.is_empty());
+
+        state.add_message(MessageRole::User, "Secret".to_string());
+        state.clear_conversation();
+        assert!(!state.is_incognito());
+        assert!(state.get_conversation().messages.is_empty());
+    }
+
+    #[test]
+    fn test_recent_transcriptions_outlive_conversations() {
+        let mut config = AppConfig::default();
+        config.audio.recent_transcriptions = 2;
+        let state = AppState::new(config, ApiKeys::none());
+        state.record_transcription("Take a note", TranscriptionSource::Dictation);
+        state.clear_conversation();
+        state.record_transcription("Open a ticket", TranscriptionSource::Query);
+        state.record_transcription("  ", TranscriptionSource::Query);
+        state.record_transcription("Yes", TranscriptionSource::Query);
+
+        let recent: Vec<String> = state.recent_transcriptions().into_iter().map(|t| t.text).collect();
+        assert_eq!(recent, ["Yes", "Open a ticket"]);
+
+        state.start_incognito();
+        state.record_transcription("Secret", TranscriptionSource::Query);
+        assert_eq!(state.recent_transcriptions().len(), 2);
+    }
+
+    #[test]
+    fn test_live_transcript_is_read_incrementally() {
+        let mut config = AppConfig::default();
+        config.audio.recent_transcriptions = 0;
+        let state = AppState::new(config, ApiKeys::none());
+        let first = state.record_transcription("Good morning", TranscriptionSource::Query).unwrap();
+        assert!(state.record_transcription("Good morning", TranscriptionSource::Retranscription).is_none());
+        let second = state.record_transcription("Buenos días", TranscriptionSource::Translation).unwrap();
+        assert_eq!((first.seq, second.seq), (1, 2));
+
+        let texts = |since| state.live_transcript(since).into_iter().map(|s| s.text).collect::<Vec<_>>();
+        assert_eq!(texts(0), ["Good morning", "Buenos días"]);
+        assert_eq!(texts(first.seq), ["Buenos días"]);
+        assert!(state.recent_transcriptions().is_empty());
+
+        // Sequence numbers keep counting after the transcript is cleared
+        state.clear_recent_transcriptions();
+        assert!(texts(0).is_empty());
+        assert_eq!(state.record_transcription("Next", TranscriptionSource::Dictation).unwrap().seq, 3);
+    }
+
     #[test]
     fn test_message_addition() {
         let config = AppConfig::default();
@@ -341,6 +1106,9 @@ mod tests {
             whisper: None,
             openwebui: None,
             elevenlabs: None,
+            azure: None,
+            openrouter: None,
+            gemini: None,
         };
         let state = AppState::new(config, keys);
         state.add_message(MessageRole::User, "Hello".to_string());
@@ -356,6 +1124,9 @@ mod tests {
             whisper: None,
             openwebui: None,
             elevenlabs: None,
+            azure: None,
+            openrouter: None,
+            gemini: None,
         };
         let state = AppState::new(config, keys);
         state.add_message(MessageRole::User, "Hello".to_string());
@@ -364,6 +1135,122 @@ mod tests {
         assert_eq!(conversation.messages.len(), 0);
     }
 
+    #[test]
+    fn test_conversation_settings_reset_with_conversation() {
+        let state = AppState::new(AppConfig::default(), ApiKeys::none());
+        let settings = ConversationSettings {
+            model: Some("codellama".to_string()),
+            temperature: Some(0.2),
+            system_prompt: Some("You are a coding assistant.".to_string()),
+            key_slots: BTreeMap::new(),
+            files: vec![FileAttachment::file("3f2c")],
+        };
+
+        state.set_conversation_settings(settings.clone());
+        assert_eq!(state.get_conversation().settings, settings);
+
+        state.clear_conversation();
+        assert_eq!(state.get_conversation().settings, ConversationSettings::default());
+    }
+
+    #[test]
+    fn test_fork_keeps_history_up_to_message() {
+        let state = AppState::new(AppConfig::default(), ApiKeys::none());
+        for content in ["Question", "Answer", "Follow-up", "Second answer"] {
+            state.add_message(MessageRole::User, content.to_string());
+        }
+        let original = state.get_conversation();
+        assert!(state.fork_conversation("missing").is_none());
+
+        let fork = state.fork_conversation(&original.messages[1].id).unwrap();
+        assert_ne!(fork.id, original.id);
+        let contents: Vec<_> = fork.messages.iter().map(|message| message.content.as_str()).collect();
+        assert_eq!(contents, ["Question", "Answer"]);
+        assert_eq!(fork.messages[1].id, original.messages[1].id);
+        assert_eq!(state.get_conversation().id, fork.id);
+    }
+
+    #[test]
+    fn test_service_history_is_rolling() {
+        let state = AppState::new(AppConfig::default(), ApiKeys::none());
+        let down = ServiceStatus::Disconnected { reason: "timed out".to_string() };
+
+        for i in 0..MAX_HEALTH_CHECKS as u64 + 5 {
+            let status = if i % 4 == 0 { &down } else { &ServiceStatus::Connected };
+            state.record_health_check("whisper", "whisper".to_string(), status, 100 + i);
+        }
+        state.record_health_check("elevenlabs", "mock".to_string(), &ServiceStatus::Connected, 1);
+
+        let history = state.get_service_history(Some("whisper"));
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].checks.len(), MAX_HEALTH_CHECKS);
+        assert_eq!(history[0].checks[0].response_ms, 105);
+        assert_eq!(history[0].success_rate, Some(0.75));
+        assert_eq!(history[0].checks.iter().filter_map(|check| check.error.as_deref()).next(), Some("timed out"));
+        assert_eq!(state.get_service_history(None).len(), 2);
+    }
+
+    #[test]
+    fn test_connectivity_cache_dropped_on_config_change() {
+        let state = AppState::new(AppConfig::default(), ApiKeys::none());
+        assert!(state.cached_connectivity().is_none());
+
+        state.update_service_status("whisper", ServiceStatus::Connected);
+        state.cache_connectivity();
+        let cached = state.cached_connectivity().unwrap();
+        assert!(matches!(cached.whisper, ServiceStatus::Connected));
+
+        state.update_config(AppConfig::default());
+        assert!(state.cached_connectivity().is_none());
+    }
+
+    #[test]
+    fn test_last_response_cleared_with_conversation() {
+        let state = AppState::new(AppConfig::default(), ApiKeys::none());
+        assert!(state.get_last_response().is_none());
+
+        state.set_last_response("Hello".to_string(), vec![1, 2, 3]);
+        let last = state.get_last_response().unwrap();
+        assert_eq!(last.text, "Hello");
+        assert_eq!(last.audio, vec![1, 2, 3]);
+        state.set_last_recording(vec![4, 5], "recording.wav");
+        assert_eq!(state.get_last_recording().unwrap().filename, "recording.wav");
+        state.set_pending_action(VoiceIntent::RepeatLast);
+
+        state.clear_conversation();
+        assert!(state.get_last_response().is_none());
+        assert!(state.get_last_recording().is_none());
+        assert!(state.take_pending_action().is_none());
+    }
+
+    #[test]
+    fn test_translator_turns_alternate() {
+        let state = AppState::new(AppConfig::default(), ApiKeys::none());
+        assert_eq!(state.translator_direction(), None);
+
+        state.set_translator_mode(true);
+        assert_eq!(state.translator_direction(), Some(TranslationDirection::AToB));
+        state.advance_translator();
+        assert_eq!(state.translator_direction(), Some(TranslationDirection::BToA));
+
+        state.set_translator_mode(false);
+        state.advance_translator();
+        assert_eq!(state.translator_direction(), None);
+    }
+
+    #[test]
+    fn test_snapshot_skips_held_lock() {
+        let state = AppState::new(AppConfig::default(), ApiKeys::none());
+        state.add_message(MessageRole::User, "Hello".to_string());
+
+        let (status, conversation, _) = state.try_snapshot().expect("state is not locked");
+        assert_eq!(status, AppStatus::Idle);
+        assert_eq!(conversation.messages.len(), 1);
+
+        let _held = state.inner.lock().unwrap();
+        assert!(state.try_snapshot().is_none());
+    }
+
     #[test]
     fn test_max_messages() {
         let config = AppConfig::default();
@@ -371,6 +1258,9 @@ mod tests {
             whisper: None,
             openwebui: None,
             elevenlabs: None,
+            azure: None,
+            openrouter: None,
+            gemini: None,
         };
         let state = AppState::new(config, keys);
 
@@ -382,4 +1272,26 @@ mod tests {
         let conversation = state.get_conversation();
         assert_eq!(conversation.messages.len(), 20); // Should be trimmed to max_messages
     }
+
+    #[test]
+    fn test_system_messages_survive_trimming() {
+        let state = AppState::new(AppConfig::default(), ApiKeys::none());
+
+        state.add_message(MessageRole::System, "You are a support agent.".to_string());
+        for i in 0..25 {
+            state.add_message(MessageRole::User, format!("Message {}", i));
+            if i == 10 {
+                state.add_message(MessageRole::System, "The customer has the Pro plan.".to_string());
+            }
+        }
+
+        let conversation = state.get_conversation();
+        assert_eq!(conversation.messages.len(), 22);
+        assert_eq!(conversation.messages[0].content, "You are a support agent.");
+
+        let api_messages = state.get_api_messages();
+        assert_eq!(api_messages[0], ("system".to_string(), "You are a support agent.".to_string()));
+        assert_eq!(api_messages[1].1, "The customer has the Pro plan.");
+        assert_eq!(api_messages[2].1, "Message 5");
+    }
 }
@@ -0,0 +1,356 @@
+//! Telemetry export
+//!
+//! Sends the pipeline's tracing spans and a latency metric to an OpenTelemetry
+//! collector over OTLP/HTTP, so a fleet of installs can be watched centrally.
+//! Export needs a build with the `telemetry` feature and `telemetry.enabled`;
+//! otherwise the layer is absent and stage measurements are only timed locally.
+//!
+//! Metrics:
+//! - `cmac.pipeline.stage.duration` (histogram, seconds) with `stage`, `provider`,
+//!   and `outcome` attributes. Its counts give provider usage and error rates.
+//!   When replies are streamed, the `llm` stage includes speaking the reply.
+
+use crate::api::provider::mock_providers_forced;
+use crate::config::{AppConfig, LlmProviderKind, SttProviderKind, TelemetryConfig, TtsProviderKind};
+use crate::crash;
+use crate::error::AppResult;
+use crate::usage;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tracing::Subscriber;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+/// Service name reported to the collector
+#[cfg_attr(not(feature = "telemetry"), allow(dead_code))]
+const SERVICE_NAME: &str = "talk-to-cmac";
+
+/// Pipeline stage measured by the stage metric
+#[derive(Debug, Clone, Copy)]
+pub enum Stage {
+    Stt,
+    Llm,
+    Tts,
+}
+
+impl Stage {
+    /// Short name used in spans, metrics, and reports
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::Stt => "stt",
+            Stage::Llm => "llm",
+            Stage::Tts => "tts",
+        }
+    }
+
+    /// Configured provider of the stage, as named in the settings
+    pub fn provider(self, config: &AppConfig) -> String {
+        if mock_providers_forced() {
+            return "mock".to_string();
+        }
+        match self {
+            Stage::Stt => setting_name(config.providers.stt),
+            Stage::Llm => setting_name(config.providers.llm),
+            Stage::Tts => setting_name(config.providers.tts),
+        }
+    }
+
+    /// Model, deployment, or voice model the stage's provider uses, if it has one
+    pub fn model(self, config: &AppConfig) -> Option<String> {
+        if mock_providers_forced() {
+            return None;
+        }
+        match self {
+            Stage::Stt => match config.providers.stt {
+                SttProviderKind::Whisper => Some(config.whisper.model.clone()),
+                SttProviderKind::AzureOpenAi => Some(config.azure.transcription_deployment.clone()),
+                SttProviderKind::Mock => None,
+            },
+            Stage::Llm => match config.providers.llm {
+                LlmProviderKind::OpenWebUi => Some(config.openwebui.model.clone()),
+                LlmProviderKind::AzureOpenAi => Some(config.azure.chat_deployment.clone()),
+                LlmProviderKind::OpenRouter => Some(config.openrouter.model.clone()),
+                LlmProviderKind::Gemini => Some(config.gemini.model.clone()),
+                LlmProviderKind::Mock => None,
+            },
+            Stage::Tts => match config.providers.tts {
+                TtsProviderKind::ElevenLabs => Some(config.elevenlabs.model_id.clone()),
+                TtsProviderKind::AzureOpenAi => Some(config.azure.speech_deployment.clone()),
+                TtsProviderKind::Mock => None,
+            },
+        }
+    }
+}
+
+/// Which provider handled a pipeline stage of one query, and how long it took
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageTiming {
+    /// Provider as named in the settings (e.g., "openwebui")
+    pub provider: String,
+
+    /// Model, deployment, or voice model used, if the provider has one
+    pub model: Option<String>,
+
+    pub duration_ms: u64,
+
+    /// The configured provider was paused, so its cooldown fallback handled the stage
+    pub failover: bool,
+}
+
+impl StageTiming {
+    /// Timing of `stage` from `started` until now
+    ///
+    /// `routed` is `configured` with the stage switched to the provider that
+    /// handled it, which differs after a failover.
+    pub fn since(stage: Stage, configured: &AppConfig, routed: &AppConfig, started: Instant) -> Self {
+        let provider = stage.provider(routed);
+        Self {
+            failover: provider != stage.provider(configured),
+            model: stage.model(routed),
+            provider,
+            duration_ms: started.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+/// Providers and timings of the stages that produced a reply
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StageTimings {
+    pub stt: Option<StageTiming>,
+    pub llm: Option<StageTiming>,
+    pub tts: Option<StageTiming>,
+}
+
+fn setting_name(kind: impl Serialize) -> String {
+    serde_json::to_value(kind)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Tracing layer exporting spans to the collector, if telemetry is enabled
+///
+/// Also installs the metric exporter. Fails if the exporters cannot be created
+/// or this build lacks the `telemetry` feature.
+pub fn layer<S>(config: &TelemetryConfig) -> AppResult<Option<Box<dyn Layer<S> + Send + Sync>>>
+where
+    S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync,
+{
+    if !config.enabled {
+        return Ok(None);
+    }
+
+    #[cfg(feature = "telemetry")]
+    {
+        otlp::start(config).map(Some)
+    }
+    #[cfg(not(feature = "telemetry"))]
+    {
+        Err(crate::error::AppError::Generic(
+            "this build does not include telemetry export (the `telemetry` feature)".to_string(),
+        ))
+    }
+}
+
+/// Flush and stop the exporters before the app exits
+pub fn shutdown() {
+    #[cfg(feature = "telemetry")]
+    otlp::shutdown();
+}
+
+/// Run one pipeline stage, recording its duration and outcome
+///
+/// Failures are also counted for anonymous usage reports, and the stage is
+/// noted for crash reports.
+pub async fn measure<T>(stage: Stage, config: &AppConfig, work: impl Future<Output = AppResult<T>>) -> AppResult<T> {
+    crash::enter_stage(stage.name());
+    let started = Instant::now();
+    let result = work.await;
+    record(stage, &stage.provider(config), started.elapsed(), result.is_ok());
+    if let Err(e) = &result {
+        usage::record_error(e);
+    }
+    result
+}
+
+#[cfg(feature = "telemetry")]
+fn record(stage: Stage, provider: &str, elapsed: Duration, ok: bool) {
+    otlp::record_stage(stage.name(), provider, elapsed, ok);
+}
+
+#[cfg(not(feature = "telemetry"))]
+fn record(stage: Stage, provider: &str, elapsed: Duration, ok: bool) {
+    tracing::trace!("{} ({}) took {} ms, ok: {}", stage.name(), provider, elapsed.as_millis(), ok);
+}
+
+/// URL of one OTLP/HTTP signal (`traces` or `metrics`) under the collector endpoint
+#[cfg_attr(not(feature = "telemetry"), allow(dead_code))]
+fn signal_url(endpoint: &str, signal: &str) -> String {
+    format!("{}/v1/{}", endpoint.trim_end_matches('/'), signal)
+}
+
+/// Name reported for this install: the configured one, else the host name
+#[cfg_attr(not(feature = "telemetry"), allow(dead_code))]
+fn instance_id(config: &TelemetryConfig) -> String {
+    config
+        .instance_id
+        .clone()
+        .filter(|id| !id.trim().is_empty())
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(feature = "telemetry")]
+mod otlp {
+    use super::{instance_id, signal_url, SERVICE_NAME};
+    use crate::config::TelemetryConfig;
+    use crate::error::{AppError, AppResult};
+    use opentelemetry::metrics::Histogram;
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::{MetricExporter, SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use opentelemetry_sdk::Resource;
+    use std::sync::OnceLock;
+    use std::time::Duration;
+    use tracing::Subscriber;
+    use tracing_subscriber::filter::filter_fn;
+    use tracing_subscriber::registry::LookupSpan;
+    use tracing_subscriber::Layer;
+
+    /// Providers kept for the final flush
+    static PROVIDERS: OnceLock<(SdkTracerProvider, SdkMeterProvider)> = OnceLock::new();
+
+    static STAGE_DURATION: OnceLock<Histogram<f64>> = OnceLock::new();
+
+    pub fn start<S>(config: &TelemetryConfig) -> AppResult<Box<dyn Layer<S> + Send + Sync>>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a> + Send + Sync,
+    {
+        let resource = Resource::builder()
+            .with_service_name(SERVICE_NAME)
+            .with_attribute(KeyValue::new("service.instance.id", instance_id(config)))
+            .with_attribute(KeyValue::new("service.version", env!("CARGO_PKG_VERSION")))
+            .build();
+
+        let span_exporter = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(signal_url(&config.endpoint, "traces"))
+            .build()
+            .map_err(|e| AppError::Generic(format!("Failed to create trace exporter: {}", e)))?;
+        let tracer_provider = SdkTracerProvider::builder()
+            .with_batch_exporter(span_exporter)
+            .with_resource(resource.clone())
+            .build();
+
+        let metric_exporter = MetricExporter::builder()
+            .with_http()
+            .with_endpoint(signal_url(&config.endpoint, "metrics"))
+            .build()
+            .map_err(|e| AppError::Generic(format!("Failed to create metric exporter: {}", e)))?;
+        let reader = PeriodicReader::builder(metric_exporter)
+            .with_interval(Duration::from_secs(config.export_interval_secs.max(1)))
+            .build();
+        let meter_provider = SdkMeterProvider::builder()
+            .with_reader(reader)
+            .with_resource(resource)
+            .build();
+
+        opentelemetry::global::set_meter_provider(meter_provider.clone());
+        let tracer = tracer_provider.tracer(SERVICE_NAME);
+        let _ = PROVIDERS.set((tracer_provider, meter_provider));
+
+        // Only the app's own spans are exported, not those of HTTP libraries (including the exporter's)
+        let layer = tracing_opentelemetry::layer()
+            .with_tracer(tracer)
+            .with_filter(filter_fn(|metadata| metadata.target().starts_with(env!("CARGO_CRATE_NAME"))));
+        Ok(Box::new(layer))
+    }
+
+    pub fn record_stage(stage: &'static str, provider: &str, elapsed: Duration, ok: bool) {
+        let histogram = STAGE_DURATION.get_or_init(|| {
+            opentelemetry::global::meter(SERVICE_NAME)
+                .f64_histogram("cmac.pipeline.stage.duration")
+                .with_unit("s")
+                .with_description("Duration of voice pipeline stages")
+                .build()
+        });
+        histogram.record(
+            elapsed.as_secs_f64(),
+            &[
+                KeyValue::new("stage", stage),
+                KeyValue::new("provider", provider.to_string()),
+                KeyValue::new("outcome", if ok { "ok" } else { "error" }),
+            ],
+        );
+    }
+
+    pub fn shutdown() {
+        if let Some((tracer_provider, meter_provider)) = PROVIDERS.get() {
+            if let Err(e) = tracer_provider.shutdown() {
+                tracing::warn!("Failed to flush traces: {}", e);
+            }
+            if let Err(e) = meter_provider.shutdown() {
+                tracing::warn!("Failed to flush metrics: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{LlmProviderKind, TtsProviderKind};
+
+    #[test]
+    fn test_signal_urls_extend_the_endpoint() {
+        assert_eq!(signal_url("http://collector:4318", "traces"), "http://collector:4318/v1/traces");
+        assert_eq!(signal_url("http://collector:4318/", "metrics"), "http://collector:4318/v1/metrics");
+    }
+
+    #[test]
+    fn test_stages_report_setting_names() {
+        let mut config = AppConfig::default();
+        config.providers.llm = LlmProviderKind::OpenRouter;
+        config.providers.tts = TtsProviderKind::AzureOpenAi;
+
+        assert_eq!(Stage::Stt.provider(&config), "whisper");
+        assert_eq!(Stage::Llm.provider(&config), "openrouter");
+        assert_eq!(Stage::Tts.provider(&config), "azure_openai");
+    }
+
+    #[test]
+    fn test_timings_note_failover() {
+        let configured = AppConfig::default();
+        let mut routed = configured.clone();
+        routed.providers.llm = LlmProviderKind::OpenRouter;
+        let started = Instant::now() - Duration::from_millis(1500);
+
+        let timing = StageTiming::since(Stage::Llm, &configured, &routed, started);
+        assert_eq!(timing.provider, "openrouter");
+        assert_eq!(timing.model, Some(routed.openrouter.model.clone()));
+        assert!(timing.failover && timing.duration_ms >= 1500);
+
+        let timing = StageTiming::since(Stage::Stt, &configured, &routed, started);
+        assert_eq!(timing.model.as_deref(), Some(configured.whisper.model.as_str()));
+        assert!(!timing.failover);
+    }
+
+    #[test]
+    fn test_configured_instance_id_wins() {
+        let config = TelemetryConfig {
+            instance_id: Some("kiosk-12".to_string()),
+            ..TelemetryConfig::default()
+        };
+        assert_eq!(instance_id(&config), "kiosk-12");
+
+        let blank = TelemetryConfig {
+            instance_id: Some("  ".to_string()),
+            ..TelemetryConfig::default()
+        };
+        assert_ne!(instance_id(&blank), "  ");
+    }
+}
@@ -0,0 +1,117 @@
+//! Voice-activated prompt templates
+//!
+//! Templates are prompts bound to trigger phrases. When a transcription starts
+//! with a trigger phrase ("daily standup", "ticket summary"), the bound template
+//! is expanded and sent to the LLM in place of the raw transcription. Matching
+//! ignores case and punctuation, so "Daily standup:" matches "daily standup".
+
+use crate::config::PromptTemplate;
+
+/// Placeholder replaced with whatever was said after the trigger phrase
+const INPUT_PLACEHOLDER: &str = "{input}";
+
+/// A template selected for a transcription
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateMatch {
+    /// Name of the matched template
+    pub name: String,
+
+    /// Prompt to send to the LLM
+    pub prompt: String,
+}
+
+/// Find the template whose trigger phrase begins `transcription`, preferring the longest trigger
+pub fn match_template(templates: &[PromptTemplate], transcription: &str) -> Option<TemplateMatch> {
+    let words: Vec<&str> = transcription.split_whitespace().collect();
+    let normalized: Vec<String> = words.iter().map(|word| normalize(word)).collect();
+
+    let (template, trigger_len) = templates
+        .iter()
+        .flat_map(|template| template.triggers.iter().map(move |trigger| (template, trigger)))
+        .filter_map(|(template, trigger)| {
+            let trigger: Vec<String> = trigger
+                .split_whitespace()
+                .map(normalize)
+                .filter(|word| !word.is_empty())
+                .collect();
+            let matches = !trigger.is_empty()
+                && normalized.len() >= trigger.len()
+                && normalized[..trigger.len()] == trigger[..];
+            matches.then_some((template, trigger.len()))
+        })
+        .max_by_key(|(_, len)| *len)?;
+
+    let input = words[trigger_len..]
+        .join(" ")
+        .trim_start_matches(|c: char| c.is_ascii_punctuation())
+        .trim()
+        .to_string();
+
+    Some(TemplateMatch {
+        name: template.name.clone(),
+        prompt: expand(&template.prompt, &input),
+    })
+}
+
+/// Fill the template prompt with the rest of the utterance
+///
+/// Without a placeholder, any remaining input is appended on its own paragraph.
+fn expand(prompt: &str, input: &str) -> String {
+    if prompt.contains(INPUT_PLACEHOLDER) {
+        prompt.replace(INPUT_PLACEHOLDER, input)
+    } else if input.is_empty() {
+        prompt.to_string()
+    } else {
+        format!("{}\n\n{}", prompt, input)
+    }
+}
+
+fn normalize(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn templates() -> Vec<PromptTemplate> {
+        vec![
+            PromptTemplate {
+                name: "Standup".to_string(),
+                triggers: vec!["daily standup".to_string()],
+                prompt: "Format these notes as a standup update: {input}".to_string(),
+            },
+            PromptTemplate {
+                name: "Ticket".to_string(),
+                triggers: vec!["ticket".to_string(), "ticket summary".to_string()],
+                prompt: "Summarize this ticket in two sentences.".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_matches_trigger_and_fills_input() {
+        let matched = match_template(&templates(), "Daily standup: fixed the login bug").unwrap();
+        assert_eq!(matched.name, "Standup");
+        assert_eq!(matched.prompt, "Format these notes as a standup update: fixed the login bug");
+    }
+
+    #[test]
+    fn test_longest_trigger_wins_and_input_is_appended() {
+        let matched = match_template(&templates(), "Ticket summary, users can't reset passwords").unwrap();
+        assert_eq!(matched.name, "Ticket");
+        assert_eq!(
+            matched.prompt,
+            "Summarize this ticket in two sentences.\n\nusers can't reset passwords"
+        );
+    }
+
+    #[test]
+    fn test_trigger_must_start_the_transcription() {
+        assert!(match_template(&templates(), "What is a daily standup?").is_none());
+        assert!(match_template(&templates(), "Tickets are piling up").is_none());
+    }
+}
@@ -0,0 +1,57 @@
+//! Test support
+//!
+//! Responses for in-process mock HTTP servers standing in for Whisper, OpenWebUI,
+//! and ElevenLabs. Client tests mount these on a `MockServer` so real requests,
+//! status codes, and streamed bodies go through the same code as in production.
+
+#![cfg(test)]
+
+use serde_json::json;
+pub use wiremock::matchers::{header, method, path};
+pub use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Error in the OpenAI format used by Whisper and OpenWebUI
+pub fn openai_error(status: u16, message: &str) -> ResponseTemplate {
+    ResponseTemplate::new(status).set_body_json(json!({
+        "error": { "message": message, "type": "invalid_request_error" }
+    }))
+}
+
+/// Error in the ElevenLabs format
+pub fn elevenlabs_error(status: u16, message: &str) -> ResponseTemplate {
+    ResponseTemplate::new(status).set_body_json(json!({
+        "detail": { "status": "error", "message": message }
+    }))
+}
+
+/// Successful Whisper transcription
+pub fn transcription(text: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({ "text": text }))
+}
+
+/// Chat completion with a single assistant message
+pub fn chat_completion(content: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({
+        "id": "chatcmpl-test",
+        "model": "test-model",
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": content },
+            "finish_reason": "stop"
+        }]
+    }))
+}
+
+/// Server-sent event stream of chat completion deltas, ending with `[DONE]`
+pub fn chat_stream(deltas: &[&str]) -> ResponseTemplate {
+    let mut body: String = deltas
+        .iter()
+        .map(|delta| {
+            let chunk = json!({ "choices": [{ "index": 0, "delta": { "content": delta } }] });
+            format!("data: {}\n\n", chunk)
+        })
+        .collect();
+    body.push_str("data: [DONE]\n\n");
+
+    ResponseTemplate::new(200).set_body_raw(body, "text/event-stream")
+}
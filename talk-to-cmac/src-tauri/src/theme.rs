@@ -0,0 +1,75 @@
+//! Theme selection
+//!
+//! `ui.theme` is "light", "dark", or "system". With "system" the app follows the
+//! operating system's dark-mode setting, which the backend reads from the main
+//! window and watches for changes; each change, and each change of the setting
+//! itself, is announced to the frontend as a `theme-changed` event carrying a
+//! [`ThemeInfo`].
+
+use serde::{Deserialize, Serialize};
+
+/// Setting value that follows the operating system ("auto" is accepted as well)
+pub const SYSTEM: &str = "system";
+
+/// Theme used when the setting is unknown or the system theme cannot be read
+const FALLBACK: &str = "dark";
+
+/// The theme setting and what it resolves to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThemeInfo {
+    /// `ui.theme` as configured
+    pub setting: String,
+
+    /// Operating system theme ("light" or "dark"), if it could be read
+    pub system: Option<String>,
+
+    /// Theme to show: "light" or "dark"
+    pub effective: String,
+}
+
+impl ThemeInfo {
+    /// Resolve `setting` against the operating system theme
+    pub fn new(setting: &str, system: Option<&str>) -> Self {
+        let effective = match setting.to_lowercase().as_str() {
+            "light" => "light",
+            "dark" => "dark",
+            "system" | "auto" => system.unwrap_or(FALLBACK),
+            _ => FALLBACK,
+        };
+
+        Self {
+            setting: setting.to_string(),
+            system: system.map(str::to_string),
+            effective: effective.to_string(),
+        }
+    }
+
+    /// Whether the theme shown depends on the operating system
+    pub fn follows_system(&self) -> bool {
+        self.setting.eq_ignore_ascii_case(SYSTEM) || self.setting.eq_ignore_ascii_case("auto")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_system_setting_follows_os() {
+        let info = ThemeInfo::new("system", Some("light"));
+        assert!(info.follows_system());
+        assert_eq!(info.effective, "light");
+        assert_eq!(ThemeInfo::new("auto", Some("dark")).effective, "dark");
+
+        // Unreadable system theme falls back to dark
+        assert_eq!(ThemeInfo::new("system", None).effective, "dark");
+    }
+
+    #[test]
+    fn test_explicit_setting_ignores_os() {
+        let info = ThemeInfo::new("Light", Some("dark"));
+        assert!(!info.follows_system());
+        assert_eq!(info.effective, "light");
+        assert_eq!(ThemeInfo::new("sepia", Some("light")).effective, "dark");
+    }
+}
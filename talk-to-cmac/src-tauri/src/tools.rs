@@ -0,0 +1,259 @@
+//! Data lookups for the LLM
+//!
+//! The LLM providers have no tool calls, so tools run before a query is sent:
+//! when the query mentions a configured trigger phrase ("service history for"),
+//! the matching source is searched and its results are added below the user's
+//! message for the LLM to answer from. Results are only sent with that one
+//! request; the stored conversation keeps the message as the user said it.
+//!
+//! Questions about the schedule ("what's on my calendar") get the calendar's
+//! events for the next `tools.calendar.days_ahead` days the same way, and
+//! questions about the inbox get its newest unread messages. A request to find
+//! a document ("find the spec sheet for ...") searches the local folders in
+//! `tools.files`. With `tools.ocr` enabled, the text in images sent with a
+//! message is recognized and sent along, for models that can't read images.
+//!
+//! A source that fails is skipped with a warning, so the query still goes out.
+//! The `tools` feature flag turns every source off at once.
+
+use crate::api::lookup::LookupClient;
+use crate::api::ImageAttachment;
+use crate::calendar;
+use crate::config::{AppConfig, ConfigManager, LookupConfig};
+use crate::email;
+use crate::feature_flags;
+use crate::files;
+use crate::error::AppResult;
+use crate::ocr;
+use crate::redaction;
+use crate::usage;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+
+/// A lookup picked for a query, with its search term
+#[derive(Debug, Clone)]
+pub struct LookupMatch<'a> {
+    pub lookup: &'a LookupConfig,
+    pub term: String,
+}
+
+/// Leading words dropped from a search term
+const ARTICLES: &[&str] = &["the", "a", "an", "our", "my"];
+
+/// Find the lookups whose trigger phrase appears in `query`, each with the words after it as its search term
+///
+/// Lookups without a term left after dropping ignored words are skipped.
+pub fn match_lookups<'a>(lookups: &'a [LookupConfig], query: &str) -> Vec<LookupMatch<'a>> {
+    let words = words(query);
+    lookups
+        .iter()
+        .filter_map(|lookup| {
+            let term = term_after(&words, &lookup.triggers, &lookup.ignore_words)?;
+            Some(LookupMatch { lookup, term })
+        })
+        .collect()
+}
+
+/// Whether `query` mentions one of the `triggers` phrases
+pub fn mentions(triggers: &[String], query: &str) -> bool {
+    let words = words(query);
+    triggers.iter().any(|trigger| find_phrase(&words, trigger).is_some())
+}
+
+/// The words after the first of `triggers` found in `words`, minus `ignore_words` and leading articles
+fn term_after(words: &[String], triggers: &[String], ignore_words: &[String]) -> Option<String> {
+    let rest = triggers
+        .iter()
+        .find_map(|trigger| find_phrase(words, trigger).map(|end| &words[end..]))?;
+
+    let ignored: Vec<String> = ignore_words.iter().map(|word| normalize(word)).collect();
+    let mut rest = rest.iter().filter(|word| !ignored.contains(*word)).peekable();
+    while rest.next_if(|word| ARTICLES.contains(&word.as_str())).is_some() {}
+    let term = rest.map(String::as_str).collect::<Vec<_>>().join(" ");
+
+    (!term.is_empty()).then_some(term)
+}
+
+fn words(query: &str) -> Vec<String> {
+    query.split_whitespace().map(normalize).filter(|word| !word.is_empty()).collect()
+}
+
+/// Index of the word after the first occurrence of `phrase` in `words`
+fn find_phrase(words: &[String], phrase: &str) -> Option<usize> {
+    let phrase: Vec<String> = phrase.split_whitespace().map(normalize).collect();
+    if phrase.is_empty() || phrase.len() > words.len() {
+        return None;
+    }
+    (0..=words.len() - phrase.len())
+        .find(|&start| words[start..start + phrase.len()] == phrase[..])
+        .map(|start| start + phrase.len())
+}
+
+/// Add results of the sources `query` asks for, and the text of `images`, to the latest user message in `messages`
+pub async fn augment(config: &AppConfig, query: &str, images: &[ImageAttachment], messages: &mut [(String, String)]) {
+    if !feature_flags::enabled(feature_flags::TOOLS) {
+        return;
+    }
+
+    let mut sections = Vec::new();
+    for matched in match_lookups(&config.tools.lookups, query) {
+        match run_lookup(&matched).await {
+            Ok(results) => {
+                tracing::info!("Lookup '{}' returned results", matched.lookup.name);
+                sections.push(format!(
+                    "Results of the {} lookup for \"{}\" (JSON):\n{}",
+                    matched.lookup.name, matched.term, results
+                ));
+            }
+            Err(e) => tracing::warn!("Lookup '{}' failed: {}", matched.lookup.name, e),
+        }
+    }
+
+    if config.tools.calendar.enabled && mentions(&config.tools.calendar.triggers, query) {
+        let days = config.tools.calendar.days_ahead;
+        let from = chrono::Utc::now().timestamp();
+        match calendar::upcoming(&config.tools.calendar, from, from + i64::from(days) * 24 * 60 * 60).await {
+            Ok(events) => {
+                tracing::info!("Calendar returned {} events", events.len());
+                sections.push(format!(
+                    "Calendar events for the next {} days (local time):\n{}",
+                    days,
+                    calendar::describe(&events)
+                ));
+            }
+            Err(e) => tracing::warn!("Calendar read failed: {}", e),
+        }
+    }
+
+    if config.tools.email.enabled && mentions(&config.tools.email.triggers, query) {
+        match email::unread(&config.tools.email).await {
+            Ok(messages) => {
+                tracing::info!("Mailbox returned {} unread messages", messages.len());
+                sections.push(format!(
+                    "Newest unread emails in {} (newest first):\n{}",
+                    config.tools.email.mailbox,
+                    email::describe(&messages)
+                ));
+            }
+            Err(e) => tracing::warn!("Mailbox read failed: {}", e),
+        }
+    }
+
+    let search = &config.tools.files;
+    if let Some(term) = term_after(&words(query), &search.triggers, &[]).filter(|_| search.enabled) {
+        let (search, search_term) = (search.clone(), term.clone());
+        match tokio::task::spawn_blocking(move || files::search(&search, &search_term)).await {
+            Ok(matches) => {
+                tracing::info!("File search returned {} matches", matches.len());
+                sections.push(format!("Local files matching \"{}\":\n{}", term, files::describe(&matches)));
+            }
+            Err(e) => tracing::warn!("File search failed: {}", e),
+        }
+    }
+
+    if config.tools.ocr.enabled {
+        for (index, image) in images.iter().enumerate() {
+            let data = match BASE64.decode(&image.data) {
+                Ok(data) => data,
+                Err(e) => {
+                    tracing::warn!("Attached image {} is not valid base64: {}", index + 1, e);
+                    continue;
+                }
+            };
+            match ocr::recognize(&config.tools.ocr, &data).await {
+                Ok(text) if text.is_empty() => {}
+                Ok(text) => sections.push(format!("Text recognized in attached image {}:\n{}", index + 1, text)),
+                Err(e) => tracing::warn!("Text recognition failed: {}", e),
+            }
+        }
+    }
+
+    if sections.is_empty() {
+        return;
+    }
+    let Some((_, content)) = messages.iter_mut().rev().find(|(role, _)| role == "user") else {
+        return;
+    };
+
+    usage::record_feature("tool_results");
+    let results = truncate(&sections.join("\n\n"), config.tools.max_result_chars);
+    let results = redaction::apply(&config.privacy.redaction, "tool", results);
+    content.push_str("\n\n---\nAnswer using this data where it is relevant:\n\n");
+    content.push_str(&results);
+}
+
+async fn run_lookup(matched: &LookupMatch<'_>) -> AppResult<String> {
+    let token = match matched.lookup.secret.as_str() {
+        "" => None,
+        name => ConfigManager::new()?.get_secret(name)?,
+    };
+    let results = LookupClient::new(matched.lookup.clone(), token)?.search(&matched.term).await?;
+    Ok(results.to_string())
+}
+
+/// First `max_chars` characters of `text`, marking a cut
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}\n[truncated]", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+fn normalize(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric() || *c == '-')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str, triggers: &[&str]) -> LookupConfig {
+        LookupConfig {
+            name: name.to_string(),
+            triggers: triggers.iter().map(|t| t.to_string()).collect(),
+            ignore_words: vec!["account".to_string()],
+            ..LookupConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_term_follows_the_trigger() {
+        let lookups = [lookup("CRM", &["service history for"]), lookup("Assets", &["serial number of"])];
+
+        let matches = match_lookups(&lookups, "What's the service history for the Johnson account?");
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].lookup.name, "CRM");
+        assert_eq!(matches[0].term, "johnson");
+
+        assert!(match_lookups(&lookups, "Tell me about service histories").is_empty());
+        assert!(match_lookups(&lookups, "Show the service history for the account").is_empty());
+    }
+
+    #[test]
+    fn test_mentions_trigger_phrases() {
+        let triggers = crate::config::CalendarConfig::default().triggers;
+        assert!(mentions(&triggers, "What's on my calendar tomorrow?"));
+        assert!(!mentions(&triggers, "Add it to the calendar"));
+
+        let triggers = crate::config::EmailConfig::default().triggers;
+        assert!(mentions(&triggers, "Anything important in my inbox?"));
+    }
+
+    #[test]
+    fn test_file_search_term() {
+        let triggers = crate::config::FileSearchConfig::default().triggers;
+        assert_eq!(
+            term_after(&words("Find the spec sheet for the model 4500."), &triggers, &[]).as_deref(),
+            Some("spec sheet for the model 4500")
+        );
+    }
+
+    #[test]
+    fn test_truncate_marks_the_cut() {
+        assert_eq!(truncate("abcdef", 3), "abc\n[truncated]");
+        assert_eq!(truncate("abc", 3), "abc");
+    }
+}
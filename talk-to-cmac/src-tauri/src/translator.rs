@@ -0,0 +1,132 @@
+//! Translator mode
+//!
+//! Turns the assistant into a two-way interpreter: each utterance is transcribed
+//! in the current speaker's language, translated by the LLM, and spoken in the
+//! other language. Turns alternate between the two configured languages.
+
+use crate::config::TranslatorConfig;
+use serde::{Deserialize, Serialize};
+
+/// Which speaker the next utterance is expected from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TranslationDirection {
+    /// Language A is spoken and translated into language B
+    AToB,
+
+    /// Language B is spoken and translated into language A
+    BToA,
+}
+
+impl TranslationDirection {
+    /// Direction of the following turn
+    pub fn flip(self) -> Self {
+        match self {
+            Self::AToB => Self::BToA,
+            Self::BToA => Self::AToB,
+        }
+    }
+}
+
+/// Languages and voice used for a single translated turn
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranslationTurn {
+    pub direction: TranslationDirection,
+
+    /// Language code passed to Whisper
+    pub source_language: String,
+
+    /// Language code the translation is spoken in
+    pub target_language: String,
+
+    /// ElevenLabs voice override for the target language
+    #[serde(skip)]
+    pub voice_id: Option<String>,
+}
+
+impl TranslationTurn {
+    /// Resolve the languages and voice for `direction`
+    pub fn new(config: &TranslatorConfig, direction: TranslationDirection) -> Self {
+        let (source, target, voice) = match direction {
+            TranslationDirection::AToB => (&config.language_a, &config.language_b, &config.voice_b),
+            TranslationDirection::BToA => (&config.language_b, &config.language_a, &config.voice_a),
+        };
+
+        Self {
+            direction,
+            source_language: source.clone(),
+            target_language: target.clone(),
+            voice_id: voice.clone(),
+        }
+    }
+
+    /// System prompt asking the LLM for a faithful translation and nothing else
+    pub fn instructions(&self) -> String {
+        format!(
+            "You are an interpreter. Translate the user's message from {} into {}. \
+             Preserve the meaning and tone, and reply with only the translation.",
+            language_name(&self.source_language),
+            language_name(&self.target_language)
+        )
+    }
+}
+
+/// English name of common ISO 639-1 codes, falling back to the code itself
+pub fn language_name(code: &str) -> &str {
+    match code {
+        "ar" => "Arabic",
+        "de" => "German",
+        "en" => "English",
+        "es" => "Spanish",
+        "fr" => "French",
+        "hi" => "Hindi",
+        "it" => "Italian",
+        "ja" => "Japanese",
+        "ko" => "Korean",
+        "nl" => "Dutch",
+        "pl" => "Polish",
+        "pt" => "Portuguese",
+        "ru" => "Russian",
+        "tr" => "Turkish",
+        "uk" => "Ukrainian",
+        "vi" => "Vietnamese",
+        "zh" => "Chinese",
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> TranslatorConfig {
+        TranslatorConfig {
+            voice_a: Some("voice-en".to_string()),
+            voice_b: Some("voice-es".to_string()),
+            ..TranslatorConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_turns_alternate_languages_and_voices() {
+        let first = TranslationTurn::new(&config(), TranslationDirection::AToB);
+        assert_eq!((first.source_language.as_str(), first.target_language.as_str()), ("en", "es"));
+        assert_eq!(first.voice_id.as_deref(), Some("voice-es"));
+
+        let second = TranslationTurn::new(&config(), first.direction.flip());
+        assert_eq!((second.source_language.as_str(), second.target_language.as_str()), ("es", "en"));
+        assert_eq!(second.voice_id.as_deref(), Some("voice-en"));
+    }
+
+    #[test]
+    fn test_instructions_name_languages() {
+        let turn = TranslationTurn::new(
+            &TranslatorConfig {
+                language_b: "sw".to_string(),
+                ..TranslatorConfig::default()
+            },
+            TranslationDirection::AToB,
+        );
+        assert!(turn.instructions().contains("from English into sw"));
+    }
+}
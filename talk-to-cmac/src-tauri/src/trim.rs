@@ -0,0 +1,111 @@
+//! Silence trimming
+//!
+//! A recording often starts with the user pausing before they speak and ends
+//! with the silence that stopped it. Cutting both before upload makes the file
+//! smaller and the Whisper bill lower. A 20 ms frame counts as sound when its
+//! RMS level reaches `audio.silence_threshold`; a little silence is kept on
+//! either side so the first and last words are not clipped.
+
+use crate::config::AudioConfig;
+use crate::error::AppResult;
+use crate::wav;
+
+/// Silence kept before the first and after the last sound (seconds)
+const PADDING: f32 = 0.25;
+
+/// `wav` without its leading and trailing silence
+///
+/// A recording with no sound at all is returned as it is. Fails unless `wav`
+/// is a 16-bit PCM WAV file.
+pub fn trim(wav: &[u8], threshold: f32) -> AppResult<Vec<u8>> {
+    let (channels, sample_rate) = wav::decode_channels(wav)?;
+    let mixed = wav::mix(&channels);
+
+    let Some(range) = sound_range(&mixed, sample_rate, threshold) else {
+        tracing::debug!("No sound above the silence threshold, not trimming the recording");
+        return Ok(wav.to_vec());
+    };
+    if range.len() == mixed.len() {
+        return Ok(wav.to_vec());
+    }
+
+    tracing::debug!(
+        "Trimmed the recording from {:.1}s to {:.1}s",
+        mixed.len() as f32 / sample_rate as f32,
+        range.len() as f32 / sample_rate as f32
+    );
+    let trimmed: Vec<&[f32]> = channels.iter().map(|channel| &channel[range.clone()]).collect();
+    Ok(wav::encode_channels(&trimmed, sample_rate))
+}
+
+/// `wav` trimmed if `audio.auto_trim` is on; anything that cannot be trimmed is returned as it is
+pub fn apply(config: &AudioConfig, wav: Vec<u8>) -> Vec<u8> {
+    if !config.auto_trim {
+        return wav;
+    }
+    match trim(&wav, config.silence_threshold) {
+        Ok(trimmed) => trimmed,
+        Err(e) => {
+            tracing::debug!("Not trimming the recording: {}", e);
+            wav
+        }
+    }
+}
+
+/// Samples from the first to the last frame with sound, padded; `None` if every frame is silent
+fn sound_range(samples: &[f32], sample_rate: u32, threshold: f32) -> Option<std::ops::Range<usize>> {
+    let frame_len = (sample_rate as usize / 50).max(1);
+    let loud = |frame: &[f32]| {
+        let power = frame.iter().map(|sample| sample * sample).sum::<f32>() / frame.len() as f32;
+        power.sqrt() >= threshold
+    };
+
+    let first = samples.chunks(frame_len).position(loud)?;
+    let last = samples.chunks(frame_len).rposition(loud)?;
+    let padding = (PADDING * sample_rate as f32) as usize;
+    let start = (first * frame_len).saturating_sub(padding);
+    let end = ((last + 1) * frame_len + padding).min(samples.len());
+    Some(start..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `before` seconds of silence, one second of tone, then `after` seconds of silence
+    fn recording(before: f32, after: f32) -> Vec<f32> {
+        let silence = |seconds: f32| vec![0.0; (16_000.0 * seconds) as usize];
+        let mut samples = silence(before);
+        samples.extend((0..16_000).map(|i| 0.3 * (i as f32 * 0.1).sin()));
+        samples.extend(silence(after));
+        samples
+    }
+
+    #[test]
+    fn test_silence_is_trimmed_with_padding() {
+        let samples = recording(2.0, 1.0);
+        let range = sound_range(&samples, 16_000, 0.01).unwrap();
+        assert_eq!(range, 28_000..52_000);
+
+        let trimmed = trim(&wav::encode(&samples, 16_000), 0.01).unwrap();
+        assert_eq!(wav::decode(&trimmed).unwrap().0.len(), 24_000);
+    }
+
+    #[test]
+    fn test_nothing_to_trim() {
+        let silent = wav::encode(&[0.0; 16_000], 16_000);
+        assert_eq!(trim(&silent, 0.01).unwrap(), silent);
+        let speech = wav::encode(&recording(0.0, 0.0), 16_000);
+        assert_eq!(trim(&speech, 0.01).unwrap(), speech);
+
+        assert!(trim(b"not audio", 0.01).is_err());
+        let config = AudioConfig {
+            auto_trim: true,
+            ..AudioConfig::default()
+        };
+        assert_eq!(apply(&config, b"not audio".to_vec()), b"not audio");
+        let padded = wav::encode(&recording(1.0, 1.0), 16_000);
+        assert_ne!(apply(&config, padded.clone()), padded);
+        assert_eq!(apply(&AudioConfig::default(), padded.clone()), padded);
+    }
+}
@@ -0,0 +1,349 @@
+//! Anonymous usage reports
+//!
+//! Counts how often features are used and which kinds of errors occur, and,
+//! during a prompt experiment, how each variant fares. A report holds only
+//! those counts, the app version, the operating system, and the days
+//! it covers: never transcripts, replies, settings, or anything identifying.
+//!
+//! Counting happens in memory, so `view_telemetry_payload` can show what would be
+//! sent before the user opts in. Reports are only written to the on-disk queue
+//! and posted when `usage_reports.enabled` is on, and reports that cannot be
+//! sent stay queued until the endpoint is reachable again.
+
+use crate::api::http;
+use crate::config::{ConfigManager, UsageReportsConfig};
+use crate::error::{AppError, AppResult, ConfigError, NetworkError};
+use crate::state::AppState;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+
+/// How long counts accumulate before they are queued as a report
+const REPORT_PERIOD_SECS: u64 = 24 * 60 * 60;
+
+/// How often the counts and the queue are checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Reports kept while the endpoint is unreachable; older ones are dropped
+const MAX_QUEUED_REPORTS: usize = 30;
+
+/// Timeout for posting the queued reports
+const SEND_TIMEOUT: Duration = Duration::from_secs(15);
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Counts since the last report
+static COUNTS: Mutex<Counts> = Mutex::new(Counts::new());
+
+/// One anonymous usage report, exactly as it is sent
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageReport {
+    /// App version
+    pub app_version: String,
+
+    /// Operating system family ("windows", "macos", or "linux")
+    pub os: String,
+
+    /// First day covered (Unix timestamp of midnight UTC)
+    pub period_start: u64,
+
+    /// Last day covered (Unix timestamp of midnight UTC)
+    pub period_end: u64,
+
+    /// Times each feature was used
+    pub features: BTreeMap<String, u64>,
+
+    /// Occurrences of each error category (e.g. "whisper/Timeout")
+    pub errors: BTreeMap<String, u64>,
+
+    /// Counts per prompt experiment variant ("experiment/variant"), e.g. of replies and errors
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variants: BTreeMap<String, BTreeMap<String, u64>>,
+}
+
+/// Everything usage reporting would send
+#[derive(Debug, Clone, Serialize)]
+pub struct TelemetryPayload {
+    /// Whether reports are queued and sent
+    pub enabled: bool,
+
+    /// Counts so far, queued as a report once the day is over
+    pub current: UsageReport,
+
+    /// Reports waiting to be sent, oldest first
+    pub queued: Vec<UsageReport>,
+}
+
+#[derive(Debug)]
+struct Counts {
+    /// Start of the counting period (Unix timestamp; 0 before anything is counted)
+    since: u64,
+    features: BTreeMap<String, u64>,
+    errors: BTreeMap<String, u64>,
+    variants: BTreeMap<String, BTreeMap<String, u64>>,
+}
+
+impl Counts {
+    const fn new() -> Self {
+        Self {
+            since: 0,
+            features: BTreeMap::new(),
+            errors: BTreeMap::new(),
+            variants: BTreeMap::new(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.features.is_empty() && self.errors.is_empty() && self.variants.is_empty()
+    }
+
+    fn start(&mut self, now: u64) {
+        if self.since == 0 {
+            self.since = now;
+        }
+    }
+
+    /// Report of the counts, covering the period up to `now`
+    fn report(&self, now: u64) -> UsageReport {
+        let start = if self.since == 0 { now } else { self.since };
+        UsageReport {
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            os: std::env::consts::OS.to_string(),
+            period_start: start - start % SECS_PER_DAY,
+            period_end: now - now % SECS_PER_DAY,
+            features: self.features.clone(),
+            errors: self.errors.clone(),
+            variants: self.variants.clone(),
+        }
+    }
+
+    /// Take the counts as a report once the period is over, starting a new one
+    fn take_due(&mut self, now: u64) -> Option<UsageReport> {
+        if self.is_empty() || now.saturating_sub(self.since) < REPORT_PERIOD_SECS {
+            return None;
+        }
+        let report = self.report(now);
+        *self = Counts::new();
+        Some(report)
+    }
+}
+
+/// Count one use of a feature
+pub fn record_feature(feature: &str) {
+    let mut counts = COUNTS.lock().unwrap();
+    counts.start(now());
+    *counts.features.entry(feature.to_string()).or_default() += 1;
+}
+
+/// Count one error by its category
+pub fn record_error(error: &AppError) {
+    let mut counts = COUNTS.lock().unwrap();
+    counts.start(now());
+    *counts.errors.entry(error.category()).or_default() += 1;
+}
+
+/// Count one event (e.g. "replies") of a prompt experiment variant
+pub fn record_variant(variant: &str, event: &str) {
+    let mut counts = COUNTS.lock().unwrap();
+    counts.start(now());
+    *counts.variants.entry(variant.to_string()).or_default().entry(event.to_string()).or_default() += 1;
+}
+
+/// Forget the counts since the last report without queueing them
+pub fn clear_counts() {
+    *COUNTS.lock().unwrap() = Counts::new();
+}
+
+/// Current counts and queued reports, as they would be sent
+pub fn payload(config: &UsageReportsConfig) -> AppResult<TelemetryPayload> {
+    Ok(TelemetryPayload {
+        enabled: config.enabled,
+        current: COUNTS.lock().unwrap().report(now()),
+        queued: UsageQueue::open_default()?.load()?,
+    })
+}
+
+/// Queue the counts so far, so they are not lost when the app exits
+pub fn queue_pending(config: &UsageReportsConfig) -> AppResult<()> {
+    if !config.enabled {
+        return Ok(());
+    }
+
+    let mut counts = COUNTS.lock().unwrap();
+    if counts.is_empty() {
+        return Ok(());
+    }
+    let report = counts.report(now());
+    *counts = Counts::new();
+    drop(counts);
+
+    UsageQueue::open_default()?.push(report)
+}
+
+/// Spawn the background task that queues a report daily and sends the queue
+pub fn spawn_reporting_task(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let config = app.state::<AppState>().get_config().usage_reports;
+            if let Err(e) = report(&config).await {
+                tracing::debug!("Usage reports not sent: {}", e);
+            }
+
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+/// Queue the counts if the period is over, then post every queued report
+async fn report(config: &UsageReportsConfig) -> AppResult<()> {
+    let queue = UsageQueue::open_default()?;
+
+    // Opting out also discards reports queued earlier
+    if !config.enabled {
+        return queue.clear();
+    }
+
+    let due = COUNTS.lock().unwrap().take_due(now());
+    if let Some(report) = due {
+        queue.push(report)?;
+    }
+
+    let reports = queue.load()?;
+    if reports.is_empty() || config.endpoint.trim().is_empty() {
+        return Ok(());
+    }
+
+    send(&config.endpoint, &reports).await?;
+    tracing::info!("Sent {} usage report(s)", reports.len());
+    queue.clear()
+}
+
+async fn send(endpoint: &str, reports: &[UsageReport]) -> AppResult<()> {
+    let request = http::client(SEND_TIMEOUT)
+        .map_err(NetworkError::from)?
+        .post(endpoint)
+        .json(&serde_json::json!({ "reports": reports }));
+
+    let response = http::send("usage_reports", request)
+        .await
+        .map_err(NetworkError::from)?;
+
+    if !response.status().is_success() {
+        return Err(NetworkError::RequestFailed(format!("HTTP {}", response.status())).into());
+    }
+    Ok(())
+}
+
+/// Reports waiting to be sent, stored as a JSON array
+pub struct UsageQueue {
+    path: PathBuf,
+}
+
+impl UsageQueue {
+    /// Queue stored in the given file
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Queue under the app data directory
+    pub fn open_default() -> AppResult<Self> {
+        Ok(Self::new(ConfigManager::new()?.data_dir().join("usage_queue.json")))
+    }
+
+    /// Queued reports, oldest first
+    pub fn load(&self) -> AppResult<Vec<UsageReport>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let contents = fs::read_to_string(&self.path).map_err(|e| ConfigError::LoadFailed(e.to_string()))?;
+        Ok(serde_json::from_str(&contents).map_err(ConfigError::from)?)
+    }
+
+    /// Add a report, dropping the oldest ones beyond the limit
+    pub fn push(&self, report: UsageReport) -> AppResult<()> {
+        let mut reports = self.load().unwrap_or_default();
+        reports.push(report);
+        let excess = reports.len().saturating_sub(MAX_QUEUED_REPORTS);
+        reports.drain(..excess);
+
+        let contents = serde_json::to_string(&reports).map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
+        fs::write(&self.path, contents).map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
+        Ok(())
+    }
+
+    /// Delete every queued report
+    pub fn clear(&self) -> AppResult<()> {
+        if self.path.exists() {
+            fs::remove_file(&self.path).map_err(|e| ConfigError::SaveFailed(e.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::WhisperError;
+
+    fn report(feature: &str) -> UsageReport {
+        let mut counts = Counts::new();
+        counts.start(1);
+        counts.features.insert(feature.to_string(), 1);
+        counts.report(1)
+    }
+
+    #[test]
+    fn test_reports_are_due_after_a_day() {
+        let mut counts = Counts::new();
+        assert_eq!(counts.take_due(REPORT_PERIOD_SECS * 5), None);
+
+        let start = 20_000 * SECS_PER_DAY + 3600;
+        counts.start(start);
+        *counts.features.entry("voice_query".to_string()).or_default() += 2;
+        assert_eq!(counts.take_due(start + 60), None);
+
+        let report = counts.take_due(start + REPORT_PERIOD_SECS).expect("a report is due");
+        assert_eq!(report.features.get("voice_query"), Some(&2));
+        assert_eq!(report.period_start, 20_000 * SECS_PER_DAY);
+        assert_eq!(report.period_end, 20_001 * SECS_PER_DAY);
+        assert!(counts.is_empty());
+        assert_eq!(counts.since, 0);
+    }
+
+    #[test]
+    fn test_reports_hold_only_categories() {
+        let mut counts = Counts::new();
+        let error = AppError::from(WhisperError::TranscriptionFailed("call me at 555-0100".to_string()));
+        *counts.errors.entry(error.category()).or_default() += 1;
+
+        let json = serde_json::to_string(&counts.report(now())).unwrap();
+        assert!(json.contains("whisper/TranscriptionFailed"));
+        assert!(!json.contains("555"));
+    }
+
+    #[test]
+    fn test_queue_keeps_newest_reports() {
+        let path = std::env::temp_dir().join(format!("talk-to-cmac-usage-{:x}.json", rand::random::<u64>()));
+        let queue = UsageQueue::new(&path);
+        assert!(queue.load().unwrap().is_empty());
+
+        for i in 0..MAX_QUEUED_REPORTS + 2 {
+            queue.push(report(&format!("feature_{}", i))).unwrap();
+        }
+        let reports = queue.load().unwrap();
+        assert_eq!(reports.len(), MAX_QUEUED_REPORTS);
+        assert_eq!(reports[0], report("feature_2"));
+
+        queue.clear().unwrap();
+        assert!(!path.exists());
+        queue.clear().unwrap();
+    }
+}
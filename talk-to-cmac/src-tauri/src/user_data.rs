@@ -0,0 +1,183 @@
+//! Exporting and erasing the user's data
+//!
+//! `export_all_user_data` packs everything the app keeps about its user into
+//! one zip file: every stored conversation (active and archived), the settings,
+//! and the usage counts. API keys are never exported; the settings do not hold
+//! them.
+//!
+//! Erasing cannot be undone, so it takes two steps: the app asks for a
+//! confirmation token, shows it to the user, and passes it back to
+//! `erase_all_user_data`. A token is valid for five minutes and only once.
+
+use crate::config::AppConfig;
+use crate::error::{AppResult, StorageError};
+use crate::history::HistoryStore;
+use crate::state::ConversationContext;
+use crate::usage::TelemetryPayload;
+use serde::Serialize;
+use std::io::{Cursor, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// How long an erase confirmation token stays valid
+const TOKEN_LIFETIME: Duration = Duration::from_secs(5 * 60);
+
+/// The erase confirmation token last handed out, and when
+static PENDING_TOKEN: Mutex<Option<(String, Instant)>> = Mutex::new(None);
+
+/// Stored conversations as they appear in `conversations.json`
+#[derive(Debug, Serialize)]
+struct Conversations {
+    active: Vec<ConversationContext>,
+    archived: Vec<ConversationContext>,
+}
+
+/// Zip file with `conversations.json`, `settings.json`, and `usage.json`
+///
+/// Fails while the history is locked, so an export never silently leaves out
+/// the conversations.
+pub fn export(history: &HistoryStore, config: &AppConfig, usage: &TelemetryPayload) -> AppResult<Vec<u8>> {
+    let conversations = Conversations {
+        active: load_conversations(history, false)?,
+        archived: load_conversations(history, true)?,
+    };
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    add_json(&mut zip, "conversations.json", &conversations)?;
+    add_json(&mut zip, "settings.json", config)?;
+    add_json(&mut zip, "usage.json", usage)?;
+    let archive = zip.finish().map_err(|e| StorageError::ExportFailed(e.to_string()))?;
+
+    tracing::info!(
+        "Exported {} conversation(s)",
+        conversations.active.len() + conversations.archived.len()
+    );
+    Ok(archive.into_inner())
+}
+
+fn load_conversations(history: &HistoryStore, archived: bool) -> AppResult<Vec<ConversationContext>> {
+    let mut conversations = Vec::new();
+    for summary in history.list_conversations(archived)? {
+        if let Some(conversation) = history.get_conversation(&summary.id, usize::MAX)? {
+            conversations.push(conversation);
+        }
+    }
+    Ok(conversations)
+}
+
+fn add_json(zip: &mut ZipWriter<Cursor<Vec<u8>>>, name: &str, value: &impl Serialize) -> AppResult<()> {
+    let json = serde_json::to_vec_pretty(value).map_err(|e| StorageError::ExportFailed(e.to_string()))?;
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file(name, options)
+        .map_err(|e| StorageError::ExportFailed(e.to_string()))?;
+    zip.write_all(&json)
+        .map_err(|e| StorageError::ExportFailed(e.to_string()))?;
+    Ok(())
+}
+
+/// Hand out a new erase confirmation token, replacing any earlier one
+pub fn issue_erase_token() -> String {
+    let token = format!("{:08x}", rand::random::<u32>());
+    *PENDING_TOKEN.lock().unwrap() = Some((token.clone(), Instant::now()));
+    token
+}
+
+/// Use up the pending erase confirmation token; fails unless it is `token` and still valid
+pub fn redeem_erase_token(token: &str) -> AppResult<()> {
+    redeem(token, Instant::now())
+}
+
+fn redeem(token: &str, now: Instant) -> AppResult<()> {
+    let mut pending = PENDING_TOKEN.lock().unwrap();
+    match pending.as_ref() {
+        Some((expected, issued)) if now.duration_since(*issued) > TOKEN_LIFETIME => {
+            tracing::debug!("Erase confirmation token {} has expired", expected);
+            *pending = None;
+            Err(StorageError::InvalidConfirmation.into())
+        }
+        Some((expected, _)) if expected == token.trim() => {
+            *pending = None;
+            Ok(())
+        }
+        _ => Err(StorageError::InvalidConfirmation.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{ConversationSettings, Message, MessageRole};
+    use crate::usage::UsageReport;
+    use std::collections::BTreeMap;
+    use std::io::Read;
+
+    fn message(content: &str) -> Message {
+        Message {
+            id: String::new(),
+            role: MessageRole::User,
+            content: content.to_string(),
+            timestamp: 100,
+            stages: None,
+            variant: None,
+            rating: None,
+        }
+    }
+
+    #[test]
+    fn test_export_contains_conversations_settings_and_usage() {
+        let history = HistoryStore::open_in_memory().unwrap();
+        let conversation = ConversationContext {
+            id: "abc".to_string(),
+            messages: Vec::new(),
+            max_messages: 20,
+            started_at: 100,
+            updated_at: 100,
+            ephemeral: false,
+            settings: ConversationSettings::default(),
+            variant: None,
+        };
+        history.append_message(&conversation, &message("Where is my parcel?")).unwrap();
+
+        let usage = TelemetryPayload {
+            enabled: false,
+            current: UsageReport {
+                app_version: "1.0.0".to_string(),
+                os: "linux".to_string(),
+                period_start: 0,
+                period_end: 0,
+                features: BTreeMap::from([("voice_query".to_string(), 3)]),
+                errors: BTreeMap::new(),
+                variants: BTreeMap::new(),
+            },
+            queued: Vec::new(),
+        };
+        let archive = export(&history, &AppConfig::default(), &usage).unwrap();
+
+        let mut zip = zip::ZipArchive::new(Cursor::new(archive)).unwrap();
+        let mut names: Vec<&str> = zip.file_names().collect();
+        names.sort_unstable();
+        assert_eq!(names, ["conversations.json", "settings.json", "usage.json"]);
+
+        let mut json = String::new();
+        zip.by_name("conversations.json").unwrap().read_to_string(&mut json).unwrap();
+        let conversations: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(conversations["active"][0]["messages"][0]["content"], "Where is my parcel?");
+        assert_eq!(conversations["archived"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_erase_token_is_single_use_and_expires() {
+        assert!(redeem_erase_token("").is_err());
+
+        let token = issue_erase_token();
+        assert!(redeem_erase_token("wrong").is_err());
+        assert!(redeem_erase_token(&token).is_ok());
+        assert!(redeem_erase_token(&token).is_err());
+
+        let token = issue_erase_token();
+        assert!(redeem(&token, Instant::now() + TOKEN_LIFETIME + Duration::from_secs(1)).is_err());
+        assert!(redeem_erase_token(&token).is_err());
+    }
+}
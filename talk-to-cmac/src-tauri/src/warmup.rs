@@ -0,0 +1,175 @@
+//! Startup warm-up
+//!
+//! Opens connections to the configured speech-to-text, LLM, and text-to-speech
+//! services at launch. The connections stay in the shared HTTP pool, so the
+//! first voice query skips connection setup. A local LLM is also sent a
+//! one-token request so its model is loaded before it is needed, and an
+//! OpenWebUI model can be kept loaded with periodic requests while idle.
+
+use crate::api::{llm_provider, stt_provider, tts_provider, GenerationOverrides};
+use crate::bandwidth;
+use crate::config::{ApiKeys, AppConfig, LlmProviderKind};
+use crate::error::AppResult;
+use crate::state::{AppState, AppStatus};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+/// How often a disabled keep-alive checks whether it has been turned on
+const KEEP_ALIVE_IDLE_CHECK: Duration = Duration::from_secs(60);
+
+/// Spawn the warm-up in the background if it is enabled, after probing an
+/// OpenWebUI endpoint whose API flavor is not known yet
+pub fn spawn_warmup_task(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        let config = state.get_config();
+        if config.providers.llm == LlmProviderKind::OpenWebUi && config.openwebui.api_flavor.is_none() {
+            if let Err(e) = crate::commands::probe_llm_api_and_apply(&app, &state).await {
+                tracing::warn!("Failed to save the probed LLM API flavor: {}", e);
+            }
+        }
+
+        let config = state.get_config();
+        if config.warmup.enabled {
+            warm_up(&config, &state.get_api_keys()).await;
+        }
+    });
+}
+
+/// Connect to every configured service, and prime a local LLM
+pub async fn warm_up(config: &AppConfig, api_keys: &ApiKeys) {
+    let started = Instant::now();
+
+    let stt = async { stt_provider(config, api_keys)?.check_connectivity().await };
+    let llm = async {
+        if config.warmup.prime_local_llm && is_local_llm(config) {
+            prime_llm(config, api_keys).await
+        } else {
+            llm_provider(config, api_keys)?.check_connectivity().await
+        }
+    };
+    let tts = async { tts_provider(config, api_keys)?.check_connectivity().await };
+
+    let (stt, llm, tts) = tokio::join!(stt, llm, tts);
+    log_result("speech-to-text", stt);
+    log_result("LLM", llm);
+    log_result("text-to-speech", tts);
+
+    tracing::info!("Warm-up finished in {} ms", started.elapsed().as_millis());
+}
+
+/// Spawn the task that keeps an OpenWebUI model loaded between queries
+///
+/// The interval is re-read from the configuration on every cycle, so the
+/// keep-alive can be turned on or off without restarting. No request is sent
+/// while a query is in progress, since the query itself keeps the model loaded.
+pub fn spawn_keep_alive_task(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let interval = bandwidth::apply(&app.state::<AppState>().get_config()).warmup.keep_alive_interval_secs;
+            if interval == 0 {
+                tokio::time::sleep(KEEP_ALIVE_IDLE_CHECK).await;
+                continue;
+            }
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+
+            let state = app.state::<AppState>();
+            let config = bandwidth::apply(&state.get_config());
+            if !keep_alive_due(&config, &state.get_status()) {
+                continue;
+            }
+
+            match prime_llm(&config, &state.get_api_keys()).await {
+                Ok(_) => tracing::debug!("Sent LLM keep-alive request"),
+                Err(e) => tracing::warn!("LLM keep-alive request failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Whether a keep-alive request should be sent now
+fn keep_alive_due(config: &AppConfig, status: &AppStatus) -> bool {
+    config.warmup.keep_alive_interval_secs > 0
+        && config.providers.llm == LlmProviderKind::OpenWebUi
+        && *status == AppStatus::Idle
+}
+
+/// Send a one-token request so the model is loaded (and stays loaded)
+async fn prime_llm(config: &AppConfig, api_keys: &ApiKeys) -> AppResult<bool> {
+    let overrides = GenerationOverrides {
+        max_tokens: Some(1),
+        ..Default::default()
+    };
+    llm_provider(config, api_keys)?
+        .send_message_with(vec![("user".to_string(), "Hi".to_string())], &overrides)
+        .await
+        .map(|_| true)
+}
+
+fn log_result(service: &str, result: AppResult<bool>) {
+    match result {
+        Ok(true) => tracing::debug!("Warmed up {} connection", service),
+        Ok(false) => tracing::warn!("Warm-up could not reach the {} service", service),
+        Err(e) => tracing::warn!("Warm-up of the {} service failed: {}", service, e),
+    }
+}
+
+/// Whether the configured LLM runs on this machine or the local network
+fn is_local_llm(config: &AppConfig) -> bool {
+    config.providers.llm == LlmProviderKind::OpenWebUi && is_local_endpoint(&config.openwebui.endpoint)
+}
+
+/// Whether an endpoint's host is loopback, a private address, or a `.local` name
+fn is_local_endpoint(endpoint: &str) -> bool {
+    let Some(host) = reqwest::Url::parse(endpoint).ok().and_then(|url| url.host_str().map(str::to_lowercase)) else {
+        return false;
+    };
+
+    match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => ip.is_loopback() || ip.is_private() || ip.is_link_local(),
+        Ok(IpAddr::V6(ip)) => ip.is_loopback(),
+        Err(_) => host == "localhost" || host.ends_with(".local") || host == "host.docker.internal",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_endpoints_are_detected() {
+        assert!(is_local_endpoint("http://localhost:3000/api/chat"));
+        assert!(is_local_endpoint("http://127.0.0.1:11434/v1"));
+        assert!(is_local_endpoint("http://192.168.1.20:3000/api/chat"));
+        assert!(is_local_endpoint("http://[::1]:3000/api/chat"));
+        assert!(is_local_endpoint("http://gpu-box.local/api/chat"));
+
+        assert!(!is_local_endpoint("https://openwebui.example.com/api/chat"));
+        assert!(!is_local_endpoint("https://8.8.8.8/api"));
+        assert!(!is_local_endpoint("not a url"));
+    }
+
+    #[test]
+    fn test_only_local_openwebui_is_primed() {
+        let mut config = AppConfig::default();
+        config.openwebui.endpoint = "http://localhost:3000/api/chat".to_string();
+        assert!(is_local_llm(&config));
+
+        config.providers.llm = LlmProviderKind::OpenRouter;
+        assert!(!is_local_llm(&config));
+    }
+
+    #[test]
+    fn test_keep_alive_only_pings_idle_openwebui() {
+        let mut config = AppConfig::default();
+        assert!(!keep_alive_due(&config, &AppStatus::Idle));
+
+        config.warmup.keep_alive_interval_secs = 240;
+        assert!(keep_alive_due(&config, &AppStatus::Idle));
+        assert!(!keep_alive_due(&config, &AppStatus::Thinking));
+
+        config.providers.llm = LlmProviderKind::Gemini;
+        assert!(!keep_alive_due(&config, &AppStatus::Idle));
+    }
+}
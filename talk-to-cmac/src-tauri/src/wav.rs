@@ -0,0 +1,139 @@
+//! 16-bit PCM WAV files
+//!
+//! The frontend records utterances as 16-bit PCM WAV, which speaker
+//! identification and gain control read, and test recordings are played back
+//! in the same format.
+
+use crate::error::{AppResult, AudioError};
+
+/// Mono samples (-1.0 to 1.0) and sample rate of a 16-bit PCM WAV file, its channels mixed
+pub fn decode(wav: &[u8]) -> AppResult<(Vec<f32>, u32)> {
+    let (channels, sample_rate) = decode_channels(wav)?;
+    Ok((mix(&channels), sample_rate))
+}
+
+/// Average of the `channels` (at least one), sample by sample
+pub fn mix(channels: &[Vec<f32>]) -> Vec<f32> {
+    let count = channels.len() as f32;
+    (0..channels[0].len())
+        .map(|i| channels.iter().map(|channel| channel[i]).sum::<f32>() / count)
+        .collect()
+}
+
+/// Samples (-1.0 to 1.0) of each channel and sample rate of a 16-bit PCM WAV file
+pub fn decode_channels(wav: &[u8]) -> AppResult<(Vec<Vec<f32>>, u32)> {
+    let invalid = |reason: &str| AudioError::InvalidFormat(reason.to_string());
+    if wav.len() < 12 || &wav[..4] != b"RIFF" || &wav[8..12] != b"WAVE" {
+        return Err(invalid("not a WAV file").into());
+    }
+
+    let mut format = None;
+    let mut offset = 12;
+    while offset + 8 <= wav.len() {
+        let id = &wav[offset..offset + 4];
+        let size = u32::from_le_bytes(wav[offset + 4..offset + 8].try_into().unwrap()) as usize;
+        let body = &wav[offset + 8..(offset + 8).saturating_add(size).min(wav.len())];
+
+        match id {
+            b"fmt " if body.len() >= 16 => {
+                let read_u16 = |at: usize| u16::from_le_bytes([body[at], body[at + 1]]);
+                let (encoding, channels, bits) = (read_u16(0), read_u16(2), read_u16(14));
+                if encoding != 1 || bits != 16 || channels == 0 {
+                    return Err(invalid("only 16-bit PCM is supported").into());
+                }
+                let sample_rate = u32::from_le_bytes(body[4..8].try_into().unwrap());
+                format = Some((usize::from(channels), sample_rate));
+            }
+            b"data" => {
+                let (channels, sample_rate) = format.ok_or_else(|| invalid("data before format"))?;
+                let mut samples = vec![Vec::with_capacity(body.len() / (2 * channels)); channels];
+                for frame in body.chunks_exact(2 * channels) {
+                    for (channel, sample) in samples.iter_mut().zip(frame.chunks_exact(2)) {
+                        channel.push(f32::from(i16::from_le_bytes([sample[0], sample[1]])) / f32::from(i16::MAX));
+                    }
+                }
+                return Ok((samples, sample_rate));
+            }
+            _ => {}
+        }
+        // A chunk claiming more bytes than the file holds ends the parse
+        match size.checked_add(8 + size % 2).and_then(|step| offset.checked_add(step)) {
+            Some(next) => offset = next,
+            None => break,
+        }
+    }
+    Err(invalid("no audio data").into())
+}
+
+/// `samples` (-1.0 to 1.0) as a 16-bit mono PCM WAV file
+pub fn encode(samples: &[f32], sample_rate: u32) -> Vec<u8> {
+    encode_channels(&[samples], sample_rate)
+}
+
+/// Samples (-1.0 to 1.0) of each of `channels` (of equal length) as a 16-bit PCM WAV file
+pub fn encode_channels(channels: &[&[f32]], sample_rate: u32) -> Vec<u8> {
+    let count = channels.len() as u16;
+    let frames = channels.first().map_or(0, |channel| channel.len());
+    let data_len = (frames * channels.len()) as u32 * 2;
+
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes()); // format chunk size
+    wav.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    wav.extend_from_slice(&count.to_le_bytes());
+    wav.extend_from_slice(&sample_rate.to_le_bytes());
+    wav.extend_from_slice(&(sample_rate * 2 * u32::from(count)).to_le_bytes()); // byte rate
+    wav.extend_from_slice(&(2 * count).to_le_bytes()); // block align
+    wav.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for i in 0..frames {
+        for channel in channels {
+            let sample = (channel[i].clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16;
+            wav.extend_from_slice(&sample.to_le_bytes());
+        }
+    }
+    wav
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_and_decode() {
+        let wav = encode(&[0.0, 1.0, -2.0], 48_000);
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(wav.len(), 44 + 3 * 2);
+        assert_eq!(u32::from_le_bytes(wav[24..28].try_into().unwrap()), 48_000);
+        assert_eq!(i16::from_le_bytes([wav[46], wav[47]]), i16::MAX);
+        assert_eq!(i16::from_le_bytes([wav[48], wav[49]]), -i16::MAX);
+
+
+        let (samples, sample_rate) = decode(&wav).unwrap();
+        assert_eq!(sample_rate, 48_000);
+        assert_eq!(samples, [0.0, 1.0, -1.0]);
+    }
+
+    #[test]
+    fn test_stereo_is_mixed() {
+        let wav = encode_channels(&[&[0.5], &[-0.5]], 16_000);
+        let (channels, _) = decode_channels(&wav).unwrap();
+        assert_eq!(channels.len(), 2);
+        assert!((channels[0][0] - 0.5).abs() < 1e-4 && (channels[1][0] + 0.5).abs() < 1e-4);
+        assert_eq!(decode(&wav).unwrap().0, [0.0]);
+    }
+
+    #[test]
+    fn test_invalid_files_are_rejected() {
+        assert!(decode(b"not a WAV file").is_err());
+        assert!(decode(&encode(&[], 16_000)[..36]).is_err());
+
+        // An oversized chunk before the audio is an error, not an overflow
+        let mut wav = encode(&[0.5], 16_000);
+        wav.splice(12..12, [b"JUNK".as_slice(), &u32::MAX.to_le_bytes()].concat());
+        assert!(decode(&wav).is_err());
+    }
+}
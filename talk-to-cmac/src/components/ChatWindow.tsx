@@ -4,8 +4,9 @@
  * Main chat interface container
  */
 
-import { memo, useCallback, useEffect } from 'react';
-import { useAppStore, selectMessages, selectStatus, selectError, selectConnectivity } from '../store/useAppStore';
+import { memo, useCallback, useEffect, useRef } from 'react';
+import { listen } from '@tauri-apps/api/event';
+import { useAppStore, selectMessages, selectStatus, selectError, selectConnectivity, selectConfig } from '../store/useAppStore';
 import { useTauri } from '../hooks/useTauri';
 import { useAudioRecorder } from '../hooks/useAudioRecorder';
 import { useAudioPlayer } from '../hooks/useAudioPlayer';
@@ -13,7 +14,7 @@ import { Header } from './Header';
 import { MessageList } from './MessageList';
 import { InputArea } from './InputArea';
 import { ErrorMessage } from './ErrorMessage';
-import type { Message } from '../types';
+import type { ClipboardResult, Message, SpeechChunk, SpokenSelection } from '../types';
 import './ChatWindow.css';
 
 export const ChatWindow = memo(function ChatWindow() {
@@ -22,12 +23,14 @@ export const ChatWindow = memo(function ChatWindow() {
   const status = useAppStore(selectStatus);
   const error = useAppStore(selectError);
   const connectivity = useAppStore(selectConnectivity);
-  const { addMessage, setError, clearError, setStatus, setSettingsOpen } = useAppStore();
+  const config = useAppStore(selectConfig);
+  const { addMessage, setMessages, setError, clearError, setStatus, setSettingsOpen } = useAppStore();
 
   // Tauri commands
   const {
     sendTextMessage,
     processVoiceQuery,
+    processTextQuery,
     clearConversation,
     checkConnectivity,
   } = useTauri();
@@ -38,9 +41,74 @@ export const ChatWindow = memo(function ChatWindow() {
     channels: 1,
     bitsPerSample: 16,
     maxDuration: 60000,
+    noSpeechTimeout: (config?.audio.listen_timeout_secs ?? 8) * 1000,
+    speechThreshold: config?.audio.silence_threshold,
+    onNoSpeech: () => setStatus('idle'),
   });
 
   const player = useAudioPlayer();
+  const { play, stop } = player;
+
+  // Streamed replies arrive sentence by sentence, in order; play each after the previous one
+  const speechQueue = useRef<Promise<void>>(Promise.resolve());
+  // Bumped by stopSpeech, so chunks queued before it are dropped instead of played
+  const speechGeneration = useRef(0);
+
+  const enqueueSpeech = useCallback((audio: Uint8Array) => {
+    const generation = speechGeneration.current;
+    speechQueue.current = speechQueue.current
+      .then(() => (generation === speechGeneration.current ? play(audio) : undefined))
+      .catch(() => {
+        // A failed chunk should not stop the rest of the reply
+      });
+  }, [play]);
+
+  const stopSpeech = useCallback(() => {
+    speechGeneration.current += 1;
+    speechQueue.current = Promise.resolve();
+    stop();
+  }, [stop]);
+
+  useEffect(() => {
+    const unlisten = listen<SpeechChunk>('speech-chunk', (event) => {
+      const audio = new Uint8Array(event.payload.audio);
+      if (event.payload.index === 0) {
+        setStatus('speaking');
+      }
+      enqueueSpeech(audio);
+    });
+
+    return () => {
+      unlisten.then((fn) => fn()).catch(() => {});
+    };
+  }, [enqueueSpeech, setStatus]);
+
+  // Selections read aloud by the speak_selection hotkey
+  useEffect(() => {
+    const unlisten = listen<SpokenSelection>('selection-spoken', (event) => {
+      const audio = new Uint8Array(event.payload.audio);
+      enqueueSpeech(audio);
+    });
+
+    return () => {
+      unlisten.then((fn) => fn()).catch(() => {});
+    };
+  }, [enqueueSpeech]);
+
+  // Results of the clipboard hotkeys, spoken if clipboard.speak_results is on
+  useEffect(() => {
+    const unlisten = listen<ClipboardResult>('clipboard-action-done', (event) => {
+      if (event.payload.audio.length === 0) {
+        return;
+      }
+      const audio = new Uint8Array(event.payload.audio);
+      enqueueSpeech(audio);
+    });
+
+    return () => {
+      unlisten.then((fn) => fn()).catch(() => {});
+    };
+  }, [enqueueSpeech]);
 
   // Handle recording start
   const handleStartRecording = useCallback(async () => {
@@ -75,6 +143,18 @@ export const ChatWindow = memo(function ChatWindow() {
       setStatus('transcribing');
       const result = await processVoiceQuery(audioData);
 
+      // Built-in voice commands are executed by the backend; mirror them in the UI
+      if (result.intent?.intent === 'stop_talking') {
+        stopSpeech();
+        setStatus('idle');
+        return;
+      }
+      if (result.intent?.intent === 'clear_conversation') {
+        setMessages([]);
+        setStatus('idle');
+        return;
+      }
+
       // Update user message with transcription
       const transcribedMessage: Message = {
         role: 'user',
@@ -89,13 +169,23 @@ export const ChatWindow = memo(function ChatWindow() {
         content: result.llm_response,
         timestamp: Date.now(),
         audioData: new Uint8Array(result.audio_response),
+        stages: result.stages,
       };
       addMessage(assistantMessage);
 
-      // Play audio response
-      setStatus('speaking');
-      await player.play(new Uint8Array(result.audio_response));
+      // Play audio response, or wait for the streamed sentences to finish playing
+      if (result.streamed) {
+        await speechQueue.current;
+      } else if (result.audio_response.length > 0) {
+        setStatus('speaking');
+        await player.play(new Uint8Array(result.audio_response));
+      }
       setStatus('idle');
+
+      // A voice command waiting for a yes or no listens for the answer straight away
+      if (result.awaiting_confirmation) {
+        await handleStartRecording();
+      }
     } catch (err) {
       setError({
         message: err instanceof Error ? err.message : 'Failed to process voice query',
@@ -104,7 +194,7 @@ export const ChatWindow = memo(function ChatWindow() {
       });
       setStatus('idle');
     }
-  }, [recorder, addMessage, setStatus, setError, processVoiceQuery, player]);
+  }, [recorder, addMessage, setMessages, setStatus, setError, processVoiceQuery, player, stopSpeech, handleStartRecording]);
 
   // Handle text message send
   const handleSendMessage = useCallback(async (message: string) => {
@@ -117,6 +207,24 @@ export const ChatWindow = memo(function ChatWindow() {
       };
       addMessage(userMessage);
 
+      // Typed queries are answered aloud when the pipeline mode speaks their replies
+      if (config?.audio.pipeline_mode === 'text_in_voice_out') {
+        const result = await processTextQuery(message);
+        addMessage({
+          role: 'assistant',
+          content: result.llm_response,
+          timestamp: Date.now(),
+          audioData: new Uint8Array(result.audio_response),
+          stages: result.stages,
+        });
+        if (result.audio_response.length > 0) {
+          setStatus('speaking');
+          await player.play(new Uint8Array(result.audio_response));
+        }
+        setStatus('idle');
+        return;
+      }
+
       // Get LLM response
       setStatus('thinking');
       const response = await sendTextMessage(message);
@@ -138,7 +246,7 @@ export const ChatWindow = memo(function ChatWindow() {
       });
       setStatus('idle');
     }
-  }, [addMessage, setStatus, setError, sendTextMessage]);
+  }, [addMessage, setStatus, setError, sendTextMessage, processTextQuery, config, player]);
 
   // Handle clear chat
   const handleClearChat = useCallback(async () => {
@@ -156,7 +264,7 @@ export const ChatWindow = memo(function ChatWindow() {
   // Handle refresh connectivity
   const handleRefreshConnectivity = useCallback(async () => {
     try {
-      await checkConnectivity();
+      await checkConnectivity(true);
     } catch (err) {
       console.error('Failed to check connectivity:', err);
     }
@@ -203,6 +311,7 @@ export const ChatWindow = memo(function ChatWindow() {
   }, [recorder.error, setError]);
 
   const isProcessing = status !== 'idle' && status !== 'recording';
+  const voiceInput = config?.audio.pipeline_mode !== 'text' && config?.audio.pipeline_mode !== 'text_in_voice_out';
 
   return (
     <div className="chat-window">
@@ -225,6 +334,7 @@ export const ChatWindow = memo(function ChatWindow() {
         onStopRecording={handleStopRecording}
         isRecording={recorder.isRecording}
         isProcessing={isProcessing}
+        voiceInput={voiceInput}
       />
     </div>
   );
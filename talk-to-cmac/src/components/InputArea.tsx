@@ -15,6 +15,8 @@ interface InputAreaProps {
   isRecording: boolean;
   isProcessing: boolean;
   disabled?: boolean;
+  /** Offer the microphone; off when the pipeline mode takes typed queries only */
+  voiceInput?: boolean;
 }
 
 export const InputArea = memo(function InputArea({
@@ -24,6 +26,7 @@ export const InputArea = memo(function InputArea({
   isRecording,
   isProcessing,
   disabled = false,
+  voiceInput = true,
 }: InputAreaProps) {
   const [message, setMessage] = useState('');
 
@@ -53,7 +56,7 @@ export const InputArea = memo(function InputArea({
           value={message}
           onChange={(e) => setMessage(e.target.value)}
           onKeyDown={handleKeyDown}
-          placeholder="Type a message or use voice..."
+          placeholder={voiceInput ? 'Type a message or use voice...' : 'Type a message...'}
           disabled={disabled || isProcessing}
           rows={1}
           maxLength={1000}
@@ -75,15 +78,17 @@ export const InputArea = memo(function InputArea({
           </svg>
         </button>
       </div>
-      <div className="mic-container">
-        <MicrophoneButton
-          isRecording={isRecording}
-          isProcessing={isProcessing}
-          onStartRecording={onStartRecording}
-          onStopRecording={onStopRecording}
-          disabled={disabled}
-        />
-      </div>
+      {voiceInput && (
+        <div className="mic-container">
+          <MicrophoneButton
+            isRecording={isRecording}
+            isProcessing={isProcessing}
+            onStartRecording={onStartRecording}
+            onStopRecording={onStopRecording}
+            disabled={disabled}
+          />
+        </div>
+      )}
     </div>
   );
 });
@@ -5,9 +5,23 @@
  */
 
 import { memo } from 'react';
-import type { MessageBubbleProps } from '../types';
+import type { MessageBubbleProps, StageTimings } from '../types';
 import './MessageBubble.css';
 
+/** "answered by llama3.2 in 3.1s", noting a failover to the fallback provider */
+function describeStages(stages: StageTimings): string | null {
+  const llm = stages.llm;
+  if (!llm) {
+    return null;
+  }
+  const seconds = (llm.duration_ms / 1000).toFixed(1);
+  const failedOver = [stages.stt, stages.llm, stages.tts].filter((stage) => stage?.failover);
+  const failover = failedOver.length > 0
+    ? ` (fallback: ${failedOver.map((stage) => stage?.provider).join(', ')})`
+    : '';
+  return `answered by ${llm.model ?? llm.provider} in ${seconds}s${failover}`;
+}
+
 export const MessageBubble = memo(function MessageBubble({
   message,
   isUser,
@@ -35,6 +49,9 @@ export const MessageBubble = memo(function MessageBubble({
       <div className="message-content">
         {message.content}
       </div>
+      {message.stages && !isUser && describeStages(message.stages) && (
+        <div className="message-stages">{describeStages(message.stages)}</div>
+      )}
       {message.audioData && !isUser && (
         <button
           className="audio-play-button"
@@ -0,0 +1,92 @@
+/**
+ * Quick Ask Component
+ *
+ * Single-field popup opened from the tray or the quick_ask hotkey. The
+ * question goes through send_message like one typed in the chat, and the
+ * reply is spoken without bringing up the main window.
+ */
+
+import { useState, useCallback, useEffect, useRef, KeyboardEvent } from 'react';
+import { getCurrentWebviewWindow } from '@tauri-apps/api/webviewWindow';
+import { useAudioPlayer } from '../hooks/useAudioPlayer';
+import { sendMessage, synthesizeSpeech, hideQuickAsk } from '../utils/tauri';
+import './QuickAsk.css';
+
+export function QuickAsk() {
+  const [question, setQuestion] = useState('');
+  const [reply, setReply] = useState<string | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  const [isProcessing, setIsProcessing] = useState(false);
+  const inputRef = useRef<HTMLInputElement>(null);
+  const { play, stop } = useAudioPlayer();
+
+  // The popup is hidden rather than closed, so focus the field each time it comes back
+  useEffect(() => {
+    const unlisten = getCurrentWebviewWindow().onFocusChanged(({ payload: focused }) => {
+      if (focused) {
+        inputRef.current?.focus();
+        inputRef.current?.select();
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  const handleAsk = useCallback(async () => {
+    const trimmed = question.trim();
+    if (!trimmed || isProcessing) {
+      return;
+    }
+
+    stop();
+    setIsProcessing(true);
+    setError(null);
+    setReply(null);
+    try {
+      const answer = await sendMessage(trimmed);
+      setReply(answer);
+      setQuestion('');
+      setIsProcessing(false);
+      await play(await synthesizeSpeech(answer));
+    } catch (err) {
+      setError(err instanceof Error ? err.message : String(err));
+      setIsProcessing(false);
+    }
+  }, [question, isProcessing, play, stop]);
+
+  const handleKeyDown = useCallback(
+    (e: KeyboardEvent<HTMLInputElement>) => {
+      if (e.key === 'Enter') {
+        e.preventDefault();
+        handleAsk();
+      } else if (e.key === 'Escape') {
+        e.preventDefault();
+        hideQuickAsk().catch(() => {});
+      }
+    },
+    [handleAsk]
+  );
+
+  return (
+    <div className="quick-ask">
+      <input
+        ref={inputRef}
+        className="quick-ask-input"
+        value={question}
+        onChange={(e) => setQuestion(e.target.value)}
+        onKeyDown={handleKeyDown}
+        placeholder={isProcessing ? 'Thinking…' : 'Ask CMAC…'}
+        disabled={isProcessing}
+        maxLength={1000}
+        autoFocus
+        aria-label="Quick question"
+      />
+      {error ? (
+        <p className="quick-ask-error" role="alert">{error}</p>
+      ) : (
+        reply && <p className="quick-ask-reply">{reply}</p>
+      )}
+    </div>
+  );
+}
@@ -26,6 +26,8 @@ export const StatusIndicator = memo(function StatusIndicator({
           return 'Thinking...';
         case 'speaking':
           return 'Speaking...';
+        case 'confirming':
+          return 'Say yes or no...';
         default:
           return 'Ready';
       }
@@ -56,6 +58,8 @@ export const StatusIndicator = memo(function StatusIndicator({
           return '●●●';
         case 'speaking':
           return '♪';
+        case 'confirming':
+          return '?';
         default:
           return '◉';
       }
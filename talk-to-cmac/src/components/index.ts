@@ -11,3 +11,4 @@ export { MicrophoneButton } from './MicrophoneButton';
 export { StatusIndicator } from './StatusIndicator';
 export { ConnectionStatus } from './ConnectionStatus';
 export { ErrorMessage } from './ErrorMessage';
+export { QuickAsk } from './QuickAsk';
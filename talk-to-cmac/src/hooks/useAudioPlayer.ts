@@ -4,6 +4,7 @@
 
 import { useState, useCallback, useRef, useEffect } from 'react';
 import { AudioPlayer } from '../utils/audio';
+import { notifyPlaybackStarted, notifyPlaybackFinished } from '../utils/tauri';
 
 export interface UseAudioPlayerResult {
   isPlaying: boolean;
@@ -54,8 +55,13 @@ export function useAudioPlayer(): UseAudioPlayerResult {
       setDuration(0);
 
       startProgressTimer();
+      notifyPlaybackStarted().catch(() => {});
 
-      await playerRef.current.play(audioData);
+      try {
+        await playerRef.current.play(audioData);
+      } finally {
+        notifyPlaybackFinished().catch(() => {});
+      }
 
       // Playback ended
       setIsPlaying(false);
@@ -73,6 +79,7 @@ export function useAudioPlayer(): UseAudioPlayerResult {
   // Stop playback
   const stop = useCallback(() => {
     playerRef.current.stop();
+    notifyPlaybackFinished().catch(() => {});
     setIsPlaying(false);
     setCurrentTime(0);
     setDuration(0);
@@ -3,11 +3,14 @@
  */
 
 import { useState, useCallback, useRef, useEffect } from 'react';
-import { createAudioRecorder, type AudioRecorder, type RecordingOptions } from '../utils/audio';
+import { listen } from '@tauri-apps/api/event';
+import { createAudioRecorder, playEarcon, type AudioRecorder, type RecordingOptions } from '../utils/audio';
+import { checkMicrophoneAccess } from '../utils/tauri';
 
 export interface UseAudioRecorderResult {
   isRecording: boolean;
   isPaused: boolean;
+  isMuted: boolean;
   duration: number;
   error: string | null;
   startRecording: () => Promise<void>;
@@ -22,6 +25,7 @@ export function useAudioRecorder(
 ): UseAudioRecorderResult {
   const [isRecording, setIsRecording] = useState(false);
   const [isPaused, setIsPaused] = useState(false);
+  const [isMuted, setIsMuted] = useState(false);
   const [duration, setDuration] = useState(0);
   const [error, setError] = useState<string | null>(null);
 
@@ -48,23 +52,49 @@ export function useAudioRecorder(
 
   // Start recording
   const startRecording = useCallback(async () => {
+    if (isMuted) {
+      setError('Microphone is muted');
+      return;
+    }
+
     try {
       setError(null);
       setDuration(0);
 
-      recorderRef.current = await createAudioRecorder(options);
-      await recorderRef.current.start();
+      // Surface missing devices and denied permissions before capture begins
+      await checkMicrophoneAccess();
+
+      // Give up on a recording in which nobody speaks, with a soft tone instead of a hot mic
+      const recorder = await createAudioRecorder({
+        ...options,
+        onNoSpeech: () => {
+          if (recorderRef.current !== recorder) return;
+          clearDurationTimer();
+          recorder.cancel();
+          recorderRef.current = null;
+          setIsRecording(false);
+          setIsPaused(false);
+          setDuration(0);
+          playEarcon().catch(() => {
+            // The tone is a courtesy; recording has already stopped
+          });
+          options.onNoSpeech?.();
+        },
+      });
+      recorderRef.current = recorder;
+      await recorder.start();
 
       setIsRecording(true);
       setIsPaused(false);
       startDurationTimer();
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : 'Failed to start recording';
+      const errorMessage =
+        err instanceof Error ? err.message : typeof err === 'string' ? err : 'Failed to start recording';
       setError(errorMessage);
       setIsRecording(false);
       recorderRef.current = null;
     }
-  }, [options, startDurationTimer]);
+  }, [options, isMuted, startDurationTimer, clearDurationTimer]);
 
   // Stop recording
   const stopRecording = useCallback(async (): Promise<Uint8Array | null> => {
@@ -115,6 +145,27 @@ export function useAudioRecorder(
     setError(null);
   }, []);
 
+  // Privacy mute: tear down the capture stream entirely, discarding any audio
+  useEffect(() => {
+    const unlisten = listen<{ muted: boolean }>('mic-muted-changed', (event) => {
+      setIsMuted(event.payload.muted);
+      if (event.payload.muted && recorderRef.current) {
+        clearDurationTimer();
+        recorderRef.current.stop().catch(() => {
+          // Ignore errors while releasing the microphone
+        });
+        recorderRef.current = null;
+        setIsRecording(false);
+        setIsPaused(false);
+        setDuration(0);
+      }
+    });
+
+    return () => {
+      unlisten.then((fn) => fn()).catch(() => {});
+    };
+  }, [clearDurationTimer]);
+
   // Cleanup on unmount
   useEffect(() => {
     return () => {
@@ -130,6 +181,7 @@ export function useAudioRecorder(
   return {
     isRecording,
     isPaused,
+    isMuted,
     duration,
     error,
     startRecording,
@@ -60,9 +60,9 @@ export function useTauri() {
   }, [setConfig, setMessages, setConversationId, setConnectivity, setStatus, setError]);
 
   // Check connectivity
-  const checkConnectivity = useCallback(async () => {
+  const checkConnectivity = useCallback(async (force?: boolean) => {
     try {
-      const connectivity = await tauri.checkConnectivity();
+      const connectivity = await tauri.checkConnectivity(force);
       setConnectivity({
         ...connectivity,
         last_checked: Date.now(),
@@ -101,6 +101,22 @@ export function useTauri() {
     []
   );
 
+  // Process typed query through the voice pipeline
+  const processTextQuery = useCallback(
+    async (message: string) => {
+      try {
+        setStatus('thinking');
+        const result = await tauri.processTextQuery(message);
+        setStatus('idle');
+        return result;
+      } catch (error) {
+        setStatus('idle');
+        throw error;
+      }
+    },
+    [setStatus]
+  );
+
   // Save configuration
   const saveConfiguration = useCallback(
     async (config: AppConfig) => {
@@ -159,6 +175,7 @@ export function useTauri() {
     checkConnectivity,
     sendTextMessage,
     processVoiceQuery,
+    processTextQuery,
     saveConfiguration,
     updateApiKey,
     clearConversation,
@@ -1,9 +1,14 @@
 import React from "react";
 import ReactDOM from "react-dom/client";
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
 import App from "./App";
+import { QuickAsk } from "./components/QuickAsk";
+
+// The quick-ask popup loads the same page as the main window
+const isQuickAsk = getCurrentWebviewWindow().label === "quick-ask";
 
 ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
   <React.StrictMode>
-    <App />
+    {isQuickAsk ? <QuickAsk /> : <App />}
   </React.StrictMode>,
 );
@@ -13,12 +13,36 @@ export type AppStatus =
   | 'transcribing'
   | 'thinking'
   | 'speaking'
+  | 'confirming'
   | { error: { message: string } };
 
 export interface AppStateResponse {
   status: AppStatus;
   message_count: number;
   connectivity: ConnectivityStatus;
+  mic_muted: boolean;
+  incognito: boolean;
+  history_locked: boolean;
+  translator: TranslationTurn | null;
+  cooldowns: ProviderCooldown[];
+}
+
+export interface ProviderCooldown {
+  provider: string;
+  reason: 'rate_limited' | 'quota_exhausted';
+  until: number;
+}
+
+export interface CrashReport {
+  timestamp: number;
+  app_version: string;
+  message: string;
+  location: string | null;
+  thread: string | null;
+  backtrace: string;
+  status: AppStatus | null;
+  last_stage: 'stt' | 'llm' | 'tts' | null;
+  conversation_saved: boolean;
 }
 
 // ============================================================================
@@ -42,6 +66,60 @@ export interface ConnectivityResponse {
   whisper: ServiceStatus;
   openwebui: ServiceStatus;
   elevenlabs: ServiceStatus;
+  /** Results of an earlier check, reused because it finished less than 30 seconds ago */
+  cached: boolean;
+}
+
+export interface HealthCheck {
+  timestamp: number;
+  provider: string;
+  connected: boolean;
+  response_ms: number;
+  error: string | null;
+}
+
+export interface ServiceHistory {
+  service: 'whisper' | 'openwebui' | 'elevenlabs';
+  checks: HealthCheck[];
+  success_rate: number | null;
+  average_response_ms: number | null;
+}
+
+export interface StageResult {
+  stage: 'stt' | 'llm' | 'tts';
+  provider: string;
+  passed: boolean;
+  duration_ms: number;
+  detail: string;
+}
+
+export interface SelfTestReport {
+  passed: boolean;
+  stages: StageResult[];
+  total_ms: number;
+}
+
+export interface LatencyStats {
+  min_ms: number;
+  median_ms: number;
+  mean_ms: number;
+  max_ms: number;
+}
+
+export interface ProviderBenchmark {
+  stage: 'stt' | 'llm' | 'tts';
+  provider: string;
+  selected: boolean;
+  successes: number;
+  failures: number;
+  stats: LatencyStats | null;
+  last_error: string | null;
+}
+
+export interface BenchmarkReport {
+  iterations: number;
+  results: ProviderBenchmark[];
+  total_ms: number;
 }
 
 // ============================================================================
@@ -50,11 +128,57 @@ export interface ConnectivityResponse {
 
 export type MessageRole = 'user' | 'assistant' | 'system';
 
+export interface NarrationSummary {
+  turns: number;
+  size_bytes: number;
+}
+
 export interface Message {
+  id?: string; // Set on messages from the backend; used by forkConversation
   role: MessageRole;
   content: string;
   timestamp: number;
   audioData?: Uint8Array; // For assistant messages with audio
+  /** Providers and timings that produced an assistant message */
+  stages?: StageTimings;
+  /** Prompt experiment variant ("experiment/variant") of the conversation */
+  variant?: string;
+  /** The user's rating of an assistant message */
+  rating?: Rating;
+}
+
+export type Thumbs = 'up' | 'down';
+
+export interface Rating {
+  thumbs: Thumbs;
+  comment: string | null;
+  /** Unix timestamp */
+  rated_at: number;
+}
+
+/** Which provider handled a pipeline stage and how long it took */
+export interface StageTiming {
+  /** Provider as named in the settings (e.g., "openwebui") */
+  provider: string;
+  model: string | null;
+  duration_ms: number;
+  /** The configured provider was paused and its cooldown fallback was used */
+  failover: boolean;
+}
+
+export interface StageTimings {
+  stt: StageTiming | null;
+  llm: StageTiming | null;
+  tts: StageTiming | null;
+}
+
+export interface ConversationSummary {
+  id: string;
+  message_count: number;
+  started_at: number;
+  updated_at: number;
+  /** When the conversation was archived, if it is */
+  archived_at: number | null;
 }
 
 export interface ConversationContext {
@@ -63,6 +187,51 @@ export interface ConversationContext {
   max_messages: number;
   started_at: number;
   updated_at: number;
+  ephemeral: boolean;
+  settings: ConversationSettings;
+  /** Prompt experiment variant ("experiment/variant") assigned when it started */
+  variant?: string;
+}
+
+export interface ConversationSettings {
+  model: string | null;
+  temperature: number | null;
+  system_prompt: string | null;
+  /** Key slot id per service, instead of the default */
+  key_slots?: Record<string, string>;
+  /** Files and knowledge collections on the OpenWebUI server to answer from */
+  files?: FileAttachment[];
+}
+
+/** File or knowledge collection on the OpenWebUI server, attached to a conversation */
+export interface FileAttachment {
+  type: 'file' | 'collection';
+  id: string;
+}
+
+/** File uploaded to the OpenWebUI server */
+export interface LlmFile {
+  id: string;
+  filename: string;
+  content_type: string | null;
+  /** Size in bytes */
+  size: number | null;
+  /** Unix timestamp of the upload */
+  created_at: number | null;
+}
+
+export interface KnowledgeCollection {
+  id: string;
+  name: string;
+  description: string | null;
+}
+
+/** Result of `list_llm_files` */
+export interface LlmFiles {
+  files: LlmFile[];
+  collections: KnowledgeCollection[];
+  /** Attached to the current conversation */
+  attached: FileAttachment[];
 }
 
 // ============================================================================
@@ -85,8 +254,151 @@ export interface Voice {
 
 export interface VoiceQueryResponse {
   transcription: string;
+  /** Full reply as markdown, for display */
   llm_response: string;
+  /** What was read aloud: the reply without code blocks or markup; null when nothing was */
+  spoken_text: string | null;
   audio_response: number[];
+  moderation: ModerationVerdict | null;
+  /** Present when the topic policy refused the transcription */
+  policy: PolicyVerdict | null;
+  follow_ups: string[];
+  intent: VoiceIntent | null;
+  translation: TranslationTurn | null;
+  reasoning: string | null;
+  /** Audio was already delivered as `speech-chunk` events */
+  streamed: boolean;
+  /** Enrolled profile recognized as the speaker, when `speaker_id.enabled` */
+  speaker: SpeakerMatch | null;
+  /** Provider and duration of each stage that ran */
+  stages: StageTimings;
+  /** Other hypotheses for an uncertain transcription, most likely first */
+  alternatives: TranscriptionCandidate[];
+  /** The first answer was empty or a refusal, so the query was sent again */
+  retried: boolean;
+  /** The reply asks to confirm `intent`; the next voice query is the answer */
+  awaiting_confirmation: boolean;
+}
+
+export interface TranscriptionCandidate {
+  text: string;
+  /** How sure the provider is (0.0-1.0), if it says */
+  confidence: number | null;
+}
+
+/** Payload of `speaker-identified` */
+export interface SpeakerMatch {
+  profile_id: string;
+  similarity: number;
+  /** Whether the app switched to the profile (never for profiles with a PIN) */
+  switched: boolean;
+}
+
+export interface SpeakerEnrollment {
+  profile_id: string;
+  samples: number;
+  /** Lowest similarity of a sample to the voiceprint; low values suggest a noisy sample */
+  consistency: number;
+}
+
+export interface SpeechChunk {
+  index: number;
+  text: string;
+  audio: number[];
+}
+
+export interface TranslationTurn {
+  direction: 'a_to_b' | 'b_to_a';
+  source_language: string;
+  target_language: string;
+}
+
+export type VoiceIntent =
+  | { intent: 'clear_conversation' }
+  | { intent: 'stop_talking' }
+  | { intent: 'switch_voice'; voice: string }
+  | { intent: 'repeat_last' }
+  | { intent: 'create_ticket'; summary: string }
+  | { intent: 'adjust_next_reply'; modifier: ReplyModifier };
+
+/** One-shot change to the next reply: a token cap, or a higher or lower temperature */
+export type ReplyModifier = 'brief' | 'creative' | 'precise';
+
+export interface ModerationVerdict {
+  blocked: boolean;
+  categories: string[];
+  refusal: string | null;
+}
+
+/** A query refused by the topic policy, also the `policy-refused` event payload */
+export interface PolicyVerdict {
+  /** Denied topic of the query, or null if it was in no allowed topic */
+  topic: string | null;
+  refusal: string;
+}
+
+export interface LastResponse {
+  text: string;
+  created_at: number;
+}
+
+/** Settings to transcribe the last recording again with */
+export interface TranscriptionOverrides {
+  /** Language code, or '' to let the provider detect it */
+  language?: string;
+  /** Whisper model, or Azure OpenAI transcription deployment */
+  model?: string;
+  temperature?: number;
+  provider?: ProviderConfig['stt'];
+}
+
+/** What a recording was transcribed for */
+export type TranscriptionSource = 'dictation' | 'query' | 'retranscription' | 'translation';
+
+/** A recording as it was transcribed, kept across conversations */
+export interface RecentTranscription {
+  text: string;
+  source: TranscriptionSource;
+  created_at: number;
+}
+
+/** A segment of the live transcript; also the payload of `transcript-appended` */
+export interface TranscriptSegment {
+  seq: number;
+  text: string;
+  source: TranscriptionSource;
+  created_at: number;
+}
+
+export type RecordingKind = 'utterance' | 'reply';
+
+export interface RecordingInfo {
+  id: string;
+  kind: RecordingKind;
+  format: string;
+  size_bytes: number;
+  text: string | null;
+  created_at: number;
+}
+
+export interface MicrophoneInfo {
+  device_name: string;
+  sample_rate: number;
+  channels: number;
+}
+
+export interface MicrophoneTest {
+  device_name: string;
+  /** Highest absolute sample level (0.0-1.0) */
+  peak: number;
+  /** Root mean square level (0.0-1.0) */
+  rms: number;
+  /** Nothing louder than background noise was heard */
+  silent: boolean;
+  /** The signal hit full scale: the input gain is too high */
+  clipped: boolean;
+  /** The recording as 16-bit mono WAV */
+  audio: number[];
 }
 
 // ============================================================================
@@ -99,6 +411,10 @@ export interface WhisperConfig {
   language?: string;
   temperature: number;
   timeout_secs: number;
+  /** Alternative hypotheses sampled when a transcription is uncertain (0 = off) */
+  alternatives: number;
+  /** Confidence (0.0-1.0) below which alternatives are sampled */
+  alternatives_below: number;
 }
 
 export interface OpenWebUIConfig {
@@ -109,6 +425,51 @@ export interface OpenWebUIConfig {
   max_tokens?: number;
   stream: boolean;
   timeout_secs: number;
+  voice_optimized: boolean;
+  voice_max_tokens: number;
+  follow_up_suggestions: boolean;
+  top_p?: number;
+  presence_penalty?: number;
+  frequency_penalty?: number;
+  stop: string[];
+  seed?: number;
+  show_reasoning: boolean;
+  keep_alive?: string | null;
+  retry: RetryConfig;
+  /** Detected by `probe_llm_api`; null until probed */
+  api_flavor?: ApiFlavor | null;
+}
+
+/** Asking again once when an answer is empty or a stock refusal */
+export interface RetryConfig {
+  enabled: boolean;
+  /** Answer openings taken for a refusal (case-insensitive) */
+  refusal_phrases: string[];
+  /** Added to the query when it is sent again */
+  instruction: string;
+}
+
+/** Payload of the `llm-retry` event */
+export type RetryReason = 'empty' | 'refusal';
+
+export interface ImageAttachment {
+  mime_type: string;
+  /** Base64-encoded image bytes */
+  data: string;
+}
+
+export interface ModelPricing {
+  /** USD per prompt token */
+  prompt: number;
+  /** USD per completion token */
+  completion: number;
+}
+
+export interface ModelInfo {
+  id: string;
+  name?: string;
+  context_length?: number;
+  pricing?: ModelPricing;
 }
 
 export interface ElevenLabsConfig {
@@ -117,6 +478,22 @@ export interface ElevenLabsConfig {
   model_id: string;
   voice_settings: VoiceSettings;
   timeout_secs: number;
+  presets: VoicePreset[];
+  /** Audio format and bitrate requested (e.g. "mp3_22050_32"); API default if unset */
+  output_format?: string | null;
+}
+
+/** A named voice with its model and settings (e.g., "Calm") */
+export interface VoicePreset {
+  name: string;
+  voice_id: string;
+  model_id: string;
+  voice_settings: VoiceSettings;
+}
+
+export interface VoicePresetStatus extends VoicePreset {
+  /** Whether the voice, model, and settings in use are the preset's */
+  active: boolean;
 }
 
 export interface AudioConfig {
@@ -127,15 +504,660 @@ export interface AudioConfig {
   silence_threshold: number;
   silence_duration: number;
   max_duration: number;
+  /** Cancel a recording with no speech within this many seconds (0 = never) */
+  listen_timeout_secs: number;
+  duck_system_audio: boolean;
+  duck_level: number;
+  save_recordings: boolean;
+  recording_retention_days: number;
+  max_recordings: number;
+  voice_commands: boolean;
+  /** Ask for a spoken yes before voice commands that act outside the app */
+  confirm_actions: boolean;
+  tts_concurrency: number;
+  /** Amplify quiet recordings before transcription */
+  auto_gain: boolean;
+  /** Speech level (RMS, 0.0-1.0) quiet recordings are amplified to */
+  auto_gain_target: number;
+  /** Most a recording is amplified by (10.0 = +20 dB) */
+  auto_gain_max: number;
+  /** Channel of a stereo recording that is transcribed */
+  input_channel: InputChannel;
+  /** Cut leading and trailing silence from recordings before upload */
+  auto_trim: boolean;
+  /** Upload WAV recordings as FLAC */
+  compress_uploads: boolean;
+  /** Speak a short explanation when a voice query fails */
+  speak_errors: boolean;
+  /** Latest transcriptions kept in memory for get_recent_transcriptions (0 = none) */
+  recent_transcriptions: number;
+  /** Whether queries are spoken or typed, and replies spoken or written */
+  pipeline_mode: PipelineMode;
+}
+
+/** Which ends of a query use speech */
+export type PipelineMode = 'voice' | 'text' | 'voice_in_text_out' | 'text_in_voice_out';
+
+/** Payload of `error-spoken` */
+export interface SpokenError {
+  text: string;
+  audio: number[];
 }
 
+/** Selected text read aloud; also the payload of `selection-spoken` */
+export interface SpokenSelection {
+  text: string;
+  audio: number[];
+}
+
+export type ClipboardAction = 'summarize' | 'translate';
+
+/** Result of a clipboard action; also the payload of `clipboard-action-done` */
+export interface ClipboardResult {
+  action: ClipboardAction;
+  text: string;
+  /** Empty if not spoken or if speech synthesis failed */
+  audio: number[];
+}
+
+export type InputChannel = 'all' | 'mix' | 'left' | 'right' | 'loudest';
+
 export interface UIConfig {
-  theme: string;
+  theme: string; // 'light' | 'dark' | 'system'
   show_transcription: boolean;
   show_thinking: boolean;
   auto_minimize: boolean;
   always_on_top: boolean;
-  global_hotkey?: string;
+  /** Language of backend text such as spoken replies and errors (see `list_locales`) */
+  locale: string;
+  hotkeys: Partial<Record<HotkeyAction, string>>;
+}
+
+/** Result of `get_theme` and payload of the `theme-changed` event */
+export interface ThemeInfo {
+  setting: string;
+  system: 'light' | 'dark' | null;
+  effective: 'light' | 'dark';
+}
+
+/** A user profile, as returned by `list_profiles` and emitted as `profile-switched` */
+export interface ProfileInfo {
+  id: string;
+  name: string;
+  has_pin: boolean;
+  active: boolean;
+}
+
+/** A locale the backend has messages for */
+export interface LocaleInfo {
+  code: string;
+  name: string;
+}
+
+export type HotkeyAction =
+  | 'toggle_record'
+  | 'push_to_talk'
+  | 'stop_speaking'
+  | 'repeat_last'
+  | 'show_overlay'
+  | 'mute'
+  | 'quick_ask'
+  | 'speak_selection'
+  | 'summarize_clipboard'
+  | 'translate_clipboard';
+
+export interface HotkeyBinding {
+  action: HotkeyAction;
+  accelerator: string | null;
+  registered: boolean;
+}
+
+export interface HotkeyValidation {
+  normalized: string;
+  bound_to: HotkeyAction | null;
+  available: boolean;
+}
+
+/** Payload of the `hotkey` event */
+export interface HotkeyEvent {
+  action: HotkeyAction;
+  pressed: boolean;
+}
+
+export type RetentionPolicy =
+  | { mode: 'days'; days: number }
+  | { mode: 'conversations'; count: number }
+  | { mode: 'nothing' };
+
+export type PiiKind = 'email' | 'phone' | 'ssn' | 'credit_card';
+
+export interface RedactionConfig {
+  enabled: boolean;
+  kinds: PiiKind[];
+  custom_patterns: string[];
+}
+
+export interface RedactionAuditEntry {
+  timestamp: number;
+  source: 'transcription' | 'message' | 'speech' | 'tool';
+  counts: Record<string, number>;
+}
+
+export interface AuditEntry {
+  timestamp: number;
+  service: string;
+  method: string;
+  /** Scheme, host, and path, without the query string */
+  endpoint: string;
+  payload_bytes: number | null;
+  status: number | null;
+  error: 'timeout' | 'connect' | 'request' | null;
+  duration_ms: number;
+  /** Request body, only present when `privacy.audit_log_content` is on and `encrypt_history` off */
+  content?: string;
+}
+
+export interface RequestParameters {
+  stt_provider: string | null;
+  stt_model: string | null;
+  llm_provider: string;
+  llm_model: string | null;
+  tts_provider: string | null;
+  temperature: number;
+  /** Token limit in effect, after low-bandwidth mode */
+  max_tokens: number | null;
+  stream: boolean;
+  pipeline_mode: PipelineMode;
+  low_bandwidth: boolean;
+  /** Recording size in bytes, or typed message length in characters */
+  input_size: number;
+}
+
+export interface JournalEntry {
+  request_id: string;
+  kind: 'voice_query' | 'text_query' | 'message';
+  started_at: number;
+  duration_ms: number;
+  parameters: RequestParameters;
+  /** Stage providers and timings; `null` for failed requests */
+  stages: StageTimings | null;
+  ok: boolean;
+  failed_stage: 'stt' | 'llm' | 'tts' | null;
+  error: string | null;
+}
+
+export interface KeySlot {
+  service: ApiService;
+  id: string;
+  label: string;
+  default: boolean;
+}
+
+export interface KeySlotStatus extends KeySlot {
+  stored: boolean;
+  in_use: boolean;
+}
+
+export interface UsageReportsConfig {
+  enabled: boolean;
+  endpoint: string;
+}
+
+export interface FeatureFlagsConfig {
+  /** URL of a JSON object of flags, overriding `feature_flags.json` (empty = local flags only) */
+  remote_url: string;
+  refresh_minutes: number;
+}
+
+export interface FeatureFlag {
+  name: string;
+  /** On, off, or the percentage of installs it is on for */
+  value: boolean | number;
+  source: 'local' | 'remote';
+  /** Whether the feature is on for this install */
+  enabled: boolean;
+}
+
+export interface UsageReport {
+  app_version: string;
+  os: string;
+  period_start: number;
+  period_end: number;
+  features: Record<string, number>;
+  errors: Record<string, number>;
+  /** Counts per prompt experiment variant, e.g. `{ "tone/friendly": { "replies": 12 } }` */
+  variants?: Record<string, Record<string, number>>;
+}
+
+export interface TelemetryPayload {
+  enabled: boolean;
+  current: UsageReport;
+  queued: UsageReport[];
+}
+
+export interface PrivacyConfig {
+  retention: RetentionPolicy;
+  encrypt_history: boolean;
+  redaction: RedactionConfig;
+  /** Minutes without activity before the session locks; 0 never locks */
+  auto_lock_minutes: number;
+  /** Archive conversations idle for this many days (0 = never) */
+  archive_after_days: number;
+  /** Record request bodies in the outbound API audit log (never while `encrypt_history` is on) */
+  audit_log_content: boolean;
+  /** How much of transcriptions and LLM replies appears in the log */
+  log_content: LogPrivacy;
+}
+
+/** `full_content_dev_only` logs metadata in release builds */
+export type LogPrivacy = 'none' | 'metadata' | 'full_content_dev_only';
+
+export type ProfanitySeverity = 'mild' | 'moderate' | 'severe';
+
+export interface ProfanityConfig {
+  enabled: boolean;
+  min_severity: ProfanitySeverity;
+  custom_words: string[];
+  allowed_words: string[];
+}
+
+export interface ModerationConfig {
+  enabled: boolean;
+  provider: 'local' | 'open_ai';
+  action: 'block' | 'flag';
+  endpoint: string;
+  model: string;
+  blocked_patterns: string[];
+  refusal_message: string;
+  timeout_secs: number;
+}
+
+export interface PolicyConfig {
+  enabled: boolean;
+  /** Topics that are always refused */
+  denied: TopicRule[];
+  /** Topics that are answered; if empty, every topic not denied is */
+  allowed: TopicRule[];
+  /** Ask the LLM for the topic when no keyword matches */
+  llm_classifier: boolean;
+  refusal_message: string;
+}
+
+export interface TopicRule {
+  name: string;
+  /** Case-insensitive whole words or phrases */
+  keywords: string[];
+}
+
+export interface PromptTemplate {
+  name: string;
+  triggers: string[];
+  prompt: string; // `{input}` is replaced with what was said after the trigger
+}
+
+export interface PromptVariant {
+  name: string;
+  /** Empty for a control without a system prompt */
+  system_prompt: string;
+  /** Share of new conversations, relative to the other variants */
+  weight: number;
+}
+
+export interface PromptExperimentConfig {
+  enabled: boolean;
+  name: string;
+  variants: PromptVariant[];
+}
+
+export interface TranslatorConfig {
+  language_a: string;
+  language_b: string;
+  voice_a?: string;
+  voice_b?: string;
+}
+
+export interface ProviderConfig {
+  stt: 'whisper' | 'azure_openai' | 'mock';
+  llm: 'openwebui' | 'azure_openai' | 'openrouter' | 'gemini' | 'mock';
+  tts: 'elevenlabs' | 'azure_openai' | 'mock';
+}
+
+export interface SpeakerIdConfig {
+  enabled: boolean;
+  min_similarity: number;
+}
+
+export interface KioskConfig {
+  /** Changed only with `setKioskMode`; `save_config` keeps the current value */
+  enabled: boolean;
+  /** Seconds without activity before the conversation is cleared; 0 never clears */
+  reset_after_secs: number;
+}
+
+export interface ContextConfig {
+  /** Send the past messages most similar to the query instead of only the latest 20 */
+  semantic: boolean;
+  relevant_messages: number;
+  /** Latest messages always sent along with the relevant ones */
+  recent_messages: number;
+  /** Messages kept in memory to pick from */
+  history_size: number;
+  embedding_model: string;
+}
+
+export interface LookupConfig {
+  name: string;
+  /** Phrases that run the lookup; the words after the phrase are the search term */
+  triggers: string[];
+  /** Search URL; `{query}` is replaced with the URL-encoded search term */
+  url: string;
+  /** Name of the keyring secret holding the token (empty sends no credentials) */
+  secret: string;
+  auth_header: string;
+  auth_prefix: string;
+  /** JSON pointer to the results in the response (empty = whole response) */
+  results_pointer: string;
+  max_results: number;
+  /** Words dropped from the search term */
+  ignore_words: string[];
+  timeout_secs: number;
+}
+
+export type CalendarSource = 'ics' | 'caldav' | 'graph';
+
+export interface CalendarConfig {
+  enabled: boolean;
+  source: CalendarSource;
+  /** ICS feed or CalDAV collection URL; empty reads the signed-in user's Graph calendar */
+  url: string;
+  /** User for basic authentication; empty sends the secret as a bearer token */
+  username: string;
+  /** Name of the keyring secret holding the password or token (empty sends no credentials) */
+  secret: string;
+  /** Phrases that add upcoming events to a query */
+  triggers: string[];
+  /** Days of events added to a query */
+  days_ahead: number;
+  timeout_secs: number;
+}
+
+export interface EmailConfig {
+  enabled: boolean;
+  /** IMAP server, reached over implicit TLS */
+  host: string;
+  port: number;
+  username: string;
+  /** Name of the keyring secret holding the password (default "email") */
+  secret: string;
+  mailbox: string;
+  /** Phrases that add unread messages to a query */
+  triggers: string[];
+  max_messages: number;
+  /** Characters of each message's text sent with it */
+  snippet_chars: number;
+  timeout_secs: number;
+}
+
+export interface FileSearchConfig {
+  enabled: boolean;
+  /** Folders searched, including their subfolders */
+  folders: string[];
+  /** Phrases that start a search; the words after the phrase are the search term */
+  triggers: string[];
+  /** Extensions of files whose contents are searched, e.g. "txt" */
+  text_extensions: string[];
+  /** Larger files are matched by name only */
+  max_file_bytes: number;
+  max_files: number;
+  max_results: number;
+  /** Characters of matching text sent per file */
+  snippet_chars: number;
+}
+
+export interface OcrConfig {
+  /** Read the text of images sent with a message and send it along */
+  enabled: boolean;
+  /** Tesseract executable, found on the PATH unless a full path is given */
+  tesseract_path: string;
+  /** Tesseract languages joined with "+", e.g. "eng+spa" */
+  languages: string;
+  timeout_secs: number;
+}
+
+export interface ToolsConfig {
+  lookups: LookupConfig[];
+  calendar: CalendarConfig;
+  email: EmailConfig;
+  files: FileSearchConfig;
+  ocr: OcrConfig;
+  /** Characters of results added to a query, across all sources */
+  max_result_chars: number;
+}
+
+export interface NewsFeed {
+  /** Source name given to the LLM, e.g. "Reuters" */
+  name: string;
+  /** RSS or Atom feed URL */
+  url: string;
+}
+
+export interface NewsConfig {
+  feeds: NewsFeed[];
+  items_per_feed: number;
+  /** Items published longer ago are left out (hours, 0 = no limit) */
+  max_age_hours: number;
+  /** Instructions for the LLM summary; the answer language is added to them */
+  prompt: string;
+  timeout_secs: number;
+}
+
+/** Settings laid over the others while enabled, to save data on metered connections */
+export interface LowBandwidthConfig {
+  enabled: boolean;
+  max_tokens: number;
+  tts_output_format: string;
+}
+
+/** First-run setup; `completed` is off on a fresh install until `complete_setup` */
+export interface SetupConfig {
+  completed: boolean;
+}
+
+/** Kind of server behind an OpenAI-style chat endpoint */
+export type ApiFlavor = 'openwebui' | 'openai' | 'ollama' | 'lm_studio' | 'openai_compatible';
+
+/** LLM server found by `detect_local_servers`, `discover_network_servers`, or `probe_llm_api` */
+export interface LocalServer {
+  kind: ApiFlavor;
+  /** Scheme, host, and port, e.g. "http://localhost:11434" or "http://192.168.1.20:3000" */
+  base_url: string;
+  /** Chat completions endpoint to use as `openwebui.endpoint` */
+  chat_endpoint: string;
+  version: string | null;
+  /** Empty for OpenWebUI, which only lists models with a key */
+  models: string[];
+}
+
+export interface SetupSuggestion {
+  /** The current settings with the suggestions applied */
+  config: AppConfig;
+  /** Settings that differ from the current ones, e.g. "openwebui.endpoint" */
+  changed: string[];
+}
+
+export interface ClipboardConfig {
+  /** Instructions for the summary; the answer language is added to them */
+  summarize_prompt: string;
+  /** Language the clipboard is translated into (ISO 639-1) */
+  translate_to: string;
+  speak_results: boolean;
+}
+
+export type HookStage = 'post_transcription' | 'pre_llm' | 'post_llm' | 'pre_tts';
+
+export interface HookConfig {
+  name: string;
+  enabled: boolean;
+  stage: HookStage;
+  /** `rhai` needs a build with the `scripting` feature */
+  kind: 'command' | 'rhai';
+  /** Program run by a command hook; it reads the text on stdin and writes the new text to stdout */
+  program: string;
+  args: string[];
+  /** Path of the `.rhai` file run by a script hook */
+  script: string;
+  timeout_secs: number;
+}
+
+export interface NewsItem {
+  source: string;
+  title: string;
+  link: string;
+  /** Unix timestamp, when the feed gives one */
+  published: number | null;
+  summary: string;
+}
+
+export interface NewsBriefing {
+  text: string;
+  /** Speech of `text`; empty if speech synthesis failed */
+  audio: number[];
+  items: NewsItem[];
+}
+
+export interface CalendarEvent {
+  title: string;
+  /** Unix timestamps; all-day events end at midnight after their last day */
+  start: number;
+  end: number;
+  all_day: boolean;
+  location: string | null;
+}
+
+export interface ScheduleBriefing {
+  text: string;
+  /** Speech of `text`; empty if speech synthesis failed */
+  audio: number[];
+  events: CalendarEvent[];
+}
+
+export type TicketSystem = 'jira' | 'servicenow';
+
+export interface TicketingConfig {
+  enabled: boolean;
+  system: TicketSystem;
+  /** Instance URL, e.g. "https://example.atlassian.net" */
+  base_url: string;
+  /** Jira project key new issues are filed under */
+  project_key: string;
+  /** Jira issue type of new issues (default "Task") */
+  issue_type: string;
+  /** ServiceNow table new records are created in (default "incident") */
+  table: string;
+  /** User for basic authentication; empty sends the token as a bearer token */
+  username: string;
+  /** Name of the keyring secret holding the API token (default "ticketing") */
+  token_secret: string;
+  timeout_secs: number;
+}
+
+export interface Ticket {
+  /** Issue key or record number, e.g. "OPS-123" or "INC0010042" */
+  number: string;
+  url: string;
+}
+
+export type HandoffMethod = 'post' | 'email' | 'url';
+
+export interface HandoffConfig {
+  enabled: boolean;
+  method: HandoffMethod;
+  /** URL the package is POSTed to */
+  endpoint: string;
+  /** Name of the keyring secret holding a bearer token for `endpoint` (default "handoff") */
+  token_secret: string;
+  /** Support address pre-filled emails are sent to */
+  email: string;
+  /** Link opened with `{subject}` and `{body}` replaced, URL-encoded */
+  url: string;
+  /** Include the saved recordings' audio in the POSTed package */
+  attach_audio: boolean;
+  timeout_secs: number;
+}
+
+export interface Handoff {
+  method: HandoffMethod;
+  /** Messages in the transcript sent */
+  messages: number;
+  /** Recordings in the package */
+  recordings: number;
+  /** Email or web link opened, for the `email` and `url` methods */
+  link: string | null;
+}
+
+export interface CooldownConfig {
+  rate_limit_secs: number;
+  quota_secs: number;
+  fallback_stt: ProviderConfig['stt'] | null;
+  fallback_llm: ProviderConfig['llm'] | null;
+  fallback_tts: ProviderConfig['tts'] | null;
+}
+
+export interface MockConfig {
+  transcription: string;
+  responses: string[];
+  latency_ms: number;
+}
+
+export interface ChaosConfig {
+  enabled: boolean;
+  max_latency_ms: number;
+  drop_rate: number;
+  error_rate: number;
+  burst_length: number;
+}
+
+export interface TelemetryConfig {
+  enabled: boolean;
+  endpoint: string;
+  instance_id: string | null;
+  export_interval_secs: number;
+}
+
+export interface AzureOpenAIConfig {
+  endpoint: string;
+  api_version: string;
+  chat_deployment: string;
+  transcription_deployment: string;
+  speech_deployment: string;
+  speech_voice: string;
+  timeout_secs: number;
+}
+
+export interface OpenRouterConfig {
+  endpoint: string;
+  model: string;
+  referer: string;
+  title: string;
+  timeout_secs: number;
+}
+
+export interface GeminiSafetySetting {
+  category: string;
+  threshold: string;
+}
+
+export interface GeminiConfig {
+  endpoint: string;
+  model: string;
+  safety_settings: GeminiSafetySetting[];
+  timeout_secs: number;
+}
+
+export interface WarmupConfig {
+  enabled: boolean;
+  prime_local_llm: boolean;
+  keep_alive_interval_secs: number;
 }
 
 export interface AppConfig {
@@ -144,6 +1166,36 @@ export interface AppConfig {
   elevenlabs: ElevenLabsConfig;
   audio: AudioConfig;
   ui: UIConfig;
+  privacy: PrivacyConfig;
+  profanity_filter: ProfanityConfig;
+  moderation: ModerationConfig;
+  policy: PolicyConfig;
+  templates: PromptTemplate[];
+  translator: TranslatorConfig;
+  providers: ProviderConfig;
+  azure: AzureOpenAIConfig;
+  openrouter: OpenRouterConfig;
+  gemini: GeminiConfig;
+  warmup: WarmupConfig;
+  mock: MockConfig;
+  chaos: ChaosConfig;
+  telemetry: TelemetryConfig;
+  usage_reports: UsageReportsConfig;
+  key_slots: KeySlot[];
+  cooldown: CooldownConfig;
+  speaker_id: SpeakerIdConfig;
+  kiosk: KioskConfig;
+  context: ContextConfig;
+  ticketing: TicketingConfig;
+  handoff: HandoffConfig;
+  tools: ToolsConfig;
+  news: NewsConfig;
+  clipboard: ClipboardConfig;
+  low_bandwidth: LowBandwidthConfig;
+  hooks: HookConfig[];
+  feature_flags: FeatureFlagsConfig;
+  experiment: PromptExperimentConfig;
+  setup: SetupConfig;
 }
 
 // ============================================================================
@@ -162,7 +1214,27 @@ export type ThemeMode = 'light' | 'dark' | 'auto';
 // Tauri Command Types
 // ============================================================================
 
-export type ApiService = 'whisper' | 'openwebui' | 'elevenlabs';
+export type ApiService = 'whisper' | 'openwebui' | 'elevenlabs' | 'azure' | 'openrouter' | 'gemini';
+
+export type KeySource =
+  | { source: 'environment'; variable: string }
+  | { source: 'keyring' }
+  | { source: 'missing' }
+  | { source: 'unavailable'; reason: string };
+
+export type KeyStatus = KeySource & {
+  service: ApiService;
+  /** The running app has a key for the service */
+  loaded: boolean;
+};
+
+export interface EnvOverride {
+  name: string;
+  /** All but the last four characters hidden */
+  masked_value: string;
+  /** False when a variable set outside the app takes priority */
+  active: boolean;
+}
 
 // ============================================================================
 // Component Props
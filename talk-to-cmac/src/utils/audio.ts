@@ -9,11 +9,19 @@ export interface RecordingOptions {
   channels?: number;
   bitsPerSample?: number;
   maxDuration?: number;
+  /** Milliseconds without speech after which `onNoSpeech` is called (0 = never) */
+  noSpeechTimeout?: number;
+  /** Level (RMS, 0.0-1.0) that counts as speech */
+  speechThreshold?: number;
+  /** Called once if no speech is heard within `noSpeechTimeout` */
+  onNoSpeech?: () => void;
 }
 
 export interface AudioRecorder {
   start: () => Promise<void>;
   stop: () => Promise<Uint8Array>;
+  /** Stop recording and discard the audio */
+  cancel: () => void;
   pause: () => void;
   resume: () => void;
   isRecording: boolean;
@@ -32,6 +40,9 @@ export async function createAudioRecorder(
     channels = 1,
     bitsPerSample = 16,
     maxDuration = 60000, // 60 seconds
+    noSpeechTimeout = 0,
+    speechThreshold = 0.01,
+    onNoSpeech,
   } = options;
 
   let mediaRecorder: MediaRecorder | null = null;
@@ -41,6 +52,14 @@ export async function createAudioRecorder(
   let pausedTime: number = 0;
   let isPaused = false;
   let maxDurationTimer: number | null = null;
+  let stopSpeechMonitor: (() => void) | null = null;
+
+  const endSpeechMonitor = () => {
+    if (stopSpeechMonitor) {
+      stopSpeechMonitor();
+      stopSpeechMonitor = null;
+    }
+  };
 
   const recorder: AudioRecorder = {
     start: async () => {
@@ -81,6 +100,14 @@ export async function createAudioRecorder(
             recorder.stop();
           }, maxDuration);
         }
+
+        // Watch for speech so an abandoned recording does not keep the microphone open
+        if (noSpeechTimeout > 0 && onNoSpeech) {
+          stopSpeechMonitor = monitorSpeech(stream, noSpeechTimeout, speechThreshold, () => {
+            stopSpeechMonitor = null;
+            onNoSpeech();
+          });
+        }
       } catch (error) {
         throw new Error(`Failed to start recording: ${error}`);
       }
@@ -100,6 +127,7 @@ export async function createAudioRecorder(
               clearTimeout(maxDurationTimer);
               maxDurationTimer = null;
             }
+            endSpeechMonitor();
 
             // Stop all tracks
             if (stream) {
@@ -133,6 +161,28 @@ export async function createAudioRecorder(
       });
     },
 
+    cancel: () => {
+      if (maxDurationTimer) {
+        clearTimeout(maxDurationTimer);
+        maxDurationTimer = null;
+      }
+      endSpeechMonitor();
+
+      if (mediaRecorder) {
+        mediaRecorder.ondataavailable = null;
+        mediaRecorder.onstop = null;
+        if (mediaRecorder.state !== 'inactive') {
+          mediaRecorder.stop();
+        }
+        mediaRecorder = null;
+      }
+      if (stream) {
+        stream.getTracks().forEach((track) => track.stop());
+        stream = null;
+      }
+      audioChunks = [];
+    },
+
     pause: () => {
       if (mediaRecorder && mediaRecorder.state === 'recording') {
         mediaRecorder.pause();
@@ -167,6 +217,76 @@ export async function createAudioRecorder(
   return recorder;
 }
 
+/**
+ * Call `onTimeout` if the level of `stream` stays below `threshold` for `timeout` ms
+ *
+ * Stops watching as soon as speech is heard. Returns a function that stops watching.
+ */
+function monitorSpeech(
+  stream: MediaStream,
+  timeout: number,
+  threshold: number,
+  onTimeout: () => void
+): () => void {
+  const audioContext = new (window.AudioContext || (window as any).webkitAudioContext)();
+  const analyser = audioContext.createAnalyser();
+  analyser.fftSize = 2048;
+  audioContext.createMediaStreamSource(stream).connect(analyser);
+
+  const samples = new Float32Array(analyser.fftSize);
+  const startedAt = Date.now();
+  let stopped = false;
+
+  const stop = () => {
+    if (stopped) return;
+    stopped = true;
+    clearInterval(timer);
+    audioContext.close().catch(() => {});
+  };
+
+  const timer = setInterval(() => {
+    analyser.getFloatTimeDomainData(samples);
+    let sum = 0;
+    for (const sample of samples) {
+      sum += sample * sample;
+    }
+    if (Math.sqrt(sum / samples.length) >= threshold) {
+      stop();
+    } else if (Date.now() - startedAt >= timeout) {
+      stop();
+      onTimeout();
+    }
+  }, 100);
+
+  return stop;
+}
+
+/**
+ * Play a soft falling two-note tone, e.g. when listening gives up
+ */
+export async function playEarcon(): Promise<void> {
+  const audioContext = new (window.AudioContext || (window as any).webkitAudioContext)();
+  const gain = audioContext.createGain();
+  gain.connect(audioContext.destination);
+
+  const start = audioContext.currentTime;
+  [660, 440].forEach((frequency, index) => {
+    const noteStart = start + index * 0.15;
+    const oscillator = audioContext.createOscillator();
+    oscillator.type = 'sine';
+    oscillator.frequency.value = frequency;
+    oscillator.connect(gain);
+    gain.gain.setValueAtTime(0.0001, noteStart);
+    gain.gain.exponentialRampToValueAtTime(0.15, noteStart + 0.02);
+    gain.gain.exponentialRampToValueAtTime(0.0001, noteStart + 0.14);
+    oscillator.start(noteStart);
+    oscillator.stop(noteStart + 0.15);
+  });
+
+  await new Promise((resolve) => setTimeout(resolve, 400));
+  await audioContext.close();
+}
+
 /**
  * Get supported MIME type for MediaRecorder
  */
@@ -25,10 +25,56 @@ async function safeInvoke<T>(command: string, args?: Record<string, unknown>): P
 import type {
   AppConfig,
   AppStateResponse,
+  AuditEntry,
+  BenchmarkReport,
+  ClipboardResult,
   ApiService,
   ConversationContext,
+  ConversationSettings,
+  ConversationSummary,
+  CrashReport,
+  EnvOverride,
+  HotkeyAction,
+  HotkeyBinding,
+  HotkeyValidation,
+  ImageAttachment,
+  JournalEntry,
+  KeySlot,
+  LlmFile,
+  LlmFiles,
+  LocalServer,
+  KeySlotStatus,
+  KeyStatus,
+  LastResponse,
+  LocaleInfo,
   ConnectivityResponse,
+  MicrophoneInfo,
+  MicrophoneTest,
+  ModelInfo,
+  NarrationSummary,
+  ProfileInfo,
+  RecordingInfo,
+  RedactionAuditEntry,
+  SelfTestReport,
+  ServiceHistory,
+  ServiceStatus,
+  SetupSuggestion,
+  SpeakerEnrollment,
+  SpokenSelection,
+  FeatureFlag,
+  TelemetryPayload,
+  Thumbs,
+  ThemeInfo,
+  Ticket,
+  Handoff,
+  ScheduleBriefing,
+  NewsBriefing,
+  TranscriptionOverrides,
+  RecentTranscription,
+  TranscriptSegment,
   Voice,
+  VoicePreset,
+  VoicePresetStatus,
   VoiceSettings,
   VoiceQueryResponse,
 } from '../types';
@@ -54,6 +100,37 @@ export async function processAudio(
   }
 }
 
+/**
+ * Transcribe the last recording again with different settings
+ */
+export async function retranscribe(overrides: TranscriptionOverrides): Promise<string> {
+  return await safeInvoke<string>('retranscribe', { overrides });
+}
+
+/**
+ * Get the latest transcriptions, newest first, across conversations
+ */
+export async function getRecentTranscriptions(limit?: number): Promise<RecentTranscription[]> {
+  return await safeInvoke<RecentTranscription[]>('get_recent_transcriptions', { limit });
+}
+
+/**
+ * Get the live transcript of the session, oldest first, after segment `since`
+ */
+export async function getLiveTranscript(since?: number): Promise<TranscriptSegment[]> {
+  return await safeInvoke<TranscriptSegment[]>('get_live_transcript', { since });
+}
+
+/**
+ * Strip leading and trailing silence from a WAV recording
+ */
+export async function trimAudio(audioData: Uint8Array): Promise<Uint8Array> {
+  const trimmed = await safeInvoke<number[]>('trim_audio', {
+    audioData: Array.from(audioData),
+  });
+  return new Uint8Array(trimmed);
+}
+
 /**
  * Convert text to speech using ElevenLabs API
  */
@@ -70,6 +147,29 @@ export async function synthesizeSpeech(text: string): Promise<Uint8Array> {
   }
 }
 
+/**
+ * Get the text of the last spoken reply
+ */
+export async function getLastResponse(): Promise<LastResponse | null> {
+  try {
+    return await safeInvoke<LastResponse | null>('get_last_response');
+  } catch (error) {
+    throw new Error(`Failed to get last response: ${error}`);
+  }
+}
+
+/**
+ * Get the audio of the last reply for replay (cached, so no new TTS request)
+ */
+export async function repeatLastResponse(): Promise<Uint8Array> {
+  try {
+    const audioBytes = await safeInvoke<number[]>('repeat_last_response');
+    return new Uint8Array(audioBytes);
+  } catch (error) {
+    throw new Error(`Failed to repeat last response: ${error}`);
+  }
+}
+
 // ============================================================================
 // LLM Interaction Commands
 // ============================================================================
@@ -77,9 +177,9 @@ export async function synthesizeSpeech(text: string): Promise<Uint8Array> {
 /**
  * Send a text message to the LLM and get a response
  */
-export async function sendMessage(message: string): Promise<string> {
+export async function sendMessage(message: string, images?: ImageAttachment[]): Promise<string> {
   try {
-    return await safeInvoke<string>('send_message', { message });
+    return await safeInvoke<string>('send_message', { message, images });
   } catch (error) {
     throw new Error(`Failed to send message: ${error}`);
   }
@@ -94,18 +194,42 @@ export async function sendMessage(message: string): Promise<string> {
  */
 export async function processVoiceQuery(
   audioData: Uint8Array,
-  filename: string = 'recording.wav'
+  filename: string = 'recording.wav',
+  speak?: boolean
 ): Promise<VoiceQueryResponse> {
   try {
     return await safeInvoke<VoiceQueryResponse>('process_voice_query', {
       audioData: Array.from(audioData),
       filename,
+      speak,
     });
   } catch (error) {
     throw new Error(`Voice query processing failed: ${error}`);
   }
 }
 
+/**
+ * Answer a typed query through the voice pipeline, spoken unless the pipeline mode writes replies only
+ */
+export async function processTextQuery(message: string, speak?: boolean): Promise<VoiceQueryResponse> {
+  try {
+    return await safeInvoke<VoiceQueryResponse>('process_text_query', { message, speak });
+  } catch (error) {
+    throw new Error(`Text query processing failed: ${error}`);
+  }
+}
+
+/**
+ * Turn translator mode on or off
+ */
+export async function setTranslatorMode(enabled: boolean): Promise<void> {
+  try {
+    await invoke('set_translator_mode', { enabled });
+  } catch (error) {
+    throw new Error(`Failed to set translator mode: ${error}`);
+  }
+}
+
 // ============================================================================
 // Configuration Management Commands
 // ============================================================================
@@ -132,6 +256,38 @@ export async function saveConfig(config: AppConfig): Promise<void> {
   }
 }
 
+/**
+ * Update one configuration section with only the fields that changed, returning the whole configuration
+ */
+export async function updateConfigSection<K extends keyof AppConfig>(
+  section: K,
+  values: Partial<AppConfig[K]>
+): Promise<AppConfig> {
+  return await safeInvoke<AppConfig>('update_config_section', { section, values });
+}
+
+/**
+ * Get the theme setting, the OS theme, and the theme to show
+ */
+export async function getTheme(): Promise<ThemeInfo> {
+  try {
+    return await safeInvoke<ThemeInfo>('get_theme');
+  } catch (error) {
+    throw new Error(`Failed to get theme: ${error}`);
+  }
+}
+
+/**
+ * List the locales the backend has messages for
+ */
+export async function listLocales(): Promise<LocaleInfo[]> {
+  try {
+    return await safeInvoke<LocaleInfo[]>('list_locales');
+  } catch (error) {
+    throw new Error(`Failed to list locales: ${error}`);
+  }
+}
+
 /**
  * Store an API key securely in the system keyring
  */
@@ -146,6 +302,176 @@ export async function updateApiKey(
   }
 }
 
+/**
+ * List the labeled key slots and whether each is stored and in use
+ */
+export async function listKeySlots(): Promise<KeySlotStatus[]> {
+  try {
+    return await safeInvoke<KeySlotStatus[]>('list_key_slots');
+  } catch (error) {
+    throw new Error(`Failed to list key slots: ${error}`);
+  }
+}
+
+/**
+ * Store an additional labeled API key for a service
+ */
+export async function addKeySlot(service: ApiService, label: string, apiKey: string): Promise<KeySlot> {
+  try {
+    return await safeInvoke<KeySlot>('add_key_slot', { service, label, apiKey });
+  } catch (error) {
+    throw new Error(`Failed to add ${service} key: ${error}`);
+  }
+}
+
+/**
+ * Delete a key slot and its stored key
+ */
+export async function removeKeySlot(service: ApiService, id: string): Promise<void> {
+  try {
+    await invoke('remove_key_slot', { service, id });
+  } catch (error) {
+    throw new Error(`Failed to remove ${service} key: ${error}`);
+  }
+}
+
+/**
+ * Make a key slot the default for its service, or clear the default with null
+ */
+export async function setDefaultKeySlot(service: ApiService, id: string | null): Promise<void> {
+  try {
+    await invoke('set_default_key_slot', { service, id });
+  } catch (error) {
+    throw new Error(`Failed to set default ${service} key: ${error}`);
+  }
+}
+
+/**
+ * Where each service's API key comes from (keys are never returned)
+ */
+export async function getKeyStatus(): Promise<KeyStatus[]> {
+  try {
+    return await safeInvoke<KeyStatus[]>('get_key_status');
+  } catch (error) {
+    throw new Error(`Failed to get key status: ${error}`);
+  }
+}
+
+/**
+ * Whether setup has not been completed yet, so the setup wizard should be shown
+ */
+export async function isFirstRun(): Promise<boolean> {
+  return await safeInvoke<boolean>('is_first_run');
+}
+
+/**
+ * Find LLM servers running on this machine
+ */
+export async function detectLocalServers(): Promise<LocalServer[]> {
+  return await safeInvoke<LocalServer[]>('detect_local_servers');
+}
+
+/**
+ * Find OpenWebUI and Ollama servers elsewhere on the local network
+ */
+export async function discoverNetworkServers(): Promise<LocalServer[]> {
+  return await safeInvoke<LocalServer[]>('discover_network_servers');
+}
+
+/**
+ * Detect the kind of server at the configured LLM endpoint and save it; null if it could not be identified
+ */
+export async function probeLlmApi(): Promise<LocalServer | null> {
+  return await safeInvoke<LocalServer | null>('probe_llm_api');
+}
+
+/**
+ * Get suggested first-run settings, from the given servers or a new detection
+ */
+export async function suggestSetup(servers?: LocalServer[]): Promise<SetupSuggestion> {
+  return await safeInvoke<SetupSuggestion>('suggest_setup', { servers });
+}
+
+/**
+ * Check an API key before storing it
+ */
+export async function testApiKey(service: ApiService, apiKey: string): Promise<ServiceStatus> {
+  return await safeInvoke<ServiceStatus>('test_api_key', { service, apiKey });
+}
+
+/**
+ * Mark setup as done, so the setup wizard is not shown again
+ */
+export async function completeSetup(): Promise<void> {
+  await safeInvoke('complete_setup');
+}
+
+/**
+ * Export the configuration as JSON for troubleshooting, without API keys
+ */
+export async function exportConfig(): Promise<string> {
+  try {
+    return await safeInvoke<string>('export_config');
+  } catch (error) {
+    throw new Error(`Failed to export config: ${error}`);
+  }
+}
+
+/**
+ * Set a variable in the managed .env, or remove it with null
+ */
+export async function setEnvOverride(name: string, value: string | null): Promise<void> {
+  try {
+    await invoke('set_env_override', { name, value });
+  } catch (error) {
+    throw new Error(`Failed to set ${name}: ${error}`);
+  }
+}
+
+/**
+ * List the variables in the managed .env, with values masked
+ */
+export async function listEnvOverrides(): Promise<EnvOverride[]> {
+  try {
+    return await safeInvoke<EnvOverride[]>('list_env_overrides');
+  } catch (error) {
+    throw new Error(`Failed to list environment overrides: ${error}`);
+  }
+}
+
+/**
+ * Store a named secret (e.g. a webhook token) in the system keyring
+ */
+export async function storeSecret(name: string, value: string): Promise<void> {
+  try {
+    await invoke('store_secret', { name, value });
+  } catch (error) {
+    throw new Error(`Failed to store secret ${name}: ${error}`);
+  }
+}
+
+/**
+ * Delete a named secret, returning whether it existed
+ */
+export async function deleteSecret(name: string): Promise<boolean> {
+  try {
+    return await safeInvoke<boolean>('delete_secret', { name });
+  } catch (error) {
+    throw new Error(`Failed to delete secret ${name}: ${error}`);
+  }
+}
+
+/**
+ * List the names of stored secrets
+ */
+export async function listSecretNames(): Promise<string[]> {
+  try {
+    return await safeInvoke<string[]>('list_secret_names');
+  } catch (error) {
+    throw new Error(`Failed to list secrets: ${error}`);
+  }
+}
+
 // ============================================================================
 // State Management Commands
 // ============================================================================
@@ -172,6 +498,39 @@ export async function getConversation(): Promise<ConversationContext> {
   }
 }
 
+/**
+ * Rate a reply in the current conversation
+ */
+export async function rateResponse(messageId: string, thumbs: Thumbs, comment?: string): Promise<void> {
+  await safeInvoke('rate_response', { messageId, thumbs, comment });
+}
+
+/**
+ * Continue the current conversation from one of its messages in a new conversation
+ */
+export async function forkConversation(messageId: string): Promise<ConversationContext> {
+  try {
+    return await safeInvoke<ConversationContext>('fork_conversation', { messageId });
+  } catch (error) {
+    throw new Error(`Failed to fork conversation: ${error}`);
+  }
+}
+
+/**
+ * Speak the current conversation into a single MP3 file, optionally with a voice per side
+ */
+export async function exportConversationAudio(
+  path: string,
+  userVoiceId?: string,
+  assistantVoiceId?: string
+): Promise<NarrationSummary> {
+  try {
+    return await safeInvoke<NarrationSummary>('export_conversation_audio', { path, userVoiceId, assistantVoiceId });
+  } catch (error) {
+    throw new Error(`Failed to export conversation audio: ${error}`);
+  }
+}
+
 /**
  * Clear conversation history and start fresh
  */
@@ -183,21 +542,117 @@ export async function clearConversation(): Promise<void> {
   }
 }
 
+/**
+ * Override the model, temperature, or system prompt for the current conversation
+ */
+export async function setConversationSettings(settings: ConversationSettings): Promise<void> {
+  try {
+    await invoke('set_conversation_settings', { settings });
+  } catch (error) {
+    throw new Error(`Failed to set conversation settings: ${error}`);
+  }
+}
+
 // ============================================================================
 // Connectivity Commands
 // ============================================================================
 
 /**
- * Check connectivity to all services
+ * Check connectivity to all services, reusing results under 30 seconds old unless `force` is set
  */
-export async function checkConnectivity(): Promise<ConnectivityResponse> {
+export async function checkConnectivity(force?: boolean): Promise<ConnectivityResponse> {
   try {
-    return await safeInvoke<ConnectivityResponse>('check_connectivity');
+    return await safeInvoke<ConnectivityResponse>('check_connectivity', { force });
   } catch (error) {
     throw new Error(`Failed to check connectivity: ${error}`);
   }
 }
 
+/**
+ * Get recent connectivity check results of one service, or of all
+ */
+export async function getServiceHistory(service?: 'whisper' | 'openwebui' | 'elevenlabs'): Promise<ServiceHistory[]> {
+  try {
+    return await safeInvoke<ServiceHistory[]>('get_service_history', { service });
+  } catch (error) {
+    throw new Error(`Failed to get service history: ${error}`);
+  }
+}
+
+/**
+ * Resume a provider paused after a rate limit or exhausted quota, or all of them
+ */
+export async function clearCooldown(provider?: string): Promise<number> {
+  try {
+    return await safeInvoke<number>('clear_cooldown', { provider });
+  } catch (error) {
+    throw new Error(`Failed to clear cooldown: ${error}`);
+  }
+}
+
+/**
+ * Run each pipeline stage once with built-in inputs
+ */
+export async function runSelfTest(): Promise<SelfTestReport> {
+  try {
+    return await safeInvoke<SelfTestReport>('run_self_test');
+  } catch (error) {
+    throw new Error(`Failed to run self-test: ${error}`);
+  }
+}
+
+/**
+ * Compare the latency of every configured provider of each stage
+ */
+export async function benchmarkProviders(iterations?: number): Promise<BenchmarkReport> {
+  try {
+    return await safeInvoke<BenchmarkReport>('benchmark_providers', { iterations });
+  } catch (error) {
+    throw new Error(`Failed to benchmark providers: ${error}`);
+  }
+}
+
+// ============================================================================
+// Model Commands
+// ============================================================================
+
+/**
+ * List models available on the OpenWebUI server
+ */
+export async function listModels(): Promise<ModelInfo[]> {
+  try {
+    return await safeInvoke<ModelInfo[]>('list_models');
+  } catch (error) {
+    throw new Error(`Failed to list models: ${error}`);
+  }
+}
+
+/**
+ * Upload a document to the OpenWebUI server and attach it to the current conversation,
+ * optionally adding it to a knowledge collection
+ */
+export async function uploadFileToLlm(path: string, knowledgeId?: string): Promise<LlmFile> {
+  return await safeInvoke<LlmFile>('upload_file_to_llm', { path, knowledgeId });
+}
+
+/**
+ * List files and knowledge collections on the OpenWebUI server, and the conversation's attachments
+ */
+export async function listLlmFiles(): Promise<LlmFiles> {
+  return await safeInvoke<LlmFiles>('list_llm_files');
+}
+
+/**
+ * Switch the default LLM model (validated against the server's model list)
+ */
+export async function setActiveModel(model: string): Promise<void> {
+  try {
+    await invoke('set_active_model', { model });
+  } catch (error) {
+    throw new Error(`Failed to set active model: ${error}`);
+  }
+}
+
 // ============================================================================
 // Voice Management Commands
 // ============================================================================
@@ -237,6 +692,417 @@ export async function updateVoiceSettings(
   }
 }
 
+/**
+ * List the voice presets, with the one in use marked
+ */
+export async function listVoicePresets(): Promise<VoicePresetStatus[]> {
+  return await safeInvoke<VoicePresetStatus[]>('list_voice_presets');
+}
+
+/**
+ * Switch to the voice, model, and settings of a preset
+ */
+export async function applyVoicePreset(name: string): Promise<VoicePreset> {
+  return await safeInvoke<VoicePreset>('apply_voice_preset', { name });
+}
+
+/**
+ * Save the voice, model, and settings in use as a preset, replacing one of the same name
+ */
+export async function saveVoicePreset(name: string): Promise<VoicePreset> {
+  return await safeInvoke<VoicePreset>('save_voice_preset', { name });
+}
+
+/**
+ * Delete a voice preset, returning whether it existed
+ */
+export async function deleteVoicePreset(name: string): Promise<boolean> {
+  return await safeInvoke<boolean>('delete_voice_preset', { name });
+}
+
+// ============================================================================
+// Microphone Commands
+// ============================================================================
+
+/**
+ * Verify the default microphone exists, is permitted, and is not in use elsewhere
+ */
+export async function checkMicrophoneAccess(): Promise<MicrophoneInfo> {
+  return await safeInvoke<MicrophoneInfo>('check_microphone_access');
+}
+
+/**
+ * Record a 3-second sample from the default microphone and measure its level
+ */
+export async function testMicrophone(): Promise<MicrophoneTest> {
+  return await safeInvoke<MicrophoneTest>('test_microphone');
+}
+
+// ============================================================================
+// Hotkey Commands
+// ============================================================================
+
+/**
+ * List every hotkey action with its shortcut and whether it is registered
+ */
+export async function listHotkeys(): Promise<HotkeyBinding[]> {
+  return await safeInvoke<HotkeyBinding[]>('list_hotkeys');
+}
+
+/**
+ * Check a shortcut before binding it: canonical form, action using it, and OS availability
+ */
+export async function validateHotkey(accelerator: string): Promise<HotkeyValidation> {
+  return await safeInvoke<HotkeyValidation>('validate_hotkey', { accelerator });
+}
+
+/**
+ * Bind a hotkey action to a global shortcut, replacing its previous one
+ */
+export async function registerHotkey(action: HotkeyAction, accelerator: string): Promise<void> {
+  await safeInvoke('register_hotkey', { action, accelerator });
+}
+
+/**
+ * Remove the shortcut of a hotkey action
+ */
+export async function unregisterHotkey(action: HotkeyAction): Promise<void> {
+  await safeInvoke('unregister_hotkey', { action });
+}
+
+/**
+ * Open the quick-ask popup next to the tray icon
+ */
+export async function showQuickAsk(): Promise<void> {
+  await safeInvoke('show_quick_ask');
+}
+
+/**
+ * Hide the quick-ask popup
+ */
+export async function hideQuickAsk(): Promise<void> {
+  await safeInvoke('hide_quick_ask');
+}
+
+/**
+ * Read the text selected in the focused application aloud
+ */
+export async function speakSelection(): Promise<SpokenSelection> {
+  return await safeInvoke<SpokenSelection>('speak_selection');
+}
+
+/**
+ * Replace the clipboard's text with a summary of it
+ */
+export async function summarizeClipboard(speak?: boolean): Promise<ClipboardResult> {
+  return await safeInvoke<ClipboardResult>('summarize_clipboard', { speak });
+}
+
+/**
+ * Replace the clipboard's text with a translation into the configured language
+ */
+export async function translateClipboard(speak?: boolean): Promise<ClipboardResult> {
+  return await safeInvoke<ClipboardResult>('translate_clipboard', { speak });
+}
+
+// ============================================================================
+// Profile Commands
+// ============================================================================
+
+/**
+ * List the profiles on this computer, the default one first
+ */
+export async function listProfiles(): Promise<ProfileInfo[]> {
+  return await safeInvoke<ProfileInfo[]>('list_profiles');
+}
+
+/**
+ * Add a profile, optionally protected by a 4-12 digit PIN
+ */
+export async function createProfile(name: string, pin?: string): Promise<ProfileInfo> {
+  return await safeInvoke<ProfileInfo>('create_profile', { name, pin });
+}
+
+/**
+ * Switch to another profile, reloading its settings, keys, and history
+ */
+export async function switchProfile(id: string, pin?: string): Promise<ProfileInfo> {
+  return await safeInvoke<ProfileInfo>('switch_profile', { id, pin });
+}
+
+/**
+ * Set or (without `pin`) remove the PIN of the active profile
+ */
+export async function setProfilePin(currentPin?: string, pin?: string): Promise<ProfileInfo> {
+  return await safeInvoke<ProfileInfo>('set_profile_pin', { currentPin, pin });
+}
+
+/**
+ * Enroll the active profile's voice from at least three recorded WAV samples
+ */
+export async function enrollSpeaker(samples: Uint8Array[]): Promise<SpeakerEnrollment> {
+  return await safeInvoke<SpeakerEnrollment>('enroll_speaker', {
+    samples: samples.map((sample) => Array.from(sample)),
+  });
+}
+
+/**
+ * Remove the active profile's voiceprint
+ */
+export async function deleteSpeakerEnrollment(): Promise<boolean> {
+  return await safeInvoke<boolean>('delete_speaker_enrollment');
+}
+
+// ============================================================================
+// Session Lock Commands
+// ============================================================================
+
+/**
+ * Check whether the session is locked
+ */
+export async function isSessionLocked(): Promise<boolean> {
+  return await safeInvoke<boolean>('is_session_locked');
+}
+
+/**
+ * Set or (without `pin`) remove the session PIN of the active profile
+ */
+export async function setSessionPin(currentPin?: string, pin?: string): Promise<void> {
+  return await safeInvoke<void>('set_session_pin', { currentPin, pin });
+}
+
+/**
+ * Lock the session until its PIN is entered
+ */
+export async function lockSession(): Promise<void> {
+  return await safeInvoke<void>('lock_session');
+}
+
+/**
+ * Unlock the session with its PIN
+ */
+export async function unlockSession(pin: string): Promise<void> {
+  return await safeInvoke<void>('unlock_session', { pin });
+}
+
+/**
+ * Report user activity in the window, postponing the auto-lock
+ */
+export async function reportActivity(): Promise<void> {
+  return await safeInvoke<void>('report_activity');
+}
+
+/**
+ * Turn kiosk mode on or off with the session PIN
+ */
+export async function setKioskMode(enabled: boolean, pin: string): Promise<void> {
+  return await safeInvoke<void>('set_kiosk_mode', { enabled, pin });
+}
+
+// ============================================================================
+// Recordings Commands
+// ============================================================================
+
+/**
+ * List saved recordings (utterances and spoken replies), newest first
+ */
+export async function listRecordings(): Promise<RecordingInfo[]> {
+  return await safeInvoke<RecordingInfo[]>('list_recordings');
+}
+
+/**
+ * Load the audio bytes of a saved recording for playback
+ */
+export async function playRecording(id: string): Promise<Uint8Array> {
+  const audioData = await safeInvoke<number[]>('play_recording', { id });
+  return new Uint8Array(audioData);
+}
+
+/**
+ * Delete a saved recording
+ */
+export async function deleteRecording(id: string): Promise<void> {
+  await safeInvoke('delete_recording', { id });
+}
+
+// ============================================================================
+// History & Privacy Commands
+// ============================================================================
+
+/**
+ * List conversations stored in history, most recently updated first: the active
+ * ones, or the archived ones
+ */
+export async function listConversations(archived: boolean = false): Promise<ConversationSummary[]> {
+  return await safeInvoke<ConversationSummary[]>('list_conversations', { archived });
+}
+
+/**
+ * Move an archived conversation back to the active list
+ */
+export async function restoreConversation(id: string): Promise<boolean> {
+  return await safeInvoke<boolean>('restore_conversation', { id });
+}
+
+/**
+ * Load a stored conversation by ID (null if it no longer exists)
+ */
+export async function getStoredConversation(id: string): Promise<ConversationContext | null> {
+  return await safeInvoke<ConversationContext | null>('get_stored_conversation', { id });
+}
+
+/**
+ * Start an incognito session: the current conversation is discarded and nothing
+ * from the new one is saved to history or recordings
+ */
+export async function startIncognitoSession(): Promise<void> {
+  await safeInvoke('start_incognito_session');
+}
+
+/**
+ * Leave the incognito session, wiping its messages from memory
+ */
+export async function endIncognitoSession(): Promise<void> {
+  await safeInvoke('end_incognito_session');
+}
+
+/**
+ * Unlock passphrase-protected conversation history
+ */
+export async function unlockHistory(passphrase: string): Promise<void> {
+  await safeInvoke('unlock_history', { passphrase });
+}
+
+/**
+ * Set, change, or remove (null) the passphrase protecting conversation history
+ */
+export async function setHistoryPassphrase(passphrase: string | null): Promise<void> {
+  await safeInvoke('set_history_passphrase', { passphrase });
+}
+
+/**
+ * Get the most recent PII redaction audit entries, newest first
+ */
+export async function getRedactionAudit(limit?: number): Promise<RedactionAuditEntry[]> {
+  return await safeInvoke<RedactionAuditEntry[]>('get_redaction_audit', { limit });
+}
+
+/**
+ * Get the most recent outbound API calls, newest first
+ */
+export async function getAuditLog(limit?: number): Promise<AuditEntry[]> {
+  return await safeInvoke<AuditEntry[]>('get_audit_log', { limit });
+}
+
+/**
+ * Get the most recent pipeline requests with their settings, timings, and outcome, newest first
+ */
+export async function getRequestJournal(limit?: number): Promise<JournalEntry[]> {
+  return await safeInvoke<JournalEntry[]>('get_request_journal', { limit });
+}
+
+/**
+ * Get the feature flags that are set and whether each is on for this install
+ */
+export async function getFeatureFlags(): Promise<FeatureFlag[]> {
+  return await safeInvoke<FeatureFlag[]>('get_feature_flags');
+}
+
+/**
+ * Show exactly what anonymous usage reporting would send
+ */
+export async function viewTelemetryPayload(): Promise<TelemetryPayload> {
+  return await safeInvoke<TelemetryPayload>('view_telemetry_payload');
+}
+
+/**
+ * Permanently delete all stored conversations, recordings, and queued usage reports
+ */
+export async function purgeAllData(): Promise<void> {
+  await safeInvoke('purge_all_data');
+}
+
+/**
+ * Write all stored conversations, the settings, and the usage counts to a zip file
+ */
+export async function exportAllUserData(path: string): Promise<void> {
+  await safeInvoke('export_all_user_data', { path });
+}
+
+/**
+ * Get the confirmation token that eraseAllUserData needs; valid for five minutes
+ */
+export async function requestEraseToken(): Promise<string> {
+  return await safeInvoke<string>('request_erase_token');
+}
+
+/**
+ * Permanently erase everything the active profile has stored and reset its settings
+ */
+export async function eraseAllUserData(token: string): Promise<void> {
+  await safeInvoke('erase_all_user_data', { token });
+}
+
+// ============================================================================
+// Playback Commands
+// ============================================================================
+
+/**
+ * Notify the backend that response audio started playing (ducks other apps if enabled)
+ */
+export async function notifyPlaybackStarted(): Promise<void> {
+  await safeInvoke('playback_started');
+}
+
+/**
+ * Notify the backend that response audio finished or was stopped
+ */
+export async function notifyPlaybackFinished(): Promise<void> {
+  await safeInvoke('playback_finished');
+}
+
+/**
+ * Get the crash report left by the previous run, if it crashed
+ */
+export async function getLastCrashReport(): Promise<CrashReport | null> {
+  return await safeInvoke<CrashReport | null>('get_last_crash_report');
+}
+
+/**
+ * Open a Jira issue or ServiceNow record, returning its number and link
+ */
+export async function createTicket(summary: string, description?: string): Promise<Ticket> {
+  return await safeInvoke<Ticket>('create_ticket', { summary, description });
+}
+
+/**
+ * Pass the current conversation to human support
+ */
+export async function requestHandoff(reason?: string): Promise<Handoff> {
+  return await safeInvoke<Handoff>('request_handoff', { reason });
+}
+
+/**
+ * Read today's events from the configured calendar and speak a summary of them
+ */
+export async function speakTodaySchedule(): Promise<ScheduleBriefing> {
+  return await safeInvoke<ScheduleBriefing>('speak_today_schedule');
+}
+
+/**
+ * Summarize the newest items of the configured news feeds and speak the summary
+ */
+export async function giveNewsBriefing(): Promise<NewsBriefing> {
+  return await safeInvoke<NewsBriefing>('give_news_briefing');
+}
+
+/**
+ * Read the text in an image file with Tesseract
+ */
+export async function ocrImage(path: string): Promise<string> {
+  return await safeInvoke<string>('ocr_image', { path });
+}
+
 // ============================================================================
 // Utility Functions
 // ============================================================================